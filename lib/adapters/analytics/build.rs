@@ -5,7 +5,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             protoc_bin_vendored::protoc_bin_path().expect("protoc binary not found"),
         );
     }
+    let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR")?);
     tonic_build::configure()
+        .file_descriptor_set_path(out_dir.join("phenome_descriptor.bin"))
         .compile_protos(&["proto/analytics.proto", "proto/ml.proto"], &["proto"])?;
     Ok(())
 }
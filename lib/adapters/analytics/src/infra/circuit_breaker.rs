@@ -1,4 +1,25 @@
-use std::time::{Duration, Instant};
+//! Circuit breaker for calls to a flaky remote endpoint.
+//!
+//! The breaker moves between three states:
+//!
+//! - `Closed`: calls go through; `failure_threshold` consecutive failures
+//!   open the circuit.
+//! - `Open`: calls are rejected until `reset_timeout` has passed since the
+//!   circuit opened, then it turns half-open.
+//! - `HalfOpen`: up to `half_open_max_calls` trial calls go through and the
+//!   rest are rejected. The circuit closes once every trial call succeeded;
+//!   a single failed trial opens it again with a fresh timeout.
+//!
+//! Opening and closing are pushed to an optional [`EventBus`] so the events
+//! feed shows which endpoint is being cut off.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use phenome_domain::{CircuitBreakerConfig, Event, EventBus, EventLevel};
+
+use crate::clock::{Clock, SystemClock};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CircuitState {
@@ -12,52 +33,280 @@ pub struct CircuitBreaker {
     state: CircuitState,
     failure_count: u32,
     failure_threshold: u32,
-    open_duration: Duration,
-    opened_at: Option<Instant>,
+    reset_timeout: Duration,
+    half_open_max_calls: u32,
+    half_open_calls: u32,
+    half_open_successes: u32,
+    /// When the circuit last opened, in ms.
+    opened_at: Option<i64>,
+    clock: Arc<dyn Clock>,
+    name: String,
+    events: Option<Arc<Mutex<EventBus>>>,
+    total_trips: u64,
+    calls_rejected: u64,
 }
 
 impl CircuitBreaker {
-    pub fn new(failure_threshold: u32, open_duration: Duration) -> Self {
+    pub fn new(failure_threshold: u32, reset_timeout: Duration, half_open_max_calls: u32) -> Self {
         Self {
             state: CircuitState::Closed,
             failure_count: 0,
             failure_threshold,
-            open_duration,
+            reset_timeout,
+            half_open_max_calls: half_open_max_calls.max(1),
+            half_open_calls: 0,
+            half_open_successes: 0,
             opened_at: None,
+            clock: Arc::new(SystemClock),
+            name: "remote endpoint".to_string(),
+            events: None,
+            total_trips: 0,
+            calls_rejected: 0,
         }
     }
 
+    /// Build a breaker with the thresholds in `analytics.circuit_breaker`.
+    pub fn from_config(config: &CircuitBreakerConfig) -> Self {
+        Self::new(
+            config.failure_threshold,
+            Duration::from_secs(config.reset_timeout_seconds),
+            config.half_open_max_calls,
+        )
+    }
+
+    /// Measure the reset timeout with `clock` instead of the system time.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Name the guarded endpoint in state transition events.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Push an event to `events` whenever the circuit opens or closes.
+    pub fn with_events(mut self, events: Arc<Mutex<EventBus>>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Times the circuit has opened, including re-opens after a failed trial.
+    pub fn total_trips(&self) -> u64 {
+        self.total_trips
+    }
+
+    /// Calls rejected without being attempted.
+    pub fn calls_rejected(&self) -> u64 {
+        self.calls_rejected
+    }
+
+    /// Current state; an open circuit whose reset timeout has passed reports
+    /// `HalfOpen` as the next call will be let through as a trial.
     pub fn state(&self) -> CircuitState {
-        self.state
+        if self.state == CircuitState::Open && self.reset_elapsed() {
+            CircuitState::HalfOpen
+        } else {
+            self.state
+        }
     }
 
     pub fn allow_request(&mut self) -> bool {
-        match self.state {
+        if self.state == CircuitState::Open && self.reset_elapsed() {
+            self.state = CircuitState::HalfOpen;
+            self.half_open_calls = 0;
+            self.half_open_successes = 0;
+        }
+        let allowed = match self.state {
             CircuitState::Closed => true,
-            CircuitState::Open => {
-                if let Some(opened_at) = self.opened_at {
-                    if opened_at.elapsed() >= self.open_duration {
-                        self.state = CircuitState::HalfOpen;
-                        return true;
-                    }
+            CircuitState::Open => false,
+            CircuitState::HalfOpen => {
+                if self.half_open_calls < self.half_open_max_calls {
+                    self.half_open_calls += 1;
+                    true
+                } else {
+                    false
                 }
-                false
             }
-            CircuitState::HalfOpen => true,
+        };
+        if !allowed {
+            self.calls_rejected += 1;
         }
+        allowed
     }
 
     pub fn record_success(&mut self) {
-        self.failure_count = 0;
+        match self.state {
+            CircuitState::Closed => self.failure_count = 0,
+            CircuitState::HalfOpen => {
+                self.half_open_successes += 1;
+                if self.half_open_successes >= self.half_open_max_calls {
+                    self.close();
+                }
+            }
+            // A call admitted before the circuit opened; the trials decide.
+            CircuitState::Open => {}
+        }
+    }
+
+    pub fn record_failure(&mut self) {
+        match self.state {
+            CircuitState::Closed => {
+                self.failure_count += 1;
+                if self.failure_count >= self.failure_threshold {
+                    self.trip();
+                }
+            }
+            CircuitState::HalfOpen => self.trip(),
+            CircuitState::Open => {}
+        }
+    }
+
+    fn reset_elapsed(&self) -> bool {
+        self.opened_at.is_some_and(|opened_at| {
+            self.clock.now_ms() - opened_at >= self.reset_timeout.as_millis() as i64
+        })
+    }
+
+    fn trip(&mut self) {
+        let message = if self.state == CircuitState::HalfOpen {
+            format!(
+                "Circuit breaker for {} reopened after a failed trial call",
+                self.name
+            )
+        } else {
+            format!(
+                "Circuit breaker for {} opened after {} failures",
+                self.name, self.failure_count
+            )
+        };
+        self.state = CircuitState::Open;
+        self.opened_at = Some(self.clock.now_ms());
+        self.half_open_calls = 0;
+        self.half_open_successes = 0;
+        self.total_trips += 1;
+        self.emit(EventLevel::Warn, message);
+    }
+
+    fn close(&mut self) {
         self.state = CircuitState::Closed;
+        self.failure_count = 0;
         self.opened_at = None;
+        self.half_open_calls = 0;
+        self.half_open_successes = 0;
+        let message = format!("Circuit breaker for {} closed", self.name);
+        self.emit(EventLevel::Info, message);
     }
 
-    pub fn record_failure(&mut self) {
-        self.failure_count += 1;
-        if self.failure_count >= self.failure_threshold {
-            self.state = CircuitState::Open;
-            self.opened_at = Some(Instant::now());
+    fn emit(&self, level: EventLevel, message: String) {
+        let Some(events) = &self.events else {
+            return;
+        };
+        match events.lock() {
+            Ok(mut events) => events.push(Event::new(level, message)),
+            Err(_) => tracing::error!("circuit breaker event bus lock poisoned"),
+        }
+    }
+}
+
+/// One circuit breaker per endpoint, created on first use, so failures of
+/// one endpoint never reject calls to another.
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerRegistry {
+    config: CircuitBreakerConfig,
+    breakers: Arc<Mutex<HashMap<String, CircuitBreaker>>>,
+    clock: Arc<dyn Clock>,
+    events: Option<Arc<Mutex<EventBus>>>,
+}
+
+impl Default for CircuitBreakerRegistry {
+    fn default() -> Self {
+        Self::new(CircuitBreakerConfig::default())
+    }
+}
+
+impl CircuitBreakerRegistry {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            breakers: Arc::new(Mutex::new(HashMap::new())),
+            clock: Arc::new(SystemClock),
+            events: None,
         }
     }
+
+    /// Measure reset timeouts with `clock` instead of the system time.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Push the state transitions of every breaker to `events`, naming the
+    /// endpoint.
+    pub fn with_events(mut self, events: Arc<Mutex<EventBus>>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Whether a call to `endpoint` may go through now.
+    pub fn allow_request(&self, endpoint: &str) -> bool {
+        self.with_breaker(endpoint, CircuitBreaker::allow_request)
+            .unwrap_or(false)
+    }
+
+    pub fn record_success(&self, endpoint: &str) {
+        self.with_breaker(endpoint, CircuitBreaker::record_success);
+    }
+
+    pub fn record_failure(&self, endpoint: &str) {
+        self.with_breaker(endpoint, CircuitBreaker::record_failure);
+    }
+
+    /// Forget the breaker of an endpoint that is no longer called.
+    pub fn remove(&self, endpoint: &str) {
+        if let Ok(mut breakers) = self.breakers.lock() {
+            breakers.remove(endpoint);
+        }
+    }
+
+    /// Snapshot of the breaker guarding `endpoint`, for its counters.
+    pub fn breaker(&self, endpoint: &str) -> Option<CircuitBreaker> {
+        self.breakers.lock().ok()?.get(endpoint).cloned()
+    }
+
+    /// State of every endpoint called so far.
+    pub fn states(&self) -> HashMap<String, CircuitState> {
+        match self.breakers.lock() {
+            Ok(breakers) => breakers
+                .iter()
+                .map(|(endpoint, breaker)| (endpoint.clone(), breaker.state()))
+                .collect(),
+            Err(_) => {
+                tracing::error!("circuit breaker registry lock poisoned");
+                HashMap::new()
+            }
+        }
+    }
+
+    fn with_breaker<T>(
+        &self,
+        endpoint: &str,
+        f: impl FnOnce(&mut CircuitBreaker) -> T,
+    ) -> Option<T> {
+        let Ok(mut breakers) = self.breakers.lock() else {
+            tracing::error!("circuit breaker registry lock poisoned");
+            return None;
+        };
+        let breaker = breakers.entry(endpoint.to_string()).or_insert_with(|| {
+            let breaker = CircuitBreaker::from_config(&self.config)
+                .with_clock(self.clock.clone())
+                .with_name(endpoint);
+            match &self.events {
+                Some(events) => breaker.with_events(events.clone()),
+                None => breaker,
+            }
+        });
+        Some(f(breaker))
+    }
 }
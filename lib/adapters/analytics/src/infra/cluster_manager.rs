@@ -1,15 +1,62 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use chrono::Utc;
 use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
-use tokio::sync::RwLock;
-
-use phenome_domain::{ClusterHealth, ClusterId, ClusterMetadata, MetricSample, MetricsQuery};
+use std::time::{Duration, Instant};
+use tokio::sync::{RwLock, Semaphore};
+
+use phenome_domain::{
+    CircuitBreakerConfig, ClusterHealth, ClusterId, ClusterMetadata, EventBus, MetricSample,
+    MetricsQuery,
+};
+
+use crate::circuit_breaker::{CircuitBreakerRegistry, CircuitState};
+use crate::prometheus::PrometheusSource;
+
+/// Wait before the first reconnect attempt; doubled after each failed one.
+const RECONNECT_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(60);
+const DEFAULT_MAX_CONCURRENT_QUERIES: usize = 8;
+const DEFAULT_QUERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Future of a client being built by a [`Connector`].
+pub type ConnectFuture = Pin<Box<dyn Future<Output = Result<kube::Client>> + Send>>;
+
+/// Builds a client for a kubeconfig context.
+pub type Connector = Arc<dyn Fn(String) -> ConnectFuture + Send + Sync>;
+
+/// Connection state of one cluster.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnState {
+    Connected,
+    /// A call failed; the client is rebuilt in the background and the
+    /// cluster is left out of collection until then.
+    Down,
+    /// A reconnect attempt is in flight.
+    Reconnecting,
+}
 
-#[derive(Clone, Default)]
+#[derive(Clone)]
 pub struct ClusterManager {
     clusters: Arc<RwLock<HashMap<ClusterId, ClusterMetadata>>>,
     clients: Arc<RwLock<HashMap<ClusterId, kube::Client>>>,
+    /// Clusters read from Prometheus instead of metrics-server.
+    prometheus: Arc<RwLock<HashMap<ClusterId, PrometheusSource>>>,
+    breakers: CircuitBreakerRegistry,
+    conn_states: Arc<RwLock<HashMap<ClusterId, ConnState>>>,
+    connector: Connector,
+    reconnect_backoff: Duration,
+    /// Bounds how many clusters `query_all_clusters` queries at once.
+    query_slots: Arc<Semaphore>,
+    query_timeout: Duration,
+}
+
+impl Default for ClusterManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl std::fmt::Debug for ClusterManager {
@@ -28,9 +75,75 @@ impl ClusterManager {
         Self {
             clusters: Arc::new(RwLock::new(HashMap::new())),
             clients: Arc::new(RwLock::new(HashMap::new())),
+            prometheus: Arc::new(RwLock::new(HashMap::new())),
+            breakers: CircuitBreakerRegistry::default(),
+            conn_states: Arc::new(RwLock::new(HashMap::new())),
+            connector: Arc::new(|context| -> ConnectFuture { Box::pin(connect_context(context)) }),
+            reconnect_backoff: RECONNECT_BACKOFF,
+            query_slots: Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_QUERIES)),
+            query_timeout: DEFAULT_QUERY_TIMEOUT,
         }
     }
 
+    /// Fail a single cluster's query once it takes longer than `timeout`.
+    pub fn with_query_timeout(mut self, timeout: Duration) -> Self {
+        self.query_timeout = timeout;
+        self
+    }
+
+    /// Query at most `limit` clusters at once in `query_all_clusters`.
+    pub fn with_max_concurrent_queries(mut self, limit: usize) -> Self {
+        self.query_slots = Arc::new(Semaphore::new(limit.max(1)));
+        self
+    }
+
+    /// Build clients with `connector` instead of from the local kubeconfig.
+    pub fn with_connector(mut self, connector: Connector) -> Self {
+        self.connector = connector;
+        self
+    }
+
+    /// Wait `backoff` before the first reconnect attempt to a down cluster.
+    pub fn with_reconnect_backoff(mut self, backoff: Duration) -> Self {
+        self.reconnect_backoff = backoff;
+        self
+    }
+
+    /// Connection state of every registered cluster.
+    pub async fn cluster_health(&self) -> HashMap<ClusterId, ConnState> {
+        let clusters = self.clusters.read().await;
+        let states = self.conn_states.read().await;
+        clusters
+            .keys()
+            .map(|id| {
+                let state = states.get(id).copied().unwrap_or(ConnState::Connected);
+                (id.clone(), state)
+            })
+            .collect()
+    }
+
+    /// Trip each cluster's circuit breaker with `config` instead of the
+    /// default thresholds.
+    pub fn with_circuit_breaker_config(mut self, config: CircuitBreakerConfig) -> Self {
+        self.breakers = CircuitBreakerRegistry::new(config);
+        self
+    }
+
+    /// Report each cluster's circuit opening and closing to `events`.
+    pub fn with_circuit_breaker_events(mut self, events: Arc<std::sync::Mutex<EventBus>>) -> Self {
+        self.breakers = self.breakers.with_events(events);
+        self
+    }
+
+    /// Circuit state of every cluster queried so far; `Open` clusters are
+    /// skipped until their reset timeout passes.
+    pub fn circuit_states(&self) -> HashMap<ClusterId, CircuitState> {
+        self.breakers.states()
+    }
+
+    /// Register the cluster of a kubeconfig context. Safe to call while a
+    /// poll runs; the cluster is queried from the next poll on. Adding a
+    /// cluster already registered keeps its state.
     pub async fn add_cluster(&self, context: String) -> Result<ClusterId> {
         let mut clusters = self.clusters.write().await;
         let id = context.clone();
@@ -44,14 +157,47 @@ impl ClusterManager {
             pod_count: 0,
             node_count: 0,
             namespace_count: 0,
+            last_collection_ms: None,
         };
-        clusters.insert(id.clone(), metadata);
+        clusters.entry(id.clone()).or_insert(metadata);
         Ok(id)
     }
 
+    /// Register a cluster reached through an already configured `client`
+    /// instead of a kubeconfig context.
+    pub async fn add_cluster_with_client(
+        &self,
+        context: String,
+        client: kube::Client,
+    ) -> Result<ClusterId> {
+        // The client goes in first so a poll never sees the cluster without it.
+        self.clients.write().await.insert(context.clone(), client);
+        self.add_cluster(context).await
+    }
+
+    /// Register a cluster whose metrics are read from Prometheus.
+    pub async fn add_cluster_with_prometheus(
+        &self,
+        context: String,
+        source: PrometheusSource,
+    ) -> Result<ClusterId> {
+        // Like the client, the source goes in before the cluster is polled.
+        self.prometheus
+            .write()
+            .await
+            .insert(context.clone(), source);
+        self.add_cluster(context).await
+    }
+
+    /// Stop querying a cluster and drop its client. A query already in
+    /// flight finishes, but the cluster is left out of later polls.
     pub async fn remove_cluster(&self, id: &ClusterId) -> Result<()> {
         let mut clusters = self.clusters.write().await;
         clusters.remove(id);
+        self.clients.write().await.remove(id);
+        self.prometheus.write().await.remove(id);
+        self.breakers.remove(id);
+        self.conn_states.write().await.remove(id);
         Ok(())
     }
 
@@ -60,6 +206,19 @@ impl ClusterManager {
         clusters.values().cloned().collect()
     }
 
+    /// Duration of the latest metrics query per cluster, for clusters
+    /// queried at least once.
+    pub async fn collection_latencies(&self) -> HashMap<ClusterId, Duration> {
+        let clusters = self.clusters.read().await;
+        clusters
+            .values()
+            .filter_map(|cluster| {
+                let millis = cluster.last_collection_ms?;
+                Some((cluster.id.clone(), Duration::from_millis(millis)))
+            })
+            .collect()
+    }
+
     pub async fn get_cluster_health(&self, id: &ClusterId) -> ClusterHealth {
         let clusters = self.clusters.read().await;
         clusters
@@ -81,55 +240,181 @@ impl ClusterManager {
             return Ok(client.clone());
         }
 
-        // In a real scenario, we'd load specific kubeconfig for the context
-        // For now, we try to infer from default or assume context switching
-        // This is a simplification; handling multi-cluster kubeconfigs properly is complex
-        let options = kube::config::KubeConfigOptions {
-            context: Some(context.to_string()),
-            ..Default::default()
-        };
-        let user_config = kube::config::Kubeconfig::read()
-            .map_err(|e| anyhow::anyhow!("Failed into read kubeconfig: {}", e))?;
-        let config = kube::Config::from_custom_kubeconfig(user_config, &options).await?;
-        let client = kube::Client::try_from(config)?;
+        let client = (self.connector)(context.to_string()).await?;
 
         clients.insert(context.to_string(), client.clone());
         Ok(client)
     }
 
+    /// Query one cluster through its circuit breaker. While the circuit is
+    /// open the call fails at once without contacting the cluster; a query
+    /// running past the query timeout fails as well.
     pub async fn query_metrics(
         &self,
         cluster_id: &ClusterId,
         query: MetricsQuery,
     ) -> Result<Vec<MetricSample>> {
-        let client = match self.get_client(cluster_id).await {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::warn!("Failed to get client for cluster {}: {}", cluster_id, e);
-                return Ok(Vec::new()); // Fallback to empty
+        if !self.breakers.allow_request(cluster_id) {
+            anyhow::bail!("circuit open for cluster {cluster_id}; skipping metrics query");
+        }
+        let result =
+            tokio::time::timeout(self.query_timeout, self.fetch_metrics(cluster_id, query))
+                .await
+                .unwrap_or_else(|_| {
+                    Err(anyhow::anyhow!(
+                        "metrics query for cluster {cluster_id} timed out after {:?}",
+                        self.query_timeout
+                    ))
+                });
+        if result.is_ok() {
+            self.breakers.record_success(cluster_id);
+        } else {
+            self.breakers.record_failure(cluster_id);
+            self.mark_down(cluster_id).await;
+        }
+        result
+    }
+
+    /// Take a cluster out of collection and start rebuilding its client,
+    /// unless a reconnect is already under way.
+    async fn mark_down(&self, cluster_id: &ClusterId) {
+        {
+            let clusters = self.clusters.read().await;
+            // A query still in flight when its cluster was removed.
+            if !clusters.contains_key(cluster_id) {
+                return;
             }
-        };
+            let mut states = self.conn_states.write().await;
+            let state = states
+                .entry(cluster_id.clone())
+                .or_insert(ConnState::Connected);
+            if *state != ConnState::Connected {
+                return;
+            }
+            *state = ConnState::Down;
+        }
+        let manager = self.clone();
+        let cluster_id = cluster_id.clone();
+        tokio::spawn(async move { manager.reconnect(cluster_id).await });
+    }
+
+    /// Rebuild the client of `cluster_id` with backoff until the API server
+    /// answers again or the cluster is removed.
+    async fn reconnect(&self, cluster_id: ClusterId) {
+        let mut backoff = self.reconnect_backoff;
+        loop {
+            tokio::time::sleep(backoff).await;
+            let Some(context) = self
+                .clusters
+                .read()
+                .await
+                .get(&cluster_id)
+                .map(|cluster| cluster.context.clone())
+            else {
+                return;
+            };
+            self.set_conn_state(&cluster_id, ConnState::Reconnecting)
+                .await;
+            match self.probe(&cluster_id, context).await {
+                Ok(client) => {
+                    if let Some(client) = client {
+                        // Don't resurrect the client of a cluster removed
+                        // while it was probed.
+                        let clusters = self.clusters.read().await;
+                        if !clusters.contains_key(&cluster_id) {
+                            return;
+                        }
+                        self.clients
+                            .write()
+                            .await
+                            .insert(cluster_id.clone(), client);
+                    }
+                    self.set_conn_state(&cluster_id, ConnState::Connected).await;
+                    tracing::info!("Reconnected to cluster {}", cluster_id);
+                    return;
+                }
+                Err(err) => {
+                    tracing::warn!("Reconnect to cluster {} failed: {:#}", cluster_id, err);
+                    self.set_conn_state(&cluster_id, ConnState::Down).await;
+                    backoff = backoff.saturating_mul(2).min(MAX_RECONNECT_BACKOFF);
+                }
+            }
+        }
+    }
+
+    /// A fresh client for `context` whose API server answered, or `None` once
+    /// the Prometheus server of a cluster read from Prometheus is ready.
+    async fn probe(&self, cluster_id: &ClusterId, context: String) -> Result<Option<kube::Client>> {
+        let source = self.prometheus.read().await.get(cluster_id).cloned();
+        if let Some(source) = source {
+            source.probe().await?;
+            return Ok(None);
+        }
+        let client = (self.connector)(context).await?;
+        client
+            .apiserver_version()
+            .await
+            .context("API server did not answer")?;
+        Ok(Some(client))
+    }
+
+    async fn set_conn_state(&self, cluster_id: &ClusterId, state: ConnState) {
+        let clusters = self.clusters.read().await;
+        // A cluster removed meanwhile stays removed.
+        if clusters.contains_key(cluster_id) {
+            self.conn_states
+                .write()
+                .await
+                .insert(cluster_id.clone(), state);
+        }
+    }
+
+    async fn fetch_metrics(
+        &self,
+        cluster_id: &ClusterId,
+        query: MetricsQuery,
+    ) -> Result<Vec<MetricSample>> {
+        let source = self.prometheus.read().await.get(cluster_id).cloned();
+        if let Some(source) = source {
+            return source.fetch_metrics(cluster_id, &query).await;
+        }
+
+        let client = self
+            .get_client(cluster_id)
+            .await
+            .with_context(|| format!("no client for cluster {cluster_id}"))?;
 
         let mut samples = Vec::new();
 
+        // metrics-server only reports CPU and memory usage.
+        let wants_usage = query.metric_types.is_empty()
+            || query.metric_types.iter().any(|metric_type| {
+                matches!(
+                    metric_type,
+                    phenome_domain::MetricType::CpuUsage | phenome_domain::MetricType::MemoryUsage
+                )
+            });
+        if !wants_usage {
+            return Ok(samples);
+        }
+
         // Fetch Node Metrics
         if query.resource_type.is_none()
             || query.resource_type == Some(phenome_domain::ResourceType::Node)
         {
-            if let Ok(node_metrics) = self.fetch_node_metrics(&client, cluster_id).await {
-                samples.extend(node_metrics);
-            }
+            samples.extend(self.fetch_node_metrics(&client, cluster_id).await?);
         }
 
         // Fetch Pod Metrics
         if query.resource_type.is_none()
             || query.resource_type == Some(phenome_domain::ResourceType::Pod)
         {
-            if let Ok(pod_metrics) = self.fetch_pod_metrics(&client, cluster_id).await {
-                samples.extend(pod_metrics);
-            }
+            samples.extend(self.fetch_pod_metrics(&client, cluster_id).await?);
         }
 
+        if !query.metric_types.is_empty() {
+            samples.retain(|sample| query.metric_types.contains(&sample.metric_type));
+        }
         Ok(samples)
     }
 
@@ -143,16 +428,10 @@ impl ClusterManager {
         let metrics_api =
             kube::Api::<kube::api::DynamicObject>::all_with(client.clone(), &api_resource);
 
-        let node_metrics = match metrics_api.list(&kube::api::ListParams::default()).await {
-            Ok(list) => list,
-            Err(e) => {
-                tracing::warn!(
-                    "Failed to fetch node metrics (is metrics-server installed?): {}",
-                    e
-                );
-                return Ok(Vec::new());
-            }
-        };
+        let node_metrics = metrics_api
+            .list(&kube::api::ListParams::default())
+            .await
+            .context("failed to fetch node metrics (is metrics-server installed?)")?;
 
         let mut samples = Vec::new();
         for metric in node_metrics {
@@ -199,13 +478,10 @@ impl ClusterManager {
         let metrics_api =
             kube::Api::<kube::api::DynamicObject>::all_with(client.clone(), &api_resource);
 
-        let pod_metrics = match metrics_api.list(&kube::api::ListParams::default()).await {
-            Ok(list) => list,
-            Err(e) => {
-                tracing::warn!("Failed to fetch pod metrics: {}", e);
-                return Ok(Vec::new());
-            }
-        };
+        let pod_metrics = metrics_api
+            .list(&kube::api::ListParams::default())
+            .await
+            .context("failed to fetch pod metrics")?;
 
         let mut samples = Vec::new();
         for metric in pod_metrics {
@@ -260,29 +536,62 @@ impl ClusterManager {
         query: MetricsQuery,
     ) -> Vec<(ClusterId, Result<Vec<MetricSample>>)> {
         let clusters = self.list_clusters().await;
+        let health = self.cluster_health().await;
         let mut set = tokio::task::JoinSet::new();
 
         for cluster in clusters {
+            if health.get(&cluster.id) != Some(&ConnState::Connected) {
+                tracing::debug!("Skipping cluster {} until it reconnects", cluster.id);
+                continue;
+            }
             let manager = self.clone();
             let q = query.clone();
             let c_id = cluster.id.clone();
+            let slots = self.query_slots.clone();
             set.spawn(async move {
+                // The semaphore is never closed, so this only waits.
+                let _slot = slots.acquire_owned().await;
+                let started = Instant::now();
                 let res = manager.query_metrics(&c_id, q).await;
-                (c_id, res)
+                (c_id, res, started.elapsed())
             });
         }
 
         let mut results = Vec::new();
+        let mut latencies = Vec::new();
         while let Some(res) = set.join_next().await {
             match res {
-                Ok(val) => results.push(val),
+                Ok((c_id, res, elapsed)) => {
+                    tracing::debug!("Collected metrics from {} in {:?}", c_id, elapsed);
+                    latencies.push((c_id.clone(), elapsed));
+                    results.push((c_id, res));
+                }
                 Err(e) => tracing::error!("Task join error: {}", e),
             }
         }
+
+        let mut clusters = self.clusters.write().await;
+        for (c_id, elapsed) in latencies {
+            if let Some(cluster) = clusters.get_mut(&c_id) {
+                cluster.last_collection_ms = Some(elapsed.as_millis() as u64);
+            }
+        }
         results
     }
 }
 
+/// Client for `context` in the local kubeconfig.
+async fn connect_context(context: String) -> Result<kube::Client> {
+    let options = kube::config::KubeConfigOptions {
+        context: Some(context),
+        ..Default::default()
+    };
+    let user_config = kube::config::Kubeconfig::read()
+        .map_err(|e| anyhow::anyhow!("Failed into read kubeconfig: {}", e))?;
+    let config = kube::Config::from_custom_kubeconfig(user_config, &options).await?;
+    Ok(kube::Client::try_from(config)?)
+}
+
 fn parse_k8s_quantity(q: &str) -> f64 {
     let q = q.trim();
     if let Ok(val) = q.parse::<f64>() {
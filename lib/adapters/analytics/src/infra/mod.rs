@@ -1,5 +1,8 @@
 pub mod circuit_breaker;
+pub mod clock;
 pub mod cluster_manager;
+pub mod prometheus;
+pub mod rate_limiter;
 
 #[cfg(test)]
 mod tests;
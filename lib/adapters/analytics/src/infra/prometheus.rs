@@ -0,0 +1,163 @@
+//! Metrics read from Prometheus instead of metrics-server.
+//!
+//! Usage comes from the cAdvisor series Prometheus scrapes from every
+//! kubelet: `container_cpu_usage_seconds_total` and
+//! `container_memory_working_set_bytes`. Node usage is read from the root
+//! cgroup (`id="/"`), pod usage is the sum over the pod's containers.
+
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde_json::Value;
+
+use phenome_domain::{MetricSample, MetricType, MetricsQuery, ResourceType};
+
+/// PromQL per resource and metric type, with the unit of its values.
+const QUERIES: &[(ResourceType, MetricType, &str, &str)] = &[
+    (
+        ResourceType::Node,
+        MetricType::CpuUsage,
+        r#"sum by (node) (rate(container_cpu_usage_seconds_total{id="/"}[5m]))"#,
+        "cores",
+    ),
+    (
+        ResourceType::Node,
+        MetricType::MemoryUsage,
+        r#"sum by (node) (container_memory_working_set_bytes{id="/"})"#,
+        "bytes",
+    ),
+    (
+        ResourceType::Pod,
+        MetricType::CpuUsage,
+        r#"sum by (namespace, pod) (rate(container_cpu_usage_seconds_total{container!=""}[5m]))"#,
+        "cores",
+    ),
+    (
+        ResourceType::Pod,
+        MetricType::MemoryUsage,
+        r#"sum by (namespace, pod) (container_memory_working_set_bytes{container!=""})"#,
+        "bytes",
+    ),
+];
+
+/// Prometheus server holding the cAdvisor metrics of one cluster.
+#[derive(Debug, Clone)]
+pub struct PrometheusSource {
+    client: Client,
+    url: String,
+}
+
+impl PrometheusSource {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            client: Client::new(),
+            url: url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Samples of `cluster_id` matching `query`'s resource and metric types.
+    pub async fn fetch_metrics(
+        &self,
+        cluster_id: &str,
+        query: &MetricsQuery,
+    ) -> Result<Vec<MetricSample>> {
+        let mut samples = Vec::new();
+        for (resource_type, metric_type, promql, unit) in QUERIES {
+            if query
+                .resource_type
+                .is_some_and(|wanted| wanted != *resource_type)
+                || (!query.metric_types.is_empty() && !query.metric_types.contains(metric_type))
+            {
+                continue;
+            }
+            let response = self.instant_query(promql).await?;
+            samples.extend(samples_from_response(
+                cluster_id,
+                *resource_type,
+                *metric_type,
+                unit,
+                &response,
+            )?);
+        }
+        Ok(samples)
+    }
+
+    /// Whether the server is up and ready to answer queries.
+    pub async fn probe(&self) -> Result<()> {
+        let url = format!("{}/-/ready", self.url);
+        self.client
+            .get(&url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("Prometheus at {} is not ready", self.url))?;
+        Ok(())
+    }
+
+    async fn instant_query(&self, promql: &str) -> Result<Value> {
+        let url = format!("{}/api/v1/query", self.url);
+        self.client
+            .get(&url)
+            .query(&[("query", promql)])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("failed to query Prometheus at {}", self.url))?
+            .json()
+            .await
+            .context("invalid Prometheus response")
+    }
+}
+
+/// Map an instant-query vector to samples. Series without the labels naming
+/// their resource are skipped.
+pub fn samples_from_response(
+    cluster_id: &str,
+    resource_type: ResourceType,
+    metric_type: MetricType,
+    unit: &str,
+    response: &Value,
+) -> Result<Vec<MetricSample>> {
+    if response["status"] != "success" {
+        anyhow::bail!(
+            "Prometheus query failed: {}",
+            response["error"].as_str().unwrap_or("unknown error")
+        );
+    }
+    let series = response["data"]["result"]
+        .as_array()
+        .context("Prometheus response has no result vector")?;
+
+    let mut samples = Vec::new();
+    for entry in series {
+        let labels = &entry["metric"];
+        let resource_id = match resource_type {
+            ResourceType::Pod => match (labels["namespace"].as_str(), labels["pod"].as_str()) {
+                (Some(namespace), Some(pod)) => format!("{namespace}/{pod}"),
+                _ => continue,
+            },
+            _ => match labels["node"].as_str().or(labels["instance"].as_str()) {
+                Some(node) => node.to_string(),
+                None => continue,
+            },
+        };
+        // Instant vectors carry `[<unix seconds>, "<value>"]`.
+        let (Some(seconds), Some(value)) = (
+            entry["value"][0].as_f64(),
+            entry["value"][1]
+                .as_str()
+                .and_then(|v| v.parse::<f64>().ok()),
+        ) else {
+            continue;
+        };
+        samples.push(MetricSample {
+            cluster_id: cluster_id.to_string(),
+            resource_type,
+            resource_id,
+            metric_type,
+            timestamp: (seconds * 1000.0) as i64,
+            value,
+            unit: unit.to_string(),
+        });
+    }
+    Ok(samples)
+}
@@ -1,4 +1,18 @@
-use crate::cluster_manager::ClusterManager;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use http::{Request, Response};
+use kube::client::Body;
+
+use phenome_domain::{
+    CircuitBreakerConfig, EventBus, EventLevel, MetricType, MetricsQuery, ResourceType,
+};
+
+use crate::circuit_breaker::{CircuitBreaker, CircuitBreakerRegistry, CircuitState};
+use crate::clock::ManualClock;
+use crate::cluster_manager::{ClusterManager, ConnState, ConnectFuture};
+use crate::prometheus;
 
 #[tokio::test]
 async fn adds_and_lists_clusters() {
@@ -8,3 +22,508 @@ async fn adds_and_lists_clusters() {
     assert_eq!(clusters.len(), 1);
     assert_eq!(clusters[0].id, id);
 }
+
+/// Cluster whose metrics API answers every list with no items after `delay`.
+fn mock_cluster(delay: Duration) -> kube::Client {
+    let (mock, handle) = tower_test::mock::pair::<Request<Body>, Response<Body>>();
+    tokio::spawn(async move {
+        let mut handle = std::pin::pin!(handle);
+        while let Some((_, send)) = handle.next_request().await {
+            tokio::time::sleep(delay).await;
+            let list = serde_json::json!({
+                "apiVersion": "metrics.k8s.io/v1beta1",
+                "kind": "List",
+                "metadata": {},
+                "items": [],
+            });
+            send.send_response(
+                Response::builder()
+                    .body(Body::from(serde_json::to_vec(&list).unwrap()))
+                    .unwrap(),
+            );
+        }
+    });
+    kube::Client::new(mock, "default")
+}
+
+/// Cluster whose API answers every request with a 503, counting the requests.
+fn failing_cluster(requests: Arc<AtomicUsize>) -> kube::Client {
+    let (mock, handle) = tower_test::mock::pair::<Request<Body>, Response<Body>>();
+    tokio::spawn(async move {
+        let mut handle = std::pin::pin!(handle);
+        while let Some((_, send)) = handle.next_request().await {
+            requests.fetch_add(1, Ordering::SeqCst);
+            send.send_response(
+                Response::builder()
+                    .status(503)
+                    .body(Body::from(b"unavailable".to_vec()))
+                    .unwrap(),
+            );
+        }
+    });
+    kube::Client::new(mock, "default")
+}
+
+#[tokio::test]
+async fn records_collection_latency_per_cluster() {
+    let manager = ClusterManager::new();
+    manager
+        .add_cluster_with_client("fast".to_string(), mock_cluster(Duration::ZERO))
+        .await
+        .unwrap();
+    manager
+        .add_cluster_with_client("slow".to_string(), mock_cluster(Duration::from_millis(100)))
+        .await
+        .unwrap();
+    assert!(manager.collection_latencies().await.is_empty());
+
+    let results = manager.query_all_clusters(MetricsQuery::default()).await;
+    assert_eq!(results.len(), 2);
+
+    let latencies = manager.collection_latencies().await;
+    assert_eq!(latencies.len(), 2);
+    // Node and pod metrics are listed one after the other.
+    assert!(latencies["slow"] >= Duration::from_millis(200));
+    assert!(latencies["slow"] > latencies["fast"]);
+    let clusters = manager.list_clusters().await;
+    assert!(clusters.iter().all(|c| c.last_collection_ms.is_some()));
+}
+
+#[test]
+fn open_circuit_rejects_calls_until_reset_timeout() {
+    let clock = ManualClock::new(0);
+    let mut breaker =
+        CircuitBreaker::new(2, Duration::from_secs(30), 1).with_clock(Arc::new(clock.clone()));
+
+    breaker.record_failure();
+    assert_eq!(breaker.state(), CircuitState::Closed);
+    breaker.record_failure();
+    assert_eq!(breaker.state(), CircuitState::Open);
+    assert!(!breaker.allow_request());
+
+    clock.advance(Duration::from_secs(29));
+    assert!(!breaker.allow_request());
+    assert_eq!(breaker.state(), CircuitState::Open);
+
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(breaker.state(), CircuitState::HalfOpen);
+    assert!(breaker.allow_request());
+    // Only `half_open_max_calls` trial calls go through.
+    assert!(!breaker.allow_request());
+}
+
+/// Breaker tripped by one failure, half-open after 10s with `trials` trial calls.
+fn half_open_breaker(clock: &ManualClock, trials: u32) -> CircuitBreaker {
+    let mut breaker =
+        CircuitBreaker::new(1, Duration::from_secs(10), trials).with_clock(Arc::new(clock.clone()));
+    breaker.record_failure();
+    clock.advance(Duration::from_secs(10));
+    assert_eq!(breaker.state(), CircuitState::HalfOpen);
+    breaker
+}
+
+#[test]
+fn half_open_circuit_closes_only_after_every_trial_succeeds() {
+    let clock = ManualClock::new(0);
+    let mut breaker = half_open_breaker(&clock, 2);
+
+    assert!(breaker.allow_request());
+    assert!(breaker.allow_request());
+    assert!(!breaker.allow_request());
+    breaker.record_success();
+    assert_eq!(breaker.state(), CircuitState::HalfOpen);
+    breaker.record_success();
+    assert_eq!(breaker.state(), CircuitState::Closed);
+    assert!(breaker.allow_request());
+}
+
+#[test]
+fn failed_trial_reopens_circuit_with_fresh_timeout() {
+    let clock = ManualClock::new(0);
+    let mut breaker = half_open_breaker(&clock, 2);
+
+    assert!(breaker.allow_request());
+    assert!(breaker.allow_request());
+    breaker.record_success();
+    breaker.record_failure();
+    assert_eq!(breaker.state(), CircuitState::Open);
+    assert!(!breaker.allow_request());
+
+    // The timeout restarts from the failed trial, not the original trip.
+    clock.advance(Duration::from_secs(9));
+    assert_eq!(breaker.state(), CircuitState::Open);
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(breaker.state(), CircuitState::HalfOpen);
+}
+
+#[tokio::test]
+async fn open_circuit_of_one_cluster_leaves_others_queried() {
+    let manager = ClusterManager::new().with_circuit_breaker_config(CircuitBreakerConfig {
+        failure_threshold: 2,
+        reset_timeout_seconds: 60,
+        half_open_max_calls: 1,
+    });
+    let requests = Arc::new(AtomicUsize::new(0));
+    let flaky = manager
+        .add_cluster_with_client("flaky".to_string(), failing_cluster(requests.clone()))
+        .await
+        .unwrap();
+    let healthy = manager
+        .add_cluster_with_client("healthy".to_string(), mock_cluster(Duration::ZERO))
+        .await
+        .unwrap();
+
+    for _ in 0..2 {
+        assert!(
+            manager
+                .query_metrics(&flaky, MetricsQuery::default())
+                .await
+                .is_err()
+        );
+    }
+    assert_eq!(manager.circuit_states()[&flaky], CircuitState::Open);
+    let sent = requests.load(Ordering::SeqCst);
+
+    // The open circuit fails fast without reaching the cluster.
+    let err = manager
+        .query_metrics(&flaky, MetricsQuery::default())
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("circuit open"));
+    assert_eq!(requests.load(Ordering::SeqCst), sent);
+
+    assert!(
+        manager
+            .query_metrics(&healthy, MetricsQuery::default())
+            .await
+            .is_ok()
+    );
+    assert_eq!(manager.circuit_states()[&healthy], CircuitState::Closed);
+}
+
+#[test]
+fn circuit_transitions_are_reported_as_events() {
+    let clock = ManualClock::new(0);
+    let events = Arc::new(Mutex::new(EventBus::default()));
+    let registry = CircuitBreakerRegistry::new(CircuitBreakerConfig {
+        failure_threshold: 2,
+        reset_timeout_seconds: 10,
+        half_open_max_calls: 1,
+    })
+    .with_clock(Arc::new(clock.clone()))
+    .with_events(events.clone());
+
+    registry.record_failure("prod-eu");
+    assert!(events.lock().unwrap().is_empty());
+    registry.record_failure("prod-eu");
+    assert!(!registry.allow_request("prod-eu"));
+    assert!(!registry.allow_request("prod-eu"));
+
+    clock.advance(Duration::from_secs(10));
+    assert!(registry.allow_request("prod-eu"));
+    registry.record_success("prod-eu");
+
+    let breaker = registry.breaker("prod-eu").unwrap();
+    assert_eq!(breaker.total_trips(), 1);
+    assert_eq!(breaker.calls_rejected(), 2);
+    let events: Vec<_> = events
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|event| (event.level, event.message.clone()))
+        .collect();
+    assert_eq!(
+        events,
+        [
+            (
+                EventLevel::Warn,
+                "Circuit breaker for prod-eu opened after 2 failures".to_string()
+            ),
+            (
+                EventLevel::Info,
+                "Circuit breaker for prod-eu closed".to_string()
+            ),
+        ]
+    );
+}
+
+/// Cluster answering the version probe and listing no metrics.
+fn reachable_cluster() -> kube::Client {
+    let (mock, handle) = tower_test::mock::pair::<Request<Body>, Response<Body>>();
+    tokio::spawn(async move {
+        let mut handle = std::pin::pin!(handle);
+        while let Some((request, send)) = handle.next_request().await {
+            let body = if request.uri().path() == "/version" {
+                serde_json::json!({
+                    "major": "1",
+                    "minor": "30",
+                    "gitVersion": "v1.30.0",
+                    "gitCommit": "",
+                    "gitTreeState": "clean",
+                    "buildDate": "",
+                    "goVersion": "",
+                    "compiler": "gc",
+                    "platform": "linux/amd64",
+                })
+            } else {
+                serde_json::json!({
+                    "apiVersion": "metrics.k8s.io/v1beta1",
+                    "kind": "List",
+                    "metadata": {},
+                    "items": [],
+                })
+            };
+            send.send_response(
+                Response::builder()
+                    .body(Body::from(serde_json::to_vec(&body).unwrap()))
+                    .unwrap(),
+            );
+        }
+    });
+    kube::Client::new(mock, "default")
+}
+
+#[tokio::test]
+async fn down_cluster_is_skipped_until_it_reconnects() {
+    let connects = Arc::new(AtomicUsize::new(0));
+    let manager = ClusterManager::new()
+        .with_reconnect_backoff(Duration::from_millis(10))
+        .with_connector({
+            let connects = connects.clone();
+            Arc::new(move |_context| -> ConnectFuture {
+                // The first rebuild fails too, as if the API server were
+                // still restarting.
+                let attempt = connects.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async move {
+                    anyhow::ensure!(attempt > 0, "connection refused");
+                    Ok(reachable_cluster())
+                })
+            })
+        });
+    let requests = Arc::new(AtomicUsize::new(0));
+    let id = manager
+        .add_cluster_with_client("edge".to_string(), failing_cluster(requests))
+        .await
+        .unwrap();
+    assert_eq!(manager.cluster_health().await[&id], ConnState::Connected);
+
+    assert!(
+        manager
+            .query_metrics(&id, MetricsQuery::default())
+            .await
+            .is_err()
+    );
+    assert_ne!(manager.cluster_health().await[&id], ConnState::Connected);
+    assert!(
+        manager
+            .query_all_clusters(MetricsQuery::default())
+            .await
+            .is_empty()
+    );
+
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while manager.cluster_health().await[&id] != ConnState::Connected {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await
+    .expect("cluster did not reconnect");
+    assert_eq!(connects.load(Ordering::SeqCst), 2);
+    let results = manager.query_all_clusters(MetricsQuery::default()).await;
+    assert_eq!(results.len(), 1);
+    assert!(results[0].1.is_ok());
+}
+
+/// Cluster reporting one node named `node`, tracking how many clusters are
+/// being answered at once in `in_flight` and the peak in `peak`.
+fn counted_cluster(
+    node: &str,
+    in_flight: Arc<AtomicUsize>,
+    peak: Arc<AtomicUsize>,
+) -> kube::Client {
+    let (mock, handle) = tower_test::mock::pair::<Request<Body>, Response<Body>>();
+    let node = node.to_string();
+    tokio::spawn(async move {
+        let mut handle = std::pin::pin!(handle);
+        while let Some((_, send)) = handle.next_request().await {
+            let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+            let list = serde_json::json!({
+                "apiVersion": "metrics.k8s.io/v1beta1",
+                "kind": "List",
+                "metadata": {},
+                "items": [{
+                    "metadata": { "name": node },
+                    "usage": { "cpu": "100m", "memory": "1Mi" },
+                }],
+            });
+            send.send_response(
+                Response::builder()
+                    .body(Body::from(serde_json::to_vec(&list).unwrap()))
+                    .unwrap(),
+            );
+        }
+    });
+    kube::Client::new(mock, "default")
+}
+
+#[tokio::test]
+async fn query_all_clusters_bounds_parallel_queries() {
+    let manager = ClusterManager::new().with_max_concurrent_queries(2);
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+    for index in 0..10 {
+        let context = format!("cluster-{index}");
+        let client = counted_cluster(&context, in_flight.clone(), peak.clone());
+        manager
+            .add_cluster_with_client(context, client)
+            .await
+            .unwrap();
+    }
+
+    let results = manager.query_all_clusters(MetricsQuery::default()).await;
+    assert_eq!(results.len(), 10);
+    assert_eq!(peak.load(Ordering::SeqCst), 2);
+    for (cluster_id, result) in results {
+        let samples = result.unwrap();
+        assert!(!samples.is_empty());
+        assert!(
+            samples
+                .iter()
+                .all(|sample| sample.cluster_id == cluster_id && sample.resource_id == cluster_id)
+        );
+    }
+}
+
+#[tokio::test]
+async fn hung_cluster_times_out_without_holding_up_others() {
+    let manager = ClusterManager::new().with_query_timeout(Duration::from_millis(200));
+    manager
+        .add_cluster_with_client("fast".to_string(), mock_cluster(Duration::ZERO))
+        .await
+        .unwrap();
+    manager
+        .add_cluster_with_client("hung".to_string(), mock_cluster(Duration::from_secs(30)))
+        .await
+        .unwrap();
+
+    let started = std::time::Instant::now();
+    let results: std::collections::HashMap<_, _> = manager
+        .query_all_clusters(MetricsQuery::default())
+        .await
+        .into_iter()
+        .collect();
+    assert!(started.elapsed() < Duration::from_secs(1));
+
+    assert!(results["fast"].is_ok());
+    let err = results["hung"].as_ref().unwrap_err();
+    assert!(err.to_string().contains("timed out"));
+}
+
+#[tokio::test]
+async fn clusters_can_be_added_and_removed_while_a_poll_runs() {
+    let manager = ClusterManager::new();
+    for name in ["a", "b", "c"] {
+        manager
+            .add_cluster_with_client(name.to_string(), mock_cluster(Duration::from_millis(100)))
+            .await
+            .unwrap();
+    }
+
+    let poll = tokio::spawn({
+        let manager = manager.clone();
+        async move { manager.query_all_clusters(MetricsQuery::default()).await }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    tokio::time::timeout(Duration::from_secs(1), async {
+        manager.remove_cluster(&"b".to_string()).await.unwrap();
+        manager
+            .add_cluster_with_client("d".to_string(), mock_cluster(Duration::ZERO))
+            .await
+            .unwrap();
+    })
+    .await
+    .expect("add/remove blocked by the running poll");
+
+    let results = tokio::time::timeout(Duration::from_secs(1), poll)
+        .await
+        .expect("poll deadlocked")
+        .unwrap();
+    // The poll started with a, b and c; all of them finish.
+    assert_eq!(results.len(), 3);
+
+    let mut ids: Vec<_> = manager
+        .list_clusters()
+        .await
+        .into_iter()
+        .map(|cluster| cluster.id)
+        .collect();
+    ids.sort();
+    assert_eq!(ids, ["a", "c", "d"]);
+
+    let next: std::collections::HashMap<_, _> = manager
+        .query_all_clusters(MetricsQuery::default())
+        .await
+        .into_iter()
+        .collect();
+    assert_eq!(next.len(), 3);
+    assert!(next.values().all(|res| res.is_ok()));
+}
+
+#[test]
+fn prometheus_vector_maps_to_pod_samples() {
+    let response = serde_json::json!({
+        "status": "success",
+        "data": {
+            "resultType": "vector",
+            "result": [
+                {
+                    "metric": { "namespace": "shop", "pod": "cart-7d9f" },
+                    "value": [1712000000.5, "0.25"]
+                },
+                {
+                    "metric": { "namespace": "kube-system", "pod": "coredns-1" },
+                    "value": [1712000000.5, "0.01"]
+                },
+                // Without a pod label the series can't be attributed.
+                {
+                    "metric": { "namespace": "shop" },
+                    "value": [1712000000.5, "3"]
+                }
+            ]
+        }
+    });
+
+    let samples = prometheus::samples_from_response(
+        "prom-cluster",
+        ResourceType::Pod,
+        MetricType::CpuUsage,
+        "cores",
+        &response,
+    )
+    .unwrap();
+
+    assert_eq!(samples.len(), 2);
+    assert_eq!(samples[0].cluster_id, "prom-cluster");
+    assert_eq!(samples[0].resource_id, "shop/cart-7d9f");
+    assert_eq!(samples[0].resource_type, ResourceType::Pod);
+    assert_eq!(samples[0].metric_type, MetricType::CpuUsage);
+    assert_eq!(samples[0].timestamp, 1_712_000_000_500);
+    assert!((samples[0].value - 0.25).abs() < 1e-9);
+    assert_eq!(samples[0].unit, "cores");
+    assert_eq!(samples[1].resource_id, "kube-system/coredns-1");
+
+    let failed = serde_json::json!({ "status": "error", "error": "parse error" });
+    let err = prometheus::samples_from_response(
+        "prom-cluster",
+        ResourceType::Node,
+        MetricType::MemoryUsage,
+        "bytes",
+        &failed,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("parse error"));
+}
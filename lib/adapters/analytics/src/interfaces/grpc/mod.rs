@@ -1,12 +1,28 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use std::io::{self, Write};
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::server::NamedService;
+use tonic::transport::{Channel, Endpoint, Identity, ServerTlsConfig};
 use tonic::{Request, Response, Status};
+use tonic_health::ServingStatus;
+use tonic_health::server::HealthReporter;
 
 use phenome_domain as domain;
+use phenome_domain::GrpcServerConfig;
 use phenome_ports::AnalyticsPort;
 
 use crate::AnalyticsService;
+use crate::analytics_service::InvalidTransition;
+use crate::circuit_breaker::{CircuitBreaker, CircuitState};
+use crate::storage;
+
+mod auth;
+
+pub use auth::{AuthInterceptor, BearerToken};
 
 pub mod analytics {
     tonic::include_proto!("analytics");
@@ -17,6 +33,22 @@ use analytics::analytics_service_server::{
 };
 use analytics::*;
 
+/// Encoded descriptors of the analytics and ML protos, for server reflection.
+const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("phenome_descriptor");
+/// How often storage and the ML service are probed for the health service.
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Bytes buffered before an export chunk is sent to the client.
+const EXPORT_CHUNK_BYTES: usize = 64 * 1024;
+/// Chunks in flight before the exporting thread blocks on the client.
+const EXPORT_CHANNEL_CAPACITY: usize = 4;
+/// Samples per `StreamMetrics` batch.
+const STREAM_BATCH_SIZE: usize = 1000;
+/// Batches in flight before the reading thread blocks on the client.
+const STREAM_CHANNEL_CAPACITY: usize = 4;
+/// Anomalies in flight to a subscriber before its forwarder waits.
+const ANOMALY_CHANNEL_CAPACITY: usize = 16;
+
 #[derive(Debug)]
 pub struct GrpcAnalyticsService {
     inner: Arc<AnalyticsService>,
@@ -35,19 +67,19 @@ impl AnalyticsServiceTrait for GrpcAnalyticsService {
         request: Request<RecordMetricsRequest>,
     ) -> Result<Response<RecordMetricsResponse>, Status> {
         let req = request.into_inner();
-        let samples: Vec<domain::MetricSample> = req
-            .samples
-            .into_iter()
-            .map(TryInto::try_into)
-            .collect::<Result<_, _>>()
-            .map_err(|e: anyhow::Error| Status::invalid_argument(e.to_string()))?;
+        let (samples, rejected) = samples_from_proto(req.samples);
+        if rejected > 0 {
+            tracing::warn!("Rejected {} malformed metric samples", rejected);
+        }
 
         self.inner
             .record_metrics(samples)
             .await
             .map_err(|e| Status::internal(e.to_string()))?;
 
-        Ok(Response::new(RecordMetricsResponse {}))
+        Ok(Response::new(RecordMetricsResponse {
+            rejected: rejected as u32,
+        }))
     }
 
     async fn query_aggregated(
@@ -113,6 +145,52 @@ impl AnalyticsServiceTrait for GrpcAnalyticsService {
         }))
     }
 
+    type SubscribeAnomaliesStream = ReceiverStream<Result<Anomaly, Status>>;
+
+    async fn subscribe_anomalies(
+        &self,
+        request: Request<SubscribeAnomaliesRequest>,
+    ) -> Result<Response<Self::SubscribeAnomaliesStream>, Status> {
+        let cluster_id = request.into_inner().cluster_id;
+        let (backlog, mut anomalies) = self.inner.subscribe_anomalies();
+        let wanted = move |anomaly: &domain::Anomaly| {
+            cluster_id
+                .as_ref()
+                .is_none_or(|id| id == &anomaly.cluster_id)
+        };
+
+        let (tx, rx) = mpsc::channel(ANOMALY_CHANNEL_CAPACITY);
+        tokio::spawn(async move {
+            for anomaly in backlog.into_iter().filter(|anomaly| wanted(anomaly)) {
+                if tx.send(Ok(anomaly.into())).await.is_err() {
+                    return;
+                }
+            }
+            loop {
+                // Watch for the client going away too, so an idle feed
+                // doesn't keep its subscription alive.
+                let anomaly = tokio::select! {
+                    _ = tx.closed() => return,
+                    received = anomalies.recv() => received,
+                };
+                match anomaly {
+                    Ok(anomaly) if wanted(&anomaly) => {
+                        if tx.send(Ok(anomaly.into())).await.is_err() {
+                            return;
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        tracing::warn!("anomaly subscriber fell behind, skipped {}", missed);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return,
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
     async fn get_recommendations(
         &self,
         request: Request<GetRecommendationsRequest>,
@@ -131,6 +209,34 @@ impl AnalyticsServiceTrait for GrpcAnalyticsService {
         }))
     }
 
+    async fn apply_recommendation(
+        &self,
+        request: Request<ApplyRecommendationRequest>,
+    ) -> Result<Response<Recommendation>, Status> {
+        let req = request.into_inner();
+        let recommendation = self
+            .inner
+            .apply_recommendation(&req.id, req.execute_at)
+            .await
+            .map_err(status_change_error)?
+            .ok_or_else(|| Status::not_found(format!("no recommendation {}", req.id)))?;
+        Ok(Response::new(recommendation.into()))
+    }
+
+    async fn dismiss_recommendation(
+        &self,
+        request: Request<DismissRecommendationRequest>,
+    ) -> Result<Response<Recommendation>, Status> {
+        let req = request.into_inner();
+        let recommendation = self
+            .inner
+            .dismiss_recommendation(&req.id, req.reason)
+            .await
+            .map_err(status_change_error)?
+            .ok_or_else(|| Status::not_found(format!("no recommendation {}", req.id)))?;
+        Ok(Response::new(recommendation.into()))
+    }
+
     async fn query_metrics(
         &self,
         request: Request<QueryMetricsRequest>,
@@ -148,154 +254,563 @@ impl AnalyticsServiceTrait for GrpcAnalyticsService {
             samples: samples.into_iter().map(Into::into).collect(),
         }))
     }
+
+    type StreamMetricsStream = ReceiverStream<Result<MetricsBatch, Status>>;
+
+    async fn stream_metrics(
+        &self,
+        request: Request<QueryMetricsRequest>,
+    ) -> Result<Response<Self::StreamMetricsStream>, Status> {
+        let query: domain::MetricsQuery = request.into_inner().into();
+
+        let (tx, rx) = mpsc::channel(STREAM_CHANNEL_CAPACITY);
+        let inner = self.inner.clone();
+        tokio::task::spawn_blocking(move || {
+            // A failed send means the client went away; stop reading.
+            let mut sink = |samples: Vec<domain::MetricSample>| {
+                let batch = MetricsBatch {
+                    samples: samples.into_iter().map(Into::into).collect(),
+                };
+                tx.blocking_send(Ok(batch)).is_ok()
+            };
+            if let Err(err) = inner.stream_metrics(query, STREAM_BATCH_SIZE, &mut sink) {
+                tracing::warn!("metric stream failed: {err:#}");
+                let _ = tx.blocking_send(Err(Status::internal(err.to_string())));
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn query_downsampled(
+        &self,
+        request: Request<QueryDownsampledRequest>,
+    ) -> Result<Response<QueryDownsampledResponse>, Status> {
+        let req = request.into_inner();
+        if req.max_points == 0 {
+            return Err(Status::invalid_argument("max_points must be positive"));
+        }
+        let query: domain::MetricsQuery = req.query.unwrap_or_default().into();
+
+        let inner = self.inner.clone();
+        let downsampled = tokio::task::spawn_blocking(move || {
+            inner.query_downsampled(query, req.max_points as usize)
+        })
+        .await
+        .map_err(|e| Status::internal(e.to_string()))?
+        .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(downsampled.into()))
+    }
+
+    async fn list_clusters(
+        &self,
+        _request: Request<ListClustersRequest>,
+    ) -> Result<Response<ListClustersResponse>, Status> {
+        let cluster_ids = self
+            .inner
+            .list_clusters()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(ListClustersResponse { cluster_ids }))
+    }
+
+    async fn get_predictions(
+        &self,
+        request: Request<GetPredictionsRequest>,
+    ) -> Result<Response<GetPredictionsResponse>, Status> {
+        let req = request.into_inner();
+        let range = req
+            .time_range
+            .ok_or_else(|| Status::invalid_argument("missing time range"))?
+            .into();
+        let horizon = u64::try_from(req.horizon_ms)
+            .ok()
+            .filter(|&ms| ms > 0)
+            .map(Duration::from_millis)
+            .ok_or_else(|| Status::invalid_argument("horizon must be positive"))?;
+        let metric_types = req
+            .metric_types
+            .into_iter()
+            .filter_map(|t| MetricType::try_from(t).ok().and_then(|t| t.try_into().ok()))
+            .collect();
+
+        let predictions = self
+            .inner
+            .predict_scaling(req.cluster_id, range, horizon, metric_types)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(GetPredictionsResponse {
+            predictions: predictions.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn list_unread_notifications(
+        &self,
+        _request: Request<ListUnreadNotificationsRequest>,
+    ) -> Result<Response<ListUnreadNotificationsResponse>, Status> {
+        let notifications = self
+            .inner
+            .list_unread_notifications()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(ListUnreadNotificationsResponse {
+            notifications: notifications.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn mark_notification_read(
+        &self,
+        request: Request<MarkNotificationReadRequest>,
+    ) -> Result<Response<MarkNotificationReadResponse>, Status> {
+        let req = request.into_inner();
+        if req.id.is_empty() {
+            return Err(Status::invalid_argument("missing notification id"));
+        }
+
+        self.inner
+            .mark_notification_read(req.id)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(MarkNotificationReadResponse {}))
+    }
+
+    type ExportMetricsStream = ReceiverStream<Result<ExportMetricsChunk, Status>>;
+
+    async fn export_metrics(
+        &self,
+        request: Request<ExportMetricsRequest>,
+    ) -> Result<Response<Self::ExportMetricsStream>, Status> {
+        let req = request.into_inner();
+        let format: storage::ExportFormat = ExportFormat::try_from(req.format)
+            .map_err(|_| Status::invalid_argument("Invalid export format"))?
+            .try_into()
+            .map_err(|e: anyhow::Error| Status::invalid_argument(e.to_string()))?;
+        let query: domain::MetricsQuery = req.query.unwrap_or_default().into();
+
+        let (tx, rx) = mpsc::channel(EXPORT_CHANNEL_CAPACITY);
+        let inner = self.inner.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut writer = ChunkWriter::new(tx.clone());
+            if let Err(err) = inner.export_metrics(query, format, &mut writer) {
+                tracing::warn!("metric export failed: {err:#}");
+                let _ = tx.blocking_send(Err(Status::internal(err.to_string())));
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
+
+/// Adapts a blocking export writer onto a gRPC response channel.
+struct ChunkWriter {
+    tx: mpsc::Sender<Result<ExportMetricsChunk, Status>>,
+    buf: Vec<u8>,
+}
+
+impl ChunkWriter {
+    fn new(tx: mpsc::Sender<Result<ExportMetricsChunk, Status>>) -> Self {
+        Self {
+            tx,
+            buf: Vec::with_capacity(EXPORT_CHUNK_BYTES),
+        }
+    }
+
+    fn send_buffered(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let data = std::mem::replace(&mut self.buf, Vec::with_capacity(EXPORT_CHUNK_BYTES));
+        self.tx
+            .blocking_send(Ok(ExportMetricsChunk { data }))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "export client disconnected"))
+    }
+}
+
+impl Write for ChunkWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        if self.buf.len() >= EXPORT_CHUNK_BYTES {
+            self.send_buffered()?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.send_buffered()
+    }
+}
+
+/// `FAILED_PRECONDITION` for a transition the recommendation's status
+/// doesn't allow, `INTERNAL` for anything else.
+fn status_change_error(err: anyhow::Error) -> Status {
+    if err.is::<InvalidTransition>() {
+        Status::failed_precondition(err.to_string())
+    } else {
+        Status::internal(format!("{err:#}"))
+    }
 }
 
 pub struct GrpcServer;
 
 impl GrpcServer {
-    pub async fn serve(addr: SocketAddr, service: Arc<AnalyticsService>) -> Result<()> {
+    /// Serve on `addr`, over TLS and requiring a bearer token when `config`
+    /// sets them. The `grpc.health.v1.Health` and reflection services are
+    /// served alongside without authentication, for probes and `grpcurl`.
+    pub async fn serve(
+        addr: SocketAddr,
+        service: Arc<AnalyticsService>,
+        config: &GrpcServerConfig,
+    ) -> Result<()> {
+        let (reporter, health_service) = tonic_health::server::health_reporter();
+        tokio::spawn(report_health(reporter, service.clone()));
+        let reflection_service = tonic_reflection::server::Builder::configure()
+            .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+            .build_v1()?;
+
         let grpc_service = GrpcAnalyticsService::new(service);
-        tonic::transport::Server::builder()
-            .add_service(AnalyticsServiceServer::new(grpc_service))
+        let auth = AuthInterceptor::new(config.auth_token.clone());
+        let mut builder = tonic::transport::Server::builder();
+        if let Some(tls) = server_tls(config)? {
+            builder = builder.tls_config(tls)?;
+        }
+        builder
+            .add_service(health_service)
+            .add_service(reflection_service)
+            .add_service(AnalyticsServiceServer::with_interceptor(grpc_service, auth))
             .serve(addr)
             .await?;
         Ok(())
     }
 }
 
+/// Report the analytics service, and the server as a whole, as serving only
+/// while storage and the ML service answer.
+async fn report_health(mut reporter: HealthReporter, service: Arc<AnalyticsService>) {
+    let mut serving = None;
+    let mut tick = tokio::time::interval(HEALTH_CHECK_INTERVAL);
+    loop {
+        tick.tick().await;
+        let health = service.check_health().await;
+        if serving == Some(health.is_ok()) {
+            continue;
+        }
+        let status = match &health {
+            Ok(()) => {
+                tracing::info!("Analytics service is serving");
+                ServingStatus::Serving
+            }
+            Err(err) => {
+                tracing::warn!("Analytics service is not serving: {:#}", err);
+                ServingStatus::NotServing
+            }
+        };
+        reporter
+            .set_service_status(
+                <AnalyticsServiceServer<GrpcAnalyticsService> as NamedService>::NAME,
+                status,
+            )
+            .await;
+        reporter.set_service_status("", status).await;
+        serving = Some(health.is_ok());
+    }
+}
+
+fn server_tls(config: &GrpcServerConfig) -> Result<Option<ServerTlsConfig>> {
+    let (cert_path, key_path) = match (&config.tls_cert_path, &config.tls_key_path) {
+        (Some(cert_path), Some(key_path)) => (cert_path, key_path),
+        (None, None) => return Ok(None),
+        _ => anyhow::bail!("gRPC TLS needs both tls_cert_path and tls_key_path"),
+    };
+    let cert = std::fs::read(cert_path)
+        .with_context(|| format!("failed to read TLS certificate {cert_path}"))?;
+    let key =
+        std::fs::read(key_path).with_context(|| format!("failed to read TLS key {key_path}"))?;
+    Ok(Some(
+        ServerTlsConfig::new().identity(Identity::from_pem(cert, key)),
+    ))
+}
+
 pub mod ml {
     tonic::include_proto!("ml");
 }
 
+use ml::ml_service_client::MlServiceClient;
+
+/// Bound on establishing the channel, so an unreachable host fails fast.
+const ML_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Attempts per call, including the first.
+const ML_MAX_ATTEMPTS: u32 = 3;
+/// Wait before the first retry; doubled for each retry after it.
+const ML_RETRY_BACKOFF: Duration = Duration::from_millis(200);
+
+/// Client for the ML service. Connects lazily, reconnects after failures and
+/// stops calling for a while once the service keeps failing.
 #[derive(Debug, Clone)]
 pub struct MlClient {
-    // In a real app, this should be a pool or a robust client wrapper
-    // For now, storing the endpoint to connect on demand or a channel if established
     endpoint: String,
+    client: Arc<tokio::sync::Mutex<Option<MlServiceClient<Channel>>>>,
+    breaker: Arc<std::sync::Mutex<CircuitBreaker>>,
+    retry_backoff: Duration,
 }
 
 impl MlClient {
     pub async fn connect(endpoint: &str) -> Result<Self> {
-        // Just store endpoint, connection happens on request or we could establish channel here
-        // verifying connectivity
+        // The service may not be up yet; the first call establishes the channel.
         Ok(Self {
             endpoint: endpoint.to_string(),
+            client: Arc::new(tokio::sync::Mutex::new(None)),
+            breaker: Arc::new(std::sync::Mutex::new(CircuitBreaker::from_config(
+                &domain::CircuitBreakerConfig::default(),
+            ))),
+            retry_backoff: ML_RETRY_BACKOFF,
         })
     }
 
+    /// Guard calls with `breaker` instead of one with the default thresholds.
+    pub fn with_circuit_breaker(self, breaker: CircuitBreaker) -> Self {
+        Self {
+            breaker: Arc::new(std::sync::Mutex::new(breaker)),
+            ..self
+        }
+    }
+
+    /// Wait `backoff` before the first retry of a failed call.
+    pub fn with_retry_backoff(mut self, backoff: Duration) -> Self {
+        self.retry_backoff = backoff;
+        self
+    }
+
+    pub fn circuit_state(&self) -> CircuitState {
+        self.breaker
+            .lock()
+            .map(|breaker| breaker.state())
+            .unwrap_or(CircuitState::Open)
+    }
+
+    /// False while the circuit is open after repeated failures.
+    pub fn is_available(&self) -> bool {
+        self.circuit_state() != CircuitState::Open
+    }
+
+    /// Fails while the circuit is open or a fresh connection to the service
+    /// can't be made; the cached channel would hide a lost connection.
+    pub async fn probe(&self) -> Result<()> {
+        if !self.is_available() {
+            anyhow::bail!("circuit open for ML service at {}", self.endpoint);
+        }
+        Endpoint::from_shared(self.endpoint.clone())?
+            .connect_timeout(ML_CONNECT_TIMEOUT)
+            .connect()
+            .await
+            .with_context(|| format!("failed to connect to ML service at {}", self.endpoint))?;
+        Ok(())
+    }
+
     pub async fn detect_anomalies(
         &self,
         series: &domain::TimeSeries,
     ) -> Result<Vec<domain::Anomaly>> {
-        let mut client =
-            ml::ml_service_client::MlServiceClient::connect(self.endpoint.clone()).await?;
-
-        // Convert domain TimeSeries to proto TimeSeries
-        // We need a helper or From/TryFrom implementation
-        // For simplicity, constructing request manually or using a stub conversion if complex
-
-        let proto_series = analytics::TimeSeries {
-            // Simplified stub mapping
-            cluster_id: series.cluster_id.clone(),
-            resource_id: series.resource_id.clone(),
-            metric_type: i32::from(analytics::MetricType::from(series.metric_type)),
-            unit: series.unit.clone(),
-            points: series
-                .points
-                .iter()
-                .map(|p| analytics::TimeSeriesPoint {
-                    timestamp: p.timestamp,
-                    value: p.value,
-                })
-                .collect(),
-        };
+        let request = detect_anomalies_request(series);
+        let mut last_error = None;
+        for attempt in 0..ML_MAX_ATTEMPTS {
+            if !self.allow_request() {
+                break;
+            }
+            if attempt > 0 {
+                tokio::time::sleep(self.retry_backoff.saturating_mul(1 << (attempt - 1))).await;
+            }
+            match self.call_detect_anomalies(request.clone()).await {
+                Ok(response) => {
+                    self.record_outcome(true);
+                    return Ok(anomalies_from_response(response));
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        "ML detect_anomalies attempt {} of {} failed: {:#}",
+                        attempt + 1,
+                        ML_MAX_ATTEMPTS,
+                        err
+                    );
+                    self.record_outcome(false);
+                    last_error = Some(err);
+                }
+            }
+        }
+        Err(last_error
+            .unwrap_or_else(|| anyhow::anyhow!("ML service at {} is unavailable", self.endpoint)))
+    }
+
+    fn allow_request(&self) -> bool {
+        self.breaker
+            .lock()
+            .map(|mut breaker| breaker.allow_request())
+            .unwrap_or(false)
+    }
 
-        let range = if let (Some(first), Some(last)) = (series.points.first(), series.points.last())
-        {
-            analytics::TimeRange {
-                start_ms: first.timestamp,
-                end_ms: last.timestamp,
+    fn record_outcome(&self, success: bool) {
+        if let Ok(mut breaker) = self.breaker.lock() {
+            if success {
+                breaker.record_success();
+            } else {
+                breaker.record_failure();
             }
-        } else {
-            analytics::TimeRange {
-                start_ms: 0,
-                end_ms: 0,
+        }
+    }
+
+    /// Send one request, connecting first if needed. A failed call drops the
+    /// channel so the next attempt reconnects.
+    async fn call_detect_anomalies(
+        &self,
+        request: ml::DetectAnomaliesRequest,
+    ) -> Result<ml::DetectAnomaliesResponse> {
+        let mut client = {
+            let mut cached = self.client.lock().await;
+            match cached.as_ref() {
+                Some(client) => client.clone(),
+                None => {
+                    let channel = Endpoint::from_shared(self.endpoint.clone())?
+                        .connect_timeout(ML_CONNECT_TIMEOUT)
+                        .connect()
+                        .await?;
+                    let client = MlServiceClient::new(channel);
+                    *cached = Some(client.clone());
+                    client
+                }
             }
         };
+        match client.detect_anomalies(tonic::Request::new(request)).await {
+            Ok(response) => Ok(response.into_inner()),
+            Err(status) => {
+                *self.client.lock().await = None;
+                Err(status.into())
+            }
+        }
+    }
+}
 
-        let time_series_data = ml::TimeSeriesData {
-            cluster_id: series.cluster_id.clone(),
-            range: Some(range),
-            series: vec![proto_series],
-        };
+fn detect_anomalies_request(series: &domain::TimeSeries) -> ml::DetectAnomaliesRequest {
+    // Convert domain TimeSeries to proto TimeSeries
+    // We need a helper or From/TryFrom implementation
+    // For simplicity, constructing request manually or using a stub conversion if complex
+
+    let proto_series = analytics::TimeSeries {
+        // Simplified stub mapping
+        cluster_id: series.cluster_id.clone(),
+        resource_id: series.resource_id.clone(),
+        metric_type: i32::from(analytics::MetricType::from(series.metric_type)),
+        unit: series.unit.clone(),
+        points: series
+            .points
+            .iter()
+            .map(|p| analytics::TimeSeriesPoint {
+                timestamp: p.timestamp,
+                value: p.value,
+            })
+            .collect(),
+    };
 
-        let request = tonic::Request::new(ml::DetectAnomaliesRequest {
-            data: Some(time_series_data),
-        });
+    let range = if let (Some(first), Some(last)) = (series.points.first(), series.points.last()) {
+        analytics::TimeRange {
+            start_ms: first.timestamp,
+            end_ms: last.timestamp,
+        }
+    } else {
+        analytics::TimeRange {
+            start_ms: 0,
+            end_ms: 0,
+        }
+    };
 
-        let response = client.detect_anomalies(request).await?;
-        let inner = response.into_inner();
+    let time_series_data = ml::TimeSeriesData {
+        cluster_id: series.cluster_id.clone(),
+        range: Some(range),
+        series: vec![proto_series],
+    };
 
-        // Convert back
-        // Stub conversion
-        Ok(inner
-            .anomalies
-            .into_iter()
-            .map(|a| domain::Anomaly {
-                id: a.id,
-                cluster_id: a.cluster_id,
-                resource_id: a.resource_id,
-                detected_at: a.detected_at,
-                metric_type: match analytics::MetricType::try_from(a.metric_type).ok() {
-                    Some(analytics::MetricType::CpuUsage) => domain::MetricType::CpuUsage,
-                    Some(analytics::MetricType::MemoryUsage) => domain::MetricType::MemoryUsage,
-                    Some(analytics::MetricType::NetworkIn) => domain::MetricType::NetworkIn,
-                    Some(analytics::MetricType::NetworkOut) => domain::MetricType::NetworkOut,
-                    Some(analytics::MetricType::DiskRead) => domain::MetricType::DiskRead,
-                    Some(analytics::MetricType::DiskWrite) => domain::MetricType::DiskWrite,
-                    _ => domain::MetricType::CpuUsage, // Fallback
-                },
-                severity: match analytics::Severity::try_from(a.severity) {
-                    Ok(analytics::Severity::Critical) => domain::Severity::Critical,
-                    Ok(analytics::Severity::Warning) => domain::Severity::Warning,
-                    Ok(analytics::Severity::Info) => domain::Severity::Info,
-                    _ => domain::Severity::Info,
-                },
-                confidence: a.confidence,
-                description: a.description,
-                baseline_value: a.baseline_value,
-                observed_value: a.observed_value,
-                deviation_sigma: a.deviation_sigma,
-                related_metrics: a.related_metrics,
-                root_cause: a.root_cause.filter(|s| !s.is_empty()),
-            })
-            .collect())
+    ml::DetectAnomaliesRequest {
+        data: Some(time_series_data),
     }
 }
 
+fn anomalies_from_response(response: ml::DetectAnomaliesResponse) -> Vec<domain::Anomaly> {
+    response
+        .anomalies
+        .into_iter()
+        .map(|a| domain::Anomaly {
+            id: a.id,
+            cluster_id: a.cluster_id,
+            resource_id: a.resource_id,
+            detected_at: a.detected_at,
+            metric_type: match analytics::MetricType::try_from(a.metric_type).ok() {
+                Some(analytics::MetricType::CpuUsage) => domain::MetricType::CpuUsage,
+                Some(analytics::MetricType::MemoryUsage) => domain::MetricType::MemoryUsage,
+                Some(analytics::MetricType::NetworkIn) => domain::MetricType::NetworkIn,
+                Some(analytics::MetricType::NetworkOut) => domain::MetricType::NetworkOut,
+                Some(analytics::MetricType::DiskRead) => domain::MetricType::DiskRead,
+                Some(analytics::MetricType::DiskWrite) => domain::MetricType::DiskWrite,
+                _ => domain::MetricType::CpuUsage, // Fallback
+            },
+            severity: match analytics::Severity::try_from(a.severity) {
+                Ok(analytics::Severity::Critical) => domain::Severity::Critical,
+                Ok(analytics::Severity::Warning) => domain::Severity::Warning,
+                Ok(analytics::Severity::Info) => domain::Severity::Info,
+                _ => domain::Severity::Info,
+            },
+            confidence: a.confidence,
+            description: a.description,
+            baseline_value: a.baseline_value,
+            observed_value: a.observed_value,
+            deviation_sigma: a.deviation_sigma,
+            related_metrics: a.related_metrics,
+            root_cause: a.root_cause.filter(|s| !s.is_empty()),
+        })
+        .collect()
+}
+
 // Conversions
 
 impl TryFrom<MetricSample> for domain::MetricSample {
     type Error = anyhow::Error;
 
     fn try_from(val: MetricSample) -> Result<Self, Self::Error> {
-        let resource_type = match ResourceType::try_from(val.resource_type)? {
-            analytics::ResourceType::Pod => domain::ResourceType::Pod,
-            analytics::ResourceType::Node => domain::ResourceType::Node,
-            analytics::ResourceType::Container => domain::ResourceType::Container,
-            analytics::ResourceType::Service => domain::ResourceType::Service,
-            _ => domain::ResourceType::Pod,
-        };
-
-        let metric_type = match MetricType::try_from(val.metric_type)? {
-            analytics::MetricType::CpuUsage => domain::MetricType::CpuUsage,
-            analytics::MetricType::MemoryUsage => domain::MetricType::MemoryUsage,
-            analytics::MetricType::NetworkIn => domain::MetricType::NetworkIn,
-            analytics::MetricType::NetworkOut => domain::MetricType::NetworkOut,
-            analytics::MetricType::DiskRead => domain::MetricType::DiskRead,
-            analytics::MetricType::DiskWrite => domain::MetricType::DiskWrite,
-            _ => domain::MetricType::CpuUsage,
-        };
+        let resource_type = ResourceType::try_from(val.resource_type)
+            .map_err(anyhow::Error::from)
+            .and_then(domain::ResourceType::try_from)
+            .with_context(|| {
+                format!(
+                    "sample of {} has invalid resource type {}",
+                    val.resource_id, val.resource_type
+                )
+            })?;
+        let metric_type = MetricType::try_from(val.metric_type)
+            .map_err(anyhow::Error::from)
+            .and_then(domain::MetricType::try_from)
+            .with_context(|| {
+                format!(
+                    "sample of {} has invalid metric type {}",
+                    val.resource_id, val.metric_type
+                )
+            })?;
+
+        if !val.value.is_finite() {
+            anyhow::bail!(
+                "sample of {} has non-finite value {}",
+                val.resource_id,
+                val.value
+            );
+        }
+        if val.timestamp <= 0 {
+            anyhow::bail!(
+                "sample of {} has non-positive timestamp {}",
+                val.resource_id,
+                val.timestamp
+            );
+        }
 
         Ok(domain::MetricSample {
             cluster_id: val.cluster_id,
@@ -309,6 +824,23 @@ impl TryFrom<MetricSample> for domain::MetricSample {
     }
 }
 
+/// Convert a batch, skipping samples that fail validation instead of
+/// failing the batch. Returns the valid samples and how many were skipped.
+pub fn samples_from_proto(samples: Vec<MetricSample>) -> (Vec<domain::MetricSample>, usize) {
+    let mut valid = Vec::with_capacity(samples.len());
+    let mut rejected = 0;
+    for sample in samples {
+        match domain::MetricSample::try_from(sample) {
+            Ok(sample) => valid.push(sample),
+            Err(err) => {
+                rejected += 1;
+                tracing::debug!("Skipping malformed metric sample: {:#}", err);
+            }
+        }
+    }
+    (valid, rejected)
+}
+
 impl From<domain::MetricSample> for MetricSample {
     fn from(val: domain::MetricSample) -> Self {
         Self {
@@ -323,6 +855,32 @@ impl From<domain::MetricSample> for MetricSample {
     }
 }
 
+impl From<domain::DownsampledMetrics> for QueryDownsampledResponse {
+    fn from(val: domain::DownsampledMetrics) -> Self {
+        Self {
+            bucket_width_ms: val.bucket_width_ms,
+            buckets: val.buckets.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<domain::MetricBucket> for MetricBucket {
+    fn from(val: domain::MetricBucket) -> Self {
+        Self {
+            cluster_id: val.cluster_id,
+            resource_type: ResourceType::from(val.resource_type).into(),
+            resource_id: val.resource_id,
+            metric_type: MetricType::from(val.metric_type).into(),
+            bucket_start: val.bucket_start,
+            count: val.count,
+            avg: val.avg,
+            min: val.min,
+            max: val.max,
+            unit: val.unit,
+        }
+    }
+}
+
 impl TryFrom<ResourceType> for domain::ResourceType {
     type Error = anyhow::Error;
 
@@ -500,6 +1058,38 @@ impl From<domain::Anomaly> for Anomaly {
     }
 }
 
+impl From<domain::ScalingPrediction> for ScalingPrediction {
+    fn from(val: domain::ScalingPrediction) -> Self {
+        Self {
+            resource_id: val.resource_id,
+            generated_at: val.generated_at,
+            horizon: val.horizon.as_millis() as i64,
+            predicted_value: val.predicted_value,
+            unit: val.unit,
+            metric_type: MetricType::from(val.metric_type).into(),
+            lower_bound: val.lower_bound,
+            upper_bound: val.upper_bound,
+            time_to_threshold_ms: val.time_to_threshold.map(|ahead| ahead.as_millis() as i64),
+        }
+    }
+}
+
+impl From<domain::Notification> for Notification {
+    fn from(val: domain::Notification) -> Self {
+        Self {
+            id: val.id,
+            title: val.title,
+            message: val.message,
+            severity: Severity::from(val.severity).into(),
+            timestamp: val.timestamp,
+            read: val.read,
+            link: val.link,
+            cluster_id: val.cluster_id,
+            resource_id: val.resource_id,
+        }
+    }
+}
+
 impl TryFrom<Severity> for domain::Severity {
     type Error = anyhow::Error;
 
@@ -675,6 +1265,18 @@ impl TryFrom<RecommendationStatusKind> for domain::RecommendationStatusKind {
     }
 }
 
+impl TryFrom<ExportFormat> for storage::ExportFormat {
+    type Error = anyhow::Error;
+
+    fn try_from(val: ExportFormat) -> Result<Self, Self::Error> {
+        match val {
+            ExportFormat::Csv => Ok(storage::ExportFormat::Csv),
+            ExportFormat::JsonLines => Ok(storage::ExportFormat::JsonLines),
+            ExportFormat::Unspecified => anyhow::bail!("unspecified export format"),
+        }
+    }
+}
+
 impl From<QueryMetricsRequest> for domain::MetricsQuery {
     fn from(val: QueryMetricsRequest) -> Self {
         domain::MetricsQuery {
@@ -694,3 +1296,22 @@ impl From<QueryMetricsRequest> for domain::MetricsQuery {
         }
     }
 }
+
+impl From<domain::MetricsQuery> for QueryMetricsRequest {
+    fn from(val: domain::MetricsQuery) -> Self {
+        QueryMetricsRequest {
+            cluster_id: val.cluster_id,
+            resource_type: val.resource_type.map(|t| ResourceType::from(t) as i32),
+            resource_ids: val.resource_ids,
+            metric_types: val
+                .metric_types
+                .into_iter()
+                .map(|t| MetricType::from(t) as i32)
+                .collect(),
+            time_range: val.time_range.map(Into::into),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,773 @@
+use std::net::{SocketAddr, TcpListener};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tonic::service::Interceptor;
+use tonic::{Code, Request, Response, Status};
+
+use phenome_domain::{
+    Anomaly, AnomalyFilter, GrpcServerConfig, MetricSample, MetricType, Notification, Priority,
+    Recommendation, RecommendationAction, RecommendationFilter, RecommendationStatus,
+    RecommendationType, ResourceLimits, ResourceType, Severity, TimeRange,
+};
+use phenome_ports::AnalyticsPort;
+
+use super::analytics;
+use super::analytics::analytics_service_server::AnalyticsService as _;
+use super::ml::ml_service_server::{MlService, MlServiceServer};
+use super::ml::*;
+use super::{
+    AuthInterceptor, BearerToken, GrpcAnalyticsService, GrpcServer, MlClient, samples_from_proto,
+};
+use crate::AnalyticsService;
+use crate::circuit_breaker::{CircuitBreaker, CircuitState};
+use crate::storage::StoragePort;
+use crate::storage::sqlite::SqliteStorage;
+
+/// ML service that flags every series it is sent.
+struct FlagEverything;
+
+#[tonic::async_trait]
+impl MlService for FlagEverything {
+    async fn detect_anomalies(
+        &self,
+        request: Request<DetectAnomaliesRequest>,
+    ) -> Result<Response<DetectAnomaliesResponse>, Status> {
+        let data = request.into_inner().data.unwrap_or_default();
+        let anomalies = data
+            .series
+            .iter()
+            .map(|series| analytics::Anomaly {
+                id: format!("anomaly-{}", series.resource_id),
+                cluster_id: data.cluster_id.clone(),
+                resource_id: series.resource_id.clone(),
+                metric_type: series.metric_type,
+                severity: analytics::Severity::Critical as i32,
+                ..analytics::Anomaly::default()
+            })
+            .collect();
+        Ok(Response::new(DetectAnomaliesResponse { anomalies }))
+    }
+
+    async fn predict_scaling_needs(
+        &self,
+        _request: Request<PredictScalingNeedsRequest>,
+    ) -> Result<Response<PredictScalingNeedsResponse>, Status> {
+        Err(Status::unimplemented("predict_scaling_needs"))
+    }
+
+    async fn generate_recommendations(
+        &self,
+        _request: Request<GenerateRecommendationsRequest>,
+    ) -> Result<Response<GenerateRecommendationsResponse>, Status> {
+        Err(Status::unimplemented("generate_recommendations"))
+    }
+}
+
+fn unused_addr() -> SocketAddr {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn ml_outage_opens_circuit_and_recovers() {
+    let addr = unused_addr();
+    let client = MlClient::connect(&format!("http://{addr}"))
+        .await
+        .unwrap()
+        .with_retry_backoff(Duration::from_millis(10))
+        .with_circuit_breaker(CircuitBreaker::new(3, Duration::from_millis(200), 1));
+
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("analytics.db");
+    let storage = Arc::new(SqliteStorage::new(db_path.to_string_lossy().to_string()).unwrap());
+    let service = AnalyticsService::new(storage, client.clone());
+    let filter = AnomalyFilter {
+        resource_id: Some("web".to_string()),
+        metric_type: Some(MetricType::CpuUsage),
+        time_range: Some(TimeRange {
+            start_ms: 0,
+            end_ms: i64::MAX,
+        }),
+        ..AnomalyFilter::default()
+    };
+
+    // Nothing listens yet: every attempt fails, the circuit opens and the
+    // service still answers from what it has stored.
+    let anomalies = service.get_anomalies(filter.clone()).await.unwrap();
+    assert!(anomalies.is_empty());
+    assert_eq!(client.circuit_state(), CircuitState::Open);
+    assert!(!service.ml_available());
+
+    tokio::spawn(
+        tonic::transport::Server::builder()
+            .add_service(MlServiceServer::new(FlagEverything))
+            .serve(addr),
+    );
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    // Past the open window the client reconnects to the restarted service.
+    let anomalies = service.get_anomalies(filter).await.unwrap();
+    assert_eq!(anomalies.len(), 1);
+    assert_eq!(anomalies[0].id, "anomaly-web");
+    assert_eq!(client.circuit_state(), CircuitState::Closed);
+}
+
+#[tokio::test]
+async fn ml_outage_falls_back_to_statistical_detection() {
+    let client = MlClient::connect(&format!("http://{}", unused_addr()))
+        .await
+        .unwrap()
+        .with_retry_backoff(Duration::from_millis(10));
+
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("analytics.db");
+    let storage = Arc::new(SqliteStorage::new(db_path.to_string_lossy().to_string()).unwrap());
+    let service = AnalyticsService::new(storage, client);
+    // A steady 50% CPU that spikes to 95% on the latest sample.
+    let samples = (0..20)
+        .map(|minute| MetricSample {
+            cluster_id: "prod".to_string(),
+            resource_type: ResourceType::Pod,
+            resource_id: "web".to_string(),
+            metric_type: MetricType::CpuUsage,
+            timestamp: minute * 60_000,
+            value: if minute == 19 { 0.95 } else { 0.5 },
+            unit: "cores".to_string(),
+        })
+        .collect();
+    service.record_metrics(samples).await.unwrap();
+
+    let anomalies = service
+        .get_anomalies(AnomalyFilter {
+            resource_id: Some("web".to_string()),
+            metric_type: Some(MetricType::CpuUsage),
+            time_range: Some(TimeRange {
+                start_ms: 0,
+                end_ms: i64::MAX,
+            }),
+            ..AnomalyFilter::default()
+        })
+        .await
+        .unwrap();
+    assert!(!service.ml_available());
+    assert_eq!(anomalies.len(), 1);
+    assert_eq!(anomalies[0].cluster_id, "prod");
+    assert_eq!(anomalies[0].detected_at, 19 * 60_000);
+    assert_eq!(anomalies[0].severity, Severity::Critical);
+    assert_eq!(anomalies[0].observed_value, 0.95);
+}
+
+#[test]
+fn sample_with_unknown_enum_value_is_rejected_with_its_value() {
+    let sample = analytics::MetricSample {
+        cluster_id: "prod".to_string(),
+        resource_type: analytics::ResourceType::Pod as i32,
+        resource_id: "default/web".to_string(),
+        metric_type: 42,
+        timestamp: 1_700_000_000_000,
+        value: 0.5,
+        unit: "cores".to_string(),
+    };
+    let err = MetricSample::try_from(sample.clone()).unwrap_err();
+    assert!(
+        format!("{err:#}").contains("default/web has invalid metric type 42"),
+        "{err:#}"
+    );
+
+    let unspecified = analytics::MetricSample {
+        resource_type: analytics::ResourceType::Unspecified as i32,
+        metric_type: analytics::MetricType::CpuUsage as i32,
+        ..sample
+    };
+    assert!(MetricSample::try_from(unspecified).is_err());
+}
+
+fn valid_sample() -> analytics::MetricSample {
+    analytics::MetricSample {
+        cluster_id: "prod".to_string(),
+        resource_type: analytics::ResourceType::Node as i32,
+        resource_id: "node-1".to_string(),
+        metric_type: analytics::MetricType::CpuUsage as i32,
+        timestamp: 1_700_000_000_000,
+        value: 0.5,
+        unit: "cores".to_string(),
+    }
+}
+
+#[test]
+fn sample_with_non_finite_value_is_rejected() {
+    for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+        let err = MetricSample::try_from(analytics::MetricSample {
+            value,
+            ..valid_sample()
+        })
+        .unwrap_err();
+        assert!(
+            format!("{err:#}").contains("node-1 has non-finite value"),
+            "{err:#}"
+        );
+    }
+}
+
+#[test]
+fn sample_with_non_positive_timestamp_is_rejected() {
+    for timestamp in [0, -1_700_000_000_000] {
+        let err = MetricSample::try_from(analytics::MetricSample {
+            timestamp,
+            ..valid_sample()
+        })
+        .unwrap_err();
+        assert!(
+            format!("{err:#}").contains(&format!("node-1 has non-positive timestamp {timestamp}")),
+            "{err:#}"
+        );
+    }
+}
+
+#[test]
+fn sample_with_unknown_resource_type_is_rejected_with_its_value() {
+    let err = MetricSample::try_from(analytics::MetricSample {
+        resource_type: 9,
+        ..valid_sample()
+    })
+    .unwrap_err();
+    assert!(
+        format!("{err:#}").contains("node-1 has invalid resource type 9"),
+        "{err:#}"
+    );
+}
+
+#[test]
+fn malformed_samples_are_counted_without_failing_the_batch() {
+    let batch = vec![
+        valid_sample(),
+        analytics::MetricSample {
+            value: f64::NAN,
+            ..valid_sample()
+        },
+        analytics::MetricSample {
+            timestamp: 0,
+            ..valid_sample()
+        },
+        analytics::MetricSample {
+            metric_type: 42,
+            ..valid_sample()
+        },
+        analytics::MetricSample {
+            resource_id: "node-2".to_string(),
+            ..valid_sample()
+        },
+    ];
+    let (samples, rejected) = samples_from_proto(batch);
+    assert_eq!(rejected, 3);
+    let ids: Vec<_> = samples.iter().map(|s| s.resource_id.as_str()).collect();
+    assert_eq!(ids, ["node-1", "node-2"]);
+}
+
+#[test]
+fn auth_interceptor_rejects_missing_or_wrong_token() {
+    let mut auth = AuthInterceptor::new(Some("s3cret".to_string()));
+
+    let mut wrong = Request::new(());
+    wrong
+        .metadata_mut()
+        .insert("authorization", "Bearer guess".parse().unwrap());
+    assert_eq!(auth.call(wrong).unwrap_err().code(), Code::Unauthenticated);
+    assert_eq!(
+        auth.call(Request::new(())).unwrap_err().code(),
+        Code::Unauthenticated
+    );
+
+    let mut client_side = BearerToken::new(Some("s3cret".to_string())).unwrap();
+    let signed = client_side.call(Request::new(())).unwrap();
+    assert!(auth.call(signed).is_ok());
+
+    // Without a configured token the server stays open.
+    assert!(AuthInterceptor::new(None).call(Request::new(())).is_ok());
+}
+
+#[tokio::test]
+async fn health_service_reports_serving_when_dependencies_answer() {
+    use tonic_health::pb::HealthCheckRequest;
+    use tonic_health::pb::health_check_response::ServingStatus;
+    use tonic_health::pb::health_client::HealthClient;
+
+    let ml_addr = unused_addr();
+    tokio::spawn(
+        tonic::transport::Server::builder()
+            .add_service(MlServiceServer::new(FlagEverything))
+            .serve(ml_addr),
+    );
+    let client = MlClient::connect(&format!("http://{ml_addr}"))
+        .await
+        .unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("analytics.db");
+    let storage = Arc::new(SqliteStorage::new(db_path.to_string_lossy().to_string()).unwrap());
+    let service = Arc::new(AnalyticsService::new(storage, client));
+
+    let addr = unused_addr();
+    let config = GrpcServerConfig {
+        auth_token: Some("s3cret".to_string()),
+        ..GrpcServerConfig::default()
+    };
+    tokio::spawn(async move { GrpcServer::serve(addr, service, &config).await });
+
+    // Probes don't carry the bearer token.
+    let status = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            if let Ok(mut health) = HealthClient::connect(format!("http://{addr}")).await {
+                let request = HealthCheckRequest {
+                    service: "analytics.AnalyticsService".to_string(),
+                };
+                if let Ok(response) = health.check(request).await {
+                    let status = response.into_inner().status;
+                    if status != ServingStatus::Unknown as i32 {
+                        return status;
+                    }
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("health service never answered");
+    assert_eq!(status, ServingStatus::Serving as i32);
+}
+
+fn detected_anomaly(id: &str, cluster_id: &str) -> Anomaly {
+    Anomaly {
+        id: id.to_string(),
+        cluster_id: cluster_id.to_string(),
+        resource_id: "node-1".to_string(),
+        detected_at: 1_700_000_000_000,
+        metric_type: MetricType::CpuUsage,
+        severity: Severity::Warning,
+        confidence: 0.9,
+        description: "node-1 cpu at 85%".to_string(),
+        baseline_value: 0.5,
+        observed_value: 0.85,
+        deviation_sigma: 3.5,
+        related_metrics: Vec::new(),
+        root_cause: None,
+    }
+}
+
+async fn next_anomaly(stream: &mut tonic::Streaming<analytics::Anomaly>) -> analytics::Anomaly {
+    tokio::time::timeout(Duration::from_secs(5), stream.message())
+        .await
+        .expect("no anomaly arrived")
+        .unwrap()
+        .expect("stream ended")
+}
+
+#[tokio::test]
+async fn subscribed_client_receives_backlog_and_new_anomalies() {
+    use analytics::SubscribeAnomaliesRequest;
+    use analytics::analytics_service_client::AnalyticsServiceClient;
+
+    let client = MlClient::connect(&format!("http://{}", unused_addr()))
+        .await
+        .unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("analytics.db");
+    let storage = Arc::new(SqliteStorage::new(db_path.to_string_lossy().to_string()).unwrap());
+    let service = Arc::new(AnalyticsService::new(storage, client));
+    service.add_anomalies(vec![detected_anomaly("before", "prod")]);
+
+    let addr = unused_addr();
+    let server = service.clone();
+    tokio::spawn(
+        async move { GrpcServer::serve(addr, server, &GrpcServerConfig::default()).await },
+    );
+    let mut grpc = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            if let Ok(grpc) = AnalyticsServiceClient::connect(format!("http://{addr}")).await {
+                return grpc;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("analytics server never came up");
+
+    let mut stream = grpc
+        .subscribe_anomalies(SubscribeAnomaliesRequest {
+            cluster_id: Some("prod".to_string()),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(next_anomaly(&mut stream).await.id, "before");
+
+    // Anomalies of other clusters are filtered out.
+    service.add_anomalies(vec![
+        detected_anomaly("staging", "staging"),
+        detected_anomaly("after", "prod"),
+    ]);
+    assert_eq!(next_anomaly(&mut stream).await.id, "after");
+
+    drop(stream);
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while service.anomaly_subscriber_count() > 0 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("dropped subscriber was never cleaned up");
+}
+
+fn pending_recommendation(id: &str, action: RecommendationAction) -> Recommendation {
+    Recommendation {
+        id: id.to_string(),
+        cluster_id: "prod".to_string(),
+        created_at: 1_700_000_000_000,
+        recommendation_type: RecommendationType::ScaleDown,
+        priority: Priority::Medium,
+        confidence: 0.8,
+        title: format!("{id} title"),
+        description: format!("{id} description"),
+        impact_estimate: "saves 2 cores".to_string(),
+        cost_impact: None,
+        action,
+        status: RecommendationStatus::Pending,
+    }
+}
+
+#[tokio::test]
+async fn recommendation_status_changes_persist_across_refetch_and_restart() {
+    use analytics::analytics_service_client::AnalyticsServiceClient;
+    use analytics::recommendation_status::Status as GrpcStatus;
+    use analytics::{
+        ApplyRecommendationRequest, DismissRecommendationRequest, GetRecommendationsRequest,
+    };
+
+    let ml_addr = format!("http://{}", unused_addr());
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("analytics.db");
+    let storage = Arc::new(SqliteStorage::new(db_path.to_string_lossy().to_string()).unwrap());
+    let service = Arc::new(AnalyticsService::new(
+        storage.clone(),
+        MlClient::connect(&ml_addr).await.unwrap(),
+    ));
+    service
+        .add_recommendations(vec![
+            pending_recommendation(
+                "scale",
+                RecommendationAction::ScaleDeployment {
+                    name: "api".to_string(),
+                    from: 4,
+                    to: 2,
+                },
+            ),
+            pending_recommendation(
+                "limits",
+                RecommendationAction::UpdateResourceLimits {
+                    resource: "api".to_string(),
+                    limits: ResourceLimits {
+                        cpu: Some("500m".to_string()),
+                        memory: None,
+                    },
+                },
+            ),
+        ])
+        .await
+        .unwrap();
+
+    let addr = unused_addr();
+    let server = service.clone();
+    tokio::spawn(
+        async move { GrpcServer::serve(addr, server, &GrpcServerConfig::default()).await },
+    );
+    let mut grpc = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            if let Ok(grpc) = AnalyticsServiceClient::connect(format!("http://{addr}")).await {
+                return grpc;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("analytics server never came up");
+
+    let execute_at = 1_800_000_000_000;
+    let scheduled = grpc
+        .apply_recommendation(ApplyRecommendationRequest {
+            id: "scale".to_string(),
+            execute_at: Some(execute_at),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(
+        scheduled.status.and_then(|s| s.status),
+        Some(GrpcStatus::ScheduledAt(execute_at))
+    );
+    let schedules = storage.get_all_schedules().await.unwrap();
+    assert_eq!(schedules.len(), 1);
+    assert_eq!(schedules[0].recommendation_id, "scale");
+    assert_eq!(schedules[0].execute_at, execute_at);
+
+    // Only scale actions can be scheduled.
+    let err = grpc
+        .apply_recommendation(ApplyRecommendationRequest {
+            id: "limits".to_string(),
+            execute_at: Some(execute_at),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::FailedPrecondition);
+
+    grpc.dismiss_recommendation(DismissRecommendationRequest {
+        id: "limits".to_string(),
+        reason: "limits are managed by the chart".to_string(),
+    })
+    .await
+    .unwrap();
+    let err = grpc
+        .apply_recommendation(ApplyRecommendationRequest {
+            id: "limits".to_string(),
+            execute_at: None,
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::FailedPrecondition);
+    let err = grpc
+        .dismiss_recommendation(DismissRecommendationRequest {
+            id: "missing".to_string(),
+            reason: String::new(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::NotFound);
+
+    let refetched = grpc
+        .get_recommendations(GetRecommendationsRequest::default())
+        .await
+        .unwrap()
+        .into_inner()
+        .recommendations;
+    let status_of = |id: &str| {
+        refetched
+            .iter()
+            .find(|rec| rec.id == id)
+            .and_then(|rec| rec.status.clone())
+            .and_then(|s| s.status)
+    };
+    assert_eq!(
+        status_of("scale"),
+        Some(GrpcStatus::ScheduledAt(execute_at))
+    );
+    assert_eq!(
+        status_of("limits"),
+        Some(GrpcStatus::DismissedReason(
+            "limits are managed by the chart".to_string()
+        ))
+    );
+
+    // A restarted service picks the statuses up from storage.
+    let restarted = AnalyticsService::new(storage, MlClient::connect(&ml_addr).await.unwrap());
+    assert_eq!(restarted.restore_recommendations().await.unwrap(), 2);
+    let restored = restarted
+        .get_recommendations(RecommendationFilter::default())
+        .await
+        .unwrap();
+    let restored_status = |id: &str| {
+        restored
+            .iter()
+            .find(|rec| rec.id == id)
+            .map(|rec| rec.status.clone())
+            .unwrap()
+    };
+    assert!(matches!(
+        restored_status("scale"),
+        RecommendationStatus::Scheduled { execute_at: at } if at == execute_at
+    ));
+    assert!(matches!(
+        restored_status("limits"),
+        RecommendationStatus::Dismissed { reason } if reason == "limits are managed by the chart"
+    ));
+}
+
+#[tokio::test]
+async fn predictions_cover_each_resource_and_metric_in_range() {
+    let client = MlClient::connect(&format!("http://{}", unused_addr()))
+        .await
+        .unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("analytics.db");
+    let storage = Arc::new(SqliteStorage::new(db_path.to_string_lossy().to_string()).unwrap());
+    let service = AnalyticsService::new(storage, client);
+    // CPU of two pods climbing 1% a minute, and one memory series.
+    let sample = |resource_id: &str, metric_type, minute: i64, value| MetricSample {
+        cluster_id: "prod".to_string(),
+        resource_type: ResourceType::Pod,
+        resource_id: resource_id.to_string(),
+        metric_type,
+        timestamp: minute * 60_000,
+        value,
+        unit: "%".to_string(),
+    };
+    let mut samples = Vec::new();
+    for minute in 1..=10 {
+        let rise = minute as f64;
+        samples.push(sample("web", MetricType::CpuUsage, minute, 85.0 + rise));
+        samples.push(sample("api", MetricType::CpuUsage, minute, 20.0 + rise));
+        samples.push(sample("api", MetricType::MemoryUsage, minute, 40.0));
+    }
+    service.record_metrics(samples).await.unwrap();
+
+    let range = TimeRange {
+        start_ms: 0,
+        end_ms: 10 * 60_000,
+    };
+    let predictions = service
+        .predict_scaling(
+            Some("prod".to_string()),
+            range,
+            Duration::from_secs(10 * 60),
+            vec![MetricType::CpuUsage],
+        )
+        .await
+        .unwrap();
+
+    let resources: Vec<&str> = predictions
+        .iter()
+        .map(|prediction| prediction.resource_id.as_str())
+        .collect();
+    assert_eq!(resources, ["api", "web"]);
+    assert!((predictions[1].predicted_value - 105.0).abs() < 1e-6);
+    assert_eq!(predictions[0].time_to_threshold, None);
+    assert_eq!(
+        predictions[1].time_to_threshold,
+        Some(Duration::from_secs(5 * 60))
+    );
+}
+
+async fn unread_ids(grpc: &GrpcAnalyticsService) -> Vec<String> {
+    grpc.list_unread_notifications(Request::new(analytics::ListUnreadNotificationsRequest {}))
+        .await
+        .unwrap()
+        .into_inner()
+        .notifications
+        .into_iter()
+        .map(|notification| notification.id)
+        .collect()
+}
+
+#[tokio::test]
+async fn marked_notification_leaves_the_unread_listing() {
+    let client = MlClient::connect(&format!("http://{}", unused_addr()))
+        .await
+        .unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("analytics.db");
+    let storage = Arc::new(SqliteStorage::new(db_path.to_string_lossy().to_string()).unwrap());
+    for (id, timestamp) in [("n-1", 1_000), ("n-2", 2_000)] {
+        storage
+            .insert_notification(Notification {
+                id: id.to_string(),
+                title: "High CPU".to_string(),
+                message: "node-1 cpu at 85%".to_string(),
+                severity: Severity::Warning,
+                timestamp,
+                read: false,
+                link: None,
+                cluster_id: Some("prod".to_string()),
+                resource_id: Some("node-1".to_string()),
+            })
+            .await
+            .unwrap();
+    }
+    let grpc = GrpcAnalyticsService::new(Arc::new(AnalyticsService::new(storage, client)));
+    assert_eq!(unread_ids(&grpc).await, ["n-2", "n-1"]);
+
+    grpc.mark_notification_read(Request::new(analytics::MarkNotificationReadRequest {
+        id: "n-2".to_string(),
+    }))
+    .await
+    .unwrap();
+    assert_eq!(unread_ids(&grpc).await, ["n-1"]);
+
+    let missing_id = grpc
+        .mark_notification_read(Request::new(analytics::MarkNotificationReadRequest {
+            id: String::new(),
+        }))
+        .await
+        .unwrap_err();
+    assert_eq!(missing_id.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn downsampled_query_buckets_each_series_server_side() {
+    let client = MlClient::connect(&format!("http://{}", unused_addr()))
+        .await
+        .unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("analytics.db");
+    let storage = Arc::new(SqliteStorage::new(db_path.to_string_lossy().to_string()).unwrap());
+    // A node and a pod sharing a name are separate series.
+    let samples = [ResourceType::Node, ResourceType::Pod]
+        .into_iter()
+        .flat_map(|resource_type| {
+            (0..10).map(move |minute| MetricSample {
+                cluster_id: "prod".to_string(),
+                resource_type,
+                resource_id: "web".to_string(),
+                metric_type: MetricType::CpuUsage,
+                timestamp: 60_000 + minute * 60_000,
+                value: minute as f64,
+                unit: "cores".to_string(),
+            })
+        })
+        .collect();
+    storage.insert_metrics(samples).await.unwrap();
+    let grpc = GrpcAnalyticsService::new(Arc::new(AnalyticsService::new(storage, client)));
+
+    let response = grpc
+        .query_downsampled(Request::new(analytics::QueryDownsampledRequest {
+            query: Some(analytics::QueryMetricsRequest {
+                time_range: Some(analytics::TimeRange {
+                    start_ms: 60_000,
+                    end_ms: 11 * 60_000 - 1,
+                }),
+                ..Default::default()
+            }),
+            max_points: 2,
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert_eq!(response.bucket_width_ms, 5 * 60_000);
+    let buckets: Vec<_> = response
+        .buckets
+        .iter()
+        .map(|b| (b.resource_type, b.bucket_start, b.count, b.avg))
+        .collect();
+    let (node, pod) = (
+        analytics::ResourceType::from(ResourceType::Node) as i32,
+        analytics::ResourceType::from(ResourceType::Pod) as i32,
+    );
+    assert_eq!(
+        buckets,
+        [
+            (node, 60_000, 5, 2.0),
+            (node, 6 * 60_000, 5, 7.0),
+            (pod, 60_000, 5, 2.0),
+            (pod, 6 * 60_000, 5, 7.0),
+        ]
+    );
+
+    let no_points = grpc
+        .query_downsampled(Request::new(analytics::QueryDownsampledRequest {
+            query: None,
+            max_points: 0,
+        }))
+        .await
+        .unwrap_err();
+    assert_eq!(no_points.code(), Code::InvalidArgument);
+}
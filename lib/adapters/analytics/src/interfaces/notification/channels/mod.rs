@@ -2,5 +2,17 @@
 
 pub mod in_tui;
 pub mod ntfy;
+pub mod slack;
 pub mod system;
 pub mod webhook;
+
+use phenome_domain::Severity;
+
+/// Lowercase severity name used in channel payloads.
+pub(crate) fn severity_label(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Critical => "critical",
+        Severity::Warning => "warning",
+        Severity::Info => "info",
+    }
+}
@@ -1,7 +1,33 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use reqwest::Client;
 
-use phenome_domain::Notification;
+use phenome_domain::{Notification, Severity};
 
-pub async fn send(_notification: &Notification, _url: &str, _topic: &str) -> Result<()> {
+/// Publish `notification` to `topic` on the ntfy server at `url`.
+pub async fn send(
+    client: &Client,
+    notification: &Notification,
+    url: &str,
+    topic: &str,
+) -> Result<()> {
+    let endpoint = format!("{}/{}", url.trim_end_matches('/'), topic);
+    client
+        .post(&endpoint)
+        .header("Title", notification.title.as_str())
+        .header("Priority", priority(notification.severity))
+        .body(notification.message.clone())
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .with_context(|| format!("failed to publish to ntfy topic {endpoint}"))?;
     Ok(())
 }
+
+/// ntfy priority name for a severity.
+pub fn priority(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Critical => "urgent",
+        Severity::Warning => "high",
+        Severity::Info => "default",
+    }
+}
@@ -0,0 +1,56 @@
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde_json::{Value, json};
+
+use phenome_domain::{Notification, Severity};
+
+/// Post `notification` to a Slack incoming webhook.
+pub async fn send(client: &Client, notification: &Notification, webhook_url: &str) -> Result<()> {
+    client
+        .post(webhook_url)
+        .json(&payload(notification))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .context("failed to post to slack webhook")?;
+    Ok(())
+}
+
+/// Block Kit message: title header, message body and a context line with the
+/// severity and resource, inside an attachment coloured by severity.
+pub fn payload(notification: &Notification) -> Value {
+    let severity = super::severity_label(notification.severity);
+    let resource = notification.resource_id.as_deref().unwrap_or("n/a");
+    json!({
+        "text": notification.title,
+        "attachments": [{
+            "color": color(notification.severity),
+            "blocks": [
+                {
+                    "type": "header",
+                    "text": { "type": "plain_text", "text": notification.title },
+                },
+                {
+                    "type": "section",
+                    "text": { "type": "mrkdwn", "text": notification.message },
+                },
+                {
+                    "type": "context",
+                    "elements": [{
+                        "type": "mrkdwn",
+                        "text": format!("*Severity:* {severity} | *Resource:* `{resource}`"),
+                    }],
+                },
+            ],
+        }],
+    })
+}
+
+/// Attachment colour for a severity.
+pub fn color(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Critical => "#d32f2f",
+        Severity::Warning => "#f9a825",
+        Severity::Info => "#1976d2",
+    }
+}
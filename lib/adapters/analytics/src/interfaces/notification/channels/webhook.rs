@@ -1,7 +1,58 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use reqwest::Client;
 
 use phenome_domain::Notification;
 
-pub async fn send(_notification: &Notification, _url: &str) -> Result<()> {
+/// Content type sent when a webhook channel does not configure one.
+pub const DEFAULT_CONTENT_TYPE: &str = "application/json";
+
+/// POST `template` rendered against `notification` to `url`.
+pub async fn send(
+    client: &Client,
+    notification: &Notification,
+    url: &str,
+    template: &str,
+    content_type: &str,
+) -> Result<()> {
+    client
+        .post(url)
+        .header("Content-Type", content_type)
+        .body(render(template, notification))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .with_context(|| format!("failed to post to webhook {url}"))?;
     Ok(())
 }
+
+/// Substitute `{{field}}` placeholders with `notification` fields. Unknown
+/// fields and unset optional fields render empty; values are not escaped.
+pub fn render(template: &str, notification: &Notification) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        rendered.push_str(&rest[..start]);
+        let field = rest[start + 2..start + 2 + len].trim();
+        rendered.push_str(&field_value(field, notification));
+        rest = &rest[start + 2 + len + 2..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+fn field_value(field: &str, notification: &Notification) -> String {
+    match field {
+        "id" => notification.id.clone(),
+        "title" => notification.title.clone(),
+        "message" => notification.message.clone(),
+        "severity" => super::severity_label(notification.severity).to_string(),
+        "timestamp" => notification.timestamp.to_string(),
+        "link" => notification.link.clone().unwrap_or_default(),
+        "cluster_id" => notification.cluster_id.clone().unwrap_or_default(),
+        "resource_id" => notification.resource_id.clone().unwrap_or_default(),
+        _ => String::new(),
+    }
+}
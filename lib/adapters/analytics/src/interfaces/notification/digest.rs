@@ -0,0 +1,61 @@
+//! Roll-up of buffered notifications for digest mode.
+
+use std::collections::BTreeMap;
+
+use phenome_domain::{Notification, Severity};
+
+use super::channels::severity_label;
+
+/// Notifications listed individually in a digest.
+const DIGEST_TOP: usize = 3;
+
+const SEVERITIES: [Severity; 3] = [Severity::Critical, Severity::Warning, Severity::Info];
+
+/// One notification summarizing `buffered`: counts by severity and cluster,
+/// then the most severe few. `None` when nothing was buffered.
+pub(crate) fn summarize(buffered: &[Notification], timestamp: i64) -> Option<Notification> {
+    let severity = SEVERITIES
+        .into_iter()
+        .find(|severity| buffered.iter().any(|n| n.severity == *severity))?;
+
+    let by_severity: Vec<String> = SEVERITIES
+        .into_iter()
+        .filter_map(|severity| {
+            let count = buffered.iter().filter(|n| n.severity == severity).count();
+            (count > 0).then(|| format!("{}: {count}", severity_label(severity)))
+        })
+        .collect();
+
+    let mut by_cluster: BTreeMap<&str, usize> = BTreeMap::new();
+    for notification in buffered {
+        let cluster = notification.cluster_id.as_deref().unwrap_or("unknown");
+        *by_cluster.entry(cluster).or_default() += 1;
+    }
+    let by_cluster: Vec<String> = by_cluster
+        .into_iter()
+        .map(|(cluster, count)| format!("{cluster}: {count}"))
+        .collect();
+
+    let mut lines = vec![by_severity.join(", "), by_cluster.join(", ")];
+    let top = SEVERITIES
+        .into_iter()
+        .flat_map(|severity| buffered.iter().filter(move |n| n.severity == severity))
+        .take(DIGEST_TOP);
+    for notification in top {
+        let resource = notification.resource_id.as_deref().unwrap_or("-");
+        lines.push(format!(
+            "- [{}] {resource}: {}",
+            severity_label(notification.severity),
+            notification.message
+        ));
+    }
+
+    Some(Notification {
+        id: uuid::Uuid::new_v4().to_string(),
+        title: format!("Anomaly digest: {} notifications", buffered.len()),
+        message: lines.join("\n"),
+        severity,
+        timestamp,
+        ..Notification::default()
+    })
+}
@@ -1,6 +1,10 @@
 //! Notification service and channels.
 
 pub mod channels;
+mod digest;
 pub mod service;
 
 pub use service::NotificationService;
+
+#[cfg(test)]
+mod tests;
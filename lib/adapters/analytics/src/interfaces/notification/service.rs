@@ -1,28 +1,150 @@
 use anyhow::Result;
 use async_trait::async_trait;
-use std::sync::{Arc, RwLock};
+use reqwest::Client;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::Duration;
 use tokio::sync::watch;
 use tokio::time::interval;
 
-use phenome_domain::{Notification, NotificationChannel};
+use phenome_domain::{
+    Anomaly, Notification, NotificationChannel, NotificationChannelConfig, NotificationRateLimit,
+    Severity,
+};
 use phenome_ports::{AnalyticsPort, NotificationPort};
 
+use crate::clock::{Clock, SystemClock};
+use crate::rate_limiter::TokenBucket;
+use crate::storage::StoragePort;
+
 const ANOMALY_POLL_INTERVAL: Duration = Duration::from_secs(60);
 const MAX_ANOMALIES_PER_TICK: usize = 50;
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_DEDUP_TTL: Duration = Duration::from_secs(60 * 60);
 
-#[derive(Debug, Clone, Default)]
+#[derive(Clone)]
 pub struct NotificationService {
     channels: Arc<RwLock<Vec<NotificationChannel>>>,
+    http_client: Client,
+    rate_limit: Option<NotificationRateLimit>,
+    limiters: Arc<Mutex<HashMap<String, ChannelLimiter>>>,
+    dedup_ttl: Duration,
+    /// When each ongoing anomaly fingerprint was last notified, in ms.
+    notified: Arc<Mutex<HashMap<u64, i64>>>,
+    clock: Arc<dyn Clock>,
+    digest_interval: Option<Duration>,
+    digest: Arc<Mutex<Vec<Notification>>>,
+    storage: Option<Arc<dyn StoragePort>>,
+}
+
+/// Rate limit state of one channel.
+#[derive(Debug)]
+struct ChannelLimiter {
+    bucket: TokenBucket,
+    suppressed: usize,
+    worst: Severity,
+}
+
+impl Default for NotificationService {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
 }
 
 impl NotificationService {
     pub fn new(channels: Vec<NotificationChannel>) -> Self {
+        let http_client = Client::builder()
+            .timeout(DELIVERY_TIMEOUT)
+            .build()
+            .unwrap_or_else(|err| {
+                tracing::warn!("Falling back to default notification HTTP client: {}", err);
+                Client::new()
+            });
         Self {
             channels: Arc::new(RwLock::new(channels)),
+            http_client,
+            rate_limit: None,
+            limiters: Arc::new(Mutex::new(HashMap::new())),
+            dedup_ttl: DEFAULT_DEDUP_TTL,
+            notified: Arc::new(Mutex::new(HashMap::new())),
+            clock: Arc::new(SystemClock),
+            digest_interval: None,
+            digest: Arc::new(Mutex::new(Vec::new())),
+            storage: None,
         }
     }
 
+    /// Keep sent notifications and their read state in `storage`, so they
+    /// stay read across restarts.
+    pub fn with_storage(mut self, storage: Arc<dyn StoragePort>) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    /// Buffer anomaly notifications and send them as one digest every
+    /// `interval` instead of one message each. A zero interval sends them as
+    /// they are detected.
+    pub fn with_digest_interval(mut self, interval: Duration) -> Self {
+        self.digest_interval = (!interval.is_zero()).then_some(interval);
+        self
+    }
+
+    /// Timestamp notifications and measure the dedup TTL with `clock`.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Limit how fast each channel sends; see [`NotificationService::flush_suppressed`].
+    pub fn with_rate_limit(mut self, rate_limit: NotificationRateLimit) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// Re-notify an ongoing anomaly only once every `ttl`.
+    pub fn with_dedup_ttl(mut self, ttl: Duration) -> Self {
+        self.dedup_ttl = ttl;
+        self
+    }
+
+    /// Build the service from the channels listed in `notifications.channels`.
+    pub fn from_config(configs: &[NotificationChannelConfig]) -> Self {
+        let channels = configs
+            .iter()
+            .map(|config| {
+                let (id, name) = match config {
+                    NotificationChannelConfig::Ntfy { url, topic, .. } => {
+                        (format!("ntfy:{url}/{topic}"), format!("ntfy {topic}"))
+                    }
+                    // Webhook URLs carry their credentials, so neither the id
+                    // nor the name may contain them.
+                    NotificationChannelConfig::Slack { webhook_url, .. } => (
+                        format!("slack:{:016x}", url_digest(webhook_url)),
+                        "slack".to_string(),
+                    ),
+                    NotificationChannelConfig::Webhook { url, .. } => {
+                        let name = reqwest::Url::parse(url)
+                            .ok()
+                            .and_then(|url| url.host_str().map(|host| format!("webhook {host}")))
+                            .unwrap_or_else(|| "webhook".to_string());
+                        (format!("webhook:{:016x}", url_digest(url)), name)
+                    }
+                };
+                NotificationChannel {
+                    id,
+                    name,
+                    enabled: true,
+                    config_json: serde_json::to_string(config).unwrap_or_default(),
+                    min_severity: config.min_severity(),
+                    ..NotificationChannel::default()
+                }
+            })
+            .collect();
+        Self::new(channels)
+    }
+
     pub fn channels(&self) -> Vec<NotificationChannel> {
         match self.channels.read() {
             Ok(guard) => guard.clone(),
@@ -32,11 +154,239 @@ impl NotificationService {
             }
         }
     }
+
+    /// Notify the anomalies currently active. An anomaly matching one already
+    /// notified within the dedup TTL is skipped; fingerprints absent from
+    /// `anomalies` count as resolved, so a recurrence notifies again.
+    pub async fn notify_anomalies(&self, mut anomalies: Vec<Anomaly>) -> Result<()> {
+        if anomalies.len() > MAX_ANOMALIES_PER_TICK {
+            tracing::warn!(
+                "Anomaly notifications capped at {} per tick",
+                MAX_ANOMALIES_PER_TICK
+            );
+            anomalies.truncate(MAX_ANOMALIES_PER_TICK);
+        }
+
+        let fresh: Vec<Anomaly> = {
+            let mut notified = self
+                .notified
+                .lock()
+                .map_err(|_| anyhow::anyhow!("notification dedup lock poisoned"))?;
+            let active: HashSet<u64> = anomalies.iter().map(fingerprint).collect();
+            notified.retain(|key, _| active.contains(key));
+            let now = self.clock.now_ms();
+            anomalies
+                .into_iter()
+                .filter(|anomaly| {
+                    let key = fingerprint(anomaly);
+                    match notified.get(&key) {
+                        Some(at) if now - at < self.dedup_ttl.as_millis() as i64 => false,
+                        _ => {
+                            notified.insert(key, now);
+                            true
+                        }
+                    }
+                })
+                .collect()
+        };
+
+        let now = self.clock.now_ms();
+        for anomaly in fresh {
+            let notification = Notification {
+                id: uuid::Uuid::new_v4().to_string(),
+                title: format!("Anomaly Detected: {:?}", anomaly.metric_type),
+                message: anomaly.description.clone(),
+                severity: anomaly.severity,
+                timestamp: now,
+                read: false,
+                link: None,
+                cluster_id: Some(anomaly.cluster_id.clone()),
+                resource_id: Some(anomaly.resource_id.clone()),
+            };
+
+            if self.digest_interval.is_some() {
+                self.digest
+                    .lock()
+                    .map_err(|_| anyhow::anyhow!("notification digest lock poisoned"))?
+                    .push(notification);
+            } else if let Err(e) = self.send_notification(notification).await {
+                tracing::error!("Failed to send anomaly notification: {}", e);
+            }
+        }
+
+        self.flush_suppressed().await
+    }
+
+    /// Send the buffered anomaly notifications as a single digest and clear
+    /// the buffer. Nothing is sent when the buffer is empty.
+    pub async fn flush_digest(&self) -> Result<()> {
+        let buffered = std::mem::take(
+            &mut *self
+                .digest
+                .lock()
+                .map_err(|_| anyhow::anyhow!("notification digest lock poisoned"))?,
+        );
+        match super::digest::summarize(&buffered, self.clock.now_ms()) {
+            Some(digest) => self.send_notification(digest).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Send each channel one summary of the notifications its rate limit
+    /// dropped since the last flush.
+    pub async fn flush_suppressed(&self) -> Result<()> {
+        let suppressed: HashMap<String, (usize, Severity)> = {
+            let mut limiters = self
+                .limiters
+                .lock()
+                .map_err(|_| anyhow::anyhow!("notification rate limiters lock poisoned"))?;
+            limiters
+                .iter_mut()
+                .filter(|(_, limiter)| limiter.suppressed > 0)
+                .map(|(id, limiter)| {
+                    let summary = (limiter.suppressed, limiter.worst);
+                    limiter.suppressed = 0;
+                    limiter.worst = Severity::Info;
+                    (id.clone(), summary)
+                })
+                .collect()
+        };
+
+        let mut failures = Vec::new();
+        for channel in self.channels() {
+            let Some((count, severity)) = suppressed.get(&channel.id) else {
+                continue;
+            };
+            let Ok(config) =
+                serde_json::from_str::<NotificationChannelConfig>(&channel.config_json)
+            else {
+                continue;
+            };
+            let summary = Notification {
+                id: uuid::Uuid::new_v4().to_string(),
+                title: "Notifications suppressed".to_string(),
+                message: format!("{count} more anomalies suppressed"),
+                severity: *severity,
+                timestamp: self.clock.now_ms(),
+                ..Notification::default()
+            };
+            if let Err(err) = self.deliver(&config, &summary).await {
+                failures.push(format!("{}: {:#}", channel.name, err));
+            }
+        }
+        if !failures.is_empty() {
+            anyhow::bail!(
+                "suppression summary not delivered to {}",
+                failures.join("; ")
+            );
+        }
+        Ok(())
+    }
+
+    /// Whether `channel_id` may send `notification` now. Notifications over
+    /// the limit are counted towards the channel's next summary.
+    fn admit(&self, channel_id: &str, notification: &Notification) -> bool {
+        let Some(rate_limit) = &self.rate_limit else {
+            return true;
+        };
+        if rate_limit.exempt_critical && notification.severity == Severity::Critical {
+            return true;
+        }
+        let Ok(mut limiters) = self.limiters.lock() else {
+            tracing::error!("notification rate limiters lock poisoned");
+            return true;
+        };
+        let limiter = limiters
+            .entry(channel_id.to_string())
+            .or_insert_with(|| ChannelLimiter {
+                bucket: TokenBucket::new(rate_limit.per_minute, rate_limit.burst),
+                suppressed: 0,
+                worst: Severity::Info,
+            });
+        if limiter.bucket.try_acquire() {
+            return true;
+        }
+        limiter.suppressed += 1;
+        if notification.severity.at_least(limiter.worst) {
+            limiter.worst = notification.severity;
+        }
+        false
+    }
+
+    fn inbox(&self) -> Result<&Arc<dyn StoragePort>> {
+        self.storage
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("notification read state requires storage"))
+    }
+
+    async fn deliver(
+        &self,
+        config: &NotificationChannelConfig,
+        notification: &Notification,
+    ) -> Result<()> {
+        match config {
+            NotificationChannelConfig::Ntfy { url, topic, .. } => {
+                super::channels::ntfy::send(&self.http_client, notification, url, topic).await
+            }
+            NotificationChannelConfig::Slack { webhook_url, .. } => {
+                super::channels::slack::send(&self.http_client, notification, webhook_url).await
+            }
+            NotificationChannelConfig::Webhook {
+                url,
+                template,
+                content_type,
+                ..
+            } => {
+                let content_type = content_type
+                    .as_deref()
+                    .unwrap_or(super::channels::webhook::DEFAULT_CONTENT_TYPE);
+                super::channels::webhook::send(
+                    &self.http_client,
+                    notification,
+                    url,
+                    template,
+                    content_type,
+                )
+                .await
+            }
+        }
+    }
 }
 
 #[async_trait]
 impl NotificationPort for NotificationService {
-    async fn send_notification(&self, _notification: Notification) -> Result<()> {
+    async fn send_notification(&self, notification: Notification) -> Result<()> {
+        let mut failures = Vec::new();
+        if let Some(storage) = &self.storage {
+            if let Err(err) = storage.insert_notification(notification.clone()).await {
+                failures.push(format!("inbox: {:#}", err));
+            }
+        }
+        for channel in self.channels() {
+            if !channel.enabled || !notification.severity.at_least(channel.min_severity) {
+                continue;
+            }
+            // Channels without a delivery config here (tui, system, ...) are
+            // handled elsewhere.
+            let Ok(config) =
+                serde_json::from_str::<NotificationChannelConfig>(&channel.config_json)
+            else {
+                continue;
+            };
+            if !self.admit(&channel.id, &notification) {
+                continue;
+            }
+            if let Err(err) = self.deliver(&config, &notification).await {
+                failures.push(format!("{}: {:#}", channel.name, err));
+            }
+        }
+        if !failures.is_empty() {
+            anyhow::bail!(
+                "notification {} not delivered to {}",
+                notification.id,
+                failures.join("; ")
+            );
+        }
         Ok(())
     }
 
@@ -56,6 +406,14 @@ impl NotificationPort for NotificationService {
         }
         Ok(())
     }
+
+    async fn mark_read(&self, id: String) -> Result<()> {
+        self.inbox()?.mark_notification_read(id).await
+    }
+
+    async fn list_unread(&self) -> Result<Vec<Notification>> {
+        self.inbox()?.list_unread_notifications().await
+    }
 }
 
 impl NotificationService {
@@ -70,7 +428,9 @@ impl NotificationService {
         mut shutdown: watch::Receiver<bool>,
     ) {
         let mut interval = interval(ANOMALY_POLL_INTERVAL);
-        let mut last_check = chrono::Utc::now().timestamp_millis();
+        let mut digest_timer =
+            tokio::time::interval(self.digest_interval.unwrap_or(ANOMALY_POLL_INTERVAL));
+        let mut last_check = self.clock.now_ms();
 
         loop {
             tokio::select! {
@@ -79,8 +439,13 @@ impl NotificationService {
                         break;
                     }
                 }
+                _ = digest_timer.tick(), if self.digest_interval.is_some() => {
+                    if let Err(e) = self.flush_digest().await {
+                        tracing::error!("Failed to send notification digest: {}", e);
+                    }
+                }
                 _ = interval.tick() => {
-                    let now = chrono::Utc::now().timestamp_millis();
+                    let now = self.clock.now_ms();
 
                     // Query anomalies detected since last check
                     let filter = phenome_domain::AnomalyFilter {
@@ -92,31 +457,9 @@ impl NotificationService {
                     };
 
                     match service.get_anomalies(filter).await {
-                        Ok(mut anomalies) => {
-                            if anomalies.len() > MAX_ANOMALIES_PER_TICK {
-                                tracing::warn!(
-                                    "Anomaly notifications capped at {} per tick",
-                                    MAX_ANOMALIES_PER_TICK
-                                );
-                                anomalies.truncate(MAX_ANOMALIES_PER_TICK);
-                            }
-
-                            for anomaly in anomalies {
-                                let notification = phenome_domain::Notification {
-                                    id: uuid::Uuid::new_v4().to_string(),
-                                    title: format!("Anomaly Detected: {:?}", anomaly.metric_type),
-                                    message: anomaly.description.clone(),
-                                    severity: anomaly.severity,
-                                    timestamp: now,
-                                    read: false,
-                                    link: None,
-                                    cluster_id: Some(anomaly.cluster_id.clone()),
-                                    resource_id: Some(anomaly.resource_id.clone()),
-                                };
-
-                                if let Err(e) = self.send_notification(notification).await {
-                                    tracing::error!("Failed to send anomaly notification: {}", e);
-                                }
+                        Ok(anomalies) => {
+                            if let Err(e) = self.notify_anomalies(anomalies).await {
+                                tracing::error!("Failed to notify anomalies: {}", e);
                             }
                         }
                         Err(err) => {
@@ -130,3 +473,20 @@ impl NotificationService {
         }
     }
 }
+
+/// Stand-in for a secret URL in channel ids.
+fn url_digest(url: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    url.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Identity of an ongoing anomaly condition for deduplication.
+fn fingerprint(anomaly: &Anomaly) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    anomaly.cluster_id.hash(&mut hasher);
+    anomaly.resource_id.hash(&mut hasher);
+    anomaly.metric_type.hash(&mut hasher);
+    anomaly.severity.hash(&mut hasher);
+    hasher.finish()
+}
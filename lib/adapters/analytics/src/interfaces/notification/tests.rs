@@ -0,0 +1,477 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use wiremock::matchers::{body_json, body_string, header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use phenome_domain::{
+    Anomaly, MetricType, Notification, NotificationChannel, NotificationChannelConfig,
+    NotificationRateLimit, Severity,
+};
+use phenome_ports::NotificationPort;
+
+use crate::notification::NotificationService;
+use crate::notification::channels::{slack, webhook};
+use crate::storage::sqlite::SqliteStorage;
+
+fn notification(severity: Severity, message: &str) -> Notification {
+    Notification {
+        id: format!("{severity:?}"),
+        title: "Anomaly Detected: CpuUsage".to_string(),
+        message: message.to_string(),
+        severity,
+        ..Notification::default()
+    }
+}
+
+#[tokio::test]
+async fn ntfy_channel_posts_message_with_priority() {
+    let server = MockServer::start().await;
+    for (priority, message) in [
+        ("urgent", "node-1 cpu at 99%"),
+        ("high", "node-1 cpu at 85%"),
+        ("default", "node-1 cpu back to normal"),
+    ] {
+        Mock::given(method("POST"))
+            .and(path("/phenome-alerts"))
+            .and(header("Title", "Anomaly Detected: CpuUsage"))
+            .and(header("Priority", priority))
+            .and(body_string(message))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+
+    let service = NotificationService::from_config(&[NotificationChannelConfig::Ntfy {
+        url: format!("{}/", server.uri()),
+        topic: "phenome-alerts".to_string(),
+        min_severity: Severity::Info,
+    }]);
+    for notification in [
+        notification(Severity::Critical, "node-1 cpu at 99%"),
+        notification(Severity::Warning, "node-1 cpu at 85%"),
+        notification(Severity::Info, "node-1 cpu back to normal"),
+    ] {
+        service.send_notification(notification).await.unwrap();
+    }
+
+    server.verify().await;
+}
+
+#[tokio::test]
+async fn ntfy_rejection_is_reported() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(403))
+        .mount(&server)
+        .await;
+
+    let service = NotificationService::from_config(&[NotificationChannelConfig::Ntfy {
+        url: server.uri(),
+        topic: "phenome-alerts".to_string(),
+        min_severity: Severity::Info,
+    }]);
+    let err = service
+        .send_notification(notification(Severity::Info, "hello"))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("403"), "{err:#}");
+}
+
+#[test]
+fn slack_payload_colors_by_severity() {
+    let mut critical = notification(Severity::Critical, "node-1 cpu at 99%");
+    critical.resource_id = Some("node-1".to_string());
+    let payload = slack::payload(&critical);
+
+    assert_eq!(payload["text"], "Anomaly Detected: CpuUsage");
+    let attachment = &payload["attachments"][0];
+    assert_eq!(attachment["color"], "#d32f2f");
+    let blocks = attachment["blocks"].as_array().unwrap();
+    let kinds: Vec<_> = blocks.iter().map(|block| &block["type"]).collect();
+    assert_eq!(kinds, ["header", "section", "context"]);
+    assert_eq!(blocks[0]["text"]["text"], "Anomaly Detected: CpuUsage");
+    assert_eq!(blocks[1]["text"]["text"], "node-1 cpu at 99%");
+    assert_eq!(
+        blocks[2]["elements"][0]["text"],
+        "*Severity:* critical | *Resource:* `node-1`"
+    );
+
+    let warning = slack::payload(&notification(Severity::Warning, "warm"));
+    assert_eq!(warning["attachments"][0]["color"], "#f9a825");
+    let info = slack::payload(&notification(Severity::Info, "fine"));
+    assert_eq!(info["attachments"][0]["color"], "#1976d2");
+}
+
+#[tokio::test]
+async fn slack_channel_posts_block_kit_message() {
+    let server = MockServer::start().await;
+    let alert = notification(Severity::Warning, "node-1 cpu at 85%");
+    Mock::given(method("POST"))
+        .and(path("/services/T000/B000/XXXX"))
+        .and(body_json(slack::payload(&alert)))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let service = NotificationService::from_config(&[NotificationChannelConfig::Slack {
+        webhook_url: format!("{}/services/T000/B000/XXXX", server.uri()),
+        min_severity: Severity::Info,
+    }]);
+    service.send_notification(alert).await.unwrap();
+
+    server.verify().await;
+}
+
+#[test]
+fn webhook_template_fills_known_fields_and_blanks_unknown_ones() {
+    let mut alert = notification(Severity::Critical, "node-1 cpu at 99%");
+    alert.cluster_id = Some("prod".to_string());
+    let rendered = webhook::render(
+        "[{{ severity }}] {{title}} on {{cluster_id}}/{{resource_id}}: {{message}}{{nope}} {{unclosed",
+        &alert,
+    );
+    assert_eq!(
+        rendered,
+        "[critical] Anomaly Detected: CpuUsage on prod/: node-1 cpu at 99% {{unclosed"
+    );
+}
+
+#[tokio::test]
+async fn webhook_channel_posts_rendered_template() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/alerts"))
+        .and(header("Content-Type", "text/plain"))
+        .and(body_string("warning: node-1 cpu at 85%"))
+        .respond_with(ResponseTemplate::new(202))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/json"))
+        .and(header("Content-Type", "application/json"))
+        .and(body_json(
+            serde_json::json!({ "summary": "node-1 cpu at 85%" }),
+        ))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let service = NotificationService::from_config(&[
+        NotificationChannelConfig::Webhook {
+            url: format!("{}/alerts", server.uri()),
+            template: "{{severity}}: {{message}}".to_string(),
+            content_type: Some("text/plain".to_string()),
+            min_severity: Severity::Info,
+        },
+        NotificationChannelConfig::Webhook {
+            url: format!("{}/json", server.uri()),
+            template: r#"{"summary": "{{message}}"}"#.to_string(),
+            content_type: None,
+            min_severity: Severity::Info,
+        },
+    ]);
+    service
+        .send_notification(notification(Severity::Warning, "node-1 cpu at 85%"))
+        .await
+        .unwrap();
+
+    server.verify().await;
+}
+
+#[tokio::test]
+async fn channels_skip_notifications_below_their_threshold() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/everything"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/phone"))
+        .and(header("Priority", "urgent"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let service = NotificationService::from_config(&[
+        NotificationChannelConfig::Ntfy {
+            url: server.uri(),
+            topic: "everything".to_string(),
+            min_severity: Severity::Info,
+        },
+        NotificationChannelConfig::Ntfy {
+            url: server.uri(),
+            topic: "phone".to_string(),
+            min_severity: Severity::Critical,
+        },
+    ]);
+    service
+        .send_notification(notification(Severity::Info, "node-1 cpu back to normal"))
+        .await
+        .unwrap();
+    service
+        .send_notification(notification(Severity::Critical, "node-1 cpu at 99%"))
+        .await
+        .unwrap();
+    server.verify().await;
+
+    // Thresholds set through `configure_channel` are kept.
+    let phone = service.channels()[1].clone();
+    service
+        .configure_channel(NotificationChannel {
+            min_severity: Severity::Warning,
+            ..phone
+        })
+        .await
+        .unwrap();
+    let channels = service.channels();
+    assert_eq!(channels.len(), 2);
+    assert_eq!(channels[1].min_severity, Severity::Warning);
+}
+
+#[test]
+fn channel_ids_and_names_leave_webhook_urls_out() {
+    let slack_url = "https://hooks.slack.com/services/T000/B000/secret-token";
+    let webhook_url = "https://alerts.example.com/hook?token=secret-token";
+    let service = NotificationService::from_config(&[
+        NotificationChannelConfig::Slack {
+            webhook_url: slack_url.to_string(),
+            min_severity: Severity::Info,
+        },
+        NotificationChannelConfig::Webhook {
+            url: webhook_url.to_string(),
+            template: "{{title}}".to_string(),
+            content_type: None,
+            min_severity: Severity::Info,
+        },
+    ]);
+
+    let channels = service.channels();
+    assert_eq!(channels[1].name, "webhook alerts.example.com");
+    assert_ne!(channels[0].id, channels[1].id);
+    for channel in &channels {
+        assert!(!channel.id.contains("secret-token"), "{}", channel.id);
+        assert!(!channel.name.contains("secret-token"), "{}", channel.name);
+    }
+}
+
+#[tokio::test]
+async fn rate_limit_coalesces_a_burst_into_one_summary() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/phenome-alerts"))
+        .and(body_string("45 more anomalies suppressed"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/phenome-alerts"))
+        .and(header("Priority", "urgent"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/phenome-alerts"))
+        .and(body_string("node-1 cpu at 85%"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(5)
+        .mount(&server)
+        .await;
+
+    let service = NotificationService::from_config(&[NotificationChannelConfig::Ntfy {
+        url: server.uri(),
+        topic: "phenome-alerts".to_string(),
+        min_severity: Severity::Info,
+    }])
+    .with_rate_limit(NotificationRateLimit {
+        per_minute: 1,
+        burst: 5,
+        exempt_critical: true,
+    });
+    for _ in 0..50 {
+        service
+            .send_notification(notification(Severity::Warning, "node-1 cpu at 85%"))
+            .await
+            .unwrap();
+    }
+    // Critical notifications bypass the exhausted bucket.
+    service
+        .send_notification(notification(Severity::Critical, "node-1 cpu at 99%"))
+        .await
+        .unwrap();
+    service.flush_suppressed().await.unwrap();
+    // Nothing left to summarize.
+    service.flush_suppressed().await.unwrap();
+
+    server.verify().await;
+}
+
+fn cpu_anomaly(id: &str, detected_at: i64) -> Anomaly {
+    Anomaly {
+        id: id.to_string(),
+        cluster_id: "prod".to_string(),
+        resource_id: "node-1".to_string(),
+        detected_at,
+        metric_type: MetricType::CpuUsage,
+        severity: Severity::Warning,
+        confidence: 0.9,
+        description: "node-1 cpu at 85%".to_string(),
+        baseline_value: 0.5,
+        observed_value: 0.85,
+        deviation_sigma: 3.5,
+        related_metrics: Vec::new(),
+        root_cause: None,
+    }
+}
+
+#[tokio::test]
+async fn ongoing_anomaly_notifies_once_until_resolved() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/phenome-alerts"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+
+    let service = NotificationService::from_config(&[NotificationChannelConfig::Ntfy {
+        url: server.uri(),
+        topic: "phenome-alerts".to_string(),
+        min_severity: Severity::Info,
+    }]);
+    // The same condition seen on two consecutive polls.
+    service
+        .notify_anomalies(vec![cpu_anomaly("a-1", 1_000)])
+        .await
+        .unwrap();
+    service
+        .notify_anomalies(vec![cpu_anomaly("a-2", 61_000)])
+        .await
+        .unwrap();
+    assert_eq!(server.received_requests().await.unwrap().len(), 1);
+
+    // Once it resolves, a recurrence is news again.
+    service.notify_anomalies(Vec::new()).await.unwrap();
+    service
+        .notify_anomalies(vec![cpu_anomaly("a-3", 181_000)])
+        .await
+        .unwrap();
+    assert_eq!(server.received_requests().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn digest_mode_rolls_up_buffered_anomalies() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/phenome-alerts"))
+        .and(header("Title", "Anomaly digest: 4 notifications"))
+        .and(header("Priority", "urgent"))
+        .and(body_string(
+            "critical: 1, warning: 3\n\
+             prod: 3, staging: 1\n\
+             - [critical] node-2: node-2 memory at 98%\n\
+             - [warning] node-1: node-1 cpu at 85%\n\
+             - [warning] node-3: node-3 cpu at 80%",
+        ))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let service = NotificationService::from_config(&[NotificationChannelConfig::Ntfy {
+        url: server.uri(),
+        topic: "phenome-alerts".to_string(),
+        min_severity: Severity::Info,
+    }])
+    .with_digest_interval(Duration::from_secs(15 * 60));
+    let mut memory = cpu_anomaly("a-2", 2_000);
+    memory.resource_id = "node-2".to_string();
+    memory.metric_type = MetricType::MemoryUsage;
+    memory.severity = Severity::Critical;
+    memory.description = "node-2 memory at 98%".to_string();
+    let mut other_node = cpu_anomaly("a-3", 3_000);
+    other_node.resource_id = "node-3".to_string();
+    other_node.description = "node-3 cpu at 80%".to_string();
+    let mut staging = cpu_anomaly("a-4", 4_000);
+    staging.cluster_id = "staging".to_string();
+    staging.resource_id = "node-4".to_string();
+    staging.description = "node-4 cpu at 90%".to_string();
+
+    service
+        .notify_anomalies(vec![cpu_anomaly("a-1", 1_000), memory, other_node, staging])
+        .await
+        .unwrap();
+    assert!(server.received_requests().await.unwrap().is_empty());
+
+    service.flush_digest().await.unwrap();
+    // The buffer was cleared, so a second flush has nothing to send.
+    service.flush_digest().await.unwrap();
+
+    server.verify().await;
+}
+
+#[tokio::test]
+async fn zero_digest_interval_sends_anomalies_as_detected() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/phenome-alerts"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let service = NotificationService::from_config(&[NotificationChannelConfig::Ntfy {
+        url: server.uri(),
+        topic: "phenome-alerts".to_string(),
+        min_severity: Severity::Info,
+    }])
+    .with_digest_interval(Duration::ZERO);
+    service
+        .notify_anomalies(vec![cpu_anomaly("a-1", 1_000)])
+        .await
+        .unwrap();
+
+    server.verify().await;
+}
+
+#[tokio::test]
+async fn marked_read_notifications_stay_out_of_unread_after_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("analytics.db");
+    let storage = Arc::new(SqliteStorage::new(db_path.to_string_lossy().to_string()).unwrap());
+    let service = NotificationService::new(Vec::new()).with_storage(storage);
+    for (id, timestamp) in [("n-1", 1_000), ("n-2", 2_000)] {
+        service
+            .send_notification(Notification {
+                id: id.to_string(),
+                timestamp,
+                ..notification(Severity::Warning, "node-1 cpu at 85%")
+            })
+            .await
+            .unwrap();
+    }
+
+    service.mark_read("n-1".to_string()).await.unwrap();
+    let unread = service.list_unread().await.unwrap();
+    assert_eq!(
+        unread.iter().map(|n| n.id.as_str()).collect::<Vec<_>>(),
+        ["n-2"]
+    );
+
+    let reopened = Arc::new(SqliteStorage::new(db_path.to_string_lossy().to_string()).unwrap());
+    let service = NotificationService::new(Vec::new()).with_storage(reopened);
+    let unread = service.list_unread().await.unwrap();
+    assert_eq!(
+        unread.iter().map(|n| n.id.as_str()).collect::<Vec<_>>(),
+        ["n-2"]
+    );
+    assert!(!unread[0].read);
+}
@@ -1,7 +1,98 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use k8s_openapi::api::apps::v1::Deployment;
+use kube::api::{Api, Patch, PatchParams};
+use serde_json::json;
 
-use phenome_domain::ScheduledAction;
+use phenome_domain::{RecommendationAction, ScheduledAction};
 
-pub async fn execute_action(_action: &ScheduledAction) -> Result<()> {
-    Ok(())
+/// Field manager recorded on patches applied by the scheduler.
+const FIELD_MANAGER: &str = "phenome-scheduler";
+
+/// What executing a scheduled action did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Execution {
+    /// The change was applied, or logged in a dry run.
+    Applied,
+    /// Nothing ran: the scheduler has no executor for this kind of action.
+    Unsupported,
+}
+
+/// Apply `action` against the cluster behind `client`. With `dry_run` the
+/// intended change is logged and nothing is sent.
+pub async fn execute_action(
+    action: &ScheduledAction,
+    client: &kube::Client,
+    dry_run: bool,
+) -> Result<Execution> {
+    match &action.action {
+        RecommendationAction::ScaleDeployment { name, from, to } => {
+            let patch = scale_patch(*to);
+            if dry_run {
+                tracing::info!(
+                    "Dry run: would scale deployment {} from {} to {} with patch {}",
+                    name,
+                    from,
+                    to,
+                    patch
+                );
+                return Ok(Execution::Applied);
+            }
+            let deployments: Api<Deployment> = Api::default_namespaced(client.clone());
+            let params = PatchParams {
+                field_manager: Some(FIELD_MANAGER.to_string()),
+                ..PatchParams::default()
+            };
+            deployments
+                .patch(name, &params, &Patch::Merge(&patch))
+                .await
+                .with_context(|| format!("failed to scale deployment {name} to {to}"))?;
+            tracing::info!("Scaled deployment {} from {} to {}", name, from, to);
+            Ok(Execution::Applied)
+        }
+        RecommendationAction::UpdateResourceLimits { .. }
+        | RecommendationAction::ReclaimStorage { .. } => {
+            tracing::warn!(
+                "Scheduled action {} has no executor yet; not applied",
+                action.id
+            );
+            Ok(Execution::Unsupported)
+        }
+    }
+}
+
+/// Compare the live cluster with what `action` assumed. Returns why the
+/// action should be skipped when the two differ by more than `tolerance`.
+pub(crate) async fn detect_drift(
+    action: &ScheduledAction,
+    client: &kube::Client,
+    tolerance: u32,
+) -> Result<Option<String>> {
+    match &action.action {
+        RecommendationAction::ScaleDeployment { name, from, .. } => {
+            let deployments: Api<Deployment> = Api::default_namespaced(client.clone());
+            let deployment = deployments
+                .get(name)
+                .await
+                .with_context(|| format!("failed to read deployment {name}"))?;
+            // The API server defaults an unset replica count to one.
+            let live = deployment
+                .spec
+                .and_then(|spec| spec.replicas)
+                .unwrap_or(1)
+                .max(0) as u32;
+            if live.abs_diff(*from) > tolerance {
+                return Ok(Some(format!(
+                    "deployment {name} runs {live} replicas, expected {from}"
+                )));
+            }
+            Ok(None)
+        }
+        RecommendationAction::UpdateResourceLimits { .. }
+        | RecommendationAction::ReclaimStorage { .. } => Ok(None),
+    }
+}
+
+/// Merge patch setting a deployment's replica count.
+pub(crate) fn scale_patch(replicas: u32) -> serde_json::Value {
+    json!({ "spec": { "replicas": replicas } })
 }
@@ -1,6 +1,10 @@
 //! Scheduler service and executor.
 
 pub mod executor;
+mod recurrence;
 pub mod service;
 
 pub use service::SchedulerService;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,24 @@
+//! Cron recurrence for scheduled actions.
+
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Utc};
+use croner::Cron;
+
+/// Parse a five-field cron expression; a leading seconds field is optional.
+pub(crate) fn parse(expression: &str) -> Result<Cron> {
+    Cron::new(expression)
+        .with_seconds_optional()
+        .parse()
+        .map_err(|err| anyhow!("invalid cron expression {expression:?}: {err}"))
+}
+
+/// First run of `expression` strictly after `after_ms`, in UTC milliseconds.
+pub(crate) fn next_run_after(expression: &str, after_ms: i64) -> Result<i64> {
+    let cron = parse(expression)?;
+    let after = DateTime::<Utc>::from_timestamp_millis(after_ms)
+        .with_context(|| format!("timestamp {after_ms} is out of range"))?;
+    let next = cron
+        .find_next_occurrence(&after, false)
+        .map_err(|err| anyhow!("cron expression {expression:?} has no next run: {err}"))?;
+    Ok(next.timestamp_millis())
+}
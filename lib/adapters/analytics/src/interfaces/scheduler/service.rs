@@ -1,39 +1,94 @@
 use anyhow::Result;
 use async_trait::async_trait;
-use chrono::Utc;
 use std::sync::Arc;
 use tokio::sync::watch;
 use tokio::time::{Duration, interval};
 
-use phenome_domain::{ScheduleId, ScheduleStatus, ScheduledAction};
+use phenome_domain::{
+    ExecutionOutcome, ScheduleExecution, ScheduleId, ScheduleStatus, ScheduledAction,
+    SchedulerConfig,
+};
 use phenome_ports::SchedulerPort;
 
+use crate::clock::{Clock, SystemClock};
+use crate::scheduler::executor::Execution;
 use crate::storage::StoragePort;
 
 const SCHEDULER_TICK_INTERVAL: Duration = Duration::from_secs(60);
 const MAX_ACTIONS_PER_TICK: usize = 64;
+/// Schedules `Executing` for longer than this are assumed orphaned by a crash.
+const STUCK_EXECUTION_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_secs(60);
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60 * 60);
 
 #[derive(Clone)]
 pub struct SchedulerService {
     storage: Arc<dyn StoragePort>,
+    dry_run: bool,
+    drift_tolerance: u32,
+    max_retries: u32,
+    retry_backoff: Duration,
+    clock: Arc<dyn Clock>,
 }
 
 impl SchedulerService {
     pub fn new(storage: Arc<dyn StoragePort>) -> Self {
-        Self { storage }
+        Self {
+            storage,
+            dry_run: false,
+            drift_tolerance: 0,
+            max_retries: DEFAULT_MAX_RETRIES,
+            retry_backoff: DEFAULT_RETRY_BACKOFF,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Decide which actions are due by `clock` instead of the system time.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Retry a failed action up to `max_retries` times, waiting `backoff`
+    /// before the first retry and doubling the wait for each one after it.
+    pub fn with_retries(mut self, max_retries: u32, backoff: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.retry_backoff = backoff;
+        self
+    }
+
+    /// Log the changes due actions would make instead of applying them.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Cancel a scale when the live replica count differs from the count the
+    /// action assumed by more than `tolerance`.
+    pub fn with_drift_tolerance(mut self, tolerance: u32) -> Self {
+        self.drift_tolerance = tolerance;
+        self
     }
 
     pub async fn run_minute(storage: Arc<dyn StoragePort>, kube_client: kube::Client) {
         let (_tx, rx) = watch::channel(false);
-        Self::run_minute_with_shutdown(storage, kube_client, rx).await;
+        Self::run_minute_with_shutdown(storage, kube_client, SchedulerConfig::default(), rx).await;
     }
 
     pub async fn run_minute_with_shutdown(
         storage: Arc<dyn StoragePort>,
         kube_client: kube::Client,
+        config: SchedulerConfig,
         shutdown: watch::Receiver<bool>,
     ) {
-        let service = Self::new(storage);
+        let service = Self::new(storage)
+            .with_dry_run(config.dry_run)
+            .with_drift_tolerance(config.drift_tolerance)
+            .with_retries(
+                config.max_retries,
+                Duration::from_secs(config.retry_backoff_seconds),
+            );
         service.run_scheduler_loop(kube_client, shutdown).await;
     }
 
@@ -42,6 +97,18 @@ impl SchedulerService {
         kube_client: kube::Client,
         mut shutdown: watch::Receiver<bool>,
     ) {
+        match self
+            .storage
+            .recover_stuck_schedules(STUCK_EXECUTION_TIMEOUT)
+            .await
+        {
+            Ok(0) => {}
+            Ok(recovered) => {
+                tracing::warn!("Reset {} stuck scheduled actions to pending", recovered);
+            }
+            Err(err) => tracing::error!("Failed to recover stuck schedules: {}", err),
+        }
+
         let mut interval_timer = interval(SCHEDULER_TICK_INTERVAL);
         loop {
             tokio::select! {
@@ -59,9 +126,9 @@ impl SchedulerService {
         }
     }
 
-    async fn check_and_execute(&self, _kube_client: &kube::Client) -> Result<()> {
+    pub(crate) async fn check_and_execute(&self, kube_client: &kube::Client) -> Result<()> {
         let all = self.storage.get_all_schedules().await?;
-        let now = Utc::now().timestamp_millis();
+        let now = self.clock.now_ms();
 
         let mut executed = 0usize;
         for mut action in all {
@@ -79,21 +146,161 @@ impl SchedulerService {
                 action.status = ScheduleStatus::Executing;
                 self.storage.update_schedule(action.clone()).await?;
 
-                let result = crate::scheduler::executor::execute_action(&action).await;
-                if let Err(err) = result {
-                    tracing::error!("Scheduled action {} failed: {}", action.id, err);
-                    action.status = ScheduleStatus::Failed {
-                        error: err.to_string(),
-                    };
-                } else {
-                    action.status = ScheduleStatus::Completed;
-                }
-                self.storage.update_schedule(action).await?;
+                let result = match self.check_drift(&action, kube_client).await {
+                    Ok(Some(drift)) => {
+                        tracing::warn!(
+                            "Skipping scheduled action {}: drift detected ({})",
+                            action.id,
+                            drift
+                        );
+                        // A recurring action only loses this run.
+                        let detail = advance(
+                            &mut action,
+                            now,
+                            ScheduleStatus::Cancelled,
+                            &format!("drift detected: {drift}"),
+                        );
+                        self.finish(action, ExecutionOutcome::Skipped, detail)
+                            .await?;
+                        executed += 1;
+                        continue;
+                    }
+                    Ok(None) => {
+                        crate::scheduler::executor::execute_action(
+                            &action,
+                            kube_client,
+                            self.dry_run,
+                        )
+                        .await
+                    }
+                    Err(err) => Err(err),
+                };
+                let (outcome, detail) = match result {
+                    Ok(Execution::Unsupported) => {
+                        action.status = ScheduleStatus::Failed {
+                            error: "no executor for this action".to_string(),
+                        };
+                        (
+                            ExecutionOutcome::Skipped,
+                            "not applied: no executor for this action".to_string(),
+                        )
+                    }
+                    Err(err) => {
+                        action.attempts = action.attempts.saturating_add(1);
+                        if action.attempts > self.max_retries {
+                            tracing::error!("Scheduled action {} failed: {:#}", action.id, err);
+                            action.status = ScheduleStatus::Failed {
+                                error: format!("{err:#}"),
+                            };
+                            (ExecutionOutcome::Failed, format!("{err:#}"))
+                        } else {
+                            let delay = self.retry_delay(action.attempts);
+                            tracing::warn!(
+                                "Scheduled action {} failed (attempt {}), retrying in {}s: {:#}",
+                                action.id,
+                                action.attempts,
+                                delay.as_secs(),
+                                err
+                            );
+                            action.execute_at = now + delay.as_millis() as i64;
+                            action.status = ScheduleStatus::Pending;
+                            let detail = format!("{err:#}; retrying at {}", action.execute_at);
+                            (ExecutionOutcome::Retrying, detail)
+                        }
+                    }
+                    Ok(Execution::Applied) => {
+                        let applied = if self.dry_run {
+                            "dry run, nothing applied"
+                        } else {
+                            "applied"
+                        };
+                        let detail = advance(&mut action, now, ScheduleStatus::Completed, applied);
+                        (ExecutionOutcome::Succeeded, detail)
+                    }
+                };
+                self.finish(action, outcome, detail).await?;
                 executed += 1;
             }
         }
         Ok(())
     }
+
+    /// Persist the new status of `action`, then its history entry. The status
+    /// goes first: a schedule left `Executing` would be run again by the
+    /// stuck-schedule recovery, while a lost history entry only costs the
+    /// audit trail.
+    async fn finish(
+        &self,
+        action: ScheduledAction,
+        outcome: ExecutionOutcome,
+        detail: String,
+    ) -> Result<()> {
+        let id = action.id.clone();
+        self.storage.update_schedule(action).await?;
+        if let Err(err) = self
+            .storage
+            .record_execution(id.clone(), outcome, self.clock.now_ms(), detail)
+            .await
+        {
+            tracing::warn!(
+                "Failed to record execution of scheduled action {}: {:#}",
+                id,
+                err
+            );
+        }
+        Ok(())
+    }
+
+    /// Drift between the cluster and `action`'s assumptions, if any. Dry runs
+    /// never contact the cluster, so they skip the check.
+    async fn check_drift(
+        &self,
+        action: &ScheduledAction,
+        kube_client: &kube::Client,
+    ) -> Result<Option<String>> {
+        if self.dry_run {
+            return Ok(None);
+        }
+        crate::scheduler::executor::detect_drift(action, kube_client, self.drift_tolerance).await
+    }
+
+    /// Wait before retrying after the `attempts`-th failure.
+    fn retry_delay(&self, attempts: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+        self.retry_backoff
+            .saturating_mul(factor)
+            .min(MAX_RETRY_BACKOFF)
+    }
+}
+
+/// Move a recurring `action` on to its first run after `now`, or give a
+/// one-off action `once_status`. Returns `detail` with the outcome appended
+/// for the execution history.
+fn advance(
+    action: &mut ScheduledAction,
+    now: i64,
+    once_status: ScheduleStatus,
+    detail: &str,
+) -> String {
+    let Some(cron) = action.cron.as_deref() else {
+        action.status = once_status;
+        return detail.to_string();
+    };
+    match super::recurrence::next_run_after(cron, now) {
+        Ok(next) => {
+            action.execute_at = next;
+            action.attempts = 0;
+            action.status = ScheduleStatus::Pending;
+            format!("{detail}; next run at {next}")
+        }
+        Err(err) => {
+            tracing::error!("Scheduled action {} cannot recur: {:#}", action.id, err);
+            action.status = ScheduleStatus::Failed {
+                error: format!("{err:#}"),
+            };
+            format!("{detail}; cannot recur: {err:#}")
+        }
+    }
 }
 
 #[async_trait]
@@ -102,6 +309,9 @@ impl SchedulerPort for SchedulerService {
         if action.id.is_empty() {
             anyhow::bail!("scheduled action id is required");
         }
+        if let Some(cron) = action.cron.as_deref() {
+            super::recurrence::parse(cron)?;
+        }
         self.storage.insert_schedule(action.clone()).await?;
         Ok(action.id)
     }
@@ -119,4 +329,8 @@ impl SchedulerPort for SchedulerService {
     async fn list_scheduled(&self) -> Result<Vec<ScheduledAction>> {
         self.storage.get_all_schedules().await
     }
+
+    async fn list_executions(&self, id: ScheduleId) -> Result<Vec<ScheduleExecution>> {
+        self.storage.list_executions(id).await
+    }
 }
@@ -0,0 +1,362 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use http::{Method, Request, Response};
+use kube::client::Body;
+
+use phenome_domain::{ExecutionOutcome, RecommendationAction, ScheduleStatus, ScheduledAction};
+use phenome_ports::SchedulerPort;
+
+use crate::clock::{Clock, ManualClock};
+use crate::scheduler::SchedulerService;
+use crate::storage::StoragePort;
+use crate::storage::sqlite::SqliteStorage;
+
+async fn storage_with_scale_action(dir: &tempfile::TempDir) -> Arc<SqliteStorage> {
+    let db_path = dir.path().join("analytics.db");
+    let storage = Arc::new(SqliteStorage::new(db_path.to_string_lossy().to_string()).unwrap());
+    storage
+        .insert_schedule(ScheduledAction {
+            id: "scale-web".to_string(),
+            execute_at: 0,
+            recommendation_id: "rec-1".to_string(),
+            action: RecommendationAction::ScaleDeployment {
+                name: "web".to_string(),
+                from: 2,
+                to: 5,
+            },
+            status: ScheduleStatus::Pending,
+            attempts: 0,
+            cron: None,
+        })
+        .await
+        .unwrap();
+    storage
+}
+
+fn deployment_response(replicas: u32) -> Response<Body> {
+    let deployment = serde_json::json!({
+        "apiVersion": "apps/v1",
+        "kind": "Deployment",
+        "metadata": { "name": "web", "namespace": "apps" },
+        "spec": {
+            "replicas": replicas,
+            "selector": { "matchLabels": { "app": "web" } },
+            "template": {}
+        }
+    });
+    Response::builder()
+        .body(Body::from(serde_json::to_vec(&deployment).unwrap()))
+        .unwrap()
+}
+
+fn timeout_response() -> Response<Body> {
+    let status = serde_json::json!({
+        "kind": "Status",
+        "apiVersion": "v1",
+        "metadata": {},
+        "status": "Failure",
+        "message": "request timed out",
+        "reason": "Timeout",
+        "code": 504
+    });
+    Response::builder()
+        .status(504)
+        .body(Body::from(serde_json::to_vec(&status).unwrap()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn scale_deployment_patches_replicas_and_completes() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = storage_with_scale_action(&dir).await;
+
+    let (mock, handle) = tower_test::mock::pair::<Request<Body>, Response<Body>>();
+    let client = kube::Client::new(mock, "apps");
+    let server = tokio::spawn(async move {
+        let mut handle = std::pin::pin!(handle);
+        let (request, send) = handle.next_request().await.expect("no drift check");
+        assert_eq!(request.method(), Method::GET);
+        send.send_response(deployment_response(2));
+
+        let (request, send) = handle.next_request().await.expect("no patch sent");
+        assert_eq!(request.method(), Method::PATCH);
+        assert_eq!(
+            request.uri().path(),
+            "/apis/apps/v1/namespaces/apps/deployments/web"
+        );
+        assert_eq!(
+            request.headers()["content-type"],
+            "application/merge-patch+json"
+        );
+        let body = request.into_body().collect_bytes().await.unwrap();
+        let patch: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(patch, serde_json::json!({ "spec": { "replicas": 5 } }));
+        send.send_response(deployment_response(5));
+    });
+
+    let service = SchedulerService::new(storage.clone());
+    service.check_and_execute(&client).await.unwrap();
+    server.await.unwrap();
+
+    let schedules = storage.get_all_schedules().await.unwrap();
+    assert!(matches!(schedules[0].status, ScheduleStatus::Completed));
+}
+
+#[tokio::test]
+async fn flaky_scale_succeeds_on_third_attempt() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = storage_with_scale_action(&dir).await;
+
+    let (mock, handle) = tower_test::mock::pair::<Request<Body>, Response<Body>>();
+    let client = kube::Client::new(mock, "apps");
+    let server = tokio::spawn(async move {
+        let mut handle = std::pin::pin!(handle);
+        for attempt in 1..=3 {
+            let (_, send) = handle.next_request().await.expect("no drift check");
+            send.send_response(deployment_response(2));
+            let (_, send) = handle.next_request().await.expect("no patch sent");
+            if attempt < 3 {
+                send.send_response(timeout_response());
+            } else {
+                send.send_response(deployment_response(5));
+            }
+        }
+    });
+
+    let service = SchedulerService::new(storage.clone()).with_retries(3, Duration::ZERO);
+    for expected_attempts in 1..=2 {
+        service.check_and_execute(&client).await.unwrap();
+        let schedules = storage.get_all_schedules().await.unwrap();
+        assert!(matches!(schedules[0].status, ScheduleStatus::Pending));
+        assert_eq!(schedules[0].attempts, expected_attempts);
+    }
+    service.check_and_execute(&client).await.unwrap();
+    server.await.unwrap();
+
+    let schedules = storage.get_all_schedules().await.unwrap();
+    assert!(matches!(schedules[0].status, ScheduleStatus::Completed));
+    assert_eq!(schedules[0].attempts, 2);
+
+    let history = service
+        .list_executions("scale-web".to_string())
+        .await
+        .unwrap();
+    let outcomes: Vec<_> = history.iter().map(|entry| entry.outcome).collect();
+    assert_eq!(
+        outcomes,
+        [
+            ExecutionOutcome::Retrying,
+            ExecutionOutcome::Retrying,
+            ExecutionOutcome::Succeeded,
+        ]
+    );
+    assert!(history[0].detail.contains("request timed out"));
+}
+
+#[tokio::test]
+async fn drifted_deployment_cancels_the_scale() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = storage_with_scale_action(&dir).await;
+
+    let (mock, handle) = tower_test::mock::pair::<Request<Body>, Response<Body>>();
+    let client = kube::Client::new(mock, "apps");
+    let server = tokio::spawn(async move {
+        let mut handle = std::pin::pin!(handle);
+        let (request, send) = handle.next_request().await.expect("no drift check");
+        assert_eq!(request.method(), Method::GET);
+        // Someone scaled to 4 by hand since the recommendation assumed 2.
+        send.send_response(deployment_response(4));
+        assert!(handle.next_request().await.is_none(), "patch sent");
+    });
+
+    let service = SchedulerService::new(storage.clone()).with_drift_tolerance(1);
+    service.check_and_execute(&client).await.unwrap();
+    drop(client);
+    server.await.unwrap();
+
+    let schedules = storage.get_all_schedules().await.unwrap();
+    assert!(matches!(schedules[0].status, ScheduleStatus::Cancelled));
+    let history = storage
+        .list_executions("scale-web".to_string())
+        .await
+        .unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].outcome, ExecutionOutcome::Skipped);
+    assert_eq!(
+        history[0].detail,
+        "drift detected: deployment web runs 4 replicas, expected 2"
+    );
+}
+
+#[tokio::test]
+async fn drift_skips_one_run_of_a_recurring_scale() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("analytics.db");
+    let storage = Arc::new(SqliteStorage::new(db_path.to_string_lossy().to_string()).unwrap());
+    storage
+        .insert_schedule(ScheduledAction {
+            id: "nightly-scale-up".to_string(),
+            execute_at: 0,
+            recommendation_id: "rec-1".to_string(),
+            action: RecommendationAction::ScaleDeployment {
+                name: "web".to_string(),
+                from: 2,
+                to: 5,
+            },
+            status: ScheduleStatus::Pending,
+            attempts: 0,
+            cron: Some("0 2 * * *".to_string()),
+        })
+        .await
+        .unwrap();
+
+    let (mock, handle) = tower_test::mock::pair::<Request<Body>, Response<Body>>();
+    let client = kube::Client::new(mock, "apps");
+    let server = tokio::spawn(async move {
+        let mut handle = std::pin::pin!(handle);
+        let (_, send) = handle.next_request().await.expect("no drift check");
+        send.send_response(deployment_response(4));
+        assert!(handle.next_request().await.is_none(), "patch sent");
+    });
+
+    let service = SchedulerService::new(storage.clone()).with_drift_tolerance(1);
+    service.check_and_execute(&client).await.unwrap();
+    drop(client);
+    server.await.unwrap();
+
+    let schedules = storage.get_all_schedules().await.unwrap();
+    assert!(matches!(schedules[0].status, ScheduleStatus::Pending));
+    let next = chrono::DateTime::from_timestamp_millis(schedules[0].execute_at).unwrap();
+    assert_eq!(next.format("%H:%M:%S").to_string(), "02:00:00");
+    let history = storage
+        .list_executions("nightly-scale-up".to_string())
+        .await
+        .unwrap();
+    assert_eq!(history[0].outcome, ExecutionOutcome::Skipped);
+    assert!(history[0].detail.starts_with("drift detected: "));
+}
+
+#[tokio::test]
+async fn action_without_executor_is_not_reported_applied() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("analytics.db");
+    let storage = Arc::new(SqliteStorage::new(db_path.to_string_lossy().to_string()).unwrap());
+    storage
+        .insert_schedule(ScheduledAction {
+            id: "reclaim-logs".to_string(),
+            execute_at: 0,
+            recommendation_id: "rec-1".to_string(),
+            action: RecommendationAction::ReclaimStorage {
+                volume: "logs".to_string(),
+                size_gb: 20,
+            },
+            status: ScheduleStatus::Pending,
+            attempts: 0,
+            cron: None,
+        })
+        .await
+        .unwrap();
+
+    let (mock, _handle) = tower_test::mock::pair::<Request<Body>, Response<Body>>();
+    let client = kube::Client::new(mock, "apps");
+    SchedulerService::new(storage.clone())
+        .check_and_execute(&client)
+        .await
+        .unwrap();
+
+    let schedules = storage.get_all_schedules().await.unwrap();
+    assert!(matches!(schedules[0].status, ScheduleStatus::Failed { .. }));
+    let history = storage
+        .list_executions("reclaim-logs".to_string())
+        .await
+        .unwrap();
+    assert_eq!(history[0].outcome, ExecutionOutcome::Skipped);
+    assert!(history[0].detail.starts_with("not applied"));
+}
+
+#[tokio::test]
+async fn recurring_action_is_rescheduled_after_success() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("analytics.db");
+    let storage = Arc::new(SqliteStorage::new(db_path.to_string_lossy().to_string()).unwrap());
+    let service = SchedulerService::new(storage.clone()).with_dry_run(true);
+    let nightly = ScheduledAction {
+        id: "nightly-scale-down".to_string(),
+        execute_at: 0,
+        recommendation_id: "rec-1".to_string(),
+        action: RecommendationAction::ScaleDeployment {
+            name: "web".to_string(),
+            from: 5,
+            to: 2,
+        },
+        status: ScheduleStatus::Pending,
+        attempts: 0,
+        cron: Some("0 2 * * *".to_string()),
+    };
+
+    let invalid = ScheduledAction {
+        cron: Some("0 25 * * *".to_string()),
+        ..nightly.clone()
+    };
+    let err = service.schedule_action(invalid).await.unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("invalid cron expression \"0 25 * * *\"")
+    );
+
+    service.schedule_action(nightly).await.unwrap();
+    let (mock, _handle) = tower_test::mock::pair::<Request<Body>, Response<Body>>();
+    let client = kube::Client::new(mock, "apps");
+    service.check_and_execute(&client).await.unwrap();
+
+    let schedules = storage.get_all_schedules().await.unwrap();
+    assert!(matches!(schedules[0].status, ScheduleStatus::Pending));
+    let next = chrono::DateTime::from_timestamp_millis(schedules[0].execute_at).unwrap();
+    assert!(next > chrono::Utc::now());
+    assert_eq!(next.format("%H:%M:%S").to_string(), "02:00:00");
+}
+
+#[tokio::test]
+async fn action_runs_once_the_clock_passes_its_due_time() {
+    const HOUR_MS: i64 = 60 * 60 * 1000;
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("analytics.db");
+    let storage = Arc::new(SqliteStorage::new(db_path.to_string_lossy().to_string()).unwrap());
+    let clock = ManualClock::new(1_700_000_000_000);
+    let service = SchedulerService::new(storage.clone())
+        .with_dry_run(true)
+        .with_clock(Arc::new(clock.clone()));
+    service
+        .schedule_action(ScheduledAction {
+            id: "scale-web".to_string(),
+            execute_at: clock.now_ms() + HOUR_MS,
+            recommendation_id: "rec-1".to_string(),
+            action: RecommendationAction::ScaleDeployment {
+                name: "web".to_string(),
+                from: 2,
+                to: 5,
+            },
+            status: ScheduleStatus::Pending,
+            attempts: 0,
+            cron: None,
+        })
+        .await
+        .unwrap();
+    let (mock, _handle) = tower_test::mock::pair::<Request<Body>, Response<Body>>();
+    let client = kube::Client::new(mock, "apps");
+
+    service.check_and_execute(&client).await.unwrap();
+    let schedules = storage.get_all_schedules().await.unwrap();
+    assert!(matches!(schedules[0].status, ScheduleStatus::Pending));
+
+    clock.advance(Duration::from_secs(61 * 60));
+    service.check_and_execute(&client).await.unwrap();
+    let schedules = storage.get_all_schedules().await.unwrap();
+    assert!(matches!(schedules[0].status, ScheduleStatus::Completed));
+    let history = storage
+        .list_executions("scale-web".to_string())
+        .await
+        .unwrap();
+    assert_eq!(history[0].timestamp, clock.now_ms());
+}
@@ -8,6 +8,9 @@ pub mod storage;
 pub use infra::cluster_manager::ClusterManager;
 pub use runtime::analytics_service::AnalyticsService;
 
-pub use infra::{circuit_breaker, cluster_manager};
+pub use infra::{circuit_breaker, clock, cluster_manager, prometheus, rate_limiter};
 pub use interfaces::{grpc, notification, scheduler};
-pub use runtime::{aggregator, analytics_engine, analytics_service, cache, metrics_collector};
+pub use runtime::{
+    aggregator, analytics_engine, analytics_service, anomaly_feed, cache, metrics_collector,
+    retention, write_queue,
+};
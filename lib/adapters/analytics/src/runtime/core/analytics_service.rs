@@ -1,27 +1,53 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use async_trait::async_trait;
+use std::collections::HashMap;
+use std::io::Write;
 use std::sync::{Arc, RwLock};
 use std::time::Duration;
+use tokio::sync::{Mutex, broadcast};
 
 use phenome_domain::{
-    AggregatedMetric, AggregatedQuery, Anomaly, AnomalyFilter, MetricSample, MetricType,
-    MetricsQuery, Recommendation, RecommendationFilter, TimeRange, TimeSeries, TimeSeriesPoint,
+    AggregatedMetric, AggregatedQuery, Anomaly, AnomalyFilter, ClusterId, DownsampledMetrics,
+    MetricSample, MetricType, MetricsQuery, Notification, Recommendation, RecommendationAction,
+    RecommendationFilter, RecommendationStatus, ScalingPrediction, ScheduleStatus, ScheduledAction,
+    TimeRange, TimeSeries, TimeSeriesData, TimeSeriesPoint,
 };
+use phenome_ml::{AnomalyDetector, ScalingPredictor};
 use phenome_ports::AnalyticsPort;
 
-use crate::aggregator::Aggregator;
+use crate::anomaly_feed::AnomalyFeed;
 use crate::grpc::MlClient;
-use crate::storage::StoragePort;
+use crate::storage::{ExportFormat, StoragePort};
 
 #[derive(Clone)]
 pub struct AnalyticsService {
     storage: Arc<dyn StoragePort>,
-    aggregator: Aggregator,
     anomalies: Arc<RwLock<Vec<Anomaly>>>,
     recommendations: Arc<RwLock<Vec<Recommendation>>>,
     ml_client: MlClient,
+    fallback_detector: AnomalyDetector,
+    scaling_predictor: ScalingPredictor,
+    anomaly_feed: AnomalyFeed,
+    /// Serializes status changes, so two requests can't both act on the same
+    /// pending recommendation.
+    status_changes: Arc<Mutex<()>>,
 }
 
+/// New status of a recommendation and the action to schedule with it.
+type StatusChange = (RecommendationStatus, Option<ScheduledAction>);
+
+/// A status change the recommendation's current status doesn't allow.
+#[derive(Debug)]
+pub struct InvalidTransition(pub String);
+
+impl std::fmt::Display for InvalidTransition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for InvalidTransition {}
+
 impl std::fmt::Debug for AnalyticsService {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let anomalies_count = self
@@ -36,10 +62,12 @@ impl std::fmt::Debug for AnalyticsService {
             .unwrap_or(0);
         f.debug_struct("AnalyticsService")
             .field("storage", &"StoragePort")
-            .field("aggregator", &self.aggregator)
             .field("anomalies_count", &anomalies_count)
             .field("recommendations_count", &recommendations_count)
             .field("ml_client", &self.ml_client)
+            .field("fallback_detector", &self.fallback_detector)
+            .field("scaling_predictor", &self.scaling_predictor)
+            .field("anomaly_subscribers", &self.anomaly_subscriber_count())
             .finish()
     }
 }
@@ -48,14 +76,27 @@ impl AnalyticsService {
     pub fn new(storage: Arc<dyn StoragePort>, ml_client: MlClient) -> Self {
         Self {
             storage,
-            aggregator: Aggregator::new(),
             anomalies: Arc::new(RwLock::new(Vec::new())),
             recommendations: Arc::new(RwLock::new(Vec::new())),
             ml_client,
+            fallback_detector: AnomalyDetector::default(),
+            scaling_predictor: ScalingPredictor::new(),
+            anomaly_feed: AnomalyFeed::default(),
+            status_changes: Arc::new(Mutex::new(())),
         }
     }
 
+    /// Keep the last `backlog` anomalies for subscribers that join late.
+    pub fn with_anomaly_backlog(mut self, backlog: usize) -> Self {
+        self.anomaly_feed = AnomalyFeed::new(backlog);
+        self
+    }
+
+    /// Store `anomalies` and push them to live subscribers.
     pub fn add_anomalies(&self, anomalies: Vec<Anomaly>) {
+        for anomaly in &anomalies {
+            self.anomaly_feed.publish(anomaly.clone());
+        }
         if let Ok(mut store) = self.anomalies.write() {
             store.extend(anomalies);
         } else {
@@ -63,24 +104,289 @@ impl AnalyticsService {
         }
     }
 
-    pub fn add_recommendations(&self, recommendations: Vec<Recommendation>) {
+    /// Recently detected anomalies and a receiver of every one detected from
+    /// now on.
+    pub fn subscribe_anomalies(&self) -> (Vec<Anomaly>, broadcast::Receiver<Anomaly>) {
+        self.anomaly_feed.subscribe()
+    }
+
+    /// Live anomaly subscriptions.
+    pub fn anomaly_subscriber_count(&self) -> usize {
+        self.anomaly_feed.subscriber_count()
+    }
+
+    /// Persist `recommendations` and serve them alongside the ones already
+    /// known.
+    pub async fn add_recommendations(&self, recommendations: Vec<Recommendation>) -> Result<()> {
+        self.storage
+            .upsert_recommendations(recommendations.clone())
+            .await?;
         if let Ok(mut store) = self.recommendations.write() {
             store.extend(recommendations);
         } else {
             tracing::error!("recommendations lock poisoned");
         }
+        Ok(())
+    }
+
+    /// Load stored recommendations, with their last status, in place of the
+    /// in-memory ones. Returns how many were loaded.
+    pub async fn restore_recommendations(&self) -> Result<usize> {
+        let stored = self.storage.list_recommendations().await?;
+        let count = stored.len();
+        match self.recommendations.write() {
+            Ok(mut store) => *store = stored,
+            Err(_) => anyhow::bail!("recommendations lock poisoned"),
+        }
+        Ok(count)
+    }
+
+    /// Mark recommendation `id` applied, or, given `execute_at` (ms), queue
+    /// its scale action for then and mark it scheduled. `None` when no
+    /// recommendation has that id.
+    pub async fn apply_recommendation(
+        &self,
+        id: &str,
+        execute_at: Option<i64>,
+    ) -> Result<Option<Recommendation>> {
+        self.change_recommendation_status(id, |recommendation| {
+            let Some(execute_at) = execute_at else {
+                return Ok((
+                    RecommendationStatus::Applied {
+                        applied_at: chrono::Utc::now().timestamp_millis(),
+                    },
+                    None,
+                ));
+            };
+            if !matches!(
+                recommendation.action,
+                RecommendationAction::ScaleDeployment { .. }
+            ) {
+                return Err(InvalidTransition(format!(
+                    "recommendation {} has no scale action to schedule",
+                    recommendation.id
+                )));
+            }
+            let schedule = ScheduledAction {
+                id: uuid::Uuid::new_v4().to_string(),
+                execute_at,
+                recommendation_id: recommendation.id.clone(),
+                action: recommendation.action.clone(),
+                status: ScheduleStatus::Pending,
+                attempts: 0,
+                cron: None,
+            };
+            Ok((
+                RecommendationStatus::Scheduled { execute_at },
+                Some(schedule),
+            ))
+        })
+        .await
+    }
+
+    /// Mark recommendation `id` dismissed for `reason`. `None` when no
+    /// recommendation has that id.
+    pub async fn dismiss_recommendation(
+        &self,
+        id: &str,
+        reason: String,
+    ) -> Result<Option<Recommendation>> {
+        self.change_recommendation_status(id, |_| {
+            Ok((RecommendationStatus::Dismissed { reason }, None))
+        })
+        .await
+    }
+
+    /// Move a pending recommendation to the status `next` picks and persist it
+    /// together with the schedule it returns. Any other status fails with
+    /// [`InvalidTransition`].
+    async fn change_recommendation_status(
+        &self,
+        id: &str,
+        next: impl FnOnce(&Recommendation) -> Result<StatusChange, InvalidTransition>,
+    ) -> Result<Option<Recommendation>> {
+        let _guard = self.status_changes.lock().await;
+        let current = self
+            .recommendations
+            .read()
+            .map_err(|_| anyhow::anyhow!("recommendations lock poisoned"))?
+            .iter()
+            .find(|rec| rec.id == id)
+            .cloned();
+        let Some(mut recommendation) = current else {
+            return Ok(None);
+        };
+        let settled = match recommendation.status {
+            RecommendationStatus::Pending => None,
+            RecommendationStatus::Scheduled { .. } => Some("scheduled"),
+            RecommendationStatus::Applied { .. } => Some("applied"),
+            RecommendationStatus::Dismissed { .. } => Some("dismissed"),
+        };
+        if let Some(settled) = settled {
+            return Err(
+                InvalidTransition(format!("recommendation {id} is already {settled}")).into(),
+            );
+        }
+
+        let (status, schedule) = next(&recommendation)?;
+        recommendation.status = status;
+        self.storage
+            .commit_recommendation(recommendation.clone(), schedule)
+            .await
+            .context("failed to store recommendation status")?;
+        match self.recommendations.write() {
+            Ok(mut store) => {
+                if let Some(stored) = store.iter_mut().find(|rec| rec.id == id) {
+                    *stored = recommendation.clone();
+                }
+            }
+            Err(_) => tracing::error!("recommendations lock poisoned"),
+        }
+        Ok(Some(recommendation))
+    }
+
+    /// Whether the ML service is currently accepting detection calls.
+    pub fn ml_available(&self) -> bool {
+        self.ml_client.is_available()
+    }
+
+    /// Fails unless both storage and the ML service answer.
+    pub async fn check_health(&self) -> Result<()> {
+        self.storage
+            .ping()
+            .await
+            .context("storage is unreachable")?;
+        self.ml_client
+            .probe()
+            .await
+            .context("ML service is unreachable")
+    }
+
+    /// Detect anomalies through the ML service, falling back to the in-process
+    /// sigma detector so detection degrades rather than stops during an outage.
+    async fn detect_anomalies(&self, series: TimeSeries, range: TimeRange) -> Result<Vec<Anomaly>> {
+        match self.ml_client.detect_anomalies(&series).await {
+            Ok(detected) => Ok(detected),
+            Err(err) => {
+                tracing::warn!(
+                    "ML anomaly detection unavailable, using statistical fallback: {:#}",
+                    err
+                );
+                self.fallback_detector.detect(&TimeSeriesData {
+                    cluster_id: series.cluster_id.clone(),
+                    range,
+                    series: vec![series],
+                })
+            }
+        }
+    }
+
+    /// Blocking export of raw samples; call from a blocking thread.
+    pub fn export_metrics(
+        &self,
+        query: MetricsQuery,
+        format: ExportFormat,
+        writer: &mut dyn Write,
+    ) -> Result<usize> {
+        self.storage.export_metrics(query, format, writer)
+    }
+
+    /// Blocking read of raw samples in batches; call from a blocking thread.
+    pub fn stream_metrics(
+        &self,
+        query: MetricsQuery,
+        batch_size: usize,
+        sink: &mut dyn FnMut(Vec<MetricSample>) -> bool,
+    ) -> Result<usize> {
+        self.storage.stream_metrics(query, batch_size, sink)
+    }
+
+    /// Blocking downsampled query of raw samples; call from a blocking
+    /// thread.
+    pub fn query_downsampled(
+        &self,
+        query: MetricsQuery,
+        max_points: usize,
+    ) -> Result<DownsampledMetrics> {
+        self.storage.query_downsampled(query, max_points)
+    }
+
+    /// Ids of the clusters with samples recorded, sorted.
+    pub async fn list_clusters(&self) -> Result<Vec<ClusterId>> {
+        self.storage.list_cluster_ids().await
+    }
+
+    /// Sent notifications not yet marked read, newest first.
+    pub async fn list_unread_notifications(&self) -> Result<Vec<Notification>> {
+        self.storage.list_unread_notifications().await
+    }
+
+    /// Mark notification `id` read so it is no longer listed as unread.
+    pub async fn mark_notification_read(&self, id: String) -> Result<()> {
+        self.storage.mark_notification_read(id).await
+    }
+
+    /// Forecast `horizon` past the end of `range` for every resource and
+    /// metric with samples in it, sorted by resource.
+    pub async fn predict_scaling(
+        &self,
+        cluster_id: Option<ClusterId>,
+        range: TimeRange,
+        horizon: Duration,
+        metric_types: Vec<MetricType>,
+    ) -> Result<Vec<ScalingPrediction>> {
+        let samples = self
+            .storage
+            .query_metrics(MetricsQuery {
+                cluster_id,
+                resource_type: None,
+                resource_ids: Vec::new(),
+                metric_types,
+                time_range: Some(range),
+            })
+            .await?;
+
+        let mut grouped: HashMap<(String, MetricType), TimeSeries> = HashMap::new();
+        for sample in samples {
+            grouped
+                .entry((sample.resource_id.clone(), sample.metric_type))
+                .or_insert_with(|| TimeSeries {
+                    cluster_id: sample.cluster_id.clone(),
+                    resource_id: sample.resource_id.clone(),
+                    metric_type: sample.metric_type,
+                    unit: sample.unit.clone(),
+                    points: Vec::new(),
+                })
+                .points
+                .push(TimeSeriesPoint {
+                    timestamp: sample.timestamp,
+                    value: sample.value,
+                });
+        }
+
+        let mut predictions = grouped
+            .into_values()
+            .map(|mut series| {
+                series.points.sort_by_key(|point| point.timestamp);
+                self.scaling_predictor
+                    .predict(&series, horizon, range.end_ms)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        predictions.sort_by(|a, b| {
+            a.resource_id
+                .cmp(&b.resource_id)
+                .then((a.metric_type as u8).cmp(&(b.metric_type as u8)))
+        });
+        Ok(predictions)
     }
 }
 
 #[async_trait]
 impl AnalyticsPort for AnalyticsService {
+    /// Stores raw samples only; hourly rows (with percentiles) are produced by
+    /// the `Aggregator` rollup once each window is complete.
     async fn record_metrics(&self, samples: Vec<MetricSample>) -> Result<()> {
-        self.storage.insert_metrics(samples.clone()).await?;
-        let aggregates = self
-            .aggregator
-            .aggregate_window(&samples, Duration::from_secs(3600))?;
-        self.storage.insert_aggregated(aggregates).await?;
-        Ok(())
+        self.storage.insert_metrics(samples).await
     }
 
     async fn query_aggregated(&self, query: AggregatedQuery) -> Result<Vec<AggregatedMetric>> {
@@ -136,12 +442,16 @@ impl AnalyticsPort for AnalyticsService {
                 .get_time_series(resource_id.clone(), metric_type, range)
                 .await
             {
-                if let Ok(detected) = self.ml_client.detect_anomalies(&series).await {
-                    // Persist anomalies
-                    if let Err(e) = self.storage.insert_anomalies(detected.clone()).await {
-                        tracing::error!("Failed to persist anomalies: {}", e);
+                match self.detect_anomalies(series, range).await {
+                    Ok(detected) => {
+                        // Persist anomalies
+                        if let Err(e) = self.storage.insert_anomalies(detected.clone()).await {
+                            tracing::error!("Failed to persist anomalies: {}", e);
+                        }
+                        self.add_anomalies(detected);
                     }
-                    self.add_anomalies(detected);
+                    // Keep serving stored anomalies when no detector produced a result.
+                    Err(err) => tracing::warn!("Anomaly detection unavailable: {:#}", err),
                 }
             }
         }
@@ -0,0 +1,85 @@
+//! Fan-out of newly detected anomalies to live subscribers.
+//!
+//! The last `backlog` anomalies are kept so a subscriber joining late still
+//! sees what was detected just before it connected.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+use phenome_domain::Anomaly;
+
+/// Anomalies kept for late subscribers by default.
+pub const DEFAULT_ANOMALY_BACKLOG: usize = 32;
+/// Anomalies buffered per subscriber before a slow one starts missing some.
+const SUBSCRIBER_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone)]
+pub struct AnomalyFeed {
+    shared: Arc<Mutex<Shared>>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    backlog: VecDeque<Anomaly>,
+    capacity: usize,
+    sender: broadcast::Sender<Anomaly>,
+}
+
+impl Default for AnomalyFeed {
+    fn default() -> Self {
+        Self::new(DEFAULT_ANOMALY_BACKLOG)
+    }
+}
+
+impl AnomalyFeed {
+    pub fn new(backlog: usize) -> Self {
+        let (sender, _) = broadcast::channel(SUBSCRIBER_CAPACITY);
+        Self {
+            shared: Arc::new(Mutex::new(Shared {
+                backlog: VecDeque::with_capacity(backlog),
+                capacity: backlog,
+                sender,
+            })),
+        }
+    }
+
+    /// Record `anomaly` in the backlog and push it to every subscriber.
+    pub fn publish(&self, anomaly: Anomaly) {
+        let Ok(mut shared) = self.shared.lock() else {
+            tracing::error!("anomaly feed lock poisoned");
+            return;
+        };
+        if shared.capacity > 0 {
+            if shared.backlog.len() >= shared.capacity {
+                shared.backlog.pop_front();
+            }
+            shared.backlog.push_back(anomaly.clone());
+        }
+        // Without subscribers there is no one to tell.
+        let _ = shared.sender.send(anomaly);
+    }
+
+    /// The backlog, oldest first, and a receiver of everything published
+    /// after it. Dropping the receiver unsubscribes.
+    pub fn subscribe(&self) -> (Vec<Anomaly>, broadcast::Receiver<Anomaly>) {
+        match self.shared.lock() {
+            Ok(shared) => (
+                shared.backlog.iter().cloned().collect(),
+                shared.sender.subscribe(),
+            ),
+            Err(poisoned) => {
+                tracing::error!("anomaly feed lock poisoned");
+                (Vec::new(), poisoned.into_inner().sender.subscribe())
+            }
+        }
+    }
+
+    /// Live subscribers.
+    pub fn subscriber_count(&self) -> usize {
+        self.shared
+            .lock()
+            .map(|shared| shared.sender.receiver_count())
+            .unwrap_or(0)
+    }
+}
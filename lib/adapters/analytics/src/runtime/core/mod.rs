@@ -1,2 +1,3 @@
 pub mod analytics_engine;
 pub mod analytics_service;
+pub mod anomaly_feed;
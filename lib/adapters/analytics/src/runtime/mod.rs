@@ -1,5 +1,5 @@
 pub mod core;
 pub mod pipeline;
 
-pub use core::{analytics_engine, analytics_service};
-pub use pipeline::{aggregator, cache, metrics_collector};
+pub use core::{analytics_engine, analytics_service, anomaly_feed};
+pub use pipeline::{aggregator, cache, metrics_collector, retention, write_queue};
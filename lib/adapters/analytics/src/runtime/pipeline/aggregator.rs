@@ -1,34 +1,64 @@
 use anyhow::Result;
-use phenome_domain::{AggregatedMetric, MetricSample, MetricType, ResourceType};
-use std::collections::HashMap;
+use phenome_domain::{
+    AggregatedMetric, AggregatedQuery, DailyAggregate, MetricSample, MetricType, MetricsQuery,
+    ResourceType, TimeRange,
+};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::watch;
 use tokio::time::{interval, timeout};
 
+use super::quantiles::Quantiles;
 use crate::storage::StoragePort;
 
 #[derive(Debug, Clone, Default)]
 pub struct Aggregator;
 
-const RETENTION_INTERVAL: Duration = Duration::from_secs(60 * 60);
-const RETENTION_BUDGET: Duration = Duration::from_secs(30);
+const ROLLUP_BUDGET: Duration = Duration::from_secs(120);
+// Raw samples older than the default raw retention are gone, so there is
+// nothing to backfill past it.
+const MAX_BACKFILL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+// Re-run hourly so window rows that land after midnight still reach the
+// previous day; the daily upsert makes repeats harmless.
+const DAILY_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const DAILY_BUDGET: Duration = Duration::from_secs(120);
 
 impl Aggregator {
     pub fn new() -> Self {
         Self
     }
 
-    pub async fn run_hourly(storage: Arc<dyn StoragePort>) {
+    /// Roll up each completed `window` of raw samples, with buckets aligned
+    /// to wall-clock multiples of `window`. Windows missed since the last
+    /// processed one are backfilled first.
+    pub async fn run(storage: Arc<dyn StoragePort>, window: Duration) {
         let (_tx, rx) = watch::channel(false);
-        Self::run_hourly_with_shutdown(storage, rx).await;
+        Self::run_with_shutdown(storage, window, rx).await;
     }
 
-    pub async fn run_hourly_with_shutdown(
+    pub async fn run_with_shutdown(
         storage: Arc<dyn StoragePort>,
+        window: Duration,
         mut shutdown: watch::Receiver<bool>,
     ) {
-        let mut tick = interval(RETENTION_INTERVAL);
+        let aggregator = Self::new();
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        tokio::select! {
+            _ = shutdown.changed() => return,
+            result = aggregator.backfill_since_watermark(storage.as_ref(), window, now_ms) => {
+                match result {
+                    Ok(windows) if windows > 0 => {
+                        tracing::info!(windows, "Backfilled missed aggregation windows");
+                    }
+                    Ok(_) => {}
+                    Err(err) => tracing::error!("Aggregation backfill failed: {}", err),
+                }
+            }
+        }
+
+        let mut tick = interval(window);
         loop {
             tokio::select! {
                 result = shutdown.changed() => {
@@ -37,16 +67,22 @@ impl Aggregator {
                     }
                 }
                 _ = tick.tick() => {
-                    match timeout(RETENTION_BUDGET, storage.cleanup_retention()).await {
-                        Ok(Ok(())) => {}
+                    let now_ms = chrono::Utc::now().timestamp_millis();
+                    let window_start = previous_window_start(now_ms, window);
+                    match timeout(
+                        ROLLUP_BUDGET,
+                        aggregator.rollup_window(storage.as_ref(), window_start, window),
+                    )
+                    .await
+                    {
+                        Ok(Ok(rows)) => {
+                            tracing::debug!(rows, window_start, "Rolled up metrics window");
+                        }
                         Ok(Err(err)) => {
-                            tracing::error!("Retention cleanup failed: {}", err);
+                            tracing::error!("Window rollup failed: {}", err);
                         }
                         Err(_) => {
-                            tracing::warn!(
-                                "Retention cleanup exceeded {:?} budget",
-                                RETENTION_BUDGET
-                            );
+                            tracing::warn!("Window rollup exceeded {:?} budget", ROLLUP_BUDGET);
                         }
                     }
                 }
@@ -54,52 +90,235 @@ impl Aggregator {
         }
     }
 
+    /// Roll the previous day's `window` aggregates into the daily tier.
+    pub async fn run_daily(storage: Arc<dyn StoragePort>, window: Duration) {
+        let (_tx, rx) = watch::channel(false);
+        Self::run_daily_with_shutdown(storage, window, rx).await;
+    }
+
+    pub async fn run_daily_with_shutdown(
+        storage: Arc<dyn StoragePort>,
+        window: Duration,
+        mut shutdown: watch::Receiver<bool>,
+    ) {
+        let aggregator = Self::new();
+        let mut tick = interval(DAILY_INTERVAL);
+        loop {
+            tokio::select! {
+                result = shutdown.changed() => {
+                    if result.is_err() || *shutdown.borrow() {
+                        break;
+                    }
+                }
+                _ = tick.tick() => {
+                    let now_ms = chrono::Utc::now().timestamp_millis();
+                    let day_start = previous_window_start(now_ms, DAY);
+                    match timeout(
+                        DAILY_BUDGET,
+                        aggregator.rollup_day(storage.as_ref(), day_start, window),
+                    )
+                    .await
+                    {
+                        Ok(Ok(rows)) => {
+                            tracing::debug!(rows, day_start, "Rolled up daily metrics");
+                        }
+                        Ok(Err(err)) => {
+                            tracing::error!("Daily rollup failed: {}", err);
+                        }
+                        Err(_) => {
+                            tracing::warn!("Daily rollup exceeded {:?} budget", DAILY_BUDGET);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Roll the `window` aggregates of the UTC day starting at `day_start`
+    /// into one min/avg/max/count row per series. Re-running for the same
+    /// day overwrites the stored rows.
+    pub async fn rollup_day(
+        &self,
+        storage: &dyn StoragePort,
+        day_start: i64,
+        window: Duration,
+    ) -> Result<usize> {
+        let day_ms = DAY.as_millis() as i64;
+        let windows = storage
+            .query_aggregated(AggregatedQuery {
+                cluster_id: None,
+                resource_type: None,
+                metric_types: Vec::new(),
+                window_duration: window,
+                time_range: Some(TimeRange {
+                    start_ms: day_start,
+                    end_ms: day_start + day_ms - 1,
+                }),
+            })
+            .await?;
+
+        type SeriesKey = (String, String, String);
+        let mut groups: HashMap<SeriesKey, DailyAggregate> = HashMap::new();
+        for metric in windows
+            .into_iter()
+            .filter(|metric| metric.window_duration == window && metric.count > 0)
+        {
+            let key = (
+                metric.cluster_id.clone(),
+                serde_json::to_string(&metric.resource_type)?,
+                serde_json::to_string(&metric.metric_type)?,
+            );
+            let daily = groups.entry(key).or_insert_with(|| DailyAggregate {
+                cluster_id: metric.cluster_id.clone(),
+                resource_type: metric.resource_type,
+                metric_type: metric.metric_type,
+                day_start,
+                count: 0,
+                sum: 0.0,
+                min: f64::INFINITY,
+                max: f64::NEG_INFINITY,
+                avg: 0.0,
+            });
+            daily.count += metric.count;
+            daily.sum += metric.sum;
+            daily.min = daily.min.min(metric.min);
+            daily.max = daily.max.max(metric.max);
+        }
+
+        let aggregates: Vec<DailyAggregate> = groups
+            .into_values()
+            .map(|mut daily| {
+                daily.avg = daily.sum / daily.count as f64;
+                daily
+            })
+            .collect();
+        let rows = aggregates.len();
+        storage.upsert_daily(aggregates).await?;
+        Ok(rows)
+    }
+
+    /// Roll up every complete `window` in `[from_ms, to_ms)` that has not
+    /// been processed yet. Returns the number of windows processed.
+    pub async fn backfill(
+        &self,
+        storage: &dyn StoragePort,
+        from_ms: i64,
+        to_ms: i64,
+        window: Duration,
+    ) -> Result<usize> {
+        let window_ms = window.as_millis() as i64;
+        if window_ms == 0 {
+            return Ok(0);
+        }
+        let first = window_floor(from_ms, window);
+        let last = window_floor(to_ms, window) - window_ms;
+        if last < first {
+            return Ok(0);
+        }
+
+        let processed: HashSet<i64> = storage
+            .processed_windows(window, first, last)
+            .await?
+            .into_iter()
+            .collect();
+        let mut windows = 0;
+        for window_start in (first..=last).step_by(window_ms as usize) {
+            if processed.contains(&window_start) {
+                continue;
+            }
+            self.commit_rollup(storage, window_start, window).await?;
+            windows += 1;
+        }
+        Ok(windows)
+    }
+
+    /// Backfill from the window after the last processed one up to `now_ms`,
+    /// looking back at most [`MAX_BACKFILL`]. A store with no processed
+    /// windows has no watermark and is left to the regular schedule.
+    async fn backfill_since_watermark(
+        &self,
+        storage: &dyn StoragePort,
+        window: Duration,
+        now_ms: i64,
+    ) -> Result<usize> {
+        let Some(watermark) = storage.last_processed_window(window).await? else {
+            return Ok(0);
+        };
+        let from_ms =
+            (watermark + window.as_millis() as i64).max(now_ms - MAX_BACKFILL.as_millis() as i64);
+        self.backfill(storage, from_ms, now_ms, window).await
+    }
+
+    /// Aggregate raw samples in `[window_start, window_start + window)` into
+    /// one row per series, including p50/p95/p99. Windows already processed
+    /// are skipped so restarts do not duplicate them.
+    pub async fn rollup_window(
+        &self,
+        storage: &dyn StoragePort,
+        window_start: i64,
+        window: Duration,
+    ) -> Result<usize> {
+        let processed = storage
+            .processed_windows(window, window_start, window_start)
+            .await?;
+        if !processed.is_empty() {
+            return Ok(0);
+        }
+        self.commit_rollup(storage, window_start, window).await
+    }
+
+    /// Aggregate one window and store it together with its processed marker.
+    async fn commit_rollup(
+        &self,
+        storage: &dyn StoragePort,
+        window_start: i64,
+        window: Duration,
+    ) -> Result<usize> {
+        let window_ms = window.as_millis() as i64;
+        let samples = storage
+            .query_metrics(MetricsQuery {
+                time_range: Some(TimeRange {
+                    start_ms: window_start,
+                    end_ms: window_start + window_ms - 1,
+                }),
+                ..MetricsQuery::default()
+            })
+            .await?;
+        let aggregates = self.aggregate_window(&samples, window)?;
+        let rows = aggregates.len();
+        storage
+            .commit_window(window_start, window, aggregates)
+            .await?;
+        Ok(rows)
+    }
+
     pub fn aggregate_window(
         &self,
         samples: &[MetricSample],
         window_duration: Duration,
     ) -> Result<Vec<AggregatedMetric>> {
-        let window_secs = window_duration.as_secs() as i64;
-        if window_secs == 0 {
+        if window_duration.as_millis() == 0 {
             return Ok(Vec::new());
         }
 
         // Key: (ClusterId, ResourceType(json), MetricType(json), window_start)
         // Using Strings for JSON-encoded enums to implement Hash
         type GroupKey = (String, String, String, i64);
-        let mut groups: HashMap<GroupKey, Vec<f64>> = HashMap::new();
+        let mut groups: HashMap<GroupKey, SeriesRollup> = HashMap::new();
 
         for s in samples {
-            // Timestamp in ms. Convert to seconds, bucketize, convert back to ms.
-            let ts_sec = s.timestamp / 1000;
-            let window_start_sec = (ts_sec / window_secs) * window_secs;
-            let window_start = window_start_sec * 1000;
-
+            let window_start = window_floor(s.timestamp, window_duration);
             let key = (
                 s.cluster_id.clone(),
                 serde_json::to_string(&s.resource_type)?,
                 serde_json::to_string(&s.metric_type)?,
                 window_start,
             );
-            groups.entry(key).or_default().push(s.value);
+            groups.entry(key).or_default().add(s.value);
         }
 
         let mut results = Vec::new();
-        for ((cluster_id, r_type_str, m_type_str, window_start), values) in groups {
-            let count = values.len() as u64;
-            let sum: f64 = values.iter().sum();
-
-            let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
-            let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-            let avg = if count > 0 { sum / count as f64 } else { 0.0 };
-
-            let mut sorted = values.clone();
-            sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
-
-            let p50 = percentile(&sorted, 0.5);
-            let p95 = percentile(&sorted, 0.95);
-            let p99 = percentile(&sorted, 0.99);
-
+        for ((cluster_id, r_type_str, m_type_str, window_start), mut rollup) in groups {
             let resource_type: ResourceType = serde_json::from_str(&r_type_str)?;
             let metric_type: MetricType = serde_json::from_str(&m_type_str)?;
 
@@ -109,14 +328,14 @@ impl Aggregator {
                 metric_type,
                 window_start,
                 window_duration,
-                count,
-                sum,
-                min,
-                max,
-                avg,
-                p50,
-                p95,
-                p99,
+                count: rollup.count,
+                sum: rollup.sum,
+                min: rollup.min,
+                max: rollup.max,
+                avg: rollup.sum / rollup.count as f64,
+                p50: rollup.quantiles.quantile(0.5),
+                p95: rollup.quantiles.quantile(0.95),
+                p99: rollup.quantiles.quantile(0.99),
             });
         }
 
@@ -124,16 +343,45 @@ impl Aggregator {
     }
 }
 
-fn percentile(sorted: &[f64], pct: f64) -> f64 {
-    if sorted.is_empty() {
-        return 0.0;
-    }
-    let idx = (sorted.len() as f64 * pct).ceil() as usize;
-    if idx == 0 {
-        return sorted[0];
+/// Running statistics for one series within one window.
+#[derive(Debug, Clone)]
+struct SeriesRollup {
+    count: u64,
+    sum: f64,
+    min: f64,
+    max: f64,
+    quantiles: Quantiles,
+}
+
+impl Default for SeriesRollup {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            sum: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            quantiles: Quantiles::default(),
+        }
     }
-    if idx >= sorted.len() {
-        return sorted[sorted.len() - 1];
+}
+
+impl SeriesRollup {
+    fn add(&mut self, value: f64) {
+        self.count += 1;
+        self.sum += value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.quantiles.add(value);
     }
-    sorted[idx - 1]
+}
+
+/// Start of the wall-clock aligned `window` containing `timestamp_ms`.
+fn window_floor(timestamp_ms: i64, window: Duration) -> i64 {
+    let window_ms = window.as_millis() as i64;
+    timestamp_ms.div_euclid(window_ms) * window_ms
+}
+
+/// Start of the last complete `window` before `now_ms`.
+fn previous_window_start(now_ms: i64, window: Duration) -> i64 {
+    window_floor(now_ms, window) - window.as_millis() as i64
 }
@@ -1,35 +1,176 @@
 use anyhow::Result;
-use std::time::Duration;
+use std::collections::HashMap;
+use std::collections::hash_map::{Entry, RandomState};
+use std::hash::BuildHasher;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 use tokio::sync::watch;
-use tokio::time::{interval, timeout};
+use tokio::time::{sleep, timeout};
 
-use phenome_domain::{MetricSample, MetricsQuery};
+use phenome_domain::{
+    ClusterId, MetricFilter, MetricSample, MetricType, MetricsQuery, ResourceType,
+};
 
 use crate::cluster_manager::ClusterManager;
+use crate::storage::StoragePort;
+use crate::write_queue::WriteQueue;
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct MetricsCollector {
     cluster_manager: ClusterManager,
     interval: Duration,
+    storage: Option<Arc<dyn StoragePort>>,
+    write_queue: Option<WriteQueue>,
+    metric_types: Vec<MetricType>,
+    resource_types: Vec<ResourceType>,
+    filters: HashMap<ClusterId, MetricFilter>,
+    /// Fraction of `interval` each sleep is randomly moved by.
+    jitter: f64,
+    /// Interval actually slept, in ms; stretched past `interval` while polls
+    /// take longer than it.
+    effective_interval_ms: Arc<AtomicU64>,
 }
 
 const MAX_COLLECTION_DURATION: Duration = Duration::from_secs(30);
 
+/// How one cluster fared in a collection pass.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClusterOutcome {
+    /// Number of samples the cluster returned.
+    Collected(usize),
+    /// The cluster answered but reported no samples.
+    Empty,
+    /// The cluster could not be queried.
+    Failed(String),
+}
+
+/// Samples from one collection pass with the outcome for every cluster queried.
+#[derive(Debug, Clone, Default)]
+pub struct CollectionResult {
+    pub samples: Vec<MetricSample>,
+    pub clusters: HashMap<ClusterId, ClusterOutcome>,
+}
+
+impl CollectionResult {
+    /// Clusters that could not be queried, with the reason.
+    pub fn failures(&self) -> impl Iterator<Item = (&ClusterId, &str)> {
+        self.clusters
+            .iter()
+            .filter_map(|(cluster_id, outcome)| match outcome {
+                ClusterOutcome::Failed(reason) => Some((cluster_id, reason.as_str())),
+                _ => None,
+            })
+    }
+
+    /// Clusters that answered without any samples.
+    pub fn silent_clusters(&self) -> impl Iterator<Item = &ClusterId> {
+        self.clusters
+            .iter()
+            .filter(|(_, outcome)| **outcome == ClusterOutcome::Empty)
+            .map(|(cluster_id, _)| cluster_id)
+    }
+
+    /// Merge one cluster's query result; a failure outweighs samples from
+    /// other queries of the same pass, which outweigh an empty answer.
+    fn record(&mut self, cluster_id: ClusterId, result: Result<Vec<MetricSample>>) {
+        let outcome = match result {
+            Ok(batch) if batch.is_empty() => ClusterOutcome::Empty,
+            Ok(batch) => {
+                let count = batch.len();
+                self.samples.extend(batch);
+                ClusterOutcome::Collected(count)
+            }
+            Err(err) => ClusterOutcome::Failed(format!("{err:#}")),
+        };
+        match self.clusters.entry(cluster_id) {
+            Entry::Vacant(entry) => {
+                entry.insert(outcome);
+            }
+            Entry::Occupied(mut entry) => {
+                let merged = match (entry.get(), outcome) {
+                    (ClusterOutcome::Failed(_), _) | (_, ClusterOutcome::Empty) => return,
+                    (_, failed @ ClusterOutcome::Failed(_)) => failed,
+                    (ClusterOutcome::Collected(count), ClusterOutcome::Collected(more)) => {
+                        ClusterOutcome::Collected(count + more)
+                    }
+                    (ClusterOutcome::Empty, collected) => collected,
+                };
+                entry.insert(merged);
+            }
+        }
+    }
+}
+
 impl MetricsCollector {
     pub fn new(cluster_manager: ClusterManager, interval: Duration) -> Self {
         Self {
             cluster_manager,
             interval,
+            storage: None,
+            write_queue: None,
+            metric_types: Vec::new(),
+            resource_types: Vec::new(),
+            filters: HashMap::new(),
+            jitter: 0.0,
+            effective_interval_ms: Arc::new(AtomicU64::new(interval.as_millis() as u64)),
         }
     }
 
-    pub async fn collect_once(&self) -> Result<Vec<MetricSample>> {
-        let query = MetricsQuery::default();
-        let results = self.cluster_manager.query_all_clusters(query).await;
-        Ok(results
-            .into_iter()
-            .flat_map(|(_, result)| result.unwrap_or_default())
-            .collect())
+    /// Persist every collected batch to `storage`.
+    pub fn with_storage(mut self, storage: Arc<dyn StoragePort>) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    /// Hand collected batches to the storage writer behind `queue` instead of
+    /// storing them inline; takes precedence over `with_storage`.
+    pub fn with_write_queue(mut self, queue: WriteQueue) -> Self {
+        self.write_queue = Some(queue);
+        self
+    }
+
+    /// Collect only these metric types; empty collects every type.
+    pub fn with_metric_types(mut self, metric_types: Vec<MetricType>) -> Self {
+        self.metric_types = metric_types;
+        self
+    }
+
+    /// Collect only these resource types; empty collects every type.
+    pub fn with_resource_types(mut self, resource_types: Vec<ResourceType>) -> Self {
+        self.resource_types = resource_types;
+        self
+    }
+
+    /// Drop samples of a cluster its filter doesn't admit, before they are
+    /// counted or stored.
+    pub fn with_filters(mut self, filters: HashMap<ClusterId, MetricFilter>) -> Self {
+        self.filters = filters;
+        self
+    }
+
+    /// Move each sleep between polls randomly by up to `percent` of the
+    /// interval, and start polling after a random offset within one interval,
+    /// so clusters sharing an interval aren't all scraped on the same tick.
+    pub fn with_jitter(mut self, percent: u8) -> Self {
+        self.jitter = f64::from(percent.min(100)) / 100.0;
+        self
+    }
+
+    pub async fn collect_once(&self) -> Result<CollectionResult> {
+        let result = self.query_scoped(MetricsQuery::default()).await?;
+        self.store(&result.samples).await?;
+        Ok(result)
+    }
+
+    /// Hand a collected batch to the write queue or storage.
+    async fn store(&self, samples: &[MetricSample]) -> Result<()> {
+        if let Some(queue) = &self.write_queue {
+            queue.push(samples.to_vec()).await;
+        } else if let Some(storage) = &self.storage {
+            storage.insert_metrics(samples.to_vec()).await?;
+        }
+        Ok(())
     }
 
     pub async fn run_polling_loop(&self) -> Result<()> {
@@ -41,7 +182,11 @@ impl MetricsCollector {
         &self,
         mut shutdown: watch::Receiver<bool>,
     ) -> Result<()> {
-        let mut tick = interval(self.interval);
+        let mut delay = if self.jitter > 0.0 {
+            self.interval.mul_f64(random_unit())
+        } else {
+            Duration::ZERO
+        };
         loop {
             tokio::select! {
                 result = shutdown.changed() => {
@@ -49,24 +194,152 @@ impl MetricsCollector {
                         break;
                     }
                 }
-                _ = tick.tick() => {
-                    match timeout(MAX_COLLECTION_DURATION, self.collect_once()).await {
-                        Ok(Ok(_)) => {}
-                        Ok(Err(err)) => {
-                            tracing::error!("Metrics poll failed: {}", err);
-                        }
-                        Err(_) => {
-                            tracing::warn!(
-                                "Metrics poll exceeded {:?} budget",
-                                MAX_COLLECTION_DURATION
-                            );
-                        }
-                    }
+                _ = sleep(delay) => {
+                    self.poll().await;
+                    delay = self.next_delay();
                 }
             }
         }
         Ok(())
     }
+
+    /// Interval between polls, stretched while polls overrun the configured
+    /// interval.
+    pub fn effective_interval(&self) -> Duration {
+        Duration::from_millis(self.effective_interval_ms.load(Ordering::Relaxed))
+    }
+
+    /// One timed collection pass, adapting the interval to how long it took.
+    /// Only the queries count against the budget: a batch waiting for room
+    /// in a `Block` write queue must not be cancelled and lost.
+    pub(crate) async fn poll(&self) {
+        let started = Instant::now();
+        let collected = timeout(
+            MAX_COLLECTION_DURATION,
+            self.query_scoped(MetricsQuery::default()),
+        )
+        .await;
+        match collected {
+            Ok(Ok(result)) => {
+                for (cluster_id, reason) in result.failures() {
+                    tracing::warn!(
+                        "Metrics collection failed for cluster {}: {}",
+                        cluster_id,
+                        reason
+                    );
+                }
+                if let Err(err) = self.store(&result.samples).await {
+                    tracing::error!("Failed to store metrics batch: {}", err);
+                }
+            }
+            Ok(Err(err)) => {
+                tracing::error!("Metrics poll failed: {}", err);
+            }
+            Err(_) => {
+                tracing::warn!("Metrics poll exceeded {:?} budget", MAX_COLLECTION_DURATION);
+            }
+        }
+        self.adapt_interval(started.elapsed());
+    }
+
+    /// Sleep before the next poll: the effective interval moved by up to the
+    /// jitter either way.
+    pub(crate) fn next_delay(&self) -> Duration {
+        let spread = self.jitter * (2.0 * random_unit() - 1.0);
+        self.effective_interval().mul_f64(1.0 + spread)
+    }
+
+    /// Stretch the effective interval to leave headroom over a poll that
+    /// overran it, or shrink it back toward the configured interval once
+    /// polls fit again.
+    fn adapt_interval(&self, elapsed: Duration) {
+        let current = self.effective_interval();
+        let headroom = elapsed.mul_f64(1.5);
+        let next = if elapsed > current {
+            tracing::warn!(
+                "Metrics poll took {:?}, longer than the {:?} interval; polling every {:?}",
+                elapsed,
+                current,
+                headroom
+            );
+            headroom
+        } else if current > self.interval {
+            (current / 2).max(headroom).clamp(self.interval, current)
+        } else {
+            return;
+        };
+        self.effective_interval_ms
+            .store(next.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// Narrow `query` to the configured metric and resource types, one query
+    /// per resource type. Empty when it only asks for excluded types.
+    pub(crate) fn scoped_queries(&self, mut query: MetricsQuery) -> Vec<MetricsQuery> {
+        if !self.metric_types.is_empty() {
+            if query.metric_types.is_empty() {
+                query.metric_types = self.metric_types.clone();
+            } else {
+                query
+                    .metric_types
+                    .retain(|metric_type| self.metric_types.contains(metric_type));
+                if query.metric_types.is_empty() {
+                    return Vec::new();
+                }
+            }
+        }
+        match query.resource_type {
+            _ if self.resource_types.is_empty() => vec![query],
+            Some(resource_type) if self.resource_types.contains(&resource_type) => vec![query],
+            Some(_) => Vec::new(),
+            None => self
+                .resource_types
+                .iter()
+                .map(|resource_type| MetricsQuery {
+                    resource_type: Some(*resource_type),
+                    ..query.clone()
+                })
+                .collect(),
+        }
+    }
+
+    fn filtered(&self, cluster_id: &ClusterId, mut batch: Vec<MetricSample>) -> Vec<MetricSample> {
+        if let Some(filter) = self.filters.get(cluster_id) {
+            batch.retain(|sample| filter.admits(sample));
+        }
+        batch
+    }
+
+    /// A query naming a cluster fails with it; otherwise each cluster's
+    /// failure is recorded in the result.
+    async fn query_scoped(&self, query: MetricsQuery) -> Result<CollectionResult> {
+        let mut result = CollectionResult::default();
+        for query in self.scoped_queries(query) {
+            match query.cluster_id.clone() {
+                Some(cluster_id) => {
+                    let batch = self
+                        .cluster_manager
+                        .query_metrics(&cluster_id, query)
+                        .await?;
+                    let batch = self.filtered(&cluster_id, batch);
+                    result.record(cluster_id, Ok(batch));
+                }
+                None => {
+                    for (cluster_id, batch) in self.cluster_manager.query_all_clusters(query).await
+                    {
+                        let batch = batch.map(|batch| self.filtered(&cluster_id, batch));
+                        result.record(cluster_id, batch);
+                    }
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Uniform in `[0, 1)`; `RandomState` is randomly keyed, which is enough to
+/// spread polls without pulling in an RNG.
+fn random_unit() -> f64 {
+    (RandomState::new().hash_one(()) >> 11) as f64 / (1u64 << 53) as f64
 }
 
 use async_trait::async_trait;
@@ -78,20 +351,14 @@ impl MetricsPort for MetricsCollector {
         &self,
         cluster_id: phenome_domain::ClusterId,
     ) -> Result<Vec<MetricSample>> {
-        let query = MetricsQuery::default();
-        self.cluster_manager.query_metrics(&cluster_id, query).await
+        let query = MetricsQuery {
+            cluster_id: Some(cluster_id),
+            ..MetricsQuery::default()
+        };
+        Ok(self.query_scoped(query).await?.samples)
     }
 
     async fn query_metrics(&self, query: MetricsQuery) -> Result<Vec<MetricSample>> {
-        let cluster_id = query.cluster_id.clone();
-        if let Some(cid) = cluster_id {
-            self.cluster_manager.query_metrics(&cid, query).await
-        } else {
-            let results = self.cluster_manager.query_all_clusters(query).await;
-            Ok(results
-                .into_iter()
-                .flat_map(|(_, result)| result.unwrap_or_default())
-                .collect())
-        }
+        Ok(self.query_scoped(query).await?.samples)
     }
 }
@@ -1,3 +1,9 @@
 pub mod aggregator;
 pub mod cache;
 pub mod metrics_collector;
+mod quantiles;
+pub mod retention;
+pub mod write_queue;
+
+#[cfg(test)]
+mod tests;
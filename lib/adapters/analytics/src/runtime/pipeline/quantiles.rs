@@ -0,0 +1,172 @@
+//! Per-series quantile accumulators used by the hourly rollup.
+//!
+//! The default accumulator keeps every value and reports exact
+//! nearest-rank percentiles. With the `tdigest` feature a merging t-digest
+//! is used instead, bounding memory per series at the cost of a small,
+//! tail-biased approximation error.
+
+#[cfg(not(feature = "tdigest"))]
+pub(crate) type Quantiles = ExactQuantiles;
+#[cfg(feature = "tdigest")]
+pub(crate) type Quantiles = TDigest;
+
+/// Exact percentiles over every value seen.
+#[cfg(not(feature = "tdigest"))]
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ExactQuantiles {
+    values: Vec<f64>,
+    sorted: bool,
+}
+
+#[cfg(not(feature = "tdigest"))]
+impl ExactQuantiles {
+    pub(crate) fn add(&mut self, value: f64) {
+        self.values.push(value);
+        self.sorted = false;
+    }
+
+    /// Nearest-rank percentile for `q` in `[0, 1]`; 0.0 when empty.
+    pub(crate) fn quantile(&mut self, q: f64) -> f64 {
+        if !self.sorted {
+            self.values
+                .sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+            self.sorted = true;
+        }
+        percentile(&self.values, q)
+    }
+}
+
+#[cfg(not(feature = "tdigest"))]
+fn percentile(sorted: &[f64], pct: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let idx = (sorted.len() as f64 * pct).ceil() as usize;
+    if idx == 0 {
+        return sorted[0];
+    }
+    if idx >= sorted.len() {
+        return sorted[sorted.len() - 1];
+    }
+    sorted[idx - 1]
+}
+
+/// Merging t-digest (Dunning) with a `q(1 - q)` size bound, so centroids
+/// near the tails stay small and p95/p99 remain accurate.
+#[cfg(feature = "tdigest")]
+#[derive(Debug, Clone)]
+pub(crate) struct TDigest {
+    compression: f64,
+    centroids: Vec<Centroid>,
+    buffer: Vec<f64>,
+    count: f64,
+    min: f64,
+    max: f64,
+}
+
+#[cfg(feature = "tdigest")]
+#[derive(Debug, Clone, Copy)]
+struct Centroid {
+    mean: f64,
+    weight: f64,
+}
+
+#[cfg(feature = "tdigest")]
+impl Default for TDigest {
+    fn default() -> Self {
+        Self::new(100.0)
+    }
+}
+
+#[cfg(feature = "tdigest")]
+impl TDigest {
+    pub(crate) fn new(compression: f64) -> Self {
+        Self {
+            compression,
+            centroids: Vec::new(),
+            buffer: Vec::new(),
+            count: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    pub(crate) fn add(&mut self, value: f64) {
+        self.buffer.push(value);
+        self.count += 1.0;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        if self.buffer.len() >= (self.compression as usize) * 5 {
+            self.compress();
+        }
+    }
+
+    /// Interpolated estimate for `q` in `[0, 1]`; 0.0 when empty.
+    pub(crate) fn quantile(&mut self, q: f64) -> f64 {
+        self.compress();
+        let Some(first) = self.centroids.first() else {
+            return 0.0;
+        };
+        if self.centroids.len() == 1 {
+            return first.mean;
+        }
+        let target = q.clamp(0.0, 1.0) * self.count;
+        let mut cumulative = 0.0;
+        let mut prev: Option<(f64, f64)> = None;
+        for centroid in &self.centroids {
+            let center = cumulative + centroid.weight / 2.0;
+            if target <= center {
+                let value = match prev {
+                    None => self.min + (centroid.mean - self.min) * (target / center.max(1.0)),
+                    Some((prev_center, prev_mean)) => {
+                        let span = center - prev_center;
+                        prev_mean + (centroid.mean - prev_mean) * (target - prev_center) / span
+                    }
+                };
+                return value.clamp(self.min, self.max);
+            }
+            prev = Some((center, centroid.mean));
+            cumulative += centroid.weight;
+        }
+        let (last_center, last_mean) = prev.unwrap_or((0.0, self.max));
+        let tail = (self.count - last_center).max(1.0);
+        (last_mean + (self.max - last_mean) * (target - last_center) / tail)
+            .clamp(self.min, self.max)
+    }
+
+    fn compress(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+        let mut all: Vec<Centroid> = self.centroids.drain(..).collect();
+        all.extend(
+            self.buffer
+                .drain(..)
+                .map(|mean| Centroid { mean, weight: 1.0 }),
+        );
+        all.sort_by(|a, b| {
+            a.mean
+                .partial_cmp(&b.mean)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let mut merged = Vec::with_capacity(all.len());
+        let mut current = all[0];
+        let mut weight_before = 0.0;
+        for next in all.into_iter().skip(1) {
+            let q = (weight_before + current.weight + next.weight / 2.0) / self.count;
+            let limit = (4.0 * self.count * q * (1.0 - q) / self.compression).max(1.0);
+            if current.weight + next.weight <= limit {
+                let weight = current.weight + next.weight;
+                current.mean += (next.mean - current.mean) * next.weight / weight;
+                current.weight = weight;
+            } else {
+                weight_before += current.weight;
+                merged.push(current);
+                current = next;
+            }
+        }
+        merged.push(current);
+        self.centroids = merged;
+    }
+}
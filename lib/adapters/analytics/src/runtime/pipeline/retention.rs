@@ -0,0 +1,46 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::interval;
+
+use crate::storage::StoragePort;
+
+const PRUNE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Daily prune of expired rows, reclaiming the freed space where the backend
+/// can.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RetentionTask;
+
+impl RetentionTask {
+    pub async fn run_daily_with_shutdown(
+        storage: Arc<dyn StoragePort>,
+        mut shutdown: watch::Receiver<bool>,
+    ) {
+        let mut tick = interval(PRUNE_INTERVAL);
+        loop {
+            tokio::select! {
+                result = shutdown.changed() => {
+                    if result.is_err() || *shutdown.borrow() {
+                        break;
+                    }
+                }
+                _ = tick.tick() => {
+                    match storage.cleanup_retention().await {
+                        Ok(stats) => {
+                            tracing::info!(
+                                raw_rows = stats.raw_rows,
+                                aggregated_rows = stats.aggregated_rows,
+                                daily_rows = stats.daily_rows,
+                                "Pruned expired analytics rows"
+                            );
+                        }
+                        Err(err) => {
+                            tracing::error!("Retention prune failed: {}", err);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
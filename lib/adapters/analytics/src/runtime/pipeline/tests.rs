@@ -0,0 +1,581 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use http::{Request, Response};
+use kube::client::Body;
+
+use phenome_domain::{
+    AggregatedMetric, AggregatedQuery, MetricFilter, MetricSample, MetricType, MetricsQuery,
+    ResourceType, WriteOverflowPolicy,
+};
+
+use crate::ClusterManager;
+use crate::aggregator::Aggregator;
+use crate::metrics_collector::{ClusterOutcome, MetricsCollector};
+use crate::storage::port::StoragePort;
+use crate::storage::sqlite::SqliteStorage;
+use crate::write_queue::WriteQueue;
+
+const HOUR_MS: i64 = 60 * 60 * 1000;
+const DAY_MS: i64 = 24 * HOUR_MS;
+
+/// Values 1..=1000 spread over one hour in a scrambled order.
+fn uniform_samples(window_start: i64) -> Vec<MetricSample> {
+    (0..1000)
+        .map(|i| MetricSample {
+            cluster_id: "cluster-1".to_string(),
+            resource_type: ResourceType::Pod,
+            resource_id: format!("pod-{}", i % 4),
+            metric_type: MetricType::CpuUsage,
+            timestamp: window_start + i * 3_600,
+            value: ((i * 7_919) % 1000 + 1) as f64,
+            unit: "cores".to_string(),
+        })
+        .collect()
+}
+
+#[test]
+fn hourly_rollup_p95_is_within_tolerance() {
+    let aggregated = Aggregator::new()
+        .aggregate_window(&uniform_samples(0), Duration::from_secs(3600))
+        .unwrap();
+    assert_eq!(aggregated.len(), 1);
+    let metric = &aggregated[0];
+
+    assert_eq!(metric.count, 1000);
+    assert_eq!((metric.min, metric.max), (1.0, 1000.0));
+    assert!((metric.avg - 500.5).abs() < 1e-9);
+    // Within 1% of the value range of the exact percentiles.
+    assert!((metric.p50 - 500.0).abs() <= 10.0, "p50 = {}", metric.p50);
+    assert!((metric.p95 - 950.0).abs() <= 10.0, "p95 = {}", metric.p95);
+    assert!((metric.p99 - 990.0).abs() <= 10.0, "p99 = {}", metric.p99);
+}
+
+#[test]
+fn samples_across_a_boundary_land_in_aligned_buckets() {
+    let window = Duration::from_secs(5 * 60);
+    let boundary = 1_700_000_100_000; // a multiple of five minutes
+    let sample = |timestamp: i64, value: f64| MetricSample {
+        cluster_id: "cluster-1".to_string(),
+        resource_type: ResourceType::Pod,
+        resource_id: "pod-a".to_string(),
+        metric_type: MetricType::CpuUsage,
+        timestamp,
+        value,
+        unit: "cores".to_string(),
+    };
+    let samples = vec![
+        sample(boundary - 90_000, 1.0),
+        sample(boundary - 1, 3.0),
+        sample(boundary, 10.0),
+        sample(boundary + 299_999, 20.0),
+    ];
+
+    let mut buckets = Aggregator::new()
+        .aggregate_window(&samples, window)
+        .unwrap();
+    buckets.sort_by_key(|metric| metric.window_start);
+
+    let summary: Vec<_> = buckets
+        .iter()
+        .map(|metric| (metric.window_start, metric.count, metric.min, metric.max))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![(boundary - 300_000, 2, 1.0, 3.0), (boundary, 2, 10.0, 20.0)]
+    );
+}
+
+#[tokio::test]
+async fn rollup_window_stores_percentiles_once() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("analytics.db");
+    let storage = SqliteStorage::new(db_path.to_string_lossy().to_string()).unwrap();
+    let window_start = 10 * HOUR_MS;
+    let mut samples = uniform_samples(window_start);
+    // Outside the window; must not affect the rollup.
+    let mut late = samples[0].clone();
+    late.timestamp = window_start + HOUR_MS;
+    late.value = 1_000_000.0;
+    samples.push(late);
+    storage.insert_metrics(samples).await.unwrap();
+
+    let aggregator = Aggregator::new();
+    let window = Duration::from_secs(3600);
+    assert_eq!(
+        aggregator
+            .rollup_window(&storage, window_start, window)
+            .await
+            .unwrap(),
+        1
+    );
+    assert_eq!(
+        aggregator
+            .rollup_window(&storage, window_start, window)
+            .await
+            .unwrap(),
+        0
+    );
+
+    let stored = storage
+        .query_aggregated(AggregatedQuery {
+            cluster_id: Some("cluster-1".to_string()),
+            resource_type: None,
+            metric_types: vec![MetricType::CpuUsage],
+            window_duration: window,
+            time_range: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].window_start, window_start);
+    assert_eq!(stored[0].max, 1000.0);
+    assert!((stored[0].p95 - 950.0).abs() <= 10.0);
+}
+
+#[tokio::test]
+async fn backfill_fills_only_unprocessed_windows() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("analytics.db");
+    let storage = SqliteStorage::new(db_path.to_string_lossy().to_string()).unwrap();
+    let start = 20 * HOUR_MS;
+    let mut samples = uniform_samples(start);
+    samples.extend(uniform_samples(start + HOUR_MS));
+    samples.extend(uniform_samples(start + 2 * HOUR_MS));
+    storage.insert_metrics(samples).await.unwrap();
+
+    let aggregator = Aggregator::new();
+    let window = Duration::from_secs(3600);
+    aggregator
+        .rollup_window(&storage, start + HOUR_MS, window)
+        .await
+        .unwrap();
+
+    let end = start + 3 * HOUR_MS;
+    assert_eq!(
+        aggregator
+            .backfill(&storage, start, end, window)
+            .await
+            .unwrap(),
+        2
+    );
+    assert_eq!(
+        aggregator
+            .backfill(&storage, start, end, window)
+            .await
+            .unwrap(),
+        0
+    );
+    assert_eq!(
+        storage.last_processed_window(window).await.unwrap(),
+        Some(start + 2 * HOUR_MS)
+    );
+
+    let mut starts: Vec<i64> = storage
+        .query_aggregated(AggregatedQuery {
+            cluster_id: None,
+            resource_type: None,
+            metric_types: Vec::new(),
+            window_duration: window,
+            time_range: None,
+        })
+        .await
+        .unwrap()
+        .iter()
+        .map(|metric| metric.window_start)
+        .collect();
+    starts.sort();
+    assert_eq!(starts, vec![start, start + HOUR_MS, start + 2 * HOUR_MS]);
+}
+
+fn hourly(window_start: i64, count: u64, min: f64, max: f64, avg: f64) -> AggregatedMetric {
+    AggregatedMetric {
+        cluster_id: "cluster-1".to_string(),
+        resource_type: ResourceType::Pod,
+        metric_type: MetricType::CpuUsage,
+        window_start,
+        window_duration: Duration::from_secs(3600),
+        count,
+        sum: avg * count as f64,
+        min,
+        max,
+        avg,
+        p50: avg,
+        p95: max,
+        p99: max,
+    }
+}
+
+#[tokio::test]
+async fn rollup_day_is_idempotent() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("analytics.db");
+    let storage = SqliteStorage::new(db_path.to_string_lossy().to_string()).unwrap();
+    let day_start = 3 * DAY_MS;
+    storage
+        .insert_aggregated(vec![
+            hourly(day_start, 10, 1.0, 4.0, 2.0),
+            hourly(day_start + 12 * HOUR_MS, 30, 0.5, 9.0, 6.0),
+            hourly(day_start + 23 * HOUR_MS, 60, 2.0, 3.0, 2.5),
+            // Next day; must not be rolled into this one.
+            hourly(day_start + DAY_MS, 5, 100.0, 200.0, 150.0),
+        ])
+        .await
+        .unwrap();
+
+    let aggregator = Aggregator::new();
+    let window = Duration::from_secs(3600);
+    assert_eq!(
+        aggregator
+            .rollup_day(&storage, day_start, window)
+            .await
+            .unwrap(),
+        1
+    );
+    assert_eq!(
+        aggregator
+            .rollup_day(&storage, day_start, window)
+            .await
+            .unwrap(),
+        1
+    );
+
+    let daily = storage
+        .query_daily(AggregatedQuery {
+            cluster_id: Some("cluster-1".to_string()),
+            resource_type: None,
+            metric_types: vec![MetricType::CpuUsage],
+            window_duration: Duration::from_secs(24 * 3600),
+            time_range: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(daily.len(), 1);
+    let day = &daily[0];
+    assert_eq!(day.day_start, day_start);
+    assert_eq!(day.count, 100);
+    assert_eq!((day.min, day.max), (0.5, 9.0));
+    // (10 * 2.0 + 30 * 6.0 + 60 * 2.5) / 100
+    assert!((day.avg - 3.5).abs() < 1e-9, "avg = {}", day.avg);
+}
+
+fn metrics_list(kind: &str, items: serde_json::Value) -> Response<Body> {
+    let list = serde_json::json!({
+        "apiVersion": "metrics.k8s.io/v1beta1",
+        "kind": kind,
+        "metadata": {},
+        "items": items,
+    });
+    Response::builder()
+        .body(Body::from(serde_json::to_vec(&list).unwrap()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn collector_limited_to_cpu_drops_other_metrics() {
+    let (mock, handle) = tower_test::mock::pair::<Request<Body>, Response<Body>>();
+    let client = kube::Client::new(mock, "default");
+    let server = tokio::spawn(async move {
+        let mut handle = std::pin::pin!(handle);
+        let mut paths = Vec::new();
+        while let Some((request, send)) = handle.next_request().await {
+            let path = request.uri().path().to_string();
+            let response = match path.as_str() {
+                "/apis/metrics.k8s.io/v1beta1/nodes" => metrics_list(
+                    "NodeMetricsList",
+                    serde_json::json!([{
+                        "metadata": { "name": "node-1" },
+                        "usage": { "cpu": "500m", "memory": "1Gi" }
+                    }]),
+                ),
+                other => panic!("unexpected request to {other}"),
+            };
+            send.send_response(response);
+            paths.push(path);
+        }
+        paths
+    });
+
+    let cluster_manager = ClusterManager::new();
+    cluster_manager
+        .add_cluster_with_client("kind-dev".to_string(), client)
+        .await
+        .unwrap();
+    let collector = MetricsCollector::new(cluster_manager, Duration::from_secs(60))
+        .with_metric_types(vec![MetricType::CpuUsage])
+        .with_resource_types(vec![ResourceType::Node]);
+
+    let queries = collector.scoped_queries(MetricsQuery::default());
+    assert_eq!(queries.len(), 1);
+    assert_eq!(queries[0].metric_types, [MetricType::CpuUsage]);
+    assert_eq!(queries[0].resource_type, Some(ResourceType::Node));
+    let memory_only = MetricsQuery {
+        metric_types: vec![MetricType::MemoryUsage],
+        ..MetricsQuery::default()
+    };
+    assert!(collector.scoped_queries(memory_only).is_empty());
+
+    let samples = collector.collect_once().await.unwrap().samples;
+    drop(collector);
+    assert_eq!(samples.len(), 1);
+    assert_eq!(samples[0].metric_type, MetricType::CpuUsage);
+    assert_eq!(samples[0].resource_id, "node-1");
+    assert!((samples[0].value - 0.5).abs() < 1e-9);
+
+    let paths = server.await.unwrap();
+    assert_eq!(paths, ["/apis/metrics.k8s.io/v1beta1/nodes"]);
+}
+
+/// Cluster whose metrics API lists `nodes` and no pods, or fails every
+/// request when `nodes` is `None`.
+fn metrics_server(nodes: Option<serde_json::Value>) -> kube::Client {
+    let (mock, handle) = tower_test::mock::pair::<Request<Body>, Response<Body>>();
+    tokio::spawn(async move {
+        let mut handle = std::pin::pin!(handle);
+        while let Some((request, send)) = handle.next_request().await {
+            let response = match (&nodes, request.uri().path()) {
+                (None, _) => {
+                    let status = serde_json::json!({
+                        "kind": "Status",
+                        "apiVersion": "v1",
+                        "metadata": {},
+                        "status": "Failure",
+                        "message": "the server is currently unable to handle the request",
+                        "reason": "ServiceUnavailable",
+                        "code": 503
+                    });
+                    Response::builder()
+                        .status(503)
+                        .body(Body::from(serde_json::to_vec(&status).unwrap()))
+                        .unwrap()
+                }
+                (Some(nodes), "/apis/metrics.k8s.io/v1beta1/nodes") => {
+                    metrics_list("NodeMetricsList", nodes.clone())
+                }
+                (Some(_), _) => metrics_list("PodMetricsList", serde_json::json!([])),
+            };
+            send.send_response(response);
+        }
+    });
+    kube::Client::new(mock, "default")
+}
+
+#[tokio::test]
+async fn collection_result_separates_empty_and_failed_clusters() {
+    let cluster_manager = ClusterManager::new();
+    let node = serde_json::json!([{
+        "metadata": { "name": "node-1" },
+        "usage": { "cpu": "500m", "memory": "1Gi" }
+    }]);
+    for (context, nodes) in [
+        ("busy", Some(node)),
+        ("idle", Some(serde_json::json!([]))),
+        ("broken", None),
+    ] {
+        cluster_manager
+            .add_cluster_with_client(context.to_string(), metrics_server(nodes))
+            .await
+            .unwrap();
+    }
+    let collector = MetricsCollector::new(cluster_manager, Duration::from_secs(60));
+
+    let result = collector.collect_once().await.unwrap();
+    assert_eq!(result.samples.len(), 2);
+    assert!(
+        result
+            .samples
+            .iter()
+            .all(|sample| sample.cluster_id == "busy")
+    );
+    assert_eq!(result.clusters.len(), 3);
+    assert_eq!(result.clusters["busy"], ClusterOutcome::Collected(2));
+    assert_eq!(result.clusters["idle"], ClusterOutcome::Empty);
+    assert!(matches!(
+        &result.clusters["broken"],
+        ClusterOutcome::Failed(reason) if reason.contains("failed to fetch node metrics")
+    ));
+
+    let silent: Vec<_> = result.silent_clusters().collect();
+    assert_eq!(silent, ["idle"]);
+    let failures: Vec<_> = result
+        .failures()
+        .map(|(cluster_id, _)| cluster_id)
+        .collect();
+    assert_eq!(failures, ["broken"]);
+}
+
+#[test]
+fn jittered_poll_delays_vary_within_the_band() {
+    let collector =
+        MetricsCollector::new(ClusterManager::new(), Duration::from_secs(10)).with_jitter(20);
+
+    let delays: Vec<_> = (0..50).map(|_| collector.next_delay()).collect();
+    assert!(
+        delays
+            .iter()
+            .all(|delay| (Duration::from_secs(8)..=Duration::from_secs(12)).contains(delay))
+    );
+    assert!(delays.iter().any(|delay| *delay != delays[0]));
+
+    let steady = MetricsCollector::new(ClusterManager::new(), Duration::from_secs(10));
+    assert_eq!(steady.next_delay(), Duration::from_secs(10));
+}
+
+/// Cluster whose metrics API answers empty lists after `delay`.
+fn slow_metrics_server(delay: Duration) -> kube::Client {
+    let (mock, handle) = tower_test::mock::pair::<Request<Body>, Response<Body>>();
+    tokio::spawn(async move {
+        let mut handle = std::pin::pin!(handle);
+        while let Some((_, send)) = handle.next_request().await {
+            tokio::time::sleep(delay).await;
+            send.send_response(metrics_list("NodeMetricsList", serde_json::json!([])));
+        }
+    });
+    kube::Client::new(mock, "default")
+}
+
+#[tokio::test]
+async fn overrunning_poll_stretches_the_next_sleep() {
+    let cluster_manager = ClusterManager::new();
+    cluster_manager
+        .add_cluster_with_client(
+            "slow".to_string(),
+            slow_metrics_server(Duration::from_millis(100)),
+        )
+        .await
+        .unwrap();
+    let collector = MetricsCollector::new(cluster_manager, Duration::from_millis(20));
+    assert_eq!(collector.next_delay(), Duration::from_millis(20));
+
+    collector.poll().await;
+
+    assert!(collector.effective_interval() > Duration::from_millis(100));
+    assert_eq!(collector.next_delay(), collector.effective_interval());
+}
+
+#[tokio::test]
+async fn filtered_out_samples_are_never_stored() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("analytics.db");
+    let storage = Arc::new(SqliteStorage::new(db_path.to_string_lossy().to_string()).unwrap());
+    let cluster_manager = ClusterManager::new();
+    for context in ["filtered", "unfiltered"] {
+        let node = serde_json::json!([{
+            "metadata": { "name": "node-1" },
+            "usage": { "cpu": "500m", "memory": "1Gi" }
+        }]);
+        cluster_manager
+            .add_cluster_with_client(context.to_string(), metrics_server(Some(node)))
+            .await
+            .unwrap();
+    }
+    let filter = MetricFilter {
+        deny_metric_types: vec![MetricType::MemoryUsage],
+        ..MetricFilter::default()
+    };
+    let collector = MetricsCollector::new(cluster_manager, Duration::from_secs(60))
+        .with_storage(storage.clone())
+        .with_filters(HashMap::from([("filtered".to_string(), filter)]));
+
+    let result = collector.collect_once().await.unwrap();
+    assert_eq!(result.clusters["filtered"], ClusterOutcome::Collected(1));
+    assert_eq!(result.clusters["unfiltered"], ClusterOutcome::Collected(2));
+
+    let mut stored: Vec<_> = storage
+        .query_metrics(MetricsQuery::default())
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|sample| (sample.cluster_id, sample.metric_type))
+        .collect();
+    stored.sort_by_key(|(cluster_id, metric_type)| (cluster_id.clone(), *metric_type as u8));
+    assert_eq!(
+        stored,
+        [
+            ("filtered".to_string(), MetricType::CpuUsage),
+            ("unfiltered".to_string(), MetricType::CpuUsage),
+            ("unfiltered".to_string(), MetricType::MemoryUsage),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn stalled_writer_drops_oldest_batches_without_blocking_collection() {
+    let cluster_manager = ClusterManager::new();
+    let node = serde_json::json!([{
+        "metadata": { "name": "node-1" },
+        "usage": { "cpu": "500m", "memory": "1Gi" }
+    }]);
+    cluster_manager
+        .add_cluster_with_client("busy".to_string(), metrics_server(Some(node)))
+        .await
+        .unwrap();
+    // Nothing drains the queue, as if the writer were stuck on a slow insert.
+    let queue = WriteQueue::new(1, WriteOverflowPolicy::DropOldest);
+    let collector = MetricsCollector::new(cluster_manager, Duration::from_secs(60))
+        .with_write_queue(queue.clone());
+
+    tokio::time::timeout(Duration::from_secs(1), async {
+        for _ in 0..3 {
+            collector.collect_once().await.unwrap();
+        }
+    })
+    .await
+    .expect("collection blocked on the full write queue");
+
+    assert_eq!(queue.dropped(), 2);
+    assert_eq!(queue.len(), 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn stalled_writer_under_block_holds_the_poll_past_its_budget() {
+    let cluster_manager = ClusterManager::new();
+    let node = serde_json::json!([{
+        "metadata": { "name": "node-1" },
+        "usage": { "cpu": "500m", "memory": "1Gi" }
+    }]);
+    cluster_manager
+        .add_cluster_with_client("busy".to_string(), metrics_server(Some(node)))
+        .await
+        .unwrap();
+    let queue = WriteQueue::new(1, WriteOverflowPolicy::Block);
+    queue.push(uniform_samples(0)).await;
+    let collector = MetricsCollector::new(cluster_manager, Duration::from_secs(60))
+        .with_write_queue(queue.clone());
+
+    let poll = tokio::spawn({
+        let collector = collector.clone();
+        async move { collector.poll().await }
+    });
+    // Well past the 30s collection budget, the batch still waits for room.
+    tokio::time::sleep(Duration::from_secs(60)).await;
+    assert!(!poll.is_finished());
+
+    assert_eq!(queue.try_pop().unwrap().len(), 1000);
+    poll.await.unwrap();
+    assert_eq!(queue.try_pop().unwrap().len(), 2);
+    assert_eq!(queue.dropped(), 0);
+}
+
+#[tokio::test]
+async fn full_write_queue_blocks_until_the_writer_takes_a_batch() {
+    let queue = WriteQueue::new(1, WriteOverflowPolicy::Block);
+    let batch = uniform_samples(0);
+    queue.push(batch.clone()).await;
+
+    let blocked = tokio::spawn({
+        let queue = queue.clone();
+        async move { queue.push(batch).await }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!blocked.is_finished());
+
+    assert!(queue.try_pop().is_some());
+    tokio::time::timeout(Duration::from_secs(1), blocked)
+        .await
+        .expect("push stayed blocked after the writer made room")
+        .unwrap();
+    assert_eq!(queue.len(), 1);
+    assert_eq!(queue.dropped(), 0);
+}
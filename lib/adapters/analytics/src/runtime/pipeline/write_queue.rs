@@ -0,0 +1,142 @@
+//! Bounded queue between the metrics collector and the storage writer, so a
+//! slow database delays or drops batches instead of stretching every poll.
+
+use anyhow::Result;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{Notify, watch};
+
+use phenome_domain::{MetricSample, WriteOverflowPolicy};
+
+use crate::storage::StoragePort;
+
+#[derive(Clone)]
+pub struct WriteQueue {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    batches: Mutex<VecDeque<Vec<MetricSample>>>,
+    capacity: usize,
+    policy: WriteOverflowPolicy,
+    /// Woken when the writer takes a batch.
+    space: Notify,
+    /// Woken when a batch is queued.
+    queued: Notify,
+    dropped: AtomicU64,
+}
+
+impl WriteQueue {
+    pub fn new(capacity: usize, policy: WriteOverflowPolicy) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                batches: Mutex::new(VecDeque::new()),
+                capacity: capacity.max(1),
+                policy,
+                space: Notify::new(),
+                queued: Notify::new(),
+                dropped: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Queue a batch for the writer. On a full queue this waits for room
+    /// under `Block` and discards the oldest batch under `DropOldest`.
+    pub async fn push(&self, batch: Vec<MetricSample>) {
+        if batch.is_empty() {
+            return;
+        }
+        loop {
+            {
+                let Ok(mut batches) = self.shared.batches.lock() else {
+                    tracing::error!("write queue lock poisoned");
+                    return;
+                };
+                if batches.len() >= self.shared.capacity
+                    && self.shared.policy == WriteOverflowPolicy::DropOldest
+                {
+                    batches.pop_front();
+                    let dropped = self.shared.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                    tracing::warn!(
+                        "Storage writer is behind; dropped the oldest metrics batch ({} so far)",
+                        dropped
+                    );
+                }
+                if batches.len() < self.shared.capacity {
+                    batches.push_back(batch);
+                    self.shared.queued.notify_one();
+                    return;
+                }
+            }
+            self.shared.space.notified().await;
+        }
+    }
+
+    /// Next batch, waiting until one is queued.
+    pub async fn pop(&self) -> Vec<MetricSample> {
+        loop {
+            if let Some(batch) = self.try_pop() {
+                return batch;
+            }
+            self.shared.queued.notified().await;
+        }
+    }
+
+    pub fn try_pop(&self) -> Option<Vec<MetricSample>> {
+        let Ok(mut batches) = self.shared.batches.lock() else {
+            tracing::error!("write queue lock poisoned");
+            return None;
+        };
+        let batch = batches.pop_front()?;
+        self.shared.space.notify_one();
+        Some(batch)
+    }
+
+    /// Batches waiting for the writer.
+    pub fn len(&self) -> usize {
+        self.shared
+            .batches
+            .lock()
+            .map(|batches| batches.len())
+            .unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Batches discarded because the queue was full.
+    pub fn dropped(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Write queued batches to `storage` until shutdown, then flush what is
+    /// left.
+    pub async fn run_writer(
+        self,
+        storage: Arc<dyn StoragePort>,
+        mut shutdown: watch::Receiver<bool>,
+    ) -> Result<()> {
+        loop {
+            tokio::select! {
+                result = shutdown.changed() => {
+                    if result.is_err() || *shutdown.borrow() {
+                        break;
+                    }
+                }
+                batch = self.pop() => write(storage.as_ref(), batch).await,
+            }
+        }
+        while let Some(batch) = self.try_pop() {
+            write(storage.as_ref(), batch).await;
+        }
+        Ok(())
+    }
+}
+
+async fn write(storage: &dyn StoragePort, batch: Vec<MetricSample>) {
+    if let Err(err) = storage.insert_metrics(batch).await {
+        tracing::error!("Failed to store metrics batch: {}", err);
+    }
+}
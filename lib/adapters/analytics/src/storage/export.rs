@@ -0,0 +1,94 @@
+//! Row encoders for streaming metric exports.
+
+use anyhow::Result;
+use chrono::{DateTime, SecondsFormat};
+use serde::Serialize;
+use std::io::Write;
+
+use phenome_domain::{MetricSample, MetricType, ResourceType};
+
+use super::rows::encode_enum;
+
+/// Output encoding for [`SqliteStorage::export`](super::sqlite::SqliteStorage::export).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Comma-separated values with a header row.
+    Csv,
+    /// One JSON object per line.
+    JsonLines,
+}
+
+const CSV_HEADER: &str =
+    "cluster_id,resource_type,resource_id,metric_type,timestamp_ms,timestamp,value,unit";
+
+#[derive(Serialize)]
+struct ExportRow<'a> {
+    cluster_id: &'a str,
+    resource_type: ResourceType,
+    resource_id: &'a str,
+    metric_type: MetricType,
+    timestamp_ms: i64,
+    timestamp: String,
+    value: f64,
+    unit: &'a str,
+}
+
+pub(crate) fn write_header(format: ExportFormat, writer: &mut impl Write) -> Result<()> {
+    match format {
+        ExportFormat::Csv => writeln!(writer, "{CSV_HEADER}")?,
+        ExportFormat::JsonLines => {}
+    }
+    Ok(())
+}
+
+pub(crate) fn write_sample(
+    format: ExportFormat,
+    writer: &mut impl Write,
+    sample: &MetricSample,
+) -> Result<()> {
+    let row = ExportRow {
+        cluster_id: &sample.cluster_id,
+        resource_type: sample.resource_type,
+        resource_id: &sample.resource_id,
+        metric_type: sample.metric_type,
+        timestamp_ms: sample.timestamp,
+        timestamp: rfc3339(sample.timestamp),
+        value: sample.value,
+        unit: &sample.unit,
+    };
+    match format {
+        ExportFormat::Csv => {
+            let fields = [
+                csv_field(row.cluster_id),
+                csv_field(&encode_enum(&row.resource_type)?),
+                csv_field(row.resource_id),
+                csv_field(&encode_enum(&row.metric_type)?),
+                row.timestamp_ms.to_string(),
+                row.timestamp,
+                row.value.to_string(),
+                csv_field(row.unit),
+            ];
+            writeln!(writer, "{}", fields.join(","))?;
+        }
+        ExportFormat::JsonLines => {
+            serde_json::to_writer(&mut *writer, &row)?;
+            writer.write_all(b"\n")?;
+        }
+    }
+    Ok(())
+}
+
+/// RFC3339 with millisecond precision in UTC; empty when out of range.
+fn rfc3339(timestamp_ms: i64) -> String {
+    DateTime::from_timestamp_millis(timestamp_ms)
+        .map(|dt| dt.to_rfc3339_opts(SecondsFormat::Millis, true))
+        .unwrap_or_default()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
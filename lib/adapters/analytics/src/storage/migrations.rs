@@ -0,0 +1,273 @@
+//! Ordered SQLite schema migrations.
+
+use anyhow::{Context, Result};
+use rusqlite::{Connection, OptionalExtension, params};
+
+/// One forward-only schema step. `up_sql` runs inside a transaction.
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    pub version: u32,
+    pub up_sql: &'static str,
+}
+
+const SCHEMA_V1: &str = r#"
+CREATE TABLE IF NOT EXISTS metrics_raw (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    cluster_id TEXT NOT NULL,
+    resource_type TEXT NOT NULL,
+    resource_id TEXT NOT NULL,
+    metric_type TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    value REAL NOT NULL,
+    unit TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_metrics_raw_cluster_time ON metrics_raw (cluster_id, timestamp);
+CREATE INDEX IF NOT EXISTS idx_metrics_raw_resource_time ON metrics_raw (resource_id, timestamp);
+
+CREATE TABLE IF NOT EXISTS metrics_aggregated (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    cluster_id TEXT NOT NULL,
+    resource_type TEXT NOT NULL,
+    metric_type TEXT NOT NULL,
+    window_start INTEGER NOT NULL,
+    window_duration INTEGER NOT NULL,
+    count INTEGER NOT NULL,
+    sum REAL NOT NULL,
+    min REAL NOT NULL,
+    max REAL NOT NULL,
+    avg REAL NOT NULL,
+    p50 REAL NOT NULL,
+    p95 REAL NOT NULL,
+    p99 REAL NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_metrics_agg_cluster_window
+    ON metrics_aggregated (cluster_id, window_start);
+
+CREATE TABLE IF NOT EXISTS anomalies (
+    id TEXT PRIMARY KEY,
+    cluster_id TEXT NOT NULL,
+    resource_id TEXT NOT NULL,
+    detected_at INTEGER NOT NULL,
+    metric_type TEXT NOT NULL,
+    severity TEXT NOT NULL,
+    confidence REAL NOT NULL,
+    description TEXT NOT NULL,
+    baseline_value REAL NOT NULL,
+    observed_value REAL NOT NULL,
+    deviation_sigma REAL NOT NULL,
+    related_metrics TEXT,
+    root_cause TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_anomalies_cluster_time
+    ON anomalies (cluster_id, detected_at);
+
+CREATE TABLE IF NOT EXISTS recommendations (
+    id TEXT PRIMARY KEY,
+    cluster_id TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    recommendation_type TEXT NOT NULL,
+    priority TEXT NOT NULL,
+    confidence REAL NOT NULL,
+    title TEXT NOT NULL,
+    description TEXT NOT NULL,
+    impact_estimate TEXT NOT NULL,
+    cost_impact_daily REAL,
+    cost_impact_currency TEXT,
+    action TEXT NOT NULL,
+    status TEXT NOT NULL,
+    status_data TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_recommendations_cluster_status
+    ON recommendations (cluster_id, status);
+
+CREATE TABLE IF NOT EXISTS clusters (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    context TEXT NOT NULL UNIQUE,
+    api_server TEXT NOT NULL,
+    health_status TEXT NOT NULL,
+    last_seen INTEGER NOT NULL,
+    pod_count INTEGER NOT NULL,
+    node_count INTEGER NOT NULL,
+    namespace_count INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS scheduled_actions (
+    id TEXT PRIMARY KEY,
+    execute_at INTEGER NOT NULL,
+    recommendation_id TEXT NOT NULL,
+    action TEXT NOT NULL,
+    status TEXT NOT NULL,
+    status_data TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_scheduled_actions_execute_at
+    ON scheduled_actions (execute_at);
+"#;
+
+const SCHEMA_V2: &str = r#"
+CREATE INDEX IF NOT EXISTS idx_metrics_agg_cluster_metric_window
+    ON metrics_aggregated (cluster_id, metric_type, window_start);
+"#;
+
+const SCHEMA_V3: &str = r#"
+CREATE INDEX IF NOT EXISTS idx_metrics_raw_series
+    ON metrics_raw (cluster_id, resource_id, metric_type, timestamp);
+"#;
+
+const SCHEMA_V4: &str = r#"
+ALTER TABLE scheduled_actions ADD COLUMN executing_since INTEGER;
+UPDATE scheduled_actions SET executing_since = 0 WHERE status = '{"type":"executing"}';
+"#;
+
+const SCHEMA_V5: &str = r#"
+CREATE TABLE IF NOT EXISTS metrics_daily (
+    cluster_id TEXT NOT NULL,
+    resource_type TEXT NOT NULL,
+    metric_type TEXT NOT NULL,
+    day_start INTEGER NOT NULL,
+    count INTEGER NOT NULL,
+    sum REAL NOT NULL,
+    min REAL NOT NULL,
+    max REAL NOT NULL,
+    avg REAL NOT NULL,
+    PRIMARY KEY (cluster_id, resource_type, metric_type, day_start)
+);
+CREATE INDEX IF NOT EXISTS idx_metrics_daily_day ON metrics_daily (day_start);
+"#;
+
+const SCHEMA_V6: &str = r#"
+CREATE TABLE IF NOT EXISTS aggregation_windows (
+    window_duration INTEGER NOT NULL,
+    window_start INTEGER NOT NULL,
+    PRIMARY KEY (window_duration, window_start)
+);
+INSERT OR IGNORE INTO aggregation_windows (window_duration, window_start)
+    SELECT DISTINCT window_duration, window_start FROM metrics_aggregated;
+"#;
+
+const SCHEMA_V7: &str = r#"
+ALTER TABLE scheduled_actions ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0;
+"#;
+
+const SCHEMA_V8: &str = r#"
+ALTER TABLE scheduled_actions ADD COLUMN cron TEXT;
+"#;
+
+const SCHEMA_V9: &str = r#"
+CREATE TABLE IF NOT EXISTS schedule_executions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    schedule_id TEXT NOT NULL,
+    outcome TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    detail TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_schedule_executions_schedule
+    ON schedule_executions (schedule_id, timestamp);
+"#;
+
+const SCHEMA_V10: &str = r#"
+CREATE TABLE IF NOT EXISTS notifications (
+    id TEXT PRIMARY KEY,
+    timestamp INTEGER NOT NULL,
+    read INTEGER NOT NULL DEFAULT 0,
+    data TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_notifications_unread
+    ON notifications (read, timestamp);
+"#;
+
+/// Migrations applied by `SqliteStorage`, in ascending version order.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        up_sql: SCHEMA_V1,
+    },
+    Migration {
+        version: 2,
+        up_sql: SCHEMA_V2,
+    },
+    Migration {
+        version: 3,
+        up_sql: SCHEMA_V3,
+    },
+    Migration {
+        version: 4,
+        up_sql: SCHEMA_V4,
+    },
+    Migration {
+        version: 5,
+        up_sql: SCHEMA_V5,
+    },
+    Migration {
+        version: 6,
+        up_sql: SCHEMA_V6,
+    },
+    Migration {
+        version: 7,
+        up_sql: SCHEMA_V7,
+    },
+    Migration {
+        version: 8,
+        up_sql: SCHEMA_V8,
+    },
+    Migration {
+        version: 9,
+        up_sql: SCHEMA_V9,
+    },
+    Migration {
+        version: 10,
+        up_sql: SCHEMA_V10,
+    },
+];
+
+const META_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS schema_meta (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    schema_version INTEGER NOT NULL
+);
+"#;
+
+/// Current schema version, or 0 for a database that has never been migrated.
+pub fn schema_version(conn: &Connection) -> Result<u32> {
+    conn.execute_batch(META_SCHEMA)
+        .context("failed to create schema_meta table")?;
+    let version: Option<u32> = conn
+        .query_row(
+            "SELECT schema_version FROM schema_meta WHERE id = 1",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(version.unwrap_or(0))
+}
+
+/// Bring the database up to the latest version in `migrations`.
+///
+/// Each pending step runs in its own transaction together with the version
+/// bump, so a failed step leaves the database at the previous version.
+pub fn apply_migrations(conn: &mut Connection, migrations: &[Migration]) -> Result<u32> {
+    let current = schema_version(conn)?;
+    let latest = migrations.last().map_or(0, |migration| migration.version);
+    if current > latest {
+        anyhow::bail!(
+            "database schema version {current} is newer than the latest supported version {latest}; \
+             refusing to open it with this build"
+        );
+    }
+
+    for migration in migrations.iter().filter(|m| m.version > current) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration.up_sql)
+            .with_context(|| format!("failed to apply schema migration v{}", migration.version))?;
+        tx.execute(
+            "INSERT INTO schema_meta (id, schema_version) VALUES (1, ?1)
+             ON CONFLICT(id) DO UPDATE SET schema_version = excluded.schema_version",
+            params![migration.version],
+        )?;
+        tx.commit()
+            .with_context(|| format!("failed to commit schema migration v{}", migration.version))?;
+        tracing::info!("Applied sqlite schema migration v{}", migration.version);
+    }
+
+    Ok(latest.max(current))
+}
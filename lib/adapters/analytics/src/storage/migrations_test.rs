@@ -0,0 +1,87 @@
+use rusqlite::{Connection, params};
+
+use phenome_domain::MetricsQuery;
+
+use crate::storage::migrations::{MIGRATIONS, Migration, apply_migrations, schema_version};
+use crate::storage::port::StoragePort;
+use crate::storage::sqlite::SqliteStorage;
+
+fn write_v1_fixture(path: &std::path::Path) {
+    let mut conn = Connection::open(path).unwrap();
+    apply_migrations(&mut conn, &MIGRATIONS[..1]).unwrap();
+    conn.execute(
+        "INSERT INTO metrics_raw (cluster_id, resource_type, resource_id, metric_type, timestamp, value, unit)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params!["cluster-1", "pod", "pod-a", "cpu_usage", 1_000, 0.5, "cores"],
+    )
+    .unwrap();
+    assert_eq!(schema_version(&conn).unwrap(), 1);
+}
+
+#[tokio::test]
+async fn migrates_v1_database_to_latest_in_place() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("analytics.db");
+    write_v1_fixture(&db_path);
+
+    let storage =
+        SqliteStorage::open_with_migrations(db_path.to_string_lossy().to_string()).unwrap();
+    assert_eq!(storage.schema_version().unwrap(), 10);
+
+    let conn = Connection::open(&db_path).unwrap();
+    let index_count: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM sqlite_master
+             WHERE type = 'index'
+               AND name IN ('idx_metrics_agg_cluster_metric_window', 'idx_metrics_raw_series')",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(index_count, 2);
+
+    let samples = storage
+        .query_metrics(MetricsQuery::default())
+        .await
+        .unwrap();
+    assert_eq!(samples.len(), 1);
+    assert_eq!(samples[0].resource_id, "pod-a");
+}
+
+#[test]
+fn refuses_database_from_newer_build() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("analytics.db");
+    {
+        let mut conn = Connection::open(&db_path).unwrap();
+        let future = [Migration {
+            version: 99,
+            up_sql: "CREATE TABLE future (id INTEGER);",
+        }];
+        apply_migrations(&mut conn, &future).unwrap();
+    }
+
+    let err =
+        SqliteStorage::open_with_migrations(db_path.to_string_lossy().to_string()).unwrap_err();
+    assert!(
+        format!("{err:#}").contains("newer than the latest supported version"),
+        "unexpected error: {err:#}"
+    );
+}
+
+#[test]
+fn failed_migration_leaves_previous_version() {
+    let mut conn = Connection::open_in_memory().unwrap();
+    let migrations = [
+        Migration {
+            version: 1,
+            up_sql: "CREATE TABLE a (id INTEGER);",
+        },
+        Migration {
+            version: 2,
+            up_sql: "CREATE TABLE b (id INTEGER); THIS IS NOT SQL;",
+        },
+    ];
+    assert!(apply_migrations(&mut conn, &migrations).is_err());
+    assert_eq!(schema_version(&conn).unwrap(), 1);
+}
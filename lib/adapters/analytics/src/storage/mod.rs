@@ -1,12 +1,20 @@
 //! Storage backends for analytics data.
 
+pub mod export;
+pub mod migrations;
 pub mod port;
+mod rows;
 pub mod sqlite;
 
 #[cfg(feature = "postgres")]
 pub mod postgres;
 
-pub use port::StoragePort;
+pub use export::ExportFormat;
+pub use port::{PruneStats, StoragePort};
 
+#[cfg(test)]
+mod migrations_test;
+#[cfg(all(test, feature = "postgres"))]
+mod postgres_test;
 #[cfg(test)]
 mod sqlite_test;
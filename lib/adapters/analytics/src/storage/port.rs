@@ -1,19 +1,134 @@
 use anyhow::Result;
 use async_trait::async_trait;
+use std::io::Write;
+use std::time::Duration;
 
-use phenome_domain::{AggregatedMetric, AggregatedQuery, MetricSample, MetricsQuery};
+use phenome_domain::{
+    AggregatedMetric, AggregatedQuery, ClusterId, DailyAggregate, DownsampledMetrics,
+    ExecutionOutcome, MetricSample, MetricsQuery, Notification, Recommendation, ScheduleExecution,
+    ScheduleId,
+};
+
+use super::export::ExportFormat;
+
+/// Rows removed by [`StoragePort::cleanup_retention`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PruneStats {
+    pub raw_rows: usize,
+    pub aggregated_rows: usize,
+    pub daily_rows: usize,
+}
 
 #[async_trait]
 pub trait StoragePort: Send + Sync {
     async fn insert_metrics(&self, samples: Vec<MetricSample>) -> Result<()>;
     async fn query_metrics(&self, query: MetricsQuery) -> Result<Vec<MetricSample>>;
+    /// Ids of the clusters with raw samples stored, sorted.
+    async fn list_cluster_ids(&self) -> Result<Vec<ClusterId>>;
     async fn insert_aggregated(&self, metrics: Vec<AggregatedMetric>) -> Result<()>;
     async fn query_aggregated(&self, query: AggregatedQuery) -> Result<Vec<AggregatedMetric>>;
+    /// Store the rollup of one window and mark the window processed, in one
+    /// transaction. Does nothing if the window is already processed.
+    async fn commit_window(
+        &self,
+        window_start: i64,
+        window: Duration,
+        metrics: Vec<AggregatedMetric>,
+    ) -> Result<()>;
+    /// Starts of the processed `window`s in `[from_ms, to_ms]`.
+    async fn processed_windows(
+        &self,
+        window: Duration,
+        from_ms: i64,
+        to_ms: i64,
+    ) -> Result<Vec<i64>>;
+    /// Start of the latest processed `window`, if any.
+    async fn last_processed_window(&self, window: Duration) -> Result<Option<i64>>;
+    /// Insert daily rollups, replacing any existing row for the same series
+    /// and day.
+    async fn upsert_daily(&self, aggregates: Vec<DailyAggregate>) -> Result<()>;
+    /// Daily rollups matching `query`, filtered on `day_start`. The query's
+    /// `window_duration` is ignored.
+    async fn query_daily(&self, query: AggregatedQuery) -> Result<Vec<DailyAggregate>>;
     async fn insert_anomalies(&self, anomalies: Vec<phenome_domain::Anomaly>) -> Result<()>;
-    async fn cleanup_retention(&self) -> Result<()>;
+    /// Delete rows past their retention window and reclaim the space where
+    /// the backend can. Returns the rows removed.
+    async fn cleanup_retention(&self) -> Result<PruneStats>;
 
     // Scheduler methods
     async fn insert_schedule(&self, action: phenome_domain::ScheduledAction) -> Result<()>;
     async fn update_schedule(&self, action: phenome_domain::ScheduledAction) -> Result<()>;
     async fn get_all_schedules(&self) -> Result<Vec<phenome_domain::ScheduledAction>>;
+    /// Return schedules left `Executing` for longer than `timeout` (e.g. by a
+    /// crash mid-execution) to `Pending`. Returns the number recovered.
+    async fn recover_stuck_schedules(&self, timeout: Duration) -> Result<usize>;
+    /// Append an attempt to the execution history of schedule `id`.
+    async fn record_execution(
+        &self,
+        id: ScheduleId,
+        outcome: ExecutionOutcome,
+        timestamp: i64,
+        detail: String,
+    ) -> Result<()>;
+    /// Execution history of schedule `id`, oldest first.
+    async fn list_executions(&self, id: ScheduleId) -> Result<Vec<ScheduleExecution>>;
+
+    // Recommendation methods
+    /// Insert recommendations, replacing any stored under the same id.
+    async fn upsert_recommendations(&self, recommendations: Vec<Recommendation>) -> Result<()>;
+    /// Store `recommendation` and insert `schedule` in one transaction, so a
+    /// recommendation is never left pending with its action queued.
+    async fn commit_recommendation(
+        &self,
+        recommendation: Recommendation,
+        schedule: Option<phenome_domain::ScheduledAction>,
+    ) -> Result<()>;
+    /// Every stored recommendation, oldest first.
+    async fn list_recommendations(&self) -> Result<Vec<Recommendation>>;
+
+    // Notification inbox methods
+    /// Store a sent notification. A notification already stored keeps its
+    /// read state.
+    async fn insert_notification(&self, notification: Notification) -> Result<()>;
+    async fn mark_notification_read(&self, id: String) -> Result<()>;
+    /// Unread notifications, newest first.
+    async fn list_unread_notifications(&self) -> Result<Vec<Notification>>;
+
+    /// Cheap round trip to the database, for health checks.
+    async fn ping(&self) -> Result<()>;
+
+    /// Blocking, row-by-row export of raw samples. Backends without a
+    /// streaming reader reject the request.
+    fn export_metrics(
+        &self,
+        _query: MetricsQuery,
+        _format: ExportFormat,
+        _writer: &mut dyn Write,
+    ) -> Result<usize> {
+        anyhow::bail!("metric export is not supported by this storage backend")
+    }
+
+    /// Blocking read of raw samples in batches of up to `batch_size`, oldest
+    /// first. Stops early once `sink` returns `false`; returns the number of
+    /// samples handed over. Backends without a streaming reader reject the
+    /// request.
+    fn stream_metrics(
+        &self,
+        _query: MetricsQuery,
+        _batch_size: usize,
+        _sink: &mut dyn FnMut(Vec<MetricSample>) -> bool,
+    ) -> Result<usize> {
+        anyhow::bail!("metric streaming is not supported by this storage backend")
+    }
+
+    /// Blocking average/min/max of raw samples in buckets, at most
+    /// `max_points` per series. Backends without a downsampling query reject
+    /// the request.
+    fn query_downsampled(
+        &self,
+        _query: MetricsQuery,
+        _max_points: usize,
+    ) -> Result<DownsampledMetrics> {
+        anyhow::bail!("downsampled queries are not supported by this storage backend")
+    }
 }
@@ -1,34 +1,996 @@
-use anyhow::Result;
+//! PostgreSQL storage backend, enabled with the `postgres` feature.
+
+use anyhow::{Context, Result};
 use async_trait::async_trait;
+use deadpool_postgres::{Manager, ManagerConfig, Object, Pool, RecyclingMethod, Runtime};
+use std::time::Duration;
+use tokio_postgres::types::ToSql;
+use tokio_postgres::{NoTls, Row};
+
+use phenome_domain::{
+    AggregatedMetric, AggregatedQuery, ClusterId, DailyAggregate, ExecutionOutcome, MetricSample,
+    MetricsQuery, Notification, Recommendation, ScheduleExecution, ScheduleId, ScheduleStatus,
+};
+
+use super::port::{PruneStats, StoragePort};
+use super::rows::{
+    FilterValue, METRIC_COLUMNS, MetricRow, RECOMMENDATION_COLUMNS, RecommendationRow, decode_enum,
+    encode_batch, encode_enum, executing_since, metrics_filter,
+};
+use super::sqlite::RetentionConfig;
+
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS metrics_raw (
+    id BIGSERIAL PRIMARY KEY,
+    cluster_id TEXT NOT NULL,
+    resource_type TEXT NOT NULL,
+    resource_id TEXT NOT NULL,
+    metric_type TEXT NOT NULL,
+    timestamp BIGINT NOT NULL,
+    value DOUBLE PRECISION NOT NULL,
+    unit TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_metrics_raw_cluster_time ON metrics_raw (cluster_id, timestamp);
+CREATE INDEX IF NOT EXISTS idx_metrics_raw_series
+    ON metrics_raw (cluster_id, resource_id, metric_type, timestamp);
+
+CREATE TABLE IF NOT EXISTS metrics_aggregated (
+    id BIGSERIAL PRIMARY KEY,
+    cluster_id TEXT NOT NULL,
+    resource_type TEXT NOT NULL,
+    metric_type TEXT NOT NULL,
+    window_start BIGINT NOT NULL,
+    window_duration BIGINT NOT NULL,
+    count BIGINT NOT NULL,
+    sum DOUBLE PRECISION NOT NULL,
+    min DOUBLE PRECISION NOT NULL,
+    max DOUBLE PRECISION NOT NULL,
+    avg DOUBLE PRECISION NOT NULL,
+    p50 DOUBLE PRECISION NOT NULL,
+    p95 DOUBLE PRECISION NOT NULL,
+    p99 DOUBLE PRECISION NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_metrics_agg_cluster_metric_window
+    ON metrics_aggregated (cluster_id, metric_type, window_start);
+
+CREATE TABLE IF NOT EXISTS aggregation_windows (
+    window_duration BIGINT NOT NULL,
+    window_start BIGINT NOT NULL,
+    PRIMARY KEY (window_duration, window_start)
+);
+
+CREATE TABLE IF NOT EXISTS metrics_daily (
+    cluster_id TEXT NOT NULL,
+    resource_type TEXT NOT NULL,
+    metric_type TEXT NOT NULL,
+    day_start BIGINT NOT NULL,
+    count BIGINT NOT NULL,
+    sum DOUBLE PRECISION NOT NULL,
+    min DOUBLE PRECISION NOT NULL,
+    max DOUBLE PRECISION NOT NULL,
+    avg DOUBLE PRECISION NOT NULL,
+    PRIMARY KEY (cluster_id, resource_type, metric_type, day_start)
+);
+CREATE INDEX IF NOT EXISTS idx_metrics_daily_day ON metrics_daily (day_start);
+
+CREATE TABLE IF NOT EXISTS anomalies (
+    id TEXT PRIMARY KEY,
+    cluster_id TEXT NOT NULL,
+    resource_id TEXT NOT NULL,
+    detected_at BIGINT NOT NULL,
+    metric_type TEXT NOT NULL,
+    severity TEXT NOT NULL,
+    confidence DOUBLE PRECISION NOT NULL,
+    description TEXT NOT NULL,
+    baseline_value DOUBLE PRECISION NOT NULL,
+    observed_value DOUBLE PRECISION NOT NULL,
+    deviation_sigma DOUBLE PRECISION NOT NULL,
+    related_metrics TEXT,
+    root_cause TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_anomalies_cluster_time
+    ON anomalies (cluster_id, detected_at);
+
+CREATE TABLE IF NOT EXISTS scheduled_actions (
+    id TEXT PRIMARY KEY,
+    execute_at BIGINT NOT NULL,
+    recommendation_id TEXT NOT NULL,
+    action TEXT NOT NULL,
+    status TEXT NOT NULL,
+    status_data TEXT,
+    executing_since BIGINT,
+    attempts BIGINT NOT NULL DEFAULT 0,
+    cron TEXT
+);
+ALTER TABLE scheduled_actions ADD COLUMN IF NOT EXISTS executing_since BIGINT;
+ALTER TABLE scheduled_actions ADD COLUMN IF NOT EXISTS attempts BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE scheduled_actions ADD COLUMN IF NOT EXISTS cron TEXT;
+CREATE INDEX IF NOT EXISTS idx_scheduled_actions_execute_at
+    ON scheduled_actions (execute_at);
+
+CREATE TABLE IF NOT EXISTS schedule_executions (
+    id BIGSERIAL PRIMARY KEY,
+    schedule_id TEXT NOT NULL,
+    outcome TEXT NOT NULL,
+    timestamp BIGINT NOT NULL,
+    detail TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_schedule_executions_schedule
+    ON schedule_executions (schedule_id, timestamp);
+
+CREATE TABLE IF NOT EXISTS recommendations (
+    id TEXT PRIMARY KEY,
+    cluster_id TEXT NOT NULL,
+    created_at BIGINT NOT NULL,
+    recommendation_type TEXT NOT NULL,
+    priority TEXT NOT NULL,
+    confidence DOUBLE PRECISION NOT NULL,
+    title TEXT NOT NULL,
+    description TEXT NOT NULL,
+    impact_estimate TEXT NOT NULL,
+    cost_impact_daily DOUBLE PRECISION,
+    cost_impact_currency TEXT,
+    action TEXT NOT NULL,
+    status TEXT NOT NULL,
+    status_data TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_recommendations_cluster_status
+    ON recommendations (cluster_id, status);
 
-use phenome_domain::{AggregatedMetric, AggregatedQuery, MetricSample, MetricsQuery};
+CREATE TABLE IF NOT EXISTS notifications (
+    id TEXT PRIMARY KEY,
+    timestamp BIGINT NOT NULL,
+    read BOOLEAN NOT NULL DEFAULT FALSE,
+    data TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_notifications_unread
+    ON notifications (read, timestamp);
+"#;
 
-use super::port::StoragePort;
+type Params = Vec<Box<dyn ToSql + Sync + Send>>;
 
-#[derive(Debug, Clone)]
-pub struct PostgresStorage;
+/// Connections kept open at most; storage calls wait for a free one.
+const POOL_SIZE: usize = 8;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone)]
+pub struct PostgresStorage {
+    pool: Pool,
+    retention: RetentionConfig,
+}
 
 impl PostgresStorage {
-    pub fn new() -> Self {
-        Self
+    /// Connect to `url` and create the schema if it does not exist yet.
+    pub async fn connect(url: &str, retention: RetentionConfig) -> Result<Self> {
+        let config: tokio_postgres::Config = url.parse().context("invalid postgres url")?;
+        let manager = Manager::from_config(
+            config,
+            NoTls,
+            ManagerConfig {
+                recycling_method: RecyclingMethod::Verified,
+            },
+        );
+        let pool = Pool::builder(manager)
+            .max_size(POOL_SIZE)
+            .runtime(Runtime::Tokio1)
+            .create_timeout(Some(CONNECT_TIMEOUT))
+            .wait_timeout(Some(CONNECT_TIMEOUT))
+            .build()
+            .context("failed to build postgres pool")?;
+        let storage = Self { pool, retention };
+        storage
+            .client()
+            .await?
+            .batch_execute(SCHEMA)
+            .await
+            .context("failed to create postgres schema")?;
+        Ok(storage)
+    }
+
+    /// Check out a pooled connection. Each checkout is verified first, so a
+    /// connection dropped by a restart or failover is replaced, not reused.
+    pub(super) async fn client(&self) -> Result<Object> {
+        self.pool
+            .get()
+            .await
+            .context("failed to get a postgres connection")
+    }
+
+    /// Insert a batch in one statement, skipping malformed samples.
+    pub async fn insert_metrics_batch(&self, samples: &[MetricSample]) -> Result<usize> {
+        let client = self.client().await?;
+        let rows = encode_batch(samples);
+        if rows.is_empty() {
+            return Ok(0);
+        }
+
+        let mut cluster_ids = Vec::with_capacity(rows.len());
+        let mut resource_types = Vec::with_capacity(rows.len());
+        let mut resource_ids = Vec::with_capacity(rows.len());
+        let mut metric_types = Vec::with_capacity(rows.len());
+        let mut timestamps = Vec::with_capacity(rows.len());
+        let mut values = Vec::with_capacity(rows.len());
+        let mut units = Vec::with_capacity(rows.len());
+        for row in rows {
+            cluster_ids.push(row.cluster_id);
+            resource_types.push(row.resource_type);
+            resource_ids.push(row.resource_id);
+            metric_types.push(row.metric_type);
+            timestamps.push(row.timestamp);
+            values.push(row.value);
+            units.push(row.unit);
+        }
+
+        let inserted = client
+            .execute(
+                &format!(
+                    "INSERT INTO metrics_raw ({METRIC_COLUMNS})
+                     SELECT * FROM UNNEST($1::text[], $2::text[], $3::text[], $4::text[], $5::bigint[], $6::float8[], $7::text[])"
+                ),
+                &[
+                    &cluster_ids,
+                    &resource_types,
+                    &resource_ids,
+                    &metric_types,
+                    &timestamps,
+                    &values,
+                    &units,
+                ],
+            )
+            .await
+            .context("failed to insert metrics batch")?;
+        Ok(inserted as usize)
+    }
+
+    /// Delete rows past their retention window relative to `now_ms`.
+    pub async fn prune_expired(&self, now_ms: i64) -> Result<PruneStats> {
+        const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+        let raw_cutoff = now_ms - self.retention.raw_days * DAY_MS;
+        let aggregated_cutoff = now_ms - self.retention.aggregated_days * DAY_MS;
+        let daily_cutoff = now_ms - self.retention.daily_days * DAY_MS;
+        let mut client = self.client().await?;
+        let tx = client.transaction().await?;
+        let raw_rows = tx
+            .execute(
+                "DELETE FROM metrics_raw WHERE timestamp < $1",
+                &[&raw_cutoff],
+            )
+            .await?;
+        let aggregated_rows = tx
+            .execute(
+                "DELETE FROM metrics_aggregated WHERE window_start < $1",
+                &[&aggregated_cutoff],
+            )
+            .await?;
+        tx.execute(
+            "DELETE FROM aggregation_windows WHERE window_start < $1",
+            &[&aggregated_cutoff],
+        )
+        .await?;
+        let daily_rows = tx
+            .execute(
+                "DELETE FROM metrics_daily WHERE day_start < $1",
+                &[&daily_cutoff],
+            )
+            .await?;
+        tx.commit()
+            .await
+            .context("failed to commit retention prune")?;
+        Ok(PruneStats {
+            raw_rows: raw_rows as usize,
+            aggregated_rows: aggregated_rows as usize,
+            daily_rows: daily_rows as usize,
+        })
     }
 }
 
 #[async_trait]
 impl StoragePort for PostgresStorage {
-    async fn insert_metrics(&self, _samples: Vec<MetricSample>) -> Result<()> {
-        anyhow::bail!("postgres storage not implemented")
+    async fn insert_metrics(&self, samples: Vec<MetricSample>) -> Result<()> {
+        self.insert_metrics_batch(&samples).await?;
+        Ok(())
+    }
+
+    async fn query_metrics(&self, query: MetricsQuery) -> Result<Vec<MetricSample>> {
+        let client = self.client().await?;
+        let (where_sql, values) = metrics_filter(&query, |n| format!("${n}"))?;
+        let params: Params = values
+            .into_iter()
+            .map(|value| -> Box<dyn ToSql + Sync + Send> {
+                match value {
+                    FilterValue::Text(text) => Box::new(text),
+                    FilterValue::Integer(int) => Box::new(int),
+                }
+            })
+            .collect();
+        let rows = client
+            .query(
+                &format!("SELECT {METRIC_COLUMNS} FROM metrics_raw{where_sql}"),
+                &param_refs(&params),
+            )
+            .await?;
+        rows.iter().map(sample_from_row).collect()
+    }
+
+    async fn list_cluster_ids(&self) -> Result<Vec<ClusterId>> {
+        let client = self.client().await?;
+        let rows = client
+            .query(
+                "SELECT DISTINCT cluster_id FROM metrics_raw ORDER BY cluster_id",
+                &[],
+            )
+            .await?;
+        rows.iter().map(|row| Ok(row.try_get(0)?)).collect()
+    }
+
+    async fn insert_aggregated(&self, metrics: Vec<AggregatedMetric>) -> Result<()> {
+        let client = self.client().await?;
+        if metrics.is_empty() {
+            return Ok(());
+        }
+
+        let stmt = client
+            .prepare(
+                "INSERT INTO metrics_aggregated
+                (cluster_id, resource_type, metric_type, window_start, window_duration, count, sum, min, max, avg, p50, p95, p99)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)",
+            )
+            .await?;
+        for metric in metrics {
+            client
+                .execute(
+                    &stmt,
+                    &[
+                        &metric.cluster_id,
+                        &encode_enum(&metric.resource_type)?,
+                        &encode_enum(&metric.metric_type)?,
+                        &metric.window_start,
+                        &(metric.window_duration.as_millis() as i64),
+                        &(metric.count as i64),
+                        &metric.sum,
+                        &metric.min,
+                        &metric.max,
+                        &metric.avg,
+                        &metric.p50,
+                        &metric.p95,
+                        &metric.p99,
+                    ],
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn commit_window(
+        &self,
+        window_start: i64,
+        window: Duration,
+        metrics: Vec<AggregatedMetric>,
+    ) -> Result<()> {
+        let client = self.client().await?;
+        let mut columns = AggregatedColumns::default();
+        for metric in metrics {
+            columns.push(metric)?;
+        }
+        // One statement, so the marker and the rows commit together; the
+        // rows are only written when this call inserted the marker.
+        client
+            .execute(
+                "WITH marked AS (
+                    INSERT INTO aggregation_windows (window_duration, window_start)
+                    VALUES ($1, $2)
+                    ON CONFLICT DO NOTHING
+                    RETURNING 1
+                 )
+                 INSERT INTO metrics_aggregated
+                 (cluster_id, resource_type, metric_type, window_start, window_duration, count, sum, min, max, avg, p50, p95, p99)
+                 SELECT * FROM UNNEST($3::text[], $4::text[], $5::text[], $6::bigint[], $7::bigint[], $8::bigint[], $9::float8[], $10::float8[], $11::float8[], $12::float8[], $13::float8[], $14::float8[], $15::float8[])
+                 WHERE EXISTS (SELECT 1 FROM marked)",
+                &[
+                    &(window.as_millis() as i64),
+                    &window_start,
+                    &columns.cluster_ids,
+                    &columns.resource_types,
+                    &columns.metric_types,
+                    &columns.window_starts,
+                    &columns.window_durations,
+                    &columns.counts,
+                    &columns.sums,
+                    &columns.mins,
+                    &columns.maxes,
+                    &columns.avgs,
+                    &columns.p50s,
+                    &columns.p95s,
+                    &columns.p99s,
+                ],
+            )
+            .await
+            .context("failed to commit aggregation window")?;
+        Ok(())
+    }
+
+    async fn processed_windows(
+        &self,
+        window: Duration,
+        from_ms: i64,
+        to_ms: i64,
+    ) -> Result<Vec<i64>> {
+        let client = self.client().await?;
+        let rows = client
+            .query(
+                "SELECT window_start FROM aggregation_windows
+                 WHERE window_duration = $1 AND window_start BETWEEN $2 AND $3
+                 ORDER BY window_start",
+                &[&(window.as_millis() as i64), &from_ms, &to_ms],
+            )
+            .await?;
+        rows.iter().map(|row| Ok(row.try_get(0)?)).collect()
+    }
+
+    async fn last_processed_window(&self, window: Duration) -> Result<Option<i64>> {
+        let client = self.client().await?;
+        let row = client
+            .query_one(
+                "SELECT MAX(window_start) FROM aggregation_windows WHERE window_duration = $1",
+                &[&(window.as_millis() as i64)],
+            )
+            .await?;
+        Ok(row.try_get(0)?)
+    }
+
+    async fn query_aggregated(&self, query: AggregatedQuery) -> Result<Vec<AggregatedMetric>> {
+        let client = self.client().await?;
+        let (where_sql, params) = aggregated_filter(&query, "window_start")?;
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT cluster_id, resource_type, metric_type, window_start, window_duration, count, sum, min, max, avg, p50, p95, p99
+                     FROM metrics_aggregated{where_sql}"
+                ),
+                &param_refs(&params),
+            )
+            .await?;
+        rows.iter()
+            .map(|row| {
+                let duration_ms: i64 = row.try_get(4)?;
+                Ok(AggregatedMetric {
+                    cluster_id: row.try_get(0)?,
+                    resource_type: decode_enum(row.try_get(1)?)?,
+                    metric_type: decode_enum(row.try_get(2)?)?,
+                    window_start: row.try_get(3)?,
+                    window_duration: Duration::from_millis(duration_ms.max(0) as u64),
+                    count: row.try_get::<_, i64>(5)? as u64,
+                    sum: row.try_get(6)?,
+                    min: row.try_get(7)?,
+                    max: row.try_get(8)?,
+                    avg: row.try_get(9)?,
+                    p50: row.try_get(10)?,
+                    p95: row.try_get(11)?,
+                    p99: row.try_get(12)?,
+                })
+            })
+            .collect()
+    }
+
+    async fn upsert_daily(&self, aggregates: Vec<DailyAggregate>) -> Result<()> {
+        let client = self.client().await?;
+        if aggregates.is_empty() {
+            return Ok(());
+        }
+
+        let stmt = client
+            .prepare(
+                "INSERT INTO metrics_daily
+                (cluster_id, resource_type, metric_type, day_start, count, sum, min, max, avg)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                 ON CONFLICT (cluster_id, resource_type, metric_type, day_start) DO UPDATE SET
+                    count = EXCLUDED.count,
+                    sum = EXCLUDED.sum,
+                    min = EXCLUDED.min,
+                    max = EXCLUDED.max,
+                    avg = EXCLUDED.avg",
+            )
+            .await?;
+        for daily in aggregates {
+            client
+                .execute(
+                    &stmt,
+                    &[
+                        &daily.cluster_id,
+                        &encode_enum(&daily.resource_type)?,
+                        &encode_enum(&daily.metric_type)?,
+                        &daily.day_start,
+                        &(daily.count as i64),
+                        &daily.sum,
+                        &daily.min,
+                        &daily.max,
+                        &daily.avg,
+                    ],
+                )
+                .await
+                .context("failed to upsert daily aggregate")?;
+        }
+        Ok(())
+    }
+
+    async fn query_daily(&self, query: AggregatedQuery) -> Result<Vec<DailyAggregate>> {
+        let client = self.client().await?;
+        let (where_sql, params) = aggregated_filter(&query, "day_start")?;
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT cluster_id, resource_type, metric_type, day_start, count, sum, min, max, avg
+                     FROM metrics_daily{where_sql} ORDER BY day_start"
+                ),
+                &param_refs(&params),
+            )
+            .await?;
+        rows.iter()
+            .map(|row| {
+                Ok(DailyAggregate {
+                    cluster_id: row.try_get(0)?,
+                    resource_type: decode_enum(row.try_get(1)?)?,
+                    metric_type: decode_enum(row.try_get(2)?)?,
+                    day_start: row.try_get(3)?,
+                    count: row.try_get::<_, i64>(4)? as u64,
+                    sum: row.try_get(5)?,
+                    min: row.try_get(6)?,
+                    max: row.try_get(7)?,
+                    avg: row.try_get(8)?,
+                })
+            })
+            .collect()
+    }
+
+    async fn insert_anomalies(&self, anomalies: Vec<phenome_domain::Anomaly>) -> Result<()> {
+        let client = self.client().await?;
+        if anomalies.is_empty() {
+            return Ok(());
+        }
+
+        let stmt = client
+            .prepare(
+                "INSERT INTO anomalies
+                 (id, cluster_id, resource_id, detected_at, metric_type, severity, confidence, description, baseline_value, observed_value, deviation_sigma, related_metrics, root_cause)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+                 ON CONFLICT (id) DO UPDATE SET
+                    cluster_id = EXCLUDED.cluster_id,
+                    resource_id = EXCLUDED.resource_id,
+                    detected_at = EXCLUDED.detected_at,
+                    metric_type = EXCLUDED.metric_type,
+                    severity = EXCLUDED.severity,
+                    confidence = EXCLUDED.confidence,
+                    description = EXCLUDED.description,
+                    baseline_value = EXCLUDED.baseline_value,
+                    observed_value = EXCLUDED.observed_value,
+                    deviation_sigma = EXCLUDED.deviation_sigma,
+                    related_metrics = EXCLUDED.related_metrics,
+                    root_cause = EXCLUDED.root_cause",
+            )
+            .await?;
+        for anomaly in anomalies {
+            client
+                .execute(
+                    &stmt,
+                    &[
+                        &anomaly.id,
+                        &anomaly.cluster_id,
+                        &anomaly.resource_id,
+                        &anomaly.detected_at,
+                        &encode_enum(&anomaly.metric_type)?,
+                        &encode_enum(&anomaly.severity)?,
+                        &anomaly.confidence,
+                        &anomaly.description,
+                        &anomaly.baseline_value,
+                        &anomaly.observed_value,
+                        &anomaly.deviation_sigma,
+                        &serde_json::to_string(&anomaly.related_metrics).unwrap_or_default(),
+                        &anomaly.root_cause,
+                    ],
+                )
+                .await?;
+        }
+        Ok(())
     }
 
-    async fn query_metrics(&self, _query: MetricsQuery) -> Result<Vec<MetricSample>> {
-        anyhow::bail!("postgres storage not implemented")
+    async fn cleanup_retention(&self) -> Result<PruneStats> {
+        self.prune_expired(chrono::Utc::now().timestamp_millis())
+            .await
     }
 
-    async fn insert_aggregated(&self, _metrics: Vec<AggregatedMetric>) -> Result<()> {
-        anyhow::bail!("postgres storage not implemented")
+    async fn insert_schedule(&self, action: phenome_domain::ScheduledAction) -> Result<()> {
+        let client = self.client().await?;
+        client
+            .execute(
+                "INSERT INTO scheduled_actions (id, execute_at, recommendation_id, action, status, executing_since, attempts, cron)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+                &[
+                    &action.id,
+                    &action.execute_at,
+                    &action.recommendation_id,
+                    &serde_json::to_string(&action.action)?,
+                    &serde_json::to_string(&action.status)?,
+                    &executing_since(&action.status),
+                    &i64::from(action.attempts),
+                    &action.cron,
+                ],
+            )
+            .await?;
+        Ok(())
     }
 
-    async fn query_aggregated(&self, _query: AggregatedQuery) -> Result<Vec<AggregatedMetric>> {
-        anyhow::bail!("postgres storage not implemented")
+    async fn update_schedule(&self, action: phenome_domain::ScheduledAction) -> Result<()> {
+        let client = self.client().await?;
+        client
+            .execute(
+                "UPDATE scheduled_actions SET execute_at = $2, recommendation_id = $3, action = $4, status = $5,
+                     executing_since = CASE WHEN $6::bigint IS NULL THEN NULL ELSE COALESCE(executing_since, $6) END,
+                     attempts = $7, cron = $8
+                 WHERE id = $1",
+                &[
+                    &action.id,
+                    &action.execute_at,
+                    &action.recommendation_id,
+                    &serde_json::to_string(&action.action)?,
+                    &serde_json::to_string(&action.status)?,
+                    &executing_since(&action.status),
+                    &i64::from(action.attempts),
+                    &action.cron,
+                ],
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn get_all_schedules(&self) -> Result<Vec<phenome_domain::ScheduledAction>> {
+        let client = self.client().await?;
+        let rows = client
+            .query(
+                "SELECT id, execute_at, recommendation_id, action, status, attempts, cron FROM scheduled_actions",
+                &[],
+            )
+            .await?;
+        rows.iter()
+            .map(|row| {
+                Ok(phenome_domain::ScheduledAction {
+                    id: row.try_get(0)?,
+                    execute_at: row.try_get(1)?,
+                    recommendation_id: row.try_get(2)?,
+                    action: serde_json::from_str(row.try_get(3)?)?,
+                    status: serde_json::from_str(row.try_get(4)?)?,
+                    attempts: u32::try_from(row.try_get::<_, i64>(5)?)?,
+                    cron: row.try_get(6)?,
+                })
+            })
+            .collect()
+    }
+
+    async fn recover_stuck_schedules(&self, timeout: Duration) -> Result<usize> {
+        let client = self.client().await?;
+        let cutoff = chrono::Utc::now().timestamp_millis() - timeout.as_millis() as i64;
+        let recovered = client
+            .execute(
+                "UPDATE scheduled_actions SET status = $1, executing_since = NULL
+                 WHERE executing_since IS NOT NULL AND executing_since <= $2",
+                &[&serde_json::to_string(&ScheduleStatus::Pending)?, &cutoff],
+            )
+            .await?;
+        Ok(recovered as usize)
+    }
+
+    async fn record_execution(
+        &self,
+        id: ScheduleId,
+        outcome: ExecutionOutcome,
+        timestamp: i64,
+        detail: String,
+    ) -> Result<()> {
+        let client = self.client().await?;
+        client
+            .execute(
+                "INSERT INTO schedule_executions (schedule_id, outcome, timestamp, detail)
+                 VALUES ($1, $2, $3, $4)",
+                &[&id, &encode_enum(&outcome)?, &timestamp, &detail],
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn list_executions(&self, id: ScheduleId) -> Result<Vec<ScheduleExecution>> {
+        let client = self.client().await?;
+        let rows = client
+            .query(
+                "SELECT outcome, timestamp, detail FROM schedule_executions
+                 WHERE schedule_id = $1 ORDER BY timestamp, id",
+                &[&id],
+            )
+            .await?;
+        rows.iter()
+            .map(|row| {
+                Ok(ScheduleExecution {
+                    schedule_id: id.clone(),
+                    outcome: decode_enum(row.try_get(0)?)?,
+                    timestamp: row.try_get(1)?,
+                    detail: row.try_get(2)?,
+                })
+            })
+            .collect()
+    }
+
+    async fn upsert_recommendations(&self, recommendations: Vec<Recommendation>) -> Result<()> {
+        let client = self.client().await?;
+        let statement = upsert_recommendation_sql("");
+        for recommendation in &recommendations {
+            let row = RecommendationRow::encode(recommendation)?;
+            client
+                .execute(&statement, &recommendation_params(&row))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn commit_recommendation(
+        &self,
+        recommendation: Recommendation,
+        schedule: Option<phenome_domain::ScheduledAction>,
+    ) -> Result<()> {
+        let client = self.client().await?;
+        let row = RecommendationRow::encode(&recommendation)?;
+        let Some(schedule) = schedule else {
+            client
+                .execute(&upsert_recommendation_sql(""), &recommendation_params(&row))
+                .await?;
+            return Ok(());
+        };
+        let action = serde_json::to_string(&schedule.action)?;
+        let status = serde_json::to_string(&schedule.status)?;
+        let since = executing_since(&schedule.status);
+        let attempts = i64::from(schedule.attempts);
+        let mut params = recommendation_params(&row).to_vec();
+        params.extend_from_slice(&[
+            &schedule.id,
+            &schedule.execute_at,
+            &schedule.recommendation_id,
+            &action,
+            &status,
+            &since,
+            &attempts,
+            &schedule.cron,
+        ]);
+        // One statement, so the schedule and the status commit together.
+        client
+            .execute(
+                &upsert_recommendation_sql(
+                    "WITH scheduled AS (
+                        INSERT INTO scheduled_actions (id, execute_at, recommendation_id, action, status, executing_since, attempts, cron)
+                        VALUES ($15, $16, $17, $18, $19, $20, $21, $22)
+                     ) ",
+                ),
+                &params,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn list_recommendations(&self) -> Result<Vec<Recommendation>> {
+        let client = self.client().await?;
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {RECOMMENDATION_COLUMNS} FROM recommendations ORDER BY created_at, id"
+                ),
+                &[],
+            )
+            .await?;
+        rows.iter()
+            .map(|row| {
+                RecommendationRow {
+                    id: row.try_get(0)?,
+                    cluster_id: row.try_get(1)?,
+                    created_at: row.try_get(2)?,
+                    recommendation_type: row.try_get(3)?,
+                    priority: row.try_get(4)?,
+                    confidence: row.try_get(5)?,
+                    title: row.try_get(6)?,
+                    description: row.try_get(7)?,
+                    impact_estimate: row.try_get(8)?,
+                    cost_impact_daily: row.try_get(9)?,
+                    cost_impact_currency: row.try_get(10)?,
+                    action: row.try_get(11)?,
+                    status: row.try_get(12)?,
+                    status_data: row.try_get(13)?,
+                }
+                .decode()
+            })
+            .collect()
+    }
+
+    async fn insert_notification(&self, notification: Notification) -> Result<()> {
+        let client = self.client().await?;
+        client
+            .execute(
+                "INSERT INTO notifications (id, timestamp, read, data)
+                 VALUES ($1, $2, $3, $4)
+                 ON CONFLICT (id) DO NOTHING",
+                &[
+                    &notification.id,
+                    &notification.timestamp,
+                    &notification.read,
+                    &serde_json::to_string(&notification)?,
+                ],
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn mark_notification_read(&self, id: String) -> Result<()> {
+        let client = self.client().await?;
+        client
+            .execute("UPDATE notifications SET read = TRUE WHERE id = $1", &[&id])
+            .await?;
+        Ok(())
+    }
+
+    async fn list_unread_notifications(&self) -> Result<Vec<Notification>> {
+        let client = self.client().await?;
+        let rows = client
+            .query(
+                "SELECT data FROM notifications WHERE NOT read ORDER BY timestamp DESC, id",
+                &[],
+            )
+            .await?;
+        rows.iter()
+            .map(|row| {
+                let notification: Notification = serde_json::from_str(row.try_get(0)?)?;
+                Ok(Notification {
+                    read: false,
+                    ..notification
+                })
+            })
+            .collect()
+    }
+
+    async fn ping(&self) -> Result<()> {
+        let client = self.client().await?;
+        client.simple_query("SELECT 1").await?;
+        Ok(())
+    }
+}
+
+/// Aggregated rows split into per-column arrays for `UNNEST`.
+#[derive(Default)]
+struct AggregatedColumns {
+    cluster_ids: Vec<String>,
+    resource_types: Vec<String>,
+    metric_types: Vec<String>,
+    window_starts: Vec<i64>,
+    window_durations: Vec<i64>,
+    counts: Vec<i64>,
+    sums: Vec<f64>,
+    mins: Vec<f64>,
+    maxes: Vec<f64>,
+    avgs: Vec<f64>,
+    p50s: Vec<f64>,
+    p95s: Vec<f64>,
+    p99s: Vec<f64>,
+}
+
+impl AggregatedColumns {
+    fn push(&mut self, metric: AggregatedMetric) -> Result<()> {
+        self.resource_types
+            .push(encode_enum(&metric.resource_type)?);
+        self.metric_types.push(encode_enum(&metric.metric_type)?);
+        self.cluster_ids.push(metric.cluster_id);
+        self.window_starts.push(metric.window_start);
+        self.window_durations
+            .push(metric.window_duration.as_millis() as i64);
+        self.counts.push(metric.count as i64);
+        self.sums.push(metric.sum);
+        self.mins.push(metric.min);
+        self.maxes.push(metric.max);
+        self.avgs.push(metric.avg);
+        self.p50s.push(metric.p50);
+        self.p95s.push(metric.p95);
+        self.p99s.push(metric.p99);
+        Ok(())
+    }
+}
+
+/// `WHERE` clause and parameters for `query` against an aggregate table whose
+/// start column is `start_column`.
+/// Upsert of one recommendation bound by [`recommendation_params`], after
+/// `prefix`.
+fn upsert_recommendation_sql(prefix: &str) -> String {
+    format!(
+        "{prefix}INSERT INTO recommendations ({RECOMMENDATION_COLUMNS})
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+         ON CONFLICT (id) DO UPDATE SET
+             cluster_id = EXCLUDED.cluster_id,
+             created_at = EXCLUDED.created_at,
+             recommendation_type = EXCLUDED.recommendation_type,
+             priority = EXCLUDED.priority,
+             confidence = EXCLUDED.confidence,
+             title = EXCLUDED.title,
+             description = EXCLUDED.description,
+             impact_estimate = EXCLUDED.impact_estimate,
+             cost_impact_daily = EXCLUDED.cost_impact_daily,
+             cost_impact_currency = EXCLUDED.cost_impact_currency,
+             action = EXCLUDED.action,
+             status = EXCLUDED.status,
+             status_data = EXCLUDED.status_data"
+    )
+}
+
+fn recommendation_params(row: &RecommendationRow) -> [&(dyn ToSql + Sync); 14] {
+    [
+        &row.id,
+        &row.cluster_id,
+        &row.created_at,
+        &row.recommendation_type,
+        &row.priority,
+        &row.confidence,
+        &row.title,
+        &row.description,
+        &row.impact_estimate,
+        &row.cost_impact_daily,
+        &row.cost_impact_currency,
+        &row.action,
+        &row.status,
+        &row.status_data,
+    ]
+}
+
+fn aggregated_filter(query: &AggregatedQuery, start_column: &str) -> Result<(String, Params)> {
+    let mut clauses = Vec::new();
+    let mut params: Params = Vec::new();
+    if let Some(cluster_id) = &query.cluster_id {
+        params.push(Box::new(cluster_id.clone()));
+        clauses.push(format!("cluster_id = ${}", params.len()));
+    }
+    if let Some(resource_type) = &query.resource_type {
+        params.push(Box::new(encode_enum(resource_type)?));
+        clauses.push(format!("resource_type = ${}", params.len()));
+    }
+    if !query.metric_types.is_empty() {
+        let metric_types = query
+            .metric_types
+            .iter()
+            .map(encode_enum)
+            .collect::<Result<Vec<_>>>()?;
+        params.push(Box::new(metric_types));
+        clauses.push(format!("metric_type = ANY(${})", params.len()));
+    }
+    if let Some(range) = &query.time_range {
+        params.push(Box::new(range.start_ms));
+        params.push(Box::new(range.end_ms));
+        clauses.push(format!(
+            "{start_column} BETWEEN ${} AND ${}",
+            params.len() - 1,
+            params.len()
+        ));
+    }
+    if clauses.is_empty() {
+        return Ok((String::new(), params));
+    }
+    Ok((format!(" WHERE {}", clauses.join(" AND ")), params))
+}
+
+fn param_refs(params: &Params) -> Vec<&(dyn ToSql + Sync)> {
+    params
+        .iter()
+        .map(|param| param.as_ref() as &(dyn ToSql + Sync))
+        .collect()
+}
+
+fn sample_from_row(row: &Row) -> Result<MetricSample> {
+    MetricRow {
+        cluster_id: row.try_get(0)?,
+        resource_type: row.try_get(1)?,
+        resource_id: row.try_get(2)?,
+        metric_type: row.try_get(3)?,
+        timestamp: row.try_get(4)?,
+        value: row.try_get(5)?,
+        unit: row.try_get(6)?,
     }
+    .decode()
 }
@@ -0,0 +1,30 @@
+use crate::storage::port::StoragePort;
+use crate::storage::postgres::PostgresStorage;
+use crate::storage::sqlite::RetentionConfig;
+
+/// Needs a live server, so it only runs when `PHENOME_TEST_POSTGRES_URL`
+/// points at one.
+fn test_url() -> Option<String> {
+    std::env::var("PHENOME_TEST_POSTGRES_URL").ok()
+}
+
+#[tokio::test]
+async fn postgres_recovers_from_a_dropped_connection() {
+    let Some(url) = test_url() else {
+        return;
+    };
+    let storage = PostgresStorage::connect(&url, RetentionConfig::default())
+        .await
+        .unwrap();
+    storage.ping().await.unwrap();
+
+    // Kill the pooled connection server-side, as a restart or failover would.
+    let client = storage.client().await.unwrap();
+    let _ = client
+        .simple_query("SELECT pg_terminate_backend(pg_backend_pid())")
+        .await;
+    drop(client);
+
+    storage.ping().await.unwrap();
+    storage.list_cluster_ids().await.unwrap();
+}
@@ -0,0 +1,256 @@
+//! Row mapping shared by the SQL storage backends.
+
+use anyhow::Result;
+use serde::{Serialize, de::DeserializeOwned};
+
+use phenome_domain::{CostImpact, MetricSample, MetricsQuery, Recommendation, ScheduleStatus};
+
+/// Column list of `metrics_raw`, in [`MetricRow`] field order.
+pub(crate) const METRIC_COLUMNS: &str =
+    "cluster_id, resource_type, resource_id, metric_type, timestamp, value, unit";
+
+/// One `metrics_raw` row with enums in their stored text form.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct MetricRow {
+    pub(crate) cluster_id: String,
+    pub(crate) resource_type: String,
+    pub(crate) resource_id: String,
+    pub(crate) metric_type: String,
+    pub(crate) timestamp: i64,
+    pub(crate) value: f64,
+    pub(crate) unit: String,
+}
+
+impl MetricRow {
+    /// Validate and encode a sample for storage.
+    pub(crate) fn encode(sample: &MetricSample) -> Result<Self> {
+        validate_sample(sample)?;
+        Ok(Self {
+            cluster_id: sample.cluster_id.clone(),
+            resource_type: encode_enum(&sample.resource_type)?,
+            resource_id: sample.resource_id.clone(),
+            metric_type: encode_enum(&sample.metric_type)?,
+            timestamp: sample.timestamp,
+            value: sample.value,
+            unit: sample.unit.clone(),
+        })
+    }
+
+    pub(crate) fn decode(self) -> Result<MetricSample> {
+        Ok(MetricSample {
+            cluster_id: self.cluster_id,
+            resource_type: decode_enum(&self.resource_type)?,
+            resource_id: self.resource_id,
+            metric_type: decode_enum(&self.metric_type)?,
+            timestamp: self.timestamp,
+            value: self.value,
+            unit: self.unit,
+        })
+    }
+}
+
+/// Column list of `recommendations`, in [`RecommendationRow`] field order.
+pub(crate) const RECOMMENDATION_COLUMNS: &str = "id, cluster_id, created_at, recommendation_type, \
+     priority, confidence, title, description, impact_estimate, cost_impact_daily, \
+     cost_impact_currency, action, status, status_data";
+
+/// One `recommendations` row. `status` holds the status kind for filtering,
+/// `status_data` the full status as JSON.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RecommendationRow {
+    pub(crate) id: String,
+    pub(crate) cluster_id: String,
+    pub(crate) created_at: i64,
+    pub(crate) recommendation_type: String,
+    pub(crate) priority: String,
+    pub(crate) confidence: f64,
+    pub(crate) title: String,
+    pub(crate) description: String,
+    pub(crate) impact_estimate: String,
+    pub(crate) cost_impact_daily: Option<f64>,
+    pub(crate) cost_impact_currency: Option<String>,
+    pub(crate) action: String,
+    pub(crate) status: String,
+    pub(crate) status_data: Option<String>,
+}
+
+impl RecommendationRow {
+    pub(crate) fn encode(recommendation: &Recommendation) -> Result<Self> {
+        Ok(Self {
+            id: recommendation.id.clone(),
+            cluster_id: recommendation.cluster_id.clone(),
+            created_at: recommendation.created_at,
+            recommendation_type: encode_enum(&recommendation.recommendation_type)?,
+            priority: encode_enum(&recommendation.priority)?,
+            confidence: recommendation.confidence,
+            title: recommendation.title.clone(),
+            description: recommendation.description.clone(),
+            impact_estimate: recommendation.impact_estimate.clone(),
+            cost_impact_daily: recommendation.cost_impact.as_ref().map(|c| c.daily_change),
+            cost_impact_currency: recommendation
+                .cost_impact
+                .as_ref()
+                .map(|c| c.currency.clone()),
+            action: serde_json::to_string(&recommendation.action)?,
+            status: encode_enum(&recommendation.status.kind())?,
+            status_data: Some(serde_json::to_string(&recommendation.status)?),
+        })
+    }
+
+    pub(crate) fn decode(self) -> Result<Recommendation> {
+        let status = match &self.status_data {
+            Some(data) => serde_json::from_str(data)?,
+            None => anyhow::bail!("recommendation {} has no status data", self.id),
+        };
+        let cost_impact = match (self.cost_impact_daily, self.cost_impact_currency) {
+            (Some(daily_change), Some(currency)) => Some(CostImpact {
+                daily_change,
+                currency,
+            }),
+            _ => None,
+        };
+        Ok(Recommendation {
+            id: self.id,
+            cluster_id: self.cluster_id,
+            created_at: self.created_at,
+            recommendation_type: decode_enum(&self.recommendation_type)?,
+            priority: decode_enum(&self.priority)?,
+            confidence: self.confidence,
+            title: self.title,
+            description: self.description,
+            impact_estimate: self.impact_estimate,
+            cost_impact,
+            action: serde_json::from_str(&self.action)?,
+            status,
+        })
+    }
+}
+
+/// Encode a batch, skipping and logging malformed samples so they cannot
+/// abort the rest of the batch.
+pub(crate) fn encode_batch(samples: &[MetricSample]) -> Vec<MetricRow> {
+    let mut rows = Vec::with_capacity(samples.len());
+    let mut rejected = 0usize;
+    for sample in samples {
+        match MetricRow::encode(sample) {
+            Ok(row) => rows.push(row),
+            Err(err) => {
+                rejected += 1;
+                tracing::debug!("Skipping malformed metric sample: {}", err);
+            }
+        }
+    }
+    if rejected > 0 {
+        tracing::warn!("Skipped {} malformed metric samples", rejected);
+    }
+    rows
+}
+
+/// Bound parameter of a generated filter clause.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum FilterValue {
+    Text(String),
+    Integer(i64),
+}
+
+/// `WHERE` clause (with leading space) and parameters for the filters in
+/// `query`. `placeholder` renders the 1-based position of each parameter in
+/// the backend's syntax. Empty when the query has no filters.
+///
+/// Equality filters on cluster, resource and metric come first and the
+/// timestamp range last, matching the `(cluster_id, resource_id,
+/// metric_type, timestamp)` series index.
+pub(crate) fn metrics_filter(
+    query: &MetricsQuery,
+    placeholder: impl Fn(usize) -> String,
+) -> Result<(String, Vec<FilterValue>)> {
+    let mut clauses = Vec::new();
+    let mut values = Vec::new();
+    let next = |values: &mut Vec<FilterValue>, value: FilterValue| {
+        values.push(value);
+        placeholder(values.len())
+    };
+
+    if let Some(cluster_id) = &query.cluster_id {
+        let p = next(&mut values, FilterValue::Text(cluster_id.clone()));
+        clauses.push(format!("cluster_id = {p}"));
+    }
+    if let Some(resource_type) = &query.resource_type {
+        let p = next(&mut values, FilterValue::Text(encode_enum(resource_type)?));
+        clauses.push(format!("resource_type = {p}"));
+    }
+    if !query.resource_ids.is_empty() {
+        let params: Vec<String> = query
+            .resource_ids
+            .iter()
+            .map(|id| next(&mut values, FilterValue::Text(id.clone())))
+            .collect();
+        clauses.push(format!("resource_id IN ({})", params.join(", ")));
+    }
+    if !query.metric_types.is_empty() {
+        let mut params = Vec::with_capacity(query.metric_types.len());
+        for metric_type in &query.metric_types {
+            params.push(next(
+                &mut values,
+                FilterValue::Text(encode_enum(metric_type)?),
+            ));
+        }
+        clauses.push(format!("metric_type IN ({})", params.join(", ")));
+    }
+    if let Some(range) = &query.time_range {
+        let start = next(&mut values, FilterValue::Integer(range.start_ms));
+        let end = next(&mut values, FilterValue::Integer(range.end_ms));
+        clauses.push(format!("timestamp BETWEEN {start} AND {end}"));
+    }
+
+    if clauses.is_empty() {
+        return Ok((String::new(), values));
+    }
+    Ok((format!(" WHERE {}", clauses.join(" AND ")), values))
+}
+
+fn validate_sample(sample: &MetricSample) -> Result<()> {
+    if sample.cluster_id.is_empty() {
+        anyhow::bail!("sample has an empty cluster id");
+    }
+    if sample.resource_id.is_empty() {
+        anyhow::bail!(
+            "sample for cluster {} has an empty resource id",
+            sample.cluster_id
+        );
+    }
+    if sample.timestamp < 0 {
+        anyhow::bail!(
+            "sample for {} has a negative timestamp {}",
+            sample.resource_id,
+            sample.timestamp
+        );
+    }
+    if !sample.value.is_finite() {
+        anyhow::bail!(
+            "sample for {} has a non-finite value {}",
+            sample.resource_id,
+            sample.value
+        );
+    }
+    Ok(())
+}
+
+/// Value for the `executing_since` column: the current time while a
+/// schedule is `Executing`, otherwise `NULL`. Updates keep the first value.
+pub(crate) fn executing_since(status: &ScheduleStatus) -> Option<i64> {
+    matches!(status, ScheduleStatus::Executing).then(|| chrono::Utc::now().timestamp_millis())
+}
+
+pub(crate) fn encode_enum<T: Serialize>(value: &T) -> Result<String> {
+    let json = serde_json::to_value(value)?;
+    match json {
+        serde_json::Value::String(value) => Ok(value),
+        _ => anyhow::bail!("expected string enum encoding"),
+    }
+}
+
+pub(crate) fn decode_enum<T: DeserializeOwned>(value: &str) -> Result<T> {
+    let value = serde_json::Value::String(value.to_string());
+    Ok(serde_json::from_value(value)?)
+}
@@ -2,112 +2,30 @@ use anyhow::{Context, Result};
 use async_trait::async_trait;
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
-use rusqlite::{Connection, params};
-use serde::{Serialize, de::DeserializeOwned};
+use rusqlite::types::Value;
+use rusqlite::{Connection, OptionalExtension, params, params_from_iter};
+use std::io::{BufWriter, Write};
 use std::time::Duration;
 
-use phenome_domain::{AggregatedMetric, AggregatedQuery, MetricSample, MetricsQuery, TimeRange};
-
-use super::port::StoragePort;
-
-const SCHEMA: &str = r#"
-CREATE TABLE IF NOT EXISTS metrics_raw (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    cluster_id TEXT NOT NULL,
-    resource_type TEXT NOT NULL,
-    resource_id TEXT NOT NULL,
-    metric_type TEXT NOT NULL,
-    timestamp INTEGER NOT NULL,
-    value REAL NOT NULL,
-    unit TEXT NOT NULL
-);
-CREATE INDEX IF NOT EXISTS idx_metrics_raw_cluster_time ON metrics_raw (cluster_id, timestamp);
-CREATE INDEX IF NOT EXISTS idx_metrics_raw_resource_time ON metrics_raw (resource_id, timestamp);
-
-CREATE TABLE IF NOT EXISTS metrics_aggregated (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    cluster_id TEXT NOT NULL,
-    resource_type TEXT NOT NULL,
-    metric_type TEXT NOT NULL,
-    window_start INTEGER NOT NULL,
-    window_duration INTEGER NOT NULL,
-    count INTEGER NOT NULL,
-    sum REAL NOT NULL,
-    min REAL NOT NULL,
-    max REAL NOT NULL,
-    avg REAL NOT NULL,
-    p50 REAL NOT NULL,
-    p95 REAL NOT NULL,
-    p99 REAL NOT NULL
-);
-CREATE INDEX IF NOT EXISTS idx_metrics_agg_cluster_window
-    ON metrics_aggregated (cluster_id, window_start);
-
-CREATE TABLE IF NOT EXISTS anomalies (
-    id TEXT PRIMARY KEY,
-    cluster_id TEXT NOT NULL,
-    resource_id TEXT NOT NULL,
-    detected_at INTEGER NOT NULL,
-    metric_type TEXT NOT NULL,
-    severity TEXT NOT NULL,
-    confidence REAL NOT NULL,
-    description TEXT NOT NULL,
-    baseline_value REAL NOT NULL,
-    observed_value REAL NOT NULL,
-    deviation_sigma REAL NOT NULL,
-    related_metrics TEXT,
-    root_cause TEXT
-);
-CREATE INDEX IF NOT EXISTS idx_anomalies_cluster_time
-    ON anomalies (cluster_id, detected_at);
-
-CREATE TABLE IF NOT EXISTS recommendations (
-    id TEXT PRIMARY KEY,
-    cluster_id TEXT NOT NULL,
-    created_at INTEGER NOT NULL,
-    recommendation_type TEXT NOT NULL,
-    priority TEXT NOT NULL,
-    confidence REAL NOT NULL,
-    title TEXT NOT NULL,
-    description TEXT NOT NULL,
-    impact_estimate TEXT NOT NULL,
-    cost_impact_daily REAL,
-    cost_impact_currency TEXT,
-    action TEXT NOT NULL,
-    status TEXT NOT NULL,
-    status_data TEXT
-);
-CREATE INDEX IF NOT EXISTS idx_recommendations_cluster_status
-    ON recommendations (cluster_id, status);
-
-CREATE TABLE IF NOT EXISTS clusters (
-    id TEXT PRIMARY KEY,
-    name TEXT NOT NULL,
-    context TEXT NOT NULL UNIQUE,
-    api_server TEXT NOT NULL,
-    health_status TEXT NOT NULL,
-    last_seen INTEGER NOT NULL,
-    pod_count INTEGER NOT NULL,
-    node_count INTEGER NOT NULL,
-    namespace_count INTEGER NOT NULL
-);
-
-CREATE TABLE IF NOT EXISTS scheduled_actions (
-    id TEXT PRIMARY KEY,
-    execute_at INTEGER NOT NULL,
-    recommendation_id TEXT NOT NULL,
-    action TEXT NOT NULL,
-    status TEXT NOT NULL,
-    status_data TEXT
-);
-CREATE INDEX IF NOT EXISTS idx_scheduled_actions_execute_at
-    ON scheduled_actions (execute_at);
-"#;
+use phenome_domain::{
+    AggregatedMetric, AggregatedQuery, ClusterId, DailyAggregate, DownsampledMetrics,
+    ExecutionOutcome, MetricBucket, MetricSample, MetricType, MetricsQuery, Notification,
+    Recommendation, ResourceType, ScheduleExecution, ScheduleId, ScheduleStatus, TimeRange,
+};
+
+use super::export::{self, ExportFormat};
+use super::migrations::{MIGRATIONS, apply_migrations, schema_version};
+use super::port::{PruneStats, StoragePort};
+use super::rows::{
+    FilterValue, METRIC_COLUMNS, MetricRow, RECOMMENDATION_COLUMNS, RecommendationRow, decode_enum,
+    encode_batch, encode_enum, executing_since, metrics_filter,
+};
 
 #[derive(Debug, Clone)]
 pub struct RetentionConfig {
     pub raw_days: i64,
     pub aggregated_days: i64,
+    pub daily_days: i64,
 }
 
 impl Default for RetentionConfig {
@@ -115,6 +33,27 @@ impl Default for RetentionConfig {
         Self {
             raw_days: 7,
             aggregated_days: 30,
+            daily_days: 365,
+        }
+    }
+}
+
+/// Connection-level pragmas applied to every pooled connection.
+#[derive(Debug, Clone)]
+pub struct SqliteTuning {
+    pub journal_mode: String,
+    pub synchronous: String,
+    pub busy_timeout: Duration,
+    pub cache_size: i64,
+}
+
+impl Default for SqliteTuning {
+    fn default() -> Self {
+        Self {
+            journal_mode: "WAL".to_string(),
+            synchronous: "NORMAL".to_string(),
+            busy_timeout: Duration::from_secs(5),
+            cache_size: 64_000,
         }
     }
 }
@@ -127,11 +66,26 @@ pub struct SqliteStorage {
 
 impl SqliteStorage {
     pub fn new(path: impl Into<String>) -> Result<Self> {
+        Self::open_with_migrations(path)
+    }
+
+    /// Open the database, upgrading its schema in place if it is older than
+    /// this build. Databases written by a newer build are rejected.
+    pub fn open_with_migrations(path: impl Into<String>) -> Result<Self> {
         Self::with_retention(path, RetentionConfig::default())
     }
 
     pub fn with_retention(path: impl Into<String>, retention: RetentionConfig) -> Result<Self> {
-        let manager = SqliteConnectionManager::file(path.into());
+        Self::with_tuning(path, retention, SqliteTuning::default())
+    }
+
+    pub fn with_tuning(
+        path: impl Into<String>,
+        retention: RetentionConfig,
+        tuning: SqliteTuning,
+    ) -> Result<Self> {
+        let manager = SqliteConnectionManager::file(path.into())
+            .with_init(move |conn| configure_sqlite(conn, &tuning));
         let pool = Pool::builder()
             .max_size(10)
             .build(manager)
@@ -141,95 +95,285 @@ impl SqliteStorage {
         Ok(storage)
     }
 
-    pub fn run_retention_cleanup(&self, now_ms: i64) -> Result<()> {
-        let conn = self.pool.get().context("failed to get sqlite connection")?;
-        let raw_cutoff = now_ms - self.retention.raw_days * 24 * 60 * 60 * 1000;
-        let agg_cutoff = now_ms - self.retention.aggregated_days * 24 * 60 * 60 * 1000;
-        conn.execute(
+    /// Delete rows past their retention window relative to `now_ms`, then
+    /// hand the freed pages back to the filesystem.
+    pub fn prune_expired(&self, now_ms: i64) -> Result<PruneStats> {
+        let mut conn = self.pool.get().context("failed to get sqlite connection")?;
+        let (raw_cutoff, agg_cutoff, daily_cutoff) = self.retention_cutoffs(now_ms);
+        let tx = conn.transaction().context("failed to begin transaction")?;
+        let raw_rows = tx.execute(
             "DELETE FROM metrics_raw WHERE timestamp < ?1",
             params![raw_cutoff],
         )?;
-        conn.execute(
+        let aggregated_rows = tx.execute(
             "DELETE FROM metrics_aggregated WHERE window_start < ?1",
             params![agg_cutoff],
         )?;
-        Ok(())
+        tx.execute(
+            "DELETE FROM aggregation_windows WHERE window_start < ?1",
+            params![agg_cutoff],
+        )?;
+        let daily_rows = tx.execute(
+            "DELETE FROM metrics_daily WHERE day_start < ?1",
+            params![daily_cutoff],
+        )?;
+        tx.commit().context("failed to commit retention prune")?;
+
+        conn.execute_batch("PRAGMA incremental_vacuum")
+            .context("failed to run incremental vacuum")?;
+        Ok(PruneStats {
+            raw_rows,
+            aggregated_rows,
+            daily_rows,
+        })
     }
 
-    fn init(&self) -> Result<()> {
-        let conn = self.pool.get().context("failed to get sqlite connection")?;
-        configure_sqlite(&conn)?;
-        conn.execute_batch(SCHEMA)
-            .context("failed to apply sqlite schema")?;
-        Ok(())
+    fn retention_cutoffs(&self, now_ms: i64) -> (i64, i64, i64) {
+        const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+        (
+            now_ms - self.retention.raw_days * DAY_MS,
+            now_ms - self.retention.aggregated_days * DAY_MS,
+            now_ms - self.retention.daily_days * DAY_MS,
+        )
     }
-}
 
-#[async_trait]
-impl StoragePort for SqliteStorage {
-    async fn insert_metrics(&self, samples: Vec<MetricSample>) -> Result<()> {
-        if samples.is_empty() {
-            return Ok(());
+    /// Insert samples in a single transaction with one prepared statement.
+    ///
+    /// Samples are validated up front; malformed ones are skipped and logged
+    /// so they cannot abort the rest of the batch. Returns the number of rows
+    /// written.
+    pub fn insert_metrics_batch(&self, samples: &[MetricSample]) -> Result<usize> {
+        let rows = encode_batch(samples);
+        if rows.is_empty() {
+            return Ok(0);
         }
 
         let mut conn = self.pool.get().context("failed to get sqlite connection")?;
-        let mut offset = 0;
-        while offset < samples.len() {
-            let end = (offset + 1000).min(samples.len());
-            let tx = conn.transaction().context("failed to begin transaction")?;
-            {
-                let mut stmt = tx.prepare(
-                    "INSERT INTO metrics_raw (cluster_id, resource_type, resource_id, metric_type, timestamp, value, unit)
-                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-                )?;
-                for sample in &samples[offset..end] {
-                    stmt.execute(params![
-                        sample.cluster_id,
-                        encode_enum(&sample.resource_type)?,
-                        sample.resource_id,
-                        encode_enum(&sample.metric_type)?,
-                        sample.timestamp,
-                        sample.value,
-                        sample.unit
-                    ])?;
-                }
+        let tx = conn.transaction().context("failed to begin transaction")?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO metrics_raw (cluster_id, resource_type, resource_id, metric_type, timestamp, value, unit)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )?;
+            for row in &rows {
+                stmt.execute(params![
+                    row.cluster_id,
+                    row.resource_type,
+                    row.resource_id,
+                    row.metric_type,
+                    row.timestamp,
+                    row.value,
+                    row.unit
+                ])?;
             }
-            tx.commit().context("failed to commit metrics batch")?;
-            offset = end;
         }
-
-        Ok(())
+        tx.commit().context("failed to commit metrics batch")?;
+        Ok(rows.len())
     }
 
-    async fn query_metrics(&self, query: MetricsQuery) -> Result<Vec<MetricSample>> {
+    /// Average/min/max buckets per series, at most `max_points` per series.
+    ///
+    /// Without a time range in `query` the span of the matching samples is
+    /// used. The returned `bucket_width_ms` is the bucket size that was
+    /// applied.
+    pub fn query_downsampled(
+        &self,
+        query: MetricsQuery,
+        max_points: usize,
+    ) -> Result<DownsampledMetrics> {
+        if max_points == 0 {
+            anyhow::bail!("max_points must be greater than zero");
+        }
         let conn = self.pool.get().context("failed to get sqlite connection")?;
-        let mut stmt = conn.prepare(
-            "SELECT cluster_id, resource_type, resource_id, metric_type, timestamp, value, unit
-             FROM metrics_raw",
-        )?;
-        let rows = stmt.query_map([], |row| {
+        let (where_sql, where_params) = metrics_filter_sql(&query)?;
+
+        let range = match query.time_range {
+            Some(range) if range.end_ms < range.start_ms => {
+                return Ok(DownsampledMetrics::default());
+            }
+            Some(range) => Some(range),
+            None => conn
+                .query_row(
+                    &format!("SELECT MIN(timestamp), MAX(timestamp) FROM metrics_raw{where_sql}"),
+                    params_from_iter(where_params.iter()),
+                    |row| {
+                        let start: Option<i64> = row.get(0)?;
+                        let end: Option<i64> = row.get(1)?;
+                        Ok(start.zip(end))
+                    },
+                )
+                .optional()?
+                .flatten()
+                .map(|(start_ms, end_ms)| TimeRange { start_ms, end_ms }),
+        };
+        let Some(range) = range else {
+            return Ok(DownsampledMetrics::default());
+        };
+
+        // Inclusive span, so a single-point range still yields one bucket.
+        let span = range.duration_ms() + 1;
+        let max_points = i64::try_from(max_points).unwrap_or(i64::MAX);
+        let bucket_width_ms = ((span + max_points - 1) / max_points).max(1);
+
+        let mut params = vec![
+            Value::Integer(range.start_ms),
+            Value::Integer(bucket_width_ms),
+        ];
+        params.extend(where_params);
+        let sql = format!(
+            "SELECT cluster_id, resource_type, resource_id, metric_type,
+                    (timestamp - ?) / ? AS bucket,
+                    COUNT(*), AVG(value), MIN(value), MAX(value), MAX(unit)
+             FROM metrics_raw{where_sql}
+             GROUP BY cluster_id, resource_type, resource_id, metric_type, bucket
+             ORDER BY cluster_id, resource_type, resource_id, metric_type, bucket"
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(params.iter()), |row| {
             let resource_type_str: String = row.get(1)?;
             let metric_type_str: String = row.get(3)?;
+            let bucket: i64 = row.get(4)?;
 
-            Ok(MetricSample {
+            Ok(MetricBucket {
                 cluster_id: row.get(0)?,
                 resource_type: decode_enum(&resource_type_str)
                     .map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))?,
                 resource_id: row.get(2)?,
                 metric_type: decode_enum(&metric_type_str)
                     .map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))?,
-                timestamp: row.get(4)?,
-                value: row.get(5)?,
-                unit: row.get(6)?,
+                bucket_start: range.start_ms + bucket * bucket_width_ms,
+                count: row.get::<_, i64>(5)? as u64,
+                avg: row.get(6)?,
+                min: row.get(7)?,
+                max: row.get(8)?,
+                unit: row.get(9)?,
             })
         })?;
 
+        let mut buckets = Vec::new();
+        for row in rows {
+            buckets.push(row?);
+        }
+
+        Ok(DownsampledMetrics {
+            bucket_width_ms,
+            buckets,
+        })
+    }
+
+    /// Stream raw samples matching `query` to `writer`, oldest first.
+    ///
+    /// Rows are encoded as they are read from sqlite, so memory use does not
+    /// grow with the size of the export. Returns the number of samples
+    /// written.
+    pub fn export<W: Write>(
+        &self,
+        query: MetricsQuery,
+        format: ExportFormat,
+        writer: W,
+    ) -> Result<usize> {
+        let mut writer = BufWriter::new(writer);
+        let conn = self.pool.get().context("failed to get sqlite connection")?;
+        let (select_sql, where_params) = metrics_select_sql(&query)?;
+        let mut stmt = conn.prepare(&format!("{select_sql} ORDER BY timestamp, id"))?;
+
+        export::write_header(format, &mut writer)?;
+        let mut rows = stmt.query(params_from_iter(where_params.iter()))?;
+        let mut written = 0;
+        while let Some(row) = rows.next()? {
+            export::write_sample(format, &mut writer, &sample_from_row(row)?)?;
+            written += 1;
+        }
+        writer.flush().context("failed to flush export")?;
+        Ok(written)
+    }
+
+    /// Hand raw samples matching `query` to `sink` in batches of up to
+    /// `batch_size`, oldest first, as they are read. Stops reading once `sink`
+    /// returns `false`. Returns the number of samples handed over.
+    pub fn read_batches(
+        &self,
+        query: MetricsQuery,
+        batch_size: usize,
+        mut sink: impl FnMut(Vec<MetricSample>) -> bool,
+    ) -> Result<usize> {
+        let batch_size = batch_size.max(1);
+        let conn = self.pool.get().context("failed to get sqlite connection")?;
+        let (select_sql, where_params) = metrics_select_sql(&query)?;
+        let mut stmt = conn.prepare(&format!("{select_sql} ORDER BY timestamp, id"))?;
+
+        let mut rows = stmt.query(params_from_iter(where_params.iter()))?;
+        let mut batch = Vec::with_capacity(batch_size);
+        let mut sent = 0;
+        while let Some(row) = rows.next()? {
+            batch.push(sample_from_row(row)?);
+            if batch.len() == batch_size {
+                sent += batch.len();
+                if !sink(std::mem::take(&mut batch)) {
+                    return Ok(sent);
+                }
+            }
+        }
+        if !batch.is_empty() {
+            sent += batch.len();
+            sink(batch);
+        }
+        Ok(sent)
+    }
+
+    /// Reset schedules that entered `Executing` at or before `now_ms - timeout`
+    /// back to `Pending`.
+    pub fn recover_stuck_schedules_at(&self, now_ms: i64, timeout: Duration) -> Result<usize> {
+        let cutoff = now_ms - timeout.as_millis() as i64;
+        let conn = self.pool.get().context("failed to get sqlite connection")?;
+        let recovered = conn.execute(
+            "UPDATE scheduled_actions SET status = ?1, executing_since = NULL
+             WHERE executing_since IS NOT NULL AND executing_since <= ?2",
+            params![serde_json::to_string(&ScheduleStatus::Pending)?, cutoff],
+        )?;
+        Ok(recovered)
+    }
+
+    pub fn schema_version(&self) -> Result<u32> {
+        let conn = self.pool.get().context("failed to get sqlite connection")?;
+        schema_version(&conn)
+    }
+
+    fn init(&self) -> Result<()> {
+        let mut conn = self.pool.get().context("failed to get sqlite connection")?;
+        enable_incremental_vacuum(&conn)?;
+        apply_migrations(&mut conn, MIGRATIONS).context("failed to migrate sqlite schema")?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StoragePort for SqliteStorage {
+    async fn insert_metrics(&self, samples: Vec<MetricSample>) -> Result<()> {
+        self.insert_metrics_batch(&samples)?;
+        Ok(())
+    }
+
+    async fn query_metrics(&self, query: MetricsQuery) -> Result<Vec<MetricSample>> {
+        let conn = self.pool.get().context("failed to get sqlite connection")?;
+        let (sql, params) = metrics_select_sql(&query)?;
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(params.iter()), sample_from_row)?;
+
         let mut samples = Vec::new();
         for row in rows {
             samples.push(row?);
         }
 
-        Ok(filter_metrics(samples, &query))
+        Ok(samples)
+    }
+
+    async fn list_cluster_ids(&self) -> Result<Vec<ClusterId>> {
+        let conn = self.pool.get().context("failed to get sqlite connection")?;
+        let mut stmt =
+            conn.prepare("SELECT DISTINCT cluster_id FROM metrics_raw ORDER BY cluster_id")?;
+        let ids = stmt.query_map([], |row| row.get(0))?;
+        Ok(ids.collect::<rusqlite::Result<_>>()?)
     }
 
     async fn insert_aggregated(&self, metrics: Vec<AggregatedMetric>) -> Result<()> {
@@ -239,34 +383,59 @@ impl StoragePort for SqliteStorage {
 
         let mut conn = self.pool.get().context("failed to get sqlite connection")?;
         let tx = conn.transaction().context("failed to begin transaction")?;
-        {
-            let mut stmt = tx.prepare(
-                "INSERT INTO metrics_aggregated
-                (cluster_id, resource_type, metric_type, window_start, window_duration, count, sum, min, max, avg, p50, p95, p99)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
-            )?;
-            for metric in metrics {
-                stmt.execute(params![
-                    metric.cluster_id,
-                    encode_enum(&metric.resource_type)?,
-                    encode_enum(&metric.metric_type)?,
-                    metric.window_start,
-                    metric.window_duration.as_millis() as i64,
-                    metric.count as i64,
-                    metric.sum,
-                    metric.min,
-                    metric.max,
-                    metric.avg,
-                    metric.p50,
-                    metric.p95,
-                    metric.p99
-                ])?;
-            }
-        }
+        insert_aggregated_rows(&tx, metrics)?;
         tx.commit().context("failed to commit aggregated metrics")?;
         Ok(())
     }
 
+    async fn commit_window(
+        &self,
+        window_start: i64,
+        window: Duration,
+        metrics: Vec<AggregatedMetric>,
+    ) -> Result<()> {
+        let mut conn = self.pool.get().context("failed to get sqlite connection")?;
+        let tx = conn.transaction().context("failed to begin transaction")?;
+        let marked = tx.execute(
+            "INSERT OR IGNORE INTO aggregation_windows (window_duration, window_start)
+             VALUES (?1, ?2)",
+            params![window.as_millis() as i64, window_start],
+        )?;
+        if marked > 0 {
+            insert_aggregated_rows(&tx, metrics)?;
+        }
+        tx.commit().context("failed to commit aggregation window")?;
+        Ok(())
+    }
+
+    async fn processed_windows(
+        &self,
+        window: Duration,
+        from_ms: i64,
+        to_ms: i64,
+    ) -> Result<Vec<i64>> {
+        let conn = self.pool.get().context("failed to get sqlite connection")?;
+        let mut stmt = conn.prepare(
+            "SELECT window_start FROM aggregation_windows
+             WHERE window_duration = ?1 AND window_start BETWEEN ?2 AND ?3
+             ORDER BY window_start",
+        )?;
+        let rows = stmt.query_map(params![window.as_millis() as i64, from_ms, to_ms], |row| {
+            row.get(0)
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<i64>>>()?)
+    }
+
+    async fn last_processed_window(&self, window: Duration) -> Result<Option<i64>> {
+        let conn = self.pool.get().context("failed to get sqlite connection")?;
+        let last = conn.query_row(
+            "SELECT MAX(window_start) FROM aggregation_windows WHERE window_duration = ?1",
+            params![window.as_millis() as i64],
+            |row| row.get(0),
+        )?;
+        Ok(last)
+    }
+
     async fn query_aggregated(&self, query: AggregatedQuery) -> Result<Vec<AggregatedMetric>> {
         let conn = self.pool.get().context("failed to get sqlite connection")?;
         let mut stmt = conn.prepare(
@@ -305,6 +474,81 @@ impl StoragePort for SqliteStorage {
         Ok(filter_aggregated(metrics, &query))
     }
 
+    async fn upsert_daily(&self, aggregates: Vec<DailyAggregate>) -> Result<()> {
+        if aggregates.is_empty() {
+            return Ok(());
+        }
+
+        let mut conn = self.pool.get().context("failed to get sqlite connection")?;
+        let tx = conn.transaction().context("failed to begin transaction")?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO metrics_daily
+                (cluster_id, resource_type, metric_type, day_start, count, sum, min, max, avg)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                 ON CONFLICT (cluster_id, resource_type, metric_type, day_start) DO UPDATE SET
+                    count = excluded.count, sum = excluded.sum, min = excluded.min,
+                    max = excluded.max, avg = excluded.avg",
+            )?;
+            for daily in aggregates {
+                stmt.execute(params![
+                    daily.cluster_id,
+                    encode_enum(&daily.resource_type)?,
+                    encode_enum(&daily.metric_type)?,
+                    daily.day_start,
+                    daily.count as i64,
+                    daily.sum,
+                    daily.min,
+                    daily.max,
+                    daily.avg
+                ])?;
+            }
+        }
+        tx.commit().context("failed to commit daily aggregates")?;
+        Ok(())
+    }
+
+    async fn query_daily(&self, query: AggregatedQuery) -> Result<Vec<DailyAggregate>> {
+        let conn = self.pool.get().context("failed to get sqlite connection")?;
+        let mut stmt = conn.prepare(
+            "SELECT cluster_id, resource_type, metric_type, day_start, count, sum, min, max, avg
+             FROM metrics_daily ORDER BY day_start",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let resource_type_str: String = row.get(1)?;
+            let metric_type_str: String = row.get(2)?;
+
+            Ok(DailyAggregate {
+                cluster_id: row.get(0)?,
+                resource_type: decode_enum(&resource_type_str)
+                    .map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))?,
+                metric_type: decode_enum(&metric_type_str)
+                    .map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))?,
+                day_start: row.get(3)?,
+                count: row.get::<_, i64>(4)? as u64,
+                sum: row.get(5)?,
+                min: row.get(6)?,
+                max: row.get(7)?,
+                avg: row.get(8)?,
+            })
+        })?;
+
+        let mut aggregates = Vec::new();
+        for row in rows {
+            let daily = row?;
+            if matches_aggregated_query(
+                &query,
+                &daily.cluster_id,
+                &daily.resource_type,
+                &daily.metric_type,
+                daily.day_start,
+            ) {
+                aggregates.push(daily);
+            }
+        }
+        Ok(aggregates)
+    }
+
     async fn insert_anomalies(&self, anomalies: Vec<phenome_domain::Anomaly>) -> Result<()> {
         if anomalies.is_empty() {
             return Ok(());
@@ -340,30 +584,21 @@ impl StoragePort for SqliteStorage {
         Ok(())
     }
 
-    async fn cleanup_retention(&self) -> Result<()> {
-        self.run_retention_cleanup(chrono::Utc::now().timestamp_millis())
+    async fn cleanup_retention(&self) -> Result<PruneStats> {
+        self.prune_expired(chrono::Utc::now().timestamp_millis())
     }
 
     async fn insert_schedule(&self, action: phenome_domain::ScheduledAction) -> Result<()> {
         let conn = self.pool.get().context("failed to get sqlite connection")?;
-        conn.execute(
-            "INSERT INTO scheduled_actions (id, execute_at, recommendation_id, action, status)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![
-                action.id,
-                action.execute_at,
-                action.recommendation_id,
-                serde_json::to_string(&action.action)?,
-                serde_json::to_string(&action.status)?,
-            ],
-        )?;
-        Ok(())
+        insert_schedule_row(&conn, &action)
     }
 
     async fn update_schedule(&self, action: phenome_domain::ScheduledAction) -> Result<()> {
         let conn = self.pool.get().context("failed to get sqlite connection")?;
         conn.execute(
-            "UPDATE scheduled_actions SET execute_at = ?2, recommendation_id = ?3, action = ?4, status = ?5
+            "UPDATE scheduled_actions SET execute_at = ?2, recommendation_id = ?3, action = ?4, status = ?5,
+                 executing_since = CASE WHEN ?6 IS NULL THEN NULL ELSE COALESCE(executing_since, ?6) END,
+                 attempts = ?7, cron = ?8
              WHERE id = ?1",
             params![
                 action.id,
@@ -371,6 +606,9 @@ impl StoragePort for SqliteStorage {
                 action.recommendation_id,
                 serde_json::to_string(&action.action)?,
                 serde_json::to_string(&action.status)?,
+                executing_since(&action.status),
+                action.attempts,
+                action.cron,
             ],
         )?;
         Ok(())
@@ -379,7 +617,7 @@ impl StoragePort for SqliteStorage {
     async fn get_all_schedules(&self) -> Result<Vec<phenome_domain::ScheduledAction>> {
         let conn = self.pool.get().context("failed to get sqlite connection")?;
         let mut stmt = conn.prepare(
-            "SELECT id, execute_at, recommendation_id, action, status FROM scheduled_actions",
+            "SELECT id, execute_at, recommendation_id, action, status, attempts, cron FROM scheduled_actions",
         )?;
         let rows = stmt.query_map([], |row| {
             let action_str: String = row.get(3)?;
@@ -392,6 +630,8 @@ impl StoragePort for SqliteStorage {
                     .map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))?,
                 status: serde_json::from_str(&status_str)
                     .map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))?,
+                attempts: row.get(5)?,
+                cron: row.get(6)?,
             })
         })?;
 
@@ -401,82 +641,359 @@ impl StoragePort for SqliteStorage {
         }
         Ok(actions)
     }
+
+    async fn recover_stuck_schedules(&self, timeout: Duration) -> Result<usize> {
+        self.recover_stuck_schedules_at(chrono::Utc::now().timestamp_millis(), timeout)
+    }
+
+    async fn record_execution(
+        &self,
+        id: ScheduleId,
+        outcome: ExecutionOutcome,
+        timestamp: i64,
+        detail: String,
+    ) -> Result<()> {
+        let conn = self.pool.get().context("failed to get sqlite connection")?;
+        conn.execute(
+            "INSERT INTO schedule_executions (schedule_id, outcome, timestamp, detail)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![id, encode_enum(&outcome)?, timestamp, detail],
+        )?;
+        Ok(())
+    }
+
+    async fn list_executions(&self, id: ScheduleId) -> Result<Vec<ScheduleExecution>> {
+        let conn = self.pool.get().context("failed to get sqlite connection")?;
+        let mut stmt = conn.prepare(
+            "SELECT outcome, timestamp, detail FROM schedule_executions
+             WHERE schedule_id = ?1 ORDER BY timestamp, id",
+        )?;
+        let rows = stmt.query_map(params![id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
+        let mut executions = Vec::new();
+        for row in rows {
+            let (outcome, timestamp, detail) = row?;
+            executions.push(ScheduleExecution {
+                schedule_id: id.clone(),
+                outcome: decode_enum(&outcome)?,
+                timestamp,
+                detail,
+            });
+        }
+        Ok(executions)
+    }
+
+    async fn upsert_recommendations(&self, recommendations: Vec<Recommendation>) -> Result<()> {
+        if recommendations.is_empty() {
+            return Ok(());
+        }
+
+        let mut conn = self.pool.get().context("failed to get sqlite connection")?;
+        let tx = conn.transaction().context("failed to begin transaction")?;
+        upsert_recommendation_rows(&tx, &recommendations)?;
+        tx.commit().context("failed to commit recommendations")?;
+        Ok(())
+    }
+
+    async fn commit_recommendation(
+        &self,
+        recommendation: Recommendation,
+        schedule: Option<phenome_domain::ScheduledAction>,
+    ) -> Result<()> {
+        let mut conn = self.pool.get().context("failed to get sqlite connection")?;
+        let tx = conn.transaction().context("failed to begin transaction")?;
+        if let Some(schedule) = &schedule {
+            insert_schedule_row(&tx, schedule)?;
+        }
+        upsert_recommendation_rows(&tx, std::slice::from_ref(&recommendation))?;
+        tx.commit().context("failed to commit recommendation")?;
+        Ok(())
+    }
+
+    async fn list_recommendations(&self) -> Result<Vec<Recommendation>> {
+        let conn = self.pool.get().context("failed to get sqlite connection")?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {RECOMMENDATION_COLUMNS} FROM recommendations ORDER BY created_at, id"
+        ))?;
+        let rows = stmt.query_map([], |row| {
+            Ok(RecommendationRow {
+                id: row.get(0)?,
+                cluster_id: row.get(1)?,
+                created_at: row.get(2)?,
+                recommendation_type: row.get(3)?,
+                priority: row.get(4)?,
+                confidence: row.get(5)?,
+                title: row.get(6)?,
+                description: row.get(7)?,
+                impact_estimate: row.get(8)?,
+                cost_impact_daily: row.get(9)?,
+                cost_impact_currency: row.get(10)?,
+                action: row.get(11)?,
+                status: row.get(12)?,
+                status_data: row.get(13)?,
+            })
+        })?;
+        let mut recommendations = Vec::new();
+        for row in rows {
+            recommendations.push(row?.decode()?);
+        }
+        Ok(recommendations)
+    }
+
+    async fn insert_notification(&self, notification: Notification) -> Result<()> {
+        let conn = self.pool.get().context("failed to get sqlite connection")?;
+        conn.execute(
+            "INSERT OR IGNORE INTO notifications (id, timestamp, read, data)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                notification.id,
+                notification.timestamp,
+                notification.read,
+                serde_json::to_string(&notification)?,
+            ],
+        )?;
+        Ok(())
+    }
+
+    async fn mark_notification_read(&self, id: String) -> Result<()> {
+        let conn = self.pool.get().context("failed to get sqlite connection")?;
+        conn.execute(
+            "UPDATE notifications SET read = 1 WHERE id = ?1",
+            params![id],
+        )?;
+        Ok(())
+    }
+
+    async fn list_unread_notifications(&self) -> Result<Vec<Notification>> {
+        let conn = self.pool.get().context("failed to get sqlite connection")?;
+        let mut stmt = conn
+            .prepare("SELECT data FROM notifications WHERE read = 0 ORDER BY timestamp DESC, id")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        let mut notifications = Vec::new();
+        for row in rows {
+            let notification: Notification = serde_json::from_str(&row?)?;
+            notifications.push(Notification {
+                read: false,
+                ..notification
+            });
+        }
+        Ok(notifications)
+    }
+
+    async fn ping(&self) -> Result<()> {
+        let conn = self.pool.get().context("failed to get sqlite connection")?;
+        conn.query_row("SELECT 1", [], |_| Ok(()))?;
+        Ok(())
+    }
+
+    fn export_metrics(
+        &self,
+        query: MetricsQuery,
+        format: ExportFormat,
+        writer: &mut dyn Write,
+    ) -> Result<usize> {
+        self.export(query, format, writer)
+    }
+
+    fn stream_metrics(
+        &self,
+        query: MetricsQuery,
+        batch_size: usize,
+        sink: &mut dyn FnMut(Vec<MetricSample>) -> bool,
+    ) -> Result<usize> {
+        self.read_batches(query, batch_size, sink)
+    }
+
+    fn query_downsampled(
+        &self,
+        query: MetricsQuery,
+        max_points: usize,
+    ) -> Result<DownsampledMetrics> {
+        SqliteStorage::query_downsampled(self, query, max_points)
+    }
+}
+
+fn insert_schedule_row(
+    conn: &rusqlite::Connection,
+    action: &phenome_domain::ScheduledAction,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO scheduled_actions (id, execute_at, recommendation_id, action, status, executing_since, attempts, cron)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            action.id,
+            action.execute_at,
+            action.recommendation_id,
+            serde_json::to_string(&action.action)?,
+            serde_json::to_string(&action.status)?,
+            executing_since(&action.status),
+            action.attempts,
+            action.cron,
+        ],
+    )?;
+    Ok(())
+}
+
+fn upsert_recommendation_rows(
+    conn: &rusqlite::Connection,
+    recommendations: &[Recommendation],
+) -> Result<()> {
+    let mut stmt = conn.prepare(&format!(
+        "INSERT OR REPLACE INTO recommendations ({RECOMMENDATION_COLUMNS})
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)"
+    ))?;
+    for recommendation in recommendations {
+        let row = RecommendationRow::encode(recommendation)?;
+        stmt.execute(params![
+            row.id,
+            row.cluster_id,
+            row.created_at,
+            row.recommendation_type,
+            row.priority,
+            row.confidence,
+            row.title,
+            row.description,
+            row.impact_estimate,
+            row.cost_impact_daily,
+            row.cost_impact_currency,
+            row.action,
+            row.status,
+            row.status_data,
+        ])?;
+    }
+    Ok(())
 }
 
-fn configure_sqlite(conn: &Connection) -> Result<()> {
-    conn.pragma_update(None, "journal_mode", &"WAL")?;
-    conn.pragma_update(None, "synchronous", &"NORMAL")?;
-    conn.pragma_update(None, "cache_size", &64_000)?;
+fn insert_aggregated_rows(
+    tx: &rusqlite::Transaction<'_>,
+    metrics: Vec<AggregatedMetric>,
+) -> Result<()> {
+    let mut stmt = tx.prepare(
+        "INSERT INTO metrics_aggregated
+        (cluster_id, resource_type, metric_type, window_start, window_duration, count, sum, min, max, avg, p50, p95, p99)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+    )?;
+    for metric in metrics {
+        stmt.execute(params![
+            metric.cluster_id,
+            encode_enum(&metric.resource_type)?,
+            encode_enum(&metric.metric_type)?,
+            metric.window_start,
+            metric.window_duration.as_millis() as i64,
+            metric.count as i64,
+            metric.sum,
+            metric.min,
+            metric.max,
+            metric.avg,
+            metric.p50,
+            metric.p95,
+            metric.p99
+        ])?;
+    }
     Ok(())
 }
 
-fn encode_enum<T: Serialize>(value: &T) -> Result<String> {
-    let json = serde_json::to_value(value)?;
-    match json {
-        serde_json::Value::String(value) => Ok(value),
-        _ => anyhow::bail!("expected string enum encoding"),
+fn configure_sqlite(conn: &mut Connection, tuning: &SqliteTuning) -> rusqlite::Result<()> {
+    conn.busy_timeout(tuning.busy_timeout)?;
+    conn.pragma_update(None, "journal_mode", &tuning.journal_mode)?;
+    conn.pragma_update(None, "synchronous", &tuning.synchronous)?;
+    conn.pragma_update(None, "cache_size", tuning.cache_size)?;
+    Ok(())
+}
+
+/// Switch the database to incremental auto-vacuum. The mode only takes
+/// effect after a full `VACUUM`, which is cheap on a fresh file and a
+/// one-off cost for databases created before pruning existed.
+fn enable_incremental_vacuum(conn: &Connection) -> Result<()> {
+    const INCREMENTAL: i64 = 2;
+    let mode: i64 = conn.query_row("PRAGMA auto_vacuum", [], |row| row.get(0))?;
+    if mode != INCREMENTAL {
+        conn.execute_batch("PRAGMA auto_vacuum = INCREMENTAL; VACUUM;")
+            .context("failed to enable incremental auto-vacuum")?;
     }
+    Ok(())
 }
 
-fn decode_enum<T: DeserializeOwned>(value: &str) -> Result<T> {
-    let value = serde_json::Value::String(value.to_string());
-    Ok(serde_json::from_value(value)?)
+/// Raw sample `SELECT` for `query`; range queries use
+/// `idx_metrics_raw_series` rather than scanning.
+pub(super) fn metrics_select_sql(query: &MetricsQuery) -> Result<(String, Vec<Value>)> {
+    let (where_sql, params) = metrics_filter_sql(query)?;
+    Ok((
+        format!("SELECT {METRIC_COLUMNS} FROM metrics_raw{where_sql}"),
+        params,
+    ))
 }
 
-fn filter_metrics(mut samples: Vec<MetricSample>, query: &MetricsQuery) -> Vec<MetricSample> {
-    samples.retain(|sample| matches_metrics_query(sample, query));
-    samples
+/// `WHERE` clause (with leading space) and positional parameters for the
+/// filters in `query`. Empty when the query has no filters.
+fn metrics_filter_sql(query: &MetricsQuery) -> Result<(String, Vec<Value>)> {
+    let (where_sql, values) = metrics_filter(query, |_| "?".to_string())?;
+    let values = values
+        .into_iter()
+        .map(|value| match value {
+            FilterValue::Text(text) => Value::Text(text),
+            FilterValue::Integer(int) => Value::Integer(int),
+        })
+        .collect();
+    Ok((where_sql, values))
 }
 
-fn matches_metrics_query(sample: &MetricSample, query: &MetricsQuery) -> bool {
-    query
-        .cluster_id
-        .as_ref()
-        .map_or(true, |id| id == &sample.cluster_id)
+fn sample_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<MetricSample> {
+    MetricRow {
+        cluster_id: row.get(0)?,
+        resource_type: row.get(1)?,
+        resource_id: row.get(2)?,
+        metric_type: row.get(3)?,
+        timestamp: row.get(4)?,
+        value: row.get(5)?,
+        unit: row.get(6)?,
+    }
+    .decode()
+    .map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))
+}
+
+fn filter_aggregated(
+    mut metrics: Vec<AggregatedMetric>,
+    query: &AggregatedQuery,
+) -> Vec<AggregatedMetric> {
+    metrics.retain(|metric| {
+        matches_aggregated_query(
+            query,
+            &metric.cluster_id,
+            &metric.resource_type,
+            &metric.metric_type,
+            metric.window_start,
+        )
+    });
+    metrics
+}
+
+fn matches_aggregated_query(
+    query: &AggregatedQuery,
+    cluster_id: &str,
+    resource_type: &ResourceType,
+    metric_type: &MetricType,
+    start: i64,
+) -> bool {
+    query.cluster_id.as_ref().is_none_or(|id| id == cluster_id)
         && query
             .resource_type
             .as_ref()
-            .map_or(true, |resource_type| resource_type == &sample.resource_type)
-        && (query.resource_ids.is_empty()
-            || query
-                .resource_ids
-                .iter()
-                .any(|id| id == &sample.resource_id))
+            .is_none_or(|wanted| wanted == resource_type)
         && (query.metric_types.is_empty()
             || query
                 .metric_types
                 .iter()
-                .any(|metric_type| metric_type == &sample.metric_type))
+                .any(|wanted| wanted == metric_type))
         && query
             .time_range
             .as_ref()
-            .map_or(true, |range| timestamp_in_range(sample.timestamp, range))
-}
-
-fn filter_aggregated(
-    mut metrics: Vec<AggregatedMetric>,
-    query: &AggregatedQuery,
-) -> Vec<AggregatedMetric> {
-    metrics.retain(|metric| {
-        query
-            .cluster_id
-            .as_ref()
-            .map_or(true, |id| id == &metric.cluster_id)
-            && query
-                .resource_type
-                .as_ref()
-                .map_or(true, |resource_type| resource_type == &metric.resource_type)
-            && (query.metric_types.is_empty()
-                || query
-                    .metric_types
-                    .iter()
-                    .any(|metric_type| metric_type == &metric.metric_type))
-            && query
-                .time_range
-                .as_ref()
-                .map_or(true, |range| timestamp_in_range(metric.window_start, range))
-    });
-    metrics
+            .is_none_or(|range| timestamp_in_range(start, range))
 }
 
 fn timestamp_in_range(timestamp: i64, range: &TimeRange) -> bool {
@@ -1,7 +1,15 @@
-use phenome_domain::{MetricSample, MetricType, MetricsQuery, ResourceType};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use crate::storage::port::StoragePort;
-use crate::storage::sqlite::SqliteStorage;
+use phenome_domain::{
+    AggregatedMetric, ExecutionOutcome, MetricSample, MetricType, MetricsQuery, Priority,
+    Recommendation, RecommendationAction, RecommendationStatus, RecommendationStatusKind,
+    RecommendationType, ResourceType, ScheduleStatus, ScheduledAction, TimeRange,
+};
+
+use crate::storage::export::ExportFormat;
+use crate::storage::port::{PruneStats, StoragePort};
+use crate::storage::sqlite::{RetentionConfig, SqliteStorage, SqliteTuning, metrics_select_sql};
 
 #[tokio::test]
 async fn sqlite_inserts_and_queries_metrics() {
@@ -36,3 +44,596 @@ async fn sqlite_inserts_and_queries_metrics() {
     assert_eq!(results.len(), 1);
     assert_eq!(results[0].resource_id, "pod-a");
 }
+
+#[tokio::test]
+async fn sqlite_lists_each_cluster_once() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("analytics.db");
+    let storage = SqliteStorage::new(db_path.to_string_lossy().to_string()).unwrap();
+    assert!(storage.list_cluster_ids().await.unwrap().is_empty());
+
+    let samples: Vec<MetricSample> = ["staging", "prod", "staging"]
+        .into_iter()
+        .enumerate()
+        .map(|(index, cluster_id)| MetricSample {
+            cluster_id: cluster_id.to_string(),
+            ..sample(index)
+        })
+        .collect();
+    storage.insert_metrics(samples).await.unwrap();
+
+    assert_eq!(
+        storage.list_cluster_ids().await.unwrap(),
+        ["prod", "staging"]
+    );
+}
+
+fn sample(index: usize) -> MetricSample {
+    MetricSample {
+        cluster_id: "cluster-1".to_string(),
+        resource_type: ResourceType::Pod,
+        resource_id: format!("pod-{}", index % 50),
+        metric_type: MetricType::CpuUsage,
+        timestamp: index as i64,
+        value: index as f64 * 0.01,
+        unit: "cores".to_string(),
+    }
+}
+
+#[tokio::test]
+async fn sqlite_batch_insert_outpaces_per_row_inserts() {
+    let dir = tempfile::tempdir().unwrap();
+    let batch_storage =
+        SqliteStorage::new(dir.path().join("batch.db").to_string_lossy().to_string()).unwrap();
+    let row_storage =
+        SqliteStorage::new(dir.path().join("rows.db").to_string_lossy().to_string()).unwrap();
+
+    let samples: Vec<MetricSample> = (0..10_000).map(sample).collect();
+    let started = Instant::now();
+    let inserted = batch_storage.insert_metrics_batch(&samples).unwrap();
+    let batch_elapsed = started.elapsed();
+    assert_eq!(inserted, 10_000);
+
+    // Time a tenth of the samples on the per-row path and extrapolate.
+    let started = Instant::now();
+    for sample in samples.iter().take(1_000) {
+        row_storage
+            .insert_metrics(vec![sample.clone()])
+            .await
+            .unwrap();
+    }
+    let per_row_elapsed = started.elapsed() * 10;
+
+    assert!(
+        batch_elapsed * 4 < per_row_elapsed,
+        "batch insert took {batch_elapsed:?}, per-row path estimated at {per_row_elapsed:?}"
+    );
+
+    let stored = batch_storage
+        .query_metrics(MetricsQuery::default())
+        .await
+        .unwrap();
+    assert_eq!(stored.len(), 10_000);
+}
+
+#[tokio::test]
+async fn sqlite_batch_insert_skips_malformed_samples() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = SqliteStorage::new(
+        dir.path()
+            .join("analytics.db")
+            .to_string_lossy()
+            .to_string(),
+    )
+    .unwrap();
+
+    let mut samples: Vec<MetricSample> = (0..10).map(sample).collect();
+    samples[3].value = f64::NAN;
+    samples[7].resource_id.clear();
+
+    let inserted = storage.insert_metrics_batch(&samples).unwrap();
+    assert_eq!(inserted, 8);
+
+    let stored = storage
+        .query_metrics(MetricsQuery::default())
+        .await
+        .unwrap();
+    assert_eq!(stored.len(), 8);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn sqlite_tolerates_concurrent_readers_and_writer() {
+    let dir = tempfile::tempdir().unwrap();
+    let tuning = SqliteTuning {
+        busy_timeout: Duration::from_secs(10),
+        ..SqliteTuning::default()
+    };
+    let storage = Arc::new(
+        SqliteStorage::with_tuning(
+            dir.path()
+                .join("analytics.db")
+                .to_string_lossy()
+                .to_string(),
+            RetentionConfig::default(),
+            tuning,
+        )
+        .unwrap(),
+    );
+
+    let writer = {
+        let storage = storage.clone();
+        tokio::spawn(async move {
+            for round in 0..20 {
+                let samples: Vec<MetricSample> =
+                    (round * 500..(round + 1) * 500).map(sample).collect();
+                storage.insert_metrics(samples).await.unwrap();
+            }
+        })
+    };
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let storage = storage.clone();
+            tokio::spawn(async move {
+                for _ in 0..20 {
+                    storage
+                        .query_metrics(MetricsQuery::default())
+                        .await
+                        .unwrap();
+                }
+            })
+        })
+        .collect();
+
+    writer.await.unwrap();
+    for reader in readers {
+        reader.await.unwrap();
+    }
+
+    let stored = storage
+        .query_metrics(MetricsQuery::default())
+        .await
+        .unwrap();
+    assert_eq!(stored.len(), 10_000);
+}
+
+#[tokio::test]
+async fn sqlite_downsamples_series_into_buckets() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = SqliteStorage::new(
+        dir.path()
+            .join("analytics.db")
+            .to_string_lossy()
+            .to_string(),
+    )
+    .unwrap();
+
+    let mut samples = Vec::new();
+    for resource_id in ["pod-a", "pod-b"] {
+        for minute in 0..600 {
+            samples.push(MetricSample {
+                cluster_id: "cluster-1".to_string(),
+                resource_type: ResourceType::Pod,
+                resource_id: resource_id.to_string(),
+                metric_type: MetricType::CpuUsage,
+                timestamp: minute * 60_000,
+                value: minute as f64,
+                unit: "cores".to_string(),
+            });
+        }
+    }
+    samples.push(MetricSample {
+        metric_type: MetricType::MemoryUsage,
+        ..samples[0].clone()
+    });
+    storage.insert_metrics_batch(&samples).unwrap();
+
+    let result = storage
+        .query_downsampled(
+            MetricsQuery {
+                resource_ids: vec!["pod-a".to_string()],
+                metric_types: vec![MetricType::CpuUsage],
+                time_range: Some(TimeRange {
+                    start_ms: 0,
+                    end_ms: 600 * 60_000 - 1,
+                }),
+                ..MetricsQuery::default()
+            },
+            50,
+        )
+        .unwrap();
+
+    assert_eq!(result.bucket_width_ms, 12 * 60_000);
+    assert_eq!(result.buckets.len(), 50);
+    assert!(result.buckets.iter().all(|b| b.resource_id == "pod-a"));
+    assert!(
+        result
+            .buckets
+            .iter()
+            .all(|b| b.metric_type == MetricType::CpuUsage)
+    );
+    let first = &result.buckets[0];
+    assert_eq!(first.bucket_start, 0);
+    assert_eq!(first.count, 12);
+    assert_eq!(first.min, 0.0);
+    assert_eq!(first.max, 11.0);
+    assert!((first.avg - 5.5).abs() < 1e-9);
+
+    let unbounded = storage
+        .query_downsampled(MetricsQuery::default(), 10)
+        .unwrap();
+    assert!(unbounded.buckets.len() <= 10 * 3);
+    assert!(
+        unbounded
+            .buckets
+            .iter()
+            .filter(|b| b.resource_id == "pod-b")
+            .count()
+            <= 10
+    );
+}
+
+#[test]
+fn sqlite_exports_csv_and_json_lines() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage =
+        SqliteStorage::new(dir.path().join("export.db").to_string_lossy().to_string()).unwrap();
+    let mut quoted = sample(1);
+    quoted.timestamp = 1_700_000_000_123;
+    quoted.resource_id = "pod,\"a\"".to_string();
+    let mut other = sample(2);
+    other.timestamp = 1_700_000_060_000;
+    other.cluster_id = "cluster-2".to_string();
+    storage.insert_metrics_batch(&[other, quoted]).unwrap();
+
+    let mut csv = Vec::new();
+    let written = storage
+        .export(MetricsQuery::default(), ExportFormat::Csv, &mut csv)
+        .unwrap();
+    assert_eq!(written, 2);
+    let csv = String::from_utf8(csv).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        lines[0],
+        "cluster_id,resource_type,resource_id,metric_type,timestamp_ms,timestamp,value,unit"
+    );
+    assert_eq!(
+        lines[1],
+        "cluster-1,pod,\"pod,\"\"a\"\"\",cpu_usage,1700000000123,2023-11-14T22:13:20.123Z,0.01,cores"
+    );
+    assert!(lines[2].starts_with("cluster-2,"));
+
+    let mut jsonl = Vec::new();
+    storage
+        .export(
+            MetricsQuery {
+                cluster_id: Some("cluster-2".to_string()),
+                ..MetricsQuery::default()
+            },
+            ExportFormat::JsonLines,
+            &mut jsonl,
+        )
+        .unwrap();
+    let jsonl = String::from_utf8(jsonl).unwrap();
+    assert_eq!(jsonl.lines().count(), 1);
+    let row: serde_json::Value = serde_json::from_str(jsonl.trim_end()).unwrap();
+    assert_eq!(row["timestamp_ms"], 1_700_000_060_000_i64);
+    assert_eq!(row["timestamp"], "2023-11-14T22:14:20.000Z");
+    assert_eq!(row["metric_type"], "cpu_usage");
+}
+
+#[test]
+fn sqlite_reads_metric_batches_within_time_range() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage =
+        SqliteStorage::new(dir.path().join("stream.db").to_string_lossy().to_string()).unwrap();
+    let samples: Vec<_> = (0..25).map(sample).collect();
+    storage.insert_metrics_batch(&samples).unwrap();
+    let query = MetricsQuery {
+        time_range: Some(TimeRange {
+            start_ms: 5,
+            end_ms: 16,
+        }),
+        ..MetricsQuery::default()
+    };
+
+    let mut batches = Vec::new();
+    let sent = storage
+        .read_batches(query.clone(), 5, |batch| {
+            batches.push(batch);
+            true
+        })
+        .unwrap();
+    assert_eq!(sent, 12);
+    let sizes: Vec<_> = batches.iter().map(Vec::len).collect();
+    assert_eq!(sizes, [5, 5, 2]);
+    let timestamps: Vec<_> = batches.iter().flatten().map(|s| s.timestamp).collect();
+    assert_eq!(timestamps, (5..=16).collect::<Vec<_>>());
+
+    // A sink refusing more, like a disconnected client, stops the read.
+    let mut calls = 0;
+    let sent = storage
+        .read_batches(query, 5, |_| {
+            calls += 1;
+            false
+        })
+        .unwrap();
+    assert_eq!((calls, sent), (1, 5));
+}
+
+fn aggregated(window_start: i64) -> AggregatedMetric {
+    AggregatedMetric {
+        cluster_id: "cluster-1".to_string(),
+        resource_type: ResourceType::Pod,
+        metric_type: MetricType::CpuUsage,
+        window_start,
+        window_duration: Duration::from_secs(3600),
+        count: 1,
+        sum: 1.0,
+        min: 1.0,
+        max: 1.0,
+        avg: 1.0,
+        p50: 1.0,
+        p95: 1.0,
+        p99: 1.0,
+    }
+}
+
+#[tokio::test]
+async fn sqlite_prune_expired_deletes_and_reclaims_pages() {
+    const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("prune.db");
+    let storage = SqliteStorage::with_retention(
+        db_path.to_string_lossy().to_string(),
+        RetentionConfig {
+            raw_days: 7,
+            aggregated_days: 30,
+            daily_days: 365,
+        },
+    )
+    .unwrap();
+    let now_ms = 100 * DAY_MS;
+
+    let mut samples: Vec<MetricSample> = (0..20_000)
+        .map(|index| {
+            let mut sample = sample(index);
+            sample.timestamp = now_ms - 8 * DAY_MS + index as i64;
+            sample
+        })
+        .collect();
+    let mut fresh = sample(0);
+    fresh.timestamp = now_ms - DAY_MS;
+    samples.push(fresh);
+    storage.insert_metrics_batch(&samples).unwrap();
+    storage
+        .insert_aggregated(vec![
+            aggregated(now_ms - 31 * DAY_MS),
+            aggregated(now_ms - 29 * DAY_MS),
+        ])
+        .await
+        .unwrap();
+
+    let page_count = |path: &std::path::Path| -> i64 {
+        rusqlite::Connection::open(path)
+            .unwrap()
+            .query_row("PRAGMA page_count", [], |row| row.get(0))
+            .unwrap()
+    };
+    let pages_before = page_count(&db_path);
+
+    let stats = storage.prune_expired(now_ms).unwrap();
+    assert_eq!(
+        stats,
+        PruneStats {
+            raw_rows: 20_000,
+            aggregated_rows: 1,
+            daily_rows: 0,
+        }
+    );
+    assert!(page_count(&db_path) < pages_before);
+
+    let remaining = storage
+        .query_metrics(MetricsQuery::default())
+        .await
+        .unwrap();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(
+        storage.prune_expired(now_ms).unwrap(),
+        PruneStats::default()
+    );
+}
+
+#[tokio::test]
+async fn sqlite_range_queries_use_series_index() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("index.db");
+    let storage = SqliteStorage::new(db_path.to_string_lossy().to_string()).unwrap();
+    let samples: Vec<MetricSample> = (0..5_000).map(sample).collect();
+    storage.insert_metrics_batch(&samples).unwrap();
+
+    let query = MetricsQuery {
+        cluster_id: Some("cluster-1".to_string()),
+        resource_type: None,
+        resource_ids: vec!["pod-7".to_string()],
+        metric_types: vec![MetricType::CpuUsage],
+        time_range: Some(TimeRange {
+            start_ms: 1_000,
+            end_ms: 2_000,
+        }),
+    };
+    let (sql, params) = metrics_select_sql(&query).unwrap();
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    let mut stmt = conn.prepare(&format!("EXPLAIN QUERY PLAN {sql}")).unwrap();
+    let plan: Vec<String> = stmt
+        .query_map(rusqlite::params_from_iter(params.iter()), |row| row.get(3))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert!(
+        plan.iter()
+            .any(|detail| detail.contains("USING INDEX idx_metrics_raw_series")),
+        "unexpected plan: {plan:?}"
+    );
+
+    let results = storage.query_metrics(query).await.unwrap();
+    assert_eq!(results.len(), 20);
+    assert!(
+        results
+            .iter()
+            .all(|s| s.resource_id == "pod-7" && (1_000..=2_000).contains(&s.timestamp))
+    );
+}
+
+fn schedule(id: &str, status: ScheduleStatus) -> ScheduledAction {
+    ScheduledAction {
+        id: id.to_string(),
+        execute_at: 1_000,
+        recommendation_id: "rec-1".to_string(),
+        action: RecommendationAction::ScaleDeployment {
+            name: "web".to_string(),
+            from: 2,
+            to: 3,
+        },
+        status,
+        attempts: 0,
+        cron: None,
+    }
+}
+
+#[tokio::test]
+async fn sqlite_recovers_only_stale_executing_schedules() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("analytics.db");
+    let storage = SqliteStorage::new(db_path.to_string_lossy().to_string()).unwrap();
+
+    for id in ["stale", "fresh"] {
+        storage
+            .insert_schedule(schedule(id, ScheduleStatus::Pending))
+            .await
+            .unwrap();
+        storage
+            .update_schedule(schedule(id, ScheduleStatus::Executing))
+            .await
+            .unwrap();
+    }
+    storage
+        .insert_schedule(schedule("done", ScheduleStatus::Completed))
+        .await
+        .unwrap();
+    // Simulate a crash an hour ago while "stale" was executing.
+    let now = chrono::Utc::now().timestamp_millis();
+    let conn = rusqlite::Connection::open(&db_path).unwrap();
+    conn.execute(
+        "UPDATE scheduled_actions SET executing_since = ?1 WHERE id = 'stale'",
+        [now - 60 * 60 * 1000],
+    )
+    .unwrap();
+
+    let recovered = storage
+        .recover_stuck_schedules(Duration::from_secs(15 * 60))
+        .await
+        .unwrap();
+    assert_eq!(recovered, 1);
+
+    let status = |id: &str, all: &[ScheduledAction]| {
+        all.iter()
+            .find(|action| action.id == id)
+            .map(|action| action.status.clone())
+            .unwrap()
+    };
+    let all = storage.get_all_schedules().await.unwrap();
+    assert!(matches!(status("stale", &all), ScheduleStatus::Pending));
+    assert!(matches!(status("fresh", &all), ScheduleStatus::Executing));
+    assert!(matches!(status("done", &all), ScheduleStatus::Completed));
+}
+
+#[tokio::test]
+async fn sqlite_keeps_execution_history_per_schedule() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("analytics.db");
+    let storage = SqliteStorage::new(db_path.to_string_lossy().to_string()).unwrap();
+
+    let attempts = [
+        ("nightly", ExecutionOutcome::Retrying, 2_000, "timeout"),
+        ("nightly", ExecutionOutcome::Succeeded, 3_000, "applied"),
+        ("other", ExecutionOutcome::Failed, 2_500, "not found"),
+        ("nightly", ExecutionOutcome::Failed, 1_000, "forbidden"),
+    ];
+    for (id, outcome, timestamp, detail) in attempts {
+        storage
+            .record_execution(id.to_string(), outcome, timestamp, detail.to_string())
+            .await
+            .unwrap();
+    }
+
+    let history = storage
+        .list_executions("nightly".to_string())
+        .await
+        .unwrap();
+    let entries: Vec<_> = history
+        .iter()
+        .map(|entry| (entry.outcome, entry.timestamp, entry.detail.as_str()))
+        .collect();
+    assert_eq!(
+        entries,
+        [
+            (ExecutionOutcome::Failed, 1_000, "forbidden"),
+            (ExecutionOutcome::Retrying, 2_000, "timeout"),
+            (ExecutionOutcome::Succeeded, 3_000, "applied"),
+        ]
+    );
+    assert!(history.iter().all(|entry| entry.schedule_id == "nightly"));
+}
+
+#[tokio::test]
+async fn sqlite_commits_recommendation_status_with_its_schedule() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("analytics.db");
+    let storage = SqliteStorage::new(db_path.to_string_lossy().to_string()).unwrap();
+
+    let mut recommendation = Recommendation {
+        id: "rec-1".to_string(),
+        cluster_id: "prod".to_string(),
+        created_at: 1_000,
+        recommendation_type: RecommendationType::ScaleUp,
+        priority: Priority::Medium,
+        confidence: 0.8,
+        title: "Scale web".to_string(),
+        description: "web is saturated".to_string(),
+        impact_estimate: "adds 1 replica".to_string(),
+        cost_impact: None,
+        action: schedule("taken", ScheduleStatus::Pending).action,
+        status: RecommendationStatus::Pending,
+    };
+    storage
+        .upsert_recommendations(vec![recommendation.clone()])
+        .await
+        .unwrap();
+    storage
+        .insert_schedule(schedule("taken", ScheduleStatus::Pending))
+        .await
+        .unwrap();
+
+    // A schedule that fails to insert must leave the recommendation pending.
+    recommendation.status = RecommendationStatus::Scheduled { execute_at: 1_000 };
+    let conflict = storage
+        .commit_recommendation(
+            recommendation.clone(),
+            Some(schedule("taken", ScheduleStatus::Pending)),
+        )
+        .await;
+    assert!(conflict.is_err());
+    let stored = storage.list_recommendations().await.unwrap();
+    assert_eq!(stored[0].status.kind(), RecommendationStatusKind::Pending);
+
+    storage
+        .commit_recommendation(
+            recommendation,
+            Some(schedule("queued", ScheduleStatus::Pending)),
+        )
+        .await
+        .unwrap();
+    let stored = storage.list_recommendations().await.unwrap();
+    assert_eq!(stored[0].status.kind(), RecommendationStatusKind::Scheduled);
+    assert_eq!(storage.get_all_schedules().await.unwrap().len(), 2);
+}
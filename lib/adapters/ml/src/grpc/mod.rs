@@ -77,12 +77,18 @@ impl MlServiceTrait for MlGrpcService {
             .map_err(|e| Status::internal(e.to_string()))?;
 
         Ok(Response::new(PredictScalingNeedsResponse {
-            prediction: Some(ml::ScalingPrediction {
+            prediction: Some(analytics::ScalingPrediction {
                 resource_id: req.resource_id,
                 generated_at: prediction.generated_at,
                 horizon: prediction.horizon.as_millis() as i64,
                 predicted_value: prediction.predicted_value,
                 unit: prediction.unit,
+                metric_type: analytics::MetricType::from(prediction.metric_type).into(),
+                lower_bound: prediction.lower_bound,
+                upper_bound: prediction.upper_bound,
+                time_to_threshold_ms: prediction
+                    .time_to_threshold
+                    .map(|ahead| ahead.as_millis() as i64),
             }),
         }))
     }
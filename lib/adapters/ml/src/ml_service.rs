@@ -2,7 +2,9 @@ use anyhow::Result;
 use async_trait::async_trait;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use phenome_domain::{Anomaly, ClusterId, Recommendation, ScalingPrediction, TimeSeriesData};
+use phenome_domain::{
+    Anomaly, ClusterId, Recommendation, ScalingPrediction, TimeSeries, TimeSeriesData,
+};
 use phenome_ml::{AnomalyDetector, RecommendationEngine, ScalingPredictor};
 use phenome_ports::MLPort;
 
@@ -55,9 +57,13 @@ impl MLPort for MlService {
         resource_id: String,
         horizon: Duration,
     ) -> Result<ScalingPrediction> {
-        let generated_at = now_millis();
+        let series = TimeSeries {
+            resource_id,
+            unit: "unknown".to_string(),
+            ..TimeSeries::default()
+        };
         self.scaling_predictor
-            .predict(resource_id, horizon, &[], "unknown", generated_at)
+            .predict(&series, horizon, now_millis())
     }
 
     async fn generate_recommendations(&self, cluster_id: ClusterId) -> Result<Vec<Recommendation>> {
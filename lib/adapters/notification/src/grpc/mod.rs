@@ -134,6 +134,9 @@ impl TryFrom<notification::NotificationChannel> for domain::NotificationChannel
             name: val.name,
             enabled: val.enabled,
             config_json,
+            min_severity: analytics::Severity::try_from(val.min_severity)
+                .map_err(|_| anyhow::anyhow!("invalid min_severity"))?
+                .try_into()?,
             ..Default::default()
         })
     }
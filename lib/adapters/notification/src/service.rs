@@ -2,18 +2,29 @@ use anyhow::Result;
 use phenome_domain::notification::{Notification, NotificationChannel};
 use phenome_ports::NotificationPort;
 
+/// Notification port without delivery or an inbox. The storage-backed
+/// service lives in the analytics adapter.
 #[derive(Debug)]
 pub struct NotificationService;
 
 #[tonic::async_trait]
 impl NotificationPort for NotificationService {
     async fn send_notification(&self, notification: Notification) -> Result<()> {
-        println!("Sending notification: {:?}", notification);
+        tracing::debug!("Dropping notification {}: no delivery", notification.id);
         Ok(())
     }
 
     async fn configure_channel(&self, channel: NotificationChannel) -> Result<()> {
-        println!("Configuring channel: {:?}", channel);
+        tracing::debug!("Ignoring notification channel {}", channel.id);
         Ok(())
     }
+
+    async fn mark_read(&self, id: String) -> Result<()> {
+        tracing::debug!("Ignoring read mark for notification {}: no inbox", id);
+        Ok(())
+    }
+
+    async fn list_unread(&self) -> Result<Vec<Notification>> {
+        Ok(Vec::new())
+    }
 }
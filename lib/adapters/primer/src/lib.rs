@@ -1,7 +1,7 @@
 pub mod controller;
 mod runtime;
 
-pub use runtime::{assembly, bootstrap, health, mapping};
+pub use runtime::{assembly, bootstrap, cache, health, mapping};
 
 use anyhow::{Context, Result};
 use std::path::PathBuf;
@@ -16,6 +16,9 @@ use tokio::sync::mpsc;
 pub use runtime::bootstrap::BootstrapAdapter;
 pub use runtime::health::LiveStatus;
 
+const CONFIG_PATH_ENV: &str = "PRIMER_CONFIG_PATH";
+const DEFAULT_CONFIG_PATH: &str = "../primer/data/configs/bootstrap-config.yaml";
+
 pub struct PrimerBackend {
     pub config: Arc<primer_api::contract::config::Config>,
     pub config_path: PathBuf,
@@ -32,7 +35,7 @@ pub struct PrimerBackend {
 
 impl PrimerBackend {
     pub fn from_env() -> Result<Self> {
-        let config_path = std::env::var("PRIMER_CONFIG_PATH")
+        let config_path = std::env::var(CONFIG_PATH_ENV)
             .map(PathBuf::from)
             .ok();
         let assembly_path = std::env::var("PRIMER_ASSEMBLY_PATH")
@@ -78,8 +81,7 @@ impl PrimerBackend {
         bootstrap_command_tx: mpsc::Sender<InteractiveCommand>,
         bootstrap_command_rx: Option<mpsc::Receiver<InteractiveCommand>>,
     ) -> Result<Self> {
-        let config_path = config_path
-            .unwrap_or_else(|| PathBuf::from("../primer/data/configs/bootstrap-config.yaml"));
+        let config_path = resolve_config_path(config_path)?;
         let config =
             primer::application::config::load_from_file(&config_path).with_context(|| {
                 format!(
@@ -97,9 +99,11 @@ impl PrimerBackend {
         let assembly_error = assembly_port.assembly_error();
         let bootstrap_assembly = assembly_port.primer_assembly().unwrap_or_default();
         let health_port = health::PrimerHealthPort::new(live_status.clone());
+        let cache_port = cache::PrimerCachePort::spawn(live_status.clone());
         let mut ports = PortSet::empty();
         ports.assembly = Arc::new(assembly_port);
         ports.health = Arc::new(health_port);
+        ports.cache = Arc::new(cache_port);
         ports.logs = Arc::new(PrimerLogPort);
         let (bootstrap_runtime, handle) = match tokio::runtime::Handle::try_current() {
             Ok(handle) => (None, handle),
@@ -159,6 +163,30 @@ impl PrimerBackend {
     }
 }
 
+/// Pick the Primer config file, failing early with the path that was tried
+/// and how to point at the right one. The default only resolves when Phenome
+/// runs from a checkout next to Primer.
+fn resolve_config_path(config_path: Option<PathBuf>) -> Result<PathBuf> {
+    let (path, source) = match config_path {
+        Some(path) => (
+            path,
+            format!("set via {CONFIG_PATH_ENV} or passed explicitly"),
+        ),
+        None => (
+            PathBuf::from(DEFAULT_CONFIG_PATH),
+            format!("the default used when {CONFIG_PATH_ENV} is unset"),
+        ),
+    };
+    if !path.is_file() {
+        anyhow::bail!(
+            "Primer config not found at {} ({source}); set {CONFIG_PATH_ENV} to the path of \
+             bootstrap-config.yaml",
+            path.display()
+        );
+    }
+    Ok(path)
+}
+
 #[derive(Clone, Copy)]
 struct PrimerLogPort;
 
@@ -167,3 +195,23 @@ impl LogPort for PrimerLogPort {
         Vec::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_config_path_names_file_and_env_var() {
+        let dir = std::env::temp_dir().join("phenome-missing-primer-config");
+        let missing = dir.join("bootstrap-config.yaml");
+
+        let message = resolve_config_path(Some(missing.clone()))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            message.contains(&missing.display().to_string()),
+            "{message}"
+        );
+        assert!(message.contains(CONFIG_PATH_ENV), "{message}");
+    }
+}
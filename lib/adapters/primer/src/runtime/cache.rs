@@ -0,0 +1,154 @@
+use std::sync::{Arc, RwLock, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use primer::application::runtime::modules::runtime::k8s::cache::ClusterCache;
+
+use phenome_ports::CachePort;
+
+use super::health::LiveStatus;
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Latest value published by a refresher, stamped with when its source last
+/// observed it.
+pub struct RefreshingCache<T> {
+    latest: Arc<RwLock<Option<(T, Instant)>>>,
+}
+
+impl<T> Clone for RefreshingCache<T> {
+    fn clone(&self) -> Self {
+        Self {
+            latest: Arc::clone(&self.latest),
+        }
+    }
+}
+
+impl<T> Default for RefreshingCache<T> {
+    fn default() -> Self {
+        Self {
+            latest: Arc::new(RwLock::new(None)),
+        }
+    }
+}
+
+impl<T: Clone> RefreshingCache<T> {
+    pub fn store(&self, value: T, observed_at: Instant) {
+        if let Ok(mut guard) = self.latest.write() {
+            *guard = Some((value, observed_at));
+        }
+    }
+
+    pub fn latest(&self) -> Option<T> {
+        self.latest
+            .read()
+            .ok()
+            .and_then(|guard| guard.as_ref().map(|(value, _)| value.clone()))
+    }
+
+    /// Time since the source observed the latest value, not since it was
+    /// copied here.
+    pub fn age(&self) -> Option<Duration> {
+        self.latest
+            .read()
+            .ok()
+            .and_then(|guard| guard.as_ref().map(|(_, observed)| observed.elapsed()))
+    }
+
+    fn downgrade(&self) -> Weak<RwLock<Option<(T, Instant)>>> {
+        Arc::downgrade(&self.latest)
+    }
+}
+
+/// Cluster cache re-read from the live status watcher in the background.
+/// Entries carry the watcher's own observation time, so the age keeps growing
+/// once the watcher stops hearing from the cluster and readers can tell the
+/// data is stale.
+#[derive(Clone)]
+pub struct PrimerCachePort {
+    live_status: Option<LiveStatus>,
+    cache: RefreshingCache<ClusterCache>,
+}
+
+impl PrimerCachePort {
+    /// Start refreshing from `live_status` every few seconds. The refresher
+    /// stops once every clone of the port is dropped.
+    pub fn spawn(live_status: Option<LiveStatus>) -> Self {
+        let port = Self {
+            live_status,
+            cache: RefreshingCache::default(),
+        };
+        port.refresh();
+        if let Some(live) = port.live_status.clone() {
+            let cache = port.cache.downgrade();
+            thread::spawn(move || {
+                loop {
+                    thread::sleep(REFRESH_INTERVAL);
+                    let Some(latest) = cache.upgrade() else {
+                        break;
+                    };
+                    refresh_from(&live, &RefreshingCache { latest });
+                }
+            });
+        }
+        port
+    }
+
+    /// Pull the current cache from the watcher now.
+    pub fn refresh(&self) {
+        if let Some(live) = &self.live_status {
+            refresh_from(live, &self.cache);
+        }
+    }
+
+    pub fn cache(&self) -> Option<ClusterCache> {
+        self.cache.latest()
+    }
+}
+
+impl CachePort for PrimerCachePort {
+    fn cache_age(&self) -> Option<Duration> {
+        self.cache.age()
+    }
+}
+
+fn refresh_from(live: &LiveStatus, cache: &RefreshingCache<ClusterCache>) {
+    if live.last_error().is_some() {
+        return;
+    }
+    if let (Some(cluster_cache), Some(observed_at)) = (live.cache(), live.observed_at()) {
+        cache.store(cluster_cache, observed_at);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exposes_age_and_latest_value_after_refresh() {
+        let cache = RefreshingCache::default();
+        assert_eq!(cache.latest(), None::<u32>);
+        assert_eq!(cache.age(), None);
+
+        cache.store(1, Instant::now());
+        thread::sleep(Duration::from_millis(20));
+        let first_age = cache.age().expect("age after first refresh");
+        assert!(first_age >= Duration::from_millis(20));
+
+        cache.store(2, Instant::now());
+        assert_eq!(cache.latest(), Some(2));
+        assert!(cache.age().expect("age after second refresh") < first_age);
+    }
+
+    #[test]
+    fn age_counts_from_the_observation_not_the_copy() {
+        let cache = RefreshingCache::default();
+        let observed_at = Instant::now();
+        thread::sleep(Duration::from_millis(20));
+
+        // A refresh that copies an old observation must not look fresh.
+        cache.store(1, observed_at);
+        assert!(cache.age().expect("age after refresh") >= Duration::from_millis(20));
+    }
+}
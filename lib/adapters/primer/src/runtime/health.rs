@@ -1,8 +1,9 @@
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::sync::{Arc, RwLock};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use primer::application::runtime::modules::runtime::k8s::cache::ClusterCache;
 use primer::application::runtime::registry;
@@ -13,93 +14,76 @@ use kube::Client;
 use phenome_domain::{ComponentHealthStatus, HealthSnapshot};
 use phenome_ports::HealthPort;
 
+/// Starts a watcher for a [`LiveStatus`]. Returns once the watcher is
+/// connected, or with the error that prevented it.
+pub type LiveStatusSpawner = Arc<dyn Fn(&LiveStatus) -> Result<(), String> + Send + Sync>;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[derive(Clone)]
 pub struct LiveStatus {
     cache: Arc<RwLock<Option<ClusterCache>>>,
+    /// When the watcher last heard from the cluster: on connect and after
+    /// every health round.
+    observed: Arc<RwLock<Option<Instant>>>,
     health: Arc<RwLock<HashMap<String, HealthStatus>>>,
     error: Arc<RwLock<Option<String>>>,
     shutdown: Arc<AtomicBool>,
+    generation: Arc<AtomicU64>,
+    spawner: LiveStatusSpawner,
 }
 
 impl LiveStatus {
     pub fn spawn(config: Arc<Config>) -> Self {
-        let live = Self {
-            cache: Arc::new(RwLock::new(None)),
-            health: Arc::new(RwLock::new(HashMap::new())),
-            error: Arc::new(RwLock::new(None)),
-            shutdown: Arc::new(AtomicBool::new(false)),
-        };
-
         if std::env::var("PHENOME_DISABLE_LIVE_STATUS")
             .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
             .unwrap_or(false)
         {
-            return live;
+            return Self::with_spawner(Arc::new(|_| Ok(())));
         }
 
-        let cache = Arc::clone(&live.cache);
-        let health = Arc::clone(&live.health);
-        let error = Arc::clone(&live.error);
-        let shutdown = Arc::clone(&live.shutdown);
-
-        thread::spawn(move || {
-            let runtime = tokio::runtime::Builder::new_multi_thread()
-                .enable_all()
-                .build();
-
-            let runtime = match runtime {
-                Ok(rt) => rt,
-                Err(err) => {
-                    if let Ok(mut guard) = error.write() {
-                        *guard = Some(format!("Failed to build tokio runtime: {err}"));
-                    }
-                    return;
-                }
-            };
-
-            runtime.block_on(async move {
-                if let Err(err) = init_cache(&cache).await {
-                    if let Ok(mut guard) = error.write() {
-                        *guard = Some(err);
-                    }
-                }
-
-                let mut interval = tokio::time::interval(Duration::from_secs(15));
-                loop {
-                    interval.tick().await;
-                    if shutdown.load(Ordering::Relaxed) {
-                        break;
-                    }
-                    let ctx = ModuleContext::new(Arc::clone(&config), ModuleMode::Render);
-                    let modules = registry::get_all_modules(config.as_ref());
-                    let mut results = HashMap::new();
-
-                    for module in modules {
-                        if !module.enabled(&ctx) {
-                            continue;
-                        }
-                        let name = module.spec().name.to_string();
-                        let status = match module.check().await {
-                            Ok(status) => status,
-                            Err(err) => HealthStatus::Unhealthy(err.to_string()),
-                        };
-                        results.insert(name, status);
-                    }
-
-                    if let Ok(mut guard) = health.write() {
-                        *guard = results;
-                    }
-                }
-            });
-        });
+        Self::with_spawner(Arc::new(move |live| {
+            spawn_watcher(live, Arc::clone(&config))
+        }))
+    }
 
+    /// Build a status whose watcher is started by `spawner`, now and on every
+    /// [`reconnect`](Self::reconnect).
+    pub fn with_spawner(spawner: LiveStatusSpawner) -> Self {
+        let live = Self {
+            cache: Arc::new(RwLock::new(None)),
+            observed: Arc::new(RwLock::new(None)),
+            health: Arc::new(RwLock::new(HashMap::new())),
+            error: Arc::new(RwLock::new(None)),
+            shutdown: Arc::new(AtomicBool::new(false)),
+            generation: Arc::new(AtomicU64::new(0)),
+            spawner,
+        };
+        if let Err(err) = (live.spawner)(&live) {
+            live.set_error(Some(err));
+        }
         live
     }
 
+    /// Retire the current watcher and start a new one. Clones share the
+    /// restarted watcher. On success the last error is cleared.
+    pub fn reconnect(&self) -> Result<(), String> {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+        self.shutdown.store(false, Ordering::Relaxed);
+        let result = (self.spawner)(self);
+        self.set_error(result.as_ref().err().cloned());
+        result
+    }
+
     pub fn cache(&self) -> Option<ClusterCache> {
         self.cache.read().ok().and_then(|guard| guard.clone())
     }
 
+    /// When the watcher last observed the cluster, `None` until it connects.
+    pub fn observed_at(&self) -> Option<Instant> {
+        self.observed.read().ok().and_then(|guard| *guard)
+    }
+
     pub fn health(&self) -> HashMap<String, HealthStatus> {
         self.health
             .read()
@@ -114,6 +98,91 @@ impl LiveStatus {
     pub fn stop(&self) {
         self.shutdown.store(true, Ordering::Relaxed);
     }
+
+    fn set_error(&self, error: Option<String>) {
+        if let Ok(mut guard) = self.error.write() {
+            *guard = error;
+        }
+    }
+}
+
+/// Run the cache and health watcher for `live` on its own runtime thread.
+/// The watcher exits on [`LiveStatus::stop`] or once a reconnect replaces it.
+fn spawn_watcher(live: &LiveStatus, config: Arc<Config>) -> Result<(), String> {
+    let cache = Arc::clone(&live.cache);
+    let observed = Arc::clone(&live.observed);
+    let health = Arc::clone(&live.health);
+    let error = Arc::clone(&live.error);
+    let shutdown = Arc::clone(&live.shutdown);
+    let generation = Arc::clone(&live.generation);
+    let current = generation.load(Ordering::Relaxed);
+    let (connected_tx, connected_rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build();
+
+        let runtime = match runtime {
+            Ok(rt) => rt,
+            Err(err) => {
+                let _ = connected_tx.send(Err(format!("Failed to build tokio runtime: {err}")));
+                return;
+            }
+        };
+
+        runtime.block_on(async move {
+            let connected = init_cache(&cache, &observed).await;
+            // A slow connect may finish after the caller timed out; record
+            // the outcome unless a reconnect has replaced this watcher.
+            if generation.load(Ordering::Relaxed) == current
+                && let Ok(mut guard) = error.write()
+            {
+                *guard = connected.as_ref().err().cloned();
+            }
+            let _ = connected_tx.send(connected);
+
+            let mut interval = tokio::time::interval(Duration::from_secs(15));
+            loop {
+                interval.tick().await;
+                let replaced = generation.load(Ordering::Relaxed) != current;
+                if shutdown.load(Ordering::Relaxed) || replaced {
+                    break;
+                }
+                let ctx = ModuleContext::new(Arc::clone(&config), ModuleMode::Render);
+                let modules = registry::get_all_modules(config.as_ref());
+                let mut results = HashMap::new();
+
+                for module in modules {
+                    if !module.enabled(&ctx) {
+                        continue;
+                    }
+                    let name = module.spec().name.to_string();
+                    let status = match module.check().await {
+                        Ok(status) => status,
+                        Err(err) => HealthStatus::Unhealthy(err.to_string()),
+                    };
+                    results.insert(name, status);
+                }
+
+                if let Ok(mut guard) = health.write() {
+                    *guard = results;
+                }
+                observe(&observed);
+            }
+        });
+    });
+
+    match connected_rx.recv_timeout(CONNECT_TIMEOUT) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => Err(format!(
+            "Timed out after {}s connecting to the cluster",
+            CONNECT_TIMEOUT.as_secs()
+        )),
+        Err(RecvTimeoutError::Disconnected) => {
+            Err("Live status watcher exited before connecting".to_string())
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -143,6 +212,13 @@ impl HealthPort for PrimerHealthPort {
             cache_ready: live.cache().is_some(),
         }
     }
+
+    fn reconnect(&self) -> Result<(), String> {
+        match &self.live_status {
+            Some(live) => live.reconnect(),
+            None => Err("Live status is not configured".to_string()),
+        }
+    }
 }
 
 fn map_health_status(status: HealthStatus) -> ComponentHealthStatus {
@@ -153,7 +229,16 @@ fn map_health_status(status: HealthStatus) -> ComponentHealthStatus {
     }
 }
 
-async fn init_cache(cache: &Arc<RwLock<Option<ClusterCache>>>) -> Result<(), String> {
+fn observe(observed: &RwLock<Option<Instant>>) {
+    if let Ok(mut guard) = observed.write() {
+        *guard = Some(Instant::now());
+    }
+}
+
+async fn init_cache(
+    cache: &Arc<RwLock<Option<ClusterCache>>>,
+    observed: &RwLock<Option<Instant>>,
+) -> Result<(), String> {
     let client = Client::try_default()
         .await
         .map_err(|err| format!("Failed to init kube client: {err}"))?;
@@ -162,5 +247,37 @@ async fn init_cache(cache: &Arc<RwLock<Option<ClusterCache>>>) -> Result<(), Str
     if let Ok(mut guard) = cache.write() {
         *guard = Some(cluster_cache.clone());
     }
+    observe(observed);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn reconnect_replaces_failed_watcher() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let spawner: LiveStatusSpawner = {
+            let attempts = Arc::clone(&attempts);
+            Arc::new(move |_| match attempts.fetch_add(1, Ordering::Relaxed) {
+                0 => Err("connection refused".to_string()),
+                _ => Ok(()),
+            })
+        };
+
+        let live = LiveStatus::with_spawner(spawner);
+        let port = PrimerHealthPort::new(Some(live.clone()));
+        assert_eq!(live.last_error().as_deref(), Some("connection refused"));
+        assert_eq!(
+            port.snapshot().last_error.as_deref(),
+            Some("connection refused")
+        );
+
+        assert_eq!(port.reconnect(), Ok(()));
+        assert_eq!(attempts.load(Ordering::Relaxed), 2);
+        assert_eq!(live.last_error(), None);
+        assert_eq!(port.snapshot().last_error, None);
+    }
+}
@@ -1,4 +1,5 @@
 pub mod assembly;
 pub mod bootstrap;
+pub mod cache;
 pub mod health;
 pub mod mapping;
@@ -24,6 +24,13 @@ pub struct NotificationChannel {
     // Or typed config if handled elsewhere
     #[serde(skip)]
     pub config: serde_json::Value,
+    /// Least severe notification this channel receives.
+    #[serde(default = "default_min_severity")]
+    pub min_severity: Severity,
+}
+
+fn default_min_severity() -> Severity {
+    Severity::Info
 }
 
 impl Default for Notification {
@@ -50,6 +57,7 @@ impl Default for NotificationChannel {
             enabled: false,
             config_json: "{}".to_string(),
             config: serde_json::Value::Null,
+            min_severity: Severity::Info,
         }
     }
 }
@@ -16,6 +16,23 @@ pub enum RecommendationType {
     StorageOptimization,
 }
 
+/// From the wire value in `analytics.proto`. A type added to the proto after
+/// this build is an error, not a stand-in default.
+impl TryFrom<i32> for RecommendationType {
+    type Error = anyhow::Error;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::ScaleUp),
+            2 => Ok(Self::ScaleDown),
+            3 => Ok(Self::OptimizeResources),
+            4 => Ok(Self::AdjustLimits),
+            5 => Ok(Self::StorageOptimization),
+            _ => anyhow::bail!("unknown recommendation type {value}"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Priority {
@@ -24,6 +41,20 @@ pub enum Priority {
     Low,
 }
 
+/// From the wire value in `analytics.proto`.
+impl TryFrom<i32> for Priority {
+    type Error = anyhow::Error;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::High),
+            2 => Ok(Self::Medium),
+            3 => Ok(Self::Low),
+            _ => anyhow::bail!("unknown priority {value}"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CostImpact {
     pub daily_change: f64,
@@ -114,6 +145,13 @@ pub struct ScheduledAction {
     pub recommendation_id: String,
     pub action: RecommendationAction,
     pub status: ScheduleStatus,
+    /// Failed executions so far; drives the retry backoff.
+    #[serde(default)]
+    pub attempts: u32,
+    /// Cron expression for recurring actions; `execute_at` then holds the
+    /// next run and the action returns to `Pending` after each success.
+    #[serde(default)]
+    pub cron: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -125,3 +163,25 @@ pub enum ScheduleStatus {
     Failed { error: String },
     Cancelled,
 }
+
+/// Result of one attempt to run a scheduled action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionOutcome {
+    Succeeded,
+    /// Failed with retries left; the action was rescheduled.
+    Retrying,
+    Failed,
+    /// Not run, because the cluster no longer matched the action's
+    /// assumptions or the scheduler has no executor for the action.
+    Skipped,
+}
+
+/// Audit entry for one attempt, kept after the schedule's status moves on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleExecution {
+    pub schedule_id: ScheduleId,
+    pub outcome: ExecutionOutcome,
+    pub timestamp: i64,
+    pub detail: String,
+}
@@ -58,6 +58,20 @@ pub struct AggregatedMetric {
     pub p99: f64,
 }
 
+/// One UTC day of a series, rolled up from its hourly aggregates.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyAggregate {
+    pub cluster_id: ClusterId,
+    pub resource_type: ResourceType,
+    pub metric_type: MetricType,
+    pub day_start: i64,
+    pub count: u64,
+    pub sum: f64,
+    pub min: f64,
+    pub max: f64,
+    pub avg: f64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct MetricsQuery {
     pub cluster_id: Option<ClusterId>,
@@ -69,6 +83,28 @@ pub struct MetricsQuery {
     pub time_range: Option<TimeRange>,
 }
 
+/// Time bucket of raw samples for a single series.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricBucket {
+    pub cluster_id: ClusterId,
+    pub resource_type: ResourceType,
+    pub resource_id: String,
+    pub metric_type: MetricType,
+    pub bucket_start: i64,
+    pub count: u64,
+    pub avg: f64,
+    pub min: f64,
+    pub max: f64,
+    pub unit: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DownsampledMetrics {
+    pub bucket_width_ms: i64,
+    #[serde(default)]
+    pub buckets: Vec<MetricBucket>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AggregatedQuery {
     pub cluster_id: Option<ClusterId>,
@@ -82,8 +118,21 @@ pub struct AggregatedQuery {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScalingPrediction {
     pub resource_id: String,
+    #[serde(default)]
+    pub metric_type: MetricType,
+    /// Time in ms the horizon counts from: the end of the fitted samples.
     pub generated_at: i64,
     pub horizon: Duration,
+    /// Value the trend reaches at the horizon.
     pub predicted_value: f64,
+    /// Range the value is expected within at the horizon (~95%).
+    #[serde(default)]
+    pub lower_bound: f64,
+    #[serde(default)]
+    pub upper_bound: f64,
     pub unit: String,
+    /// How long after `generated_at` the trend reaches saturation, when it
+    /// gets there within the horizon.
+    #[serde(default)]
+    pub time_to_threshold: Option<Duration>,
 }
@@ -12,6 +12,35 @@ pub enum Severity {
     Info,
 }
 
+impl Severity {
+    /// Whether this is at least as severe as `threshold`.
+    pub fn at_least(self, threshold: Severity) -> bool {
+        self.rank() >= threshold.rank()
+    }
+
+    fn rank(self) -> u8 {
+        match self {
+            Severity::Info => 0,
+            Severity::Warning => 1,
+            Severity::Critical => 2,
+        }
+    }
+}
+
+/// From the wire value in `analytics.proto`; unspecified (0) is an error.
+impl TryFrom<i32> for Severity {
+    type Error = anyhow::Error;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::Critical),
+            2 => Ok(Self::Warning),
+            3 => Ok(Self::Info),
+            _ => anyhow::bail!("unknown severity {value}"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Anomaly {
     pub id: String,
@@ -14,6 +14,22 @@ pub enum ResourceType {
     Service,
 }
 
+/// From the wire value in `analytics.proto`. Unspecified (0) and values this
+/// build doesn't know are rejected rather than guessed.
+impl TryFrom<i32> for ResourceType {
+    type Error = anyhow::Error;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::Pod),
+            2 => Ok(Self::Node),
+            3 => Ok(Self::Container),
+            4 => Ok(Self::Service),
+            _ => anyhow::bail!("unknown resource type {value}"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum MetricType {
@@ -26,6 +42,23 @@ pub enum MetricType {
     DiskWrite,
 }
 
+/// From the wire value in `analytics.proto`.
+impl TryFrom<i32> for MetricType {
+    type Error = anyhow::Error;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::CpuUsage),
+            2 => Ok(Self::MemoryUsage),
+            3 => Ok(Self::NetworkIn),
+            4 => Ok(Self::NetworkOut),
+            5 => Ok(Self::DiskRead),
+            6 => Ok(Self::DiskWrite),
+            _ => anyhow::bail!("unknown metric type {value}"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MetricSample {
     pub cluster_id: ClusterId,
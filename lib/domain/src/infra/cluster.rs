@@ -23,4 +23,7 @@ pub struct ClusterMetadata {
     pub pod_count: u32,
     pub node_count: u32,
     pub namespace_count: u32,
+    /// How long the latest metrics query against this cluster took.
+    #[serde(default)]
+    pub last_collection_ms: Option<u64>,
 }
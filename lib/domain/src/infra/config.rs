@@ -1,8 +1,13 @@
 //! Phenome configuration schema and loader.
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, de::Error as _};
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
+use std::time::Duration;
+
+use crate::anomaly::Severity;
+use crate::metrics::{MetricSample, MetricType, ResourceType};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PhenomeConfig {
@@ -12,6 +17,8 @@ pub struct PhenomeConfig {
     pub clusters: Vec<ClusterConfig>,
     pub services: ServicesConfig,
     pub notifications: NotificationsConfig,
+    #[serde(default)]
+    pub ui: UiConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,20 +30,220 @@ pub struct DeploymentConfig {
 pub struct AnalyticsConfig {
     pub storage: String,
     pub sqlite_path: String,
+    /// Connection string used when `storage` is `postgres`.
+    #[serde(default)]
+    pub postgres_url: Option<String>,
     pub retention: RetentionConfig,
     pub collection: CollectionConfig,
+    /// Bucket size for metric rollups, written as `30s`, `5m`, `15m` or `1h`.
+    /// Must evenly divide a day so buckets align to wall-clock time.
+    #[serde(default = "default_aggregation_window", with = "window_format")]
+    pub aggregation_window: Duration,
+    #[serde(default)]
+    pub scheduler: SchedulerConfig,
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+    #[serde(default)]
+    pub grpc: GrpcServerConfig,
+}
+
+/// Transport security of the analytics gRPC server. Left unset, the server
+/// listens in plaintext and accepts every request, as in local development.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GrpcServerConfig {
+    /// PEM certificate chain; TLS is on when this and `tls_key_path` are set.
+    #[serde(default)]
+    pub tls_cert_path: Option<String>,
+    /// PEM private key of the certificate.
+    #[serde(default)]
+    pub tls_key_path: Option<String>,
+    /// Bearer token every request must carry.
+    #[serde(default)]
+    pub auth_token: Option<String>,
+}
+
+fn default_aggregation_window() -> Duration {
+    Duration::from_secs(60 * 60)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RetentionConfig {
     pub full_resolution_days: i64,
     pub aggregated_days: i64,
+    #[serde(default = "default_daily_days")]
+    pub daily_days: i64,
+}
+
+fn default_daily_days() -> i64 {
+    365
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchedulerConfig {
+    /// Log the changes due actions would make without applying them.
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Replicas a deployment may differ from the count an action assumed
+    /// before the action is cancelled as drifted.
+    #[serde(default)]
+    pub drift_tolerance: u32,
+    /// Times a failed action is retried before it is marked failed.
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// Seconds before the first retry; each retry after it waits twice as
+    /// long.
+    #[serde(default = "default_retry_backoff_seconds")]
+    pub retry_backoff_seconds: u64,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            dry_run: false,
+            drift_tolerance: 0,
+            max_retries: default_max_retries(),
+            retry_backoff_seconds: default_retry_backoff_seconds(),
+        }
+    }
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_retry_backoff_seconds() -> u64 {
+    60
+}
+
+/// Default trip thresholds for the circuit breakers guarding calls to the ML
+/// service and to clusters.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failed calls before the circuit opens.
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+    /// Seconds an open circuit rejects calls before letting trial calls through.
+    #[serde(default = "default_reset_timeout_seconds")]
+    pub reset_timeout_seconds: u64,
+    /// Trial calls let through while the circuit is half-open.
+    #[serde(default = "default_half_open_max_calls")]
+    pub half_open_max_calls: u32,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: default_failure_threshold(),
+            reset_timeout_seconds: default_reset_timeout_seconds(),
+            half_open_max_calls: default_half_open_max_calls(),
+        }
+    }
+}
+
+fn default_failure_threshold() -> u32 {
+    3
+}
+
+fn default_reset_timeout_seconds() -> u64 {
+    30
+}
+
+fn default_half_open_max_calls() -> u32 {
+    1
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CollectionConfig {
     pub interval_seconds: u64,
     pub batch_size: usize,
+    /// Metric types to collect; empty collects every type.
+    #[serde(default)]
+    pub metric_types: Vec<MetricType>,
+    /// Resource types to collect; empty collects every type.
+    #[serde(default)]
+    pub resource_types: Vec<ResourceType>,
+    /// Clusters queried at the same time; the rest wait for a free slot.
+    #[serde(default = "default_max_concurrent_clusters")]
+    pub max_concurrent_clusters: usize,
+    /// Seconds one cluster's query may take before it fails with a timeout.
+    #[serde(default = "default_query_timeout_seconds")]
+    pub query_timeout_seconds: u64,
+    /// Percentage by which each poll interval is randomly stretched or
+    /// shortened; non-zero also delays the first poll by a random offset.
+    #[serde(default)]
+    pub jitter_percent: u8,
+    /// Samples to drop before they are stored, keyed by cluster context.
+    #[serde(default)]
+    pub filters: HashMap<String, MetricFilter>,
+    /// Collected batches waiting for the storage writer.
+    #[serde(default = "default_write_queue_capacity")]
+    pub write_queue_capacity: usize,
+    /// What the collector does when the write queue is full.
+    #[serde(default)]
+    pub write_overflow: WriteOverflowPolicy,
+}
+
+fn default_write_queue_capacity() -> usize {
+    16
+}
+
+/// Handling of a collected batch that finds the write queue full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WriteOverflowPolicy {
+    /// Wait for the writer to make room, delaying the next poll.
+    #[default]
+    Block,
+    /// Discard the oldest queued batch to make room.
+    DropOldest,
+}
+
+/// Which samples of a cluster are kept. Empty allowlists keep everything;
+/// a denylist wins over an allowlist.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MetricFilter {
+    #[serde(default)]
+    pub allow_metric_types: Vec<MetricType>,
+    #[serde(default)]
+    pub deny_metric_types: Vec<MetricType>,
+    /// Namespaces of namespaced resources; cluster-wide resources such as
+    /// nodes are not affected.
+    #[serde(default)]
+    pub allow_namespaces: Vec<String>,
+    #[serde(default)]
+    pub deny_namespaces: Vec<String>,
+}
+
+impl MetricFilter {
+    pub fn admits(&self, sample: &MetricSample) -> bool {
+        if self.deny_metric_types.contains(&sample.metric_type)
+            || (!self.allow_metric_types.is_empty()
+                && !self.allow_metric_types.contains(&sample.metric_type))
+        {
+            return false;
+        }
+        // Namespaced resources are identified as `namespace/name`.
+        let Some((namespace, _)) = sample.resource_id.split_once('/') else {
+            return true;
+        };
+        !self
+            .deny_namespaces
+            .iter()
+            .any(|denied| denied == namespace)
+            && (self.allow_namespaces.is_empty()
+                || self
+                    .allow_namespaces
+                    .iter()
+                    .any(|allowed| allowed == namespace))
+    }
+}
+
+fn default_max_concurrent_clusters() -> usize {
+    8
+}
+
+fn default_query_timeout_seconds() -> u64 {
+    10
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,6 +268,19 @@ pub struct MlThresholdsConfig {
 pub struct ClusterConfig {
     pub name: String,
     pub context: String,
+    #[serde(default)]
+    pub metrics_source: MetricsSourceConfig,
+}
+
+/// Where a cluster's metrics are read from.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MetricsSourceConfig {
+    /// The `metrics.k8s.io` API served by metrics-server.
+    #[default]
+    MetricsServer,
+    /// cAdvisor container metrics from a Prometheus server at `url`.
+    Prometheus { url: String },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -72,12 +292,165 @@ pub struct ServicesConfig {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NotificationsConfig {
     pub channels: Vec<NotificationChannelConfig>,
+    /// Flood control applied to each channel separately; unset sends everything.
+    #[serde(default)]
+    pub rate_limit: Option<NotificationRateLimit>,
+    /// Seconds before an ongoing anomaly is notified again.
+    #[serde(default = "default_dedup_ttl_seconds")]
+    pub dedup_ttl_seconds: u64,
+    /// Send anomalies as one digest every this many seconds instead of
+    /// one notification each; unset sends them as they are detected.
+    #[serde(default, deserialize_with = "positive_seconds")]
+    pub digest_interval_seconds: Option<u64>,
+}
+
+fn default_dedup_ttl_seconds() -> u64 {
+    60 * 60
 }
 
+fn positive_seconds<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+    match Option::<u64>::deserialize(deserializer)? {
+        Some(0) => Err(D::Error::custom(
+            "digest_interval_seconds must be positive; leave it unset to send anomalies as they are detected",
+        )),
+        seconds => Ok(seconds),
+    }
+}
+
+/// Token bucket limiting how fast a single channel sends. Notifications over
+/// the limit are coalesced into one "N more anomalies suppressed" summary.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationRateLimit {
+    /// Notifications a channel may send per minute once its burst is spent.
+    pub per_minute: u32,
+    /// Notifications a channel may send back to back.
+    pub burst: u32,
+    /// Deliver critical notifications even when the channel is over its limit.
+    #[serde(default)]
+    pub exempt_critical: bool,
+}
+
+/// A notification channel; every variant takes an optional `min_severity`
+/// (default `info`) below which notifications are not sent to it.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum NotificationChannelConfig {
-    Ntfy { url: String, topic: String },
+    Ntfy {
+        url: String,
+        topic: String,
+        #[serde(default = "default_min_severity")]
+        min_severity: Severity,
+    },
+    Slack {
+        webhook_url: String,
+        #[serde(default = "default_min_severity")]
+        min_severity: Severity,
+    },
+    /// POST `template` with `{{field}}` placeholders filled from the
+    /// notification, e.g. `{{title}}`, `{{severity}}` or `{{resource_id}}`.
+    Webhook {
+        url: String,
+        template: String,
+        #[serde(default)]
+        content_type: Option<String>,
+        #[serde(default = "default_min_severity")]
+        min_severity: Severity,
+    },
+}
+
+impl NotificationChannelConfig {
+    pub fn min_severity(&self) -> Severity {
+        match self {
+            NotificationChannelConfig::Ntfy { min_severity, .. }
+            | NotificationChannelConfig::Slack { min_severity, .. }
+            | NotificationChannelConfig::Webhook { min_severity, .. } => *min_severity,
+        }
+    }
+}
+
+fn default_min_severity() -> Severity {
+    Severity::Info
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UiConfig {
+    /// Extra dependency classification rules, checked before the built-in defaults.
+    #[serde(default)]
+    pub dependency_rules: Vec<DependencyRuleConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DependencyRuleConfig {
+    pub category: DependencyCategory,
+    /// Case-insensitive substrings matched against the dependency name.
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// Case-insensitive regular expressions matched against the dependency name.
+    #[serde(default)]
+    pub patterns: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyCategory {
+    Database,
+    Storage,
+    Security,
+    Network,
+    Infrastructure,
+    Other,
+}
+
+mod window_format {
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+    use std::time::Duration;
+
+    const DAY_SECS: u64 = 24 * 60 * 60;
+
+    pub fn serialize<S: Serializer>(window: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        let secs = window.as_secs();
+        let text = if secs.is_multiple_of(3600) {
+            format!("{}h", secs / 3600)
+        } else if secs.is_multiple_of(60) {
+            format!("{}m", secs / 60)
+        } else {
+            format!("{secs}s")
+        };
+        serializer.serialize_str(&text)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        parse(&text).map_err(D::Error::custom)
+    }
+
+    fn parse(text: &str) -> Result<Duration, String> {
+        let text = text.trim();
+        let (amount, unit) = text.split_at(text.trim_end_matches(char::is_alphabetic).len());
+        let unit_secs = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 3600,
+            _ => {
+                return Err(format!(
+                    "aggregation window {text:?} needs a unit of s, m or h"
+                ));
+            }
+        };
+        let secs = amount
+            .parse::<u64>()
+            .ok()
+            .and_then(|amount| amount.checked_mul(unit_secs))
+            .ok_or_else(|| {
+                format!("invalid aggregation window {text:?}; expected e.g. 5m or 1h")
+            })?;
+        if secs == 0 || !DAY_SECS.is_multiple_of(secs) {
+            return Err(format!(
+                "aggregation window {text:?} must evenly divide a day"
+            ));
+        }
+        Ok(Duration::from_secs(secs))
+    }
 }
 
 impl PhenomeConfig {
@@ -10,25 +10,27 @@ pub use ops::{actions, assembly, events, snapshot};
 
 pub use actions::{ActionDefinition, ActionId, ActionRegistry, ActionSafety};
 pub use analytics::analytics::{
-    AggregatedMetric, AggregatedQuery, MetricsQuery, ScalingPrediction, TimeRange, TimeSeries,
-    TimeSeriesData, TimeSeriesPoint,
+    AggregatedMetric, AggregatedQuery, DailyAggregate, DownsampledMetrics, MetricBucket,
+    MetricsQuery, ScalingPrediction, TimeRange, TimeSeries, TimeSeriesData, TimeSeriesPoint,
 };
 pub use analytics::anomaly::{Anomaly, AnomalyFilter, RootCauseAnalysis, Severity};
 pub use assembly::{Assembly, AssemblyStepDef};
 pub use cluster::{ClusterHealth, ClusterId, ClusterMetadata};
 pub use config::{
-    AnalyticsConfig, ClusterConfig, CollectionConfig, DeploymentConfig, MlConfig, MlModelsConfig,
-    MlThresholdsConfig, NotificationChannelConfig, NotificationsConfig, RetentionConfig,
-    PhenomeConfig, ServicesConfig,
+    AnalyticsConfig, CircuitBreakerConfig, ClusterConfig, CollectionConfig, DependencyCategory,
+    DependencyRuleConfig, DeploymentConfig, GrpcServerConfig, MetricFilter, MetricsSourceConfig,
+    MlConfig, MlModelsConfig, MlThresholdsConfig, NotificationChannelConfig, NotificationRateLimit,
+    NotificationsConfig, PhenomeConfig, RetentionConfig, SchedulerConfig, ServicesConfig, UiConfig,
+    WriteOverflowPolicy,
 };
 pub use events::{Event, EventBus, EventLevel};
 pub use health::{ComponentHealthStatus, HealthSnapshot};
 pub use metrics::{MetricSample, MetricType, ResourceType};
 pub use notification::{Notification, NotificationChannel};
 pub use recommendation::{
-    CostImpact, Priority, Recommendation, RecommendationAction, RecommendationFilter,
-    RecommendationStatus, RecommendationStatusKind, RecommendationType, ResourceLimits, ScheduleId,
-    ScheduleStatus, ScheduledAction,
+    CostImpact, ExecutionOutcome, Priority, Recommendation, RecommendationAction,
+    RecommendationFilter, RecommendationStatus, RecommendationStatusKind, RecommendationType,
+    ResourceLimits, ScheduleExecution, ScheduleId, ScheduleStatus, ScheduledAction,
 };
 pub use snapshot::{
     ActionStatus, AssemblyStep, AssemblyStepStatus, AssemblySummary, Capability, CapabilityStatus,
@@ -35,10 +35,13 @@ impl Event {
     }
 }
 
+/// Most recent events, capped at a fixed capacity. The oldest events are
+/// dropped first and counted so diagnostics can report the loss.
 #[derive(Debug, Clone)]
 pub struct EventBus {
     max_events: usize,
     events: VecDeque<Event>,
+    dropped: u64,
 }
 
 impl EventBus {
@@ -46,6 +49,7 @@ impl EventBus {
         Self {
             max_events,
             events: VecDeque::new(),
+            dropped: 0,
         }
     }
 
@@ -53,9 +57,19 @@ impl EventBus {
         self.events.push_back(event);
         while self.events.len() > self.max_events {
             self.events.pop_front();
+            self.dropped = self.dropped.saturating_add(1);
         }
     }
 
+    pub fn capacity(&self) -> usize {
+        self.max_events
+    }
+
+    /// Events evicted to stay within capacity since the bus was created.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = &Event> {
         self.events.iter()
     }
@@ -1,5 +1,6 @@
 use std::collections::VecDeque;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use phenome_domain::{Assembly, Event, HealthSnapshot};
 
@@ -27,6 +28,22 @@ pub trait AssemblyPort: Send + Sync {
 
 pub trait HealthPort: Send + Sync {
     fn snapshot(&self) -> HealthSnapshot;
+
+    /// Re-establish the live status connection.
+    fn reconnect(&self) -> Result<(), String> {
+        Err("Live status reconnect is not supported".to_string())
+    }
+}
+
+/// Freshness of the cluster cache that backs gate readiness.
+pub trait CachePort: Send + Sync {
+    /// Time since the cache was last refreshed; `None` before the first refresh.
+    fn cache_age(&self) -> Option<Duration>;
+
+    /// Whether the cache has been refreshed within `max_age`.
+    fn is_fresh(&self, max_age: Duration) -> bool {
+        self.cache_age().is_some_and(|age| age <= max_age)
+    }
 }
 
 pub trait LogPort: Send + Sync {
@@ -37,6 +54,7 @@ pub trait LogPort: Send + Sync {
 pub struct PortSet {
     pub assembly: Arc<dyn AssemblyPort>,
     pub health: Arc<dyn HealthPort>,
+    pub cache: Arc<dyn CachePort>,
     pub logs: Arc<dyn LogPort>,
     pub bootstrap: Arc<dyn BootstrapPort>,
     pub metrics: Arc<dyn MetricsPort>,
@@ -51,6 +69,7 @@ impl PortSet {
         Self {
             assembly: Arc::new(NullAssemblyPort),
             health: Arc::new(NullHealthPort),
+            cache: Arc::new(NullCachePort),
             logs: Arc::new(NullLogPort),
             bootstrap: Arc::new(NullBootstrapPort),
             metrics: Arc::new(NullMetricsPort),
@@ -60,6 +79,89 @@ impl PortSet {
             scheduler: Arc::new(NullSchedulerPort),
         }
     }
+
+    /// Start from [`PortSet::empty`] and replace individual ports.
+    pub fn builder() -> PortSetBuilder {
+        PortSetBuilder::default()
+    }
+}
+
+/// Fluent construction of a [`PortSet`]; ports that are not set stay null.
+///
+/// # Examples
+/// ```rust
+/// use phenome_ports::PortSet;
+///
+/// let ports = PortSet::builder().build();
+/// assert!(ports.health.snapshot().health.is_empty());
+/// ```
+#[derive(Clone)]
+pub struct PortSetBuilder {
+    ports: PortSet,
+}
+
+impl Default for PortSetBuilder {
+    fn default() -> Self {
+        Self {
+            ports: PortSet::empty(),
+        }
+    }
+}
+
+impl PortSetBuilder {
+    pub fn with_assembly(mut self, port: impl AssemblyPort + 'static) -> Self {
+        self.ports.assembly = Arc::new(port);
+        self
+    }
+
+    pub fn with_health(mut self, port: impl HealthPort + 'static) -> Self {
+        self.ports.health = Arc::new(port);
+        self
+    }
+
+    pub fn with_cache(mut self, port: impl CachePort + 'static) -> Self {
+        self.ports.cache = Arc::new(port);
+        self
+    }
+
+    pub fn with_logs(mut self, port: impl LogPort + 'static) -> Self {
+        self.ports.logs = Arc::new(port);
+        self
+    }
+
+    pub fn with_bootstrap(mut self, port: impl BootstrapPort + 'static) -> Self {
+        self.ports.bootstrap = Arc::new(port);
+        self
+    }
+
+    pub fn with_metrics(mut self, port: impl MetricsPort + 'static) -> Self {
+        self.ports.metrics = Arc::new(port);
+        self
+    }
+
+    pub fn with_analytics(mut self, port: impl AnalyticsPort + 'static) -> Self {
+        self.ports.analytics = Arc::new(port);
+        self
+    }
+
+    pub fn with_ml(mut self, port: impl MLPort + 'static) -> Self {
+        self.ports.ml = Arc::new(port);
+        self
+    }
+
+    pub fn with_notifications(mut self, port: impl NotificationPort + 'static) -> Self {
+        self.ports.notifications = Arc::new(port);
+        self
+    }
+
+    pub fn with_scheduler(mut self, port: impl SchedulerPort + 'static) -> Self {
+        self.ports.scheduler = Arc::new(port);
+        self
+    }
+
+    pub fn build(self) -> PortSet {
+        self.ports
+    }
 }
 
 #[derive(Clone, Default)]
@@ -88,6 +190,15 @@ impl HealthPort for NullHealthPort {
     }
 }
 
+#[derive(Clone, Default)]
+struct NullCachePort;
+
+impl CachePort for NullCachePort {
+    fn cache_age(&self) -> Option<Duration> {
+        None
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct InMemoryLogPort {
     events: Arc<Mutex<VecDeque<Event>>>,
@@ -263,10 +374,14 @@ impl MLPort for NullMLPort {
     ) -> anyhow::Result<phenome_domain::ScalingPrediction> {
         Ok(phenome_domain::ScalingPrediction {
             resource_id,
+            metric_type: phenome_domain::MetricType::default(),
             generated_at: 0,
             horizon,
             predicted_value: 0.0,
+            lower_bound: 0.0,
+            upper_bound: 0.0,
             unit: String::new(),
+            time_to_threshold: None,
         })
     }
 
@@ -296,6 +411,14 @@ impl NotificationPort for NullNotificationPort {
     ) -> anyhow::Result<()> {
         Ok(())
     }
+
+    async fn mark_read(&self, _id: String) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn list_unread(&self) -> anyhow::Result<Vec<phenome_domain::Notification>> {
+        Ok(Vec::new())
+    }
 }
 
 #[derive(Clone, Default)]
@@ -317,4 +440,11 @@ impl SchedulerPort for NullSchedulerPort {
     async fn list_scheduled(&self) -> anyhow::Result<Vec<phenome_domain::ScheduledAction>> {
         Ok(Vec::new())
     }
+
+    async fn list_executions(
+        &self,
+        _id: phenome_domain::ScheduleId,
+    ) -> anyhow::Result<Vec<phenome_domain::ScheduleExecution>> {
+        Ok(Vec::new())
+    }
 }
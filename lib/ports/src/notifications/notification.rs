@@ -7,4 +7,8 @@ use phenome_domain::{Notification, NotificationChannel};
 pub trait NotificationPort: Send + Sync {
     async fn send_notification(&self, notification: Notification) -> Result<()>;
     async fn configure_channel(&self, channel: NotificationChannel) -> Result<()>;
+    /// Mark notification `id` read so it is no longer listed as unread.
+    async fn mark_read(&self, id: String) -> Result<()>;
+    /// Notifications not yet marked read, newest first.
+    async fn list_unread(&self) -> Result<Vec<Notification>>;
 }
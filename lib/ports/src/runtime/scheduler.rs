@@ -1,11 +1,13 @@
 use anyhow::Result;
 use async_trait::async_trait;
 
-use phenome_domain::{ScheduleId, ScheduledAction};
+use phenome_domain::{ScheduleExecution, ScheduleId, ScheduledAction};
 
 #[async_trait]
 pub trait SchedulerPort: Send + Sync {
     async fn schedule_action(&self, action: ScheduledAction) -> Result<ScheduleId>;
     async fn cancel_schedule(&self, id: ScheduleId) -> Result<()>;
     async fn list_scheduled(&self) -> Result<Vec<ScheduledAction>>;
+    /// Every execution attempt of schedule `id`, oldest first.
+    async fn list_executions(&self, id: ScheduleId) -> Result<Vec<ScheduleExecution>>;
 }
@@ -1,3 +1,6 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
 use anyhow::{Result, anyhow};
 
 use phenome_domain::{ActionId, ActionRegistry, ActionSafety};
@@ -7,11 +10,18 @@ use phenome_domain::{
 use phenome_domain::{Event, EventBus, EventLevel};
 use phenome_ports::PortSet;
 
+/// Gate readiness read from a cache older than this is ignored.
+const STALE_CACHE_AFTER: Duration = Duration::from_secs(60);
+/// Number of recent refresh timestamps kept for cadence tracking.
+const REFRESH_HISTORY: usize = 16;
+
 pub struct Runtime {
     registry: ActionRegistry,
     snapshot: Snapshot,
     events: EventBus,
     refresh_count: u64,
+    refresh_times: VecDeque<Instant>,
+    cache_age: Option<Duration>,
     assembly: Option<Assembly>,
     ports: PortSet,
 }
@@ -35,6 +45,8 @@ impl Runtime {
             snapshot,
             events,
             refresh_count: 0,
+            refresh_times: VecDeque::with_capacity(REFRESH_HISTORY),
+            cache_age: ports.cache.cache_age(),
             assembly,
             ports,
         };
@@ -59,8 +71,45 @@ impl Runtime {
         &mut self.events
     }
 
+    /// Age of the cluster cache as of the last refresh; `None` when no cache
+    /// has been loaded.
+    pub fn cache_age(&self) -> Option<Duration> {
+        self.cache_age
+    }
+
+    /// When the most recent refreshes happened, oldest first.
+    pub fn refresh_times(&self) -> &VecDeque<Instant> {
+        &self.refresh_times
+    }
+
+    /// Mean time between the recorded refreshes; `None` until there are two.
+    pub fn average_refresh_interval(&self) -> Option<Duration> {
+        let (first, last) = (self.refresh_times.front()?, self.refresh_times.back()?);
+        let gaps = u32::try_from(self.refresh_times.len() - 1).ok()?;
+        (gaps > 0).then(|| last.duration_since(*first) / gaps)
+    }
+
+    /// How far the average refresh interval lags behind `configured`.
+    pub fn refresh_drift(&self, configured: Duration) -> Option<Duration> {
+        self.average_refresh_interval()
+            .map(|average| average.saturating_sub(configured))
+    }
+
+    fn record_refresh(&mut self, at: Instant) {
+        if self.refresh_times.len() == REFRESH_HISTORY {
+            self.refresh_times.pop_front();
+        }
+        self.refresh_times.push_back(at);
+    }
+
+    fn cache_is_stale(&self) -> bool {
+        self.cache_age.is_some_and(|age| age > STALE_CACHE_AFTER)
+    }
+
     pub fn refresh_snapshot(&mut self) {
         self.refresh_count = self.refresh_count.saturating_add(1);
+        self.record_refresh(Instant::now());
+        self.cache_age = self.ports.cache.cache_age();
         self.drain_port_events();
         if !self.snapshot.assembly_steps.is_empty() {
             self.update_action_statuses();
@@ -135,7 +184,11 @@ impl Runtime {
         };
 
         let health_snapshot = self.ports.health.snapshot();
-        let readiness = self.ports.assembly.step_readiness();
+        let readiness = if self.cache_is_stale() {
+            std::collections::HashMap::new()
+        } else {
+            self.ports.assembly.step_readiness()
+        };
         let step_map: std::collections::HashMap<_, _> = assembly
             .steps
             .iter()
@@ -224,3 +277,111 @@ impl Default for Runtime {
         Self::new(ActionRegistry::default())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use phenome_domain::{ComponentHealthStatus, HealthSnapshot};
+    use phenome_ports::{AssemblyPort, HealthPort};
+
+    use super::*;
+
+    struct SingleStepAssembly;
+
+    impl AssemblyPort for SingleStepAssembly {
+        fn assembly(&self) -> Option<Assembly> {
+            Some(Assembly {
+                steps: vec![AssemblyStepDef {
+                    id: "database".to_string(),
+                    kind: "helm".to_string(),
+                    depends_on: Vec::new(),
+                    provides: vec!["sql".to_string()],
+                    domain: "data".to_string(),
+                    pod: None,
+                    has_gates: false,
+                }],
+            })
+        }
+
+        fn assembly_error(&self) -> Option<String> {
+            None
+        }
+
+        fn step_readiness(&self) -> HashMap<String, bool> {
+            HashMap::new()
+        }
+    }
+
+    struct UnhealthyDatabase;
+
+    impl HealthPort for UnhealthyDatabase {
+        fn snapshot(&self) -> HealthSnapshot {
+            HealthSnapshot {
+                health: HashMap::from([(
+                    "database".to_string(),
+                    ComponentHealthStatus::Unhealthy("crash loop".to_string()),
+                )]),
+                ..HealthSnapshot::default()
+            }
+        }
+    }
+
+    #[test]
+    fn runtime_reflects_health_from_built_port_set() {
+        let ports = PortSet::builder()
+            .with_assembly(SingleStepAssembly)
+            .with_health(UnhealthyDatabase)
+            .build();
+        let mut runtime = Runtime::new_with_ports(ActionRegistry::default(), ports);
+        runtime.refresh_snapshot();
+
+        let step = &runtime.snapshot().assembly_steps[0];
+        assert_eq!(step.id, "database");
+        assert_eq!(step.status, AssemblyStepStatus::Failed);
+    }
+
+    #[test]
+    fn runtime_tracks_refresh_cadence() {
+        let mut runtime = Runtime::default();
+        assert_eq!(runtime.average_refresh_interval(), None);
+        runtime.refresh_snapshot();
+        runtime.refresh_snapshot();
+        assert_eq!(runtime.refresh_times().len(), 2);
+
+        let mut runtime = Runtime::default();
+        let start = Instant::now();
+        for offset_ms in [0, 1_000, 2_000, 4_000] {
+            runtime.record_refresh(start + Duration::from_millis(offset_ms));
+        }
+        assert_eq!(
+            runtime.average_refresh_interval(),
+            Some(Duration::from_millis(4_000) / 3)
+        );
+        assert_eq!(
+            runtime.refresh_drift(Duration::from_secs(1)),
+            Some(Duration::from_millis(4_000) / 3 - Duration::from_secs(1))
+        );
+    }
+
+    #[test]
+    fn event_bus_drops_oldest_beyond_capacity() {
+        let mut runtime = Runtime::default();
+        let capacity = runtime.events().capacity();
+        for i in 0..capacity + 10 {
+            runtime
+                .events_mut()
+                .push(Event::new(EventLevel::Info, format!("event {i}")));
+        }
+
+        let events = runtime.events();
+        assert_eq!(events.len(), capacity);
+        // The "Runtime initialized" event is evicted along with the first ten.
+        assert_eq!(events.dropped(), 11);
+        assert_eq!(events.iter().next().unwrap().message, "event 10");
+        assert_eq!(
+            events.iter().last().unwrap().message,
+            format!("event {}", capacity + 9)
+        );
+    }
+}
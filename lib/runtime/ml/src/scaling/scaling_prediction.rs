@@ -1,7 +1,12 @@
 use anyhow::Result;
 use std::time::Duration;
 
-use phenome_domain::ScalingPrediction;
+use phenome_domain::{ScalingPrediction, TimeSeries};
+
+/// Fewest samples a trend is fitted to; fewer predict their average.
+const MIN_POINTS: usize = 3;
+/// Band half-width in residual standard deviations (~95%).
+const BAND_SIGMAS: f64 = 2.0;
 
 #[derive(Debug, Clone, Default)]
 pub struct ScalingPredictor;
@@ -11,26 +16,80 @@ impl ScalingPredictor {
         Self
     }
 
+    /// Least-squares trend of `series` continued `horizon` past
+    /// `generated_at`, with a confidence band that widens the further it
+    /// reaches. Percentages also get the time until they saturate.
     pub fn predict(
         &self,
-        resource_id: String,
+        series: &TimeSeries,
         horizon: Duration,
-        history: &[f64],
-        unit: &str,
         generated_at: i64,
     ) -> Result<ScalingPrediction> {
-        let predicted_value = if history.is_empty() {
-            0.0
-        } else {
-            history.iter().sum::<f64>() / history.len() as f64
-        };
-
-        Ok(ScalingPrediction {
-            resource_id,
+        let points: Vec<(f64, f64)> = series
+            .points
+            .iter()
+            .filter(|point| point.value.is_finite())
+            .map(|point| (point.timestamp as f64, point.value))
+            .collect();
+        let mut prediction = ScalingPrediction {
+            resource_id: series.resource_id.clone(),
+            metric_type: series.metric_type,
             generated_at,
             horizon,
-            predicted_value,
-            unit: unit.to_string(),
-        })
+            predicted_value: 0.0,
+            lower_bound: 0.0,
+            upper_bound: 0.0,
+            unit: series.unit.clone(),
+            time_to_threshold: None,
+        };
+
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let sxx: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+        if points.len() < MIN_POINTS || sxx <= 0.0 {
+            let average = if points.is_empty() { 0.0 } else { mean_y };
+            prediction.predicted_value = average;
+            prediction.lower_bound = average;
+            prediction.upper_bound = average;
+            return Ok(prediction);
+        }
+
+        let sxy: f64 = points
+            .iter()
+            .map(|(x, y)| (x - mean_x) * (y - mean_y))
+            .sum();
+        let slope = sxy / sxx;
+        let fit = |x: f64| mean_y + slope * (x - mean_x);
+        let residual = points
+            .iter()
+            .map(|&(x, y)| (y - fit(x)).powi(2))
+            .sum::<f64>();
+        let sigma = (residual / (n - 2.0)).sqrt();
+
+        let now = generated_at as f64;
+        let ahead = horizon.as_millis() as f64;
+        let target = now + ahead;
+        let spread = (1.0 + 1.0 / n + (target - mean_x).powi(2) / sxx).sqrt();
+        let half = BAND_SIGMAS * sigma * spread;
+        prediction.predicted_value = fit(target);
+        prediction.lower_bound = prediction.predicted_value - half;
+        prediction.upper_bound = prediction.predicted_value + half;
+
+        prediction.time_to_threshold = threshold_of(&series.unit).and_then(|threshold| {
+            let current = fit(now);
+            if slope <= 0.0 || current >= threshold {
+                return None;
+            }
+            let until = (threshold - current) / slope;
+            (until <= ahead).then(|| Duration::from_millis(until.round() as u64))
+        });
+        Ok(prediction)
     }
 }
+
+/// Value worth warning about reaching: saturation for percentages. Other
+/// units carry no capacity to measure against.
+fn threshold_of(unit: &str) -> Option<f64> {
+    matches!(unit, "%" | "percent").then_some(100.0)
+}
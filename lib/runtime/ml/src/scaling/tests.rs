@@ -1,19 +1,75 @@
 use std::time::Duration;
 
+use phenome_domain::{MetricType, TimeSeries, TimeSeriesPoint};
+
 use crate::scaling::scaling_prediction::ScalingPredictor;
 
+fn series(unit: &str, values: &[f64]) -> TimeSeries {
+    TimeSeries {
+        cluster_id: "prod".to_string(),
+        resource_id: "deployment-a".to_string(),
+        metric_type: MetricType::CpuUsage,
+        unit: unit.to_string(),
+        points: values
+            .iter()
+            .enumerate()
+            .map(|(index, &value)| TimeSeriesPoint {
+                timestamp: index as i64 * 100,
+                value,
+            })
+            .collect(),
+    }
+}
+
 #[test]
-fn predicts_average_for_history() {
+fn predicts_average_for_short_history() {
     let predictor = ScalingPredictor::new();
     let prediction = predictor
         .predict(
-            "deployment-a".to_string(),
+            &series("cores", &[1.0, 3.0]),
             Duration::from_secs(3600),
-            &[1.0, 2.0, 3.0],
-            "cores",
-            0,
+            100,
         )
         .unwrap();
 
     assert_eq!(prediction.predicted_value, 2.0);
+    assert_eq!(prediction.lower_bound, 2.0);
+    assert_eq!(prediction.upper_bound, 2.0);
+    assert_eq!(prediction.time_to_threshold, None);
+}
+
+#[test]
+fn rising_percentage_reaches_saturation_ahead() {
+    let predictor = ScalingPredictor::new();
+    // Rising by 5 per 100ms, at 95 by the last sample.
+    let values: Vec<f64> = (0..10).map(|i| 50.0 + 5.0 * i as f64).collect();
+    let prediction = predictor
+        .predict(&series("%", &values), Duration::from_millis(500), 900)
+        .unwrap();
+
+    assert_eq!(prediction.resource_id, "deployment-a");
+    assert_eq!(prediction.metric_type, MetricType::CpuUsage);
+    assert!((prediction.predicted_value - 120.0).abs() < 1e-9);
+    // A perfect fit leaves no band.
+    assert!((prediction.upper_bound - prediction.lower_bound).abs() < 1e-9);
+    assert_eq!(
+        prediction.time_to_threshold,
+        Some(Duration::from_millis(100))
+    );
+}
+
+#[test]
+fn noisy_history_gets_a_band_and_no_saturation() {
+    let predictor = ScalingPredictor::new();
+    let prediction = predictor
+        .predict(
+            &series("%", &[1.0, 3.0, 1.0, 3.0, 1.0, 3.0]),
+            Duration::from_secs(60),
+            500,
+        )
+        .unwrap();
+
+    assert!(prediction.lower_bound < prediction.predicted_value);
+    assert!(prediction.upper_bound > prediction.predicted_value);
+    assert_eq!(prediction.time_to_threshold, None);
 }
@@ -0,0 +1,132 @@
+//! Event summaries: condense recent events into one status line.
+//!
+//! ## Responsibility
+//! - Count warnings and errors inside a recent time window.
+//! - Report the most severe level seen so callers can pick a color.
+//!
+//! ## Non-goals
+//! - No per-source breakdown; the event log remains the detailed view.
+
+use std::time::Duration;
+
+use phenome_domain::{Event, EventLevel};
+
+/// Counts of recent events by level.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EventSummary {
+    pub window: Duration,
+    pub infos: usize,
+    pub warnings: usize,
+    pub errors: usize,
+}
+
+impl EventSummary {
+    /// Most severe level in the window, if any events fell inside it.
+    pub fn worst_level(&self) -> Option<EventLevel> {
+        if self.errors > 0 {
+            Some(EventLevel::Error)
+        } else if self.warnings > 0 {
+            Some(EventLevel::Warn)
+        } else if self.infos > 0 {
+            Some(EventLevel::Info)
+        } else {
+            None
+        }
+    }
+
+    /// Single-line text such as `⚠ 3 warnings, 1 error in last 5m`.
+    pub fn label(&self) -> String {
+        let window = format_window(self.window);
+        if self.warnings == 0 && self.errors == 0 {
+            return format!("✓ no warnings in last {window}");
+        }
+        let mut parts = Vec::new();
+        if self.warnings > 0 {
+            parts.push(plural(self.warnings, "warning"));
+        }
+        if self.errors > 0 {
+            parts.push(plural(self.errors, "error"));
+        }
+        format!("⚠ {} in last {window}", parts.join(", "))
+    }
+}
+
+/// Summarize `events` stamped within `window` before `now_ms`.
+pub fn summarize_events<'a>(
+    events: impl IntoIterator<Item = &'a Event>,
+    now_ms: u64,
+    window: Duration,
+) -> EventSummary {
+    let since = now_ms.saturating_sub(window.as_millis() as u64);
+    let mut summary = EventSummary {
+        window,
+        ..EventSummary::default()
+    };
+    for event in events {
+        if event.timestamp_ms < since {
+            continue;
+        }
+        match event.level {
+            EventLevel::Info => summary.infos += 1,
+            EventLevel::Warn => summary.warnings += 1,
+            EventLevel::Error => summary.errors += 1,
+        }
+    }
+    summary
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
+}
+
+fn format_window(window: Duration) -> String {
+    let secs = window.as_secs();
+    if secs >= 3600 && secs.is_multiple_of(3600) {
+        format!("{}h", secs / 3600)
+    } else if secs >= 60 && secs.is_multiple_of(60) {
+        format!("{}m", secs / 60)
+    } else {
+        format!("{secs}s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(level: EventLevel, timestamp_ms: u64) -> Event {
+        Event {
+            timestamp_ms,
+            level,
+            message: String::new(),
+        }
+    }
+
+    #[test]
+    fn summary_counts_levels_inside_the_window() {
+        let now = 10 * 60 * 1_000;
+        let events = [
+            event(EventLevel::Error, 1_000),
+            event(EventLevel::Info, now - 60_000),
+            event(EventLevel::Warn, now - 120_000),
+            event(EventLevel::Warn, now - 30_000),
+            event(EventLevel::Warn, now),
+            event(EventLevel::Error, now - 1_000),
+        ];
+
+        let summary = summarize_events(&events, now, Duration::from_secs(5 * 60));
+        assert_eq!(summary.infos, 1);
+        assert_eq!(summary.warnings, 3);
+        assert_eq!(summary.errors, 1);
+        assert_eq!(summary.worst_level(), Some(EventLevel::Error));
+        assert_eq!(summary.label(), "⚠ 3 warnings, 1 error in last 5m");
+
+        let quiet = summarize_events(&events[..2], now, Duration::from_secs(5 * 60));
+        assert_eq!(quiet.worst_level(), Some(EventLevel::Info));
+        assert_eq!(quiet.label(), "✓ no warnings in last 5m");
+    }
+}
@@ -1,7 +1,11 @@
 //! Shared formatting helpers used by UI and CLI.
 
 mod assembly;
+mod events;
+mod onboarding;
 mod problems;
 
 pub use assembly::{AssemblyGroup, AssemblyStepInfo, assembly_groups};
+pub use events::{EventSummary, summarize_events};
+pub use onboarding::{AnalyticsData, OnboardingPanel, onboarding_hints};
 pub use problems::problem_lines;
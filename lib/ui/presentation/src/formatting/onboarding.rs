@@ -0,0 +1,93 @@
+//! First-run onboarding: guidance shown before any analytics data exists.
+//!
+//! ## Responsibility
+//! - Detect a fresh install from the analytics data received so far.
+//! - Provide short per-panel hints on connecting clusters and where data lands.
+//!
+//! ## Non-goals
+//! - No persisted "seen" flag; hints disappear as soon as data arrives.
+
+/// Amount of analytics data the UI has received.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AnalyticsData {
+    pub metrics: usize,
+    pub anomalies: usize,
+    pub recommendations: usize,
+}
+
+impl AnalyticsData {
+    /// Nothing has been collected yet, as on a fresh install.
+    pub fn is_first_run(&self) -> bool {
+        self.metrics == 0 && self.anomalies == 0 && self.recommendations == 0
+    }
+}
+
+/// Panels that show onboarding hints while empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnboardingPanel {
+    Realtime,
+    Historical,
+    Insights,
+    Recommendations,
+}
+
+const CONNECT_HINTS: [&str; 3] = [
+    "No data yet - this looks like a fresh install.",
+    "List your clusters under `clusters:` in phenome-config.yaml, one kubeconfig context each.",
+    "Start `analytics-service`; metrics-server must run in every cluster.",
+];
+
+/// Hints for `panel` on a fresh install; `None` once any data has arrived.
+pub fn onboarding_hints(data: AnalyticsData, panel: OnboardingPanel) -> Option<Vec<&'static str>> {
+    if !data.is_first_run() {
+        return None;
+    }
+    let where_data_appears = match panel {
+        OnboardingPanel::Realtime => {
+            "Live CPU and memory totals appear here after the first collection interval."
+        }
+        OnboardingPanel::Historical => {
+            "Hourly rollups appear here once an hour of metrics has been collected."
+        }
+        OnboardingPanel::Insights => {
+            "Anomalies are listed here once each resource has a few minutes of history."
+        }
+        OnboardingPanel::Recommendations => {
+            "Scaling and right-sizing recommendations appear here after the first analysis."
+        }
+    };
+    let mut hints = CONNECT_HINTS.to_vec();
+    hints.push(where_data_appears);
+    Some(hints)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hints_show_on_first_run_and_disappear_with_data() {
+        let empty = AnalyticsData::default();
+        assert!(empty.is_first_run());
+        let hints = onboarding_hints(empty, OnboardingPanel::Realtime).unwrap();
+        assert_eq!(hints[0], "No data yet - this looks like a fresh install.");
+        assert!(hints.iter().any(|hint| hint.contains("`clusters:`")));
+        assert!(hints.last().unwrap().contains("first collection interval"));
+        let insights = onboarding_hints(empty, OnboardingPanel::Insights).unwrap();
+        assert!(insights.last().unwrap().starts_with("Anomalies"));
+
+        let with_metrics = AnalyticsData {
+            metrics: 12,
+            ..AnalyticsData::default()
+        };
+        assert!(!with_metrics.is_first_run());
+        for panel in [
+            OnboardingPanel::Realtime,
+            OnboardingPanel::Historical,
+            OnboardingPanel::Insights,
+            OnboardingPanel::Recommendations,
+        ] {
+            assert_eq!(onboarding_hints(with_metrics, panel), None);
+        }
+    }
+}
@@ -1,13 +1,20 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use tokio_stream::StreamExt;
 
-use phenome_adapter_analytics::grpc::analytics::GetAnomaliesRequest;
-use phenome_domain::{Anomaly, MetricType, Severity};
+use phenome_adapter_analytics::grpc::analytics::{
+    Anomaly as GrpcAnomaly, GetAnomaliesRequest, SubscribeAnomaliesRequest,
+};
+use phenome_domain::{Anomaly, ClusterId, MetricType, Severity};
 
-use super::AnalyticsClient;
+use super::{AnalyticsClient, AnomalyStream};
 
-pub(super) async fn fetch_anomalies(client: &AnalyticsClient) -> Result<Vec<Anomaly>> {
-    let mut grpc = client.client.clone();
+pub(super) async fn fetch_anomalies(
+    client: &AnalyticsClient,
+    cluster_id: Option<ClusterId>,
+) -> Result<Vec<Anomaly>> {
+    let mut grpc = client.grpc();
     let request = GetAnomaliesRequest {
+        cluster_id,
         limit: Some(50),
         ..Default::default()
     };
@@ -16,49 +23,106 @@ pub(super) async fn fetch_anomalies(client: &AnalyticsClient) -> Result<Vec<Anom
 
     Ok(anomalies
         .into_iter()
-        .map(|a| {
-            let metric_type = map_metric_type(a.metric_type());
-            let severity = map_severity(a.severity());
-            Anomaly {
-                id: a.id,
-                cluster_id: a.cluster_id,
-                resource_id: a.resource_id,
-                detected_at: a.detected_at,
-                metric_type,
-                severity,
-                confidence: a.confidence,
-                description: a.description,
-                baseline_value: a.baseline_value,
-                observed_value: a.observed_value,
-                deviation_sigma: a.deviation_sigma,
-                related_metrics: a.related_metrics,
-                root_cause: a.root_cause,
+        .filter_map(|a| match anomaly_from_proto(a) {
+            Ok(anomaly) => Some(anomaly),
+            Err(err) => {
+                tracing::warn!("Skipping anomaly: {:#}", err);
+                None
             }
         })
         .collect())
 }
 
-fn map_metric_type(metric: phenome_adapter_analytics::grpc::analytics::MetricType) -> MetricType {
-    match metric {
-        phenome_adapter_analytics::grpc::analytics::MetricType::CpuUsage => MetricType::CpuUsage,
-        phenome_adapter_analytics::grpc::analytics::MetricType::MemoryUsage => {
-            MetricType::MemoryUsage
-        }
-        phenome_adapter_analytics::grpc::analytics::MetricType::NetworkIn => MetricType::NetworkIn,
-        phenome_adapter_analytics::grpc::analytics::MetricType::NetworkOut => {
-            MetricType::NetworkOut
+pub(super) async fn subscribe_anomalies(
+    client: &AnalyticsClient,
+    cluster_id: Option<ClusterId>,
+) -> Result<AnomalyStream> {
+    let mut grpc = client.grpc();
+    let anomalies = grpc
+        .subscribe_anomalies(SubscribeAnomaliesRequest { cluster_id })
+        .await
+        .context("failed to subscribe to anomalies")?
+        .into_inner();
+
+    Ok(Box::pin(anomalies.filter_map(|anomaly| match anomaly {
+        Ok(a) => match anomaly_from_proto(a) {
+            Ok(anomaly) => Some(Ok(anomaly)),
+            Err(err) => {
+                tracing::warn!("Skipping streamed anomaly: {:#}", err);
+                None
+            }
+        },
+        Err(status) => Some(Err(
+            anyhow::Error::from(status).context("anomaly stream failed"),
+        )),
+    })))
+}
+
+fn anomaly_from_proto(a: GrpcAnomaly) -> Result<Anomaly> {
+    let metric_type = MetricType::try_from(a.metric_type)
+        .with_context(|| format!("anomaly {} has an invalid metric type", a.id))?;
+    let severity = Severity::try_from(a.severity)
+        .with_context(|| format!("anomaly {} has an invalid severity", a.id))?;
+    Ok(Anomaly {
+        id: a.id,
+        cluster_id: a.cluster_id,
+        resource_id: a.resource_id,
+        detected_at: a.detected_at,
+        metric_type,
+        severity,
+        confidence: a.confidence,
+        description: a.description,
+        baseline_value: a.baseline_value,
+        observed_value: a.observed_value,
+        deviation_sigma: a.deviation_sigma,
+        related_metrics: a.related_metrics,
+        root_cause: a.root_cause,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use phenome_adapter_analytics::grpc::analytics::{
+        Anomaly as GrpcAnomaly, MetricType as GrpcMetricType, Severity as GrpcSeverity,
+    };
+    use phenome_domain::{MetricType, Severity};
+
+    use super::anomaly_from_proto;
+
+    fn grpc_anomaly(metric_type: i32, severity: i32) -> GrpcAnomaly {
+        GrpcAnomaly {
+            id: "a-1".to_string(),
+            cluster_id: "prod".to_string(),
+            resource_id: "node-1".to_string(),
+            metric_type,
+            severity,
+            ..Default::default()
         }
-        phenome_adapter_analytics::grpc::analytics::MetricType::DiskRead => MetricType::DiskRead,
-        phenome_adapter_analytics::grpc::analytics::MetricType::DiskWrite => MetricType::DiskWrite,
-        _ => MetricType::CpuUsage,
     }
-}
 
-fn map_severity(severity: phenome_adapter_analytics::grpc::analytics::Severity) -> Severity {
-    match severity {
-        phenome_adapter_analytics::grpc::analytics::Severity::Critical => Severity::Critical,
-        phenome_adapter_analytics::grpc::analytics::Severity::Warning => Severity::Warning,
-        phenome_adapter_analytics::grpc::analytics::Severity::Info => Severity::Info,
-        _ => Severity::Info,
+    #[test]
+    fn known_enum_values_map_to_their_variants() {
+        let anomaly = anomaly_from_proto(grpc_anomaly(
+            GrpcMetricType::DiskWrite as i32,
+            GrpcSeverity::Critical as i32,
+        ))
+        .unwrap();
+        assert_eq!(anomaly.metric_type, MetricType::DiskWrite);
+        assert_eq!(anomaly.severity, Severity::Critical);
+    }
+
+    #[test]
+    fn out_of_range_enum_values_are_rejected_not_coerced() {
+        let err = anomaly_from_proto(grpc_anomaly(99, GrpcSeverity::Info as i32)).unwrap_err();
+        assert!(
+            format!("{err:#}").contains("unknown metric type 99"),
+            "{err:#}"
+        );
+
+        let err = anomaly_from_proto(grpc_anomaly(GrpcMetricType::CpuUsage as i32, 7)).unwrap_err();
+        assert!(format!("{err:#}").contains("unknown severity 7"), "{err:#}");
+
+        // Unspecified is as unusable as an unknown value.
+        assert!(anomaly_from_proto(grpc_anomaly(0, GrpcSeverity::Info as i32)).is_err());
     }
 }
@@ -1,14 +1,175 @@
 use anyhow::{Context, Result};
+use std::time::Duration;
+use tonic::Code;
+use tonic::transport::{Certificate, ClientTlsConfig, Endpoint};
 
+use phenome_adapter_analytics::grpc::BearerToken;
 use phenome_adapter_analytics::grpc::analytics::analytics_service_client::AnalyticsServiceClient;
 
 use super::AnalyticsClient;
 
-pub(super) async fn connect_from_env() -> Result<AnalyticsClient> {
-    let endpoint =
+/// Longest wait between two reconnect attempts.
+pub const RECONNECT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Client of `PHENOME_ANALYTICS_URL`, sending `PHENOME_ANALYTICS_TOKEN` as a
+/// bearer token and trusting the PEM CA at `PHENOME_ANALYTICS_CA` when set.
+pub(super) fn from_env() -> Result<AnalyticsClient> {
+    let url =
         std::env::var("PHENOME_ANALYTICS_URL").unwrap_or_else(|_| "http://localhost:50051".into());
-    let client = AnalyticsServiceClient::connect(endpoint)
+    let mut endpoint = Endpoint::from_shared(url).context("invalid analytics service URL")?;
+    if let Ok(ca_path) = std::env::var("PHENOME_ANALYTICS_CA") {
+        let ca = std::fs::read(&ca_path)
+            .with_context(|| format!("failed to read analytics CA {ca_path}"))?;
+        endpoint = endpoint
+            .tls_config(ClientTlsConfig::new().ca_certificate(Certificate::from_pem(ca)))
+            .context("invalid analytics TLS configuration")?;
+    }
+    let token = BearerToken::new(std::env::var("PHENOME_ANALYTICS_TOKEN").ok())?;
+    Ok(AnalyticsClient::new(endpoint, token))
+}
+
+pub(super) async fn reconnect(client: &AnalyticsClient) -> Result<()> {
+    let channel = client
+        .endpoint
+        .connect()
         .await
         .context("failed to connect to analytics service")?;
-    Ok(AnalyticsClient { client })
+    let grpc = AnalyticsServiceClient::with_interceptor(channel, client.token.clone());
+    match client.client.write() {
+        Ok(mut current) => *current = grpc,
+        Err(_) => tracing::error!("analytics client lock poisoned"),
+    }
+    Ok(())
+}
+
+/// Whether `err` means the connection failed, so reconnecting may help.
+/// Anything the service answered with, such as a rejected token or an
+/// unknown method, would fail the same way on a fresh connection.
+pub fn is_transport_error(err: &anyhow::Error) -> bool {
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<tonic::Status>())
+        .is_some_and(|status| status.code() == Code::Unavailable)
+}
+
+pub(super) async fn reconnect_with_backoff(client: &AnalyticsClient, initial_backoff: Duration) {
+    let mut backoff = initial_backoff;
+    loop {
+        match reconnect(client).await {
+            Ok(()) => return,
+            Err(err) => {
+                tracing::warn!(
+                    "Analytics service unreachable, retrying in {:?}: {:#}",
+                    backoff,
+                    err
+                );
+            }
+        }
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(RECONNECT_MAX_BACKOFF);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{SocketAddr, TcpListener};
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use tokio::sync::oneshot;
+    use tokio::task::JoinHandle;
+    use tonic::transport::{Endpoint, Server};
+
+    use phenome_adapter_analytics::AnalyticsService;
+    use phenome_adapter_analytics::grpc::analytics::analytics_service_server::AnalyticsServiceServer;
+    use phenome_adapter_analytics::grpc::{BearerToken, GrpcAnalyticsService, MlClient};
+    use phenome_adapter_analytics::storage::sqlite::SqliteStorage;
+
+    use super::{AnalyticsClient, is_transport_error};
+
+    fn unused_addr() -> SocketAddr {
+        TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+    }
+
+    /// Serve analytics on `addr`, backed by the database at `db_path`, until
+    /// `shutdown` fires.
+    async fn serve(
+        addr: SocketAddr,
+        db_path: &Path,
+        shutdown: oneshot::Receiver<()>,
+    ) -> JoinHandle<()> {
+        let storage = Arc::new(SqliteStorage::new(db_path.to_string_lossy().to_string()).unwrap());
+        let ml_client = MlClient::connect(&format!("http://{}", unused_addr()))
+            .await
+            .unwrap();
+        let service =
+            GrpcAnalyticsService::new(Arc::new(AnalyticsService::new(storage, ml_client)));
+        tokio::spawn(async move {
+            Server::builder()
+                .add_service(AnalyticsServiceServer::new(service))
+                .serve_with_shutdown(addr, async {
+                    shutdown.await.ok();
+                })
+                .await
+                .unwrap();
+        })
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn client_reconnects_after_the_service_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("analytics.db");
+        let addr = unused_addr();
+        let (stop, stopped) = oneshot::channel();
+        let server = serve(addr, &db_path, stopped).await;
+
+        let endpoint = Endpoint::from_shared(format!("http://{addr}")).unwrap();
+        let client = AnalyticsClient::new(endpoint, BearerToken::default());
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            client.reconnect_with_backoff(Duration::from_millis(20)),
+        )
+        .await
+        .expect("never connected");
+        assert!(client.fetch_metrics(None).await.is_ok());
+
+        stop.send(()).unwrap();
+        server.await.unwrap();
+        assert!(client.fetch_metrics(None).await.is_err());
+
+        let (_stop, stopped) = oneshot::channel();
+        tokio::spawn({
+            let db_path = db_path.clone();
+            async move {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                serve(addr, &db_path, stopped).await
+            }
+        });
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            client.reconnect_with_backoff(Duration::from_millis(20)),
+        )
+        .await
+        .expect("never reconnected");
+        assert!(client.fetch_metrics(None).await.is_ok());
+    }
+
+    #[test]
+    fn only_unavailable_calls_count_as_transport_errors() {
+        let failed =
+            |status: tonic::Status| anyhow::Error::from(status).context("failed to query metrics");
+        assert!(is_transport_error(&failed(tonic::Status::unavailable(
+            "connection refused"
+        ))));
+        assert!(!is_transport_error(&failed(
+            tonic::Status::unauthenticated("bad token")
+        )));
+        assert!(!is_transport_error(&failed(tonic::Status::unimplemented(
+            "ListClusters"
+        ))));
+        assert!(!is_transport_error(&anyhow::anyhow!("malformed sample")));
+    }
 }
@@ -1,25 +1,156 @@
 use anyhow::{Context, Result};
+use std::time::Duration;
+use tokio_stream::StreamExt;
 
-use phenome_adapter_analytics::grpc::analytics::QueryMetricsRequest;
-use phenome_domain::MetricSample;
+use phenome_adapter_analytics::grpc::analytics::{
+    ListClustersRequest, MetricBucket, MetricSample as GrpcMetricSample, QueryDownsampledRequest,
+    QueryMetricsRequest,
+};
+use phenome_adapter_analytics::grpc::samples_from_proto;
+use phenome_domain::{ClusterId, MetricSample, MetricType, MetricsQuery, ResourceType, TimeRange};
 
-use super::AnalyticsClient;
+use super::{AnalyticsClient, MetricsStream};
 
-pub(super) async fn fetch_metrics(client: &AnalyticsClient) -> Result<Vec<MetricSample>> {
-    let mut grpc = client.client.clone();
-    let request = QueryMetricsRequest {
-        cluster_id: None,
-        resource_type: None,
-        resource_ids: Vec::new(),
-        metric_types: Vec::new(),
-        time_range: None,
+pub(super) async fn fetch_metrics(
+    client: &AnalyticsClient,
+    cluster_id: Option<ClusterId>,
+) -> Result<Vec<MetricSample>> {
+    let query = MetricsQuery {
+        cluster_id,
+        ..MetricsQuery::default()
     };
-    let response = grpc.query_metrics(request).await?;
-    let samples = response.into_inner().samples;
+    query_metrics(client, query).await
+}
+
+pub(super) async fn fetch_metrics_range(
+    client: &AnalyticsClient,
+    cluster_id: Option<ClusterId>,
+    range: TimeRange,
+    resource_type: Option<ResourceType>,
+    metric_types: Vec<MetricType>,
+) -> Result<Vec<MetricSample>> {
+    let query = MetricsQuery {
+        cluster_id,
+        resource_type,
+        metric_types,
+        time_range: Some(range),
+        ..MetricsQuery::default()
+    };
+    query_metrics(client, query).await
+}
+
+pub(super) async fn fetch_metrics_downsampled(
+    client: &AnalyticsClient,
+    cluster_id: Option<ClusterId>,
+    range: TimeRange,
+    resolution: Duration,
+) -> Result<Vec<MetricSample>> {
+    let query = MetricsQuery {
+        cluster_id,
+        time_range: Some(range),
+        ..MetricsQuery::default()
+    };
+    let mut grpc = client.grpc();
+    let response = grpc
+        .query_downsampled(QueryDownsampledRequest {
+            query: Some(QueryMetricsRequest::from(query)),
+            max_points: max_points(range, resolution),
+        })
+        .await
+        .context("failed to query downsampled metrics")?;
+    let buckets = response.into_inner().buckets;
+    let mut samples = convert_samples(buckets.into_iter().map(bucket_average).collect());
+    samples.sort_by_key(|sample| sample.timestamp);
+    Ok(samples)
+}
+
+/// Buckets per series for one bucket per `resolution` step of `range`.
+fn max_points(range: TimeRange, resolution: Duration) -> u32 {
+    let width_ms = (resolution.as_millis() as i64).max(1);
+    let span_ms = range.duration_ms() + 1;
+    u32::try_from((span_ms + width_ms - 1) / width_ms)
+        .unwrap_or(u32::MAX)
+        .max(1)
+}
 
+/// A bucket as one sample carrying its average at the bucket start.
+fn bucket_average(bucket: MetricBucket) -> GrpcMetricSample {
+    GrpcMetricSample {
+        cluster_id: bucket.cluster_id,
+        resource_type: bucket.resource_type,
+        resource_id: bucket.resource_id,
+        metric_type: bucket.metric_type,
+        timestamp: bucket.bucket_start,
+        value: bucket.avg,
+        unit: bucket.unit,
+    }
+}
+
+async fn query_metrics(client: &AnalyticsClient, query: MetricsQuery) -> Result<Vec<MetricSample>> {
+    let mut grpc = client.grpc();
+    let response = grpc
+        .query_metrics(QueryMetricsRequest::from(query))
+        .await
+        .context("failed to query metrics")?;
+    Ok(convert_samples(response.into_inner().samples))
+}
+
+pub(super) async fn list_clusters(client: &AnalyticsClient) -> Result<Vec<ClusterId>> {
+    let mut grpc = client.grpc();
+    let response = grpc
+        .list_clusters(ListClustersRequest {})
+        .await
+        .context("failed to list clusters")?;
+    Ok(response.into_inner().cluster_ids)
+}
+
+pub(super) async fn stream_metrics(
+    client: &AnalyticsClient,
+    query: MetricsQuery,
+) -> Result<MetricsStream> {
+    let mut grpc = client.grpc();
+    let batches = grpc
+        .stream_metrics(QueryMetricsRequest::from(query))
+        .await
+        .context("failed to start metrics stream")?
+        .into_inner();
+
+    Ok(Box::pin(batches.map(|batch| {
+        Ok(convert_samples(
+            batch.context("metrics stream failed")?.samples,
+        ))
+    })))
+}
+
+/// Valid samples of a response; malformed ones are dropped and counted in
+/// the log rather than failing the whole response.
+fn convert_samples(samples: Vec<GrpcMetricSample>) -> Vec<MetricSample> {
+    let (samples, rejected) = samples_from_proto(samples);
+    if rejected > 0 {
+        tracing::warn!(
+            "Skipped {} malformed metric samples from analytics",
+            rejected
+        );
+    }
     samples
-        .into_iter()
-        .map(|s| s.try_into())
-        .collect::<Result<Vec<_>, _>>()
-        .context("failed to convert metrics")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use phenome_domain::TimeRange;
+
+    use super::max_points;
+
+    #[test]
+    fn one_bucket_per_resolution_step() {
+        let hour = TimeRange {
+            start_ms: 0,
+            end_ms: 60 * 60_000 - 1,
+        };
+        assert_eq!(max_points(hour, Duration::from_secs(60)), 60);
+        assert_eq!(max_points(hour, Duration::from_secs(7 * 60)), 9);
+        assert_eq!(max_points(hour, Duration::from_secs(24 * 60 * 60)), 1);
+    }
 }
@@ -1,33 +1,185 @@
 use anyhow::Result;
-use tonic::transport::Channel;
+use std::pin::Pin;
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::Duration;
+use tokio_stream::Stream;
+use tonic::codegen::InterceptedService;
+use tonic::transport::{Channel, Endpoint};
 
+use phenome_adapter_analytics::grpc::BearerToken;
 use phenome_adapter_analytics::grpc::analytics::analytics_service_client::AnalyticsServiceClient;
-use phenome_domain::{Anomaly, MetricSample, Recommendation};
+use phenome_domain::{
+    Anomaly, ClusterId, MetricSample, MetricType, MetricsQuery, Notification, Recommendation,
+    ResourceType, ScalingPrediction, TimeRange,
+};
 
 mod anomalies;
 mod connection;
 mod metrics;
+mod notifications;
+mod predictions;
 mod recommendations;
 
+pub use connection::{RECONNECT_MAX_BACKOFF, is_transport_error};
+
+/// Sample batches of a streamed query, oldest first. Dropping the stream
+/// cancels the query on the server.
+pub type MetricsStream = Pin<Box<dyn Stream<Item = Result<Vec<MetricSample>>> + Send>>;
+
+/// Anomalies as the server detects them, starting with its recent backlog.
+pub type AnomalyStream = Pin<Box<dyn Stream<Item = Result<Anomaly>> + Send>>;
+
+type GrpcClient = AnalyticsServiceClient<InterceptedService<Channel, BearerToken>>;
+
+/// Client of the analytics service. Clones share the connection, so a
+/// reconnect through any of them is seen by all.
 #[derive(Debug, Clone)]
 pub struct AnalyticsClient {
-    client: AnalyticsServiceClient<Channel>,
+    client: Arc<RwLock<GrpcClient>>,
+    endpoint: Endpoint,
+    token: BearerToken,
 }
 
 impl AnalyticsClient {
+    /// Client of `endpoint` that connects on its first call.
+    pub fn new(endpoint: Endpoint, token: BearerToken) -> Self {
+        let channel = endpoint.connect_lazy();
+        Self {
+            client: Arc::new(RwLock::new(AnalyticsServiceClient::with_interceptor(
+                channel,
+                token.clone(),
+            ))),
+            endpoint,
+            token,
+        }
+    }
+
+    /// Client configured from the environment, connecting on its first call.
+    pub fn from_env() -> Result<Self> {
+        connection::from_env()
+    }
+
     pub async fn connect_from_env() -> Result<Self> {
-        connection::connect_from_env().await
+        let client = Self::from_env()?;
+        client.reconnect().await?;
+        Ok(client)
+    }
+
+    /// Replace the channel with a fresh connection.
+    pub async fn reconnect(&self) -> Result<()> {
+        connection::reconnect(self).await
+    }
+
+    /// Reconnect until it succeeds, waiting `initial_backoff` after the first
+    /// failed attempt and doubling the wait per attempt up to
+    /// [`RECONNECT_MAX_BACKOFF`].
+    pub async fn reconnect_with_backoff(&self, initial_backoff: Duration) {
+        connection::reconnect_with_backoff(self, initial_backoff).await
+    }
+
+    /// Raw samples of cluster `cluster_id`, or of every cluster for `None`.
+    pub async fn fetch_metrics(&self, cluster_id: Option<ClusterId>) -> Result<Vec<MetricSample>> {
+        metrics::fetch_metrics(self, cluster_id).await
+    }
+
+    /// Raw samples recorded within `range`, narrowed to `cluster_id`,
+    /// `resource_type` and `metric_types` when given.
+    pub async fn fetch_metrics_range(
+        &self,
+        cluster_id: Option<ClusterId>,
+        range: TimeRange,
+        resource_type: Option<ResourceType>,
+        metric_types: Vec<MetricType>,
+    ) -> Result<Vec<MetricSample>> {
+        metrics::fetch_metrics_range(self, cluster_id, range, resource_type, metric_types).await
+    }
+
+    /// Samples within `range` averaged by the server per series over
+    /// buckets of about `resolution`, so a long range arrives as one point
+    /// per bucket rather than every raw sample.
+    pub async fn fetch_metrics_downsampled(
+        &self,
+        cluster_id: Option<ClusterId>,
+        range: TimeRange,
+        resolution: Duration,
+    ) -> Result<Vec<MetricSample>> {
+        metrics::fetch_metrics_downsampled(self, cluster_id, range, resolution).await
+    }
+
+    /// Ids of the clusters the service has samples of, sorted.
+    pub async fn list_clusters(&self) -> Result<Vec<ClusterId>> {
+        metrics::list_clusters(self).await
+    }
+
+    /// Stream raw samples matching `query` in batches as the server reads
+    /// them, for views that render progressively.
+    pub async fn stream_metrics(&self, query: MetricsQuery) -> Result<MetricsStream> {
+        metrics::stream_metrics(self, query).await
+    }
+
+    pub async fn fetch_anomalies(&self, cluster_id: Option<ClusterId>) -> Result<Vec<Anomaly>> {
+        anomalies::fetch_anomalies(self, cluster_id).await
+    }
+
+    /// Follow anomalies of `cluster_id`, or of every cluster for `None`, as
+    /// they are detected; dropping the stream ends the subscription.
+    pub async fn subscribe_anomalies(
+        &self,
+        cluster_id: Option<ClusterId>,
+    ) -> Result<AnomalyStream> {
+        anomalies::subscribe_anomalies(self, cluster_id).await
+    }
+
+    /// Forecasts `horizon` past the end of `range`, one per resource and
+    /// metric with samples in it.
+    pub async fn fetch_predictions(
+        &self,
+        cluster_id: Option<ClusterId>,
+        range: TimeRange,
+        horizon: Duration,
+        metric_types: Vec<MetricType>,
+    ) -> Result<Vec<ScalingPrediction>> {
+        predictions::fetch_predictions(self, cluster_id, range, horizon, metric_types).await
+    }
+
+    pub async fn fetch_recommendations(
+        &self,
+        cluster_id: Option<ClusterId>,
+    ) -> Result<Vec<Recommendation>> {
+        recommendations::fetch_recommendations(self, cluster_id).await
+    }
+
+    /// Apply recommendation `id` now, or schedule its scale action for
+    /// `execute_at` (ms). Returns the recommendation with its new status.
+    pub async fn apply_recommendation(
+        &self,
+        id: &str,
+        execute_at: Option<i64>,
+    ) -> Result<Recommendation> {
+        recommendations::apply_recommendation(self, id, execute_at).await
+    }
+
+    /// Dismiss recommendation `id` for `reason`. Returns the recommendation
+    /// with its new status.
+    pub async fn dismiss_recommendation(&self, id: &str, reason: &str) -> Result<Recommendation> {
+        recommendations::dismiss_recommendation(self, id, reason).await
     }
 
-    pub async fn fetch_metrics(&self) -> Result<Vec<MetricSample>> {
-        metrics::fetch_metrics(self).await
+    /// Notifications of the inbox not yet marked read, newest first.
+    pub async fn fetch_unread_notifications(&self) -> Result<Vec<Notification>> {
+        notifications::fetch_unread_notifications(self).await
     }
 
-    pub async fn fetch_anomalies(&self) -> Result<Vec<Anomaly>> {
-        anomalies::fetch_anomalies(self).await
+    /// Mark notification `id` read, so it is not unread on the next start.
+    pub async fn mark_notification_read(&self, id: String) -> Result<()> {
+        notifications::mark_notification_read(self, id).await
     }
 
-    pub async fn fetch_recommendations(&self) -> Result<Vec<Recommendation>> {
-        recommendations::fetch_recommendations(self).await
+    fn grpc(&self) -> GrpcClient {
+        // A clone can't observe a half-finished write, so poisoning is moot.
+        self.client
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
     }
 }
@@ -0,0 +1,83 @@
+use anyhow::{Context, Result};
+
+use phenome_adapter_analytics::grpc::analytics::{
+    ListUnreadNotificationsRequest, MarkNotificationReadRequest, Notification as GrpcNotification,
+};
+use phenome_domain::{Notification, NotificationChannel, Severity};
+use phenome_ports::NotificationPort;
+
+use super::AnalyticsClient;
+
+pub(super) async fn fetch_unread_notifications(
+    client: &AnalyticsClient,
+) -> Result<Vec<Notification>> {
+    let mut grpc = client.grpc();
+    let response = grpc
+        .list_unread_notifications(ListUnreadNotificationsRequest {})
+        .await
+        .context("failed to fetch unread notifications")?;
+
+    Ok(response
+        .into_inner()
+        .notifications
+        .into_iter()
+        .filter_map(|n| match notification_from_proto(n) {
+            Ok(notification) => Some(notification),
+            Err(err) => {
+                tracing::warn!("Skipping notification: {:#}", err);
+                None
+            }
+        })
+        .collect())
+}
+
+pub(super) async fn mark_notification_read(client: &AnalyticsClient, id: String) -> Result<()> {
+    let mut grpc = client.grpc();
+    grpc.mark_notification_read(MarkNotificationReadRequest { id: id.clone() })
+        .await
+        .with_context(|| format!("failed to mark notification {id} read"))?;
+    Ok(())
+}
+
+fn notification_from_proto(n: GrpcNotification) -> Result<Notification> {
+    let severity = Severity::try_from(n.severity)
+        .with_context(|| format!("notification {} has an invalid severity", n.id))?;
+    Ok(Notification {
+        id: n.id,
+        title: n.title,
+        message: n.message,
+        severity,
+        timestamp: n.timestamp,
+        read: n.read,
+        link: n.link,
+        cluster_id: n.cluster_id,
+        resource_id: n.resource_id,
+    })
+}
+
+/// The inbox kept by analytics-service. Delivery stays with the service, so
+/// only the read state is reachable through the client.
+#[tonic::async_trait]
+impl NotificationPort for AnalyticsClient {
+    async fn send_notification(&self, notification: Notification) -> Result<()> {
+        anyhow::bail!(
+            "notification {} not sent: analytics-service delivers notifications",
+            notification.id
+        )
+    }
+
+    async fn configure_channel(&self, channel: NotificationChannel) -> Result<()> {
+        anyhow::bail!(
+            "channel {} not configured: channels are set in the analytics-service config",
+            channel.id
+        )
+    }
+
+    async fn mark_read(&self, id: String) -> Result<()> {
+        self.mark_notification_read(id).await
+    }
+
+    async fn list_unread(&self) -> Result<Vec<Notification>> {
+        self.fetch_unread_notifications().await
+    }
+}
@@ -0,0 +1,62 @@
+use anyhow::{Context, Result};
+use std::time::Duration;
+
+use phenome_adapter_analytics::grpc::analytics::{
+    GetPredictionsRequest, MetricType as GrpcMetricType, ScalingPrediction as GrpcPrediction,
+};
+use phenome_domain::{ClusterId, MetricType, ScalingPrediction, TimeRange};
+
+use super::AnalyticsClient;
+
+pub(super) async fn fetch_predictions(
+    client: &AnalyticsClient,
+    cluster_id: Option<ClusterId>,
+    range: TimeRange,
+    horizon: Duration,
+    metric_types: Vec<MetricType>,
+) -> Result<Vec<ScalingPrediction>> {
+    let mut grpc = client.grpc();
+    let request = GetPredictionsRequest {
+        cluster_id,
+        time_range: Some(range.into()),
+        horizon_ms: horizon.as_millis() as i64,
+        metric_types: metric_types
+            .into_iter()
+            .map(|t| GrpcMetricType::from(t) as i32)
+            .collect(),
+    };
+    let response = grpc
+        .get_predictions(request)
+        .await
+        .context("failed to fetch predictions")?;
+
+    Ok(response
+        .into_inner()
+        .predictions
+        .into_iter()
+        .filter_map(|p| match prediction_from_proto(p) {
+            Ok(prediction) => Some(prediction),
+            Err(err) => {
+                tracing::warn!("Skipping prediction: {:#}", err);
+                None
+            }
+        })
+        .collect())
+}
+
+fn prediction_from_proto(p: GrpcPrediction) -> Result<ScalingPrediction> {
+    let metric_type = MetricType::try_from(p.metric_type)
+        .with_context(|| format!("prediction of {} has an invalid metric type", p.resource_id))?;
+    let millis = |ms: i64| Duration::from_millis(ms.max(0) as u64);
+    Ok(ScalingPrediction {
+        resource_id: p.resource_id,
+        metric_type,
+        generated_at: p.generated_at,
+        horizon: millis(p.horizon),
+        predicted_value: p.predicted_value,
+        lower_bound: p.lower_bound,
+        upper_bound: p.upper_bound,
+        unit: p.unit,
+        time_to_threshold: p.time_to_threshold_ms.map(millis),
+    })
+}
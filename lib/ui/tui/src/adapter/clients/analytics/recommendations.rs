@@ -1,20 +1,24 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 
 use phenome_adapter_analytics::grpc::analytics::{
-    recommendation_action::Action as GrpcAction,
+    ApplyRecommendationRequest, DismissRecommendationRequest, GetRecommendationsRequest,
+    Recommendation as GrpcRecommendation, recommendation_action::Action as GrpcAction,
     recommendation_status::Status as GrpcStatus,
-    GetRecommendationsRequest, Priority as GrpcPriority, RecommendationType as GrpcType,
 };
 use phenome_domain::{
-    CostImpact, Priority, Recommendation, RecommendationAction, RecommendationStatus,
+    ClusterId, CostImpact, Priority, Recommendation, RecommendationAction, RecommendationStatus,
     RecommendationType, ResourceLimits,
 };
 
 use super::AnalyticsClient;
 
-pub(super) async fn fetch_recommendations(client: &AnalyticsClient) -> Result<Vec<Recommendation>> {
-    let mut grpc = client.client.clone();
+pub(super) async fn fetch_recommendations(
+    client: &AnalyticsClient,
+    cluster_id: Option<ClusterId>,
+) -> Result<Vec<Recommendation>> {
+    let mut grpc = client.grpc();
     let request = GetRecommendationsRequest {
+        cluster_id,
         limit: Some(20),
         ..Default::default()
     };
@@ -23,60 +27,76 @@ pub(super) async fn fetch_recommendations(client: &AnalyticsClient) -> Result<Ve
 
     Ok(recs
         .into_iter()
-        .map(|r| {
-            let recommendation_type = map_type(r.recommendation_type());
-            let priority = map_priority(r.priority());
-            Recommendation {
-                id: r.id,
-                cluster_id: r.cluster_id,
-                created_at: r.created_at,
-                recommendation_type,
-                priority,
-            confidence: r.confidence,
-            title: r.title,
-            description: r.description,
-            impact_estimate: r.impact_estimate,
-            cost_impact: r.cost_impact.map(|cost| CostImpact {
-                daily_change: cost.daily_change,
-                currency: cost.currency,
-            }),
-            action: r
-                .action
-                .and_then(|a| a.action)
-                .map(map_action)
-                .unwrap_or(RecommendationAction::ScaleDeployment {
-                    name: "unknown".into(),
-                    from: 0,
-                    to: 0,
-                }),
-            status: r
-                .status
-                .and_then(|s| s.status)
-                .map(map_status)
-                .unwrap_or(RecommendationStatus::Pending),
+        .filter_map(|r| match recommendation_from_proto(r) {
+            Ok(recommendation) => Some(recommendation),
+            Err(err) => {
+                tracing::warn!("Skipping recommendation: {:#}", err);
+                None
             }
         })
         .collect())
 }
 
-fn map_type(rec_type: GrpcType) -> RecommendationType {
-    match rec_type {
-        GrpcType::ScaleUp => RecommendationType::ScaleUp,
-        GrpcType::ScaleDown => RecommendationType::ScaleDown,
-        GrpcType::OptimizeResources => RecommendationType::OptimizeResources,
-        GrpcType::AdjustLimits => RecommendationType::AdjustLimits,
-        GrpcType::StorageOptimizations => RecommendationType::StorageOptimization,
-        _ => RecommendationType::OptimizeResources,
-    }
+pub(super) async fn apply_recommendation(
+    client: &AnalyticsClient,
+    id: &str,
+    execute_at: Option<i64>,
+) -> Result<Recommendation> {
+    let mut grpc = client.grpc();
+    let request = ApplyRecommendationRequest {
+        id: id.to_string(),
+        execute_at,
+    };
+    let response = grpc.apply_recommendation(request).await?;
+    recommendation_from_proto(response.into_inner())
 }
 
-fn map_priority(priority: GrpcPriority) -> Priority {
-    match priority {
-        GrpcPriority::High => Priority::High,
-        GrpcPriority::Medium => Priority::Medium,
-        GrpcPriority::Low => Priority::Low,
-        _ => Priority::Medium,
-    }
+pub(super) async fn dismiss_recommendation(
+    client: &AnalyticsClient,
+    id: &str,
+    reason: &str,
+) -> Result<Recommendation> {
+    let mut grpc = client.grpc();
+    let request = DismissRecommendationRequest {
+        id: id.to_string(),
+        reason: reason.to_string(),
+    };
+    let response = grpc.dismiss_recommendation(request).await?;
+    recommendation_from_proto(response.into_inner())
+}
+
+fn recommendation_from_proto(r: GrpcRecommendation) -> Result<Recommendation> {
+    let recommendation_type = RecommendationType::try_from(r.recommendation_type)
+        .with_context(|| format!("recommendation {} has an invalid type", r.id))?;
+    let priority = Priority::try_from(r.priority)
+        .with_context(|| format!("recommendation {} has an invalid priority", r.id))?;
+    Ok(Recommendation {
+        id: r.id,
+        cluster_id: r.cluster_id,
+        created_at: r.created_at,
+        recommendation_type,
+        priority,
+        confidence: r.confidence,
+        title: r.title,
+        description: r.description,
+        impact_estimate: r.impact_estimate,
+        cost_impact: r.cost_impact.map(|cost| CostImpact {
+            daily_change: cost.daily_change,
+            currency: cost.currency,
+        }),
+        action: r.action.and_then(|a| a.action).map(map_action).unwrap_or(
+            RecommendationAction::ScaleDeployment {
+                name: "unknown".into(),
+                from: 0,
+                to: 0,
+            },
+        ),
+        status: r
+            .status
+            .and_then(|s| s.status)
+            .map(map_status)
+            .unwrap_or(RecommendationStatus::Pending),
+    })
 }
 
 fn map_action(action: GrpcAction) -> RecommendationAction {
@@ -2,7 +2,7 @@ use anyhow::Result;
 
 use phenome_domain::{ActionId, ActionSafety, Event, EventLevel};
 
-use super::super::super::{App, ConfirmPrompt};
+use super::super::super::{App, ConfirmPrompt, ConfirmTarget};
 
 impl App {
     pub fn request_action(
@@ -14,7 +14,7 @@ impl App {
     ) -> Result<()> {
         if requires_confirmation || safety == ActionSafety::Destructive {
             self.confirm = Some(ConfirmPrompt {
-                action_id,
+                target: ConfirmTarget::Action(action_id),
                 label: label.to_string(),
                 safety,
             });
@@ -30,7 +30,12 @@ impl App {
     pub fn confirm_action(&mut self, approved: bool) -> Result<()> {
         if let Some(confirm) = self.confirm.take() {
             if approved {
-                self.runtime.trigger_action(confirm.action_id)?;
+                match confirm.target {
+                    ConfirmTarget::Action(action_id) => self.runtime.trigger_action(action_id)?,
+                    ConfirmTarget::ApplyRecommendation { id, action } => {
+                        self.apply_recommendation(id, &action)
+                    }
+                }
             } else {
                 self.runtime.events_mut().push(Event::new(
                     EventLevel::Warn,
@@ -1,6 +1,11 @@
+use graphviz_rust::cmd::Format;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use phenome_domain::{Event, EventLevel};
 
-use crate::app::App;
+use crate::adapter::ui::support::clipboard::{CopyMethod, copy_text};
+use crate::app::{App, NavView};
 
 impl App {
     pub fn activate_graph_selection(&mut self) {
@@ -12,4 +17,111 @@ impl App {
             format!("Topology focus: {}", node.label),
         ));
     }
+
+    /// Access URLs whose service name matches the step id, ignoring case
+    /// and dashes.
+    pub fn step_access_urls(&self, step_id: &str) -> Vec<String> {
+        let id_lower = step_id.to_lowercase();
+        let id_norm = id_lower.replace('-', "");
+        self.context
+            .ports
+            .bootstrap
+            .access_urls()
+            .into_iter()
+            .filter(|info| {
+                let svc_lower = info.service.to_lowercase();
+                let svc_norm = svc_lower.replace('-', "");
+                svc_lower.contains(&id_lower)
+                    || id_lower.contains(&svc_lower)
+                    || svc_norm.contains(&id_norm)
+                    || id_norm.contains(&svc_norm)
+            })
+            .map(|info| info.url)
+            .collect()
+    }
+
+    /// Copy the selected node's id and its access URLs, one per line.
+    pub fn copy_selected_node(&mut self) {
+        let Some(id) = self.graph.selected_id().map(str::to_string) else {
+            return;
+        };
+        let urls = self.step_access_urls(&id);
+        let text = std::iter::once(id.clone())
+            .chain(urls.iter().cloned())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let what = match urls.len() {
+            0 => id,
+            1 => format!("{id} and 1 URL"),
+            count => format!("{id} and {count} URLs"),
+        };
+        let event = match copy_text(&text) {
+            Ok(CopyMethod::System) => Event::new(EventLevel::Info, format!("Copied {what}")),
+            Ok(CopyMethod::Osc52) => Event::new(
+                EventLevel::Info,
+                format!("Copied {what} via terminal clipboard"),
+            ),
+            Err(error) => Event::new(EventLevel::Warn, format!("Copy failed: {error:#}")),
+        };
+        self.runtime.events_mut().push(event);
+    }
+
+    /// Collapse the selected step's domain into one node, or expand the
+    /// collapsed domain that is selected.
+    pub fn toggle_selected_domain(&mut self) {
+        let domain = match self.graph.selected_collapsed_domain() {
+            Some(domain) => domain.to_string(),
+            None => {
+                let Some(id) = self.graph.selected_id() else {
+                    return;
+                };
+                let steps = &self.runtime.snapshot().assembly_steps;
+                let Some(step) = steps.iter().find(|step| step.id == id) else {
+                    return;
+                };
+                step.domain.clone()
+            }
+        };
+        self.graph.toggle_domain(&domain);
+    }
+
+    /// Start exporting the shown graph into the working directory as SVG or
+    /// PNG. Where it went is reported once graphviz is done.
+    pub fn export_graph(&mut self, format: Format) {
+        let view = match self.active_view() {
+            NavView::TopologyDagGraph => "dag",
+            NavView::TopologyDualGraph => "dual",
+            _ => return,
+        };
+        let extension = match format {
+            Format::Png => "png",
+            _ => "svg",
+        };
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        let path = PathBuf::from(format!("topology-{view}-{stamp}.{extension}"));
+        if let Err(error) = self.graph.export_to_file(path, format) {
+            self.runtime.events_mut().push(Event::new(
+                EventLevel::Warn,
+                format!("Graph export failed: {error:#}"),
+            ));
+        }
+    }
+
+    /// Report a finished graph export.
+    pub(crate) fn poll_graph_export(&mut self) {
+        let Some(result) = self.graph.poll_export() else {
+            return;
+        };
+        let event = match result {
+            Ok(path) => Event::new(
+                EventLevel::Info,
+                format!("Graph exported to {}", path.display()),
+            ),
+            Err(error) => Event::new(EventLevel::Warn, format!("Graph export failed: {error:#}")),
+        };
+        self.runtime.events_mut().push(event);
+    }
 }
@@ -0,0 +1,56 @@
+use std::sync::mpsc::{self, TryRecvError};
+use std::thread;
+
+use phenome_domain::{Event, EventLevel};
+
+use crate::app::App;
+
+impl App {
+    /// Restart the live status watcher on a background thread; connecting
+    /// can take seconds. The outcome is picked up on tick.
+    pub fn reconnect_live_status(&mut self) {
+        if self.live_status_reconnect.is_some() {
+            return;
+        }
+        let health = self.context.ports.health.clone();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = tx.send(health.reconnect());
+        });
+        self.live_status_reconnect = Some(rx);
+        self.runtime
+            .events_mut()
+            .push(Event::new(EventLevel::Info, "Reconnecting live status"));
+    }
+
+    /// Apply the outcome of a finished reconnect, clearing the offline state
+    /// on success.
+    pub(crate) fn poll_live_status_reconnect(&mut self) {
+        let Some(rx) = &self.live_status_reconnect else {
+            return;
+        };
+        let result = match rx.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => {
+                Err("live status reconnect worker panicked".to_string())
+            }
+        };
+        self.live_status_reconnect = None;
+        match result {
+            Ok(()) => {
+                self.context.live_status_error = None;
+                self.runtime
+                    .events_mut()
+                    .push(Event::new(EventLevel::Info, "Live status reconnected"));
+            }
+            Err(error) => {
+                self.runtime.events_mut().push(Event::new(
+                    EventLevel::Warn,
+                    format!("Live status reconnect failed: {error}"),
+                ));
+                self.context.live_status_error = Some(error);
+            }
+        }
+    }
+}
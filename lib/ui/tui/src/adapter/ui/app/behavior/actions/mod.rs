@@ -1,4 +1,7 @@
 mod confirm;
 mod graph;
+mod live_status;
 mod logs;
+mod notifications;
+mod recommendations;
 mod selection;
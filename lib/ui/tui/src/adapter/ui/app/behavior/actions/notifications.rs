@@ -0,0 +1,55 @@
+use phenome_domain::Notification;
+
+use crate::app::App;
+
+impl App {
+    /// Notifications not yet marked read, for the header badge.
+    pub fn unread_notification_count(&self) -> usize {
+        self.notifications.iter().filter(|n| !n.read).count()
+    }
+
+    /// Replace the unread notifications with a fresh listing. Those read
+    /// while the panel is open stay until it closes, so the one being
+    /// looked at doesn't vanish.
+    pub(crate) fn set_unread_notifications(&mut self, unread: Vec<Notification>) {
+        self.notifications.retain(|n| n.read);
+        for notification in unread {
+            if !self.notifications.iter().any(|n| n.id == notification.id) {
+                self.notifications.push(notification);
+            }
+        }
+        self.notifications
+            .sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    }
+
+    /// Mark the notifications with `ids` read once the panel has shown them,
+    /// so they are not unread again on the next start.
+    pub(crate) fn mark_notifications_read(&mut self, ids: &[String]) {
+        let mut newly_read = Vec::new();
+        for notification in &mut self.notifications {
+            if !notification.read && ids.contains(&notification.id) {
+                notification.read = true;
+                newly_read.push(notification.id.clone());
+            }
+        }
+        if newly_read.is_empty() {
+            return;
+        }
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let notifications = self.context.ports.notifications.clone();
+        handle.spawn(async move {
+            for id in newly_read {
+                if let Err(err) = notifications.mark_read(id.clone()).await {
+                    tracing::warn!("Failed to mark notification {} read: {:#}", id, err);
+                }
+            }
+        });
+    }
+
+    /// Drop the notifications read while the panel was open.
+    pub(crate) fn forget_read_notifications(&mut self) {
+        self.notifications.retain(|n| !n.read);
+    }
+}
@@ -0,0 +1,220 @@
+use phenome_domain::{
+    ActionSafety, Event, EventLevel, Recommendation, RecommendationAction, RecommendationStatus,
+    now_millis,
+};
+
+use crate::app::App;
+use crate::app::core::{AnalyticsUpdate, ConfirmPrompt, ConfirmTarget, DismissPrompt};
+
+impl App {
+    /// The recommendation A/D act on, if the list has any.
+    pub fn selected_recommendation(&self) -> Option<&Recommendation> {
+        let recommendations = self.analytics_recommendations.as_ref()?;
+        let last = recommendations.len().checked_sub(1)?;
+        recommendations.get(self.analytics_recommendation_index.min(last))
+    }
+
+    pub fn select_next_recommendation(&mut self) {
+        let count = self.analytics_recommendations.as_ref().map_or(0, Vec::len);
+        if count > 0 {
+            self.analytics_recommendation_index =
+                (self.analytics_recommendation_index + 1).min(count - 1);
+        }
+    }
+
+    pub fn select_prev_recommendation(&mut self) {
+        self.analytics_recommendation_index = self.analytics_recommendation_index.saturating_sub(1);
+    }
+
+    /// Ask to apply the selected recommendation. The prompt says whether
+    /// anything will run or the change is only recorded as made.
+    pub fn request_apply_recommendation(&mut self) {
+        let Some(recommendation) = self.pending_selection("apply") else {
+            return;
+        };
+        let label = if runs_on_apply(&recommendation.action) {
+            format!("Run now: {}", recommendation.title)
+        } else {
+            format!("Mark applied, nothing is run: {}", recommendation.title)
+        };
+        self.confirm = Some(ConfirmPrompt {
+            target: ConfirmTarget::ApplyRecommendation {
+                id: recommendation.id,
+                action: recommendation.action.clone(),
+            },
+            label: label.clone(),
+            safety: action_safety(&recommendation.action),
+        });
+        self.runtime.events_mut().push(Event::new(
+            EventLevel::Warn,
+            format!("Confirmation required: {label}"),
+        ));
+    }
+
+    /// Apply recommendation `id` with `action` in the background; the new
+    /// status arrives as an analytics update.
+    pub(crate) fn apply_recommendation(&self, id: String, action: &RecommendationAction) {
+        let (Some(client), Some(tx)) = (self.analytics_client.clone(), self.analytics_tx.clone())
+        else {
+            return;
+        };
+        let execute_at = apply_execute_at(action, now_millis() as i64);
+        tokio::spawn(async move {
+            let result = client
+                .apply_recommendation(&id, execute_at)
+                .await
+                .map_err(|err| format!("{err:#}"));
+            // A closed channel means the app is gone.
+            let _ = tx
+                .send(AnalyticsUpdate::RecommendationChanged(id, result))
+                .await;
+        });
+    }
+
+    /// Open the reason prompt for dismissing the selected recommendation.
+    pub fn start_dismiss_recommendation(&mut self) {
+        let Some(recommendation) = self.pending_selection("dismiss") else {
+            return;
+        };
+        self.analytics_dismiss = Some(DismissPrompt {
+            id: recommendation.id.clone(),
+            title: recommendation.title.clone(),
+            reason: String::new(),
+        });
+    }
+
+    /// Send the dismissal typed into the prompt. An empty reason keeps the
+    /// prompt open.
+    pub fn submit_dismiss_recommendation(&mut self) {
+        let Some(prompt) = self
+            .analytics_dismiss
+            .take_if(|prompt| !prompt.reason.trim().is_empty())
+        else {
+            return;
+        };
+        let (Some(client), Some(tx)) = (self.analytics_client.clone(), self.analytics_tx.clone())
+        else {
+            return;
+        };
+        tokio::spawn(async move {
+            let result = client
+                .dismiss_recommendation(&prompt.id, prompt.reason.trim())
+                .await
+                .map_err(|err| format!("{err:#}"));
+            let _ = tx
+                .send(AnalyticsUpdate::RecommendationChanged(prompt.id, result))
+                .await;
+        });
+    }
+
+    /// Show the status the service answered with in place of the old one.
+    pub(crate) fn record_recommendation_change(
+        &mut self,
+        id: String,
+        result: Result<Recommendation, String>,
+    ) {
+        let updated = match result {
+            Ok(updated) => updated,
+            Err(err) => {
+                self.runtime.events_mut().push(Event::new(
+                    EventLevel::Warn,
+                    format!("Recommendation {id} not updated: {err}"),
+                ));
+                return;
+            }
+        };
+        self.runtime.events_mut().push(Event::new(
+            EventLevel::Info,
+            format!(
+                "Recommendation {}: {}",
+                updated.title,
+                status_label(&updated.status)
+            ),
+        ));
+        if let Some(known) = self
+            .analytics_recommendations
+            .as_mut()
+            .and_then(|recs| recs.iter_mut().find(|rec| rec.id == updated.id))
+        {
+            *known = updated;
+        }
+    }
+
+    /// The selected recommendation if it can still be acted on, otherwise
+    /// an event saying why not.
+    fn pending_selection(&mut self, verb: &str) -> Option<Recommendation> {
+        let recommendation = self.selected_recommendation()?.clone();
+        if matches!(recommendation.status, RecommendationStatus::Pending) {
+            return Some(recommendation);
+        }
+        self.runtime.events_mut().push(Event::new(
+            EventLevel::Warn,
+            format!(
+                "Can't {verb} {}: already {}",
+                recommendation.title,
+                status_label(&recommendation.status)
+            ),
+        ));
+        None
+    }
+}
+
+/// Whether applying `action` runs it. The scheduler only executes scale
+/// actions; applying any other records the change as made by hand.
+fn runs_on_apply(action: &RecommendationAction) -> bool {
+    matches!(action, RecommendationAction::ScaleDeployment { .. })
+}
+
+/// `execute_at` sent when applying `action` at `now_ms`: schedule actions
+/// that run to start at once, leave the rest unscheduled.
+fn apply_execute_at(action: &RecommendationAction, now_ms: i64) -> Option<i64> {
+    runs_on_apply(action).then_some(now_ms)
+}
+
+fn action_safety(action: &RecommendationAction) -> ActionSafety {
+    match action {
+        RecommendationAction::ReclaimStorage { .. } => ActionSafety::Destructive,
+        RecommendationAction::ScaleDeployment { .. }
+        | RecommendationAction::UpdateResourceLimits { .. } => ActionSafety::Guarded,
+    }
+}
+
+fn status_label(status: &RecommendationStatus) -> &'static str {
+    match status {
+        RecommendationStatus::Pending => "pending",
+        RecommendationStatus::Scheduled { .. } => "scheduled",
+        RecommendationStatus::Applied { .. } => "applied",
+        RecommendationStatus::Dismissed { .. } => "dismissed",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use phenome_domain::{RecommendationAction, ResourceLimits};
+
+    use super::apply_execute_at;
+
+    #[test]
+    fn only_scale_actions_are_scheduled_on_apply() {
+        let scale = RecommendationAction::ScaleDeployment {
+            name: "web".to_string(),
+            from: 2,
+            to: 5,
+        };
+        assert_eq!(apply_execute_at(&scale, 1_000), Some(1_000));
+
+        let reclaim = RecommendationAction::ReclaimStorage {
+            volume: "logs".to_string(),
+            size_gb: 20,
+        };
+        assert_eq!(apply_execute_at(&reclaim, 1_000), None);
+        let limits = RecommendationAction::UpdateResourceLimits {
+            resource: "web".to_string(),
+            limits: ResourceLimits {
+                cpu: Some("500m".to_string()),
+                memory: None,
+            },
+        };
+        assert_eq!(apply_execute_at(&limits, 1_000), None);
+    }
+}
@@ -2,13 +2,18 @@
 
 use ratatui::widgets::ListState;
 use std::path::PathBuf;
-use std::time::Instant;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 // use tokio::sync::mpsc;
 
 use crate::app::{GraphRenderState, NavSection, NavView};
 use crate::state::UiState;
+use crate::util::DependencyClassifier;
 use phenome_application::Runtime;
-use phenome_domain::{ActionId, ActionSafety, Anomaly, MetricSample, Recommendation};
+use phenome_domain::{
+    ActionId, ActionSafety, Anomaly, ClusterId, MetricSample, Notification, Recommendation,
+    RecommendationAction, ScalingPrediction, TimeRange, UiConfig,
+};
 use phenome_ports::PortSet;
 
 use crate::analytics_client::AnalyticsClient;
@@ -21,6 +26,7 @@ pub struct AppContext {
     pub assembly_error: Option<String>,
     pub live_status_error: Option<String>,
     pub ports: PortSet,
+    pub ui_config: UiConfig,
 }
 
 impl AppContext {
@@ -38,6 +44,7 @@ impl AppContext {
             assembly_error: None,
             live_status_error: None,
             ports,
+            ui_config: UiConfig::default(),
         }
     }
 }
@@ -66,28 +73,221 @@ pub struct App {
     pub should_quit: bool,
     pub ui: UiState,
     pub graph: GraphRenderState,
+    pub dependency_classifier: DependencyClassifier,
     pub active_nav: NavSection,
     pub active_view: NavView,
     pub nav_sub_index: [usize; 3],
+    /// Outcome of the live status reconnect running in the background.
+    pub live_status_reconnect: Option<std::sync::mpsc::Receiver<Result<(), String>>>,
     pub analytics_metrics: Option<Vec<MetricSample>>,
+    /// Order of the real-time per-resource table.
+    pub analytics_resource_sort: ResourceSort,
+    /// Rows of the real-time per-resource table scrolled past.
+    pub analytics_resource_scroll: usize,
     pub analytics_anomalies: Option<Vec<Anomaly>>,
+    /// Anomaly timeline bucket listed by the insights panel, counted back
+    /// from the newest.
+    pub analytics_anomaly_offset: usize,
     pub analytics_recommendations: Option<Vec<Recommendation>>,
+    /// Row of the recommendations table that A/D act on.
+    pub analytics_recommendation_index: usize,
+    /// Reason being typed for a dismissal, while the prompt is open.
+    pub analytics_dismiss: Option<DismissPrompt>,
     pub analytics_cache_timestamp: Option<Instant>,
+    /// Wait between background analytics polls.
+    pub analytics_interval: Duration,
+    /// Handle on the running poll loop, once analytics started.
+    pub analytics_poll: Option<AnalyticsPoll>,
+    /// Cluster the analytics views are scoped to, `None` for all of them.
+    pub analytics_cluster: Option<ClusterId>,
+    /// Clusters the analytics service has samples of, in selector order.
+    pub analytics_clusters: Vec<ClusterId>,
     pub analytics_client: Option<AnalyticsClient>,
+    pub analytics_connection: AnalyticsConnection,
+    pub analytics_history_window: HistoricalWindow,
+    pub analytics_history: Option<Result<Vec<MetricSample>, String>>,
+    /// Range the historical samples were fetched for.
+    pub analytics_history_range: Option<TimeRange>,
+    /// Forecasts fitted to the historical window, one per resource and
+    /// metric.
+    pub analytics_predictions: Option<Result<Vec<ScalingPrediction>, String>>,
+    pub analytics_tx: Option<tokio::sync::mpsc::Sender<AnalyticsUpdate>>,
     pub analytics_rx: Option<tokio::sync::mpsc::Receiver<AnalyticsUpdate>>,
+    /// Unread notifications of the inbox, newest first, plus those marked
+    /// read while the open panel shows them.
+    pub notifications: Vec<Notification>,
 }
 
 #[derive(Debug)]
 pub enum AnalyticsUpdate {
-    Metrics(Vec<MetricSample>),
-    Anomalies(Vec<Anomaly>),
-    Recommendations(Vec<Recommendation>),
+    /// Samples of the cluster they were fetched for, `None` for all.
+    Metrics(Option<ClusterId>, Vec<MetricSample>),
+    /// Anomalies of the cluster they were fetched for, `None` for all.
+    Anomalies(Option<ClusterId>, Vec<Anomaly>),
+    /// An anomaly pushed by the live feed.
+    NewAnomaly(Anomaly),
+    /// Recommendations of the cluster they were fetched for, `None` for all.
+    Recommendations(Option<ClusterId>, Vec<Recommendation>),
+    /// Clusters the service has samples of.
+    Clusters(Vec<ClusterId>),
+    /// Unread notifications of the inbox, newest first.
+    Notifications(Vec<Notification>),
+    /// Outcome of applying or dismissing the recommendation with this id.
+    RecommendationChanged(String, Result<Recommendation, String>),
+    Connection(AnalyticsConnection),
+    /// Samples of the historical view over the cluster and range they were
+    /// fetched for, or why they couldn't be fetched.
+    History(
+        HistoricalWindow,
+        Option<ClusterId>,
+        TimeRange,
+        Result<Vec<MetricSample>, String>,
+    ),
+    /// Forecasts fitted to the historical window over the cluster they were
+    /// fetched for, or why they couldn't be fetched.
+    Predictions(
+        HistoricalWindow,
+        Option<ClusterId>,
+        Result<Vec<ScalingPrediction>, String>,
+    ),
+}
+
+/// Column the real-time per-resource table is sorted by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResourceSort {
+    /// Highest CPU first.
+    #[default]
+    Cpu,
+    /// Highest memory first.
+    Memory,
+    /// Resource id, alphabetically.
+    Name,
+}
+
+impl ResourceSort {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Cpu => Self::Memory,
+            Self::Memory => Self::Name,
+            Self::Name => Self::Cpu,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Cpu => "cpu",
+            Self::Memory => "memory",
+            Self::Name => "name",
+        }
+    }
+}
+
+/// Buckets the insights panel splits the anomaly history into.
+pub const ANOMALY_TIMELINE_BUCKETS: usize = 48;
+
+/// Lets the app steer the background analytics poll loop.
+#[derive(Debug, Clone)]
+pub struct AnalyticsPoll {
+    /// Current poll interval; the loop picks up changes at once.
+    pub interval: tokio::sync::watch::Sender<Duration>,
+    /// Wakes the loop for an immediate poll.
+    pub refresh: Arc<tokio::sync::Notify>,
+    /// Cluster the loop and the anomaly feed fetch for; changes refetch at
+    /// once.
+    pub cluster: tokio::sync::watch::Sender<Option<ClusterId>>,
+}
+
+/// State of the background connection to the analytics service.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AnalyticsConnection {
+    #[default]
+    Connecting,
+    Connected,
+    /// The service was reached before and is being reconnected to.
+    Reconnecting,
+}
+
+/// Time window shown by the historical analytics view.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HistoricalWindow {
+    #[default]
+    LastHour,
+    Last6Hours,
+    LastDay,
+    LastWeek,
+}
+
+impl HistoricalWindow {
+    pub fn next(self) -> Self {
+        match self {
+            Self::LastHour => Self::Last6Hours,
+            Self::Last6Hours => Self::LastDay,
+            Self::LastDay => Self::LastWeek,
+            Self::LastWeek => Self::LastHour,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::LastHour => "last hour",
+            Self::Last6Hours => "last 6 hours",
+            Self::LastDay => "last 24 hours",
+            Self::LastWeek => "last 7 days",
+        }
+    }
+
+    pub fn duration(self) -> Duration {
+        const HOUR: u64 = 60 * 60;
+        Duration::from_secs(match self {
+            Self::LastHour => HOUR,
+            Self::Last6Hours => 6 * HOUR,
+            Self::LastDay => 24 * HOUR,
+            Self::LastWeek => 7 * 24 * HOUR,
+        })
+    }
+
+    /// Width of the averages the window is fetched as, `None` for raw
+    /// samples. Long windows would otherwise pull every sample of the week.
+    pub fn resolution(self) -> Option<Duration> {
+        match self {
+            Self::LastHour | Self::Last6Hours => None,
+            Self::LastDay => Some(Duration::from_secs(60)),
+            Self::LastWeek => Some(Duration::from_secs(10 * 60)),
+        }
+    }
+
+    /// The window ending at `now_ms`.
+    pub fn time_range(self, now_ms: i64) -> TimeRange {
+        TimeRange {
+            start_ms: now_ms - self.duration().as_millis() as i64,
+            end_ms: now_ms,
+        }
+    }
 }
 
 /// Confirmation prompt details for high-risk actions.
 #[derive(Debug, Clone)]
 pub struct ConfirmPrompt {
-    pub action_id: ActionId,
+    pub target: ConfirmTarget,
     pub label: String,
     pub safety: ActionSafety,
-}
\ No newline at end of file
+}
+
+/// What a confirmed prompt goes on to do.
+#[derive(Debug, Clone)]
+pub enum ConfirmTarget {
+    Action(ActionId),
+    /// Apply the recommendation with this id and action.
+    ApplyRecommendation {
+        id: String,
+        action: RecommendationAction,
+    },
+}
+
+/// Dismissal of a recommendation waiting for its reason.
+#[derive(Debug, Clone)]
+pub struct DismissPrompt {
+    pub id: String,
+    pub title: String,
+    pub reason: String,
+}
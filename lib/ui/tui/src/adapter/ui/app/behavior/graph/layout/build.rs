@@ -1,12 +1,14 @@
 use std::collections::HashMap;
 
 use super::super::types::{GraphEdge, GraphNode};
-use super::GraphLayout;
+use super::components::connected_components;
+use super::{GraphLayout, NodeGrid};
 
 pub(super) struct GraphEdgeRaw {
     pub(super) tail: String,
     pub(super) head: String,
     pub(super) points: Vec<(f64, f64)>,
+    pub(super) label: Option<String>,
 }
 
 pub(super) fn build_layout(
@@ -35,11 +37,15 @@ pub(super) fn build_layout(
             tail,
             head,
             points: edge.points,
+            label: edge.label,
+            dependency: None,
         });
         outgoing[tail].push(edge_index);
         incoming[head].push(edge_index);
     }
 
+    let spatial = NodeGrid::build(&nodes);
+    let (component_of, components) = connected_components(nodes.len(), &resolved_edges);
     GraphLayout {
         width,
         height,
@@ -48,5 +54,8 @@ pub(super) fn build_layout(
         node_index,
         outgoing,
         incoming,
+        spatial,
+        component_of,
+        components,
     }
 }
@@ -0,0 +1,41 @@
+use super::super::types::GraphEdge;
+
+/// Weakly connected components of the layout.
+///
+/// Returns the component of every node and each component's node indices in
+/// ascending order. Components are ordered by their lowest node index.
+pub(super) fn connected_components(
+    node_count: usize,
+    edges: &[GraphEdge],
+) -> (Vec<usize>, Vec<Vec<usize>>) {
+    let mut parent: Vec<usize> = (0..node_count).collect();
+    for edge in edges {
+        let tail = find(&mut parent, edge.tail);
+        let head = find(&mut parent, edge.head);
+        if tail != head {
+            parent[tail.max(head)] = tail.min(head);
+        }
+    }
+
+    let mut component_of = vec![0; node_count];
+    let mut components: Vec<Vec<usize>> = Vec::new();
+    let mut root_component = vec![usize::MAX; node_count];
+    for (index, component) in component_of.iter_mut().enumerate() {
+        let root = find(&mut parent, index);
+        if root_component[root] == usize::MAX {
+            root_component[root] = components.len();
+            components.push(Vec::new());
+        }
+        *component = root_component[root];
+        components[root_component[root]].push(index);
+    }
+    (component_of, components)
+}
+
+fn find(parent: &mut [usize], mut index: usize) -> usize {
+    while parent[index] != index {
+        parent[index] = parent[parent[index]];
+        index = parent[index];
+    }
+    index
+}
@@ -1,11 +1,15 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 
-use super::types::{GraphDependencyPath, GraphEdge, GraphNode};
+use super::types::{GraphBounds, GraphDependencyPath, GraphEdge, GraphEdgeDependency, GraphNode};
 
 mod build;
+mod components;
 mod parse;
+mod spatial;
 mod tokens;
 
+pub(crate) use spatial::NodeGrid;
+
 #[derive(Debug, Clone)]
 pub struct GraphLayout {
     pub width: f64,
@@ -15,6 +19,9 @@ pub struct GraphLayout {
     pub(crate) node_index: HashMap<String, usize>,
     pub(crate) outgoing: Vec<Vec<usize>>,
     pub(crate) incoming: Vec<Vec<usize>>,
+    pub(crate) spatial: NodeGrid,
+    pub(crate) component_of: Vec<usize>,
+    pub(crate) components: Vec<Vec<usize>>,
 }
 
 impl GraphLayout {
@@ -27,40 +34,176 @@ impl GraphLayout {
         self.node_index.get(id).copied()
     }
 
+    /// Node under the point, falling back to the nearest center within `radius`.
+    pub fn hit_test(&self, x: f64, y: f64, radius: f64) -> Option<&GraphNode> {
+        let index = self
+            .spatial
+            .node_containing(&self.nodes, x, y)
+            .or_else(|| self.spatial.nearest_center(&self.nodes, x, y, radius))?;
+        self.nodes.get(index)
+    }
+
+    /// Edge whose drawn line passes within `radius` of the point, nearest
+    /// first.
+    pub fn edge_at(&self, x: f64, y: f64, radius: f64) -> Option<usize> {
+        self.edges
+            .iter()
+            .enumerate()
+            .filter_map(|(index, edge)| {
+                let distance = edge
+                    .points
+                    .windows(2)
+                    .map(|segment| segment_distance((x, y), segment[0], segment[1]))
+                    .fold(f64::INFINITY, f64::min);
+                (distance <= radius).then_some((index, distance))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _)| index)
+    }
+
+    /// Attach to each edge the dependency between its ends, in whichever
+    /// direction it runs.
+    pub(crate) fn annotate_edges(&mut self, dependencies: &[GraphEdgeDependency]) {
+        let by_ends: HashMap<(&str, &str), &GraphEdgeDependency> = dependencies
+            .iter()
+            .map(|dependency| {
+                let ends = (dependency.dependent.as_str(), dependency.provider.as_str());
+                (ends, dependency)
+            })
+            .collect();
+        for edge in &mut self.edges {
+            let tail = self.nodes[edge.tail].id.as_str();
+            let head = self.nodes[edge.head].id.as_str();
+            edge.dependency = by_ends
+                .get(&(tail, head))
+                .or_else(|| by_ends.get(&(head, tail)))
+                .map(|&dependency| dependency.clone());
+        }
+    }
+
+    pub fn component_count(&self) -> usize {
+        self.components.len()
+    }
+
+    /// Connected component containing the node, ignoring edge direction.
+    pub fn component_of(&self, id: &str) -> Option<usize> {
+        self.component_of.get(self.node_index(id)?).copied()
+    }
+
+    pub fn component_nodes(&self, component: usize) -> &[usize] {
+        self.components
+            .get(component)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Extent of the component's node boxes.
+    pub fn component_bounds(&self, component: usize) -> Option<GraphBounds> {
+        let mut nodes = self
+            .component_nodes(component)
+            .iter()
+            .filter_map(|&index| self.nodes.get(index));
+        let first = nodes.next()?;
+        let mut bounds = node_box(first);
+        for node in nodes {
+            let next = node_box(node);
+            bounds.x_min = bounds.x_min.min(next.x_min);
+            bounds.x_max = bounds.x_max.max(next.x_max);
+            bounds.y_min = bounds.y_min.min(next.y_min);
+            bounds.y_max = bounds.y_max.max(next.y_max);
+        }
+        Some(bounds)
+    }
+
     pub fn dependency_paths(&self, selected_id: &str) -> GraphDependencyPath {
+        self.dependency_paths_within(selected_id, usize::MAX)
+    }
+
+    /// Upstream and downstream of the node, at most `depth` edges away.
+    pub fn dependency_paths_within(&self, selected_id: &str, depth: usize) -> GraphDependencyPath {
         let Some(selected_index) = self.node_index(selected_id) else {
             return GraphDependencyPath::default();
         };
-        let mut nodes = HashSet::new();
-        let mut edges = HashSet::new();
-        nodes.insert(selected_index);
-
-        let mut stack = vec![selected_index];
-        while let Some(index) = stack.pop() {
-            for &edge_index in self.outgoing.get(index).into_iter().flatten() {
-                if edges.insert(edge_index) {
-                    let head = self.edges[edge_index].head;
-                    if nodes.insert(head) {
-                        stack.push(head);
-                    }
-                }
-            }
-        }
+        let mut path = GraphDependencyPath::default();
+        path.nodes.insert(selected_index);
+        self.walk(selected_index, depth, true, &mut path);
+        self.walk(selected_index, depth, false, &mut path);
+        path
+    }
 
-        let mut stack = vec![selected_index];
-        while let Some(index) = stack.pop() {
-            for &edge_index in self.incoming.get(index).into_iter().flatten() {
-                if edges.insert(edge_index) {
-                    let tail = self.edges[edge_index].tail;
-                    if nodes.insert(tail) {
-                        stack.push(tail);
-                    }
+    /// Breadth-first, so every node is reached by its shortest path and the
+    /// depth cut-off is exact.
+    fn walk(&self, start: usize, depth: usize, downstream: bool, path: &mut GraphDependencyPath) {
+        let adjacent = if downstream {
+            &self.outgoing
+        } else {
+            &self.incoming
+        };
+        let mut visited = HashSet::from([start]);
+        let mut queue = VecDeque::from([(start, 0)]);
+        while let Some((index, distance)) = queue.pop_front() {
+            if distance >= depth {
+                continue;
+            }
+            for &edge_index in adjacent.get(index).into_iter().flatten() {
+                path.edges.insert(edge_index);
+                let edge = &self.edges[edge_index];
+                let node = if downstream { edge.head } else { edge.tail };
+                path.nodes.insert(node);
+                if visited.insert(node) {
+                    queue.push_back((node, distance + 1));
                 }
             }
         }
+    }
+}
 
-        GraphDependencyPath { nodes, edges }
+fn node_box(node: &GraphNode) -> GraphBounds {
+    GraphBounds {
+        x_min: node.x - node.width / 2.0,
+        x_max: node.x + node.width / 2.0,
+        y_min: node.y - node.height / 2.0,
+        y_max: node.y + node.height / 2.0,
     }
 }
 
+/// Distance from `point` to the segment from `a` to `b`.
+fn segment_distance(point: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length_sq = dx * dx + dy * dy;
+    let t = if length_sq > 0.0 {
+        (((point.0 - a.0) * dx + (point.1 - a.1) * dy) / length_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (point.0 - (a.0 + t * dx)).hypot(point.1 - (a.1 + t * dy))
+}
+
 pub use parse::parse_plain_layout;
+
+#[cfg(test)]
+mod tests {
+    use super::parse_plain_layout;
+    use crate::app::GraphEdgeDependency;
+
+    #[test]
+    fn edges_carry_the_dependency_between_their_ends_either_way() {
+        let plain = "graph 1 4 4\n\
+            node api 1 3 1 0.5 api solid box black lightgrey\n\
+            node db 3 1 1 0.5 db solid box black lightgrey\n\
+            node web 3 3 1 0.5 web solid box black lightgrey\n\
+            edge db api 2 3 1 1 3 solid black\n\
+            edge api web 2 1 3 3 3 solid black\n\
+            stop\n";
+        let mut layout = parse_plain_layout(plain).unwrap();
+        let api_needs_db = GraphEdgeDependency {
+            dependent: "api".to_string(),
+            provider: "db".to_string(),
+            capabilities: vec!["sql".to_string()],
+        };
+        layout.annotate_edges(std::slice::from_ref(&api_needs_db));
+
+        assert_eq!(layout.edges[0].dependency.as_ref(), Some(&api_needs_db));
+        assert_eq!(layout.edges[1].dependency, None);
+    }
+}
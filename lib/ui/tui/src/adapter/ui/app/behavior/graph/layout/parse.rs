@@ -11,11 +11,7 @@ pub fn parse_plain_layout(text: &str) -> Result<super::GraphLayout> {
     let mut edges = Vec::new();
 
     for line in text.lines() {
-        let tokens = if line.contains('"') {
-            split_plain_tokens(line)
-        } else {
-            line.split_whitespace().map(|s| s.to_string()).collect()
-        };
+        let tokens = split_plain_tokens(line);
         if tokens.is_empty() {
             continue;
         }
@@ -30,6 +26,8 @@ pub fn parse_plain_layout(text: &str) -> Result<super::GraphLayout> {
                 }
             }
             "node" => {
+                // node name x y width height label style shape color fillcolor;
+                // anything after the label is styling and ignored.
                 if tokens.len() < 6 {
                     continue;
                 }
@@ -66,7 +64,14 @@ pub fn parse_plain_layout(text: &str) -> Result<super::GraphLayout> {
                     points.push((x, y));
                     idx += 2;
                 }
-                edges.push(GraphEdgeRaw { tail, head, points });
+                // An optional `label xl yl` comes before the style and color.
+                let label = (tokens.len() >= idx + 5).then(|| tokens[idx].clone());
+                edges.push(GraphEdgeRaw {
+                    tail,
+                    head,
+                    points,
+                    label,
+                });
             }
             "stop" => break,
             _ => {}
@@ -75,3 +80,40 @@ pub fn parse_plain_layout(text: &str) -> Result<super::GraphLayout> {
 
     Ok(build_layout(width, height, nodes, edges))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::parse_plain_layout;
+
+    #[test]
+    fn quoted_labels_with_spaces_and_quotes_keep_fields_aligned() {
+        let plain = concat!(
+            "graph 1 4 3\n",
+            r#"node "app/web" 1.5 2.25 1.8 0.5 "web \"frontend\" tier" solid box black lightgrey"#,
+            "\n",
+            r#"node db 3 0.75 1 0.5 "" solid ellipse black white"#,
+            "\n",
+            r#"node cache 0.5 0.5 0.9 0.4 "line\none" solid box"#,
+            "\n",
+            r#"edge "app/web" db 2 1.5 2 3 1 "uses it" 1 2 solid black"#,
+            "\n",
+            "edge db cache 2 3 1 0.5 0.5 solid black",
+            "\nstop\n",
+        );
+        let layout = parse_plain_layout(plain).unwrap();
+
+        let web = layout.node("app/web").unwrap();
+        assert_eq!(web.label, r#"web "frontend" tier"#);
+        assert_eq!((web.x, web.y, web.width, web.height), (1.5, 2.25, 1.8, 0.5));
+
+        let db = layout.node("db").unwrap();
+        assert_eq!(db.label, "");
+        assert_eq!((db.x, db.y, db.width, db.height), (3.0, 0.75, 1.0, 0.5));
+
+        assert_eq!(layout.node("cache").unwrap().label, r"line\none");
+        assert_eq!(layout.edges.len(), 2);
+        assert_eq!(layout.edges[0].label.as_deref(), Some("uses it"));
+        assert_eq!(layout.edges[0].points, [(1.5, 2.0), (3.0, 1.0)]);
+        assert_eq!(layout.edges[1].label, None);
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+
+use super::super::types::GraphNode;
+
+type Cell = (i64, i64);
+
+/// Uniform grid over node boxes and centers, built once per layout.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct NodeGrid {
+    cell_size: f64,
+    boxes: HashMap<Cell, Vec<usize>>,
+    centers: HashMap<Cell, Vec<usize>>,
+}
+
+impl NodeGrid {
+    pub(crate) fn build(nodes: &[GraphNode]) -> Self {
+        if nodes.is_empty() {
+            return Self::default();
+        }
+        let mean_extent = nodes
+            .iter()
+            .map(|node| node.width.max(node.height))
+            .sum::<f64>()
+            / nodes.len() as f64;
+        let mut grid = Self {
+            cell_size: (mean_extent * 2.0).max(0.25),
+            boxes: HashMap::new(),
+            centers: HashMap::new(),
+        };
+        for (index, node) in nodes.iter().enumerate() {
+            let half_w = node.width / 2.0;
+            let half_h = node.height / 2.0;
+            let (min_x, min_y) = grid.cell(node.x - half_w, node.y - half_h);
+            let (max_x, max_y) = grid.cell(node.x + half_w, node.y + half_h);
+            for cx in min_x..=max_x {
+                for cy in min_y..=max_y {
+                    grid.boxes.entry((cx, cy)).or_default().push(index);
+                }
+            }
+            let center = grid.cell(node.x, node.y);
+            grid.centers.entry(center).or_default().push(index);
+        }
+        grid
+    }
+
+    /// First node (in layout order) whose box contains the point.
+    pub(crate) fn node_containing(&self, nodes: &[GraphNode], x: f64, y: f64) -> Option<usize> {
+        self.boxes
+            .get(&self.cell(x, y))?
+            .iter()
+            .copied()
+            .filter(|&index| contains(&nodes[index], x, y))
+            .min()
+    }
+
+    /// Node whose center is closest to the point and strictly within `radius`.
+    pub(crate) fn nearest_center(
+        &self,
+        nodes: &[GraphNode],
+        x: f64,
+        y: f64,
+        radius: f64,
+    ) -> Option<usize> {
+        if self.centers.is_empty() || radius <= 0.0 {
+            return None;
+        }
+        let (min_x, min_y) = self.cell(x - radius, y - radius);
+        let (max_x, max_y) = self.cell(x + radius, y + radius);
+        let mut best: Option<(f64, usize)> = None;
+        for cx in min_x..=max_x {
+            for cy in min_y..=max_y {
+                let Some(indices) = self.centers.get(&(cx, cy)) else {
+                    continue;
+                };
+                for &index in indices {
+                    let node = &nodes[index];
+                    let dist = ((x - node.x).powi(2) + (y - node.y).powi(2)).sqrt();
+                    if dist >= radius {
+                        continue;
+                    }
+                    let better = match best {
+                        None => true,
+                        Some((best_dist, best_index)) => {
+                            dist < best_dist || (dist == best_dist && index < best_index)
+                        }
+                    };
+                    if better {
+                        best = Some((dist, index));
+                    }
+                }
+            }
+        }
+        best.map(|(_, index)| index)
+    }
+
+    fn cell(&self, x: f64, y: f64) -> Cell {
+        (
+            (x / self.cell_size).floor() as i64,
+            (y / self.cell_size).floor() as i64,
+        )
+    }
+}
+
+pub(crate) fn contains(node: &GraphNode, x: f64, y: f64) -> bool {
+    let half_w = node.width / 2.0;
+    let half_h = node.height / 2.0;
+    x >= node.x - half_w && x <= node.x + half_w && y >= node.y - half_h && y <= node.y + half_h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::types::GraphNode;
+    use super::{NodeGrid, contains};
+
+    fn node(id: usize, x: f64, y: f64, width: f64, height: f64) -> GraphNode {
+        GraphNode {
+            id: format!("n{id}"),
+            label: format!("n{id}"),
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn linear_hit(nodes: &[GraphNode], x: f64, y: f64, radius: f64) -> Option<usize> {
+        if let Some(index) = nodes.iter().position(|node| contains(node, x, y)) {
+            return Some(index);
+        }
+        nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(index, node)| {
+                let dist = ((x - node.x).powi(2) + (y - node.y).powi(2)).sqrt();
+                (dist < radius).then_some((dist, index))
+            })
+            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(_, index)| index)
+    }
+
+    #[test]
+    fn grid_matches_linear_scan() {
+        let mut nodes = Vec::new();
+        for row in 0..12 {
+            for col in 0..15 {
+                let id = row * 15 + col;
+                let width = 0.6 + (id % 4) as f64 * 0.35;
+                let height = 0.4 + (id % 3) as f64 * 0.2;
+                nodes.push(node(id, col as f64 * 1.7, row as f64 * 1.1, width, height));
+            }
+        }
+        // Overlapping node to exercise the layout-order tie break.
+        nodes.push(node(999, 3.4, 2.2, 3.0, 2.0));
+        let grid = NodeGrid::build(&nodes);
+
+        let mut checked = 0;
+        for xi in -20..300 {
+            for yi in -20..150 {
+                let x = xi as f64 * 0.0931;
+                let y = yi as f64 * 0.0877;
+                for radius in [0.5, 3.0] {
+                    let expected = linear_hit(&nodes, x, y, radius);
+                    let actual = grid
+                        .node_containing(&nodes, x, y)
+                        .or_else(|| grid.nearest_center(&nodes, x, y, radius));
+                    assert_eq!(actual, expected, "mismatch at ({x}, {y}) r={radius}");
+                    checked += 1;
+                }
+            }
+        }
+        assert!(checked > 100_000);
+    }
+
+    #[test]
+    fn empty_grid_returns_nothing() {
+        let grid = NodeGrid::build(&[]);
+        assert_eq!(grid.node_containing(&[], 0.0, 0.0), None);
+        assert_eq!(grid.nearest_center(&[], 0.0, 0.0, 3.0), None);
+    }
+}
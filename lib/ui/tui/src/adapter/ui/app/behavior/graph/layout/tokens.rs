@@ -1,37 +1,55 @@
+/// Split one line of graphviz `-Tplain` output into fields.
+///
+/// Quoted strings are a single field (possibly empty) with `\"` and `\\`
+/// unescaped; other backslash sequences such as `\n` are kept verbatim.
+/// HTML-like labels (`<...>`, nesting allowed) are also a single field.
 pub(super) fn split_plain_tokens(line: &str) -> Vec<String> {
     let mut tokens = Vec::new();
     let mut current = String::new();
-    let mut in_quotes = false;
+    let mut started = false;
     let mut chars = line.chars().peekable();
     while let Some(ch) = chars.next() {
-        if in_quotes {
-            if ch == '\\' {
-                if let Some(next) = chars.next() {
-                    current.push(next);
-                }
-                continue;
-            }
-            if ch == '"' {
-                in_quotes = false;
-                continue;
+        if ch.is_whitespace() {
+            if started {
+                tokens.push(std::mem::take(&mut current));
+                started = false;
             }
-            current.push(ch);
             continue;
         }
-        if ch == '"' {
-            in_quotes = true;
+        if ch == '"' && !started {
+            started = true;
+            while let Some(ch) = chars.next() {
+                match ch {
+                    '"' => break,
+                    '\\' if matches!(chars.peek(), Some('"' | '\\')) => {
+                        current.extend(chars.next());
+                    }
+                    _ => current.push(ch),
+                }
+            }
             continue;
         }
-        if ch.is_whitespace() {
-            if !current.is_empty() {
-                tokens.push(current.clone());
-                current.clear();
+        if ch == '<' && !started {
+            started = true;
+            current.push(ch);
+            let mut depth = 1;
+            for ch in chars.by_ref() {
+                current.push(ch);
+                match ch {
+                    '<' => depth += 1,
+                    '>' => depth -= 1,
+                    _ => {}
+                }
+                if depth == 0 {
+                    break;
+                }
             }
             continue;
         }
+        started = true;
         current.push(ch);
     }
-    if !current.is_empty() {
+    if started {
         tokens.push(current);
     }
     tokens
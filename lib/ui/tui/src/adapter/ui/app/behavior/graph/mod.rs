@@ -4,8 +4,11 @@ mod state;
 mod types;
 
 pub use layout::GraphLayout;
+pub(crate) use render::GraphDiskCache;
 pub use state::GraphRenderState;
 pub use types::{
-    GraphBounds, GraphDependencyPath, GraphDirection, GraphEdge, GraphNode, GraphRenderRequest,
-    GraphRenderStatus, TerminalImageProtocol,
+    GraphAttributes, GraphAutoFit, GraphBounds, GraphDependencyPath, GraphDirection, GraphEdge,
+    GraphEdgeDependency, GraphEngine, GraphHitRadii, GraphIsolation, GraphNode, GraphRankDir,
+    GraphRenderRequest, GraphRenderStats, GraphRenderStatus, GraphvizProbe, KittyPlacement,
+    TerminalImageProtocol,
 };
@@ -0,0 +1,180 @@
+//! Graphviz output kept on disk between runs so an unchanged graph opens
+//! without shelling out again.
+//!
+//! Entries are keyed by the input hash together with the graphviz version,
+//! so upgrading graphviz simply misses the old entries. Once the entries
+//! outgrow the size cap, the least recently used ones are removed.
+
+use std::env;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::{StableHasher, graphviz_version};
+use crate::util::env_flag;
+
+/// Bytes the cache directory may hold before old entries are removed.
+const MAX_CACHE_BYTES: u64 = 256 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub(crate) struct GraphDiskCache {
+    dir: PathBuf,
+    /// Graphviz version the entries are keyed by; `None` asks the graphviz
+    /// probe on every access.
+    version: Option<String>,
+    max_bytes: u64,
+}
+
+impl GraphDiskCache {
+    /// Cache in `dir` keyed by a fixed graphviz `version`.
+    pub(crate) fn new(dir: PathBuf, version: String) -> Self {
+        Self {
+            dir,
+            version: Some(version),
+            max_bytes: MAX_CACHE_BYTES,
+        }
+    }
+
+    /// Cache under `~/.phenome/cache/graph`, unless `PHENOME_TUI_GRAPH_CACHE`
+    /// turns it off. Entries are keyed by the graphviz version probed at the
+    /// time, so a graphviz installed while phenome runs starts caching too.
+    pub(crate) fn from_env() -> Option<Self> {
+        if !env_flag("PHENOME_TUI_GRAPH_CACHE", true) {
+            return None;
+        }
+        Some(Self {
+            dir: cache_dir(),
+            version: None,
+            max_bytes: MAX_CACHE_BYTES,
+        })
+    }
+
+    /// Remove the least recently used entries once they exceed `max_bytes`.
+    pub(crate) fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Plain layout output of the input hashed to `hash`.
+    pub(crate) fn load_layout(&self, hash: u64) -> Option<String> {
+        let path = self.path("layout", hash, "plain")?;
+        let plain = fs::read_to_string(&path).ok()?;
+        touch(&path);
+        Some(plain)
+    }
+
+    pub(crate) fn store_layout(&self, hash: u64, plain: &str) {
+        if let Some(path) = self.path("layout", hash, "plain") {
+            self.store(&path, plain.as_bytes());
+        }
+    }
+
+    /// Rendered PNG of the input hashed to `hash`.
+    pub(crate) fn load_image(&self, hash: u64) -> Option<Vec<u8>> {
+        let path = self.path("image", hash, "png")?;
+        let png = fs::read(&path).ok()?;
+        touch(&path);
+        Some(png)
+    }
+
+    pub(crate) fn store_image(&self, hash: u64, png: &[u8]) {
+        if let Some(path) = self.path("image", hash, "png") {
+            self.store(&path, png);
+        }
+    }
+
+    /// `None` while no graphviz version is known.
+    fn path(&self, kind: &str, hash: u64, extension: &str) -> Option<PathBuf> {
+        let version = match &self.version {
+            Some(version) => version.clone(),
+            None => graphviz_version()?,
+        };
+        let mut hasher = StableHasher::default();
+        hash.hash(&mut hasher);
+        version.hash(&mut hasher);
+        Some(
+            self.dir
+                .join(format!("{kind}-{:016x}.{extension}", hasher.finish())),
+        )
+    }
+
+    /// Write through a temporary file so a concurrent reader never sees a
+    /// partial entry. A failed write only costs a later cache miss.
+    fn store(&self, path: &Path, bytes: &[u8]) {
+        let temp = path.with_extension("tmp");
+        let result = fs::create_dir_all(&self.dir)
+            .and_then(|()| fs::write(&temp, bytes))
+            .and_then(|()| fs::rename(&temp, path));
+        match result {
+            Ok(()) => self.evict(path),
+            Err(error) => {
+                tracing::debug!("graph cache write to {} failed: {error}", path.display());
+            }
+        }
+    }
+
+    /// Remove the least recently used entries, other than `kept`, until the
+    /// cache fits in `max_bytes`.
+    fn evict(&self, kept: &Path) {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return;
+        };
+        let mut files: Vec<(SystemTime, u64, PathBuf)> = entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok().filter(fs::Metadata::is_file)?;
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                Some((modified, metadata.len(), entry.path()))
+            })
+            .collect();
+        let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+        if total <= self.max_bytes {
+            return;
+        }
+        files.sort();
+        for (_, len, path) in files {
+            if total <= self.max_bytes {
+                break;
+            }
+            if path != kept && fs::remove_file(&path).is_ok() {
+                total -= len;
+            }
+        }
+    }
+}
+
+/// Mark an entry used, so eviction takes it last.
+fn touch(path: &Path) {
+    if let Ok(file) = fs::File::options().append(true).open(path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+}
+
+fn cache_dir() -> PathBuf {
+    match env::var("HOME") {
+        Ok(home) => Path::new(&home)
+            .join(".phenome")
+            .join("cache")
+            .join("graph"),
+        Err(_) => env::temp_dir().join("phenome-graph-cache"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GraphDiskCache;
+
+    #[test]
+    fn oldest_entries_are_evicted_past_the_size_cap() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache =
+            GraphDiskCache::new(dir.path().to_path_buf(), "test".to_string()).with_max_bytes(10);
+
+        cache.store_layout(1, "aaaaaa");
+        cache.store_layout(2, "bbbbbb");
+
+        assert_eq!(cache.load_layout(1), None);
+        assert_eq!(cache.load_layout(2).as_deref(), Some("bbbbbb"));
+    }
+}
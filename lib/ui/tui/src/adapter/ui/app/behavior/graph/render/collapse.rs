@@ -0,0 +1,236 @@
+use anyhow::Result;
+use graphviz_rust::dot_structures::{
+    Attribute, Edge, EdgeTy, Graph, Id, Node, NodeId, Stmt, Vertex,
+};
+use graphviz_rust::parse;
+use graphviz_rust::printer::{DotPrinter, PrinterContext};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use super::isolate::id_name;
+
+/// Id prefix of the node a collapsed domain is drawn as.
+pub(crate) const DOMAIN_NODE_PREFIX: &str = "domain:";
+
+/// `dot` with the steps of every `collapsed` domain folded into one node per
+/// domain. Edges into or out of a folded step attach to its domain's node;
+/// edges inside a domain disappear and parallel ones are merged.
+pub(crate) fn collapse_dot(
+    dot: &str,
+    domain_of: &HashMap<String, String>,
+    collapsed: &BTreeSet<String>,
+) -> Result<String> {
+    let graph = parse(dot).map_err(|e| anyhow::anyhow!("failed to parse DOT: {e}"))?;
+    let mut collapser = Collapser {
+        domain_of,
+        collapsed,
+        members: BTreeMap::new(),
+        edges: HashSet::new(),
+    };
+    let graph = match graph {
+        Graph::Graph { id, strict, stmts } => Graph::Graph {
+            id,
+            strict,
+            stmts: collapser.rewrite_graph(stmts),
+        },
+        Graph::DiGraph { id, strict, stmts } => Graph::DiGraph {
+            id,
+            strict,
+            stmts: collapser.rewrite_graph(stmts),
+        },
+    };
+    Ok(graph.print(&mut PrinterContext::default()))
+}
+
+struct Collapser<'a> {
+    domain_of: &'a HashMap<String, String>,
+    collapsed: &'a BTreeSet<String>,
+    /// Steps folded into each domain node.
+    members: BTreeMap<String, HashSet<String>>,
+    /// Rewritten edges already emitted, by endpoint names.
+    edges: HashSet<(String, String)>,
+}
+
+impl Collapser<'_> {
+    fn rewrite_graph(&mut self, stmts: Vec<Stmt>) -> Vec<Stmt> {
+        let mut stmts = self.rewrite(stmts);
+        stmts.extend(
+            self.members
+                .iter()
+                .map(|(domain, steps)| domain_node(domain, steps.len())),
+        );
+        stmts
+    }
+
+    fn rewrite(&mut self, stmts: Vec<Stmt>) -> Vec<Stmt> {
+        let mut rewritten = Vec::with_capacity(stmts.len());
+        for stmt in stmts {
+            match stmt {
+                Stmt::Node(node) => {
+                    if self.fold(&id_name(&node.id.0)).is_none() {
+                        rewritten.push(Stmt::Node(node));
+                    }
+                }
+                Stmt::Edge(edge) => {
+                    rewritten.extend(self.rewrite_edge(edge).into_iter().map(Stmt::Edge));
+                }
+                Stmt::Subgraph(mut subgraph) => {
+                    subgraph.stmts = self.rewrite(subgraph.stmts);
+                    rewritten.push(Stmt::Subgraph(subgraph));
+                }
+                other => rewritten.push(other),
+            }
+        }
+        rewritten
+    }
+
+    fn collapsed_domain(&self, name: &str) -> Option<&String> {
+        self.domain_of
+            .get(name)
+            .filter(|domain| self.collapsed.contains(*domain))
+    }
+
+    /// Record `name` as folded when its domain is collapsed, returning the
+    /// id of the node it folds into.
+    fn fold(&mut self, name: &str) -> Option<String> {
+        let domain = self.collapsed_domain(name)?.clone();
+        let domain_id = format!("{DOMAIN_NODE_PREFIX}{domain}");
+        self.members
+            .entry(domain)
+            .or_default()
+            .insert(name.to_string());
+        Some(domain_id)
+    }
+
+    fn rewrite_edge(&mut self, edge: Edge) -> Vec<Edge> {
+        let folds = |vertex: &Vertex| match vertex {
+            Vertex::N(node_id) => self.collapsed_domain(&id_name(&node_id.0)).is_some(),
+            Vertex::S(_) => false,
+        };
+        let touches_collapsed = match &edge.ty {
+            EdgeTy::Pair(tail, head) => folds(tail) || folds(head),
+            EdgeTy::Chain(vertices) => vertices.iter().any(folds),
+        };
+        if !touches_collapsed {
+            return vec![edge];
+        }
+
+        let Edge { ty, attributes } = edge;
+        let vertices = match ty {
+            EdgeTy::Pair(tail, head) => vec![tail, head],
+            EdgeTy::Chain(vertices) => vertices,
+        };
+        let vertices: Vec<(Option<String>, Vertex)> = vertices
+            .into_iter()
+            .map(|vertex| match vertex {
+                Vertex::N(NodeId(id, port)) => {
+                    let name = id_name(&id);
+                    match self.fold(&name) {
+                        Some(domain_id) => {
+                            let vertex = Vertex::N(NodeId(quoted(&domain_id), None));
+                            (Some(domain_id), vertex)
+                        }
+                        None => (Some(name), Vertex::N(NodeId(id, port))),
+                    }
+                }
+                subgraph => (None, subgraph),
+            })
+            .collect();
+
+        let mut edges = Vec::new();
+        for pair in vertices.windows(2) {
+            let [(tail_name, tail), (head_name, head)] = pair else {
+                continue;
+            };
+            if let (Some(tail_name), Some(head_name)) = (tail_name, head_name) {
+                if tail_name == head_name
+                    || !self.edges.insert((tail_name.clone(), head_name.clone()))
+                {
+                    continue;
+                }
+            }
+            edges.push(Edge {
+                ty: EdgeTy::Pair(tail.clone(), head.clone()),
+                attributes: attributes.clone(),
+            });
+        }
+        edges
+    }
+}
+
+fn domain_node(domain: &str, steps: usize) -> Stmt {
+    let label = match steps {
+        1 => format!("{domain} (1 step)"),
+        _ => format!("{domain} ({steps} steps)"),
+    };
+    Stmt::Node(Node::new(
+        NodeId(quoted(&format!("{DOMAIN_NODE_PREFIX}{domain}")), None),
+        vec![
+            Attribute(Id::Plain("label".to_string()), quoted(&label)),
+            Attribute(
+                Id::Plain("shape".to_string()),
+                Id::Plain("folder".to_string()),
+            ),
+        ],
+    ))
+}
+
+fn quoted(text: &str) -> Id {
+    Id::Escaped(format!(
+        "\"{}\"",
+        text.replace('\\', "\\\\").replace('"', "\\\"")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap};
+
+    use super::super::super::layout::parse_plain_layout;
+    use super::super::super::types::{GraphAttributes, GraphEngine, GraphRankDir};
+    use super::super::render_dot_plain;
+    use super::collapse_dot;
+
+    #[test]
+    fn collapsed_domain_becomes_one_node_with_merged_edges() {
+        let dot = "digraph G { a; b; c; d; a -> b; b -> c; a -> c; c -> d; }";
+        let domain_of: HashMap<String, String> = [("a", "infra"), ("b", "infra"), ("c", "app")]
+            .iter()
+            .map(|(step, domain)| (step.to_string(), domain.to_string()))
+            .collect();
+        let collapsed = BTreeSet::from(["infra".to_string()]);
+
+        let collapsed_dot = collapse_dot(dot, &domain_of, &collapsed).unwrap();
+        let plain = render_dot_plain(
+            &collapsed_dot,
+            GraphRankDir::default(),
+            GraphEngine::default(),
+            GraphAttributes::default(),
+        )
+        .unwrap();
+        let layout = parse_plain_layout(&plain).unwrap();
+
+        let mut ids: Vec<_> = layout.nodes.iter().map(|node| node.id.as_str()).collect();
+        ids.sort();
+        assert_eq!(ids, ["c", "d", "domain:infra"]);
+        assert_eq!(
+            layout.node("domain:infra").unwrap().label,
+            "infra (2 steps)"
+        );
+        assert_eq!(layout.edges.len(), 2);
+    }
+
+    #[test]
+    fn nothing_collapsed_keeps_the_graph() {
+        let dot = "digraph G { a -> b; }";
+        let domain_of = HashMap::from([("a".to_string(), "infra".to_string())]);
+        let collapsed_dot = collapse_dot(dot, &domain_of, &BTreeSet::new()).unwrap();
+        let plain = render_dot_plain(
+            &collapsed_dot,
+            GraphRankDir::default(),
+            GraphEngine::default(),
+            GraphAttributes::default(),
+        )
+        .unwrap();
+        assert_eq!(parse_plain_layout(&plain).unwrap().nodes.len(), 2);
+    }
+}
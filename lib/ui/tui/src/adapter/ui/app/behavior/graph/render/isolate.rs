@@ -0,0 +1,105 @@
+use anyhow::Result;
+use graphviz_rust::dot_structures::{Edge, EdgeTy, Graph, Id, Stmt, Vertex};
+use graphviz_rust::parse;
+use graphviz_rust::printer::{DotPrinter, PrinterContext};
+use std::collections::HashSet;
+
+/// `dot` with only the nodes named in `keep` and the edges between them.
+/// Graph, node and edge defaults and subgraphs are kept as they are.
+pub(crate) fn filter_dot(dot: &str, keep: &HashSet<String>) -> Result<String> {
+    let graph = parse(dot).map_err(|e| anyhow::anyhow!("failed to parse DOT: {e}"))?;
+    let graph = match graph {
+        Graph::Graph { id, strict, stmts } => Graph::Graph {
+            id,
+            strict,
+            stmts: filter_stmts(stmts, keep),
+        },
+        Graph::DiGraph { id, strict, stmts } => Graph::DiGraph {
+            id,
+            strict,
+            stmts: filter_stmts(stmts, keep),
+        },
+    };
+    Ok(graph.print(&mut PrinterContext::default()))
+}
+
+fn filter_stmts(stmts: Vec<Stmt>, keep: &HashSet<String>) -> Vec<Stmt> {
+    stmts
+        .into_iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Node(node) => keep
+                .contains(&id_name(&node.id.0))
+                .then_some(Stmt::Node(node)),
+            Stmt::Edge(edge) => edge_kept(&edge, keep).then_some(Stmt::Edge(edge)),
+            Stmt::Subgraph(mut subgraph) => {
+                subgraph.stmts = filter_stmts(subgraph.stmts, keep);
+                Some(Stmt::Subgraph(subgraph))
+            }
+            other => Some(other),
+        })
+        .collect()
+}
+
+/// An edge chain is kept only when every node on it is.
+fn edge_kept(edge: &Edge, keep: &HashSet<String>) -> bool {
+    let kept = |vertex: &Vertex| match vertex {
+        Vertex::N(node_id) => keep.contains(&id_name(&node_id.0)),
+        Vertex::S(_) => true,
+    };
+    match &edge.ty {
+        EdgeTy::Pair(tail, head) => kept(tail) && kept(head),
+        EdgeTy::Chain(vertices) => vertices.iter().all(kept),
+    }
+}
+
+/// Node name as graphviz reports it in plain output, without DOT quoting.
+pub(super) fn id_name(id: &Id) -> String {
+    match id {
+        Id::Escaped(quoted) => quoted
+            .strip_prefix('"')
+            .and_then(|rest| rest.strip_suffix('"'))
+            .unwrap_or(quoted)
+            .replace("\\\"", "\"")
+            .replace("\\\\", "\\"),
+        Id::Html(name) | Id::Plain(name) | Id::Anonymous(name) => name.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::super::super::layout::parse_plain_layout;
+    use super::super::super::types::{GraphAttributes, GraphEngine, GraphRankDir};
+    use super::super::render_dot_plain;
+    use super::filter_dot;
+
+    #[test]
+    fn keeps_only_listed_nodes_and_their_edges() {
+        let dot = r#"digraph G {
+            node [shape=box];
+            a [label="A"];
+            b;
+            "c d" [label="C"];
+            e;
+            a -> b;
+            b -> "c d";
+            "c d" -> e;
+        }"#;
+        let keep: HashSet<String> = ["b", "c d"].iter().map(|id| id.to_string()).collect();
+        let filtered = filter_dot(dot, &keep).unwrap();
+
+        let plain = render_dot_plain(
+            &filtered,
+            GraphRankDir::default(),
+            GraphEngine::default(),
+            GraphAttributes::default(),
+        )
+        .unwrap();
+        let layout = parse_plain_layout(&plain).unwrap();
+        let mut ids: Vec<_> = layout.nodes.iter().map(|node| node.id.as_str()).collect();
+        ids.sort();
+        assert_eq!(ids, ["b", "c d"]);
+        assert_eq!(layout.edges.len(), 1);
+    }
+}
@@ -1,48 +1,231 @@
 use anyhow::{Context, Result};
-use graphviz_rust::cmd::{CommandArg, Format, Layout};
+use graphviz_rust::cmd::{CommandArg, Format};
+use graphviz_rust::dot_structures::Graph;
 use graphviz_rust::printer::PrinterContext;
 use graphviz_rust::{exec, parse};
-use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
+use std::io;
+use std::process::Command;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use super::types::{GraphAttributes, GraphEngine, GraphRankDir, GraphvizProbe};
+
+mod cache;
+mod collapse;
+mod isolate;
+
+pub(crate) use cache::GraphDiskCache;
+pub(crate) use collapse::{DOMAIN_NODE_PREFIX, collapse_dot};
+pub(crate) use isolate::filter_dot;
+
+/// The graphviz `dot` binary is not installed or not on `PATH`.
+#[derive(Debug)]
+pub(crate) struct GraphvizMissing;
+
+impl std::fmt::Display for GraphvizMissing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("graphviz `dot` binary not found")
+    }
+}
+
+impl std::error::Error for GraphvizMissing {}
+
+/// How long a failed probe stands before `dot -V` is run again, so graphviz
+/// installed or repaired while phenome runs is picked up.
+const PROBE_RETRY: Duration = Duration::from_secs(5);
+
+static PROBE: Mutex<Option<(GraphvizProbe, Instant)>> = Mutex::new(None);
+
+/// Result of `dot -V`. A found graphviz is remembered for the process; a
+/// failure is asked again once [`PROBE_RETRY`] has passed.
+pub(super) fn probe_graphviz() -> GraphvizProbe {
+    let mut cached = PROBE.lock().unwrap_or_else(PoisonError::into_inner);
+    match &*cached {
+        Some((probe @ GraphvizProbe::Found(_), _)) => return probe.clone(),
+        Some((probe, at)) if at.elapsed() < PROBE_RETRY => return probe.clone(),
+        _ => {}
+    }
+    let probe = run_probe();
+    *cached = Some((probe.clone(), Instant::now()));
+    probe
+}
+
+/// Drop a found probe after a run could not find `dot`, so graphviz counts
+/// as missing until the next probe finds it again.
+pub(super) fn forget_graphviz() {
+    let mut cached = PROBE.lock().unwrap_or_else(PoisonError::into_inner);
+    *cached = Some((GraphvizProbe::Missing, Instant::now()));
+}
+
+/// Run `dot -V`. Graphviz prints the version on stderr.
+fn run_probe() -> GraphvizProbe {
+    let output = match Command::new("dot").arg("-V").output() {
+        Ok(output) => output,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return GraphvizProbe::Missing,
+        Err(err) => return GraphvizProbe::Failed(err.to_string()),
+    };
+    let text = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    match (output.status.success(), text.is_empty()) {
+        (true, false) => GraphvizProbe::Found(text),
+        (true, true) => GraphvizProbe::Failed("no version reported".to_string()),
+        (false, true) => GraphvizProbe::Failed(output.status.to_string()),
+        (false, false) => GraphvizProbe::Failed(text),
+    }
+}
+
+pub(super) fn graphviz_version() -> Option<String> {
+    match probe_graphviz() {
+        GraphvizProbe::Found(version) => Some(version),
+        GraphvizProbe::Missing | GraphvizProbe::Failed(_) => None,
+    }
+}
+
+pub(super) fn graphviz_available() -> bool {
+    graphviz_version().is_some()
+}
+
+/// FNV-1a. Unlike `DefaultHasher` its output is fixed across runs and
+/// builds, so hashes can name cache entries on disk.
+pub(super) struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
 
 pub(super) fn hash_dot(dot: &str) -> u64 {
-    let mut hasher = DefaultHasher::new();
+    let mut hasher = StableHasher::default();
     dot.hash(&mut hasher);
     hasher.finish()
 }
 
-pub(super) fn render_dot_with_args(dot: &str, args: Vec<CommandArg>) -> Result<Vec<u8>> {
+/// Hash of everything that determines the plain layout.
+pub(super) fn hash_layout(
+    dot: &str,
+    rank_dir: GraphRankDir,
+    engine: GraphEngine,
+    attributes: GraphAttributes,
+) -> u64 {
+    let mut hasher = StableHasher::default();
+    dot.hash(&mut hasher);
+    rank_dir.hash(&mut hasher);
+    engine.hash(&mut hasher);
+    attributes.hash(&mut hasher);
+    hasher.finish()
+}
+
+pub(super) fn rank_dir_arg(rank_dir: GraphRankDir) -> CommandArg {
+    CommandArg::Custom(format!("-Grankdir={}", rank_dir.as_arg()))
+}
+
+/// Dots per inch graph images are rendered at.
+pub(super) const IMAGE_DPI: f64 = 96.0;
+
+/// `width` x `height` inches scaled down, keeping the aspect, so the image
+/// at `IMAGE_DPI` has at most `max_pixels` pixels. `None` when it fits.
+pub(super) fn clamp_image_size(width: f64, height: f64, max_pixels: u64) -> Option<(f64, f64)> {
+    let pixels = width * height * IMAGE_DPI * IMAGE_DPI;
+    if pixels <= max_pixels as f64 {
+        return None;
+    }
+    let scale = (max_pixels as f64 / pixels).sqrt();
+    Some((width * scale, height * scale))
+}
+
+pub(super) fn attribute_args(attributes: GraphAttributes) -> Vec<CommandArg> {
+    vec![
+        CommandArg::Custom(format!("-Goverlap={}", attributes.overlap)),
+        CommandArg::Custom(format!("-Gsplines={}", attributes.splines)),
+        CommandArg::Custom(format!("-Gnodesep={}", attributes.nodesep)),
+        CommandArg::Custom(format!("-Granksep={}", attributes.ranksep)),
+    ]
+}
+
+/// Arguments of a plain layout, spaced like the image of the same graph.
+pub(super) fn plain_args(
+    rank_dir: GraphRankDir,
+    engine: GraphEngine,
+    attributes: GraphAttributes,
+) -> Vec<CommandArg> {
+    let mut args = vec![
+        CommandArg::Format(Format::Plain),
+        CommandArg::Layout(engine.layout()),
+        rank_dir_arg(rank_dir),
+    ];
+    args.extend(attribute_args(attributes));
+    args
+}
+
+/// Run `engine` on `dot` with `args`, which should not pick a layout.
+pub(super) fn render_dot_with_args(
+    dot: &str,
+    engine: GraphEngine,
+    mut args: Vec<CommandArg>,
+) -> Result<Vec<u8>> {
     let graph = parse(dot).map_err(|e| anyhow::anyhow!("failed to parse DOT: {e}"))?;
-    let bytes =
-        exec(graph, &mut PrinterContext::default(), args).context("failed to execute graphviz")?;
-    Ok(bytes)
+    args.push(CommandArg::Layout(engine.layout()));
+    run_graphviz(graph, args)
 }
 
-pub(super) fn render_dot_plain(dot: &str) -> Result<String> {
+pub(super) fn render_dot_plain(
+    dot: &str,
+    rank_dir: GraphRankDir,
+    engine: GraphEngine,
+    attributes: GraphAttributes,
+) -> Result<String> {
     let graph = parse(dot).map_err(|e| anyhow::anyhow!("failed to parse DOT: {e}"))?;
-    let bytes = exec(
-        graph,
-        &mut PrinterContext::default(),
-        vec![
-            CommandArg::Format(Format::Plain),
-            CommandArg::Layout(Layout::Dot),
-        ],
-    )
-    .context("failed to execute graphviz")?;
+    let bytes = run_graphviz(graph, plain_args(rank_dir, engine, attributes))?;
     let text = String::from_utf8(bytes).context("plain output is not utf-8")?;
     Ok(text)
 }
 
+/// Execute graphviz, telling a missing binary apart from other failures.
+fn run_graphviz(graph: Graph, args: Vec<CommandArg>) -> Result<Vec<u8>> {
+    exec(graph, &mut PrinterContext::default(), args).map_err(|err| {
+        if err.kind() == io::ErrorKind::NotFound {
+            anyhow::Error::new(GraphvizMissing)
+        } else {
+            anyhow::Error::new(err).context("failed to execute graphviz")
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
-    use super::render_dot_plain;
-    use crate::app::{GraphRenderState, TerminalImageProtocol, graph::GraphRenderStatus};
+    use super::{
+        GraphDiskCache, IMAGE_DPI, attribute_args, clamp_image_size, plain_args, render_dot_plain,
+    };
+    use crate::app::{
+        GraphRenderState, TerminalImageProtocol,
+        graph::{GraphAttributes, GraphEngine, GraphRankDir, GraphRenderStatus},
+    };
+    use graphviz_rust::cmd::{CommandArg, Format};
     use ratatui::layout::Rect;
+    use std::time::{Duration, Instant};
 
     #[test]
     fn test_graphviz_installed() {
         let dot = "digraph G { a -> b; }";
-        let plain = render_dot_plain(dot);
+        let plain = render_dot_plain(
+            dot,
+            GraphRankDir::default(),
+            GraphEngine::default(),
+            GraphAttributes::default(),
+        );
         assert!(
             plain.is_ok(),
             "Graphviz 'dot' command failed. Is graphviz installed? Error: {:?}",
@@ -50,17 +233,61 @@ mod tests {
         );
     }
 
+    /// Poll until background layout and image work is done.
+    fn settle(state: &mut GraphRenderState) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while state.layout_pending() || state.image_job.is_some() {
+            assert!(Instant::now() < deadline, "graphviz never finished");
+            state.poll_layout();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
     #[test]
     fn test_ensure_layout() {
         let mut state = GraphRenderState::new();
         let dot = "digraph G { a -> b; }";
-        let res = state.ensure_layout(dot);
-        assert!(res.is_ok(), "ensure_layout failed: {:?}", res.err());
+        state.ensure_layout(dot);
+        settle(&mut state);
+        assert!(
+            state.layout_error().is_none(),
+            "ensure_layout failed: {:?}",
+            state.layout_error()
+        );
         assert!(state.layout().is_some(), "Layout should be populated");
         let layout = state.layout().unwrap();
         assert_eq!(layout.nodes.len(), 2, "Should have 2 nodes");
     }
 
+    #[test]
+    fn relayout_keeps_previous_layout_until_ready() {
+        let mut state = GraphRenderState::new();
+        state.ensure_layout("digraph G { a -> b; }");
+        assert!(state.layout_pending());
+        assert!(state.layout().is_none());
+        settle(&mut state);
+
+        state.ensure_layout("digraph G { a -> b; b -> c; }");
+        assert!(state.layout_pending());
+        assert_eq!(state.layout().unwrap().nodes.len(), 2);
+        settle(&mut state);
+        assert_eq!(state.layout().unwrap().nodes.len(), 3);
+
+        // An unchanged graph is not laid out again.
+        state.ensure_layout("digraph G { a -> b; b -> c; }");
+        assert!(!state.layout_pending());
+    }
+
+    #[test]
+    fn failed_layout_is_not_retried_for_the_same_dot() {
+        let mut state = GraphRenderState::new();
+        state.ensure_layout("digraph G { a -> ");
+        settle(&mut state);
+        assert!(state.layout_error().is_some());
+        state.ensure_layout("digraph G { a -> ");
+        assert!(!state.layout_pending());
+    }
+
     #[test]
     fn test_ensure_image_generation() {
         let mut state = GraphRenderState::new();
@@ -69,8 +296,14 @@ mod tests {
         let dot = "digraph G { a -> b; }";
         state.queue_request(Rect::new(0, 0, 100, 100), dot.to_string());
 
-        let res = state.ensure_image();
-        assert!(res.is_ok(), "ensure_image failed: {:?}", res.err());
+        state.ensure_image();
+        assert_eq!(state.status(), GraphRenderStatus::Pending);
+        settle(&mut state);
+        assert!(
+            state.last_error().is_none(),
+            "ensure_image failed: {:?}",
+            state.last_error()
+        );
 
         assert!(state.image().is_some(), "Image bytes should be present");
         assert!(
@@ -78,5 +311,213 @@ mod tests {
             "Image should not be empty"
         );
         assert_eq!(state.status(), GraphRenderStatus::Rendered);
+        assert_eq!(state.image_generation(), 1);
+    }
+
+    #[test]
+    fn switching_view_renders_image_again_once_its_layout_lands() {
+        let mut state = GraphRenderState::new();
+        state.protocol = TerminalImageProtocol::Kitty;
+        let area = Rect::new(0, 0, 100, 100);
+        let dag = "digraph G { a -> b; b -> c; }";
+        state.ensure_layout(dag);
+        settle(&mut state);
+        state.queue_request(area, dag.to_string());
+        state.ensure_image();
+        settle(&mut state);
+        assert_eq!(state.status(), GraphRenderStatus::Rendered);
+
+        // The other view's image is first cut with the old layout's bounds.
+        let dual = "digraph G { a -> b; a -> c; c -> d; }";
+        state.ensure_layout(dual);
+        state.queue_request(area, dual.to_string());
+        state.ensure_image();
+        settle(&mut state);
+        assert_eq!(state.layout().unwrap().nodes.len(), 4);
+
+        state.queue_request(area, dual.to_string());
+        assert_eq!(state.status(), GraphRenderStatus::Pending);
+        state.ensure_image();
+        assert!(state.image_job.is_some(), "new layout should render again");
+        settle(&mut state);
+        assert_eq!(state.status(), GraphRenderStatus::Rendered);
+
+        // Nothing changed since, so the image is reused.
+        state.queue_request(area, dual.to_string());
+        assert_eq!(state.status(), GraphRenderStatus::Rendered);
+    }
+
+    #[test]
+    fn oversized_image_is_clamped_to_pixel_cap() {
+        // A 400x100 cell area asks for 40x20 inches, 7.4M pixels at 96 dpi.
+        let (width, height) = clamp_image_size(40.0, 20.0, 4_000_000).unwrap();
+        let pixels = width * height * IMAGE_DPI * IMAGE_DPI;
+        assert!(pixels <= 4_000_000.0 + 1.0, "{pixels}");
+        assert!(pixels > 3_999_000.0, "{pixels}");
+        assert!((width / height - 2.0).abs() < 1e-9);
+
+        assert_eq!(clamp_image_size(10.0, 5.0, 4_000_000), None);
+    }
+
+    #[test]
+    fn default_attributes_keep_previous_image_args() {
+        let args: Vec<String> = attribute_args(GraphAttributes::default())
+            .into_iter()
+            .filter_map(|arg| match arg {
+                CommandArg::Custom(value) => Some(value),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            args,
+            [
+                "-Goverlap=false",
+                "-Gsplines=true",
+                "-Gnodesep=0.6",
+                "-Granksep=1",
+            ]
+        );
+
+        let wider = GraphAttributes::default().wider();
+        assert!(wider.nodesep > 0.6 && wider.ranksep > 1.0);
+        let mut tight = GraphAttributes::default();
+        for _ in 0..50 {
+            tight = tight.tighter();
+        }
+        assert!(tight.nodesep > 0.0);
+    }
+
+    #[test]
+    fn changing_spacing_renders_image_again() {
+        let mut state = GraphRenderState::new();
+        state.protocol = TerminalImageProtocol::Kitty;
+        let area = Rect::new(0, 0, 100, 100);
+        let dot = "digraph G { a -> b; }";
+        state.queue_request(area, dot.to_string());
+        state.ensure_image();
+        settle(&mut state);
+        state.queue_request(area, dot.to_string());
+        assert_eq!(state.status(), GraphRenderStatus::Rendered);
+
+        state.widen_spacing();
+        state.queue_request(area, dot.to_string());
+        assert_eq!(state.status(), GraphRenderStatus::Pending);
+    }
+
+    #[test]
+    fn export_writes_queued_graph_without_image_support() {
+        let mut state = GraphRenderState::new();
+        state.protocol = TerminalImageProtocol::None;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("graph.svg");
+        assert!(state.export_to_file(path.clone(), Format::Svg).is_err());
+
+        let dot = "digraph G { a -> b; }";
+        state.queue_request(Rect::new(0, 0, 100, 100), dot.to_string());
+        state.export_to_file(path.clone(), Format::Svg).unwrap();
+        assert!(state.export_to_file(path.clone(), Format::Svg).is_err());
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let written = loop {
+            if let Some(result) = state.poll_export() {
+                break result.unwrap();
+            }
+            assert!(Instant::now() < deadline, "graphviz never finished");
+            std::thread::sleep(Duration::from_millis(5));
+        };
+        assert_eq!(written, path);
+        let svg = std::fs::read_to_string(&path).unwrap();
+        assert!(svg.contains("<svg"));
+    }
+
+    #[test]
+    fn second_layout_of_same_dot_hits_disk_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = GraphDiskCache::new(dir.path().to_path_buf(), "test".to_string());
+        let dot = "digraph G { a -> b; }";
+
+        let mut first = GraphRenderState::with_disk_cache(Some(cache.clone()));
+        first.ensure_layout(dot);
+        assert!(first.layout_pending());
+        settle(&mut first);
+
+        let mut second = GraphRenderState::with_disk_cache(Some(cache));
+        second.ensure_layout(dot);
+        assert!(
+            !second.layout_pending(),
+            "cached layout should skip graphviz"
+        );
+        assert_eq!(second.layout().unwrap().nodes.len(), 2);
+
+        // Another graphviz version must not reuse the entry.
+        let mut upgraded = GraphRenderState::with_disk_cache(Some(GraphDiskCache::new(
+            dir.path().to_path_buf(),
+            "other".to_string(),
+        )));
+        upgraded.ensure_layout(dot);
+        assert!(upgraded.layout_pending());
+    }
+
+    #[test]
+    fn test_rank_dir_changes_layout() {
+        let has_rank_dir = |rank_dir: GraphRankDir, expected: &str| {
+            plain_args(rank_dir, GraphEngine::Dot, GraphAttributes::default())
+                .iter()
+                .any(|arg| matches!(arg, CommandArg::Custom(value) if value == expected))
+        };
+        assert!(has_rank_dir(GraphRankDir::TopBottom, "-Grankdir=TB"));
+        assert!(has_rank_dir(GraphRankDir::LeftRight, "-Grankdir=LR"));
+
+        let dot = "digraph G { a -> b; }";
+        let mut state = GraphRenderState::new();
+        state.ensure_layout(dot);
+        settle(&mut state);
+        let layout = state.layout().unwrap();
+        let (a_tb, b_tb) = (layout.node("a").unwrap().y, layout.node("b").unwrap().y);
+        assert!(a_tb > b_tb, "TB should stack a above b");
+
+        state.set_rank_dir(GraphRankDir::LeftRight);
+        state.ensure_layout(dot);
+        settle(&mut state);
+        let layout = state.layout().unwrap();
+        let (a_lr, b_lr) = (layout.node("a").unwrap(), layout.node("b").unwrap());
+        assert!(a_lr.x < b_lr.x, "LR should place a left of b");
+        assert_eq!(a_lr.y, b_lr.y);
+    }
+
+    #[test]
+    fn switching_engine_relayouts() {
+        let dot = "digraph G { a -> b; b -> c; c -> a; }";
+        let mut state = GraphRenderState::new();
+        state.ensure_layout(dot);
+        settle(&mut state);
+        let dot_hash = state.layout_hash;
+
+        assert_eq!(state.cycle_engine(), GraphEngine::Neato);
+        state.ensure_layout(dot);
+        assert!(state.layout_pending(), "engine change should relayout");
+        settle(&mut state);
+        assert!(state.layout_error().is_none(), "{:?}", state.layout_error());
+        assert_ne!(state.layout_hash, dot_hash);
+        assert_eq!(state.layout().unwrap().nodes.len(), 3);
+    }
+
+    #[test]
+    fn spacing_changes_relayout() {
+        let dot = "digraph G { a -> b; }";
+        let gap = |state: &GraphRenderState| {
+            let layout = state.layout().unwrap();
+            let (a, b) = (layout.node("a").unwrap(), layout.node("b").unwrap());
+            (a.x - b.x).hypot(a.y - b.y)
+        };
+        let mut state = GraphRenderState::new();
+        state.ensure_layout(dot);
+        settle(&mut state);
+        let narrow = gap(&state);
+
+        state.widen_spacing();
+        state.ensure_layout(dot);
+        assert!(state.layout_pending(), "spacing change should relayout");
+        settle(&mut state);
+        assert!(gap(&state) > narrow, "{} vs {narrow}", gap(&state));
     }
 }
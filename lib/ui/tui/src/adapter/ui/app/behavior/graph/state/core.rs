@@ -1,7 +1,14 @@
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
 use super::super::layout::GraphLayout;
+use super::super::render::GraphDiskCache;
 use super::super::types::{
-    GraphRenderRequest, GraphRenderStatus, TerminalImageProtocol,
+    GraphAttributes, GraphAutoFit, GraphEdgeDependency, GraphEngine, GraphHitRadii, GraphIsolation,
+    GraphRankDir, GraphRenderRequest, GraphRenderStats, GraphRenderStatus, KittyPlacement,
+    TerminalImageProtocol, isolation_depth_from_env, max_image_pixels_from_env,
 };
+use super::render::RenderJob;
 
 #[derive(Debug)]
 pub struct GraphRenderState {
@@ -13,18 +20,48 @@ pub struct GraphRenderState {
     pub(crate) error: Option<String>,
     pub(crate) failed_hash: Option<u64>,
     pub(crate) image_id: u32,
+    pub(crate) image_generation: u64,
     pub(crate) image_active: bool,
+    pub(crate) kitty_placement: Option<KittyPlacement>,
     pub(crate) layout: Option<GraphLayout>,
     pub(crate) layout_hash: Option<u64>,
     pub(crate) layout_error: Option<String>,
+    pub(crate) layout_failed_hash: Option<u64>,
+    pub(crate) layout_job: Option<RenderJob<GraphLayout>>,
+    /// Dependencies laid onto the edges of every installed layout.
+    pub(crate) edge_dependencies: Vec<GraphEdgeDependency>,
+    pub(crate) image_job: Option<RenderJob<Vec<u8>>>,
+    pub(crate) export_job: Option<RenderJob<PathBuf>>,
     pub(crate) selected_id: Option<String>,
     pub(crate) zoom: f64,
     pub(crate) pan_x: f64,
     pub(crate) pan_y: f64,
+    pub(crate) hit_radii: GraphHitRadii,
+    pub(crate) auto_fit: GraphAutoFit,
+    pub(crate) initial_fit_done: bool,
+    pub(crate) rank_dir: GraphRankDir,
+    pub(crate) engine: GraphEngine,
+    pub(crate) attributes: GraphAttributes,
+    pub(crate) disk_cache: Option<GraphDiskCache>,
+    pub(crate) search_query: String,
+    pub(crate) search_matches: Vec<usize>,
+    pub(crate) search_cursor: Option<usize>,
+    pub(crate) isolation: Option<GraphIsolation>,
+    pub(crate) isolation_depth: usize,
+    pub(crate) collapsed_domains: BTreeSet<String>,
+    pub(crate) collapsed_dot: Option<(u64, String)>,
+    pub(crate) stats: GraphRenderStats,
+    pub(crate) max_image_pixels: u64,
 }
 
 impl GraphRenderState {
+    /// State without a disk cache.
     pub fn new() -> Self {
+        Self::with_disk_cache(None)
+    }
+
+    /// State that keeps graphviz output in `disk_cache` between runs.
+    pub(crate) fn with_disk_cache(disk_cache: Option<GraphDiskCache>) -> Self {
         Self {
             protocol: TerminalImageProtocol::detect(),
             request: None,
@@ -34,14 +71,37 @@ impl GraphRenderState {
             error: None,
             failed_hash: None,
             image_id: 1,
+            image_generation: 0,
             image_active: false,
+            kitty_placement: None,
             layout: None,
             layout_hash: None,
             layout_error: None,
+            layout_failed_hash: None,
+            layout_job: None,
+            edge_dependencies: Vec::new(),
+            image_job: None,
+            export_job: None,
             selected_id: None,
             zoom: 1.0,
             pan_x: 0.0,
             pan_y: 0.0,
+            hit_radii: GraphHitRadii::default(),
+            auto_fit: GraphAutoFit::from_env(),
+            initial_fit_done: false,
+            rank_dir: GraphRankDir::default(),
+            engine: GraphEngine::default(),
+            attributes: GraphAttributes::from_env(),
+            disk_cache,
+            search_query: String::new(),
+            search_matches: Vec::new(),
+            search_cursor: None,
+            isolation: None,
+            isolation_depth: isolation_depth_from_env(),
+            collapsed_domains: BTreeSet::new(),
+            collapsed_dot: None,
+            stats: GraphRenderStats::default(),
+            max_image_pixels: max_image_pixels_from_env(),
         }
     }
 
@@ -86,6 +146,27 @@ impl GraphRenderState {
 
     pub fn set_image_active(&mut self, active: bool) {
         self.image_active = active;
+        if !active {
+            self.kitty_placement = None;
+        }
+    }
+
+    /// Bumped whenever a different image is taken in, so a terminal that
+    /// keeps images can tell whether to send it again.
+    pub fn image_generation(&self) -> u64 {
+        self.image_generation
+    }
+
+    pub fn kitty_placement(&self) -> Option<KittyPlacement> {
+        self.kitty_placement
+    }
+
+    pub fn set_kitty_placement(&mut self, placement: KittyPlacement) {
+        self.kitty_placement = Some(placement);
+    }
+
+    pub fn stats(&self) -> &GraphRenderStats {
+        &self.stats
     }
 
     pub fn clear_request(&mut self) {
@@ -0,0 +1,263 @@
+use ratatui::layout::Rect;
+
+use super::super::layout::GraphLayout;
+use super::super::types::GraphAutoFit;
+use super::core::GraphRenderState;
+use super::view::{MAX_ZOOM, MIN_ZOOM, aspect_ratio};
+
+impl GraphRenderState {
+    pub fn auto_fit(&self) -> GraphAutoFit {
+        self.auto_fit
+    }
+
+    pub fn set_auto_fit(&mut self, auto_fit: GraphAutoFit) {
+        self.auto_fit = auto_fit;
+    }
+
+    /// Choose the initial zoom/pan the first time a layout is available: the
+    /// whole graph fitted to `area`, or, with auto-fit, zoomed in on the
+    /// roots when the overview would be unreadable.
+    ///
+    /// Runs once per state; later layouts keep whatever view the user has.
+    /// Waits while a layout is computing, so a superseded one isn't fitted.
+    pub fn apply_initial_fit(&mut self, area: Rect) {
+        if self.initial_fit_done || self.layout_pending() {
+            return;
+        }
+        let Some(layout) = self.layout.as_ref() else {
+            return;
+        };
+        self.initial_fit_done = true;
+        self.zoom = fit_zoom(layout, area);
+        self.pan_x = 0.0;
+        self.pan_y = 0.0;
+        if !self.auto_fit.enabled || area.height == 0 {
+            return;
+        }
+        let Some(zoom) = readable_zoom(layout, area, self.auto_fit.min_node_rows) else {
+            return;
+        };
+        let focus = roots_center(layout).or_else(|| {
+            let node = layout.node(self.selected_id.as_deref()?)?;
+            Some((node.x, node.y))
+        });
+
+        self.zoom = zoom;
+        if let Some((x, y)) = focus {
+            self.pan_x = x - layout.width.max(1.0) / 2.0;
+            self.pan_y = y - layout.height.max(1.0) / 2.0;
+        }
+        self.keep_selected_in_view(area);
+    }
+
+    /// Zoom and pan so the whole graph just fits `area`. Zooms out past
+    /// `MIN_ZOOM` if a huge graph needs it; an empty layout or area resets to
+    /// zoom 1.0.
+    pub fn fit_to_view(&mut self, area: Rect) {
+        self.pan_x = 0.0;
+        self.pan_y = 0.0;
+        self.zoom = match self.layout.as_ref() {
+            Some(layout) => fit_zoom(layout, area),
+            None => 1.0,
+        };
+    }
+
+    /// Zoom and pan so the selected node's connected component fills the view.
+    pub fn frame_selected_component(&mut self, area: Rect) -> bool {
+        let Some(layout) = self.layout.as_ref() else {
+            return false;
+        };
+        let Some(bounds) = self
+            .selected_id
+            .as_deref()
+            .and_then(|id| layout.component_of(id))
+            .and_then(|component| layout.component_bounds(component))
+        else {
+            return false;
+        };
+        let width = layout.width.max(1.0);
+        let height = layout.height.max(1.0);
+        let span_w = (bounds.x_max - bounds.x_min).max(0.1) / FRAME_FILL;
+        let span_h = (bounds.y_max - bounds.y_min).max(0.1) / FRAME_FILL;
+
+        // view_h = height / zoom and view_w = view_h * aspect must both cover
+        // the component.
+        let zoom = (height / span_h).min(height * aspect_ratio(area) / span_w);
+        self.zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        self.pan_x = (bounds.x_min + bounds.x_max) / 2.0 - width / 2.0;
+        self.pan_y = (bounds.y_min + bounds.y_max) / 2.0 - height / 2.0;
+        true
+    }
+}
+
+/// Share of the view a framed component may occupy, leaving a margin.
+const FRAME_FILL: f64 = 0.9;
+
+/// At zoom 1.0 the view spans the layout's height, so only a graph wider
+/// than the view needs zooming out. The view is centered while unpanned.
+fn fit_zoom(layout: &GraphLayout, area: Rect) -> f64 {
+    if layout.width <= 0.0 || layout.height <= 0.0 || area.width == 0 || area.height == 0 {
+        return 1.0;
+    }
+    let fit = layout.height.max(1.0) * aspect_ratio(area) / layout.width.max(1.0);
+    fit.clamp(MIN_FIT_ZOOM, 1.0)
+}
+
+/// Floor for fitting huge graphs; the view itself stops shrinking at 0.1.
+const MIN_FIT_ZOOM: f64 = 0.1;
+
+/// Zoom at which the average node spans `min_node_rows` rows, or `None` when
+/// the full overview is already readable.
+fn readable_zoom(layout: &GraphLayout, area: Rect, min_node_rows: f64) -> Option<f64> {
+    if layout.nodes.is_empty() {
+        return None;
+    }
+    let mean_height =
+        layout.nodes.iter().map(|node| node.height).sum::<f64>() / layout.nodes.len() as f64;
+    if mean_height <= 0.0 {
+        return None;
+    }
+    let rows_at_overview = mean_height * area.height as f64 / layout.height.max(1.0);
+    if rows_at_overview >= min_node_rows {
+        return None;
+    }
+    Some((min_node_rows / rows_at_overview).clamp(1.0, MAX_ZOOM))
+}
+
+/// Center of the bounding box around nodes without incoming edges.
+fn roots_center(layout: &GraphLayout) -> Option<(f64, f64)> {
+    let mut roots = layout
+        .nodes
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| {
+            layout
+                .incoming
+                .get(*index)
+                .is_none_or(|edges| edges.is_empty())
+        })
+        .map(|(_, node)| node);
+    let first = roots.next()?;
+    let (mut x_min, mut x_max, mut y_min, mut y_max) = (first.x, first.x, first.y, first.y);
+    for node in roots {
+        x_min = x_min.min(node.x);
+        x_max = x_max.max(node.x);
+        y_min = y_min.min(node.y);
+        y_max = y_max.max(node.y);
+    }
+    Some(((x_min + x_max) / 2.0, (y_min + y_max) / 2.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::layout::Rect;
+
+    use super::super::super::layout::parse_plain_layout;
+    use super::super::super::types::GraphAutoFit;
+    use super::super::core::GraphRenderState;
+
+    /// Layered graph: one root per column on the top rank, each feeding a chain.
+    fn layered_state(columns: usize, ranks: usize) -> GraphRenderState {
+        let width = columns as f64 * 2.0;
+        let height = ranks as f64 * 1.5;
+        let mut plain = format!("graph 1 {width} {height}\n");
+        for col in 0..columns {
+            for rank in 0..ranks {
+                plain.push_str(&format!(
+                    "node n{col}_{rank} {} {} 1.2 0.5 n{col}_{rank} solid box black lightgrey\n",
+                    1.0 + col as f64 * 2.0,
+                    height - 0.75 - rank as f64 * 1.5,
+                ));
+            }
+        }
+        for col in 0..columns {
+            for rank in 1..ranks {
+                plain.push_str(&format!(
+                    "edge n{col}_{} n{col}_{rank} 0 solid black\n",
+                    rank - 1
+                ));
+            }
+        }
+        plain.push_str("stop\n");
+        let mut state = GraphRenderState::new();
+        state.set_auto_fit(GraphAutoFit::default());
+        let layout = parse_plain_layout(&plain).unwrap();
+        state.selected_id = layout.nodes.first().map(|node| node.id.clone());
+        state.layout = Some(layout);
+        state
+    }
+
+    #[test]
+    fn large_graph_opens_zoomed_on_roots() {
+        let area = Rect::new(0, 0, 160, 40);
+        let mut state = layered_state(40, 40);
+        let layout = state.layout.clone().unwrap();
+        let overview = state.view_bounds_for(&layout, area);
+
+        state.apply_initial_fit(area);
+        let fitted = state.view_bounds_for(&layout, area);
+
+        assert!(state.zoom > 1.0);
+        assert!(fitted.y_max - fitted.y_min < overview.y_max - overview.y_min);
+        // Roots sit on the top rank, which must be in view.
+        assert!(fitted.y_max >= layout.height - 0.75);
+        assert!(fitted.y_min <= layout.height - 0.75);
+
+        // Only the first layout is fitted.
+        state.zoom = 1.0;
+        state.apply_initial_fit(area);
+        assert_eq!(state.zoom, 1.0);
+    }
+
+    #[test]
+    fn wide_graph_is_fitted_whole() {
+        let area = Rect::new(0, 0, 160, 40);
+        let mut state = layered_state(20, 3);
+        state.set_auto_fit(GraphAutoFit {
+            enabled: false,
+            ..GraphAutoFit::default()
+        });
+        state.apply_initial_fit(area);
+        let layout = state.layout.clone().unwrap();
+        let bounds = state.view_bounds_for(&layout, area);
+        assert!(state.zoom < 1.0);
+        assert!(bounds.x_min <= 1e-9 && bounds.x_max >= layout.width - 1e-9);
+        assert!(bounds.y_min <= 0.0 && bounds.y_max >= layout.height);
+        // Exactly: the width is the binding side.
+        assert!((bounds.x_max - bounds.x_min - layout.width).abs() < 1e-9);
+
+        state.zoom_in(area);
+        state.pan(5.0, 1.0);
+        state.fit_to_view(area);
+        assert_eq!(state.view_bounds_for(&layout, area).x_min, bounds.x_min);
+    }
+
+    #[test]
+    fn zero_size_layout_or_area_fits_at_zoom_one() {
+        let mut state = GraphRenderState::new();
+        state.layout = Some(parse_plain_layout("graph 1 0 0\nstop\n").unwrap());
+        state.fit_to_view(Rect::new(0, 0, 160, 40));
+        assert_eq!(state.zoom, 1.0);
+
+        let mut state = layered_state(3, 3);
+        state.fit_to_view(Rect::new(0, 0, 0, 0));
+        assert_eq!(state.zoom, 1.0);
+    }
+
+    #[test]
+    fn small_or_disabled_graph_keeps_overview() {
+        let area = Rect::new(0, 0, 160, 40);
+        let mut small = layered_state(3, 3);
+        small.apply_initial_fit(area);
+        assert_eq!(small.zoom, 1.0);
+        assert_eq!((small.pan_x, small.pan_y), (0.0, 0.0));
+
+        let mut disabled = layered_state(40, 40);
+        disabled.set_auto_fit(GraphAutoFit {
+            enabled: false,
+            ..GraphAutoFit::default()
+        });
+        disabled.apply_initial_fit(area);
+        assert_eq!(disabled.zoom, 1.0);
+    }
+}
@@ -1,4 +1,5 @@
 mod core;
+mod fit;
 mod render;
 mod selection;
 mod view;
@@ -0,0 +1,41 @@
+use anyhow::{Context, Result};
+use graphviz_rust::cmd::{CommandArg, Format};
+use std::path::PathBuf;
+
+use super::super::super::render::{attribute_args, rank_dir_arg, render_dot_with_args};
+use super::super::core::GraphRenderState;
+use super::job::RenderJob;
+
+impl GraphRenderState {
+    /// Start rendering the whole graph last queued for display to `path` as
+    /// `format`, with the current rank direction, engine and attributes, on
+    /// a background thread. Runs graphviz directly, so it works without
+    /// terminal image support. One export runs at a time.
+    pub fn export_to_file(&mut self, path: PathBuf, format: Format) -> Result<()> {
+        if self.export_job.is_some() {
+            anyhow::bail!("an export is already running");
+        }
+        let request = self.request.as_ref().context("no graph to export")?;
+        let dot = request.dot.clone();
+        let engine = self.engine;
+        let mut args = vec![CommandArg::Format(format), rank_dir_arg(self.rank_dir)];
+        args.extend(attribute_args(self.attributes));
+        // Exports are never deduplicated, so the job needs no input hash.
+        self.export_job = Some(RenderJob::spawn(0, move || {
+            let bytes =
+                render_dot_with_args(&dot, engine, args).context("graphviz render failed")?;
+            std::fs::write(&path, bytes)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+            Ok(path)
+        }));
+        Ok(())
+    }
+
+    /// Where the running export was written once it is done, `None` while it
+    /// still runs or when none was started. Called on tick.
+    pub fn poll_export(&mut self) -> Option<Result<PathBuf>> {
+        let result = self.export_job.as_ref()?.try_finish()?;
+        self.export_job = None;
+        Some(result)
+    }
+}
@@ -1,17 +1,45 @@
-use anyhow::{Context, Result};
-use graphviz_rust::cmd::{CommandArg, Format, Layout};
+use anyhow::Context;
+use graphviz_rust::cmd::{CommandArg, Format};
 use ratatui::layout::Rect;
-use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 
 use super::super::core::GraphRenderState;
-use super::super::super::render::{hash_dot, render_dot_with_args};
-use super::super::super::types::{GraphRenderRequest, GraphRenderStatus};
+use super::super::super::render::{
+    GraphvizMissing, IMAGE_DPI, StableHasher, attribute_args, clamp_image_size, forget_graphviz,
+    rank_dir_arg, render_dot_with_args,
+};
+use super::super::super::types::{GraphAttributes, GraphRenderRequest, GraphRenderStatus};
+use super::job::RenderJob;
 
 impl GraphRenderState {
+    pub fn attributes(&self) -> GraphAttributes {
+        self.attributes
+    }
+
+    /// Change the graphviz attributes of layouts, images and exports. Like a
+    /// rank direction change, the next `ensure_layout` re-runs graphviz and
+    /// the view is refitted to the new layout.
+    pub fn set_attributes(&mut self, attributes: GraphAttributes) {
+        if self.attributes == attributes {
+            return;
+        }
+        self.attributes = attributes;
+        self.reset_view();
+        self.initial_fit_done = false;
+    }
+
+    pub fn widen_spacing(&mut self) {
+        self.set_attributes(self.attributes.wider());
+    }
+
+    pub fn tighten_spacing(&mut self) {
+        self.set_attributes(self.attributes.tighter());
+    }
+
     pub fn queue_request(&mut self, area: Rect, dot: String) {
-        let hash = hash_dot(&dot);
-        self.request = Some(GraphRenderRequest { area, dot });
+        let request = GraphRenderRequest { area, dot };
+        let hash = self.image_hash(&request);
+        self.request = Some(request);
         if !self.supports_images() {
             self.status = GraphRenderStatus::Idle;
             return;
@@ -27,37 +55,62 @@ impl GraphRenderState {
         self.status = GraphRenderStatus::Pending;
     }
 
-    pub fn ensure_image(&mut self) -> Result<()> {
-        let request = match self.request.as_ref() {
-            Some(request) => request,
-            None => {
-                self.status = GraphRenderStatus::Idle;
-                return Ok(());
-            }
+    /// Start rendering the queued request on a background thread unless its
+    /// image is shown already or known to fail. The shown image stays until
+    /// [`poll_layout`](Self::poll_layout) swaps in the result.
+    pub fn ensure_image(&mut self) {
+        let Some(request) = self.request.as_ref() else {
+            self.status = GraphRenderStatus::Idle;
+            return;
         };
-        if !self.supports_images() {
+        if !self.supports_images() || !self.graphviz_available() {
             self.status = GraphRenderStatus::Idle;
-            return Ok(());
+            return;
         }
 
-        let mut hasher = DefaultHasher::new();
-        request.dot.hash(&mut hasher);
-        format!("{:.2},{:.2},{:.2}", self.zoom, self.pan_x, self.pan_y).hash(&mut hasher);
-        request.area.width.hash(&mut hasher);
-        request.area.height.hash(&mut hasher);
-        let hash = hasher.finish();
+        let hash = self.image_hash(request);
 
         if self.cache_hash == Some(hash) {
             self.status = GraphRenderStatus::Rendered;
-            return Ok(());
+            return;
         }
         if self.failed_hash == Some(hash) {
             self.status = GraphRenderStatus::Failed;
-            return Ok(());
+            return;
+        }
+        self.status = GraphRenderStatus::Pending;
+        // Panning queues a render per step; let the running one finish and
+        // render only the latest view after it.
+        if self.image_job.is_some() {
+            return;
+        }
+        let cached = self
+            .disk_cache
+            .as_ref()
+            .and_then(|cache| cache.load_image(hash));
+        if let Some(png) = cached {
+            self.stats.cache_hits += 1;
+            self.stats.image_bytes = png.len();
+            self.cache_hash = Some(hash);
+            self.image = Some(png);
+            self.image_generation += 1;
+            self.status = GraphRenderStatus::Rendered;
+            self.error = None;
+            return;
         }
 
-        let target_w = (request.area.width as f64) / 10.0;
-        let target_h = (request.area.height as f64) / 5.0;
+        self.stats.cache_misses += 1;
+
+        let mut target_w = (request.area.width as f64) / 10.0;
+        let mut target_h = (request.area.height as f64) / 5.0;
+        if let Some((width, height)) = clamp_image_size(target_w, target_h, self.max_image_pixels) {
+            tracing::debug!(
+                "graph image clamped from {target_w:.2}x{target_h:.2}in to \
+                 {width:.2}x{height:.2}in to stay under {} pixels",
+                self.max_image_pixels
+            );
+            (target_w, target_h) = (width, height);
+        }
 
         let viewport_arg = if let Some(layout) = self.layout.as_ref() {
             let b = self.view_bounds_for(layout, request.area);
@@ -74,37 +127,75 @@ impl GraphRenderState {
             None
         };
 
-        let mut args = vec![
-            CommandArg::Format(Format::Png),
-            CommandArg::Layout(Layout::Dot),
-        ];
+        let mut args = vec![CommandArg::Format(Format::Png), rank_dir_arg(self.rank_dir)];
         args.push(CommandArg::Custom(format!(
             "-Gsize={target_w:.2},{target_h:.2}!"
         )));
-        args.push(CommandArg::Custom("-Goverlap=false".to_string()));
-        args.push(CommandArg::Custom("-Gsplines=true".to_string()));
-        args.push(CommandArg::Custom("-Gnodesep=0.6".to_string()));
-        args.push(CommandArg::Custom("-Granksep=1.0".to_string()));
+        args.push(CommandArg::Custom(format!("-Gdpi={IMAGE_DPI}")));
+        args.extend(attribute_args(self.attributes));
         if let Some(vp) = viewport_arg {
             args.push(CommandArg::Custom(format!("-Gviewport={vp}")));
         }
 
-        let png = render_dot_with_args(&request.dot, args).context("graphviz render failed")?;
-        self.cache_hash = Some(hash);
-        self.image = Some(png);
-        self.status = GraphRenderStatus::Rendered;
-        self.error = None;
-        Ok(())
+        let dot = request.dot.clone();
+        let engine = self.engine;
+        let cache = self.disk_cache.clone();
+        self.image_job = Some(RenderJob::spawn(hash, move || {
+            let png = render_dot_with_args(&dot, engine, args).context("graphviz render failed")?;
+            if let Some(cache) = cache {
+                cache.store_image(hash, &png);
+            }
+            Ok(png)
+        }));
+    }
+
+    /// Key of the image for `request` in the current view. The viewport is
+    /// cut from the installed layout's bounds, so the layout is part of the
+    /// key: switching views swaps the layout after the DOT, and the same
+    /// zoom and pan over the new layout is a different image.
+    fn image_hash(&self, request: &GraphRenderRequest) -> u64 {
+        let mut hasher = StableHasher::default();
+        request.dot.hash(&mut hasher);
+        self.layout_hash.hash(&mut hasher);
+        self.rank_dir.hash(&mut hasher);
+        self.engine.hash(&mut hasher);
+        self.attributes.hash(&mut hasher);
+        format!("{:.2},{:.2},{:.2}", self.zoom, self.pan_x, self.pan_y).hash(&mut hasher);
+        request.area.width.hash(&mut hasher);
+        request.area.height.hash(&mut hasher);
+        hasher.finish()
     }
 
-    pub fn mark_failed(&mut self, error: String) {
-        let mut hasher = DefaultHasher::new();
-        if let Some(req) = &self.request {
-            req.dot.hash(&mut hasher);
-            format!("{:.2},{:.2},{:.2}", self.zoom, self.pan_x, self.pan_y).hash(&mut hasher);
-            self.failed_hash = Some(hasher.finish());
+    /// Take in a finished image render. Returns whether one finished.
+    pub(super) fn poll_image(&mut self) -> bool {
+        let Some(job) = self.image_job.as_ref() else {
+            return false;
+        };
+        let Some(result) = job.try_finish() else {
+            return false;
+        };
+        let hash = job.hash;
+        self.stats.image_time = Some(job.elapsed());
+        self.image_job = None;
+        match result {
+            Ok(png) => {
+                self.stats.image_bytes = png.len();
+                self.cache_hash = Some(hash);
+                self.image = Some(png);
+                self.image_generation += 1;
+                self.status = GraphRenderStatus::Rendered;
+                self.error = None;
+            }
+            Err(error) => {
+                if error.is::<GraphvizMissing>() {
+                    forget_graphviz();
+                } else {
+                    self.failed_hash = Some(hash);
+                }
+                self.status = GraphRenderStatus::Failed;
+                self.error = Some(format!("{error:#}"));
+            }
         }
-        self.status = GraphRenderStatus::Failed;
-        self.error = Some(error);
+        true
     }
 }
@@ -0,0 +1,41 @@
+use anyhow::{Result, anyhow};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Graphviz run on a background thread so a heavy layout or render doesn't
+/// stall input handling. `hash` identifies the input it was started for.
+#[derive(Debug)]
+pub(crate) struct RenderJob<T> {
+    pub(crate) hash: u64,
+    started: Instant,
+    result: Receiver<Result<T>>,
+}
+
+impl<T: Send + 'static> RenderJob<T> {
+    pub(crate) fn spawn(hash: u64, work: impl FnOnce() -> Result<T> + Send + 'static) -> Self {
+        let (tx, result) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = tx.send(work());
+        });
+        Self {
+            hash,
+            started: Instant::now(),
+            result,
+        }
+    }
+
+    /// Time since the job was started.
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// The outcome once the thread is done, `None` while it still runs.
+    pub(crate) fn try_finish(&self) -> Option<Result<T>> {
+        match self.result.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(anyhow!("graphviz worker panicked"))),
+        }
+    }
+}
@@ -1,17 +1,118 @@
-use anyhow::{Context, Result};
+use anyhow::Context;
 
 use super::super::core::GraphRenderState;
 use super::super::super::layout::{GraphLayout, parse_plain_layout};
-use super::super::super::render::{hash_dot, render_dot_plain};
+use super::super::super::render::{
+    GraphvizMissing, forget_graphviz, graphviz_available, hash_layout, probe_graphviz,
+    render_dot_plain,
+};
+use super::super::super::types::{GraphEdgeDependency, GraphEngine, GraphRankDir, GraphvizProbe};
+use super::job::RenderJob;
 
 impl GraphRenderState {
-    pub fn ensure_layout(&mut self, dot: &str) -> Result<()> {
-        let hash = hash_dot(dot);
-        if self.layout_hash == Some(hash) {
-            return Ok(());
+    /// Start laying out `dot` on a background thread unless that layout is
+    /// shown already or known to fail. One layout runs at a time; a newer
+    /// `dot` waits for the running one, and the shown layout stays until
+    /// [`poll_layout`](Self::poll_layout) swaps in the result. A layout found
+    /// in the disk cache is installed right away.
+    pub fn ensure_layout(&mut self, dot: &str) {
+        let hash = hash_layout(dot, self.rank_dir, self.engine, self.attributes);
+        if self.layout_hash == Some(hash)
+            || self.layout_failed_hash == Some(hash)
+            || self.layout_job.is_some()
+            || !self.graphviz_available()
+        {
+            return;
         }
-        let plain = render_dot_plain(dot).context("graphviz plain render failed")?;
-        let layout = parse_plain_layout(&plain).context("graphviz plain parse failed")?;
+        if let Some(layout) = self.cached_layout(hash) {
+            self.stats.cache_hits += 1;
+            self.install_layout(layout, hash);
+            return;
+        }
+        self.stats.cache_misses += 1;
+        let dot = dot.to_string();
+        let (rank_dir, engine, attributes) = (self.rank_dir, self.engine, self.attributes);
+        let cache = self.disk_cache.clone();
+        self.layout_job = Some(RenderJob::spawn(hash, move || {
+            let plain = render_dot_plain(&dot, rank_dir, engine, attributes)
+                .context("graphviz plain render failed")?;
+            let layout = parse_plain_layout(&plain).context("graphviz plain parse failed")?;
+            if let Some(cache) = cache {
+                cache.store_layout(hash, &plain);
+            }
+            Ok(layout)
+        }));
+    }
+
+    fn cached_layout(&self, hash: u64) -> Option<GraphLayout> {
+        let plain = self.disk_cache.as_ref()?.load_layout(hash)?;
+        parse_plain_layout(&plain).ok()
+    }
+
+    /// Take in finished background work, layout and image. Returns whether
+    /// anything changed. Called on tick.
+    pub fn poll_layout(&mut self) -> bool {
+        let layout_done = self.poll_layout_job();
+        let image_done = self.poll_image();
+        layout_done || image_done
+    }
+
+    fn poll_layout_job(&mut self) -> bool {
+        let Some(job) = self.layout_job.as_ref() else {
+            return false;
+        };
+        let Some(result) = job.try_finish() else {
+            return false;
+        };
+        let hash = job.hash;
+        self.stats.layout_time = Some(job.elapsed());
+        self.layout_job = None;
+        match result {
+            Ok(layout) => self.install_layout(layout, hash),
+            Err(error) => {
+                // Without graphviz the layout is retried once it is found
+                // again, rather than remembered as failing.
+                if error.is::<GraphvizMissing>() {
+                    forget_graphviz();
+                } else {
+                    self.layout_failed_hash = Some(hash);
+                }
+                self.mark_layout_failed(format!("{error:#}"));
+            }
+        }
+        true
+    }
+
+    /// Whether graphviz can be run at all. A failed probe is retried every
+    /// few seconds, so installing graphviz needs no restart.
+    pub fn graphviz_available(&self) -> bool {
+        graphviz_available()
+    }
+
+    /// Why graphviz can or can't be run, for the view shown without it.
+    pub fn graphviz_probe(&self) -> GraphvizProbe {
+        probe_graphviz()
+    }
+
+    /// Whether a layout is being computed.
+    pub fn layout_pending(&self) -> bool {
+        self.layout_job.is_some()
+    }
+
+    /// Dependencies between the steps the graph is drawn from, shown on the
+    /// edges of the current layout and of every one installed later.
+    pub fn set_edge_dependencies(&mut self, dependencies: Vec<GraphEdgeDependency>) {
+        if self.edge_dependencies == dependencies {
+            return;
+        }
+        self.edge_dependencies = dependencies;
+        if let Some(layout) = self.layout.as_mut() {
+            layout.annotate_edges(&self.edge_dependencies);
+        }
+    }
+
+    fn install_layout(&mut self, mut layout: GraphLayout, hash: u64) {
+        layout.annotate_edges(&self.edge_dependencies);
         let previous = self.selected_id.clone();
         self.selected_id = previous
             .filter(|id| layout.node_index.contains_key(id))
@@ -19,7 +120,53 @@ impl GraphRenderState {
         self.layout = Some(layout);
         self.layout_hash = Some(hash);
         self.layout_error = None;
-        Ok(())
+        self.refresh_search_matches();
+    }
+
+    pub fn rank_dir(&self) -> GraphRankDir {
+        self.rank_dir
+    }
+
+    /// Change the rank direction. The next `ensure_layout` re-runs graphviz,
+    /// and the view is reset so it is fitted to the new layout.
+    pub fn set_rank_dir(&mut self, rank_dir: GraphRankDir) {
+        if self.rank_dir == rank_dir {
+            return;
+        }
+        self.rank_dir = rank_dir;
+        self.reset_view();
+        self.initial_fit_done = false;
+    }
+
+    pub fn cycle_rank_dir(&mut self) -> GraphRankDir {
+        self.set_rank_dir(self.rank_dir.next());
+        self.rank_dir
+    }
+
+    /// Switch between top-to-bottom and left-to-right flow.
+    pub fn flip_rank_dir(&mut self) -> GraphRankDir {
+        self.set_rank_dir(self.rank_dir.flipped());
+        self.rank_dir
+    }
+
+    pub fn engine(&self) -> GraphEngine {
+        self.engine
+    }
+
+    /// Switch the graphviz engine; like a rank direction change this
+    /// re-runs the layout and refits the view.
+    pub fn set_engine(&mut self, engine: GraphEngine) {
+        if self.engine == engine {
+            return;
+        }
+        self.engine = engine;
+        self.reset_view();
+        self.initial_fit_done = false;
+    }
+
+    pub fn cycle_engine(&mut self) -> GraphEngine {
+        self.set_engine(self.engine.next());
+        self.engine
     }
 
     pub fn layout(&self) -> Option<&GraphLayout> {
@@ -34,5 +181,6 @@ impl GraphRenderState {
         self.layout_error = Some(error);
         self.layout = None;
         self.layout_hash = None;
+        self.refresh_search_matches();
     }
 }
@@ -1,2 +1,6 @@
+mod export;
 mod image;
+mod job;
 mod layout;
+
+pub(crate) use job::RenderJob;
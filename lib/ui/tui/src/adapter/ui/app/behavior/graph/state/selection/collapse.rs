@@ -0,0 +1,92 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap};
+use std::hash::{Hash, Hasher};
+
+use super::super::core::GraphRenderState;
+use super::super::super::render::{DOMAIN_NODE_PREFIX, collapse_dot};
+
+impl GraphRenderState {
+    pub fn collapsed_domains(&self) -> &BTreeSet<String> {
+        &self.collapsed_domains
+    }
+
+    /// Domain of the selected node when it is a collapsed domain.
+    pub fn selected_collapsed_domain(&self) -> Option<&str> {
+        self.selected_id
+            .as_deref()?
+            .strip_prefix(DOMAIN_NODE_PREFIX)
+    }
+
+    /// Collapse `domain` into one node, selecting it, or expand it when
+    /// already collapsed. Returns whether it is collapsed now.
+    pub fn toggle_domain(&mut self, domain: &str) -> bool {
+        if self.collapsed_domains.remove(domain) {
+            return false;
+        }
+        self.collapsed_domains.insert(domain.to_string());
+        self.selected_id = Some(format!("{DOMAIN_NODE_PREFIX}{domain}"));
+        true
+    }
+
+    pub fn expand_all_domains(&mut self) {
+        self.collapsed_domains.clear();
+    }
+
+    /// `dot` with the collapsed domains folded, or unchanged when none is.
+    /// `domain_of` maps step ids to their domain and is only asked when the
+    /// DOT or the collapsed set changed since the last call.
+    pub fn collapse_dot(
+        &mut self,
+        dot: String,
+        domain_of: impl FnOnce() -> HashMap<String, String>,
+    ) -> String {
+        if self.collapsed_domains.is_empty() {
+            return dot;
+        }
+        let mut hasher = DefaultHasher::new();
+        dot.hash(&mut hasher);
+        self.collapsed_domains.hash(&mut hasher);
+        let hash = hasher.finish();
+        if let Some((cached_hash, collapsed)) = self.collapsed_dot.as_ref() {
+            if *cached_hash == hash {
+                return collapsed.clone();
+            }
+        }
+        match collapse_dot(&dot, &domain_of(), &self.collapsed_domains) {
+            Ok(collapsed) => {
+                self.collapsed_dot = Some((hash, collapsed.clone()));
+                collapsed
+            }
+            Err(error) => {
+                tracing::debug!("collapsing graph domains failed: {error:#}");
+                dot
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::super::super::core::GraphRenderState;
+
+    #[test]
+    fn toggling_domain_selects_its_node_and_expands_again() {
+        let mut state = GraphRenderState::new();
+        assert!(state.toggle_domain("storage"));
+        assert_eq!(state.selected_collapsed_domain(), Some("storage"));
+
+        let dot = "digraph G { a -> b; }".to_string();
+        let domains = HashMap::from([
+            ("a".to_string(), "storage".to_string()),
+            ("b".to_string(), "network".to_string()),
+        ]);
+        let collapsed = state.collapse_dot(dot.clone(), || domains.clone());
+        assert!(collapsed.contains("domain:storage"));
+
+        assert!(!state.toggle_domain("storage"));
+        assert!(state.collapsed_domains().is_empty());
+        assert_eq!(state.collapse_dot(dot.clone(), HashMap::new), dot);
+    }
+}
@@ -0,0 +1,110 @@
+use super::super::core::GraphRenderState;
+
+impl GraphRenderState {
+    /// Jump to the next disconnected component, wrapping around.
+    pub fn select_next_component(&mut self) -> bool {
+        self.select_component_offset(1)
+    }
+
+    /// Jump to the previous disconnected component, wrapping around.
+    pub fn select_prev_component(&mut self) -> bool {
+        self.select_component_offset(-1)
+    }
+
+    fn select_component_offset(&mut self, offset: isize) -> bool {
+        let target_id = {
+            let Some(layout) = self.layout.as_ref() else {
+                return false;
+            };
+            let count = layout.component_count();
+            if count < 2 {
+                return false;
+            }
+            let current = self
+                .selected_id
+                .as_deref()
+                .and_then(|id| layout.component_of(id));
+            let target = match current {
+                Some(component) => {
+                    (component as isize + offset).rem_euclid(count as isize) as usize
+                }
+                None => 0,
+            };
+            // Prefer a root so the jump lands at the top of the component.
+            let nodes = layout.component_nodes(target);
+            let Some(&index) = nodes
+                .iter()
+                .find(|&&index| {
+                    layout
+                        .incoming
+                        .get(index)
+                        .is_none_or(|edges| edges.is_empty())
+                })
+                .or_else(|| nodes.first())
+            else {
+                return false;
+            };
+            layout.nodes[index].id.clone()
+        };
+        self.select_node(&target_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::layout::Rect;
+
+    use super::super::super::super::layout::parse_plain_layout;
+    use super::super::super::core::GraphRenderState;
+
+    /// Two chains side by side with no edges between them.
+    fn two_cluster_state() -> GraphRenderState {
+        let plain = "graph 1 20 6\n\
+            node a1 2 5 1 0.5 a1 solid box black lightgrey\n\
+            node a2 2 3 1 0.5 a2 solid box black lightgrey\n\
+            node a3 3 1 1 0.5 a3 solid box black lightgrey\n\
+            node b1 16 5 1 0.5 b1 solid box black lightgrey\n\
+            node b2 17 3 1 0.5 b2 solid box black lightgrey\n\
+            edge a1 a2 0 solid black\n\
+            edge a2 a3 0 solid black\n\
+            edge b1 b2 0 solid black\n\
+            stop\n";
+        let mut state = GraphRenderState::new();
+        state.layout = Some(parse_plain_layout(plain).unwrap());
+        state
+    }
+
+    #[test]
+    fn components_are_detected_and_navigable() {
+        let mut state = two_cluster_state();
+        {
+            let layout = state.layout().unwrap();
+            assert_eq!(layout.component_count(), 2);
+            assert_eq!(layout.component_of("a1"), layout.component_of("a3"));
+            assert_eq!(layout.component_of("b1"), layout.component_of("b2"));
+            assert_ne!(layout.component_of("a2"), layout.component_of("b2"));
+        }
+
+        assert!(state.select_node("a3"));
+        assert!(state.select_next_component());
+        assert_eq!(state.selected_id(), Some("b1"));
+        assert!(state.select_next_component());
+        assert_eq!(state.selected_id(), Some("a1"));
+        assert!(state.select_prev_component());
+        assert_eq!(state.selected_id(), Some("b1"));
+
+        let area = Rect::new(0, 0, 80, 24);
+        assert!(state.frame_selected_component(area));
+        let layout = state.layout().unwrap().clone();
+        let view = state.view_bounds_for(&layout, area);
+        let component = layout
+            .component_bounds(layout.component_of("b1").unwrap())
+            .unwrap();
+        assert!(view.x_min <= component.x_min && view.x_max >= component.x_max);
+        assert!(view.y_min <= component.y_min && view.y_max >= component.y_max);
+        assert!(
+            view.x_min > 4.0,
+            "view should not include the other cluster"
+        );
+    }
+}
@@ -1,87 +1,191 @@
+use super::super::super::types::GraphHitRadii;
 use super::super::core::GraphRenderState;
 
 impl GraphRenderState {
-    pub fn node_id_at(&self, x: f64, y: f64) -> Option<String> {
-        let layout = self.layout.as_ref()?;
+    pub fn hit_radii(&self) -> GraphHitRadii {
+        self.hit_radii
+    }
 
-        let exact = layout
-            .nodes
-            .iter()
-            .find(|node| {
-                let half_w = node.width / 2.0;
-                let half_h = node.height / 2.0;
-                x >= node.x - half_w
-                    && x <= node.x + half_w
-                    && y >= node.y - half_h
-                    && y <= node.y + half_h
-            })
-            .map(|n| n.id.clone());
+    pub fn set_hit_radii(&mut self, radii: GraphHitRadii) {
+        self.hit_radii = radii;
+    }
 
-        if exact.is_some() {
-            return exact;
-        }
+    /// Hover radius in layout units at the current zoom.
+    pub fn effective_hover_radius(&self) -> f64 {
+        self.hit_radii.hover / self.zoom.max(0.1)
+    }
+
+    /// Click-selection radius in layout units at the current zoom.
+    pub fn effective_select_radius(&self) -> f64 {
+        self.hit_radii.select / self.zoom.max(0.1)
+    }
 
-        let radius = 3.0;
+    pub fn node_id_at(&self, x: f64, y: f64) -> Option<String> {
+        let layout = self.layout.as_ref()?;
         layout
-            .nodes
-            .iter()
-            .filter_map(|node| {
-                let dx = x - node.x;
-                let dy = y - node.y;
-                let dist = (dx * dx + dy * dy).sqrt();
-                if dist < radius {
-                    Some((dist, node.id.clone()))
-                } else {
-                    None
-                }
-            })
-            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal))
-            .map(|(_, id)| id)
+            .hit_test(x, y, self.effective_hover_radius())
+            .map(|node| node.id.clone())
+    }
+
+    /// Tail and head ids of the edge under the point, within the select
+    /// radius since edges are thin.
+    pub fn edge_at(&self, x: f64, y: f64) -> Option<(String, String)> {
+        let layout = self.layout.as_ref()?;
+        let edge = &layout.edges[layout.edge_at(x, y, self.effective_select_radius())?];
+        Some((
+            layout.nodes[edge.tail].id.clone(),
+            layout.nodes[edge.head].id.clone(),
+        ))
     }
 
     pub fn select_node_at(&mut self, x: f64, y: f64) -> bool {
         let Some(layout) = self.layout.as_ref() else {
             return false;
         };
+        let radius = self.effective_select_radius();
+        let Some(id) = layout.hit_test(x, y, radius).map(|node| node.id.clone()) else {
+            return false;
+        };
+        self.select_node(&id)
+    }
+}
 
-        let exact_match = layout
-            .nodes
-            .iter()
-            .find(|node| {
-                let half_w = node.width / 2.0;
-                let half_h = node.height / 2.0;
-                x >= node.x - half_w
-                    && x <= node.x + half_w
-                    && y >= node.y - half_h
-                    && y <= node.y + half_h
-            })
-            .map(|n| n.id.clone());
+#[cfg(test)]
+mod tests {
+    use super::super::super::super::layout::parse_plain_layout;
+    use super::super::super::super::types::GraphHitRadii;
+    use super::super::super::super::types::GraphNode;
+    use super::super::super::core::GraphRenderState;
 
-        if let Some(id) = exact_match {
-            return self.select_node(&id);
+    fn linear_hit(nodes: &[GraphNode], x: f64, y: f64, radius: f64) -> Option<String> {
+        let exact = nodes.iter().find(|node| {
+            let half_w = node.width / 2.0;
+            let half_h = node.height / 2.0;
+            x >= node.x - half_w
+                && x <= node.x + half_w
+                && y >= node.y - half_h
+                && y <= node.y + half_h
+        });
+        if let Some(node) = exact {
+            return Some(node.id.clone());
         }
-
-        let radius = 0.5;
-        let best_match = layout
-            .nodes
+        nodes
             .iter()
             .filter_map(|node| {
-                let dx = x - node.x;
-                let dy = y - node.y;
-                let dist = (dx * dx + dy * dy).sqrt();
-                if dist < radius {
-                    Some((dist, node.id.clone()))
-                } else {
-                    None
-                }
+                let dist = ((x - node.x).powi(2) + (y - node.y).powi(2)).sqrt();
+                (dist < radius).then(|| (dist, node.id.clone()))
             })
             .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal))
-            .map(|(_, id)| id);
+            .map(|(_, id)| id)
+    }
 
-        if let Some(id) = best_match {
-            self.select_node(&id)
-        } else {
-            false
+    fn state_with_grid() -> GraphRenderState {
+        let mut plain = String::from("graph 1 24 14\n");
+        for row in 0..8 {
+            for col in 0..10 {
+                let width = 0.4 + ((row + col) % 3) as f64 * 0.3;
+                plain.push_str(&format!(
+                    "node n{row}_{col} {} {} {width} 0.4 n{row}_{col} solid box black lightgrey\n",
+                    col as f64 * 2.3,
+                    row as f64 * 1.6,
+                ));
+            }
+        }
+        plain.push_str("stop\n");
+        let mut state = GraphRenderState::new();
+        state.layout = Some(parse_plain_layout(&plain).unwrap());
+        state
+    }
+
+    #[test]
+    fn indexed_selection_matches_linear_scan() {
+        let mut state = state_with_grid();
+        let nodes = state.layout.as_ref().unwrap().nodes.clone();
+        let mut fuzzy_hits = 0;
+
+        for xi in -10..250 {
+            for yi in -10..120 {
+                let x = xi as f64 * 0.1;
+                let y = yi as f64 * 0.13;
+
+                let expected_hover = linear_hit(&nodes, x, y, 3.0);
+                assert_eq!(
+                    state.node_id_at(x, y),
+                    expected_hover,
+                    "hover at ({x}, {y})"
+                );
+
+                let expected = linear_hit(&nodes, x, y, 0.5);
+                let exact = linear_hit(&nodes, x, y, 0.0);
+                if exact.is_none() && expected.is_some() {
+                    fuzzy_hits += 1;
+                }
+                state.selected_id = None;
+                let selected = state.select_node_at(x, y);
+                assert_eq!(selected, expected.is_some(), "select at ({x}, {y})");
+                assert_eq!(state.selected_id, expected, "select at ({x}, {y})");
+            }
         }
+
+        assert!(fuzzy_hits > 0, "grid should exercise the fuzzy fallback");
+    }
+
+    #[test]
+    fn edge_under_point_is_found_along_its_segments() {
+        let plain = "graph 1 10 10\n\
+            node a 1 9 1 0.5 a solid box black lightgrey\n\
+            node b 9 1 1 0.5 b solid box black lightgrey\n\
+            node c 9 9 1 0.5 c solid box black lightgrey\n\
+            edge a b 3 1 8.75 5 8.75 9 1.25 solid black\n\
+            edge a c 2 1.5 9 8.5 9 solid black\n\
+            stop\n";
+        let mut state = GraphRenderState::new();
+        state.layout = Some(parse_plain_layout(plain).unwrap());
+
+        let ab = Some(("a".to_string(), "b".to_string()));
+        // Near both edges; the closer one wins.
+        assert_eq!(state.edge_at(3.0, 8.6), ab);
+        assert_eq!(state.edge_at(7.1, 5.0), ab);
+        assert_eq!(
+            state.edge_at(5.0, 9.1),
+            Some(("a".to_string(), "c".to_string()))
+        );
+        assert_eq!(state.edge_at(5.0, 5.0), None);
+    }
+
+    #[test]
+    fn radii_scale_with_zoom() {
+        let mut state = GraphRenderState::new();
+        state.set_hit_radii(GraphHitRadii {
+            hover: 2.0,
+            select: 1.0,
+        });
+        assert_eq!(state.effective_select_radius(), 1.0);
+
+        state.zoom = 2.0;
+        assert_eq!(state.effective_hover_radius(), 1.0);
+        assert_eq!(state.effective_select_radius(), 0.5);
+
+        state.zoom = 0.5;
+        assert_eq!(state.effective_select_radius(), 2.0);
+    }
+
+    #[test]
+    fn point_just_outside_radius_does_not_select() {
+        let plain = "graph 1 10 10\nnode a 5 5 0.2 0.2 a solid box black lightgrey\nstop\n";
+        let mut state = GraphRenderState::new();
+        state.layout = Some(parse_plain_layout(plain).unwrap());
+        state.selected_id = None;
+        state.zoom = 2.0;
+
+        // Select radius 0.5 at zoom 2.0 is 0.25 layout units from the center.
+        assert!(!state.select_node_at(5.26, 5.0));
+        assert_eq!(state.selected_id, None);
+        assert!(state.select_node_at(5.24, 5.0));
+        assert_eq!(state.selected_id.as_deref(), Some("a"));
+
+        state.selected_id = None;
+        state.zoom = 1.0;
+        assert!(state.select_node_at(5.26, 5.0));
     }
 }
@@ -0,0 +1,129 @@
+use super::super::core::GraphRenderState;
+use super::super::super::render::{filter_dot, hash_dot};
+use super::super::super::types::GraphIsolation;
+
+impl GraphRenderState {
+    /// Narrow the graph to the selected node and whatever lies up to
+    /// `isolation_depth` edges up- or downstream of it, or restore the full
+    /// graph when already narrowed. Returns whether the graph is now
+    /// isolated.
+    pub fn toggle_isolation(&mut self) -> bool {
+        if self.isolation.take().is_none() {
+            let Some(isolation) = self.selected_isolation() else {
+                return false;
+            };
+            self.isolation = Some(isolation);
+        }
+        self.reset_view();
+        self.initial_fit_done = false;
+        self.isolation.is_some()
+    }
+
+    fn selected_isolation(&self) -> Option<GraphIsolation> {
+        let layout = self.layout.as_ref()?;
+        let root = self.selected_id.clone()?;
+        let path = layout.dependency_paths_within(&root, self.isolation_depth);
+        if path.nodes.is_empty() {
+            return None;
+        }
+        let nodes = path
+            .nodes
+            .iter()
+            .map(|&index| layout.nodes[index].id.clone())
+            .collect();
+        Some(GraphIsolation {
+            root,
+            nodes,
+            filtered: None,
+        })
+    }
+
+    pub fn isolation(&self) -> Option<&GraphIsolation> {
+        self.isolation.as_ref()
+    }
+
+    /// `dot` narrowed to the isolated neighborhood, or unchanged while the
+    /// full graph is shown. The filtered DOT is kept until `dot` changes.
+    pub fn isolate_dot(&mut self, dot: String) -> String {
+        let Some(isolation) = self.isolation.as_mut() else {
+            return dot;
+        };
+        let hash = hash_dot(&dot);
+        if let Some((cached_hash, filtered)) = isolation.filtered.as_ref() {
+            if *cached_hash == hash {
+                return filtered.clone();
+            }
+        }
+        match filter_dot(&dot, &isolation.nodes) {
+            Ok(filtered) => {
+                isolation.filtered = Some((hash, filtered.clone()));
+                filtered
+            }
+            Err(error) => {
+                tracing::debug!("graph isolation failed, showing the full graph: {error:#}");
+                dot
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::super::layout::parse_plain_layout;
+    use super::super::super::core::GraphRenderState;
+
+    /// a -> b -> c -> d, plus x -> c.
+    fn chain_state() -> GraphRenderState {
+        let plain = "graph 1 10 10\n\
+            node a 2 9 1 0.5 a solid box black lightgrey\n\
+            node b 2 7 1 0.5 b solid box black lightgrey\n\
+            node c 2 5 1 0.5 c solid box black lightgrey\n\
+            node d 2 3 1 0.5 d solid box black lightgrey\n\
+            node x 6 7 1 0.5 x solid box black lightgrey\n\
+            edge a b 0 solid black\n\
+            edge b c 0 solid black\n\
+            edge c d 0 solid black\n\
+            edge x c 0 solid black\n\
+            stop\n";
+        let mut state = GraphRenderState::new();
+        state.layout = Some(parse_plain_layout(plain).unwrap());
+        state
+    }
+
+    fn isolated_ids(state: &GraphRenderState) -> Vec<&str> {
+        let mut ids: Vec<_> = state
+            .isolation()
+            .unwrap()
+            .nodes
+            .iter()
+            .map(String::as_str)
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn isolation_keeps_neighbors_within_depth_and_toggles_back() {
+        let mut state = chain_state();
+        state.select_node("c");
+        state.isolation_depth = 1;
+        assert!(state.toggle_isolation());
+        assert_eq!(isolated_ids(&state), ["b", "c", "d", "x"]);
+
+        assert!(!state.toggle_isolation());
+        assert!(state.isolation().is_none());
+
+        state.isolation_depth = 2;
+        state.toggle_isolation();
+        assert_eq!(isolated_ids(&state), ["a", "b", "c", "d", "x"]);
+    }
+
+    #[test]
+    fn isolation_needs_a_selection() {
+        let mut state = chain_state();
+        state.selected_id = None;
+        assert!(!state.toggle_isolation());
+        let dot = "digraph G { a -> b; }".to_string();
+        assert_eq!(state.isolate_dot(dot.clone()), dot);
+    }
+}
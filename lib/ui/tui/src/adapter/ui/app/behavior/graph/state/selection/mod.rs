@@ -1,3 +1,7 @@
 mod navigation;
 mod hit;
 mod target;
+mod component;
+mod collapse;
+mod isolate;
+mod search;
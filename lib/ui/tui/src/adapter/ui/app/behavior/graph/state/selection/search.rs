@@ -0,0 +1,166 @@
+use super::super::core::GraphRenderState;
+
+impl GraphRenderState {
+    /// Highlight every node whose id or label contains `query`, ignoring
+    /// case. An empty query clears the highlight.
+    pub fn set_search_query(&mut self, query: &str) {
+        let query = query.to_lowercase();
+        if query == self.search_query {
+            return;
+        }
+        self.search_query = query;
+        self.search_cursor = None;
+        self.refresh_search_matches();
+    }
+
+    pub fn clear_search(&mut self) {
+        self.set_search_query("");
+    }
+
+    /// Indices into the layout's nodes matching the search, in layout order.
+    pub fn search_matches(&self) -> &[usize] {
+        &self.search_matches
+    }
+
+    pub fn is_search_match(&self, index: usize) -> bool {
+        self.search_matches.binary_search(&index).is_ok()
+    }
+
+    /// Recompute the matches, e.g. for a new layout of the same query.
+    pub(crate) fn refresh_search_matches(&mut self) {
+        self.search_matches.clear();
+        let Some(layout) = self.layout.as_ref() else {
+            return;
+        };
+        if self.search_query.is_empty() {
+            return;
+        }
+        let query = self.search_query.as_str();
+        self.search_matches.extend(
+            layout
+                .nodes
+                .iter()
+                .enumerate()
+                .filter(|(_, node)| {
+                    node.id.to_lowercase().contains(query)
+                        || node.label.to_lowercase().contains(query)
+                })
+                .map(|(index, _)| index),
+        );
+        if self
+            .search_cursor
+            .is_some_and(|cursor| cursor >= self.search_matches.len())
+        {
+            self.search_cursor = None;
+        }
+    }
+
+    /// Select the match whose id is exactly the query, or else the first.
+    pub fn select_best_match(&mut self) -> bool {
+        let Some(layout) = self.layout.as_ref() else {
+            return false;
+        };
+        let exact = self
+            .search_matches
+            .iter()
+            .position(|&index| layout.nodes[index].id.to_lowercase() == self.search_query);
+        self.select_match(exact.unwrap_or(0))
+    }
+
+    /// Select the next match, wrapping around, and pan to it.
+    pub fn select_next_match(&mut self) -> bool {
+        let next = self.search_cursor.map_or(0, |cursor| cursor + 1);
+        self.select_match(next)
+    }
+
+    /// Select the previous match, wrapping around, and pan to it.
+    pub fn select_prev_match(&mut self) -> bool {
+        let count = self.search_matches.len();
+        let prev = self
+            .search_cursor
+            .map_or(count.saturating_sub(1), |cursor| cursor + count - 1);
+        self.select_match(prev)
+    }
+
+    fn select_match(&mut self, cursor: usize) -> bool {
+        if self.search_matches.is_empty() {
+            return false;
+        }
+        let cursor = cursor % self.search_matches.len();
+        let Some(id) = self
+            .layout
+            .as_ref()
+            .map(|layout| layout.nodes[self.search_matches[cursor]].id.clone())
+        else {
+            return false;
+        };
+        self.search_cursor = Some(cursor);
+        self.select_node(&id);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::super::layout::parse_plain_layout;
+    use super::super::super::core::GraphRenderState;
+
+    fn labelled_state() -> GraphRenderState {
+        let plain = "graph 1 20 6\n\
+            node api_db 2 5 1 0.5 Migrations solid box black lightgrey\n\
+            node db 6 5 1 0.5 Postgres solid box black lightgrey\n\
+            node api 12 5 1 0.5 Gateway solid box black lightgrey\n\
+            node cache 18 5 1 0.5 Redis solid box black lightgrey\n\
+            stop\n";
+        let mut state = GraphRenderState::new();
+        state.layout = Some(parse_plain_layout(plain).unwrap());
+        state
+    }
+
+    #[test]
+    fn matches_id_and_label_ignoring_case() {
+        let mut state = labelled_state();
+        state.set_search_query("DB");
+        assert_eq!(state.search_matches(), &[0, 1]);
+
+        state.set_search_query("redis");
+        assert_eq!(state.search_matches(), &[3]);
+        assert!(state.is_search_match(3));
+        assert!(!state.is_search_match(0));
+    }
+
+    #[test]
+    fn matches_cycle_both_ways_and_select() {
+        let mut state = labelled_state();
+        state.set_search_query("a");
+        assert_eq!(state.search_matches(), &[0, 2, 3]);
+
+        assert!(state.select_next_match());
+        assert_eq!(state.selected_id(), Some("api_db"));
+        state.select_next_match();
+        state.select_next_match();
+        assert_eq!(state.selected_id(), Some("cache"));
+        state.select_next_match();
+        assert_eq!(state.selected_id(), Some("api_db"));
+        state.select_prev_match();
+        assert_eq!(state.selected_id(), Some("cache"));
+    }
+
+    #[test]
+    fn best_match_prefers_exact_id() {
+        let mut state = labelled_state();
+        state.set_search_query("API");
+        assert_eq!(state.search_matches(), &[0, 2]);
+        assert!(state.select_best_match());
+        assert_eq!(state.selected_id(), Some("api"));
+    }
+
+    #[test]
+    fn clearing_query_clears_matches() {
+        let mut state = labelled_state();
+        state.set_search_query("db");
+        state.clear_search();
+        assert!(state.search_matches().is_empty());
+        assert!(!state.select_next_match());
+    }
+}
@@ -4,13 +4,68 @@ use super::core::GraphRenderState;
 use super::super::layout::GraphLayout;
 use super::super::types::GraphBounds;
 
+pub(super) const MAX_ZOOM: f64 = 4.0;
+pub(super) const MIN_ZOOM: f64 = 0.4;
+const ZOOM_STEP: f64 = 1.2;
+/// Fractions of the visible view moved by one pan and one fine pan.
+const PAN_STEP: f64 = 0.1;
+const FINE_PAN_STEP: f64 = 0.02;
+
 impl GraphRenderState {
-    pub fn zoom_in(&mut self) {
-        self.zoom = (self.zoom * 1.2).min(4.0);
+    /// Zoom in around the selected node, or the view center without one.
+    pub fn zoom_in(&mut self, area: Rect) {
+        let anchor = self.selected_point();
+        self.zoom_around((self.zoom * ZOOM_STEP).min(MAX_ZOOM), anchor, area);
+    }
+
+    /// Zoom out down to `MIN_ZOOM`, or not at all once a fitted huge graph
+    /// is already below it.
+    pub fn zoom_out(&mut self, area: Rect) {
+        let anchor = self.selected_point();
+        self.zoom_around(self.zoom_out_level(), anchor, area);
+    }
+
+    /// Zoom in keeping layout point `(x, y)`, e.g. under the mouse, where it
+    /// is on screen.
+    pub fn zoom_in_at(&mut self, x: f64, y: f64, area: Rect) {
+        self.zoom_around((self.zoom * ZOOM_STEP).min(MAX_ZOOM), Some((x, y)), area);
+    }
+
+    pub fn zoom_out_at(&mut self, x: f64, y: f64, area: Rect) {
+        self.zoom_around(self.zoom_out_level(), Some((x, y)), area);
     }
 
-    pub fn zoom_out(&mut self) {
-        self.zoom = (self.zoom / 1.2).max(0.4);
+    fn zoom_out_level(&self) -> f64 {
+        (self.zoom / ZOOM_STEP).max(MIN_ZOOM.min(self.zoom))
+    }
+
+    fn selected_point(&self) -> Option<(f64, f64)> {
+        let node = self.layout.as_ref()?.node(self.selected_id.as_deref()?)?;
+        Some((node.x, node.y))
+    }
+
+    /// Change the zoom so `anchor` stays put on screen. An anchor outside the
+    /// current view would drag the view away from what is shown, so the
+    /// view center is kept instead.
+    fn zoom_around(&mut self, zoom: f64, anchor: Option<(f64, f64)>, area: Rect) {
+        let Some(layout) = self.layout.as_ref() else {
+            self.zoom = zoom;
+            return;
+        };
+        let bounds = self.view_bounds_for(layout, area);
+        // Start from the clamped center so overscroll does not carry over.
+        let mut center_x = (bounds.x_min + bounds.x_max) / 2.0;
+        let mut center_y = (bounds.y_min + bounds.y_max) / 2.0;
+        if let Some((x, y)) = anchor.filter(|&(x, y)| {
+            (bounds.x_min..=bounds.x_max).contains(&x) && (bounds.y_min..=bounds.y_max).contains(&y)
+        }) {
+            let scale = self.zoom / zoom;
+            center_x = x - (x - center_x) * scale;
+            center_y = y - (y - center_y) * scale;
+        }
+        self.pan_x = center_x - layout.width.max(1.0) / 2.0;
+        self.pan_y = center_y - layout.height.max(1.0) / 2.0;
+        self.zoom = zoom;
     }
 
     pub fn reset_view(&mut self) {
@@ -24,6 +79,47 @@ impl GraphRenderState {
         self.pan_y += dy;
     }
 
+    /// Pan, then nudge the view back if the selected node left it entirely.
+    pub fn pan_within(&mut self, dx: f64, dy: f64, area: Rect) {
+        self.pan(dx, dy);
+        self.keep_selected_in_view(area);
+    }
+
+    /// Center the view on layout point `(x, y)`, clamped at the graph edges
+    /// like any other pan.
+    pub fn center_on(&mut self, x: f64, y: f64) {
+        let Some(layout) = self.layout.as_ref() else {
+            return;
+        };
+        self.pan_x = x - layout.width.max(1.0) / 2.0;
+        self.pan_y = y - layout.height.max(1.0) / 2.0;
+    }
+
+    /// Shift the view so the selected node is fully visible again once no
+    /// part of it overlaps the view bounds. Partially visible nodes are left
+    /// alone so small pans near the edge still feel free.
+    pub fn keep_selected_in_view(&mut self, area: Rect) {
+        let Some(layout) = self.layout.as_ref() else {
+            return;
+        };
+        let Some(node) = self.selected_id.as_deref().and_then(|id| layout.node(id)) else {
+            return;
+        };
+        let bounds = self.view_bounds_for(layout, area);
+        let nudge_x = nudge_into_view(bounds.x_min, bounds.x_max, node.x, node.width / 2.0);
+        let nudge_y = nudge_into_view(bounds.y_min, bounds.y_max, node.y, node.height / 2.0);
+        if nudge_x == 0.0 && nudge_y == 0.0 {
+            return;
+        }
+
+        // Re-derive pan from the clamped center so overscroll does not
+        // accumulate past the graph edge.
+        let center_x = (bounds.x_min + bounds.x_max) / 2.0 + nudge_x;
+        let center_y = (bounds.y_min + bounds.y_max) / 2.0 + nudge_y;
+        self.pan_x = center_x - layout.width.max(1.0) / 2.0;
+        self.pan_y = center_y - layout.height.max(1.0) / 2.0;
+    }
+
     pub fn view_bounds(&self, area: Rect) -> Option<GraphBounds> {
         let layout = self.layout.as_ref()?;
         Some(self.view_bounds_for(layout, area))
@@ -33,12 +129,8 @@ impl GraphRenderState {
         let width = layout.width.max(1.0);
         let height = layout.height.max(1.0);
 
-        let screen_w = area.width as f64;
-        let screen_h = area.height.max(1) as f64;
-        let aspect_ratio = screen_w / (screen_h * 2.1);
-
         let view_h = height / self.zoom.max(0.1);
-        let view_w = view_h * aspect_ratio;
+        let view_w = view_h * aspect_ratio(area);
 
         let mut center_x = width / 2.0 + self.pan_x;
         let mut center_y = height / 2.0 + self.pan_y;
@@ -67,9 +159,160 @@ impl GraphRenderState {
     }
 
     pub fn pan_step(&self, layout: &GraphLayout, area: Rect) -> (f64, f64) {
+        self.pan_step_by(layout, area, PAN_STEP)
+    }
+
+    /// A fifth of `pan_step`, for lining up a specific region.
+    pub fn fine_pan_step(&self, layout: &GraphLayout, area: Rect) -> (f64, f64) {
+        self.pan_step_by(layout, area, FINE_PAN_STEP)
+    }
+
+    fn pan_step_by(&self, layout: &GraphLayout, area: Rect, fraction: f64) -> (f64, f64) {
         let bounds = self.view_bounds_for(layout, area);
-        let step_x = (bounds.x_max - bounds.x_min) * 0.1;
-        let step_y = (bounds.y_max - bounds.y_min) * 0.1;
+        let step_x = (bounds.x_max - bounds.x_min) * fraction;
+        let step_y = (bounds.y_max - bounds.y_min) * fraction;
         (step_x.max(0.1), step_y.max(0.1))
     }
 }
+
+/// Width-to-height ratio of the area in layout units; cells are ~2.1x taller
+/// than wide.
+pub(super) fn aspect_ratio(area: Rect) -> f64 {
+    let screen_w = area.width as f64;
+    let screen_h = area.height.max(1) as f64;
+    screen_w / (screen_h * 2.1)
+}
+
+/// Offset that brings `[center - half, center + half]` fully inside
+/// `[min, max]`, or zero while the two still overlap.
+fn nudge_into_view(min: f64, max: f64, center: f64, half: f64) -> f64 {
+    let lo = center - half;
+    let hi = center + half;
+    if hi >= min && lo <= max {
+        0.0
+    } else if hi - lo >= max - min {
+        center - (min + max) / 2.0
+    } else if hi < min {
+        lo - min
+    } else {
+        hi - max
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::layout::Rect;
+
+    use super::super::super::layout::parse_plain_layout;
+    use super::super::core::GraphRenderState;
+
+    fn wide_state() -> GraphRenderState {
+        let mut plain = String::from("graph 1 60 20\n");
+        for row in 0..5 {
+            for col in 0..20 {
+                plain.push_str(&format!(
+                    "node n{row}_{col} {} {} 1.2 0.5 n{row}_{col} solid box black lightgrey\n",
+                    1.0 + col as f64 * 3.0,
+                    1.0 + row as f64 * 4.5,
+                ));
+            }
+        }
+        plain.push_str("stop\n");
+        let mut state = GraphRenderState::new();
+        state.layout = Some(parse_plain_layout(&plain).unwrap());
+        state
+    }
+
+    fn overlaps(state: &GraphRenderState, id: &str, area: Rect) -> bool {
+        let layout = state.layout.as_ref().unwrap();
+        let node = layout.node(id).unwrap();
+        let bounds = state.view_bounds_for(layout, area);
+        node.x + node.width / 2.0 >= bounds.x_min
+            && node.x - node.width / 2.0 <= bounds.x_max
+            && node.y + node.height / 2.0 >= bounds.y_min
+            && node.y - node.height / 2.0 <= bounds.y_max
+    }
+
+    #[test]
+    fn panning_far_keeps_selected_node_in_view() {
+        let area = Rect::new(0, 0, 120, 40);
+        let mut state = wide_state();
+        state.zoom = 4.0;
+        assert!(state.select_node("n2_3"));
+        assert!(overlaps(&state, "n2_3", area));
+
+        for _ in 0..200 {
+            let (step_x, step_y) = state.pan_step(state.layout.as_ref().unwrap(), area);
+            state.pan_within(step_x, -step_y, area);
+            assert!(overlaps(&state, "n2_3", area));
+        }
+        for _ in 0..200 {
+            let (step_x, step_y) = state.pan_step(state.layout.as_ref().unwrap(), area);
+            state.pan_within(-step_x, step_y, area);
+            assert!(overlaps(&state, "n2_3", area));
+        }
+    }
+
+    #[test]
+    fn panning_without_selection_is_unconstrained() {
+        let area = Rect::new(0, 0, 120, 40);
+        let mut state = wide_state();
+        state.zoom = 4.0;
+        state.pan_within(100.0, 0.0, area);
+        assert_eq!(state.pan_x, 100.0);
+    }
+
+    #[test]
+    fn zoom_keeps_anchor_in_place_on_screen() {
+        let area = Rect::new(0, 0, 120, 40);
+        let mut state = wide_state();
+        state.zoom = 2.0;
+        state.center_on(20.0, 8.0);
+        let before = state.view_bounds(area).unwrap();
+        let (x, y) = (24.0, 10.0);
+        let ratio_x = (x - before.x_min) / (before.x_max - before.x_min);
+        let ratio_y = (y - before.y_min) / (before.y_max - before.y_min);
+
+        state.zoom_in_at(x, y, area);
+        let after = state.view_bounds(area).unwrap();
+        assert!(after.x_max - after.x_min < before.x_max - before.x_min);
+        assert!(((x - after.x_min) / (after.x_max - after.x_min) - ratio_x).abs() < 1e-9);
+        assert!(((y - after.y_min) / (after.y_max - after.y_min) - ratio_y).abs() < 1e-9);
+
+        assert!(state.select_node("n2_5"));
+        state.pan(2.0, 0.0);
+        let (node_x, node_y) = (16.0, 10.0);
+        let before = state.view_bounds(area).unwrap();
+        let ratio_x = (node_x - before.x_min) / (before.x_max - before.x_min);
+        state.zoom_in(area);
+        let after = state.view_bounds(area).unwrap();
+        assert!(((node_x - after.x_min) / (after.x_max - after.x_min) - ratio_x).abs() < 1e-9);
+        assert!(node_y > after.y_min && node_y < after.y_max);
+    }
+
+    #[test]
+    fn fine_pan_step_is_smaller() {
+        let area = Rect::new(0, 0, 120, 40);
+        let state = wide_state();
+        let layout = state.layout.as_ref().unwrap();
+        let (coarse_x, coarse_y) = state.pan_step(layout, area);
+        let (fine_x, fine_y) = state.fine_pan_step(layout, area);
+        assert!(fine_x < coarse_x && fine_y < coarse_y);
+    }
+
+    #[test]
+    fn center_on_moves_view_center_to_point() {
+        let area = Rect::new(0, 0, 120, 40);
+        let mut state = wide_state();
+        state.zoom = 4.0;
+        state.center_on(45.0, 12.0);
+        let bounds = state.view_bounds(area).unwrap();
+        assert!(((bounds.x_min + bounds.x_max) / 2.0 - 45.0).abs() < 1e-9);
+        assert!(((bounds.y_min + bounds.y_max) / 2.0 - 12.0).abs() < 1e-9);
+
+        state.center_on(0.0, 0.0);
+        let bounds = state.view_bounds(area).unwrap();
+        assert!(bounds.x_min.abs() < 1e-9);
+        assert!(bounds.y_min.abs() < 1e-9);
+    }
+}
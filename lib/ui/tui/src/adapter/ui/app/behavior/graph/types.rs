@@ -1,6 +1,11 @@
+use graphviz_rust::cmd::Layout;
 use ratatui::layout::Rect;
 use std::collections::HashSet;
 use std::env;
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+
+use crate::util::env_flag;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TerminalImageProtocol {
@@ -50,6 +55,26 @@ impl TerminalImageProtocol {
     }
 }
 
+/// Kitty image on screen: the image generation it shows, where it is
+/// placed and the id its data was sent under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KittyPlacement {
+    pub generation: u64,
+    pub area: Rect,
+    pub image_id: u32,
+}
+
+/// Outcome of running `dot -V`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GraphvizProbe {
+    /// Graphviz runs and reported this version.
+    Found(String),
+    /// No `dot` binary on `PATH`.
+    Missing,
+    /// `dot` was found but `dot -V` failed, with what it reported.
+    Failed(String),
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GraphRenderStatus {
     Idle,
@@ -66,6 +91,164 @@ pub enum GraphDirection {
     Down,
 }
 
+/// Graphviz `rankdir`: the direction edges flow between ranks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum GraphRankDir {
+    #[default]
+    TopBottom,
+    LeftRight,
+    BottomTop,
+    RightLeft,
+}
+
+impl GraphRankDir {
+    /// Value passed to graphviz as `-Grankdir`.
+    pub fn as_arg(self) -> &'static str {
+        match self {
+            Self::TopBottom => "TB",
+            Self::LeftRight => "LR",
+            Self::BottomTop => "BT",
+            Self::RightLeft => "RL",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::TopBottom => Self::LeftRight,
+            Self::LeftRight => Self::BottomTop,
+            Self::BottomTop => Self::RightLeft,
+            Self::RightLeft => Self::TopBottom,
+        }
+    }
+
+    /// Switch between vertical and horizontal flow, keeping the sense.
+    pub fn flipped(self) -> Self {
+        match self {
+            Self::TopBottom => Self::LeftRight,
+            Self::LeftRight => Self::TopBottom,
+            Self::BottomTop => Self::RightLeft,
+            Self::RightLeft => Self::BottomTop,
+        }
+    }
+}
+
+/// Graphviz spacing and routing attributes for layouts and rendered images,
+/// in inches.
+///
+/// `PHENOME_TUI_GRAPH_NODESEP` and `PHENOME_TUI_GRAPH_RANKSEP` override the
+/// default spacing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GraphAttributes {
+    pub nodesep: f64,
+    pub ranksep: f64,
+    pub overlap: bool,
+    pub splines: bool,
+}
+
+impl GraphAttributes {
+    const SPACING_STEP: f64 = 1.25;
+    const MIN_SPACING: f64 = 0.1;
+    const MAX_SPACING: f64 = 5.0;
+
+    pub fn from_env() -> Self {
+        let spacing = |name: &str| {
+            env::var(name)
+                .ok()
+                .and_then(|value| value.parse::<f64>().ok())
+                .filter(|value| value.is_finite() && *value > 0.0)
+        };
+        let defaults = Self::default();
+        Self {
+            nodesep: spacing("PHENOME_TUI_GRAPH_NODESEP").unwrap_or(defaults.nodesep),
+            ranksep: spacing("PHENOME_TUI_GRAPH_RANKSEP").unwrap_or(defaults.ranksep),
+            ..defaults
+        }
+    }
+
+    /// Spread nodes and ranks further apart.
+    pub fn wider(self) -> Self {
+        self.scaled(Self::SPACING_STEP)
+    }
+
+    /// Pack nodes and ranks closer together.
+    pub fn tighter(self) -> Self {
+        self.scaled(1.0 / Self::SPACING_STEP)
+    }
+
+    fn scaled(self, factor: f64) -> Self {
+        let clamp = |value: f64| (value * factor).clamp(Self::MIN_SPACING, Self::MAX_SPACING);
+        Self {
+            nodesep: clamp(self.nodesep),
+            ranksep: clamp(self.ranksep),
+            ..self
+        }
+    }
+}
+
+impl Default for GraphAttributes {
+    fn default() -> Self {
+        Self {
+            nodesep: 0.6,
+            ranksep: 1.0,
+            overlap: false,
+            splines: true,
+        }
+    }
+}
+
+impl Hash for GraphAttributes {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.nodesep.to_bits().hash(state);
+        self.ranksep.to_bits().hash(state);
+        self.overlap.hash(state);
+        self.splines.hash(state);
+    }
+}
+
+/// Graphviz layout engine. `dot` suits hierarchies, the force-directed
+/// ones read better on dense meshes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum GraphEngine {
+    #[default]
+    Dot,
+    Neato,
+    Fdp,
+    Sfdp,
+    Twopi,
+}
+
+impl GraphEngine {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Dot => "dot",
+            Self::Neato => "neato",
+            Self::Fdp => "fdp",
+            Self::Sfdp => "sfdp",
+            Self::Twopi => "twopi",
+        }
+    }
+
+    pub fn layout(self) -> Layout {
+        match self {
+            Self::Dot => Layout::Dot,
+            Self::Neato => Layout::Neato,
+            Self::Fdp => Layout::Fdp,
+            Self::Sfdp => Layout::Sfdp,
+            Self::Twopi => Layout::Twopi,
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Dot => Self::Neato,
+            Self::Neato => Self::Fdp,
+            Self::Fdp => Self::Sfdp,
+            Self::Sfdp => Self::Twopi,
+            Self::Twopi => Self::Dot,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct GraphRenderRequest {
     pub area: Rect,
@@ -87,6 +270,28 @@ pub struct GraphEdge {
     pub tail: usize,
     pub head: usize,
     pub points: Vec<(f64, f64)>,
+    /// Edge `label` from the DOT, if it had one.
+    pub label: Option<String>,
+    /// Dependency between assembly steps the edge draws, if it joins two.
+    pub dependency: Option<GraphEdgeDependency>,
+}
+
+/// One assembly step requiring another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphEdgeDependency {
+    pub dependent: String,
+    pub provider: String,
+    /// What the dependent requires that the provider provides.
+    pub capabilities: Vec<String>,
+}
+
+/// Neighborhood of `root` the graph is narrowed to, by node id.
+#[derive(Debug, Clone)]
+pub struct GraphIsolation {
+    pub root: String,
+    pub nodes: HashSet<String>,
+    /// Last filtered DOT with the hash of the full DOT it came from.
+    pub(crate) filtered: Option<(u64, String)>,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -95,6 +300,86 @@ pub struct GraphDependencyPath {
     pub edges: HashSet<usize>,
 }
 
+/// Fuzzy hit-test radii in layout units at zoom 1.0.
+///
+/// The effective radius shrinks as the view zooms in so the on-screen
+/// tolerance stays roughly constant.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GraphHitRadii {
+    pub hover: f64,
+    pub select: f64,
+}
+
+impl Default for GraphHitRadii {
+    fn default() -> Self {
+        Self {
+            hover: 3.0,
+            select: 0.5,
+        }
+    }
+}
+
+/// Initial zoom/pan chosen the first time a layout is shown.
+///
+/// Graphs whose nodes would be shorter than `min_node_rows` terminal rows at
+/// zoom 1.0 open zoomed in on their roots instead of the full overview.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GraphAutoFit {
+    pub enabled: bool,
+    pub min_node_rows: f64,
+}
+
+impl GraphAutoFit {
+    pub fn from_env() -> Self {
+        Self {
+            enabled: env_flag("PHENOME_TUI_GRAPH_AUTOFIT", true),
+            ..Self::default()
+        }
+    }
+}
+
+/// Edges followed either way from the node an isolated view is built
+/// around, from `PHENOME_TUI_GRAPH_ISOLATE_DEPTH`.
+pub(crate) fn isolation_depth_from_env() -> usize {
+    env::var("PHENOME_TUI_GRAPH_ISOLATE_DEPTH")
+        .ok()
+        .and_then(|value| value.parse::<usize>().ok())
+        .filter(|&depth| depth > 0)
+        .unwrap_or(1)
+}
+
+/// Upper bound on the pixels of a rendered graph image, from
+/// `PHENOME_TUI_GRAPH_MAX_PIXELS`. Every pixel is encoded and sent to the
+/// terminal, so this bounds the cost of a frame.
+pub(crate) fn max_image_pixels_from_env() -> u64 {
+    env::var("PHENOME_TUI_GRAPH_MAX_PIXELS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|&pixels| pixels > 0)
+        .unwrap_or(4_000_000)
+}
+
+impl Default for GraphAutoFit {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_node_rows: 2.0,
+        }
+    }
+}
+
+/// Timings and sizes of the most recent graphviz work, for the metrics
+/// overlay. Cache hits count work served from the disk cache, misses count
+/// graphviz runs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GraphRenderStats {
+    pub layout_time: Option<Duration>,
+    pub image_time: Option<Duration>,
+    pub image_bytes: usize,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct GraphBounds {
     pub x_min: f64,
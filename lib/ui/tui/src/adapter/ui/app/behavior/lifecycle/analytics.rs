@@ -1,57 +1,295 @@
-use std::time::{Duration, Instant};
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::Notify;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::watch;
+use tokio_stream::StreamExt;
 
+use phenome_domain::{Anomaly, ClusterId, MetricType};
+
+use crate::analytics_client::{AnalyticsClient, RECONNECT_MAX_BACKOFF, is_transport_error};
 use crate::app::App;
+use crate::app::core::{AnalyticsConnection, AnalyticsPoll, AnalyticsUpdate};
 
+/// Poll interval unless `PHENOME_TUI_ANALYTICS_INTERVAL` says otherwise.
 const ANALYTICS_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Poll intervals `<` and `>` step through, in seconds.
+const ANALYTICS_INTERVAL_STEPS: [u64; 6] = [1, 2, 5, 10, 30, 60];
+/// Wait after the first failed reconnect; doubled per further attempt.
+const ANALYTICS_RECONNECT_BACKOFF: Duration = Duration::from_secs(1);
 const ANALYTICS_MAX_UPDATES_PER_TICK: usize = 32;
+/// Anomalies kept for the panels, matching the size of the initial fetch.
+const ANALYTICS_MAX_ANOMALIES: usize = 50;
+/// Wait between refreshes of the cluster list, which changes rarely and
+/// costs the service a scan of its samples.
+const ANALYTICS_CLUSTER_LIST_INTERVAL: Duration = Duration::from_secs(60);
+/// Metrics the predictions view forecasts.
+const ANALYTICS_FORECAST_TYPES: [MetricType; 2] = [MetricType::CpuUsage, MetricType::MemoryUsage];
+/// Forecast length as a fraction of the historical window it is fitted to.
+const ANALYTICS_FORECAST_HORIZON_FRACTION: f64 = 0.5;
 
 impl App {
     pub(super) fn start_analytics(&mut self) {
-        if let Ok(client) = tokio::task::block_in_place(|| {
-            tokio::runtime::Handle::current()
-                .block_on(crate::analytics_client::AnalyticsClient::connect_from_env())
-        }) {
-            let client = client.clone();
-            self.analytics_client = Some(client.clone());
-            let (tx, rx) = tokio::sync::mpsc::channel(10);
-            self.analytics_rx = Some(rx);
-
-            tokio::spawn(async move {
-                let mut tick = tokio::time::interval(ANALYTICS_POLL_INTERVAL);
-                loop {
-                    if tx.is_closed() {
+        let client = match AnalyticsClient::from_env() {
+            Ok(client) => client,
+            Err(err) => {
+                tracing::warn!("Analytics disabled: {:#}", err);
+                return;
+            }
+        };
+        self.analytics_client = Some(client.clone());
+        // The inbox lives with analytics-service, reached over the same
+        // authenticated connection as the panels.
+        self.context.ports.notifications = Arc::new(client.clone());
+        let (tx, rx) = tokio::sync::mpsc::channel(10);
+        self.analytics_tx = Some(tx.clone());
+        self.analytics_rx = Some(rx);
+        let (interval, mut interval_rx) = watch::channel(self.analytics_interval);
+        let (cluster, mut cluster_rx) = watch::channel(self.analytics_cluster.clone());
+        let refresh = Arc::new(Notify::new());
+        self.analytics_poll = Some(AnalyticsPoll {
+            interval,
+            refresh: refresh.clone(),
+            cluster,
+        });
+
+        tokio::spawn(follow_anomalies(
+            client.clone(),
+            tx.clone(),
+            cluster_rx.clone(),
+        ));
+        tokio::spawn(follow_clusters(client.clone(), tx.clone()));
+        tokio::spawn(async move {
+            let mut connection = AnalyticsConnection::Connecting;
+            let mut failure_backoff = ANALYTICS_RECONNECT_BACKOFF;
+            loop {
+                if tx.is_closed() {
+                    break;
+                }
+                if connection != AnalyticsConnection::Connected {
+                    if tx
+                        .send(AnalyticsUpdate::Connection(connection))
+                        .await
+                        .is_err()
+                    {
                         break;
                     }
-                    if let Ok(metrics) = client.fetch_metrics().await {
-                        if tx
-                            .send(crate::app::core::AnalyticsUpdate::Metrics(metrics))
-                            .await
-                            .is_err()
-                        {
-                            break;
-                        }
-                    }
-                    if let Ok(anomalies) = client.fetch_anomalies().await {
-                        if tx
-                            .send(crate::app::core::AnalyticsUpdate::Anomalies(anomalies))
-                            .await
-                            .is_err()
-                        {
-                            break;
-                        }
+                    client
+                        .reconnect_with_backoff(ANALYTICS_RECONNECT_BACKOFF)
+                        .await;
+                    connection = AnalyticsConnection::Connected;
+                    if tx
+                        .send(AnalyticsUpdate::Connection(connection))
+                        .await
+                        .is_err()
+                    {
+                        break;
                     }
-                    if let Ok(recs) = client.fetch_recommendations().await {
-                        if tx
-                            .send(crate::app::core::AnalyticsUpdate::Recommendations(recs))
-                            .await
-                            .is_err()
-                        {
-                            break;
+                }
+                let cluster_id = cluster_rx.borrow_and_update().clone();
+                match poll_analytics(&client, &tx, cluster_id).await {
+                    Ok(true) => failure_backoff = ANALYTICS_RECONNECT_BACKOFF,
+                    Ok(false) => break,
+                    Err(err) => {
+                        if is_transport_error(&err) {
+                            tracing::warn!("Analytics poll failed, reconnecting: {:#}", err);
+                            connection = AnalyticsConnection::Reconnecting;
+                        } else {
+                            tracing::warn!(
+                                "Analytics poll failed, retrying in {:?}: {:#}",
+                                failure_backoff,
+                                err
+                            );
                         }
+                        // Back off either way: a connect can succeed while
+                        // every call on it still fails.
+                        tokio::time::sleep(failure_backoff).await;
+                        failure_backoff = (failure_backoff * 2).min(RECONNECT_MAX_BACKOFF);
+                        continue;
                     }
-                    tick.tick().await;
                 }
-            });
+                let wait = *interval_rx.borrow_and_update();
+                tokio::select! {
+                    _ = tokio::time::sleep(wait) => {}
+                    _ = refresh.notified() => {}
+                    // Poll now and wait the new interval from there.
+                    _ = interval_rx.changed() => {}
+                    _ = cluster_rx.changed() => {}
+                }
+            }
+        });
+    }
+
+    /// Poll analytics now rather than at the next interval, and refetch the
+    /// historical window.
+    pub fn refresh_analytics_now(&self) {
+        if let Some(poll) = &self.analytics_poll {
+            poll.refresh.notify_one();
+        }
+        self.fetch_history();
+    }
+
+    /// Poll more often, down to the shortest of `ANALYTICS_INTERVAL_STEPS`.
+    pub fn shorten_analytics_interval(&mut self) {
+        let current = self.analytics_interval.as_secs();
+        let shorter = ANALYTICS_INTERVAL_STEPS
+            .iter()
+            .rev()
+            .find(|&&step| step < current)
+            .unwrap_or(&ANALYTICS_INTERVAL_STEPS[0]);
+        self.set_analytics_interval(Duration::from_secs(*shorter));
+    }
+
+    /// Poll less often, up to the longest of `ANALYTICS_INTERVAL_STEPS`.
+    pub fn lengthen_analytics_interval(&mut self) {
+        let current = self.analytics_interval.as_secs();
+        let longer = ANALYTICS_INTERVAL_STEPS
+            .iter()
+            .find(|&&step| step > current)
+            .unwrap_or(&ANALYTICS_INTERVAL_STEPS[ANALYTICS_INTERVAL_STEPS.len() - 1]);
+        self.set_analytics_interval(Duration::from_secs(*longer));
+    }
+
+    /// Change the poll interval of the running loop.
+    pub fn set_analytics_interval(&mut self, interval: Duration) {
+        self.analytics_interval = interval;
+        if let Some(poll) = &self.analytics_poll {
+            // The loop only ends with the app, so nobody to tell otherwise.
+            let _ = poll.interval.send(interval);
+        }
+    }
+
+    /// Scope the analytics views to the next known cluster, and back to all
+    /// clusters after the last one.
+    pub fn cycle_analytics_cluster(&mut self) {
+        let next = match &self.analytics_cluster {
+            None => self.analytics_clusters.first().cloned(),
+            // A cluster no longer listed moves on to all clusters.
+            Some(current) => self
+                .analytics_clusters
+                .iter()
+                .skip_while(|id| *id != current)
+                .nth(1)
+                .cloned(),
+        };
+        self.set_analytics_cluster(next);
+    }
+
+    /// Scope the analytics views to `cluster_id`, or to all clusters for
+    /// `None`, and refetch everything for it.
+    pub fn set_analytics_cluster(&mut self, cluster_id: Option<ClusterId>) {
+        if cluster_id == self.analytics_cluster {
+            return;
+        }
+        self.analytics_cluster = cluster_id;
+        // Data of the previous scope would pass for the new one until the
+        // refetch lands.
+        self.analytics_metrics = None;
+        self.analytics_anomalies = None;
+        self.analytics_recommendations = None;
+        self.analytics_history = None;
+        self.analytics_predictions = None;
+        self.analytics_cache_timestamp = None;
+        self.analytics_resource_scroll = 0;
+        self.analytics_anomaly_offset = 0;
+        self.analytics_recommendation_index = 0;
+        if let Some(poll) = &self.analytics_poll {
+            let _ = poll.cluster.send(self.analytics_cluster.clone());
+        }
+        self.fetch_history();
+    }
+
+    /// Fetch the cluster list in the background, keeping the current one if
+    /// that fails.
+    fn fetch_clusters(&self) {
+        let (Some(client), Some(tx)) = (self.analytics_client.clone(), self.analytics_tx.clone())
+        else {
+            return;
+        };
+        tokio::spawn(async move {
+            send_clusters(&client, &tx).await;
+        });
+    }
+
+    /// Show the next historical time window and fetch its samples.
+    pub fn cycle_history_window(&mut self) {
+        self.analytics_history_window = self.analytics_history_window.next();
+        self.analytics_history = None;
+        self.analytics_predictions = None;
+        self.fetch_history();
+    }
+
+    /// Fetch the samples of the current historical window, and the forecasts
+    /// fitted to it, in the background.
+    pub fn fetch_history(&self) {
+        let (Some(client), Some(tx)) = (self.analytics_client.clone(), self.analytics_tx.clone())
+        else {
+            return;
+        };
+        let window = self.analytics_history_window;
+        let cluster_id = self.analytics_cluster.clone();
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as i64)
+            .unwrap_or_default();
+        let range = window.time_range(now_ms);
+        let horizon = window
+            .duration()
+            .mul_f64(ANALYTICS_FORECAST_HORIZON_FRACTION);
+        tokio::spawn(async move {
+            let samples = match window.resolution() {
+                Some(resolution) => {
+                    client
+                        .fetch_metrics_downsampled(cluster_id.clone(), range, resolution)
+                        .await
+                }
+                None => {
+                    client
+                        .fetch_metrics_range(cluster_id.clone(), range, None, Vec::new())
+                        .await
+                }
+            }
+            .map_err(|err| format!("{err:#}"));
+            // A closed channel means the app is gone.
+            let _ = tx
+                .send(AnalyticsUpdate::History(
+                    window,
+                    cluster_id.clone(),
+                    range,
+                    samples,
+                ))
+                .await;
+            let predictions = client
+                .fetch_predictions(
+                    cluster_id.clone(),
+                    range,
+                    horizon,
+                    ANALYTICS_FORECAST_TYPES.to_vec(),
+                )
+                .await
+                .map_err(|err| format!("{err:#}"));
+            let _ = tx
+                .send(AnalyticsUpdate::Predictions(
+                    window,
+                    cluster_id,
+                    predictions,
+                ))
+                .await;
+        });
+    }
+
+    /// Add a streamed anomaly unless the full fetch already returned it,
+    /// keeping the newest `ANALYTICS_MAX_ANOMALIES`.
+    fn push_anomaly(&mut self, anomaly: Anomaly) {
+        let anomalies = self.analytics_anomalies.get_or_insert_with(Vec::new);
+        if anomalies.iter().any(|known| known.id == anomaly.id) {
+            return;
+        }
+        anomalies.push(anomaly);
+        if anomalies.len() > ANALYTICS_MAX_ANOMALIES {
+            let excess = anomalies.len() - ANALYTICS_MAX_ANOMALIES;
+            anomalies.drain(..excess);
         }
     }
 
@@ -63,19 +301,67 @@ impl App {
                     Ok(update) => update,
                     Err(_) => break,
                 };
+                drained += 1;
+                // Drop answers fetched for a cluster the user has moved past.
+                let stale = match &update {
+                    AnalyticsUpdate::Metrics(cluster_id, _)
+                    | AnalyticsUpdate::Anomalies(cluster_id, _)
+                    | AnalyticsUpdate::Recommendations(cluster_id, _)
+                    | AnalyticsUpdate::History(_, cluster_id, _, _)
+                    | AnalyticsUpdate::Predictions(_, cluster_id, _) => {
+                        *cluster_id != self.analytics_cluster
+                    }
+                    AnalyticsUpdate::NewAnomaly(anomaly) => self
+                        .analytics_cluster
+                        .as_ref()
+                        .is_some_and(|cluster_id| *cluster_id != anomaly.cluster_id),
+                    _ => false,
+                };
+                if stale {
+                    continue;
+                }
+                let is_data = matches!(
+                    update,
+                    AnalyticsUpdate::Metrics(..)
+                        | AnalyticsUpdate::Anomalies(..)
+                        | AnalyticsUpdate::NewAnomaly(_)
+                        | AnalyticsUpdate::Recommendations(..)
+                );
                 match update {
-                    crate::app::core::AnalyticsUpdate::Metrics(m) => {
-                        self.analytics_metrics = Some(m)
+                    AnalyticsUpdate::Metrics(_, m) => self.analytics_metrics = Some(m),
+                    AnalyticsUpdate::Anomalies(_, a) => self.analytics_anomalies = Some(a),
+                    AnalyticsUpdate::NewAnomaly(anomaly) => self.push_anomaly(anomaly),
+                    AnalyticsUpdate::Recommendations(_, r) => {
+                        self.analytics_recommendations = Some(r)
                     }
-                    crate::app::core::AnalyticsUpdate::Anomalies(a) => {
-                        self.analytics_anomalies = Some(a)
+                    AnalyticsUpdate::Clusters(c) => self.analytics_clusters = c,
+                    AnalyticsUpdate::Notifications(n) => self.set_unread_notifications(n),
+                    AnalyticsUpdate::RecommendationChanged(id, result) => {
+                        self.record_recommendation_change(id, result);
                     }
-                    crate::app::core::AnalyticsUpdate::Recommendations(r) => {
-                        self.analytics_recommendations = Some(r)
+                    AnalyticsUpdate::Connection(c) => {
+                        self.analytics_connection = c;
+                        if c == AnalyticsConnection::Connected {
+                            self.fetch_history();
+                            self.fetch_clusters();
+                        }
+                    }
+                    AnalyticsUpdate::History(window, _, range, samples) => {
+                        // Drop answers for a window the user has moved past.
+                        if window == self.analytics_history_window {
+                            self.analytics_history = Some(samples);
+                            self.analytics_history_range = Some(range);
+                        }
+                    }
+                    AnalyticsUpdate::Predictions(window, _, predictions) => {
+                        if window == self.analytics_history_window {
+                            self.analytics_predictions = Some(predictions);
+                        }
                     }
                 }
-                self.analytics_cache_timestamp = Some(Instant::now());
-                drained += 1;
+                if is_data {
+                    self.analytics_cache_timestamp = Some(Instant::now());
+                }
             }
             if drained >= ANALYTICS_MAX_UPDATES_PER_TICK {
                 tracing::warn!(
@@ -86,3 +372,126 @@ impl App {
         }
     }
 }
+
+/// Poll interval from `PHENOME_TUI_ANALYTICS_INTERVAL` in seconds, five by
+/// default.
+pub(super) fn analytics_interval_from_env() -> Duration {
+    std::env::var("PHENOME_TUI_ANALYTICS_INTERVAL")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|&secs| secs > 0)
+        .map_or(ANALYTICS_POLL_INTERVAL, Duration::from_secs)
+}
+
+/// Follow the anomaly feed of the selected cluster until the app drops `tx`,
+/// resubscribing after the stream ends and at once when the selection
+/// changes. Each subscription starts with a full fetch so anomalies detected
+/// while unsubscribed aren't missed.
+async fn follow_anomalies(
+    client: AnalyticsClient,
+    tx: Sender<AnalyticsUpdate>,
+    mut cluster_rx: watch::Receiver<Option<ClusterId>>,
+) {
+    loop {
+        let cluster_id = cluster_rx.borrow_and_update().clone();
+        tokio::select! {
+            _ = tx.closed() => return,
+            Ok(()) = cluster_rx.changed() => continue,
+            result = forward_anomalies(&client, &tx, cluster_id) => {
+                if let Err(err) = result {
+                    tracing::warn!("Anomaly subscription ended: {:#}", err);
+                }
+            }
+        }
+        tokio::time::sleep(ANALYTICS_RECONNECT_BACKOFF).await;
+    }
+}
+
+async fn forward_anomalies(
+    client: &AnalyticsClient,
+    tx: &Sender<AnalyticsUpdate>,
+    cluster_id: Option<ClusterId>,
+) -> Result<()> {
+    let mut stream = client.subscribe_anomalies(cluster_id.clone()).await?;
+    let anomalies = client.fetch_anomalies(cluster_id.clone()).await?;
+    if tx
+        .send(AnalyticsUpdate::Anomalies(cluster_id, anomalies))
+        .await
+        .is_err()
+    {
+        return Ok(());
+    }
+    while let Some(anomaly) = stream.next().await {
+        if tx
+            .send(AnalyticsUpdate::NewAnomaly(anomaly?))
+            .await
+            .is_err()
+        {
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
+/// Send one round of analytics for `cluster_id` to `tx`, failing on the
+/// first failed call. Returns `false` once the app has dropped the receiver.
+async fn poll_analytics(
+    client: &AnalyticsClient,
+    tx: &Sender<AnalyticsUpdate>,
+    cluster_id: Option<ClusterId>,
+) -> Result<bool> {
+    let metrics = client.fetch_metrics(cluster_id.clone()).await?;
+    if tx
+        .send(AnalyticsUpdate::Metrics(cluster_id.clone(), metrics))
+        .await
+        .is_err()
+    {
+        return Ok(false);
+    }
+    let recommendations = client.fetch_recommendations(cluster_id.clone()).await?;
+    if tx
+        .send(AnalyticsUpdate::Recommendations(
+            cluster_id,
+            recommendations,
+        ))
+        .await
+        .is_err()
+    {
+        return Ok(false);
+    }
+    // The badge keeps its last count rather than failing the whole poll.
+    match client.fetch_unread_notifications().await {
+        Ok(unread) => Ok(tx
+            .send(AnalyticsUpdate::Notifications(unread))
+            .await
+            .is_ok()),
+        Err(err) => {
+            tracing::warn!("Failed to fetch unread notifications: {:#}", err);
+            Ok(true)
+        }
+    }
+}
+
+/// Refresh the cluster list every `ANALYTICS_CLUSTER_LIST_INTERVAL` until
+/// the app drops `tx`. The first list is fetched on connect.
+async fn follow_clusters(client: AnalyticsClient, tx: Sender<AnalyticsUpdate>) {
+    loop {
+        tokio::time::sleep(ANALYTICS_CLUSTER_LIST_INTERVAL).await;
+        if tx.is_closed() {
+            return;
+        }
+        send_clusters(&client, &tx).await;
+    }
+}
+
+/// Send the cluster list to `tx`. A failure is only logged: the selector
+/// keeps the list it has, and the views don't depend on it.
+async fn send_clusters(client: &AnalyticsClient, tx: &Sender<AnalyticsUpdate>) {
+    match client.list_clusters().await {
+        // A closed channel means the app is gone.
+        Ok(clusters) => {
+            let _ = tx.send(AnalyticsUpdate::Clusters(clusters)).await;
+        }
+        Err(err) => tracing::warn!("Failed to list analytics clusters: {:#}", err),
+    }
+}
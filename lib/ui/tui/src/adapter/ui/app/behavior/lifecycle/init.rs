@@ -4,6 +4,7 @@ use std::time::Instant;
 use phenome_domain::{Event, EventLevel};
 
 use crate::app::{App, AppContext};
+use crate::util::DependencyClassifier;
 
 impl App {
     pub fn new(mut runtime: phenome_application::Runtime, context: AppContext) -> Self {
@@ -30,6 +31,17 @@ impl App {
             ));
         }
 
+        let dependency_classifier = match DependencyClassifier::from_config(&context.ui_config) {
+            Ok(classifier) => classifier,
+            Err(error) => {
+                runtime.events_mut().push(Event::new(
+                    EventLevel::Warn,
+                    format!("Dependency rules ignored: {error:#}"),
+                ));
+                DependencyClassifier::default()
+            }
+        };
+
         let mut action_state = ListState::default();
         if !runtime.registry().actions().is_empty() {
             action_state.select(Some(0));
@@ -43,16 +55,36 @@ impl App {
             last_refresh: Instant::now(),
             should_quit: false,
             ui: crate::state::UiState::new(),
-            graph: crate::app::GraphRenderState::new(),
+            graph: crate::app::GraphRenderState::with_disk_cache(
+                crate::app::graph::GraphDiskCache::from_env(),
+            ),
+            dependency_classifier,
             active_nav: crate::app::NavSection::Analytics,
             active_view: crate::app::NavView::AnalyticsRealtime,
             nav_sub_index: [0; 3],
+            live_status_reconnect: None,
             analytics_client: None,
+            analytics_connection: crate::app::core::AnalyticsConnection::default(),
+            analytics_history_window: crate::app::core::HistoricalWindow::default(),
+            analytics_history: None,
+            analytics_history_range: None,
+            analytics_predictions: None,
+            analytics_tx: None,
             analytics_metrics: None,
+            analytics_resource_sort: crate::app::core::ResourceSort::default(),
+            analytics_resource_scroll: 0,
             analytics_anomalies: None,
+            analytics_anomaly_offset: 0,
             analytics_recommendations: None,
+            analytics_recommendation_index: 0,
+            analytics_dismiss: None,
             analytics_cache_timestamp: None,
+            analytics_interval: super::analytics::analytics_interval_from_env(),
+            analytics_poll: None,
+            analytics_cluster: None,
+            analytics_clusters: Vec::new(),
             analytics_rx: None,
+            notifications: Vec::new(),
         };
 
         app.start_analytics();
@@ -3,13 +3,19 @@ use std::time::{Duration, Instant};
 use crate::app::App;
 
 impl App {
+    /// Target interval between automatic snapshot refreshes while watching.
+    pub const AUTO_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
     pub fn on_tick(&mut self) {
-        if self.ui.auto_refresh && self.last_refresh.elapsed() >= Duration::from_secs(1) {
+        if self.ui.auto_refresh && self.last_refresh.elapsed() >= Self::AUTO_REFRESH_INTERVAL {
             self.runtime.refresh_snapshot();
             self.last_refresh = Instant::now();
         }
         self.refresh_log_cache(false);
         self.refresh_analytics_cache();
+        self.poll_live_status_reconnect();
+        self.graph.poll_layout();
+        self.poll_graph_export();
 
         let hold_trigger = if let Some(hold) = &mut self.ui.hold_state {
             if !hold.triggered && hold.started_at.elapsed() >= Duration::from_secs(3) {
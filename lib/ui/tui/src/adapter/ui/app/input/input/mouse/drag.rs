@@ -17,7 +17,8 @@ pub(super) fn handle_graph_drag(app: &mut App, column: u16, row: u16) {
             let graph_dx = dx * (bounds.x_max - bounds.x_min) / screen_w;
             let graph_dy = dy * (bounds.y_max - bounds.y_min) / screen_h;
 
-            app.graph.pan(-graph_dx, graph_dy);
+            app.graph
+                .pan_within(-graph_dx, graph_dy, app.ui.assembly_area);
         }
     }
 }
@@ -1,5 +1,37 @@
+use ratatui::layout::Margin;
+
 use crate::app::{App, NavView};
 
+/// Recenter the view on the point clicked in the minimap.
+pub(super) fn handle_minimap_click(app: &mut App, column: u16, row: u16) -> bool {
+    let view = app.active_view();
+    if !matches!(view, NavView::TopologyDagGraph | NavView::TopologyDualGraph) {
+        return false;
+    }
+    let area = app.ui.minimap_area;
+    if area.is_empty() || !area.contains((column, row).into()) {
+        return false;
+    }
+    let Some(layout) = app.graph.layout() else {
+        return false;
+    };
+    let (width, height) = (layout.width.max(1.0), layout.height.max(1.0));
+    let inner = area.inner(Margin {
+        horizontal: 1,
+        vertical: 1,
+    });
+    if inner.is_empty() {
+        return true;
+    }
+    // Clicks on the border count as its nearest edge.
+    let col = column.clamp(inner.x, inner.right() - 1) - inner.x;
+    let row = row.clamp(inner.y, inner.bottom() - 1) - inner.y;
+    let x = (f64::from(col) + 0.5) / f64::from(inner.width) * width;
+    let y = height - (f64::from(row) + 0.5) / f64::from(inner.height) * height;
+    app.graph.center_on(x, y);
+    true
+}
+
 pub(super) fn handle_graph_click(app: &mut App, column: u16, row: u16) -> bool {
     let view = app.active_view();
     if !matches!(view, NavView::TopologyDagGraph | NavView::TopologyDualGraph) {
@@ -12,17 +44,27 @@ pub(super) fn handle_graph_click(app: &mut App, column: u16, row: u16) -> bool {
     if !area.contains((column, row).into()) {
         return false;
     }
-    let Some(bounds) = app.graph.view_bounds(app.ui.assembly_area) else {
+    let Some((x, y)) = graph_point_at(app, column, row) else {
         return true;
     };
+    if app.graph.select_node_at(x, y) {
+        app.ui.show_detail_panel = true;
+    }
+    true
+}
+
+/// Layout coordinates under a cell of the graph area, if it shows a graph.
+pub(super) fn graph_point_at(app: &App, column: u16, row: u16) -> Option<(f64, f64)> {
+    let area = app.ui.assembly_area;
+    if area.is_empty() || !area.contains((column, row).into()) {
+        return None;
+    }
+    let bounds = app.graph.view_bounds(area)?;
     let width = area.width.saturating_sub(1).max(1);
     let height = area.height.saturating_sub(1).max(1);
     let x_ratio = (column.saturating_sub(area.x) as f64) / (width as f64);
     let y_ratio = (row.saturating_sub(area.y) as f64) / (height as f64);
     let x = bounds.x_min + x_ratio * (bounds.x_max - bounds.x_min);
     let y = bounds.y_max - y_ratio * (bounds.y_max - bounds.y_min);
-    if app.graph.select_node_at(x, y) {
-        app.ui.show_detail_panel = true;
-    }
-    true
+    Some((x, y))
 }
@@ -19,6 +19,9 @@ impl App {
                 if navbar::handle_navbar_click(self, mouse.column, mouse.row) {
                     return Ok(());
                 }
+                if graph::handle_minimap_click(self, mouse.column, mouse.row) {
+                    return Ok(());
+                }
                 if graph::handle_graph_click(self, mouse.column, mouse.row) {
                     return Ok(());
                 }
@@ -35,7 +38,11 @@ impl App {
                 if is_detail_hover {
                     self.ui.detail_scroll = self.ui.detail_scroll.saturating_add(1);
                 } else if matches!(view, NavView::TopologyDagGraph | NavView::TopologyDualGraph) {
-                    self.graph.zoom_out();
+                    let area = self.ui.assembly_area;
+                    match graph::graph_point_at(self, mouse.column, mouse.row) {
+                        Some((x, y)) => self.graph.zoom_out_at(x, y, area),
+                        None => self.graph.zoom_out(area),
+                    }
                 } else {
                     self.update_hover(mouse.column, mouse.row);
                     self.scroll_active_panel(1);
@@ -50,7 +57,11 @@ impl App {
                 if is_detail_hover {
                     self.ui.detail_scroll = self.ui.detail_scroll.saturating_sub(1);
                 } else if matches!(view, NavView::TopologyDagGraph | NavView::TopologyDualGraph) {
-                    self.graph.zoom_in();
+                    let area = self.ui.assembly_area;
+                    match graph::graph_point_at(self, mouse.column, mouse.row) {
+                        Some((x, y)) => self.graph.zoom_in_at(x, y, area),
+                        None => self.graph.zoom_in(area),
+                    }
                 } else {
                     self.update_hover(mouse.column, mouse.row);
                     self.scroll_active_panel(-1);
@@ -1,11 +1,12 @@
 use anyhow::Result;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
+use crate::app::core::ANOMALY_TIMELINE_BUCKETS;
 use crate::app::{App, NavView};
 
 impl App {
     pub fn handle_key_event(&mut self, key: KeyEvent) -> Result<()> {
-        if self.handle_search_key(key) {
+        if self.handle_search_key(key) || self.handle_dismiss_key(key) {
             return Ok(());
         }
 
@@ -27,6 +28,7 @@ impl App {
         match key.code {
             KeyCode::Char('q') | KeyCode::Esc => self.should_quit = true,
             KeyCode::Char('r') => self.runtime.refresh_snapshot(),
+            KeyCode::Char('R') => self.reconnect_live_status(),
             KeyCode::Char('f') => {
                 if matches!(
                     view,
@@ -39,6 +41,43 @@ impl App {
             KeyCode::Char('n') => self.toggle_notifications_panel(),
             KeyCode::Char('w') => self.ui.auto_refresh = !self.ui.auto_refresh,
             KeyCode::Char('a') => self.set_active_nav(crate::app::NavSection::Analytics),
+            KeyCode::Char('t')
+                if matches!(
+                    view,
+                    NavView::AnalyticsHistorical | NavView::AnalyticsPredictions
+                ) =>
+            {
+                self.cycle_history_window();
+            }
+            KeyCode::Char('A') if matches!(view, NavView::AnalyticsRecommendations) => {
+                self.request_apply_recommendation();
+            }
+            KeyCode::Char('D') if matches!(view, NavView::AnalyticsRecommendations) => {
+                self.start_dismiss_recommendation();
+            }
+            KeyCode::Char('u') if self.active_nav() == crate::app::NavSection::Analytics => {
+                self.refresh_analytics_now();
+            }
+            KeyCode::Char('<') if self.active_nav() == crate::app::NavSection::Analytics => {
+                self.shorten_analytics_interval();
+            }
+            KeyCode::Char('>') if self.active_nav() == crate::app::NavSection::Analytics => {
+                self.lengthen_analytics_interval();
+            }
+            KeyCode::Char('c') if self.active_nav() == crate::app::NavSection::Analytics => {
+                self.cycle_analytics_cluster();
+            }
+            KeyCode::Char('s') if matches!(view, NavView::AnalyticsRealtime) => {
+                self.analytics_resource_sort = self.analytics_resource_sort.next();
+                self.analytics_resource_scroll = 0;
+            }
+            KeyCode::Char('h') if matches!(view, NavView::AnalyticsInsights) => {
+                self.analytics_anomaly_offset =
+                    (self.analytics_anomaly_offset + 1).min(ANOMALY_TIMELINE_BUCKETS - 1);
+            }
+            KeyCode::Char('l') if matches!(view, NavView::AnalyticsInsights) => {
+                self.analytics_anomaly_offset = self.analytics_anomaly_offset.saturating_sub(1);
+            }
             KeyCode::Char('1') if self.active_nav() == crate::app::NavSection::Analytics => {
                 self.set_nav_sub_index(0);
             }
@@ -61,6 +100,11 @@ impl App {
             KeyCode::Up | KeyCode::Char('k') => {
                 if matches!(view, NavView::TerminalCommands) {
                     self.select_previous_action();
+                } else if matches!(view, NavView::AnalyticsRecommendations) {
+                    self.select_prev_recommendation();
+                } else if matches!(view, NavView::AnalyticsRealtime) {
+                    self.analytics_resource_scroll =
+                        self.analytics_resource_scroll.saturating_sub(1);
                 } else {
                     self.prev_nav_sub();
                 }
@@ -68,6 +112,11 @@ impl App {
             KeyCode::Down | KeyCode::Char('j') => {
                 if matches!(view, NavView::TerminalCommands) {
                     self.select_next_action();
+                } else if matches!(view, NavView::AnalyticsRecommendations) {
+                    self.select_next_recommendation();
+                } else if matches!(view, NavView::AnalyticsRealtime) {
+                    // The panel clamps this to its last page.
+                    self.analytics_resource_scroll += 1;
                 } else {
                     self.next_nav_sub();
                 }
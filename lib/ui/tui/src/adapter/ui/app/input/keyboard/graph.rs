@@ -1,5 +1,6 @@
 use anyhow::Result;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use graphviz_rust::cmd::Format;
 
 use crate::app::{App, GraphDirection, NavView};
 
@@ -22,16 +23,116 @@ impl App {
                 self.ui.search_active = true;
                 Ok(true)
             }
+            // While a search left matches behind, n/N step through them and
+            // esc drops them; otherwise `n` falls through to the notifications
+            // toggle.
+            KeyCode::Char('n') if self.has_search_matches() => {
+                self.graph.select_next_match();
+                Ok(true)
+            }
+            KeyCode::Char('N') if self.has_search_matches() => {
+                self.graph.select_prev_match();
+                Ok(true)
+            }
+            KeyCode::Esc if self.has_search_matches() => {
+                self.ui.search_query.clear();
+                self.graph.clear_search();
+                Ok(true)
+            }
             KeyCode::Char('+') | KeyCode::Char('=') => {
-                self.graph.zoom_in();
+                self.graph.zoom_in(self.ui.assembly_area);
                 Ok(true)
             }
             KeyCode::Char('-') => {
-                self.graph.zoom_out();
+                self.graph.zoom_out(self.ui.assembly_area);
                 Ok(true)
             }
             KeyCode::Char('0') => {
-                self.graph.reset_view();
+                self.graph.fit_to_view(self.ui.assembly_area);
+                Ok(true)
+            }
+            KeyCode::Char('c') => {
+                self.graph.select_next_component();
+                Ok(true)
+            }
+            KeyCode::Char('C') => {
+                self.graph.select_prev_component();
+                Ok(true)
+            }
+            KeyCode::Char('z') => {
+                self.graph.frame_selected_component(self.ui.assembly_area);
+                Ok(true)
+            }
+            KeyCode::Char('i') => {
+                self.graph.toggle_isolation();
+                Ok(true)
+            }
+            KeyCode::Char('x') => {
+                self.toggle_selected_domain();
+                Ok(true)
+            }
+            KeyCode::Char('X') => {
+                self.graph.expand_all_domains();
+                Ok(true)
+            }
+            KeyCode::Char('y') => {
+                self.copy_selected_node();
+                Ok(true)
+            }
+            KeyCode::Char('m') => {
+                self.ui.show_minimap = !self.ui.show_minimap;
+                Ok(true)
+            }
+            KeyCode::Char('e') => {
+                self.export_graph(Format::Svg);
+                Ok(true)
+            }
+            KeyCode::Char('E') => {
+                self.export_graph(Format::Png);
+                Ok(true)
+            }
+            KeyCode::Char('o') => {
+                self.graph.cycle_rank_dir();
+                Ok(true)
+            }
+            KeyCode::Char('O') => {
+                self.graph.flip_rank_dir();
+                Ok(true)
+            }
+            KeyCode::Char('l') => {
+                self.graph.cycle_engine();
+                Ok(true)
+            }
+            KeyCode::Char('>') => {
+                self.graph.widen_spacing();
+                Ok(true)
+            }
+            KeyCode::Char('<') => {
+                self.graph.tighten_spacing();
+                Ok(true)
+            }
+            KeyCode::Char('p') if self.ui.graph_stats => {
+                self.ui.show_graph_stats = !self.ui.show_graph_stats;
+                Ok(true)
+            }
+            KeyCode::Char('v') if self.ui.credential_hints => {
+                self.ui.reveal_credentials = !self.ui.reveal_credentials;
+                Ok(true)
+            }
+            KeyCode::Left if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.fine_pan_graph(GraphDirection::Left);
+                Ok(true)
+            }
+            KeyCode::Right if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.fine_pan_graph(GraphDirection::Right);
+                Ok(true)
+            }
+            KeyCode::Up if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.fine_pan_graph(GraphDirection::Up);
+                Ok(true)
+            }
+            KeyCode::Down if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.fine_pan_graph(GraphDirection::Down);
                 Ok(true)
             }
             KeyCode::Up if key.modifiers.contains(KeyModifiers::SHIFT) => {
@@ -78,16 +179,33 @@ impl App {
         }
     }
 
+    fn has_search_matches(&self) -> bool {
+        !self.graph.search_matches().is_empty()
+    }
+
     fn pan_graph(&mut self, direction: GraphDirection) {
         let Some(layout) = self.graph.layout() else {
             return;
         };
-        let (step_x, step_y) = self.graph.pan_step(layout, self.ui.assembly_area);
+        let step = self.graph.pan_step(layout, self.ui.assembly_area);
+        self.pan_graph_by(direction, step);
+    }
+
+    fn fine_pan_graph(&mut self, direction: GraphDirection) {
+        let Some(layout) = self.graph.layout() else {
+            return;
+        };
+        let step = self.graph.fine_pan_step(layout, self.ui.assembly_area);
+        self.pan_graph_by(direction, step);
+    }
+
+    fn pan_graph_by(&mut self, direction: GraphDirection, (step_x, step_y): (f64, f64)) {
+        let area = self.ui.assembly_area;
         match direction {
-            GraphDirection::Left => self.graph.pan(-step_x, 0.0),
-            GraphDirection::Right => self.graph.pan(step_x, 0.0),
-            GraphDirection::Up => self.graph.pan(0.0, step_y),
-            GraphDirection::Down => self.graph.pan(0.0, -step_y),
+            GraphDirection::Left => self.graph.pan_within(-step_x, 0.0, area),
+            GraphDirection::Right => self.graph.pan_within(step_x, 0.0, area),
+            GraphDirection::Up => self.graph.pan_within(0.0, step_y, area),
+            GraphDirection::Down => self.graph.pan_within(0.0, -step_y, area),
         }
     }
 }
@@ -1,3 +1,4 @@
 mod core;
 mod graph;
+mod recommendations;
 mod search;
@@ -0,0 +1,21 @@
+use crate::app::App;
+use crossterm::event::{KeyCode, KeyEvent};
+
+impl App {
+    /// Type into the dismissal reason prompt while it is open.
+    pub fn handle_dismiss_key(&mut self, key: KeyEvent) -> bool {
+        let Some(prompt) = self.analytics_dismiss.as_mut() else {
+            return false;
+        };
+        match key.code {
+            KeyCode::Esc => self.analytics_dismiss = None,
+            KeyCode::Enter => self.submit_dismiss_recommendation(),
+            KeyCode::Backspace => {
+                prompt.reason.pop();
+            }
+            KeyCode::Char(c) => prompt.reason.push(c),
+            _ => {}
+        }
+        true
+    }
+}
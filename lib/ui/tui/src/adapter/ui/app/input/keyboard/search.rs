@@ -10,17 +10,20 @@ impl App {
             KeyCode::Esc => {
                 self.ui.search_active = false;
                 self.ui.search_query.clear();
+                self.graph.clear_search();
             }
             KeyCode::Enter => {
+                // The query stays so its matches remain highlighted for n/N.
                 self.execute_search();
                 self.ui.search_active = false;
-                self.ui.search_query.clear();
             }
             KeyCode::Backspace => {
                 self.ui.search_query.pop();
+                self.graph.set_search_query(&self.ui.search_query);
             }
             KeyCode::Char(c) => {
                 self.ui.search_query.push(c);
+                self.graph.set_search_query(&self.ui.search_query);
             }
             _ => {}
         }
@@ -28,28 +31,7 @@ impl App {
     }
 
     pub fn execute_search(&mut self) {
-        let query = self.ui.search_query.to_lowercase();
-        if query.is_empty() {
-            return;
-        }
-        let Some(layout) = self.graph.layout() else {
-            return;
-        };
-
-        let best = layout
-            .nodes
-            .iter()
-            .find(|n| n.id.to_lowercase() == query)
-            .or_else(|| {
-                layout
-                    .nodes
-                    .iter()
-                    .find(|n| n.label.to_lowercase().contains(&query))
-            })
-            .map(|n| n.id.clone());
-
-        if let Some(id) = best {
-            self.graph.select_node(&id);
-        }
+        self.graph.set_search_query(&self.ui.search_query);
+        self.graph.select_best_match();
     }
 }
@@ -24,11 +24,13 @@ pub use input::{input as process_input, keyboard};
 pub use layout::{layout as update_layout, navigation, panel};
 pub use state::{collapse, hover, scroll, tooltips};
 
-pub(crate) use graph::{GraphDirection, GraphRenderState, TerminalImageProtocol};
+pub(crate) use graph::{
+    GraphDirection, GraphEdgeDependency, GraphRenderState, GraphvizProbe, TerminalImageProtocol,
+};
 #[doc(inline)]
 pub use navigation::{NavAction, NavSection, NavSubItem, NavView, nav_items};
 #[doc(inline)]
 pub use panel::PanelId;
 
 #[doc(inline)]
-pub use core::{App, AppContext, ConfirmPrompt};
+pub use core::{App, AppContext, ConfirmPrompt, ConfirmTarget, DismissPrompt};
@@ -6,6 +6,9 @@ impl App {
     pub fn toggle_notifications_panel(&mut self) {
         let next = !self.panel_collapsed(PanelId::Notifications);
         self.set_panel_collapsed(PanelId::Notifications, next);
+        if next {
+            self.forget_read_notifications();
+        }
     }
 
     pub(crate) fn is_collapsed(&self, panel: PanelId) -> bool {
@@ -13,7 +13,10 @@ pub(super) fn update_graph_hover(app: &mut App, column: u16, row: u16) {
         let y = bounds.y_max - y_ratio * (bounds.y_max - bounds.y_min);
 
         app.ui.hover_node_id = app.graph.node_id_at(x, y);
-        if app.ui.hover_node_id.is_some() {
+        if app.ui.hover_node_id.is_none() {
+            app.ui.hover_edge = app.graph.edge_at(x, y);
+        }
+        if app.ui.hover_node_id.is_some() || app.ui.hover_edge.is_some() {
             app.ui.hover_panel = HoverPanel::Graph;
         }
     }
@@ -15,6 +15,7 @@ impl App {
         self.ui.hover_action_index = None;
         self.ui.hover_capability_index = None;
         self.ui.hover_node_id = None;
+        self.ui.hover_edge = None;
 
         if self.ui.assembly_area.contains(pos) && !self.ui.collapsed_assembly_steps {
             let view = self.active_view();
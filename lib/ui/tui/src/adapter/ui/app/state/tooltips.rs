@@ -36,6 +36,9 @@ impl App {
                 });
             }
         }
+        if let Some((tail, head)) = &self.ui.hover_edge {
+            return Some(self.edge_tooltip(tail, head));
+        }
         if let Some(index) = self.ui.hover_action_index {
             let step = self.runtime.snapshot().assembly_steps.get(index)?;
             return Some(Tooltip {
@@ -72,6 +75,40 @@ impl App {
         None
     }
 
+    /// What a graph edge stands for: which step requires which, and the
+    /// capabilities the one requires that the other provides.
+    fn edge_tooltip(&self, tail: &str, head: &str) -> Tooltip {
+        let edge = self.graph.layout().and_then(|layout| {
+            layout.edges.iter().find(|edge| {
+                layout.nodes[edge.tail].id == tail && layout.nodes[edge.head].id == head
+            })
+        });
+
+        let mut lines = Vec::new();
+        match edge.and_then(|edge| edge.dependency.as_ref()) {
+            Some(dependency) => {
+                lines.push(format!(
+                    "{} requires {}",
+                    dependency.dependent, dependency.provider
+                ));
+                let capabilities = if dependency.capabilities.is_empty() {
+                    "-".to_string()
+                } else {
+                    dependency.capabilities.join(", ")
+                };
+                lines.push(format!("Capabilities: {capabilities}"));
+            }
+            None => lines.push("Not a dependency between assembly steps".to_string()),
+        }
+        if let Some(label) = edge.and_then(|edge| edge.label.as_ref()) {
+            lines.push(format!("Label: {label}"));
+        }
+        Tooltip {
+            title: format!("Edge {tail} -> {head}"),
+            lines,
+        }
+    }
+
     pub fn pin_tooltip(&mut self) {
         if let Some(tooltip) = self.current_tooltip() {
             self.ui.pinned_tooltip = Some(tooltip);
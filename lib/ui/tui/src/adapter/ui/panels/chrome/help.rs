@@ -1,12 +1,19 @@
+use std::time::Duration;
+
 use ratatui::{
     layout::Rect,
     prelude::{Alignment, Frame},
-    style::{Modifier, Style},
+    style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph, Wrap},
 };
 
 use crate::app::App;
+use phenome_domain::{EventLevel, now_millis};
+use phenome_ui_presentation::formatting::summarize_events;
+
+/// How far back the footer event summary looks.
+const EVENT_SUMMARY_WINDOW: Duration = Duration::from_secs(5 * 60);
 
 /// Render the footer help panel.
 ///
@@ -25,19 +32,41 @@ use crate::app::App;
 ///     .unwrap();
 /// ```
 pub fn render_footer(frame: &mut Frame, area: Rect, app: &mut App) {
+    let block = Block::default()
+        .title("Help")
+        .title(event_summary(app))
+        .borders(Borders::ALL);
     if app.ui.collapsed_help {
-        let block = Block::default().title("Help").borders(Borders::ALL);
         frame.render_widget(block, area);
         return;
     }
     let lines = help_lines(app);
     let paragraph = Paragraph::new(lines)
-        .block(Block::default().title("Help").borders(Borders::ALL))
+        .block(block)
         .alignment(Alignment::Left)
         .wrap(Wrap { trim: true });
     frame.render_widget(paragraph, area);
 }
 
+/// Right-aligned count of recent warnings and errors, colored by the worst.
+fn event_summary(app: &App) -> Line<'static> {
+    let summary = summarize_events(
+        app.runtime.events().iter(),
+        now_millis(),
+        EVENT_SUMMARY_WINDOW,
+    );
+    let color = match summary.worst_level() {
+        Some(EventLevel::Error) => Color::Red,
+        Some(EventLevel::Warn) => Color::Yellow,
+        Some(EventLevel::Info) | None => Color::DarkGray,
+    };
+    Line::from(Span::styled(
+        format!(" {} ", summary.label()),
+        Style::default().fg(color),
+    ))
+    .right_aligned()
+}
+
 fn help_lines(app: &App) -> Vec<Line<'_>> {
     let mut lines = Vec::new();
     lines.push(section_title("Navigation"));
@@ -46,6 +75,7 @@ fn help_lines(app: &App) -> Vec<Line<'_>> {
     lines.push(Line::from("enter: activate menu  n: toggle diagnostics"));
     lines.push(Line::from("menu items with * run a command"));
     lines.push(Line::from("q/esc: quit  r: refresh snapshot"));
+    lines.push(Line::from("R: reconnect live status"));
     if let Some(item) = app.active_subitem() {
         lines.push(Line::from(format!(
             "Active: {} > {}",
@@ -63,6 +93,40 @@ fn help_lines(app: &App) -> Vec<Line<'_>> {
         | crate::app::NavView::AnalyticsInsights => {
             lines.push(section_title("Analytics"));
             lines.push(Line::from("1-4: switch analytics views"));
+            lines.push(Line::from(format!(
+                "u: refresh now  < >: poll interval (current: {}s)",
+                app.analytics_interval.as_secs()
+            )));
+            lines.push(Line::from(format!(
+                "c: cycle cluster (current: {})",
+                crate::panels::analytics::scope(app)
+            )));
+            if matches!(
+                app.active_view(),
+                crate::app::NavView::AnalyticsHistorical
+                    | crate::app::NavView::AnalyticsPredictions
+            ) {
+                lines.push(Line::from(format!(
+                    "t: time window (current: {})",
+                    app.analytics_history_window.label()
+                )));
+            }
+            if matches!(
+                app.active_view(),
+                crate::app::NavView::AnalyticsRecommendations
+            ) {
+                lines.push(Line::from("j/k: select recommendation"));
+                lines.push(Line::from("A: apply  D: dismiss with a reason"));
+            }
+            if matches!(app.active_view(), crate::app::NavView::AnalyticsRealtime) {
+                lines.push(Line::from(format!(
+                    "s: sort resources (current: {})  j/k: scroll",
+                    app.analytics_resource_sort.label()
+                )));
+            }
+            if matches!(app.active_view(), crate::app::NavView::AnalyticsInsights) {
+                lines.push(Line::from("h/l: older/newer anomaly bucket"));
+            }
         }
         crate::app::NavView::TopologyAssembly
         | crate::app::NavView::TopologyDomains
@@ -74,7 +138,40 @@ fn help_lines(app: &App) -> Vec<Line<'_>> {
             lines.push(section_title("Topology"));
             lines.push(Line::from("click: select node  enter: activate"));
             lines.push(Line::from("arrows: navigate  shift+arrows: pan"));
-            lines.push(Line::from("+/-: zoom  0: reset view"));
+            lines.push(Line::from("ctrl+arrows: fine pan"));
+            lines.push(Line::from("+/-: zoom around selection  0: fit to screen"));
+            lines.push(Line::from("/: search  n/N: next/prev match  esc: clear"));
+            lines.push(Line::from("c/C: next/prev component  z: frame component"));
+            match app.graph.isolation() {
+                Some(isolation) => lines.push(Line::from(format!(
+                    "i: full graph (isolated around {})",
+                    isolation.root
+                ))),
+                None => lines.push(Line::from("i: isolate selected node's neighbors")),
+            }
+            lines.push(Line::from("x: collapse/expand domain  X: expand all"));
+            lines.push(Line::from("y: copy selected node id and URLs"));
+            lines.push(Line::from("m: toggle minimap (click it to jump)"));
+            lines.push(Line::from("e/E: export graph as SVG/PNG"));
+            lines.push(Line::from(format!(
+                "o: rank direction (current: {})  O: flip TB/LR",
+                app.graph.rank_dir().as_arg()
+            )));
+            lines.push(Line::from(format!(
+                "l: layout engine (current: {})",
+                app.graph.engine().as_str()
+            )));
+            let attributes = app.graph.attributes();
+            lines.push(Line::from(format!(
+                "</>: image spacing (nodesep {:.2}, ranksep {:.2})",
+                attributes.nodesep, attributes.ranksep
+            )));
+            if app.ui.graph_stats {
+                lines.push(Line::from("p: toggle render stats"));
+            }
+            if app.ui.credential_hints {
+                lines.push(Line::from("v: reveal/hide credential hints"));
+            }
             lines.push(Line::from("paths highlight dependencies from selection"));
             if let Some(node) = app.graph.selected_node() {
                 lines.push(Line::from(format!("Selected: {}", node.label)));
@@ -1,3 +1,4 @@
 pub mod help;
 pub mod navbar;
 pub mod notifications;
+pub mod status;
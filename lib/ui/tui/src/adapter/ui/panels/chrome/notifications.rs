@@ -3,74 +3,64 @@ use ratatui::{
     layout::Rect,
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Wrap},
+    widgets::{Block, Borders, Paragraph},
 };
 
-#[derive(Clone, Copy)]
-struct AlertItem {
-    source: &'static str,
-    message: &'static str,
-    hint: &'static str,
-    severity: AlertSeverity,
-}
+use phenome_domain::{Notification, Severity};
 
-#[derive(Clone, Copy)]
-enum AlertSeverity {
-    Critical,
-    Warning,
-}
+/// Lines a notification takes: its title, its message and a gap.
+const NOTIFICATION_LINES: u16 = 3;
 
-impl AlertSeverity {
-    fn label(self) -> &'static str {
-        match self {
-            AlertSeverity::Critical => "CRIT",
-            AlertSeverity::Warning => "WARN",
-        }
+fn severity_label(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Critical => "CRIT",
+        Severity::Warning => "WARN",
+        Severity::Info => "INFO",
     }
+}
 
-    fn style(self) -> Style {
-        match self {
-            AlertSeverity::Critical => Style::default()
-                .fg(Color::Red)
-                .add_modifier(Modifier::BOLD),
-            AlertSeverity::Warning => Style::default()
-                .fg(Color::Yellow)
-                .add_modifier(Modifier::BOLD),
-        }
-    }
+fn severity_style(severity: Severity) -> Style {
+    let color = match severity {
+        Severity::Critical => Color::Red,
+        Severity::Warning => Color::Yellow,
+        Severity::Info => Color::Cyan,
+    };
+    Style::default().fg(color).add_modifier(Modifier::BOLD)
 }
 
-const ALERTS: [AlertItem; 2] = [
-    AlertItem {
-        source: "backend-api",
-        message: "High memory usage detected (92% of limit)",
-        hint: "Consider increasing memory limits",
-        severity: AlertSeverity::Warning,
-    },
-    AlertItem {
-        source: "worker-job",
-        message: "ImagePullBackOff: Failed to pull image",
-        hint: "Verify image repository credentials",
-        severity: AlertSeverity::Critical,
-    },
-];
-
-pub struct NotificationPanel {}
-
-impl NotificationPanel {
-    pub fn new() -> Self {
-        Self {}
+pub struct NotificationPanel<'a> {
+    notifications: &'a [Notification],
+}
+
+impl<'a> NotificationPanel<'a> {
+    pub fn new(notifications: &'a [Notification]) -> Self {
+        Self { notifications }
     }
 
-    pub fn render(&self, f: &mut Frame, area: Rect) {
+    /// Render as many notifications as fit, newest first, and return the
+    /// ids of those shown.
+    pub fn render(&self, f: &mut Frame, area: Rect) -> Vec<String> {
         if area.width == 0 || area.height == 0 {
-            return;
+            return Vec::new();
+        }
+        let inner_height = area.height.saturating_sub(2);
+        // The last notification needs no gap below it.
+        let fits = usize::from((inner_height + 1) / NOTIFICATION_LINES);
+        let shown = &self.notifications[..self.notifications.len().min(fits)];
+
+        let mut title = vec![Span::styled(
+            "Notifications (n to close)",
+            Style::default().add_modifier(Modifier::BOLD),
+        )];
+        let hidden = self.notifications.len() - shown.len();
+        if hidden > 0 {
+            title.push(Span::styled(
+                format!(" +{hidden} more"),
+                Style::default().fg(Color::DarkGray),
+            ));
         }
         let block = Block::default()
-            .title(Span::styled(
-                "Error Diagnostics (n to close)",
-                Style::default().add_modifier(Modifier::BOLD),
-            ))
+            .title(Line::from(title))
             .borders(Borders::ALL)
             .border_style(Style::default().fg(Color::DarkGray))
             .style(Style::default().bg(Color::Rgb(22, 24, 28)));
@@ -78,35 +68,104 @@ impl NotificationPanel {
         let inner = block.inner(area);
         f.render_widget(block, area);
 
+        if shown.is_empty() {
+            f.render_widget(
+                Paragraph::new(Span::styled(
+                    "No unread notifications.",
+                    Style::default().fg(Color::DarkGray),
+                )),
+                inner,
+            );
+            return Vec::new();
+        }
+
         let mut lines = Vec::new();
-        for (index, item) in ALERTS.iter().enumerate() {
-            lines.push(Line::from(vec![
-                Span::styled(item.severity.label(), item.severity.style()),
+        for (index, notification) in shown.iter().enumerate() {
+            let mut heading = vec![
+                Span::styled(
+                    severity_label(notification.severity),
+                    severity_style(notification.severity),
+                ),
                 Span::raw(" "),
                 Span::styled(
-                    item.source,
-                    Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+                    notification.title.clone(),
+                    Style::default()
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD),
                 ),
-            ]));
+            ];
+            if notification.read {
+                heading.push(Span::styled(
+                    " (read)",
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+            lines.push(Line::from(heading));
             lines.push(Line::from(Span::styled(
-                item.message,
+                notification.message.clone(),
                 Style::default().fg(Color::Gray),
             )));
-            lines.push(Line::from(vec![
-                Span::styled("Hint: ", Style::default().fg(Color::Cyan)),
-                Span::styled(item.hint, Style::default().fg(Color::LightBlue)),
-            ]));
-            if index + 1 < ALERTS.len() {
+            if index + 1 < shown.len() {
                 lines.push(Line::from(""));
             }
         }
 
-        let paragraph = Paragraph::new(lines).wrap(Wrap { trim: true });
-        f.render_widget(paragraph, inner);
+        // Unwrapped, so every notification counted as shown is on screen.
+        f.render_widget(Paragraph::new(lines), inner);
+        shown.iter().map(|n| n.id.clone()).collect()
     }
 }
 
-pub fn render_notifications(f: &mut Frame, area: Rect) {
-    let panel = NotificationPanel::new();
-    panel.render(f, area);
+pub fn render_notifications(
+    f: &mut Frame,
+    area: Rect,
+    notifications: &[Notification],
+) -> Vec<String> {
+    let panel = NotificationPanel::new(notifications);
+    panel.render(f, area)
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{Terminal, backend::TestBackend};
+
+    use phenome_domain::{Notification, Severity};
+
+    use super::render_notifications;
+
+    fn notification(id: &str, title: &str) -> Notification {
+        Notification {
+            id: id.to_string(),
+            title: title.to_string(),
+            message: "node-1 cpu at 85%".to_string(),
+            severity: Severity::Warning,
+            timestamp: 0,
+            read: false,
+            link: None,
+            cluster_id: None,
+            resource_id: None,
+        }
+    }
+
+    #[test]
+    fn only_notifications_that_fit_count_as_shown() {
+        let notifications = [
+            notification("n-3", "High CPU"),
+            notification("n-2", "Pod restarting"),
+            notification("n-1", "Disk pressure"),
+        ];
+        // Two notifications and the gap between them, inside the border.
+        let mut terminal = Terminal::new(TestBackend::new(40, 7)).unwrap();
+        let mut shown = Vec::new();
+        terminal
+            .draw(|frame| shown = render_notifications(frame, frame.area(), &notifications))
+            .unwrap();
+
+        assert_eq!(shown, ["n-3", "n-2"]);
+        let buffer = terminal.backend().buffer();
+        let title: String = (0..buffer.area.width)
+            .map(|x| buffer[(x, 0)].symbol())
+            .collect();
+        assert!(title.contains("+1 more"), "{title}");
+    }
 }
@@ -0,0 +1,95 @@
+//! Connection status and unread notifications shown in the main panel
+//! header.
+
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders},
+};
+
+use crate::app::AppContext;
+
+const OFFLINE_BADGE: &str = " OFFLINE · static data ";
+
+/// Right-aligned badge shown while live status is unavailable, so stale
+/// data is not mistaken for live cluster state.
+pub fn offline_badge(context: &AppContext) -> Option<Line<'static>> {
+    context.live_status_error.as_ref()?;
+    let style = Style::default()
+        .fg(Color::Black)
+        .bg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+    Some(Line::from(Span::styled(OFFLINE_BADGE, style)).right_aligned())
+}
+
+/// Right-aligned count of unread notifications, `None` with none unread.
+pub fn unread_badge(unread: usize) -> Option<Line<'static>> {
+    if unread == 0 {
+        return None;
+    }
+    let style = Style::default()
+        .fg(Color::Black)
+        .bg(Color::LightBlue)
+        .add_modifier(Modifier::BOLD);
+    Some(Line::from(Span::styled(format!(" {unread} unread (n) "), style)).right_aligned())
+}
+
+/// Bordered main panel block with `title` and, when they apply, the offline
+/// and unread notification badges.
+pub fn header_block<'a>(title: Span<'a>, context: &AppContext, unread: usize) -> Block<'a> {
+    let mut block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::DarkGray));
+    if let Some(badge) = unread_badge(unread) {
+        block = block.title(badge);
+    }
+    if let Some(badge) = offline_badge(context) {
+        block = block.title(badge);
+    }
+    block
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{Terminal, backend::TestBackend, text::Span};
+
+    use super::header_block;
+    use crate::app::AppContext;
+    use phenome_ports::PortSet;
+
+    fn header_text(context: &AppContext, unread: usize) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(60, 3)).unwrap();
+        terminal
+            .draw(|frame| {
+                frame.render_widget(
+                    header_block(Span::raw("Topology"), context, unread),
+                    frame.area(),
+                )
+            })
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.width)
+            .map(|x| buffer[(x, 0)].symbol())
+            .collect()
+    }
+
+    #[test]
+    fn offline_badge_renders_when_live_status_is_unavailable() {
+        let mut context =
+            AppContext::new("localhost", "config.yml", "assembly.yml", PortSet::empty());
+        assert!(!header_text(&context, 0).contains("OFFLINE"));
+
+        context.live_status_error = Some("connection refused".to_string());
+        let header = header_text(&context, 0);
+        assert!(header.contains("Topology"));
+        assert!(header.contains("OFFLINE · static data"));
+    }
+
+    #[test]
+    fn unread_badge_counts_unread_notifications() {
+        let context = AppContext::new("localhost", "config.yml", "assembly.yml", PortSet::empty());
+        assert!(!header_text(&context, 0).contains("unread"));
+        assert!(header_text(&context, 3).contains("3 unread (n)"));
+    }
+}
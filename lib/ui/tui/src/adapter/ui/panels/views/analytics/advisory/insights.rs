@@ -7,33 +7,272 @@ use ratatui::{
 };
 
 use crate::app::App;
+use crate::app::core::ANOMALY_TIMELINE_BUCKETS;
+use crate::util::format_age;
+use phenome_domain::{Anomaly, MetricType, Severity};
+use phenome_ui_presentation::formatting::OnboardingPanel;
+
+/// Heat glyphs by anomaly count: one, two, three or four, five and more.
+const HEAT: [char; 4] = ['░', '▒', '▓', '█'];
 
 pub fn render_insights(frame: &mut Frame, area: Rect, app: &mut App) {
+    if let Some(hints) = super::super::onboarding(app, OnboardingPanel::Insights) {
+        frame.render_widget(hints, area);
+        return;
+    }
+
     let mut lines = Vec::new();
-    lines.push(section_title("Insights"));
+    lines.push(Line::from(vec![
+        section_title("Insights"),
+        Span::styled(
+            format!("  {}", super::super::data_status(app)),
+            Style::default().fg(Color::DarkGray),
+        ),
+    ]));
 
     match app.analytics_anomalies.as_ref() {
         Some(anomalies) if !anomalies.is_empty() => {
-            for anomaly in anomalies.iter().take(8) {
-                lines.push(Line::from(format!(
-                    "- [{}] {}",
-                    format!("{:?}", anomaly.severity).to_lowercase(),
-                    anomaly.description
-                )));
-            }
+            let timeline = AnomalyTimeline::new(anomalies, ANOMALY_TIMELINE_BUCKETS);
+            let selected = ANOMALY_TIMELINE_BUCKETS - 1 - app.analytics_anomaly_offset;
+            lines.push(severity_summary(anomalies));
+            lines.push(metric_summary(anomalies));
+            lines.push(Line::from(""));
+            lines.extend(timeline_lines(&timeline, selected, area.width));
+            lines.push(Line::from(""));
+            lines.extend(bucket_lines(&timeline, selected));
         }
         _ => {
             lines.push(Line::from("No anomalies detected."));
         }
     }
 
-    let paragraph = Paragraph::new(lines).wrap(Wrap { trim: true });
+    let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false });
     frame.render_widget(paragraph, area);
 }
 
-fn section_title(label: &'static str) -> Line<'static> {
-    Line::from(Span::styled(
+fn section_title(label: &'static str) -> Span<'static> {
+    Span::styled(
         label,
-        Style::default().fg(Color::LightBlue).add_modifier(Modifier::BOLD),
-    ))
+        Style::default()
+            .fg(Color::LightBlue)
+            .add_modifier(Modifier::BOLD),
+    )
+}
+
+/// Anomalies split into equal time buckets ending with the newest one.
+struct AnomalyTimeline<'a> {
+    start_ms: i64,
+    bucket_ms: i64,
+    buckets: Vec<Vec<&'a Anomaly>>,
+}
+
+impl<'a> AnomalyTimeline<'a> {
+    /// `count` buckets spanning the oldest to the newest of `anomalies`,
+    /// which must not be empty.
+    fn new(anomalies: &'a [Anomaly], count: usize) -> Self {
+        let oldest = anomalies.iter().map(|a| a.detected_at).min().unwrap_or(0);
+        let newest = anomalies.iter().map(|a| a.detected_at).max().unwrap_or(0);
+        // End just past the newest so it lands in the last bucket.
+        let end_ms = newest + 1;
+        let slots = count.max(1) as i64;
+        let bucket_ms = ((end_ms - oldest + slots - 1) / slots).max(1);
+        let start_ms = end_ms - bucket_ms * slots;
+        let mut buckets = vec![Vec::new(); count.max(1)];
+        for anomaly in anomalies {
+            let index = ((anomaly.detected_at - start_ms) / bucket_ms) as usize;
+            buckets[index].push(anomaly);
+        }
+        Self {
+            start_ms,
+            bucket_ms,
+            buckets,
+        }
+    }
+
+    fn bucket_start(&self, index: usize) -> i64 {
+        self.start_ms + self.bucket_ms * index as i64
+    }
+}
+
+/// The most severe of `anomalies`, if any.
+fn worst(anomalies: &[&Anomaly]) -> Option<Severity> {
+    anomalies
+        .iter()
+        .map(|anomaly| anomaly.severity)
+        .reduce(|worst, severity| {
+            if severity.at_least(worst) {
+                severity
+            } else {
+                worst
+            }
+        })
+}
+
+fn severity_color(severity: Severity) -> Color {
+    match severity {
+        Severity::Critical => Color::Red,
+        Severity::Warning => Color::Yellow,
+        Severity::Info => Color::Blue,
+    }
+}
+
+fn heat_glyph(count: usize) -> char {
+    match count {
+        0 => '·',
+        1 => HEAT[0],
+        2 => HEAT[1],
+        3..=4 => HEAT[2],
+        _ => HEAT[3],
+    }
+}
+
+fn severity_summary(anomalies: &[Anomaly]) -> Line<'static> {
+    let count = |severity: Severity| {
+        anomalies
+            .iter()
+            .filter(|anomaly| anomaly.severity == severity)
+            .count()
+    };
+    let mut spans = vec![Span::raw(format!("Anomalies: {}  ", anomalies.len()))];
+    for severity in [Severity::Critical, Severity::Warning, Severity::Info] {
+        spans.push(Span::styled(
+            format!("{severity:?} {}  ", count(severity)).to_lowercase(),
+            Style::default().fg(severity_color(severity)),
+        ));
+    }
+    Line::from(spans)
+}
+
+/// Counts per metric type, most frequent first.
+fn metric_summary(anomalies: &[Anomaly]) -> Line<'static> {
+    let mut counts: Vec<(MetricType, usize)> = Vec::new();
+    for anomaly in anomalies {
+        match counts
+            .iter_mut()
+            .find(|(metric, _)| *metric == anomaly.metric_type)
+        {
+            Some((_, count)) => *count += 1,
+            None => counts.push((anomaly.metric_type, 1)),
+        }
+    }
+    counts.sort_by(|a, b| b.1.cmp(&a.1));
+    let parts: Vec<String> = counts
+        .iter()
+        .map(|(metric, count)| format!("{metric:?} {count}"))
+        .collect();
+    Line::from(format!("By metric: {}", parts.join("  ")))
+}
+
+/// The heat row, a caret under the selected bucket and the time axis. The
+/// oldest buckets are left out when `width` can't fit them all.
+fn timeline_lines(timeline: &AnomalyTimeline, selected: usize, width: u16) -> Vec<Line<'static>> {
+    let total = timeline.buckets.len();
+    let shown = total.min(usize::from(width).max(1));
+    let first = total - shown;
+    let columns = (usize::from(width) / shown).max(1);
+
+    let mut heat = Vec::with_capacity(shown);
+    let mut caret = String::new();
+    for (index, bucket) in timeline.buckets.iter().enumerate().skip(first) {
+        let color = worst(bucket).map_or(Color::DarkGray, severity_color);
+        let glyph = heat_glyph(bucket.len()).to_string().repeat(columns);
+        heat.push(Span::styled(glyph, Style::default().fg(color)));
+        let mark = if index == selected { '^' } else { ' ' };
+        caret.extend(std::iter::repeat_n(mark, columns));
+    }
+
+    let oldest = format_age(timeline.bucket_start(first).max(0) as u64);
+    let newest = format_age(timeline.bucket_start(total).max(0) as u64);
+    let gap = (shown * columns).saturating_sub(oldest.len() + newest.len());
+    vec![
+        Line::from(heat),
+        Line::from(Span::styled(caret, Style::default().fg(Color::White))),
+        Line::from(Span::styled(
+            format!("{oldest}{}{newest}", " ".repeat(gap)),
+            Style::default().fg(Color::DarkGray),
+        )),
+    ]
+}
+
+/// The anomalies of the selected bucket with their root causes.
+fn bucket_lines(timeline: &AnomalyTimeline, selected: usize) -> Vec<Line<'static>> {
+    let bucket = &timeline.buckets[selected];
+    let from = format_age(timeline.bucket_start(selected).max(0) as u64);
+    let to = format_age(timeline.bucket_start(selected + 1).max(0) as u64);
+    let mut lines = vec![Line::from(vec![
+        Span::styled(
+            format!("{from} to {to}: {} anomalies", bucket.len()),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        Span::styled("  (h/l: older/newer)", Style::default().fg(Color::DarkGray)),
+    ])];
+    for anomaly in bucket {
+        let severity = format!("{:?}", anomaly.severity).to_lowercase();
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("- [{severity}] "),
+                Style::default().fg(severity_color(anomaly.severity)),
+            ),
+            Span::raw(format!(
+                "{:?} on {}: {}",
+                anomaly.metric_type, anomaly.resource_id, anomaly.description
+            )),
+        ]));
+        let cause = anomaly.root_cause.as_deref().unwrap_or("unknown");
+        lines.push(Line::from(Span::styled(
+            format!("  root cause: {cause}"),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use phenome_domain::{Anomaly, MetricType, Severity};
+
+    use super::{AnomalyTimeline, worst};
+
+    fn anomaly(detected_at: i64, severity: Severity) -> Anomaly {
+        Anomaly {
+            id: format!("a-{detected_at}"),
+            cluster_id: String::new(),
+            resource_id: "api".to_string(),
+            detected_at,
+            metric_type: MetricType::CpuUsage,
+            severity,
+            confidence: 0.9,
+            description: String::new(),
+            baseline_value: 0.0,
+            observed_value: 0.0,
+            deviation_sigma: 0.0,
+            related_metrics: Vec::new(),
+            root_cause: None,
+        }
+    }
+
+    #[test]
+    fn buckets_span_oldest_to_newest() {
+        let anomalies = [
+            anomaly(1_000, Severity::Info),
+            anomaly(1_050, Severity::Critical),
+            anomaly(1_399, Severity::Warning),
+        ];
+        let timeline = AnomalyTimeline::new(&anomalies, 4);
+        let counts: Vec<usize> = timeline.buckets.iter().map(Vec::len).collect();
+        assert_eq!(counts, [2, 0, 0, 1]);
+        assert!(timeline.bucket_start(0) <= 1_000);
+        assert_eq!(worst(&timeline.buckets[0]), Some(Severity::Critical));
+        assert_eq!(worst(&timeline.buckets[1]), None);
+    }
+
+    #[test]
+    fn simultaneous_anomalies_share_the_last_bucket() {
+        let anomalies = [
+            anomaly(5_000, Severity::Info),
+            anomaly(5_000, Severity::Info),
+        ];
+        let timeline = AnomalyTimeline::new(&anomalies, 3);
+        assert_eq!(timeline.buckets[2].len(), 2);
+    }
 }
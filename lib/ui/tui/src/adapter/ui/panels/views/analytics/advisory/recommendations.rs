@@ -1,13 +1,15 @@
 use ratatui::{
-    layout::{Constraint, Rect},
+    layout::{Constraint, Layout, Rect},
     prelude::Frame,
     style::{Color, Modifier, Style, Stylize},
-    widgets::{Block, Borders, Cell, Padding, Row, Table},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Padding, Paragraph, Row, Table, TableState},
 };
 
 use crate::app::App;
 use crate::util::centered_rect;
 use phenome_domain::{Priority, RecommendationAction, RecommendationStatus};
+use phenome_ui_presentation::formatting::OnboardingPanel;
 
 pub fn render_recommendations(frame: &mut Frame, area: Rect, app: &mut App) {
     let recommendations = app
@@ -16,15 +18,20 @@ pub fn render_recommendations(frame: &mut Frame, area: Rect, app: &mut App) {
         .map(|recs| recs.as_slice())
         .unwrap_or_default();
 
+    let title = format!("Recommendations ({})", super::super::data_status(app));
     let block = Block::default()
         .borders(Borders::ALL)
-        .title("Recommendations")
+        .title(title)
         .padding(Padding::uniform(1));
 
     let inner_area = block.inner(area);
     frame.render_widget(block, area);
 
     if recommendations.is_empty() {
+        if let Some(hints) = super::super::onboarding(app, OnboardingPanel::Recommendations) {
+            frame.render_widget(hints, inner_area);
+            return;
+        }
         let msg = if app.analytics_recommendations.is_none() {
             "Waiting for data..."
         } else {
@@ -103,7 +110,37 @@ pub fn render_recommendations(frame: &mut Frame, area: Rect, app: &mut App) {
                 .underlined(),
         ),
     )
-    .row_highlight_style(Style::default().add_modifier(Modifier::BOLD));
+    .row_highlight_style(Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED));
 
-    frame.render_widget(table, inner_area);
+    let [table_area, footer_area] =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(inner_area);
+    let selected = app
+        .analytics_recommendation_index
+        .min(recommendations.len() - 1);
+    let mut state = TableState::default().with_selected(selected);
+    frame.render_stateful_widget(table, table_area, &mut state);
+    frame.render_widget(Paragraph::new(footer_line(app)), footer_area);
+}
+
+/// The dismissal prompt while it is open, otherwise the keys and why the
+/// selected recommendation was dismissed, if it was.
+fn footer_line(app: &App) -> Line<'static> {
+    let hint = Style::default().fg(Color::DarkGray);
+    if let Some(prompt) = &app.analytics_dismiss {
+        return Line::from(vec![
+            Span::raw(format!("Dismiss \"{}\" because: ", prompt.title)),
+            Span::styled(
+                format!("{}_", prompt.reason),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::styled("  (enter: dismiss, esc: cancel)", hint),
+        ]);
+    }
+    let mut spans = vec![Span::styled("j/k: select  A: apply  D: dismiss", hint)];
+    if let Some(RecommendationStatus::Dismissed { reason }) =
+        app.selected_recommendation().map(|rec| &rec.status)
+    {
+        spans.push(Span::styled(format!("  dismissed: {reason}"), hint));
+    }
+    Line::from(spans)
 }
@@ -1,5 +1,15 @@
 //! Analytics panel renderers.
 
+use ratatui::{
+    layout::Alignment,
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Paragraph, Wrap},
+};
+
+use crate::app::App;
+use phenome_ui_presentation::formatting::{AnalyticsData, OnboardingPanel, onboarding_hints};
+
 pub mod advisory;
 pub mod timeline;
 
@@ -8,3 +18,59 @@ pub use advisory::recommendations::render_recommendations;
 pub use timeline::historical::render_historical;
 pub use timeline::predictions::render_predictions;
 pub use timeline::realtime::render_realtime;
+
+/// Which cluster the analytics data covers and how fresh it is, for panel
+/// headers: "cluster prod, updated 3s ago, every 5s".
+pub(crate) fn data_status(app: &App) -> String {
+    let every = app.analytics_interval.as_secs();
+    let scope = scope(app);
+    match app.analytics_cache_timestamp {
+        Some(at) => {
+            let secs = at.elapsed().as_secs();
+            let age = if secs < 60 {
+                format!("{secs}s")
+            } else {
+                format!("{}m", secs / 60)
+            };
+            format!("{scope}, updated {age} ago, every {every}s")
+        }
+        None => format!("{scope}, not updated yet, every {every}s"),
+    }
+}
+
+/// The cluster the analytics views are scoped to: "cluster prod" or "all
+/// clusters".
+pub(crate) fn scope(app: &App) -> String {
+    match &app.analytics_cluster {
+        Some(cluster_id) => format!("cluster {cluster_id}"),
+        None => "all clusters".to_string(),
+    }
+}
+
+/// Onboarding hints for `panel` once the service has answered with no
+/// analytics data at all. Nothing while answers are still outstanding, as
+/// when connecting or after switching clusters.
+pub(crate) fn onboarding(app: &App, panel: OnboardingPanel) -> Option<Paragraph<'static>> {
+    let (Some(metrics), Some(anomalies), Some(recommendations)) = (
+        &app.analytics_metrics,
+        &app.analytics_anomalies,
+        &app.analytics_recommendations,
+    ) else {
+        return None;
+    };
+    let data = AnalyticsData {
+        metrics: metrics.len(),
+        anomalies: anomalies.len(),
+        recommendations: recommendations.len(),
+    };
+    let lines: Vec<Line> = onboarding_hints(data, panel)?
+        .into_iter()
+        .map(Line::from)
+        .collect();
+    Some(
+        Paragraph::new(lines)
+            .style(Style::default().fg(Color::DarkGray).italic())
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true }),
+    )
+}
@@ -1,5 +1,5 @@
 use ratatui::{
-    layout::Rect,
+    layout::{Constraint, Layout, Rect},
     prelude::Frame,
     style::{Color, Modifier, Style},
     text::{Line, Span},
@@ -7,28 +7,240 @@ use ratatui::{
 };
 
 use crate::app::App;
+use phenome_domain::{MetricSample, MetricType, TimeRange};
+use phenome_ui_presentation::formatting::OnboardingPanel;
+
+/// Block glyphs of a sparkline, lowest first.
+const SPARK_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
 
 pub fn render_historical(frame: &mut Frame, area: Rect, app: &mut App) {
+    if let Some(hints) = super::super::onboarding(app, OnboardingPanel::Historical) {
+        frame.render_widget(hints, area);
+        return;
+    }
+
     let mut lines = Vec::new();
-    lines.push(section_title("Historical Metrics"));
-    lines.push(Line::from(
-        "Time-series charts and CSV export are not yet connected.",
+    lines.push(Line::from(vec![
+        section_title("Historical Metrics"),
+        Span::styled(
+            format!("  {}", super::super::data_status(app)),
+            Style::default().fg(Color::DarkGray),
+        ),
+    ]));
+    lines.push(Line::from(format!(
+        "Window: {}  (t: change)",
+        app.analytics_history_window.label()
+    )));
+    lines.push(Line::from(""));
+
+    let samples = match &app.analytics_history {
+        None => {
+            lines.push(Line::from(Span::styled(
+                "Loading samples...",
+                Style::default().fg(Color::DarkGray),
+            )));
+            None
+        }
+        Some(Err(error)) => {
+            lines.push(Line::from(Span::styled(
+                format!("Failed to load samples: {error}"),
+                Style::default().fg(Color::Red),
+            )));
+            None
+        }
+        Some(Ok(samples)) => {
+            let count = match app.analytics_history_window.resolution() {
+                Some(resolution) => format!(
+                    "Samples: {} averages of {}m",
+                    samples.len(),
+                    resolution.as_secs() / 60
+                ),
+                None => format!("Samples: {}", samples.len()),
+            };
+            lines.push(Line::from(count));
+            Some(samples)
+        }
+    };
+    let (Some(samples), Some(range)) = (samples, app.analytics_history_range) else {
+        let paragraph = Paragraph::new(lines).wrap(Wrap { trim: true });
+        frame.render_widget(paragraph, area);
+        return;
+    };
+
+    let [header_area, cpu_area, memory_area, rest_area] = Layout::vertical([
+        Constraint::Length(lines.len() as u16 + 1),
+        Constraint::Length(3),
+        Constraint::Length(3),
+        Constraint::Min(0),
+    ])
+    .areas(area);
+    frame.render_widget(Paragraph::new(lines), header_area);
+    render_chart(frame, cpu_area, samples, MetricType::CpuUsage, range);
+    render_chart(frame, memory_area, samples, MetricType::MemoryUsage, range);
+
+    let rest: Vec<Line> = METRIC_TYPES
+        .into_iter()
+        .filter(|metric_type| !CHART_TYPES.contains(metric_type))
+        .filter_map(|metric_type| summary_line(samples, metric_type))
+        .collect();
+    frame.render_widget(Paragraph::new(rest).wrap(Wrap { trim: true }), rest_area);
+}
+
+/// Summary line of `metric_type` above a sparkline of it across `range`,
+/// one bucket per column.
+fn render_chart(
+    frame: &mut Frame,
+    area: Rect,
+    samples: &[MetricSample],
+    metric_type: MetricType,
+    range: TimeRange,
+) {
+    let Some(summary) = summary_line(samples, metric_type) else {
+        let empty = Line::from(Span::styled(
+            format!("{metric_type:?}: no samples"),
+            Style::default().fg(Color::DarkGray),
+        ));
+        frame.render_widget(Paragraph::new(empty), area);
+        return;
+    };
+    let buckets = bucket_averages(samples, metric_type, range, usize::from(area.width));
+    let chart = Line::from(Span::styled(
+        sparkline(&buckets),
+        Style::default().fg(Color::Cyan),
     ));
+    // No wrapping: trimming would shift a chart that starts with a gap.
+    frame.render_widget(Paragraph::new(vec![summary, chart]), area);
+}
 
-    let metrics = app
-        .analytics_metrics
-        .as_ref()
-        .map(|metrics| metrics.len())
-        .unwrap_or(0);
-    lines.push(Line::from(format!("Cached samples: {metrics}")));
+/// Average of the `metric_type` samples in each of `buckets` equal slices
+/// of `range`, `None` where a slice has no samples.
+pub(super) fn bucket_averages<'a>(
+    samples: impl IntoIterator<Item = &'a MetricSample>,
+    metric_type: MetricType,
+    range: TimeRange,
+    buckets: usize,
+) -> Vec<Option<f64>> {
+    let span = range.end_ms - range.start_ms;
+    if buckets == 0 || span <= 0 {
+        return Vec::new();
+    }
+    let mut sums = vec![(0.0, 0usize); buckets];
+    for sample in samples {
+        if sample.metric_type != metric_type
+            || sample.timestamp < range.start_ms
+            || sample.timestamp > range.end_ms
+        {
+            continue;
+        }
+        let offset = (sample.timestamp - range.start_ms) as i128;
+        let index = (offset * buckets as i128 / span as i128) as usize;
+        let (sum, count) = &mut sums[index.min(buckets - 1)];
+        *sum += sample.value;
+        *count += 1;
+    }
+    sums.into_iter()
+        .map(|(sum, count)| (count > 0).then(|| sum / count as f64))
+        .collect()
+}
 
-    let paragraph = Paragraph::new(lines).wrap(Wrap { trim: true });
-    frame.render_widget(paragraph, area);
+/// One bar per bucket scaled between the lowest and highest value, with a
+/// blank for each empty bucket rather than an interpolated bar.
+fn sparkline(buckets: &[Option<f64>]) -> String {
+    let values = buckets.iter().flatten().copied();
+    let min = values.clone().fold(f64::INFINITY, f64::min);
+    let max = values.fold(f64::NEG_INFINITY, f64::max);
+    let top = SPARK_BARS.len() - 1;
+    buckets
+        .iter()
+        .map(|bucket| match bucket {
+            None => ' ',
+            Some(_) if max <= min => SPARK_BARS[top / 2],
+            Some(value) => {
+                let level = ((value - min) / (max - min) * top as f64).round() as usize;
+                SPARK_BARS[level.min(top)]
+            }
+        })
+        .collect()
 }
 
-fn section_title(label: &'static str) -> Line<'static> {
-    Line::from(Span::styled(
+fn section_title(label: &'static str) -> Span<'static> {
+    Span::styled(
         label,
-        Style::default().fg(Color::LightBlue).add_modifier(Modifier::BOLD),
-    ))
+        Style::default()
+            .fg(Color::LightBlue)
+            .add_modifier(Modifier::BOLD),
+    )
+}
+
+const METRIC_TYPES: [MetricType; 6] = [
+    MetricType::CpuUsage,
+    MetricType::MemoryUsage,
+    MetricType::NetworkIn,
+    MetricType::NetworkOut,
+    MetricType::DiskRead,
+    MetricType::DiskWrite,
+];
+
+/// Metrics drawn as sparklines; the others only get a summary line.
+const CHART_TYPES: [MetricType; 2] = [MetricType::CpuUsage, MetricType::MemoryUsage];
+
+/// Count, min, average and max of the `metric_type` samples, if any.
+fn summary_line(samples: &[MetricSample], metric_type: MetricType) -> Option<Line<'static>> {
+    let values: Vec<f64> = samples
+        .iter()
+        .filter(|sample| sample.metric_type == metric_type)
+        .map(|sample| sample.value)
+        .collect();
+    if values.is_empty() {
+        return None;
+    }
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let avg = values.iter().sum::<f64>() / values.len() as f64;
+    Some(Line::from(format!(
+        "{metric_type:?}: {} samples  min {min:.2}  avg {avg:.2}  max {max:.2}",
+        values.len()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use phenome_domain::{ClusterId, MetricSample, MetricType, ResourceType, TimeRange};
+
+    use super::{bucket_averages, sparkline};
+
+    fn sample(metric_type: MetricType, timestamp: i64, value: f64) -> MetricSample {
+        MetricSample {
+            cluster_id: ClusterId::default(),
+            resource_type: ResourceType::Pod,
+            resource_id: "api".to_string(),
+            metric_type,
+            timestamp,
+            value,
+            unit: String::new(),
+        }
+    }
+
+    #[test]
+    fn empty_buckets_stay_blank() {
+        let range = TimeRange {
+            start_ms: 0,
+            end_ms: 400,
+        };
+        let samples = [
+            sample(MetricType::CpuUsage, 10, 1.0),
+            sample(MetricType::CpuUsage, 90, 3.0),
+            sample(MetricType::MemoryUsage, 150, 9.0),
+            sample(MetricType::CpuUsage, 399, 8.0),
+        ];
+        let buckets = bucket_averages(&samples, MetricType::CpuUsage, range, 4);
+        assert_eq!(buckets, [Some(2.0), None, None, Some(8.0)]);
+        assert_eq!(sparkline(&buckets), "▁  █");
+    }
+
+    #[test]
+    fn flat_series_draws_mid_bars() {
+        assert_eq!(sparkline(&[Some(5.0), None, Some(5.0)]), "▄ ▄");
+        assert_eq!(sparkline(&[None, None]), "  ");
+    }
 }
@@ -1,28 +1,368 @@
+use std::time::Duration;
+
 use ratatui::{
-    layout::Rect,
+    layout::{Constraint, Layout, Rect},
     prelude::Frame,
     style::{Color, Modifier, Style},
+    symbols::Marker,
     text::{Line, Span},
-    widgets::{Paragraph, Wrap},
+    widgets::canvas::{Canvas, Line as CanvasLine, Points},
+    widgets::{Block, Borders, Paragraph, Wrap},
 };
 
+use super::historical::bucket_averages;
 use crate::app::App;
+use phenome_domain::{MetricSample, ScalingPrediction, TimeRange};
+
+/// Rows a chart needs for its border and a readable trend.
+const MIN_CHART_HEIGHT: u16 = 6;
+
+pub fn render_predictions(frame: &mut Frame, area: Rect, app: &mut App) {
+    let mut title = Line::from(vec![
+        Span::styled(
+            "Predictions",
+            Style::default()
+                .fg(Color::LightBlue)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            format!(
+                "  fitted to the {}  (t: change)  {}",
+                app.analytics_history_window.label(),
+                super::super::data_status(app)
+            ),
+            Style::default().fg(Color::DarkGray),
+        ),
+    ]);
+
+    let (Some(Ok(samples)), Some(range), Some(Ok(predictions))) = (
+        &app.analytics_history,
+        app.analytics_history_range,
+        &app.analytics_predictions,
+    ) else {
+        render_placeholder(frame, area, title);
+        return;
+    };
+    if predictions.is_empty() {
+        render_placeholder(frame, area, title);
+        return;
+    }
+
+    // Resources heading for saturation first, soonest first; the rest keep
+    // the service's order by resource.
+    let mut ordered: Vec<&ScalingPrediction> = predictions.iter().collect();
+    ordered.sort_by_key(|prediction| {
+        (
+            prediction.time_to_threshold.is_none(),
+            prediction.time_to_threshold,
+        )
+    });
+
+    let [title_area, charts_area] =
+        Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(area);
+    let fits = usize::from(charts_area.height / MIN_CHART_HEIGHT).max(1);
+    let shown = ordered.len().min(fits);
+    if ordered.len() > shown {
+        title.spans.push(Span::styled(
+            format!("  +{} more", ordered.len() - shown),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    frame.render_widget(Paragraph::new(title), title_area);
+
+    let chart_areas = Layout::vertical(vec![Constraint::Fill(1); shown]).split(charts_area);
+    // One bucket per column inside the border.
+    let columns = usize::from(charts_area.width.saturating_sub(2)).max(1);
+    for (prediction, &chart_area) in ordered.iter().zip(chart_areas.iter()) {
+        let forecast = Forecast::new(samples, range, prediction, columns);
+        render_chart(frame, chart_area, prediction, &forecast);
+    }
+}
+
+fn render_placeholder(frame: &mut Frame, area: Rect, title: Line<'static>) {
+    let lines = vec![
+        title,
+        Line::from(""),
+        Line::from(Span::styled(
+            "No prediction data yet.",
+            Style::default().fg(Color::DarkGray),
+        )),
+        Line::from(Span::styled(
+            "Forecasts appear once the historical window holds a few samples of CPU or memory.",
+            Style::default().fg(Color::DarkGray),
+        )),
+    ];
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: true }), area);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ForecastPoint {
+    /// Timestamp in ms.
+    x: f64,
+    value: f64,
+    lower: f64,
+    upper: f64,
+}
+
+/// A prediction laid out for the canvas next to the samples it was fitted
+/// to.
+#[derive(Debug, Clone, PartialEq)]
+struct Forecast {
+    /// Bucket centers (ms) with their average, `None` for empty buckets.
+    actuals: Vec<(f64, Option<f64>)>,
+    /// From the last actual to the predicted value at the horizon, with the
+    /// band widening from nothing to the predicted bounds.
+    projection: Vec<ForecastPoint>,
+}
+
+impl Forecast {
+    /// Samples of the predicted resource and metric in `columns` buckets of
+    /// `range`, and the projection in as many steps.
+    fn new(
+        samples: &[MetricSample],
+        range: TimeRange,
+        prediction: &ScalingPrediction,
+        columns: usize,
+    ) -> Self {
+        let resource = samples
+            .iter()
+            .filter(|sample| sample.resource_id == prediction.resource_id);
+        let buckets = bucket_averages(resource, prediction.metric_type, range, columns);
+        let width = (range.end_ms - range.start_ms) as f64 / buckets.len().max(1) as f64;
+        let actuals: Vec<(f64, Option<f64>)> = buckets
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| (range.start_ms as f64 + (index as f64 + 0.5) * width, value))
+            .collect();
+
+        let (x0, y0) = actuals
+            .iter()
+            .rev()
+            .find_map(|&(x, value)| value.map(|value| (x, value)))
+            .unwrap_or((prediction.generated_at as f64, prediction.predicted_value));
+        let x1 = prediction.generated_at as f64 + prediction.horizon.as_millis() as f64;
+        let y1 = prediction.predicted_value;
+        let projection = (0..=columns)
+            .map(|step| {
+                let t = step as f64 / columns as f64;
+                let value = y0 + t * (y1 - y0);
+                ForecastPoint {
+                    x: x0 + t * (x1 - x0),
+                    value,
+                    lower: value + t * (prediction.lower_bound - y1),
+                    upper: value + t * (prediction.upper_bound - y1),
+                }
+            })
+            .collect();
 
-pub fn render_predictions(frame: &mut Frame, area: Rect, _app: &mut App) {
-    let mut lines = Vec::new();
-    lines.push(section_title("Predictions"));
-    lines.push(Line::from(
-        "Scaling predictions will appear once the ML service is available.",
-    ));
-    lines.push(Line::from("Horizons: 1h, 6h, 24h"));
+        Self {
+            actuals,
+            projection,
+        }
+    }
 
-    let paragraph = Paragraph::new(lines).wrap(Wrap { trim: true });
-    frame.render_widget(paragraph, area);
+    /// Lowest and highest value across actuals and the band.
+    fn bounds(&self) -> (f64, f64) {
+        let actual = self.actuals.iter().filter_map(|(_, value)| *value);
+        let lower = self.projection.iter().map(|point| point.lower);
+        let upper = self.projection.iter().map(|point| point.upper);
+        let min = actual.clone().chain(lower).fold(f64::INFINITY, f64::min);
+        let max = actual.chain(upper).fold(f64::NEG_INFINITY, f64::max);
+        (min, max)
+    }
 }
 
-fn section_title(label: &'static str) -> Line<'static> {
-    Line::from(Span::styled(
-        label,
-        Style::default().fg(Color::LightBlue).add_modifier(Modifier::BOLD),
-    ))
+/// Actuals in cyan running into the forecast in yellow, over the shaded
+/// confidence band.
+fn render_chart(
+    frame: &mut Frame,
+    area: Rect,
+    prediction: &ScalingPrediction,
+    forecast: &Forecast,
+) {
+    let mut title = vec![Span::raw(format!(
+        " {} {:?} ",
+        prediction.resource_id, prediction.metric_type
+    ))];
+    if let Some(ahead) = prediction.time_to_threshold {
+        title.push(Span::styled(
+            format!("full in {} ", rough_duration(ahead)),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    let x_min = forecast.actuals.first().map_or(0.0, |&(x, _)| x);
+    let x_max = forecast.projection.last().map_or(x_min, |point| point.x);
+    let (y_min, y_max) = forecast.bounds();
+    // Keeps a flat series off the border.
+    let pad = ((y_max - y_min) * 0.05).max(0.5);
+
+    let canvas = Canvas::default()
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::DarkGray))
+                .title(Line::from(title)),
+        )
+        .marker(Marker::Braille)
+        .x_bounds([x_min, x_max])
+        .y_bounds([y_min - pad, y_max + pad])
+        .paint(move |ctx| {
+            for point in &forecast.projection {
+                ctx.draw(&CanvasLine {
+                    x1: point.x,
+                    y1: point.lower,
+                    x2: point.x,
+                    y2: point.upper,
+                    color: Color::DarkGray,
+                });
+            }
+            ctx.layer();
+
+            // Only neighbouring buckets are joined, so gaps stay blank.
+            for pair in forecast.actuals.windows(2) {
+                if let [(x1, Some(y1)), (x2, Some(y2))] = *pair {
+                    ctx.draw(&CanvasLine {
+                        x1,
+                        y1,
+                        x2,
+                        y2,
+                        color: Color::Cyan,
+                    });
+                }
+            }
+            let lone = lone_points(&forecast.actuals);
+            ctx.draw(&Points {
+                coords: &lone,
+                color: Color::Cyan,
+            });
+
+            if let (Some(first), Some(last)) =
+                (forecast.projection.first(), forecast.projection.last())
+            {
+                ctx.draw(&CanvasLine {
+                    x1: first.x,
+                    y1: first.value,
+                    x2: last.x,
+                    y2: last.value,
+                    color: Color::Yellow,
+                });
+            }
+        });
+    frame.render_widget(canvas, area);
+}
+
+/// Filled buckets with empty neighbours on both sides, which no segment
+/// would otherwise show.
+fn lone_points(actuals: &[(f64, Option<f64>)]) -> Vec<(f64, f64)> {
+    let filled = |index: usize| actuals.get(index).is_some_and(|(_, value)| value.is_some());
+    actuals
+        .iter()
+        .enumerate()
+        .filter_map(|(index, &(x, value))| {
+            let value = value?;
+            let neighbour = (index > 0 && filled(index - 1)) || filled(index + 1);
+            (!neighbour).then_some((x, value))
+        })
+        .collect()
+}
+
+/// "~3d", "~5h" or "~20m", for annotations where precision would mislead.
+fn rough_duration(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    let hours = minutes / 60;
+    if hours >= 24 {
+        format!("~{}d", (hours + 12) / 24)
+    } else if hours > 0 {
+        format!("~{hours}h")
+    } else {
+        format!("~{}m", minutes.max(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use phenome_domain::{MetricSample, MetricType, ResourceType, ScalingPrediction, TimeRange};
+
+    use super::{Forecast, lone_points, rough_duration};
+
+    #[test]
+    fn only_isolated_buckets_become_points() {
+        let actuals = [
+            (0.0, Some(1.0)),
+            (1.0, None),
+            (2.0, Some(2.0)),
+            (3.0, Some(3.0)),
+            (4.0, None),
+            (5.0, Some(4.0)),
+        ];
+        assert_eq!(lone_points(&actuals), [(0.0, 1.0), (5.0, 4.0)]);
+    }
+
+    #[test]
+    fn forecast_runs_from_the_last_actual_of_its_resource_to_the_horizon() {
+        let sample = |resource_id: &str, timestamp: i64, value: f64| MetricSample {
+            cluster_id: "prod".to_string(),
+            resource_type: ResourceType::Pod,
+            resource_id: resource_id.to_string(),
+            metric_type: MetricType::CpuUsage,
+            timestamp,
+            value,
+            unit: "%".to_string(),
+        };
+        let samples = [
+            sample("web", 100, 40.0),
+            sample("web", 900, 60.0),
+            sample("api", 900, 5.0),
+        ];
+        let prediction = ScalingPrediction {
+            resource_id: "web".to_string(),
+            metric_type: MetricType::CpuUsage,
+            generated_at: 1_000,
+            horizon: Duration::from_millis(500),
+            predicted_value: 80.0,
+            lower_bound: 70.0,
+            upper_bound: 90.0,
+            unit: "%".to_string(),
+            time_to_threshold: None,
+        };
+        let range = TimeRange {
+            start_ms: 0,
+            end_ms: 1_000,
+        };
+        let forecast = Forecast::new(&samples, range, &prediction, 4);
+
+        assert_eq!(
+            forecast.actuals,
+            [
+                (125.0, Some(40.0)),
+                (375.0, None),
+                (625.0, None),
+                (875.0, Some(60.0)),
+            ]
+        );
+        let first = forecast.projection.first().unwrap();
+        assert_eq!(
+            (first.x, first.value, first.lower, first.upper),
+            (875.0, 60.0, 60.0, 60.0)
+        );
+        let last = forecast.projection.last().unwrap();
+        assert_eq!(
+            (last.x, last.value, last.lower, last.upper),
+            (1_500.0, 80.0, 70.0, 90.0)
+        );
+        assert_eq!(forecast.bounds(), (40.0, 90.0));
+    }
+
+    #[test]
+    fn rough_durations_round_to_one_unit() {
+        assert_eq!(
+            rough_duration(Duration::from_secs(3 * 86_400 - 3_600)),
+            "~3d"
+        );
+        assert_eq!(rough_duration(Duration::from_secs(5 * 3_600 + 59)), "~5h");
+        assert_eq!(rough_duration(Duration::from_secs(20)), "~1m");
+    }
 }
@@ -4,10 +4,13 @@ use ratatui::style::{Color, Modifier, Style, Stylize};
 use ratatui::widgets::{Block, Borders, Padding, Paragraph};
 
 use crate::app::App;
+use crate::app::core::AnalyticsConnection;
 use crate::util::centered_rect;
+use phenome_ui_presentation::formatting::OnboardingPanel;
 
 mod cards;
 mod format;
+mod resources;
 mod stats;
 
 pub fn render_realtime(frame: &mut Frame, area: Rect, app: &mut App) {
@@ -22,12 +25,17 @@ pub fn render_realtime(frame: &mut Frame, area: Rect, app: &mut App) {
         .constraints([
             Constraint::Length(3),
             Constraint::Length(10),
+            Constraint::Length(2),
             Constraint::Min(0),
         ])
         .split(area);
 
+    let title = match app.analytics_connection {
+        AnalyticsConnection::Reconnecting => "Real-time Metrics (reconnecting...)".to_string(),
+        _ => format!("Real-time Metrics  ({})", super::super::data_status(app)),
+    };
     frame.render_widget(
-        Paragraph::new("Real-time Metrics")
+        Paragraph::new(title)
             .style(
                 Style::default()
                     .fg(Color::Cyan)
@@ -38,8 +46,17 @@ pub fn render_realtime(frame: &mut Frame, area: Rect, app: &mut App) {
     );
 
     if app_metrics.is_empty() {
+        if let Some(hints) = super::super::onboarding(app, OnboardingPanel::Realtime) {
+            frame.render_widget(hints, centered_rect(70, 50, area));
+            return;
+        }
+        let waiting = match app.analytics_connection {
+            AnalyticsConnection::Connecting => "Connecting to analytics service...",
+            AnalyticsConnection::Connected => "Waiting for metrics stream...",
+            AnalyticsConnection::Reconnecting => "Reconnecting to analytics service...",
+        };
         frame.render_widget(
-            Paragraph::new("Waiting for metrics stream...")
+            Paragraph::new(waiting)
                 .style(Style::default().fg(Color::DarkGray).italic())
                 .alignment(Alignment::Center),
             centered_rect(50, 50, area),
@@ -89,4 +106,12 @@ pub fn render_realtime(frame: &mut Frame, area: Rect, app: &mut App) {
             .block(Block::default().padding(Padding::top(1))),
         chunks[2],
     );
+
+    resources::render_resource_table(
+        frame,
+        chunks[3],
+        app_metrics,
+        app.analytics_resource_sort,
+        &mut app.analytics_resource_scroll,
+    );
 }
@@ -0,0 +1,104 @@
+use ratatui::layout::{Constraint, Rect};
+use ratatui::prelude::Frame;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, Borders, Cell, Row, Table};
+
+use super::format::format_bytes;
+use super::stats::{ResourceUsage, Trend, resource_usage};
+use crate::app::core::ResourceSort;
+use phenome_domain::MetricSample;
+
+/// Resources listed at most, busiest first.
+const TOP_RESOURCES: usize = 50;
+
+/// Table of the busiest resources by `sort`, scrolled by j/k. `scroll` is
+/// clamped to the last page.
+pub(super) fn render_resource_table(
+    frame: &mut Frame,
+    area: Rect,
+    metrics: &[MetricSample],
+    sort: ResourceSort,
+    scroll: &mut usize,
+) {
+    // The busiest resources make the cut even when listed by name.
+    let rank = match sort {
+        ResourceSort::Name => ResourceSort::Cpu,
+        other => other,
+    };
+    let mut rows = resource_usage(metrics, rank);
+    rows.truncate(TOP_RESOURCES);
+    if sort == ResourceSort::Name {
+        rows.sort_by(|a, b| a.resource_id.cmp(&b.resource_id));
+    }
+
+    // Borders and the header take three rows.
+    let visible = usize::from(area.height.saturating_sub(3));
+    let max_scroll = rows.len().saturating_sub(visible);
+    *scroll = (*scroll).min(max_scroll);
+
+    let sorted_column = match sort {
+        ResourceSort::Name => 0,
+        ResourceSort::Cpu => 2,
+        ResourceSort::Memory => 3,
+    };
+    let header = ["Resource", "Type", "CPU", "Memory"]
+        .into_iter()
+        .enumerate()
+        .map(|(index, label)| {
+            if index == sorted_column {
+                format!("{label} ▼")
+            } else {
+                label.to_string()
+            }
+        });
+
+    let table = Table::new(
+        rows.iter().skip(*scroll).map(resource_row),
+        [
+            Constraint::Percentage(40),
+            Constraint::Percentage(14),
+            Constraint::Percentage(20),
+            Constraint::Percentage(26),
+        ],
+    )
+    .header(
+        Row::new(header).style(
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        ),
+    )
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::DarkGray))
+            .title(format!(
+                " Top resources by {}  (s: sort, j/k: scroll) ",
+                sort.label()
+            )),
+    );
+    frame.render_widget(table, area);
+}
+
+fn resource_row(usage: &ResourceUsage) -> Row<'static> {
+    let cpu = usage
+        .cpu
+        .map_or_else(|| "-".to_string(), |cpu| format!("{cpu:.2} cores"));
+    let memory = usage.memory.map_or_else(|| "-".to_string(), format_bytes);
+    Row::new(vec![
+        Cell::from(usage.resource_id.clone()),
+        Cell::from(format!("{:?}", usage.resource_type).to_lowercase()),
+        Cell::from(format!("{cpu} {}", usage.cpu_trend.arrow()))
+            .style(trend_style(usage.cpu_trend)),
+        Cell::from(format!("{memory} {}", usage.memory_trend.arrow()))
+            .style(trend_style(usage.memory_trend)),
+    ])
+}
+
+fn trend_style(trend: Trend) -> Style {
+    match trend {
+        Trend::Up => Style::default().fg(Color::LightRed),
+        Trend::Down => Style::default().fg(Color::LightGreen),
+        Trend::Flat | Trend::Unknown => Style::default(),
+    }
+}
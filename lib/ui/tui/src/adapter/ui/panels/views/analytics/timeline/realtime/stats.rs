@@ -1,3 +1,7 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use crate::app::core::ResourceSort;
 use phenome_domain::{MetricSample, MetricType, ResourceType};
 
 pub(super) struct MetricTotals {
@@ -36,21 +40,190 @@ pub(super) fn aggregate_metrics(metrics: &[MetricSample]) -> MetricTotals {
 }
 
 pub(super) fn build_info(metrics: &[MetricSample]) -> String {
-    let pod_count = metrics
-        .iter()
-        .filter(|s| matches!(s.resource_type, ResourceType::Pod))
-        .count()
-        / 2;
-    let node_count = metrics
-        .iter()
-        .filter(|s| matches!(s.resource_type, ResourceType::Node))
-        .count()
-        / 2;
+    let count = |resource_type: ResourceType| {
+        metrics
+            .iter()
+            .filter(|sample| sample.resource_type == resource_type)
+            .map(|sample| (&sample.cluster_id, &sample.resource_id))
+            .collect::<HashSet<_>>()
+            .len()
+    };
 
     format!(
         "Samples: {} | Pods: {} | Nodes: {}",
         metrics.len(),
-        pod_count,
-        node_count
+        count(ResourceType::Pod),
+        count(ResourceType::Node)
     )
 }
+
+/// Direction between a resource's last two samples of a metric.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Trend {
+    Up,
+    Down,
+    Flat,
+    /// Fewer than two samples.
+    Unknown,
+}
+
+impl Trend {
+    pub(super) fn arrow(self) -> &'static str {
+        match self {
+            Trend::Up => "↑",
+            Trend::Down => "↓",
+            Trend::Flat => "→",
+            Trend::Unknown => " ",
+        }
+    }
+}
+
+/// Latest CPU and memory of one resource.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct ResourceUsage {
+    pub(super) resource_type: ResourceType,
+    pub(super) resource_id: String,
+    pub(super) cpu: Option<f64>,
+    pub(super) cpu_trend: Trend,
+    pub(super) memory: Option<f64>,
+    pub(super) memory_trend: Trend,
+}
+
+/// Per-resource usage sorted by `sort`, resources without the sorted metric
+/// last.
+pub(super) fn resource_usage(metrics: &[MetricSample], sort: ResourceSort) -> Vec<ResourceUsage> {
+    let mut series: HashMap<(&str, ResourceType, &str), [Vec<&MetricSample>; 2]> = HashMap::new();
+    for sample in metrics {
+        let slot = match sample.metric_type {
+            MetricType::CpuUsage => 0,
+            MetricType::MemoryUsage => 1,
+            _ => continue,
+        };
+        series
+            .entry((
+                sample.cluster_id.as_str(),
+                sample.resource_type,
+                sample.resource_id.as_str(),
+            ))
+            .or_default()[slot]
+            .push(sample);
+    }
+
+    let mut rows: Vec<ResourceUsage> = series
+        .into_iter()
+        .map(|((_, resource_type, resource_id), [cpu, memory])| {
+            let (cpu, cpu_trend) = latest(cpu);
+            let (memory, memory_trend) = latest(memory);
+            ResourceUsage {
+                resource_type,
+                resource_id: resource_id.to_string(),
+                cpu,
+                cpu_trend,
+                memory,
+                memory_trend,
+            }
+        })
+        .collect();
+    let descending = |a: Option<f64>, b: Option<f64>| match (a, b) {
+        (Some(a), Some(b)) => b.total_cmp(&a),
+        (a, b) => b.is_some().cmp(&a.is_some()),
+    };
+    rows.sort_by(|a, b| {
+        let order = match sort {
+            ResourceSort::Cpu => descending(a.cpu, b.cpu),
+            ResourceSort::Memory => descending(a.memory, b.memory),
+            ResourceSort::Name => Ordering::Equal,
+        };
+        order.then_with(|| a.resource_id.cmp(&b.resource_id))
+    });
+    rows
+}
+
+/// Newest value of `samples` and how it moved from the one before.
+fn latest(mut samples: Vec<&MetricSample>) -> (Option<f64>, Trend) {
+    samples.sort_by_key(|sample| sample.timestamp);
+    match samples.as_slice() {
+        [] => (None, Trend::Unknown),
+        [only] => (Some(only.value), Trend::Unknown),
+        [.., previous, last] => {
+            let trend = match last.value.total_cmp(&previous.value) {
+                Ordering::Greater => Trend::Up,
+                Ordering::Less => Trend::Down,
+                Ordering::Equal => Trend::Flat,
+            };
+            (Some(last.value), trend)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use phenome_domain::{MetricSample, MetricType, ResourceType};
+
+    use super::{ResourceUsage, Trend, build_info, resource_usage};
+    use crate::app::core::ResourceSort;
+
+    fn ids(rows: &[ResourceUsage]) -> Vec<&str> {
+        rows.iter().map(|row| row.resource_id.as_str()).collect()
+    }
+
+    fn sample(
+        resource_type: ResourceType,
+        resource_id: &str,
+        metric_type: MetricType,
+        timestamp: i64,
+        value: f64,
+    ) -> MetricSample {
+        MetricSample {
+            cluster_id: "c1".to_string(),
+            resource_type,
+            resource_id: resource_id.to_string(),
+            metric_type,
+            timestamp,
+            value,
+            unit: String::new(),
+        }
+    }
+
+    fn metrics() -> Vec<MetricSample> {
+        use MetricType::{CpuUsage, MemoryUsage, NetworkIn};
+        use ResourceType::{Node, Pod};
+        vec![
+            sample(Pod, "api", CpuUsage, 2, 0.5),
+            sample(Pod, "api", CpuUsage, 1, 0.2),
+            sample(Pod, "api", MemoryUsage, 1, 100.0),
+            sample(Pod, "db", CpuUsage, 1, 1.5),
+            sample(Pod, "db", CpuUsage, 2, 1.0),
+            sample(Pod, "db", MemoryUsage, 1, 300.0),
+            sample(Pod, "db", MemoryUsage, 2, 300.0),
+            sample(Pod, "web", NetworkIn, 1, 7.0),
+            sample(Node, "node-1", MemoryUsage, 1, 900.0),
+        ]
+    }
+
+    #[test]
+    fn counts_distinct_resources() {
+        assert_eq!(build_info(&metrics()), "Samples: 9 | Pods: 3 | Nodes: 1");
+    }
+
+    #[test]
+    fn rows_carry_latest_value_and_trend() {
+        let rows = resource_usage(&metrics(), ResourceSort::Cpu);
+        assert_eq!(ids(&rows), ["db", "api", "node-1"]);
+        assert_eq!(rows[0].cpu, Some(1.0));
+        assert_eq!(rows[0].cpu_trend, Trend::Down);
+        assert_eq!(rows[0].memory_trend, Trend::Flat);
+        assert_eq!(rows[1].cpu_trend, Trend::Up);
+        assert_eq!(rows[1].memory_trend, Trend::Unknown);
+        assert_eq!(rows[2].cpu, None);
+    }
+
+    #[test]
+    fn sorts_by_memory_or_name() {
+        let by_memory = resource_usage(&metrics(), ResourceSort::Memory);
+        assert_eq!(ids(&by_memory), ["node-1", "db", "api"]);
+
+        let by_name = resource_usage(&metrics(), ResourceSort::Name);
+        assert_eq!(ids(&by_name), ["api", "db", "node-1"]);
+    }
+}
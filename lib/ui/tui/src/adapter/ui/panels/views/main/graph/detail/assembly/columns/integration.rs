@@ -7,9 +7,9 @@ use ratatui::widgets::{Paragraph, Wrap};
 use crate::app::App;
 use phenome_domain::AssemblyStep;
 
-use super::access::{gather_ingress_urls, gather_ip_info};
+use super::access::gather_ip_info;
 use super::ProvisionSets;
-use super::super::super::helpers::{classify_dependency, DepCategory};
+use crate::util::dependency_icon;
 
 pub(super) fn render_integration(
     frame: &mut Frame,
@@ -33,7 +33,7 @@ pub(super) fn render_integration(
             .add_modifier(Modifier::BOLD),
     )));
 
-    let ingress_urls = gather_ingress_urls(app, step);
+    let ingress_urls = app.step_access_urls(&step.id);
     let ip_info = gather_ip_info(app, step);
     let mut access_shown = false;
 
@@ -59,12 +59,21 @@ pub(super) fn render_integration(
     lines.push(Line::from(""));
 
     if !provisions.admin_creds.is_empty() {
+        let reveal = app.ui.reveal_credentials;
+        let title = if reveal {
+            "🔑 Admin Access:"
+        } else {
+            "🔑 Admin Access (v: reveal):"
+        };
         lines.push(Line::from(Span::styled(
-            "🔑 Admin Access:",
+            title,
             Style::default().fg(Color::LightYellow),
         )));
         for entry in &provisions.admin_creds {
-            lines.push(Line::from(format!("  ➢ {entry}")));
+            lines.push(Line::from(format!(
+                "  ➢ {}",
+                credential_hint_text(entry, reveal)
+            )));
         }
         lines.push(Line::from(""));
     }
@@ -80,14 +89,7 @@ pub(super) fn render_integration(
         )));
     } else {
         for dep in &step.depends_on {
-            let icon = match classify_dependency(dep) {
-                DepCategory::Security => "🔒",
-                DepCategory::Database => "🔌",
-                DepCategory::Storage => "💾",
-                DepCategory::Network => "🌐",
-                DepCategory::Infrastructure => "🏗️",
-                DepCategory::Other => "📦",
-            };
+            let icon = dependency_icon(app.dependency_classifier.classify(dep));
             lines.push(Line::from(format!("  {icon} {dep}")));
         }
     }
@@ -97,3 +99,12 @@ pub(super) fn render_integration(
         .scroll((app.ui.detail_scroll, 0));
     frame.render_widget(paragraph, area);
 }
+
+/// Text shown for a credential-flagged provide; masked unless revealed.
+pub(super) fn credential_hint_text(entry: &str, reveal: bool) -> String {
+    if reveal {
+        entry.to_string()
+    } else {
+        "•".repeat(entry.chars().count().clamp(4, 12))
+    }
+}
@@ -19,7 +19,7 @@ pub(super) fn render_columns(frame: &mut Frame, area: Rect, app: &App, step: &As
         ])
         .split(area);
 
-    let provisions = split_provisions(step);
+    let provisions = split_provisions(step, app.ui.credential_hints);
     metadata::render_metadata(frame, chunks[0], app, step);
     integration::render_integration(frame, chunks[1], app, step, &provisions);
     capabilities::render_capabilities(frame, chunks[2], app, &provisions);
@@ -30,21 +30,12 @@ pub(super) struct ProvisionSets<'a> {
     pub(super) other_provs: Vec<&'a str>,
 }
 
-fn split_provisions(step: &AssemblyStep) -> ProvisionSets<'_> {
+fn split_provisions(step: &AssemblyStep, credential_hints: bool) -> ProvisionSets<'_> {
     let mut admin_creds = Vec::new();
     let mut other_provs = Vec::new();
 
     for prov in &step.provides {
-        let p_lower = prov.to_lowercase();
-        if p_lower.contains("admin")
-            || p_lower.contains("password")
-            || p_lower.contains("cred")
-            || p_lower.contains("login")
-            || p_lower.contains("user")
-            || p_lower.contains("token")
-            || p_lower.contains("secret")
-            || p_lower.contains("key")
-        {
+        if credential_hints && is_credential_hint(prov) {
             admin_creds.push(prov.as_str());
         } else {
             other_provs.push(prov.as_str());
@@ -56,3 +47,57 @@ fn split_provisions(step: &AssemblyStep) -> ProvisionSets<'_> {
         other_provs,
     }
 }
+
+fn is_credential_hint(prov: &str) -> bool {
+    let p_lower = prov.to_lowercase();
+    p_lower.contains("admin")
+        || p_lower.contains("password")
+        || p_lower.contains("cred")
+        || p_lower.contains("login")
+        || p_lower.contains("user")
+        || p_lower.contains("token")
+        || p_lower.contains("secret")
+        || p_lower.contains("key")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::integration::credential_hint_text;
+    use super::split_provisions;
+    use phenome_domain::{AssemblyStep, AssemblyStepStatus};
+
+    fn step_with_provides(provides: &[&str]) -> AssemblyStep {
+        AssemblyStep {
+            id: "grafana".to_string(),
+            kind: "helm".to_string(),
+            depends_on: Vec::new(),
+            provides: provides.iter().map(|p| p.to_string()).collect(),
+            status: AssemblyStepStatus::Succeeded,
+            domain: "observability".to_string(),
+            pod: None,
+        }
+    }
+
+    #[test]
+    fn credential_hints_are_masked_until_revealed() {
+        let step = step_with_provides(&["grafana-admin-password", "dashboards"]);
+        let provisions = split_provisions(&step, true);
+        assert_eq!(provisions.admin_creds, vec!["grafana-admin-password"]);
+        assert_eq!(provisions.other_provs, vec!["dashboards"]);
+
+        let masked = credential_hint_text("grafana-admin-password", false);
+        assert!(!masked.contains("grafana-admin-password"));
+        assert!(masked.chars().all(|c| c == '•'));
+
+        let revealed = credential_hint_text("grafana-admin-password", true);
+        assert_eq!(revealed, "grafana-admin-password");
+    }
+
+    #[test]
+    fn disabled_heuristic_keeps_all_provides_as_capabilities() {
+        let step = step_with_provides(&["grafana-admin-password", "dashboards"]);
+        let provisions = split_provisions(&step, false);
+        assert!(provisions.admin_creds.is_empty());
+        assert_eq!(provisions.other_provs.len(), 2);
+    }
+}
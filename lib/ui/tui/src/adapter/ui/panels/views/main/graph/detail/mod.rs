@@ -7,7 +7,6 @@ use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
 use crate::app::App;
 
 mod assembly;
-mod helpers;
 mod registry;
 
 pub(super) fn render_detail_sidebar(frame: &mut Frame, area: Rect, app: &mut App) {
@@ -6,7 +6,7 @@ use ratatui::widgets::{Paragraph, Wrap};
 
 use crate::app::App;
 
-use super::helpers::{DepCategory, classify_dependency};
+use crate::util::dependency_icon;
 
 pub(super) fn render_registry_detail(
     frame: &mut Frame,
@@ -64,14 +64,7 @@ pub(super) fn render_registry_detail(
         lines.push(Line::from("  (None)"));
     } else {
         for req in spec.required.iter() {
-            let icon = match classify_dependency(req) {
-                DepCategory::Security => "🔒",
-                DepCategory::Database => "🔌",
-                DepCategory::Storage => "💾",
-                DepCategory::Infrastructure => "🏗️",
-                DepCategory::Network => "🌐",
-                DepCategory::Other => "📦",
-            };
+            let icon = dependency_icon(app.dependency_classifier.classify(req));
             lines.push(Line::from(format!("  {icon} reg:{req}")));
         }
     }
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use ratatui::layout::Rect;
 use ratatui::prelude::Frame;
 use ratatui::style::Color;
@@ -7,6 +9,12 @@ use ratatui::widgets::canvas::{Canvas, Line, Rectangle};
 use crate::app::graph::GraphLayout;
 use crate::app::App;
 
+use phenome_domain::AssemblyStepStatus;
+
+/// Arrowhead barb length in Braille dots and its angle off the edge.
+const ARROW_DOTS: f64 = 3.0;
+const ARROW_ANGLE: f64 = 0.5;
+
 pub(super) fn render_canvas(frame: &mut Frame, area: Rect, app: &App, layout: &GraphLayout) {
     let bounds = app.graph.view_bounds_for(layout, area);
     let selected = app.graph.selected_id();
@@ -15,6 +23,18 @@ pub(super) fn render_canvas(frame: &mut Frame, area: Rect, app: &App, layout: &G
         .unwrap_or_default();
     let selected_id = selected.map(|id| id.to_string());
     let image_active = app.graph.image_active();
+    let statuses: HashMap<&str, AssemblyStepStatus> = app
+        .runtime
+        .snapshot()
+        .assembly_steps
+        .iter()
+        .map(|step| (step.id.as_str(), step.status))
+        .collect();
+    // Layout units per Braille dot; a cell is two dots wide, four tall.
+    let dot_size = (
+        (bounds.x_max - bounds.x_min) / (f64::from(area.width.max(1)) * 2.0),
+        (bounds.y_max - bounds.y_min) / (f64::from(area.height.max(1)) * 4.0),
+    );
 
     let canvas = Canvas::default()
         .marker(Marker::Braille)
@@ -39,16 +59,26 @@ pub(super) fn render_canvas(frame: &mut Frame, area: Rect, app: &App, layout: &G
                             color,
                         });
                     }
+                    if let Some((tip, barbs)) = arrowhead(&edge.points, dot_size) {
+                        for barb in barbs {
+                            ctx.draw(&Line {
+                                x1: tip.0,
+                                y1: tip.1,
+                                x2: barb.0,
+                                y2: barb.1,
+                                color,
+                            });
+                        }
+                    }
                 }
 
                 for (i, node) in layout.nodes.iter().enumerate() {
-                    let color = if selected_id.as_deref() == Some(node.id.as_str()) {
-                        Color::Yellow
-                    } else if dependency.nodes.contains(&i) {
-                        Color::Cyan
-                    } else {
-                        Color::Blue
-                    };
+                    let color = node_color(
+                        selected_id.as_deref() == Some(node.id.as_str()),
+                        app.graph.is_search_match(i),
+                        dependency.nodes.contains(&i),
+                        statuses.get(node.id.as_str()).copied(),
+                    );
 
                     let rect = Rectangle {
                         x: node.x - node.width / 2.0,
@@ -75,3 +105,95 @@ pub(super) fn render_canvas(frame: &mut Frame, area: Rect, app: &App, layout: &G
 
     frame.render_widget(canvas, area);
 }
+
+/// Tip and barb ends of an arrowhead at the head end of an edge, sized in
+/// Braille dots so it looks the same at any zoom. `None` for a degenerate
+/// edge without a direction.
+fn arrowhead(points: &[(f64, f64)], dot_size: (f64, f64)) -> Option<((f64, f64), [(f64, f64); 2])> {
+    let (&tip, rest) = points.split_last()?;
+    // Splines may repeat their end point; walk back to one that differs.
+    let &from = rest.iter().rev().find(|&&point| point != tip)?;
+    let dx = (tip.0 - from.0) / dot_size.0;
+    let dy = (tip.1 - from.1) / dot_size.1;
+    let length = dx.hypot(dy);
+    if !length.is_finite() || length == 0.0 {
+        return None;
+    }
+    let (back_x, back_y) = (-dx / length * ARROW_DOTS, -dy / length * ARROW_DOTS);
+    let barb = |angle: f64| {
+        let (sin, cos) = angle.sin_cos();
+        (
+            tip.0 + (back_x * cos - back_y * sin) * dot_size.0,
+            tip.1 + (back_x * sin + back_y * cos) * dot_size.1,
+        )
+    };
+    Some((tip, [barb(ARROW_ANGLE), barb(-ARROW_ANGLE)]))
+}
+
+/// Selection, search and dependency highlights win over the step status;
+/// nodes without an assembly step keep the plain color.
+fn node_color(
+    selected: bool,
+    matched: bool,
+    on_path: bool,
+    status: Option<AssemblyStepStatus>,
+) -> Color {
+    if selected {
+        return Color::Yellow;
+    }
+    if matched {
+        return Color::Magenta;
+    }
+    if on_path {
+        return Color::Cyan;
+    }
+    match status {
+        Some(AssemblyStepStatus::Succeeded) => Color::Green,
+        Some(AssemblyStepStatus::Blocked) => Color::Yellow,
+        Some(AssemblyStepStatus::Failed) => Color::Red,
+        Some(AssemblyStepStatus::Pending) => Color::Gray,
+        Some(AssemblyStepStatus::Running) | None => Color::Blue,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::Color;
+
+    use phenome_domain::AssemblyStepStatus;
+
+    use super::{arrowhead, node_color};
+
+    #[test]
+    fn highlights_take_precedence_over_status() {
+        let failed = Some(AssemblyStepStatus::Failed);
+        assert_eq!(node_color(true, true, true, failed), Color::Yellow);
+        assert_eq!(node_color(false, true, true, failed), Color::Magenta);
+        assert_eq!(node_color(false, false, true, failed), Color::Cyan);
+        assert_eq!(node_color(false, false, false, failed), Color::Red);
+    }
+
+    #[test]
+    fn arrowhead_points_back_along_last_segment() {
+        let points = [(0.0, 0.0), (4.0, 0.0), (10.0, 0.0), (10.0, 0.0)];
+        let (tip, barbs) = arrowhead(&points, (0.5, 0.25)).unwrap();
+        assert_eq!(tip, (10.0, 0.0));
+        for barb in barbs {
+            assert!(barb.0 < tip.0);
+            assert!(tip.0 - barb.0 < 3.0 * 0.5 + 1e-9);
+        }
+        assert!(barbs[0].1 * barbs[1].1 < 0.0);
+
+        assert!(arrowhead(&[(1.0, 1.0), (1.0, 1.0)], (0.5, 0.25)).is_none());
+        assert!(arrowhead(&[(1.0, 1.0)], (0.5, 0.25)).is_none());
+    }
+
+    #[test]
+    fn status_colors_plain_nodes() {
+        let color = |status| node_color(false, false, false, Some(status));
+        assert_eq!(color(AssemblyStepStatus::Succeeded), Color::Green);
+        assert_eq!(color(AssemblyStepStatus::Blocked), Color::Yellow);
+        assert_eq!(color(AssemblyStepStatus::Pending), Color::Gray);
+        assert_eq!(node_color(false, false, false, None), Color::Blue);
+    }
+}
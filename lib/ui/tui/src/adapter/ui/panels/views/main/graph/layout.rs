@@ -2,12 +2,14 @@ use std::collections::HashMap;
 
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
 use ratatui::prelude::Frame;
-use ratatui::text::Line;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
 use ratatui::widgets::{Paragraph, Wrap};
 
-use crate::app::App;
+use crate::app::{App, GraphEdgeDependency, GraphvizProbe};
 use crate::panels::views::main::shared::section_title;
 use primer::application::flows::reconcile::visualize;
+use primer::domain::models::assembly::Assembly;
 
 pub(super) fn prepare_graph(
     app: &mut App,
@@ -36,15 +38,54 @@ pub(super) fn prepare_graph(
 
     let index_map: HashMap<_, _> = node_map.iter().map(|(k, v)| (*v, k.clone())).collect();
     let dot = visualize::render::generate_pretty_dot(&graph, &index_map);
+    let dot = app.graph.collapse_dot(dot, || {
+        app.runtime
+            .snapshot()
+            .assembly_steps
+            .iter()
+            .map(|step| (step.id.clone(), step.domain.clone()))
+            .collect()
+    });
+    let dot = app.graph.isolate_dot(dot);
 
-    if let Err(error) = app.graph.ensure_layout(&dot) {
-        app.graph.mark_layout_failed(error.to_string());
-    }
+    let dependencies = edge_dependencies(app.context.ports.bootstrap.dependency_graph());
+    app.graph.set_edge_dependencies(dependencies);
+    app.graph.ensure_layout(&dot);
+    app.graph.apply_initial_fit(graph_area);
 
     app.graph.queue_request(graph_area, dot.clone());
     (graph_area, sidebar_area, dot)
 }
 
+/// Every step requiring another, with the capabilities it requires that
+/// the other provides.
+fn edge_dependencies(assembly: &Assembly) -> Vec<GraphEdgeDependency> {
+    let mut dependencies = Vec::new();
+    for dependent in &assembly.steps {
+        for provider in &assembly.steps {
+            if provider.id == dependent.id {
+                continue;
+            }
+            let capabilities: Vec<String> = dependent
+                .required
+                .iter()
+                .filter(|required| provider.provides.contains(required))
+                .cloned()
+                .collect();
+            // A step may also name the step it needs rather than a capability.
+            if capabilities.is_empty() && !dependent.required.contains(&provider.id) {
+                continue;
+            }
+            dependencies.push(GraphEdgeDependency {
+                dependent: dependent.id.clone(),
+                provider: provider.id.clone(),
+                capabilities,
+            });
+        }
+    }
+    dependencies
+}
+
 pub(super) fn render_dot_fallback(
     frame: &mut Frame,
     area: Rect,
@@ -52,9 +93,17 @@ pub(super) fn render_dot_fallback(
     label: &str,
     dot: &str,
 ) {
+    let probe = app.graph.graphviz_probe();
+    if !matches!(probe, GraphvizProbe::Found(_)) {
+        render_without_graphviz(frame, area, app, label, &probe);
+        return;
+    }
     let mut lines = Vec::new();
     lines.push(section_title(label));
-    if let Some(error) = app.graph.layout_error() {
+    if app.graph.layout_pending() {
+        lines.push(Line::from("Computing layout..."));
+        lines.push(Line::from(""));
+    } else if let Some(error) = app.graph.layout_error() {
         lines.push(Line::from(format!("Interactive layout failed: {error}")));
         lines.push(Line::from(""));
     }
@@ -66,3 +115,69 @@ pub(super) fn render_dot_fallback(
         .scroll((app.ui.assembly_scroll, 0));
     frame.render_widget(paragraph, area);
 }
+
+/// Install hint plus the plain dependency list, so the view stays useful
+/// without graphviz. Graphviz is probed again every few seconds, so the graph
+/// appears once it is installed.
+fn render_without_graphviz(
+    frame: &mut Frame,
+    area: Rect,
+    app: &App,
+    label: &str,
+    probe: &GraphvizProbe,
+) {
+    let hint = Style::default().fg(Color::DarkGray);
+    let warning = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+    let mut lines = vec![section_title(label)];
+    if let GraphvizProbe::Failed(reason) = probe {
+        lines.push(Line::from(Span::styled(
+            "Graphviz is installed but `dot -V` failed, so the graph can't be laid out:",
+            warning,
+        )));
+        lines.push(Line::from(Span::styled(format!("  {reason}"), hint)));
+        lines.push(Line::from(
+            "Repair or reinstall it; the graph appears once it runs:",
+        ));
+    } else {
+        lines.push(Line::from(Span::styled(
+            "Graphviz is not installed, so the graph can't be laid out.",
+            warning,
+        )));
+        lines.push(Line::from(
+            "Install it; the graph appears once it is found:",
+        ));
+    }
+    lines.extend([
+        Line::from(Span::styled(
+            "  Debian/Ubuntu: sudo apt install graphviz",
+            hint,
+        )),
+        Line::from(Span::styled(
+            "  Fedora:        sudo dnf install graphviz",
+            hint,
+        )),
+        Line::from(Span::styled("  macOS:         brew install graphviz", hint)),
+        Line::from(""),
+        section_title("Dependencies"),
+    ]);
+    for step in &app.context.ports.bootstrap.dependency_graph().steps {
+        let requires = if step.required.is_empty() {
+            "-".to_string()
+        } else {
+            step.required.join(", ")
+        };
+        lines.push(Line::from(vec![
+            Span::styled(
+                step.id.clone(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(format!("  <- {requires}"), hint),
+        ]));
+    }
+    let paragraph = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .scroll((app.ui.assembly_scroll, 0));
+    frame.render_widget(paragraph, area);
+}
@@ -0,0 +1,93 @@
+use ratatui::layout::Rect;
+use ratatui::prelude::Frame;
+use ratatui::style::{Color, Style};
+use ratatui::symbols::Marker;
+use ratatui::widgets::canvas::{Canvas, Points, Rectangle};
+use ratatui::widgets::{Block, Borders, Clear};
+
+use crate::app::graph::GraphLayout;
+use crate::app::App;
+
+const MIN_WIDTH: u16 = 16;
+const MAX_WIDTH: u16 = 40;
+const MIN_HEIGHT: u16 = 5;
+
+/// Draw the whole graph scaled into the bottom-right corner of `graph_area`
+/// with the visible part outlined. Returns where it was drawn, or an empty
+/// rect when the graph area is too small to spare a corner.
+pub(super) fn render_minimap(
+    frame: &mut Frame,
+    graph_area: Rect,
+    app: &App,
+    layout: &GraphLayout,
+) -> Rect {
+    let area = minimap_area(graph_area, layout);
+    if area.is_empty() {
+        return area;
+    }
+    let width = layout.width.max(1.0);
+    let height = layout.height.max(1.0);
+    let view = app.graph.view_bounds_for(layout, graph_area);
+    let selected = app.graph.selected_id().and_then(|id| layout.node(id));
+    let points: Vec<(f64, f64)> = layout.nodes.iter().map(|node| (node.x, node.y)).collect();
+
+    // The view may reach past the graph when zoomed out; only the part over
+    // the graph is meaningful here.
+    let x_min = view.x_min.max(0.0);
+    let y_min = view.y_min.max(0.0);
+    let view_rect = Rectangle {
+        x: x_min,
+        y: y_min,
+        width: (view.x_max.min(width) - x_min).max(0.0),
+        height: (view.y_max.min(height) - y_min).max(0.0),
+        color: Color::White,
+    };
+
+    let canvas = Canvas::default()
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::DarkGray)),
+        )
+        .marker(Marker::Braille)
+        .x_bounds([0.0, width])
+        .y_bounds([0.0, height])
+        .paint(move |ctx| {
+            ctx.draw(&Points {
+                coords: &points,
+                color: Color::Blue,
+            });
+            if let Some(node) = selected {
+                ctx.draw(&Points {
+                    coords: &[(node.x, node.y)],
+                    color: Color::Yellow,
+                });
+            }
+            ctx.draw(&view_rect);
+        });
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(canvas, area);
+    area
+}
+
+/// Corner rect keeping roughly the graph's proportions, about a quarter of
+/// the graph area wide.
+fn minimap_area(graph_area: Rect, layout: &GraphLayout) -> Rect {
+    let max_height = graph_area.height / 3;
+    if graph_area.width < MIN_WIDTH * 2 || max_height < MIN_HEIGHT {
+        return Rect::default();
+    }
+    let width = (graph_area.width / 4).clamp(MIN_WIDTH, MAX_WIDTH);
+    // Cells are ~2.1x taller than wide, borders take one cell per side.
+    let inner_width = f64::from(width - 2);
+    let ratio = layout.height.max(1.0) / layout.width.max(1.0);
+    let inner_height = (inner_width * ratio / 2.1).round() as u16;
+    let height = (inner_height + 2).clamp(MIN_HEIGHT, max_height);
+    Rect {
+        x: graph_area.right() - width,
+        y: graph_area.bottom() - height,
+        width,
+        height,
+    }
+}
@@ -7,6 +7,7 @@ use primer::application::flows::reconcile::visualize;
 mod detail;
 mod draw;
 mod layout;
+mod minimap;
 mod overlay;
 
 pub(super) fn render_topology_graph(
@@ -20,9 +21,18 @@ pub(super) fn render_topology_graph(
 
     if let Some(layout) = app.graph.layout() {
         draw::render_canvas(frame, graph_area, app, layout);
+        // A terminal image would paint over the minimap, so it is canvas only.
+        app.ui.minimap_area = if app.ui.show_minimap && !app.graph.image_active() {
+            minimap::render_minimap(frame, graph_area, app, layout)
+        } else {
+            Rect::default()
+        };
         if let Some(sidebar) = sidebar_area {
             detail::render_detail_sidebar(frame, sidebar, app);
         }
+        if app.ui.graph_stats && app.ui.show_graph_stats {
+            overlay::render_stats_overlay(frame, graph_area, app, layout);
+        }
         if app.ui.search_active {
             overlay::render_search_overlay(frame, graph_area, app);
         }
@@ -1,9 +1,11 @@
 use ratatui::layout::Rect;
 use ratatui::prelude::Frame;
 use ratatui::style::{Color, Style};
-use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+use std::time::Duration;
 
 use crate::app::App;
+use crate::app::graph::GraphLayout;
 
 pub(super) fn render_search_overlay(frame: &mut Frame, graph_area: Rect, app: &App) {
     let search_area = Rect {
@@ -12,11 +14,57 @@ pub(super) fn render_search_overlay(frame: &mut Frame, graph_area: Rect, app: &A
         width: 40,
         height: 3,
     };
+    let title = match app.graph.search_matches().len() {
+        0 if app.ui.search_query.is_empty() => "Search Node".to_string(),
+        1 => "Search Node (1 match)".to_string(),
+        count => format!("Search Node ({count} matches)"),
+    };
     let block = Block::default()
-        .title("Search Node")
+        .title(title)
         .borders(Borders::ALL)
         .style(Style::default().bg(Color::Blue).fg(Color::White));
-    frame.render_widget(ratatui::widgets::Clear, search_area);
+    frame.render_widget(Clear, search_area);
     let paragraph = Paragraph::new(app.ui.search_query.as_str()).block(block);
     frame.render_widget(paragraph, search_area);
 }
+
+/// Graphviz timings, graph size and cache counters in the top-right corner
+/// of the graph area.
+pub(super) fn render_stats_overlay(
+    frame: &mut Frame,
+    graph_area: Rect,
+    app: &App,
+    layout: &GraphLayout,
+) {
+    let stats = app.graph.stats();
+    let (nodes, edges) = (layout.nodes.len(), layout.edges.len());
+    let (hits, misses) = (stats.cache_hits, stats.cache_misses);
+    let lines = vec![
+        format!("layout: {}", format_duration(stats.layout_time)),
+        format!("image:  {}", format_duration(stats.image_time)),
+        format!("nodes: {nodes}  edges: {edges}"),
+        format!("png: {:.1} KiB", stats.image_bytes as f64 / 1024.0),
+        format!("cache: {hits} hit / {misses} miss"),
+    ];
+    let width = 30.min(graph_area.width);
+    let height = (lines.len() as u16 + 2).min(graph_area.height);
+    let area = Rect {
+        x: graph_area.right().saturating_sub(width),
+        y: graph_area.y,
+        width,
+        height,
+    };
+    let block = Block::default()
+        .title("Render stats")
+        .borders(Borders::ALL)
+        .style(Style::default().fg(Color::White));
+    frame.render_widget(Clear, area);
+    frame.render_widget(Paragraph::new(lines.join("\n")).block(block), area);
+}
+
+fn format_duration(duration: Option<Duration>) -> String {
+    match duration {
+        Some(duration) => format!("{:.1} ms", duration.as_secs_f64() * 1000.0),
+        None => "-".to_string(),
+    }
+}
@@ -3,11 +3,11 @@ use ratatui::{
     prelude::Frame,
     style::{Color, Modifier, Style},
     text::Span,
-    widgets::{Block, Borders},
 };
 
 use crate::app::{App, NavView};
 use crate::panels::analytics;
+use crate::panels::chrome::status::header_block;
 use primer::application::flows::reconcile::visualize;
 
 mod graph;
@@ -51,13 +51,11 @@ pub fn render_main(frame: &mut Frame, area: Rect, app: &mut App) {
         );
     }
 
-    let block = Block::default()
-        .title(Span::styled(
-            title,
-            Style::default().add_modifier(Modifier::BOLD),
-        ))
-        .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::DarkGray));
+    let block = header_block(
+        Span::styled(title, Style::default().add_modifier(Modifier::BOLD)),
+        &app.context,
+        app.unread_notification_count(),
+    );
 
     let block = if matches!(
         app.active_view(),
@@ -84,17 +82,19 @@ pub fn render_main(frame: &mut Frame, area: Rect, app: &mut App) {
         NavView::TopologyHealth => render_topology_health(frame, inner, app),
         NavView::TopologyDagGraph => {
             let label = format!(
-                "DAG Graph [{}] img:{}",
+                "DAG Graph [{}] img:{} engine:{}",
                 app.graph.protocol_label(),
-                app.graph.image_active()
+                app.graph.image_active(),
+                app.graph.engine().as_str()
             );
             render_topology_graph(frame, inner, app, visualize::ViewType::Full, &label);
         }
         NavView::TopologyDualGraph => {
             let label = format!(
-                "Dual Graph [{}] img:{}",
+                "Dual Graph [{}] img:{} engine:{}",
                 app.graph.protocol_label(),
-                app.graph.image_active()
+                app.graph.image_active(),
+                app.graph.engine().as_str()
             );
             render_topology_graph(frame, inner, app, visualize::ViewType::Dual, &label);
         }
@@ -18,6 +18,7 @@ pub(super) fn reset_panel_areas(app: &mut App) {
     app.ui.assembly_area = Rect::default();
     app.ui.capabilities_area = Rect::default();
     app.ui.logs_area = Rect::default();
+    app.ui.minimap_area = Rect::default();
     app.ui.collapsed_actions = true;
     app.ui.collapsed_logs = true;
     app.ui.collapsed_assembly_steps = true;
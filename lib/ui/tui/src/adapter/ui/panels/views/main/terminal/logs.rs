@@ -19,6 +19,20 @@ pub fn render_terminal_logs(frame: &mut Frame, area: Rect, app: &mut App) {
         app.ui.log_config.interval.as_secs(),
         if app.ui.auto_refresh { "on" } else { "off" }
     )));
+    if app.ui.auto_refresh {
+        lines.push(refresh_cadence_line(app));
+    }
+    let bus = app.runtime.events();
+    if bus.dropped() > 0 {
+        lines.push(Line::from(Span::styled(
+            format!(
+                "Dropped {} older events (keeping the last {})",
+                bus.dropped(),
+                bus.capacity()
+            ),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
     lines.push(Line::from(""));
 
     let events = app.filtered_events();
@@ -44,3 +58,27 @@ pub fn render_terminal_logs(frame: &mut Frame, area: Rect, app: &mut App) {
         .scroll((app.ui.log_scroll, 0));
     frame.render_widget(paragraph, area);
 }
+
+/// Average refresh interval against the configured one, highlighted when
+/// refreshes fall behind by more than half an interval.
+fn refresh_cadence_line(app: &App) -> Line<'static> {
+    let target = App::AUTO_REFRESH_INTERVAL;
+    let Some(average) = app.runtime.average_refresh_interval() else {
+        return Line::from(format!(
+            "Refresh: target {:.1}s  avg -",
+            target.as_secs_f64()
+        ));
+    };
+    let drift = average.saturating_sub(target);
+    let text = format!(
+        "Refresh: target {:.1}s  avg {:.1}s  drift +{:.1}s",
+        target.as_secs_f64(),
+        average.as_secs_f64(),
+        drift.as_secs_f64()
+    );
+    if drift > target / 2 {
+        Line::from(Span::styled(text, Style::default().fg(Color::Yellow)))
+    } else {
+        Line::from(text)
+    }
+}
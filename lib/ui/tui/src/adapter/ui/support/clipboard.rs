@@ -0,0 +1,85 @@
+//! Copying text out of the TUI, through the system clipboard when there is
+//! one and an OSC 52 escape otherwise so it still works over SSH.
+
+use anyhow::Result;
+use arboard::Clipboard;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+/// How a copy reached the clipboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CopyMethod {
+    System,
+    Osc52,
+}
+
+// On X11 the copied text is served by its owner, so the clipboard handle has
+// to outlive the copy.
+static CLIPBOARD: Mutex<Option<Clipboard>> = Mutex::new(None);
+
+pub(crate) fn copy_text(text: &str) -> Result<CopyMethod> {
+    match copy_to_system(text) {
+        Ok(()) => Ok(CopyMethod::System),
+        Err(error) => {
+            tracing::debug!("system clipboard unavailable, using OSC 52: {error}");
+            let mut stdout = io::stdout();
+            write_osc52(&mut stdout, text, is_tmux())?;
+            stdout.flush()?;
+            Ok(CopyMethod::Osc52)
+        }
+    }
+}
+
+fn copy_to_system(text: &str) -> Result<(), arboard::Error> {
+    let mut clipboard = CLIPBOARD
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if clipboard.is_none() {
+        *clipboard = Some(Clipboard::new()?);
+    }
+    let result = clipboard
+        .as_mut()
+        .map_or(Ok(()), |clipboard| clipboard.set_text(text));
+    if result.is_err() {
+        // A broken connection won't recover; reconnect on the next copy.
+        *clipboard = None;
+    }
+    result
+}
+
+/// Ask the terminal to set its clipboard, wrapped for tmux passthrough.
+fn write_osc52<W: Write>(out: &mut W, text: &str, is_tmux: bool) -> io::Result<()> {
+    let sequence = format!("\x1b]52;c;{}\x07", STANDARD.encode(text));
+    if is_tmux {
+        write!(
+            out,
+            "\x1bPtmux;{}\x1b\\",
+            sequence.replace('\x1b', "\x1b\x1b")
+        )
+    } else {
+        out.write_all(sequence.as_bytes())
+    }
+}
+
+fn is_tmux() -> bool {
+    std::env::var("TMUX").is_ok()
+        || std::env::var("TERM").is_ok_and(|t| t.starts_with("screen") || t.starts_with("tmux"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::write_osc52;
+
+    #[test]
+    fn osc52_encodes_text_and_wraps_for_tmux() {
+        let mut plain = Vec::new();
+        write_osc52(&mut plain, "api", false).unwrap();
+        assert_eq!(plain, b"\x1b]52;c;YXBp\x07");
+
+        let mut tmux = Vec::new();
+        write_osc52(&mut tmux, "api", true).unwrap();
+        assert_eq!(tmux, b"\x1bPtmux;\x1b\x1b]52;c;YXBp\x07\x1b\\");
+    }
+}
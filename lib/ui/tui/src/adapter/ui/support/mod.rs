@@ -1,3 +1,4 @@
+pub(crate) mod clipboard;
 pub mod macros;
 pub mod state;
 pub mod util;
@@ -7,6 +7,7 @@ use phenome_domain::Event;
 use phenome_ui_presentation::logging::LogStreamConfig;
 
 use super::{HoldState, HoverPanel, Tooltip};
+use crate::util::env_flag;
 
 /// Aggregated UI state shared across panels and input handlers.
 pub struct UiState {
@@ -45,8 +46,16 @@ pub struct UiState {
     pub search_query: String,
     pub show_detail_panel: bool,
     pub hover_node_id: Option<String>,
+    /// Tail and head node ids of the hovered graph edge.
+    pub hover_edge: Option<(String, String)>,
     pub detail_scroll: u16,
     pub detail_area: Rect,
+    pub show_minimap: bool,
+    pub minimap_area: Rect,
+    pub credential_hints: bool,
+    pub reveal_credentials: bool,
+    pub graph_stats: bool,
+    pub show_graph_stats: bool,
 }
 
 impl UiState {
@@ -88,8 +97,15 @@ impl UiState {
             search_query: String::new(),
             show_detail_panel: false,
             hover_node_id: None,
+            hover_edge: None,
             detail_scroll: 0,
             detail_area: Rect::default(),
+            show_minimap: true,
+            minimap_area: Rect::default(),
+            credential_hints: credential_hints_from_env(),
+            reveal_credentials: false,
+            graph_stats: graph_stats_from_env(),
+            show_graph_stats: false,
         }
     }
 }
@@ -99,3 +115,15 @@ impl Default for UiState {
         Self::new()
     }
 }
+
+/// Read `PHENOME_TUI_CREDENTIAL_HINTS` to decide whether credential-like
+/// provides are flagged in the detail sidebar. Enabled unless explicitly off.
+fn credential_hints_from_env() -> bool {
+    env_flag("PHENOME_TUI_CREDENTIAL_HINTS", true)
+}
+
+/// Read `PHENOME_TUI_GRAPH_STATS` to decide whether the graph render metrics
+/// overlay can be toggled. Disabled unless explicitly on.
+fn graph_stats_from_env() -> bool {
+    env_flag("PHENOME_TUI_GRAPH_STATS", false)
+}
@@ -0,0 +1,179 @@
+//! Dependency classification helpers.
+
+use anyhow::{Context, Result};
+use regex::{Regex, RegexBuilder};
+
+use phenome_domain::{DependencyCategory, UiConfig};
+
+const DEFAULT_RULES: &[(DependencyCategory, &[&str])] = &[
+    (
+        DependencyCategory::Database,
+        &["postgres", "redis", "mongo", "qdrant", "sql", "db", "data"],
+    ),
+    (
+        DependencyCategory::Storage,
+        &["minio", "longhorn", "s3", "storage"],
+    ),
+    (
+        DependencyCategory::Security,
+        &["oidc", "authelia", "secret", "cert", "vault", "auth"],
+    ),
+    (
+        DependencyCategory::Network,
+        &["ingress", "dns", "network", "proxy"],
+    ),
+    (
+        DependencyCategory::Infrastructure,
+        &["kro", "cnpg", "operator"],
+    ),
+];
+
+#[derive(Debug, Clone)]
+struct DependencyRule {
+    category: DependencyCategory,
+    keywords: Vec<String>,
+    patterns: Vec<Regex>,
+}
+
+impl DependencyRule {
+    fn matches(&self, dep_lower: &str) -> bool {
+        self.keywords
+            .iter()
+            .any(|keyword| dep_lower.contains(keyword))
+            || self
+                .patterns
+                .iter()
+                .any(|pattern| pattern.is_match(dep_lower))
+    }
+}
+
+/// Ordered keyword/regex rules mapping dependency names to categories.
+///
+/// # Examples
+/// ```rust
+/// use phenome_domain::DependencyCategory;
+/// use phenome_ui_tui::util::DependencyClassifier;
+///
+/// let classifier = DependencyClassifier::default();
+/// assert_eq!(classifier.classify("postgres-main"), DependencyCategory::Database);
+/// ```
+#[derive(Debug, Clone)]
+pub struct DependencyClassifier {
+    rules: Vec<DependencyRule>,
+}
+
+impl DependencyClassifier {
+    /// Build a classifier with configured rules ahead of the built-in defaults.
+    pub fn from_config(config: &UiConfig) -> Result<Self> {
+        let mut rules = Vec::new();
+        for rule in &config.dependency_rules {
+            let mut patterns = Vec::with_capacity(rule.patterns.len());
+            for pattern in &rule.patterns {
+                let regex = RegexBuilder::new(pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .with_context(|| format!("invalid dependency pattern {pattern:?}"))?;
+                patterns.push(regex);
+            }
+            rules.push(DependencyRule {
+                category: rule.category,
+                keywords: rule.keywords.iter().map(|k| k.to_lowercase()).collect(),
+                patterns,
+            });
+        }
+        rules.extend(default_rules());
+        Ok(Self { rules })
+    }
+
+    /// Category of the first rule matching `dep`, or `Other`.
+    pub fn classify(&self, dep: &str) -> DependencyCategory {
+        let dep_lower = dep.to_lowercase();
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(&dep_lower))
+            .map(|rule| rule.category)
+            .unwrap_or(DependencyCategory::Other)
+    }
+}
+
+impl Default for DependencyClassifier {
+    fn default() -> Self {
+        Self {
+            rules: default_rules().collect(),
+        }
+    }
+}
+
+/// Icon shown next to a dependency of the given category.
+pub fn dependency_icon(category: DependencyCategory) -> &'static str {
+    match category {
+        DependencyCategory::Security => "🔒",
+        DependencyCategory::Database => "🔌",
+        DependencyCategory::Storage => "💾",
+        DependencyCategory::Network => "🌐",
+        DependencyCategory::Infrastructure => "🏗️",
+        DependencyCategory::Other => "📦",
+    }
+}
+
+fn default_rules() -> impl Iterator<Item = DependencyRule> {
+    DEFAULT_RULES
+        .iter()
+        .map(|(category, keywords)| DependencyRule {
+            category: *category,
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            patterns: Vec::new(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DependencyClassifier;
+    use phenome_domain::{DependencyCategory, DependencyRuleConfig, UiConfig};
+
+    #[test]
+    fn custom_rules_extend_defaults() {
+        let config = UiConfig {
+            dependency_rules: vec![
+                DependencyRuleConfig {
+                    category: DependencyCategory::Database,
+                    keywords: vec!["ClickHouse".to_string()],
+                    patterns: Vec::new(),
+                },
+                DependencyRuleConfig {
+                    category: DependencyCategory::Network,
+                    keywords: Vec::new(),
+                    patterns: vec![r"^edge-gw-\d+$".to_string()],
+                },
+            ],
+        };
+        let classifier = DependencyClassifier::from_config(&config).unwrap();
+
+        assert_eq!(
+            classifier.classify("clickhouse-events"),
+            DependencyCategory::Database
+        );
+        assert_eq!(
+            classifier.classify("Edge-GW-2"),
+            DependencyCategory::Network
+        );
+        assert_eq!(classifier.classify("minio"), DependencyCategory::Storage);
+        assert_eq!(
+            classifier.classify("authelia"),
+            DependencyCategory::Security
+        );
+        assert_eq!(classifier.classify("widget"), DependencyCategory::Other);
+    }
+
+    #[test]
+    fn invalid_pattern_is_rejected() {
+        let config = UiConfig {
+            dependency_rules: vec![DependencyRuleConfig {
+                category: DependencyCategory::Storage,
+                keywords: Vec::new(),
+                patterns: vec!["(unclosed".to_string()],
+            }],
+        };
+        assert!(DependencyClassifier::from_config(&config).is_err());
+    }
+}
@@ -1,2 +1,3 @@
 pub mod assembly;
+pub mod dependency;
 pub mod problems;
@@ -0,0 +1,29 @@
+//! On/off switches read from the environment.
+
+/// Read the on/off variable `name`. `1`, `true`, `on` and `enabled` turn it
+/// on, `0`, `false`, `off` and `disabled` turn it off, case-insensitively;
+/// unset or any other value leaves `default`.
+pub fn env_flag(name: &str, default: bool) -> bool {
+    std::env::var(name).map_or(default, |value| parse_flag(&value, default))
+}
+
+fn parse_flag(value: &str, default: bool) -> bool {
+    match value.to_lowercase().as_str() {
+        "1" | "true" | "on" | "enabled" => true,
+        "0" | "false" | "off" | "disabled" => false,
+        _ => default,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_flag;
+
+    #[test]
+    fn unrecognised_values_keep_the_default() {
+        assert!(!parse_flag("OFF", true));
+        assert!(parse_flag("Enabled", false));
+        assert!(parse_flag("maybe", true));
+        assert!(!parse_flag("", false));
+    }
+}
@@ -8,11 +8,14 @@
 //! ```
 
 mod data;
+mod env;
 mod format;
 mod geometry;
 
 pub use data::assembly::{AssemblyLine, assembly_lines, assembly_status_icon, capability_icon};
+pub use data::dependency::{DependencyClassifier, dependency_icon};
 pub use data::problems::collect_problems;
+pub use env::env_flag;
 pub use format::color::{animated_color, traveling_glow};
 pub use format::time::{format_age, spinner_frame};
 pub use geometry::rect::{anchored_rect, anchored_rect_with_offset, centered_rect};
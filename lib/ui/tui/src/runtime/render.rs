@@ -49,7 +49,8 @@ pub(crate) fn render(frame: &mut Frame, app: &mut App) {
             .saturating_add(body_area.height.saturating_sub(height) / 2);
         let overlay_area = Rect::new(x, y, width, height);
         frame.render_widget(Clear, overlay_area);
-        panels::render_notifications(frame, overlay_area);
+        let shown = panels::render_notifications(frame, overlay_area, &app.notifications);
+        app.mark_notifications_read(&shown);
     }
 
     panels::render_confirmation(frame, app);
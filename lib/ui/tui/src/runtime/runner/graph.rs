@@ -5,10 +5,11 @@ use ratatui::backend::CrosstermBackend;
 use std::io::Write;
 use std::io::Stdout;
 
+use crate::app::graph::{GraphRenderRequest, KittyPlacement};
 use crate::app::{App, PanelId, TerminalImageProtocol};
 
 use super::iterm::write_iterm2_image;
-use super::kitty::write_kitty_image;
+use super::kitty::{delete_kitty_image, place_kitty_image, transmit_kitty_image};
 
 pub(super) fn render_graph(
     terminal: &mut Terminal<CrosstermBackend<Stdout>>,
@@ -36,25 +37,64 @@ pub(super) fn render_graph(
     if request.area.width < 2 || request.area.height < 2 {
         return Ok(());
     }
-    if let Err(err) = app.graph.ensure_image() {
-        app.graph.mark_failed(err.to_string());
+    app.graph.ensure_image();
+    let Some(image) = app.graph.image() else {
+        return Ok(());
+    };
+
+    if app.graph.protocol() == TerminalImageProtocol::Kitty {
+        return render_kitty_graph(terminal, app, &request, is_tmux);
+    }
+    let stdout = terminal.backend_mut();
+    queue!(stdout, MoveTo(request.area.x, request.area.y))?;
+    if app.graph.protocol() == TerminalImageProtocol::ITerm2 {
+        write_iterm2_image(stdout, image, request.area)?;
+    }
+    stdout.flush()?;
+    app.graph.set_image_active(true);
+    Ok(())
+}
+
+/// Kitty keeps placed images, so only changes are sent. A new image goes
+/// to the id not on screen and replaces the shown one once it is placed,
+/// which avoids the blank frame of deleting first. Two ids alternate.
+fn render_kitty_graph(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    app: &mut App,
+    request: &GraphRenderRequest,
+    is_tmux: bool,
+) -> Result<()> {
+    let generation = app.graph.image_generation();
+    let shown = app.graph.kitty_placement();
+    if shown.is_some_and(|shown| shown.generation == generation && shown.area == request.area) {
         return Ok(());
     }
     let Some(image) = app.graph.image() else {
         return Ok(());
     };
+    let base_id = app.graph.image_id();
+    let image_id = match shown {
+        Some(shown) if shown.generation == generation => shown.image_id,
+        Some(shown) if shown.image_id == base_id => base_id + 1,
+        _ => base_id,
+    };
 
     let stdout = terminal.backend_mut();
+    if shown.is_none_or(|shown| shown.generation != generation) {
+        transmit_kitty_image(stdout, image, image_id, is_tmux)?;
+    }
     queue!(stdout, MoveTo(request.area.x, request.area.y))?;
-    match app.graph.protocol() {
-        TerminalImageProtocol::Kitty => {
-            write_kitty_image(stdout, image, request.area, app.graph.image_id(), is_tmux)?
-        }
-        TerminalImageProtocol::ITerm2 => write_iterm2_image(stdout, image, request.area)?,
-        TerminalImageProtocol::None => {}
+    place_kitty_image(stdout, request.area, image_id, is_tmux)?;
+    if let Some(shown) = shown.filter(|shown| shown.image_id != image_id) {
+        delete_kitty_image(stdout, shown.image_id, is_tmux)?;
     }
     stdout.flush()?;
     app.graph.set_image_active(true);
+    app.graph.set_kitty_placement(KittyPlacement {
+        generation,
+        area: request.area,
+        image_id,
+    });
     Ok(())
 }
 
@@ -65,7 +105,7 @@ fn clear_graph_image(
     match app.graph.protocol() {
         TerminalImageProtocol::Kitty => {
             let stdout = terminal.backend_mut();
-            write!(stdout, "\x1b_Ga=d,d=A\x1b\\")?;
+            write!(stdout, "\x1b_Ga=d,d=A,q=2\x1b\\")?;
             stdout.flush()?;
         }
         TerminalImageProtocol::ITerm2 => {
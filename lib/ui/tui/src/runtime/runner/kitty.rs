@@ -3,44 +3,68 @@ use base64::Engine;
 use base64::engine::general_purpose::STANDARD;
 use std::io::Write;
 
-pub(super) fn write_kitty_image<W: Write>(
+/// Send `image` as PNG data under `image_id` without showing it, so it can
+/// be placed once complete instead of replacing what is on screen.
+pub(super) fn transmit_kitty_image<W: Write>(
     stdout: &mut W,
     image: &[u8],
-    area: ratatui::layout::Rect,
     image_id: u32,
     is_tmux: bool,
 ) -> Result<()> {
     let encoded = STANDARD.encode(image);
     let chunk_size = 4096;
-    let total_chunks = (encoded.len() + chunk_size - 1) / chunk_size;
+    let total_chunks = encoded.len().div_ceil(chunk_size);
     for (index, chunk) in encoded.as_bytes().chunks(chunk_size).enumerate() {
         let more = if index + 1 < total_chunks { 1 } else { 0 };
-        let payload = if index == 0 {
-            format!(
-                "\x1b_Gf=100,a=T,c={},r={},i={},m={};",
-                area.width, area.height, image_id, more
-            )
+        let control = if index == 0 {
+            format!("f=100,a=t,i={image_id},q=2,m={more}")
         } else {
-            format!("\x1b_Gm={};", more)
+            format!("m={more}")
         };
+        write_kitty_command(stdout, &control, chunk, is_tmux)?;
+    }
+    Ok(())
+}
 
-        if is_tmux {
-            write!(stdout, "\x1bPtmux;\x1b")?;
-            write!(stdout, "{}", payload.replace("\x1b", "\x1b\x1b"))?;
-        } else {
-            write!(stdout, "{}", payload)?;
-        }
+/// Show image `image_id` at the cursor, scaled to `area`. Placing it again
+/// moves or resizes the one placement rather than adding another.
+pub(super) fn place_kitty_image<W: Write>(
+    stdout: &mut W,
+    area: ratatui::layout::Rect,
+    image_id: u32,
+    is_tmux: bool,
+) -> Result<()> {
+    let control = format!(
+        "a=p,i={image_id},p=1,c={},r={},q=2",
+        area.width, area.height
+    );
+    write_kitty_command(stdout, &control, &[], is_tmux)
+}
 
-        if is_tmux {
-            for byte in chunk {
-                write!(stdout, "{}", *byte as char)?;
-            }
-            write!(stdout, "\x1b\x1b\\")?;
-            write!(stdout, "\x1b\\")?;
-        } else {
-            stdout.write_all(chunk)?;
-            write!(stdout, "\x1b\\")?;
-        }
+/// Remove image `image_id` from the screen and free its data.
+pub(super) fn delete_kitty_image<W: Write>(
+    stdout: &mut W,
+    image_id: u32,
+    is_tmux: bool,
+) -> Result<()> {
+    write_kitty_command(stdout, &format!("a=d,d=I,i={image_id},q=2"), &[], is_tmux)
+}
+
+fn write_kitty_command<W: Write>(
+    stdout: &mut W,
+    control: &str,
+    payload: &[u8],
+    is_tmux: bool,
+) -> Result<()> {
+    if is_tmux {
+        write!(stdout, "\x1bPtmux;\x1b\x1b_G{control};")?;
+        stdout.write_all(payload)?;
+        write!(stdout, "\x1b\x1b\\")?;
+        write!(stdout, "\x1b\\")?;
+    } else {
+        write!(stdout, "\x1b_G{control};")?;
+        stdout.write_all(payload)?;
+        write!(stdout, "\x1b\\")?;
     }
     Ok(())
 }
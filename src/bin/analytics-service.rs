@@ -6,10 +6,14 @@ use std::time::Duration;
 use tokio::sync::watch;
 
 use phenome_adapter_analytics::AnalyticsService;
+use phenome_adapter_analytics::circuit_breaker::CircuitBreaker;
 use phenome_adapter_analytics::cluster_manager::ClusterManager;
 use phenome_adapter_analytics::grpc::GrpcServer;
+use phenome_adapter_analytics::prometheus::PrometheusSource;
+use phenome_adapter_analytics::storage::StoragePort;
 use phenome_adapter_analytics::storage::sqlite::{RetentionConfig, SqliteStorage};
-use phenome_domain::PhenomeConfig;
+use phenome_adapter_analytics::write_queue::WriteQueue;
+use phenome_domain::{AnalyticsConfig, MetricsSourceConfig, PhenomeConfig};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -24,33 +28,74 @@ async fn main() -> anyhow::Result<()> {
         }
     });
 
-    let retention = RetentionConfig {
-        raw_days: config.analytics.retention.full_resolution_days,
-        aggregated_days: config.analytics.retention.aggregated_days,
-    };
-    let storage = Arc::new(SqliteStorage::with_retention(
-        &config.analytics.sqlite_path,
-        retention,
-    )?);
+    let storage = open_storage(&config.analytics).await?;
 
     let ml_url = config.services.ml_url.clone();
-    let ml_client = phenome_adapter_analytics::grpc::MlClient::connect(&ml_url).await?;
+    let ml_client = phenome_adapter_analytics::grpc::MlClient::connect(&ml_url)
+        .await?
+        .with_circuit_breaker(CircuitBreaker::from_config(
+            &config.analytics.circuit_breaker,
+        ));
 
     let service = AnalyticsService::new(storage.clone(), ml_client);
+    match service.restore_recommendations().await {
+        Ok(count) => tracing::info!("Restored {} stored recommendations", count),
+        Err(err) => tracing::warn!("Failed to restore recommendations: {:#}", err),
+    }
     let service = Arc::new(service);
 
-    let cm = ClusterManager::new();
+    let cm = ClusterManager::new()
+        .with_circuit_breaker_config(config.analytics.circuit_breaker.clone())
+        .with_max_concurrent_queries(config.analytics.collection.max_concurrent_clusters)
+        .with_query_timeout(Duration::from_secs(
+            config.analytics.collection.query_timeout_seconds,
+        ));
     for cluster_config in config.clusters {
-        cm.add_cluster(cluster_config.context).await?;
+        match cluster_config.metrics_source {
+            MetricsSourceConfig::MetricsServer => {
+                cm.add_cluster(cluster_config.context).await?;
+            }
+            MetricsSourceConfig::Prometheus { url } => {
+                cm.add_cluster_with_prometheus(cluster_config.context, PrometheusSource::new(url))
+                    .await?;
+            }
+        }
     }
+    let collection = &config.analytics.collection;
+    let write_queue = WriteQueue::new(collection.write_queue_capacity, collection.write_overflow);
+    tokio::spawn(
+        write_queue
+            .clone()
+            .run_writer(storage.clone(), shutdown_rx.clone()),
+    );
     let mc = phenome_adapter_analytics::metrics_collector::MetricsCollector::new(
         cm,
-        Duration::from_secs(config.collection.interval),
-    );
+        Duration::from_secs(collection.interval_seconds),
+    )
+    .with_write_queue(write_queue)
+    .with_metric_types(collection.metric_types.clone())
+    .with_resource_types(collection.resource_types.clone())
+    .with_jitter(collection.jitter_percent)
+    .with_filters(collection.filters.clone());
     let _hc = tokio::spawn(mc.run_polling_loop_with_shutdown(shutdown_rx.clone()));
 
+    let aggregation_window = config.analytics.aggregation_window;
+    tokio::spawn(
+        phenome_adapter_analytics::aggregator::Aggregator::run_with_shutdown(
+            storage.clone(),
+            aggregation_window,
+            shutdown_rx.clone(),
+        ),
+    );
+    tokio::spawn(
+        phenome_adapter_analytics::aggregator::Aggregator::run_daily_with_shutdown(
+            storage.clone(),
+            aggregation_window,
+            shutdown_rx.clone(),
+        ),
+    );
     tokio::spawn(
-        phenome_adapter_analytics::aggregator::Aggregator::run_hourly_with_shutdown(
+        phenome_adapter_analytics::retention::RetentionTask::run_daily_with_shutdown(
             storage.clone(),
             shutdown_rx.clone(),
         ),
@@ -59,28 +104,23 @@ async fn main() -> anyhow::Result<()> {
     let kube_client = match kube::Client::try_default().await {
         Ok(client) => Some(client),
         Err(err) => {
-            tracing::warn!(
-                "Failed to create kube client; scheduler disabled: {}",
-                err
-            );
+            tracing::warn!("Failed to create kube client; scheduler disabled: {}", err);
             None
         }
     };
 
-    let mut channels = Vec::new();
-    channels.push(phenome_domain::NotificationChannel::InTui);
-    channels.push(phenome_domain::NotificationChannel::System);
-
-    for channel_config in config.notifications.channels {
-        match channel_config {
-            phenome_domain::NotificationChannelConfig::Ntfy { url, topic } => {
-                channels.push(phenome_domain::NotificationChannel::Ntfy { url, topic });
-            }
-        }
+    let mut notifier = phenome_adapter_analytics::notification::NotificationService::from_config(
+        &config.notifications.channels,
+    )
+    .with_dedup_ttl(Duration::from_secs(config.notifications.dedup_ttl_seconds))
+    .with_storage(storage.clone());
+    if let Some(rate_limit) = config.notifications.rate_limit.clone() {
+        notifier = notifier.with_rate_limit(rate_limit);
     }
-
-    let notifier =
-        Arc::new(phenome_adapter_analytics::notification::NotificationService::new(channels));
+    if let Some(seconds) = config.notifications.digest_interval_seconds {
+        notifier = notifier.with_digest_interval(Duration::from_secs(seconds));
+    }
+    let notifier = Arc::new(notifier);
     {
         let notifier = notifier.clone();
         let service = service.clone();
@@ -97,6 +137,7 @@ async fn main() -> anyhow::Result<()> {
             phenome_adapter_analytics::scheduler::SchedulerService::run_minute_with_shutdown(
                 storage.clone(),
                 kube_client,
+                config.analytics.scheduler.clone(),
                 shutdown_rx.clone(),
             ),
         );
@@ -104,10 +145,41 @@ async fn main() -> anyhow::Result<()> {
 
     let addr = parse_addr(&config.services.analytics_url)
         .unwrap_or_else(|| "127.0.0.1:50051".parse().expect("invalid fallback addr"));
-    GrpcServer::serve(addr, service).await?;
+    GrpcServer::serve(addr, service, &config.analytics.grpc).await?;
     Ok(())
 }
 
+/// Open the backend named by `analytics.storage`.
+async fn open_storage(config: &AnalyticsConfig) -> anyhow::Result<Arc<dyn StoragePort>> {
+    let retention = RetentionConfig {
+        raw_days: config.retention.full_resolution_days,
+        aggregated_days: config.retention.aggregated_days,
+        daily_days: config.retention.daily_days,
+    };
+    match config.storage.as_str() {
+        "sqlite" => {
+            let storage = SqliteStorage::with_retention(&config.sqlite_path, retention)?;
+            Ok(Arc::new(storage))
+        }
+        #[cfg(feature = "analytics-postgres")]
+        "postgres" => {
+            let url = config.postgres_url.as_deref().ok_or_else(|| {
+                anyhow::anyhow!("analytics.postgres_url is required for postgres storage")
+            })?;
+            let storage = phenome_adapter_analytics::storage::postgres::PostgresStorage::connect(
+                url, retention,
+            )
+            .await?;
+            Ok(Arc::new(storage))
+        }
+        #[cfg(not(feature = "analytics-postgres"))]
+        "postgres" => {
+            anyhow::bail!("postgres storage requires building with the analytics-postgres feature")
+        }
+        other => anyhow::bail!("unknown analytics storage backend {other:?}"),
+    }
+}
+
 fn config_path() -> PathBuf {
     if let Ok(path) = env::var("PHENOME_CONFIG_PATH") {
         return PathBuf::from(path);
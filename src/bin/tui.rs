@@ -1,6 +1,9 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
 use phenome_adapter_primer::PrimerBackend;
 use phenome_application::Runtime;
-use phenome_domain::ActionRegistry;
+use phenome_domain::{ActionRegistry, PhenomeConfig};
 use phenome_ui_tui as tui;
 use phenome_ui_tui::app::AppContext;
 
@@ -8,6 +11,13 @@ fn main() -> anyhow::Result<()> {
     // 1. Initialize backend (Sync) - do this before starting any global runtime
     let backend = PrimerBackend::from_env()?;
     let ports = backend.ports();
+    let config = load_config();
+
+    // 2. Create runtime for TUI components that need it (like App::new's async connection)
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+
     let runtime = Runtime::new_with_ports(ActionRegistry::default(), ports.clone());
     let context = AppContext {
         host_domain: backend.config.network.host_domain.clone(),
@@ -19,6 +29,7 @@ fn main() -> anyhow::Result<()> {
             .as_ref()
             .and_then(|live| live.last_error()),
         ports,
+        ui_config: config.map(|config| config.ui).unwrap_or_default(),
     };
 
     // Check config for single-binary mode (Config field missing, using env var fallback)
@@ -43,11 +54,32 @@ fn main() -> anyhow::Result<()> {
         }
     }
 
-    // 2. Create runtime for TUI components that need it (like App::new's async connection)
-    let rt = tokio::runtime::Builder::new_multi_thread()
-        .enable_all()
-        .build()?;
-
     // 3. Run TUI inside runtime
     rt.block_on(async { tui::start(runtime, context) })
 }
+
+fn load_config() -> Option<PhenomeConfig> {
+    let path = phenome_config_path();
+    if !path.exists() {
+        return None;
+    }
+    match PhenomeConfig::load_from_path(&path) {
+        Ok(config) => Some(config),
+        Err(e) => {
+            eprintln!("Failed to load {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+fn phenome_config_path() -> PathBuf {
+    if let Ok(path) = env::var("PHENOME_CONFIG_PATH") {
+        return PathBuf::from(path);
+    }
+
+    if let Ok(home) = env::var("HOME") {
+        return Path::new(&home).join(".phenome").join("config.yaml");
+    }
+
+    PathBuf::from("phenome-config.yaml")
+}